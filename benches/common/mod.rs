@@ -0,0 +1,55 @@
+//! Test vectors shared by the benchmarks.
+
+use tdx_workload_attestation::core::quote::{
+    CERT_DATA_PCK_CHAIN, CERT_DATA_QE_REPORT, ECDSA_P256_KEY_TYPE, QE_REPORT_LEN, QUOTE_HEADER_LEN,
+    TD_QUOTE_BODY_V10_LEN, TDX_TEE_TYPE,
+};
+
+/// Returns the header and body of a v4 quote, which are signed by the
+/// attestation key.
+pub fn quote_signed_data() -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend(4u16.to_le_bytes());
+    bytes.extend(ECDSA_P256_KEY_TYPE.to_le_bytes());
+    bytes.extend(TDX_TEE_TYPE.to_le_bytes());
+    bytes.resize(QUOTE_HEADER_LEN, 0);
+    bytes.resize(QUOTE_HEADER_LEN + TD_QUOTE_BODY_V10_LEN, 0xab);
+    bytes
+}
+
+/// The signature data of a quote.
+pub struct QuoteSignature {
+    /// The signature over the header and body.
+    pub signature: [u8; 64],
+    /// The raw attestation public key.
+    pub attestation_key: [u8; 64],
+    /// The QE report.
+    pub qe_report: [u8; QE_REPORT_LEN],
+    /// The signature over the QE report.
+    pub qe_report_signature: [u8; 64],
+    /// The PEM-encoded PCK certificate chain.
+    pub pck_chain: String,
+}
+
+/// Assembles a v4 quote from its signed data and signature data.
+pub fn assemble_quote(signed_data: &[u8], sig: &QuoteSignature) -> Vec<u8> {
+    let mut qe_data = vec![];
+    qe_data.extend(sig.qe_report);
+    qe_data.extend(sig.qe_report_signature);
+    qe_data.extend(0u16.to_le_bytes());
+    qe_data.extend(CERT_DATA_PCK_CHAIN.to_le_bytes());
+    qe_data.extend((sig.pck_chain.len() as u32).to_le_bytes());
+    qe_data.extend(sig.pck_chain.as_bytes());
+
+    let mut sig_data = vec![];
+    sig_data.extend(sig.signature);
+    sig_data.extend(sig.attestation_key);
+    sig_data.extend(CERT_DATA_QE_REPORT.to_le_bytes());
+    sig_data.extend((qe_data.len() as u32).to_le_bytes());
+    sig_data.extend(qe_data);
+
+    let mut quote = signed_data.to_vec();
+    quote.extend((sig_data.len() as u32).to_le_bytes());
+    quote.extend(sig_data);
+    quote
+}
@@ -0,0 +1,84 @@
+//! Measures the peak heap usage of reading and replaying event logs of
+//! increasing sizes, showing that streaming reads (`EventLog::replay()`) use
+//! bounded memory, unlike collecting the log (`EventLog::events()`).
+//!
+//! Run with `cargo bench --bench event_log`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use tdx_workload_attestation::measure::event_log::{
+    Event, EventLog, EventPayload, EventWriter, replay,
+};
+
+/// An allocator tracking the current and peak heap usage.
+struct PeakAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+/// Runs `f`, and returns the peak heap usage above the current one, in KiB.
+fn peak_kib<T>(f: impl FnOnce() -> T) -> usize {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    drop(f());
+    (PEAK.load(Ordering::Relaxed) - base) / 1024
+}
+
+/// Writes a log of `len` IMA-like events.
+fn write_log(log: &EventLog, len: usize) {
+    let mut writer = EventWriter::new(BufWriter::new(File::create(log.path()).unwrap())).unwrap();
+    for i in 0..len {
+        let line = format!("10 {:040x} ima-ng sha256:{:064x} /usr/lib/file-{}", i, i, i);
+        writer
+            .write(&Event::new(3, EventPayload::Ima { line }))
+            .unwrap();
+    }
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>10} {:>14} {:>16} {:>12}",
+        "events", "log (KiB)", "replay (KiB)", "collect (KiB)", "replay (ms)"
+    );
+    for len in [1_000, 10_000, 100_000] {
+        let log = EventLog::new(
+            std::env::temp_dir().join(format!("tdx-event-log-bench-{}.cbor", std::process::id())),
+        );
+        write_log(&log, len);
+        let size = std::fs::metadata(log.path()).unwrap().len() / 1024;
+
+        let start = Instant::now();
+        let streamed = peak_kib(|| log.replay().unwrap());
+        let elapsed = start.elapsed().as_millis();
+        let collected = peak_kib(|| replay(&log.events().unwrap()).unwrap());
+
+        println!(
+            "{:>8} {:>10} {:>14} {:>16} {:>12}",
+            len, size, streamed, collected, elapsed
+        );
+        std::fs::remove_file(log.path()).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+//! Benchmarks of the parsing hot paths: TD reports, quotes, and event log
+//! replay and import.
+//!
+//! Run with `cargo bench --bench parsing`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use tdx_workload_attestation::core::quote::{QE_REPORT_LEN, Quote};
+use tdx_workload_attestation::core::report::TdReportV15;
+use tdx_workload_attestation::measure::event_log::{
+    Event, EventPayload, export_events, import_events, replay,
+};
+
+mod common;
+use common::{QuoteSignature, assemble_quote, quote_signed_data};
+
+/// Returns `len` IMA-like events, indexed as in a log.
+fn ima_events(len: usize) -> Vec<Event> {
+    (0..len)
+        .map(|i| {
+            let line = format!("10 {:040x} ima-ng sha256:{:064x} /usr/lib/file-{}", i, i, i);
+            Event {
+                index: i as u64,
+                ..Event::new(3, EventPayload::Ima { line })
+            }
+        })
+        .collect()
+}
+
+fn bench_tdreport(c: &mut Criterion) {
+    let raw = TdReportV15::create_request(&[0x5a; 64]);
+    c.bench_function("tdreport_parse", |b| {
+        b.iter(|| TdReportV15::get_tdreport_from_bytes(black_box(&raw)).unwrap())
+    });
+}
+
+fn bench_quote(c: &mut Criterion) {
+    let quote = assemble_quote(
+        &quote_signed_data(),
+        &QuoteSignature {
+            signature: [1; 64],
+            attestation_key: [2; 64],
+            qe_report: [3; QE_REPORT_LEN],
+            qe_report_signature: [4; 64],
+            pck_chain: "-----BEGIN CERTIFICATE-----\n".repeat(64),
+        },
+    );
+    c.bench_function("quote_parse", |b| {
+        b.iter(|| Quote::from_bytes(black_box(&quote)).unwrap())
+    });
+}
+
+fn bench_event_log(c: &mut Criterion) {
+    let events = ima_events(1_000);
+    c.bench_function("rtmr_replay_1k", |b| {
+        b.iter(|| replay(black_box(&events)).unwrap())
+    });
+    c.bench_function("event_digest_1k", |b| {
+        b.iter(|| black_box(&events).iter().all(|event| event.verify_digest()))
+    });
+
+    let exported = export_events(&events).unwrap();
+    c.bench_function("event_log_import_1k", |b| {
+        b.iter(|| import_events(black_box(&exported)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_tdreport, bench_quote, bench_event_log);
+criterion_main!(benches);
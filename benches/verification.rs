@@ -0,0 +1,154 @@
+//! Benchmarks of the signature verification hot paths: quote signature
+//! chains (with OpenSSL and, with the `rustcrypto-verification` feature,
+//! RustCrypto) and RSA-PSS signatures.
+//!
+//! Run with `cargo bench --bench verification --features host-verification`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNumContext;
+use openssl::ec::{EcGroup, EcKey, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private, Public};
+use openssl::rsa::{Padding, Rsa};
+use openssl::sha::sha256;
+use openssl::sign::{RsaPssSaltlen, Signer};
+use openssl::x509::{X509, X509NameBuilder};
+use std::hint::black_box;
+
+use tdx_workload_attestation::core::quote::{QE_REPORT_LEN, Quote};
+use tdx_workload_attestation::verification::quote::verify_quote_signature;
+use tdx_workload_attestation::verification::signature::verify_signature_sha256_rsa_pss;
+
+mod common;
+use common::{QuoteSignature, assemble_quote, quote_signed_data};
+
+fn ec_key() -> EcKey<Private> {
+    EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()
+}
+
+fn make_cert(subject: &str, key: &EcKey<Private>, issuer_key: &EcKey<Private>) -> X509 {
+    let name = |cn: &str| {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        name.build()
+    };
+
+    let mut cert = X509::builder().unwrap();
+    cert.set_subject_name(&name(subject)).unwrap();
+    cert.set_issuer_name(&name("Bench Root CA")).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    let public = EcKey::from_public_key(key.group(), key.public_key()).unwrap();
+    cert.set_pubkey(&PKey::from_ec_key(public).unwrap())
+        .unwrap();
+    cert.sign(
+        &PKey::from_ec_key(issuer_key.clone()).unwrap(),
+        MessageDigest::sha256(),
+    )
+    .unwrap();
+    cert.build()
+}
+
+/// Returns a raw (`r || s`) ECDSA P-256 signature over `data`.
+fn sign(data: &[u8], key: &EcKey<Private>) -> [u8; 64] {
+    let sig = EcdsaSig::sign(&sha256(data), key).unwrap();
+    let mut raw = [0u8; 64];
+    raw[..32].copy_from_slice(&sig.r().to_vec_padded(32).unwrap());
+    raw[32..].copy_from_slice(&sig.s().to_vec_padded(32).unwrap());
+    raw
+}
+
+/// Returns a quote signed under a new PCK hierarchy, with its DER-encoded
+/// PCK chain and root certificate.
+fn signed_quote() -> (Quote, Vec<Vec<u8>>, X509) {
+    let root_key = ec_key();
+    let pck_key = ec_key();
+    let attestation_key = ec_key();
+    let root = make_cert("Bench Root CA", &root_key, &root_key);
+    let pck = make_cert("Bench PCK", &pck_key, &root_key);
+
+    let mut ctx = BigNumContext::new().unwrap();
+    let point = attestation_key
+        .public_key()
+        .to_bytes(
+            attestation_key.group(),
+            PointConversionForm::UNCOMPRESSED,
+            &mut ctx,
+        )
+        .unwrap();
+    let mut raw_key = [0u8; 64];
+    raw_key.copy_from_slice(&point[1..]);
+
+    // the QE report binds the attestation key (without QE authentication
+    // data)
+    let mut qe_report = [0u8; QE_REPORT_LEN];
+    qe_report[QE_REPORT_LEN - 64..QE_REPORT_LEN - 32].copy_from_slice(&sha256(&raw_key));
+
+    let signed_data = quote_signed_data();
+    let bytes = assemble_quote(
+        &signed_data,
+        &QuoteSignature {
+            signature: sign(&signed_data, &attestation_key),
+            attestation_key: raw_key,
+            qe_report,
+            qe_report_signature: sign(&qe_report, &pck_key),
+            pck_chain: String::new(),
+        },
+    );
+
+    let chain = vec![pck.to_der().unwrap(), root.to_der().unwrap()];
+    (Quote::from_bytes(&bytes).unwrap(), chain, root)
+}
+
+fn bench_quote_signature(c: &mut Criterion) {
+    let (quote, chain, root) = signed_quote();
+    assert!(verify_quote_signature(&quote, &chain, &root).unwrap());
+    c.bench_function("quote_signature_openssl", |b| {
+        b.iter(|| verify_quote_signature(black_box(&quote), &chain, &root).unwrap())
+    });
+
+    #[cfg(feature = "rustcrypto-verification")]
+    {
+        use tdx_workload_attestation::verification::rustcrypto;
+
+        let root = root.to_der().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(rustcrypto::verify_quote_signature(&quote, &chain, &root, now).unwrap());
+        c.bench_function("quote_signature_rustcrypto", |b| {
+            b.iter(|| {
+                rustcrypto::verify_quote_signature(black_box(&quote), &chain, &root, now).unwrap()
+            })
+        });
+    }
+}
+
+fn bench_rsa_pss(c: &mut Criterion) {
+    let key = PKey::from_rsa(Rsa::generate(4096).unwrap()).unwrap();
+    let public: PKey<Public> =
+        PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap();
+    let data = [0x5a; 1024];
+
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+    signer.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+    signer
+        .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+        .unwrap();
+    signer.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+    signer.update(&data).unwrap();
+    let signature = signer.sign_to_vec().unwrap();
+
+    c.bench_function("rsa_pss_4096", |b| {
+        b.iter(|| verify_signature_sha256_rsa_pss(black_box(&data), &signature, &public).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_quote_signature, bench_rsa_pss);
+criterion_main!(benches);
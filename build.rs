@@ -1,39 +1,83 @@
-#[cfg(feature = "host-gcp-tdx")]
-use protobuf_codegen::{Codegen, Customize};
-#[cfg(feature = "host-gcp-tdx")]
-use reqwest;
-#[cfg(feature = "host-gcp-tdx")]
-use std::fs;
-#[cfg(feature = "host-gcp-tdx")]
-use std::io::Write;
+#[cfg(any(feature = "host-gcp-tdx", feature = "proto"))]
+use protobuf_codegen::Codegen;
+#[cfg(feature = "proto")]
+use protobuf_codegen::Customize;
 
 #[cfg(feature = "host-gcp-tdx")]
 fn generate_gcp_protos() {
-    // Download the endorsement proto from the GCE TCB verifier repo
-    fs::create_dir_all("target/gcp").unwrap();
-    let endorsement_proto =
-        reqwest::blocking::get("https://raw.githubusercontent.com/google/gce-tcb-verifier/refs/heads/main/proto/endorsement.proto").unwrap().text().unwrap();
+    // Generate the vendored endorsement schema with the pure-Rust parser
+    // into OUT_DIR, so that neither protoc, network access nor a writable
+    // source tree are needed
+    println!("cargo:rerun-if-changed=proto/gcp/endorsement.proto");
 
-    let mut file = fs::File::create("target/gcp/endorsement.proto").unwrap();
-    file.write_all(endorsement_proto.as_bytes()).unwrap();
+    Codegen::new()
+        .pure()
+        .cargo_out_dir("gcp")
+        .include("proto/gcp")
+        .input("proto/gcp/endorsement.proto")
+        .run()
+        .expect("Protobuf codegen failed");
+}
 
+#[cfg(feature = "proto")]
+fn generate_evidence_protos() {
+    // Generate the evidence exchange schema with the pure-Rust parser, so
+    // that protoc isn't needed
+    println!("cargo:rerun-if-changed=proto/evidence/v1/evidence.proto");
     let no_mod_cfg = Customize::default();
 
     Codegen::new()
-        .out_dir("src/gcp")
-        .include("target/gcp") // this dir is created by the setup script
-        .input("target/gcp/endorsement.proto")
+        .pure()
+        .out_dir("src/evidence/exchange")
+        .include("proto")
+        .input("proto/evidence/v1/evidence.proto")
         .customize(no_mod_cfg.gen_mod_rs(false))
         .run()
         .expect("Protobuf codegen failed");
 }
 
-#[cfg(feature = "host-gcp-tdx")]
-fn setup_gcp_guest() {
-    generate_gcp_protos();
+#[cfg(feature = "ffi")]
+fn generate_ffi_header() {
+    // Generate the C header for the ffi module
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file("cbindgen.toml").unwrap();
+
+    // Define the feature macros guarding the feature-specific functions
+    let mut defines = String::new();
+    for (enabled, define) in [
+        (
+            cfg!(feature = "host-verification"),
+            "TDX_ATTEST_HOST_VERIFICATION",
+        ),
+        (
+            cfg!(feature = "rustcrypto-verification"),
+            "TDX_ATTEST_RUSTCRYPTO_VERIFICATION",
+        ),
+    ] {
+        if enabled {
+            defines.push_str(&format!("\n#define {}", define));
+        }
+    }
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .with_after_include(defines)
+        .generate()
+        .expect("C header generation failed")
+        .write_to_file("target/include/tdx_workload_attestation.h");
 }
 
 fn main() {
     #[cfg(feature = "host-gcp-tdx")]
-    setup_gcp_guest();
+    generate_gcp_protos();
+
+    #[cfg(feature = "proto")]
+    generate_evidence_protos();
+
+    #[cfg(feature = "ffi")]
+    generate_ffi_header();
 }
@@ -0,0 +1,20 @@
+//! Verifies the current TDX guest's launch measurement against the launch
+//! endorsement published by Google Cloud Platform.
+//!
+//! Run from within a TDX guest on GCP with:
+//! ```bash
+//! cargo run --example gcp --features host-gcp-tdx
+//! ```
+
+use tdx_workload_attestation::error::Result;
+use tdx_workload_attestation::verify_launch_endorsement;
+
+fn main() -> Result<()> {
+    if verify_launch_endorsement("gcp-tdx")? {
+        println!("TD launch measurement (MRTD) verification passed!");
+    } else {
+        println!("TD launch measurement (MRTD) verification failed!");
+    }
+
+    Ok(())
+}
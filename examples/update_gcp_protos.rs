@@ -0,0 +1,44 @@
+//! Updates the vendored GCP launch endorsement schema
+//! (`proto/gcp/endorsement.proto`) from Google's `gce-tcb-verifier`.
+//!
+//! Run from the repository root with:
+//! ```bash
+//! cargo run --example update_gcp_protos --features host-gcp-tdx
+//! ```
+//! then bump `ENDORSEMENT_SCHEMA_VERSION` and set `ENDORSEMENT_SCHEMA_SHA256`
+//! in the `gcp::endorsement` module to the printed digest.
+
+use sha2::{Digest, Sha256};
+use tdx_workload_attestation::error::{Error, Result};
+use tdx_workload_attestation::gcp::endorsement::{
+    ENDORSEMENT_SCHEMA_SHA256, ENDORSEMENT_SCHEMA_VERSION,
+};
+
+const ENDORSEMENT_PROTO_URL: &str = "https://raw.githubusercontent.com/google/gce-tcb-verifier/refs/heads/main/proto/endorsement.proto";
+
+const ENDORSEMENT_PROTO_PATH: &str = "proto/gcp/endorsement.proto";
+
+fn main() -> Result<()> {
+    let schema = reqwest::blocking::get(ENDORSEMENT_PROTO_URL)
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.bytes())
+        .map_err(|e| Error::NetworkError(e.to_string()))?;
+
+    let digest = hex::encode(Sha256::digest(&schema));
+    if digest == ENDORSEMENT_SCHEMA_SHA256 {
+        println!(
+            "Endorsement schema version {} is up to date",
+            ENDORSEMENT_SCHEMA_VERSION
+        );
+        return Ok(());
+    }
+
+    std::fs::write(ENDORSEMENT_PROTO_PATH, &schema)?;
+    println!("Updated {}", ENDORSEMENT_PROTO_PATH);
+    println!(
+        "Set ENDORSEMENT_SCHEMA_VERSION to {} and ENDORSEMENT_SCHEMA_SHA256 to {}",
+        ENDORSEMENT_SCHEMA_VERSION + 1,
+        digest
+    );
+    Ok(())
+}
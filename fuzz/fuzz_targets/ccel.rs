@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdx_workload_attestation::measure::ccel::{parse_ccel, replay_ccel};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(events) = parse_ccel(data) {
+        let _ = replay_ccel(&events);
+    }
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::sync::LazyLock;
+use tdx_workload_attestation::gcp::GcpTdxHost;
+use tdx_workload_attestation::host::{Evidence, TeeHost, VerificationContext};
+use tdx_workload_attestation::trust::{TrustAnchor, TrustAnchorKind, TrustAnchors};
+
+// A host with a placeholder root, so building it doesn't download the GCE
+// root cert. Signature verification fails, but only after the endorsement
+// and its golden measurement are parsed.
+static HOST: LazyLock<GcpTdxHost> = LazyLock::new(|| {
+    let mut anchors = TrustAnchors::new();
+    anchors.add(TrustAnchor::new(
+        TrustAnchorKind::GceTcbRoot,
+        "fuzz",
+        b"root",
+    ));
+    GcpTdxHost::builder()
+        .trust_anchors(anchors)
+        .without_cache()
+        .build()
+        .unwrap()
+});
+
+fuzz_target!(|data: &[u8]| {
+    let evidence = Evidence::new(&[0; 48]).with_endorsement(data);
+    let _ = HOST.verify(&evidence, &VerificationContext::new());
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdx_workload_attestation::evidence::Bundle;
+
+fuzz_target!(|data: &[u8]| {
+    for bundle in [Bundle::from_bytes(data), Bundle::from_proto_bytes(data)]
+        .into_iter()
+        .flatten()
+    {
+        let _ = bundle.parse_quote();
+    }
+});
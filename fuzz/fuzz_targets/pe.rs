@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdx_workload_attestation::measure::pe::authenticode_sha384;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = authenticode_sha384(data);
+});
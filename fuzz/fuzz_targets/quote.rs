@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdx_workload_attestation::evidence::interop::GoTdxGuestQuote;
+use tdx_workload_attestation::evidence::quote::Quote;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(quote) = Quote::from_bytes(data) {
+        let _ = quote.signed_data();
+        let _ = quote.qe_report_binds_attestation_key();
+        let _ = quote.pck_chain();
+        let _ = GoTdxGuestQuote::from_quote(&quote);
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdx_workload_attestation::core::report::TdReportV15;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(report) = TdReportV15::from_bytes(data) {
+        let _ = report.get_rtmrs();
+        let _ = report.is_servtd_bound();
+    }
+});
@@ -0,0 +1,284 @@
+//! # Agent Client Bindings
+//!
+//! When several workloads share the agent, a quote over arbitrary
+//! `report_data` lets any of them claim another's key: a relying party that
+//! trusts a quote's `report_data` as the hash of a workload's public key
+//! cannot tell which client actually requested it. This module implements
+//! the agent's `BindingRegistry`, which binds each quote to the identity of
+//! the client requesting it instead.
+//!
+//! A bound quote's `report_data` is computed by the agent from the client's
+//! public key and a nonce (see `report_data_for_client()`), so relying
+//! parties can recompute it from the key and nonce they were given. The
+//! registry records which client (user) first bound each key, and rejects
+//! other clients' requests for quotes over it, as well as the quotes issued,
+//! so operators can trace a quote's `report_data` back to its requester.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::agent::binding::{BindingRegistry, report_data_for_client};
+//! use tdx_workload_attestation::agent::peer::PeerCredentials;
+//!
+//! let registry = BindingRegistry::new();
+//! let client = PeerCredentials { pid: 1234, uid: 1000, gid: 1000 };
+//!
+//! let binding = registry.bind(&client, b"public key", b"nonce").unwrap();
+//! assert_eq!(binding.report_data, report_data_for_client(b"public key", b"nonce"));
+//! registry.record(binding);
+//!
+//! // other clients cannot obtain quotes over the same key
+//! let other = PeerCredentials { pid: 1235, uid: 1001, gid: 1001 };
+//! assert!(registry.bind(&other, b"public key", b"nonce").is_err());
+//! ```
+//!
+//! # Notes
+//! - Bindings are kept in memory, for the lifetime of the agent. Only the
+//!   latest `DEFAULT_BINDING_CAPACITY` quotes are recorded by default, but
+//!   key ownership is never forgotten, so the agent should be rate limited
+//!   (see the `limit` module).
+
+use super::peer::PeerCredentials;
+use crate::error::{Error, Result};
+use crate::tdx::TDX_REPORT_DATA_LEN;
+
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The default number of bound quotes recorded by a `BindingRegistry`.
+pub const DEFAULT_BINDING_CAPACITY: usize = 1024;
+
+/// The maximum length of a client's nonce.
+pub const MAX_NONCE_LEN: usize = 64;
+
+// The domain separator of client bindings
+const CLIENT_BINDING_CONTEXT: &[u8] = b"tdx-workload-attestation/client-binding/v1";
+
+/// Returns the SHA-256 digest of a client's `public_key`, which identifies
+/// it in a `BindingRegistry`.
+pub fn client_key_hash(public_key: &[u8]) -> [u8; 32] {
+    Sha256::digest(public_key).into()
+}
+
+/// Computes the `report_data` of a quote bound to a client's `public_key`
+/// and `nonce`, i.e., the SHA-512 digest of a domain separator followed by
+/// the key's SHA-256 digest and the nonce.
+pub fn report_data_for_client(public_key: &[u8], nonce: &[u8]) -> [u8; TDX_REPORT_DATA_LEN] {
+    let mut hasher = Sha512::new();
+    hasher.update(CLIENT_BINDING_CONTEXT);
+    hasher.update(client_key_hash(public_key));
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+/// The binding of a quote to the client that requested it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    /// The credentials of the client.
+    pub client: PeerCredentials,
+    /// The SHA-256 digest of the client's public key.
+    pub key_hash: [u8; 32],
+    /// The client's nonce.
+    pub nonce: Vec<u8>,
+    /// The `report_data` of the quote.
+    pub report_data: [u8; TDX_REPORT_DATA_LEN],
+    /// The time of the binding, in seconds since the Unix epoch.
+    pub issued_at: u64,
+    /// The boot session ID of the TD that issued the quote, if known (see
+    /// `Agent::with_session()`).
+    pub session_id: Option<String>,
+}
+
+/// Binds quotes to the clients requesting them, and records the bindings.
+#[derive(Debug)]
+pub struct BindingRegistry {
+    capacity: usize,
+    state: Mutex<Registry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    owners: HashMap<[u8; 32], u32>,
+    bindings: VecDeque<Binding>,
+}
+
+impl Default for BindingRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BindingRegistry {
+    /// Creates an empty registry, recording the latest
+    /// `DEFAULT_BINDING_CAPACITY` bound quotes.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_BINDING_CAPACITY)
+    }
+
+    /// Creates an empty registry, recording the latest `capacity` bound
+    /// quotes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(Registry::default()),
+        }
+    }
+
+    /// Binds a quote to `client`'s `public_key` and `nonce`, claiming the
+    /// key for the client's user if it's unclaimed.
+    ///
+    /// The binding isn't recorded until `record()` is called, i.e., once its
+    /// quote is issued.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::ParseError` if the key is empty or the nonce is empty or
+    ///   longer than `MAX_NONCE_LEN`.
+    /// - `Error::VerificationError` if the key was claimed by another user.
+    pub fn bind(
+        &self,
+        client: &PeerCredentials,
+        public_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<Binding> {
+        if public_key.is_empty() {
+            return Err(Error::ParseError("Empty client public key".to_string()));
+        }
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN {
+            return Err(Error::ParseError(format!(
+                "Invalid nonce length {} (expected 1 to {} bytes)",
+                nonce.len(),
+                MAX_NONCE_LEN
+            )));
+        }
+
+        let key_hash = client_key_hash(public_key);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let owner = *state.owners.entry(key_hash).or_insert(client.uid);
+        if owner != client.uid {
+            return Err(Error::VerificationError(format!(
+                "Public key {} is bound to another client",
+                hex::encode(key_hash)
+            )));
+        }
+
+        Ok(Binding {
+            client: *client,
+            key_hash,
+            nonce: nonce.to_vec(),
+            report_data: report_data_for_client(public_key, nonce),
+            issued_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            session_id: None,
+        })
+    }
+
+    /// Records the binding of an issued quote, evicting the oldest one if
+    /// the registry is full.
+    pub fn record(&self, binding: Binding) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.bindings.len() == self.capacity {
+            state.bindings.pop_front();
+        }
+        state.bindings.push_back(binding);
+    }
+
+    /// Returns the user ID of the client that claimed `public_key`, if any.
+    pub fn owner(&self, public_key: &[u8]) -> Option<u32> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.owners.get(&client_key_hash(public_key)).copied()
+    }
+
+    /// Returns the recorded binding of the quote over `report_data`, if any.
+    pub fn lookup(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Option<Binding> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .bindings
+            .iter()
+            .rev()
+            .find(|binding| &binding.report_data == report_data)
+            .cloned()
+    }
+
+    /// Returns the recorded bindings, oldest first.
+    pub fn bindings(&self) -> Vec<Binding> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.bindings.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(uid: u32) -> PeerCredentials {
+        PeerCredentials {
+            pid: 1,
+            uid,
+            gid: uid,
+        }
+    }
+
+    #[test]
+    fn test_bind_client_keys() -> Result<()> {
+        let registry = BindingRegistry::new();
+
+        let binding = registry.bind(&client(1000), b"key A", b"nonce")?;
+        assert_eq!(binding.key_hash, client_key_hash(b"key A"));
+        assert_ne!(
+            binding.report_data,
+            report_data_for_client(b"key B", b"nonce")
+        );
+        assert_ne!(
+            binding.report_data,
+            report_data_for_client(b"key A", b"nonce2")
+        );
+
+        // the key's owner can bind it again, but no other client
+        assert!(registry.bind(&client(1000), b"key A", b"nonce2").is_ok());
+        assert!(
+            registry
+                .bind(&client(1001), b"key A", b"nonce")
+                .is_err_and(|e| e.is_verification_failure())
+        );
+        assert!(registry.bind(&client(1001), b"key B", b"nonce").is_ok());
+        assert_eq!(registry.owner(b"key A"), Some(1000));
+        assert_eq!(registry.owner(b"key B"), Some(1001));
+        assert_eq!(registry.owner(b"key C"), None);
+
+        assert!(registry.bind(&client(1000), b"", b"nonce").is_err());
+        assert!(registry.bind(&client(1000), b"key A", b"").is_err());
+        assert!(registry.bind(&client(1000), b"key A", &[0; 65]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_bindings() -> Result<()> {
+        let registry = BindingRegistry::with_capacity(2);
+        for nonce in [b"1", b"2", b"3"] {
+            let binding = registry.bind(&client(1000), b"key", nonce)?;
+            registry.record(binding);
+        }
+
+        let bindings = registry.bindings();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0].nonce, b"2");
+
+        let report_data = report_data_for_client(b"key", b"3");
+        let binding = registry.lookup(&report_data).unwrap();
+        assert_eq!(binding.client.uid, 1000);
+        assert!(
+            registry
+                .lookup(&report_data_for_client(b"key", b"1"))
+                .is_none()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! # Agent Boot Epochs
+//!
+//! The agent's state outlives its process: evidence archives and on-disk
+//! caches (e.g., of PCK certificates) persist across reboots of the TD, while
+//! the evidence they hold may no longer describe it, e.g., after an image or
+//! TDX module update. This module detects reboots, so that the agent can
+//! start a new boot session with fresh state.
+//!
+//! A `BootEpoch` records what identifies a boot of the TD: the kernel's boot
+//! ID (see `evidence::store::BOOT_ID_PATH`), the TD's `MRTD` and its `RTMR0`
+//! (the firmware's measurement of the TD's configuration), along with its
+//! boot session (see the `evidence::boot_session` module). The agent persists
+//! the epoch it started in to its `AgentState` file, and compares it with the
+//! current epoch on startup (see `AgentState::advance()`): any change means a
+//! new boot, whose `NewBoot` event lists the changes.
+//!
+//! On a new boot, `tdx-attest serve` invalidates the configured PCK
+//! certificate cache, rotates its evidence archive (see
+//! `EvidenceArchive::prune()`), and sends a `new_boot_session` alert (with
+//! the `alerts` feature), so that no evidence collected in a previous boot is
+//! served as the current one's.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::agent::epoch::{AgentState, BootEpoch, DEFAULT_STATE_PATH};
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let epoch = BootEpoch::current(&LinuxTdxProvider::new()).unwrap();
+//! if let Some(new_boot) = AgentState::new(DEFAULT_STATE_PATH).advance(&epoch).unwrap() {
+//!     println!("New boot session: {}", serde_json::to_string(&new_boot).unwrap());
+//! }
+//! ```
+//!
+//! # Notes
+//! - The first epoch recorded by the agent isn't reported as a new boot,
+//!   since there's no previous state to invalidate.
+
+#[cfg(feature = "alerts")]
+use crate::alert::{Alert, AlertKind};
+use crate::core::report::TdReportV15;
+use crate::core::report::diff::Fields;
+use crate::error::{Error, Result};
+use crate::evidence::boot_session::{BootSession, boot_time};
+use crate::evidence::store::BOOT_ID_PATH;
+use crate::tdx::LinuxTdxProvider;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The default path of the agent's state file.
+pub const DEFAULT_STATE_PATH: &str = "/var/lib/tdx-attest/agent-state.json";
+
+/// What identifies a boot of the TD.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootEpoch {
+    /// The kernel's boot ID.
+    pub boot_id: String,
+    /// The hex-encoded `MRTD` of the TD.
+    pub mrtd: String,
+    /// The hex-encoded `RTMR0` of the TD.
+    pub rtmr0: String,
+    /// The ID of the TD's boot session, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl BootEpoch {
+    /// Creates the epoch of the boot with `boot_id` of the TD with `report`.
+    pub fn new(report: &TdReportV15, boot_id: &str) -> Self {
+        Self {
+            boot_id: boot_id.trim().to_string(),
+            mrtd: report.get_mrtd().to_string(),
+            rtmr0: report.get_rtmrs()[0].to_string(),
+            session_id: None,
+        }
+    }
+
+    /// Returns the epoch of the current boot of the TD, with its boot
+    /// session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TD's report, the boot ID or the boot time
+    /// cannot be read.
+    pub fn current(provider: &LinuxTdxProvider) -> Result<Self> {
+        let report = provider.get_tdreport()?.into_report();
+        let session = BootSession::derive(&Fields::from(&report), boot_time()?);
+        Ok(Self::new(&report, &fs::read_to_string(BOOT_ID_PATH)?).with_session(&session))
+    }
+
+    /// Sets the boot session of the epoch.
+    pub fn with_session(mut self, session: &BootSession) -> Self {
+        self.session_id = Some(session.id.clone());
+        self
+    }
+
+    /// Returns what changed since the `previous` epoch.
+    pub fn changes_since(&self, previous: &BootEpoch) -> Vec<RebootCause> {
+        let mut causes = vec![];
+        if self.boot_id != previous.boot_id {
+            causes.push(RebootCause::BootId);
+        }
+        if self.mrtd != previous.mrtd {
+            causes.push(RebootCause::Mrtd);
+        }
+        if self.rtmr0 != previous.rtmr0 {
+            causes.push(RebootCause::Rtmr0);
+        }
+        causes
+    }
+}
+
+/// What revealed a reboot of the TD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootCause {
+    /// The kernel's boot ID changed.
+    BootId,
+    /// The TD's `MRTD` changed, i.e., it was relaunched with other firmware.
+    Mrtd,
+    /// The TD's `RTMR0` changed, e.g., its configuration changed.
+    Rtmr0,
+}
+
+/// The event of a new boot of the TD.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NewBoot {
+    /// The epoch of the previous boot.
+    pub previous: BootEpoch,
+    /// The epoch of the new boot.
+    pub current: BootEpoch,
+    /// What revealed the reboot.
+    pub causes: Vec<RebootCause>,
+}
+
+impl NewBoot {
+    /// Converts the event into a `new_boot_session` alert, tagged with the
+    /// new boot session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the system time is unavailable, or the event
+    /// cannot be encoded.
+    #[cfg(feature = "alerts")]
+    pub fn to_alert(&self) -> Result<Alert> {
+        let summary = format!(
+            "The TD rebooted (boot ID {} -> {})",
+            self.previous.boot_id, self.current.boot_id
+        );
+        let mut alert = Alert::new(AlertKind::NewBootSession, &summary)?.with_details(self)?;
+        if let Some(session_id) = &self.current.session_id {
+            alert = alert.with_session_id(session_id);
+        }
+        Ok(alert)
+    }
+}
+
+/// The agent's state file, persisting the epoch it last ran in.
+#[derive(Clone, Debug)]
+pub struct AgentState {
+    path: PathBuf,
+}
+
+impl AgentState {
+    /// Creates the state persisted at `path` (e.g., `DEFAULT_STATE_PATH`).
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Loads the persisted epoch, or `None` if none was persisted yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the file cannot be read, or an
+    /// `Error::ParseError` if it's malformed.
+    pub fn load(&self) -> Result<Option<BootEpoch>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            Error::ParseError(format!(
+                "Invalid agent state {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Persists `epoch`, atomically so that a crash can't leave a truncated
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the file cannot be written.
+    pub fn save(&self, epoch: &BootEpoch) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(epoch)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists the `current` epoch, and returns the `NewBoot` event if it
+    /// differs from the persisted one.
+    ///
+    /// Malformed state is replaced, and reported as a new boot from an
+    /// unknown epoch, so that the state derived from it is invalidated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the file cannot be read or written.
+    pub fn advance(&self, current: &BootEpoch) -> Result<Option<NewBoot>> {
+        let previous = match self.load() {
+            Ok(previous) => previous,
+            Err(Error::ParseError(_)) => Some(BootEpoch::default()),
+            Err(e) => return Err(e),
+        };
+        self.save(current)?;
+
+        Ok(previous.and_then(|previous| {
+            let causes = current.changes_since(&previous);
+            (!causes.is_empty()).then(|| NewBoot {
+                previous,
+                current: current.clone(),
+                causes,
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch(boot_id: &str, mrtd: u8, rtmr0: u8) -> BootEpoch {
+        let mut rtmrs = [[0; 48]; 4];
+        rtmrs[0] = [rtmr0; 48];
+        let report = TdReportV15::builder()
+            .with_mrtd(&[mrtd; 48])
+            .with_rtmrs(&rtmrs)
+            .build();
+        BootEpoch::new(&report, boot_id)
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let previous = epoch("a\n", 1, 2);
+        assert_eq!(previous.boot_id, "a");
+        assert!(epoch("a", 1, 2).changes_since(&previous).is_empty());
+        assert_eq!(
+            epoch("b", 1, 3).changes_since(&previous),
+            vec![RebootCause::BootId, RebootCause::Rtmr0]
+        );
+        assert_eq!(
+            epoch("a", 4, 2).changes_since(&previous),
+            vec![RebootCause::Mrtd]
+        );
+    }
+
+    #[test]
+    fn test_agent_state() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-agent-state-{}", rand::random::<u64>()));
+        let state = AgentState::new(dir.join("state.json"));
+        assert_eq!(state.load()?, None);
+
+        // the first epoch isn't a new boot
+        let first = epoch("a", 1, 2).with_session(&BootSession {
+            id: "00".to_string(),
+            boot_time: 1,
+        });
+        assert_eq!(state.advance(&first)?, None);
+        assert_eq!(state.load()?, Some(first.clone()));
+        assert_eq!(state.advance(&first)?, None);
+
+        let second = epoch("b", 1, 2);
+        let new_boot = state.advance(&second)?.unwrap();
+        assert_eq!(new_boot.previous, first);
+        assert_eq!(new_boot.current, second);
+        assert_eq!(new_boot.causes, vec![RebootCause::BootId]);
+
+        // malformed state is replaced
+        fs::write(dir.join("state.json"), "garbage")?;
+        assert!(state.load().is_err());
+        assert_eq!(
+            state.advance(&second)?.unwrap().causes,
+            vec![RebootCause::BootId, RebootCause::Mrtd, RebootCause::Rtmr0]
+        );
+        assert_eq!(state.load()?, Some(second));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "alerts")]
+    #[test]
+    fn test_new_boot_alert() -> Result<()> {
+        let new_boot = NewBoot {
+            previous: epoch("a", 1, 2),
+            current: epoch("b", 1, 2).with_session(&BootSession {
+                id: "ff".to_string(),
+                boot_time: 1,
+            }),
+            causes: vec![RebootCause::BootId],
+        };
+        let alert = new_boot.to_alert()?;
+        assert_eq!(alert.kind, AlertKind::NewBootSession);
+        assert_eq!(alert.session_id.as_deref(), Some("ff"));
+        assert_eq!(alert.details["causes"][0], "boot_id");
+        Ok(())
+    }
+}
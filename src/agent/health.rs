@@ -0,0 +1,405 @@
+//! # Agent Health and Readiness Endpoints
+//!
+//! This module serves the health of the TD's attestation stack over HTTP
+//! (see `tdx-attest serve --health-addr`), so that orchestrators such as
+//! Kubernetes can gate a pod's liveness and readiness on it:
+//!
+//! - `GET /healthz` (liveness): the TDX guest interface is accessible.
+//! - `GET /readyz` (readiness): additionally, the quote generation service
+//!   is reachable, and the trust anchors (if configured) are valid.
+//!
+//! Both endpoints respond with `200 OK` if all their checks pass, or `503
+//! Service Unavailable` otherwise, and a JSON body listing each check, e.g.:
+//!
+//! ```json
+//! {"healthy": true, "checks": [{"name": "device", "passed": true, "detail": null}]}
+//! ```
+//!
+//! The quote generation service is the QGS if its vsock port is configured
+//! (see the `tdx::linux::qgs` module), or the Azure IMDS with a Hyper-V
+//! paravisor. Otherwise, quotes are requested through the kernel, and the
+//! QGS can't be probed from the guest, so only the configfs-tsm interface
+//! is checked.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::agent::health::HealthChecker;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//! use std::net::TcpListener;
+//!
+//! let checker = HealthChecker::new(&LinuxTdxProvider::new());
+//! println!("Ready: {}", checker.readiness().healthy);
+//!
+//! let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+//! checker.serve(&listener).unwrap();
+//! ```
+//!
+//! # Notes
+//! - The endpoints are unauthenticated, and only disclose which checks
+//!   fail, but should still only be exposed to the node (e.g., bound to
+//!   localhost, or the pod's network namespace).
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::Result;
+use crate::platform::TSM_REPORT_PATH;
+use crate::tdx::linux::{device, qgs, tsm};
+use crate::tdx::{LinuxTdxProvider, TdxBackend, hcl};
+use crate::trust::{TrustAnchorKind, TrustAnchors};
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// The maximum length of a request's head, which bounds the memory a client
+// can make the server allocate
+const MAX_REQUEST_LEN: u64 = 8 * 1024;
+
+// The time a client may take to send a request or read a response
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The time to wait for the Azure IMDS to accept a connection
+const IMDS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The result of a health check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// The name of the check (`device`, `quote-service` or
+    /// `trust-anchors`).
+    pub name: String,
+    /// Whether the check passed.
+    pub passed: bool,
+    /// Why the check failed, or a note on how it passed.
+    pub detail: Option<String>,
+}
+
+impl HealthCheck {
+    fn pass(name: &str, detail: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            detail,
+        }
+    }
+
+    fn fail(name: &str, detail: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            detail: Some(detail),
+        }
+    }
+}
+
+/// The health of the attestation stack, as served by an endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthStatus {
+    /// Whether all checks passed.
+    pub healthy: bool,
+    /// The checks, in order.
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthStatus {
+    fn new(checks: Vec<HealthCheck>) -> Self {
+        Self {
+            healthy: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+/// A checker of the attestation stack's health, for the backend of a
+/// `LinuxTdxProvider`.
+#[derive(Clone, Debug)]
+pub struct HealthChecker {
+    backend: TdxBackend,
+    device_path: String,
+    trust_anchors: Option<TrustAnchors>,
+    clock: Arc<dyn Clock>,
+}
+
+impl HealthChecker {
+    /// Creates a checker for the backend (and TDX guest device) of
+    /// `provider`, which doesn't check trust anchors.
+    pub fn new(provider: &LinuxTdxProvider) -> Self {
+        Self {
+            backend: provider.backend(),
+            device_path: provider.device_path().to_string(),
+            trust_anchors: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Also checks, for readiness, that `trust_anchors` include an Intel SGX
+    /// root, and that none of them has expired or isn't yet valid.
+    pub fn with_trust_anchors(mut self, trust_anchors: TrustAnchors) -> Self {
+        self.trust_anchors = Some(trust_anchors);
+        self
+    }
+
+    /// Sets the clock the trust anchors' validity is checked at.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Checks liveness: the TDX guest interface is accessible.
+    pub fn liveness(&self) -> HealthStatus {
+        HealthStatus::new(vec![self.check_device()])
+    }
+
+    /// Checks readiness: the TDX guest interface is accessible, the quote
+    /// generation service is reachable, and the trust anchors (if any) are
+    /// valid.
+    pub fn readiness(&self) -> HealthStatus {
+        let mut checks = vec![self.check_device(), self.check_quote_service()];
+        if let Some(anchors) = &self.trust_anchors {
+            checks.push(self.check_trust_anchors(anchors));
+        }
+        HealthStatus::new(checks)
+    }
+
+    /// Serves the endpoints to the clients connecting to `listener`, each on
+    /// its own thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if a connection cannot be accepted.
+    /// Failed connections are closed without stopping the server.
+    pub fn serve(&self, listener: &TcpListener) -> Result<()> {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                scope.spawn(move || {
+                    let _ = self.handle_connection(stream);
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Serves a single HTTP request of the client connected on `stream`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client's connection fails.
+    pub fn handle_connection(&self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+
+        let mut reader = BufReader::new(stream).take(MAX_REQUEST_LEN);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // drain the headers, which are ignored
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                break;
+            }
+        }
+
+        let (status, body) = self.respond(&request_line);
+        write!(
+            writer,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        Ok(())
+    }
+
+    /// Returns the status line and body of the response to a request.
+    fn respond(&self, request_line: &str) -> (&'static str, String) {
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next(), parts.next());
+        // ignore any query string
+        let path = target.map(|target| target.split('?').next().unwrap_or_default());
+
+        let status = match (method, path) {
+            (Some("GET"), Some("/healthz")) => self.liveness(),
+            (Some("GET"), Some("/readyz")) => self.readiness(),
+            (Some("GET"), _) => return ("404 Not Found", r#"{"error":"Not found"}"#.to_string()),
+            _ => {
+                return (
+                    "405 Method Not Allowed",
+                    r#"{"error":"Method not allowed"}"#.to_string(),
+                );
+            }
+        };
+        let code = match status.healthy {
+            true => "200 OK",
+            false => "503 Service Unavailable",
+        };
+        // serializing the status can't fail
+        (code, serde_json::to_string(&status).unwrap_or_default())
+    }
+
+    fn check_device(&self) -> HealthCheck {
+        match self.backend {
+            TdxBackend::Kvm => {
+                let probe = device::probe_at(&self.device_path);
+                if probe.is_available() {
+                    HealthCheck::pass("device", None)
+                } else {
+                    HealthCheck::fail(
+                        "device",
+                        probe
+                            .error
+                            .unwrap_or_else(|| format!("{} is not accessible", self.device_path)),
+                    )
+                }
+            }
+            TdxBackend::HyperV => match hcl::is_available() {
+                Ok(true) => HealthCheck::pass("device", None),
+                Ok(false) => HealthCheck::fail(
+                    "device",
+                    "The vTPM doesn't expose a TDX HCL report".to_string(),
+                ),
+                Err(e) => HealthCheck::fail("device", e.to_string()),
+            },
+            // quotes are generated without the device
+            TdxBackend::Tsm => match tsm::is_available() {
+                true => HealthCheck::pass("device", None),
+                false => HealthCheck::fail(
+                    "device",
+                    format!(
+                        "The configfs-tsm interface ({}) is not available",
+                        TSM_REPORT_PATH
+                    ),
+                ),
+            },
+        }
+    }
+
+    fn check_quote_service(&self) -> HealthCheck {
+        const NAME: &str = "quote-service";
+
+        if self.backend == TdxBackend::HyperV {
+            let reachable = hcl::AZURE_IMDS_ADDR
+                .parse()
+                .is_ok_and(|addr| TcpStream::connect_timeout(&addr, IMDS_TIMEOUT).is_ok());
+            return match reachable {
+                true => HealthCheck::pass(NAME, None),
+                false => HealthCheck::fail(NAME, "The Azure IMDS is not reachable".to_string()),
+            };
+        }
+
+        if !Path::new(TSM_REPORT_PATH).is_dir() {
+            return HealthCheck::fail(
+                NAME,
+                format!(
+                    "The configfs-tsm interface ({}) is not available",
+                    TSM_REPORT_PATH
+                ),
+            );
+        }
+        match qgs::configured_vsock_port() {
+            Ok(Some(port)) if qgs::is_vsock_reachable(port) => HealthCheck::pass(NAME, None),
+            Ok(Some(port)) => HealthCheck::fail(
+                NAME,
+                format!("The QGS is not reachable on vsock port {}", port),
+            ),
+            Ok(None) => HealthCheck::pass(
+                NAME,
+                Some("The QGS is reached through the kernel, and was not probed".to_string()),
+            ),
+            Err(e) => HealthCheck::fail(NAME, e.to_string()),
+        }
+    }
+
+    fn check_trust_anchors(&self, anchors: &TrustAnchors) -> HealthCheck {
+        const NAME: &str = "trust-anchors";
+
+        if !anchors.has_roots(TrustAnchorKind::IntelSgxRoot) {
+            return HealthCheck::fail(NAME, "No Intel SGX root is trusted".to_string());
+        }
+        let now = match self.clock.now() {
+            Ok(now) => now,
+            Err(e) => return HealthCheck::fail(NAME, e.to_string()),
+        };
+        // only anchors that are invalid now fail the check
+        let invalid = anchors.expiry_warnings(now, 0);
+        match invalid.is_empty() {
+            true => HealthCheck::pass(NAME, None),
+            false => HealthCheck::fail(NAME, invalid.join("; ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    fn checker(device_path: &str) -> HealthChecker {
+        HealthChecker::new(
+            &LinuxTdxProvider::with_backend(TdxBackend::Kvm).with_device_path(device_path),
+        )
+    }
+
+    #[test]
+    fn test_liveness() {
+        let status = checker("/nonexistent/tdx_guest").liveness();
+        assert!(!status.healthy);
+        assert_eq!(status.checks.len(), 1);
+        assert_eq!(status.checks[0].name, "device");
+        assert!(status.checks[0].detail.is_some());
+    }
+
+    #[test]
+    fn test_trust_anchors() {
+        let checker = checker("/nonexistent/tdx_guest").with_clock(Arc::new(FixedClock::new(0)));
+
+        let status = checker.clone().readiness();
+        assert_eq!(status.checks.len(), 2);
+
+        let status = checker.with_trust_anchors(TrustAnchors::new()).readiness();
+        let check = &status.checks[2];
+        assert_eq!(check.name, "trust-anchors");
+        assert!(!check.passed);
+    }
+
+    #[test]
+    fn test_serve() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let checker = checker("/nonexistent/tdx_guest");
+
+        let get = |request: &str| -> Result<String> {
+            let mut stream = TcpStream::connect(addr)?;
+            stream.write_all(request.as_bytes())?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+            Ok(response)
+        };
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for stream in listener.incoming().take(4) {
+                    let _ = checker.handle_connection(stream.unwrap());
+                }
+            });
+
+            let response = get("GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+            assert!(response.starts_with("HTTP/1.1 503"));
+            let (_, body) = response.split_once("\r\n\r\n").unwrap();
+            let status: HealthStatus = serde_json::from_str(body).unwrap();
+            assert!(!status.healthy);
+
+            let response = get("GET /readyz?verbose HTTP/1.1\r\n\r\n")?;
+            assert!(response.starts_with("HTTP/1.1 503"));
+            assert!(response.contains("quote-service"));
+
+            assert!(get("GET /metrics HTTP/1.1\r\n\r\n")?.starts_with("HTTP/1.1 404"));
+            assert!(get("POST /healthz HTTP/1.1\r\n\r\n")?.starts_with("HTTP/1.1 405"));
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,225 @@
+//! # Quote Rate Limiting
+//!
+//! Quote generation goes through the TDX module and the host's Quote
+//! Generation Service (QGS), so unbounded callers can starve other
+//! workloads, or the whole host, of quotes. This module implements the
+//! agent's `RateLimiter`, which enforces token-bucket limits on quote
+//! requests, both per client (identified by its user ID, see the `peer`
+//! module) and across all clients.
+//!
+//! A `RateLimit` allows bursts of up to `burst` requests, and refills at
+//! `burst` requests per `interval`. Rejected requests fail with an
+//! `Error::RateLimited`, which carries the time after which the request may
+//! be retried (see `Error::retry_after()`).
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::agent::limit::{RateLimit, RateLimiter};
+//!
+//! let limiter = RateLimiter::new()
+//!     .with_client_limit(RateLimit::per_minute(10))
+//!     .with_global_limit(RateLimit::per_minute(100));
+//!
+//! match limiter.check(1000) {
+//!     Ok(()) => println!("Quote allowed"),
+//!     Err(e) => println!("Retry after {:?}", e.retry_after()),
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A token-bucket rate limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The maximum number of requests in a burst.
+    pub burst: u32,
+    /// The time over which `burst` requests are refilled.
+    pub interval: Duration,
+}
+
+impl RateLimit {
+    /// Creates a limit of `burst` requests per `interval`.
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        Self { burst, interval }
+    }
+
+    /// Creates a limit of `n` requests per minute.
+    pub fn per_minute(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(60))
+    }
+}
+
+/// A token bucket, refilled continuously.
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    /// Refills the bucket up to `now`.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.updated = now;
+        if self.limit.interval.is_zero() {
+            self.tokens = self.limit.burst as f64;
+            return;
+        }
+        let refilled = elapsed.as_secs_f64() / self.limit.interval.as_secs_f64();
+        self.tokens =
+            (self.tokens + refilled * self.limit.burst as f64).min(self.limit.burst as f64);
+    }
+
+    /// Returns the time until the bucket holds a token.
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        if self.limit.burst == 0 {
+            return Duration::MAX;
+        }
+        let missing = 1.0 - self.tokens;
+        self.limit
+            .interval
+            .mul_f64(missing / self.limit.burst as f64)
+    }
+}
+
+/// Rate limits quote requests, per client and across all clients.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    client_limit: Option<RateLimit>,
+    global_limit: Option<RateLimit>,
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    clients: HashMap<u32, TokenBucket>,
+    global: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the requests of each client.
+    pub fn with_client_limit(mut self, limit: RateLimit) -> Self {
+        self.client_limit = Some(limit);
+        self
+    }
+
+    /// Limits the requests across all clients.
+    pub fn with_global_limit(mut self, limit: RateLimit) -> Self {
+        self.global_limit = Some(limit);
+        self
+    }
+
+    /// Takes a request of the client with user ID `client` from its quota
+    /// and the global one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::RateLimited` if either quota is exhausted, in which
+    /// case neither is taken from.
+    pub fn check(&self, client: u32) -> Result<()> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: u32, now: Instant) -> Result<()> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let Buckets { clients, global } = &mut *buckets;
+
+        let client_bucket = self.client_limit.map(|limit| {
+            clients
+                .entry(client)
+                .or_insert_with(|| TokenBucket::new(limit, now))
+        });
+        let global_bucket = self
+            .global_limit
+            .map(|limit| global.get_or_insert_with(|| TokenBucket::new(limit, now)));
+
+        let mut taken: Vec<&mut TokenBucket> =
+            client_bucket.into_iter().chain(global_bucket).collect();
+        for bucket in taken.iter_mut() {
+            bucket.refill(now);
+        }
+        let wait = taken.iter().map(|b| b.wait()).max().unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(Error::RateLimited(wait));
+        }
+        for bucket in taken {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_limit() {
+        let limiter =
+            RateLimiter::new().with_client_limit(RateLimit::new(2, Duration::from_secs(10)));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(1000, start).is_ok());
+        assert!(limiter.check_at(1000, start).is_ok());
+        let e = limiter.check_at(1000, start).unwrap_err();
+        assert_eq!(e.retry_after(), Some(Duration::from_secs(5)));
+
+        // other clients have their own quota
+        assert!(limiter.check_at(1001, start).is_ok());
+
+        // and quotas refill over time
+        assert!(
+            limiter
+                .check_at(1000, start + Duration::from_secs(4))
+                .is_err()
+        );
+        assert!(
+            limiter
+                .check_at(1000, start + Duration::from_secs(5))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_global_limit() {
+        let limiter = RateLimiter::new()
+            .with_client_limit(RateLimit::new(2, Duration::from_secs(10)))
+            .with_global_limit(RateLimit::new(3, Duration::from_secs(30)));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(1000, start).is_ok());
+        assert!(limiter.check_at(1001, start).is_ok());
+        assert!(limiter.check_at(1002, start).is_ok());
+        let e = limiter.check_at(1003, start).unwrap_err();
+        assert_eq!(e.retry_after(), Some(Duration::from_secs(10)));
+
+        // a rejected request doesn't take from the client's quota
+        let later = start + Duration::from_secs(10);
+        assert!(limiter.check_at(1003, later).is_ok());
+        assert!(limiter.check_at(1003, later).is_err());
+
+        assert!(RateLimiter::new().check_at(1000, start).is_ok());
+    }
+}
@@ -0,0 +1,671 @@
+//! # Attestation Agent
+//!
+//! This module implements an attestation agent, which serves TD quotes to
+//! the workloads of a TD over a Unix socket (see `tdx-attest serve`), so
+//! that they don't need access to the TDX guest device or configfs-tsm
+//! themselves.
+//!
+//! Since a quote binds its `report_data`, which relying parties trust as the
+//! workload's (e.g., the hash of its public key), only designated workloads
+//! may request quotes: the socket's permissions restrict who can connect,
+//! and each client's credentials are checked against the agent's
+//! `AccessPolicy` (see the `peer` module) before any request is served.
+//!
+//! Clients send one JSON request per line, and receive one JSON response per
+//! line, e.g.:
+//!
+//! ```json
+//! {"method": "quote", "report_data": "<hex-encoded 64 bytes>"}
+//! {"quote": "<base64-encoded quote>"}
+//! ```
+//!
+//! Clients can also request the TD's `TDREPORT` over their `report_data`
+//! (e.g., for a service TD on the same platform to verify without a quote):
+//!
+//! ```json
+//! {"method": "report", "report_data": "<hex-encoded 64 bytes>"}
+//! {"report": "<base64-encoded TDREPORT>"}
+//! ```
+//!
+//! so that unprivileged processes can use the library without access to the
+//! TDX guest device: the agent runs as root, owning the device, and the
+//! processes use the `provider::AgentProvider` client.
+//!
+//! Failed requests receive `{"error": "<message>"}` instead. Quote requests
+//! may be rate limited (see the `limit` module), in which case the error also
+//! carries the time after which to retry, in `"retry_after_ms"`.
+//!
+//! Agents shared by several tenants should bind quotes to their clients (see
+//! `Agent::with_bindings()` and the `binding` module): clients then request
+//! quotes over their public key and a nonce, from which the agent computes
+//! the `report_data`, and quotes over arbitrary `report_data` are refused:
+//!
+//! ```json
+//! {"method": "bound_quote", "public_key": "<base64-encoded key>", "nonce": "<hex-encoded nonce>"}
+//! ```
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::agent::{Agent, DEFAULT_SOCKET_MODE, DEFAULT_SOCKET_PATH, bind};
+//! use tdx_workload_attestation::agent::peer::AccessPolicy;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let listener = bind(DEFAULT_SOCKET_PATH, DEFAULT_SOCKET_MODE).unwrap();
+//! let agent = Agent::new(LinuxTdxProvider::new(), AccessPolicy::new().with_uid(1000));
+//! agent.serve(&listener).unwrap();
+//! ```
+//!
+//! Agents identify the TD's boot session (see `Agent::with_session()` and
+//! the `evidence::boot_session` module) in the bindings they record and the
+//! alerts they send, so that operators can correlate them with the evidence
+//! collected during the same boot.
+//!
+//! The `epoch` submodule detects the reboots of the TD across the agent's
+//! restarts, so that the state it persists (e.g., its caches and evidence
+//! archives) is invalidated on a new boot. The `watch` submodule detects the
+//! runtime drift of the TD, by periodically re-collecting its report (see
+//! `tdx-attest watch`), and the `health` submodule serves the health of the
+//! attestation stack over HTTP, for liveness and readiness probes.
+//!
+//! # Notes
+//! - The socket's permissions are set after it's bound, so it should be
+//!   created in a directory only accessible to the agent's clients.
+
+pub mod binding;
+pub mod epoch;
+pub mod health;
+pub mod limit;
+pub mod peer;
+pub mod watch;
+
+#[cfg(feature = "alerts")]
+use crate::alert::{Alert, AlertKind, AlertSink};
+use crate::error::{Error, Result};
+use crate::evidence::boot_session::BootSession;
+use crate::tdx::report::{CollectedEvidence, TdReportV15};
+use crate::tdx::{LinuxTdxProvider, TDX_REPORT_DATA_LEN};
+use binding::BindingRegistry;
+use limit::RateLimiter;
+use peer::{AccessPolicy, PeerCredentials};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// The default path of the agent's socket.
+pub const DEFAULT_SOCKET_PATH: &str = "/run/tdx-attest/agent.sock";
+
+/// The default permissions of the agent's socket (owner and group only).
+pub const DEFAULT_SOCKET_MODE: u32 = 0o660;
+
+// The maximum length of a request line, which bounds the memory a client
+// can make the agent allocate
+const MAX_REQUEST_LEN: u64 = 64 * 1024;
+
+// The time a client may take to send a request or read a response
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A source of TD quotes (and reports) for the agent.
+pub trait QuoteSource: Sync {
+    /// Retrieves a signed TD quote over `report_data`.
+    fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>>;
+
+    /// Retrieves the TD's `TDREPORT` over `report_data`.
+    ///
+    /// Sources don't serve reports by default.
+    fn get_tdreport(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TdReportV15> {
+        let _ = report_data;
+        Err(Error::NotSupported(
+            "The agent doesn't serve TD reports".to_string(),
+        ))
+    }
+}
+
+impl QuoteSource for LinuxTdxProvider {
+    fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+        LinuxTdxProvider::get_quote(self, report_data)
+    }
+
+    fn get_tdreport(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TdReportV15> {
+        let mut reports = self.get_attestation_reports(&[*report_data])?;
+        reports
+            .pop()
+            .map(CollectedEvidence::into_report)
+            .ok_or_else(|| Error::QuoteError("No TD report was retrieved".to_string()))
+    }
+}
+
+/// A request to the agent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    /// Requests a quote over the hex-encoded `report_data`.
+    Quote {
+        /// The hex-encoded `report_data` to bind into the quote.
+        report_data: String,
+    },
+    /// Requests a quote bound to the client's public key and nonce (see
+    /// `binding::report_data_for_client()`).
+    BoundQuote {
+        /// The base64-encoded public key of the client.
+        public_key: String,
+        /// The hex-encoded nonce of the client.
+        nonce: String,
+    },
+    /// Requests the TD's `TDREPORT` over the hex-encoded `report_data`.
+    Report {
+        /// The hex-encoded `report_data` to bind into the report.
+        report_data: String,
+    },
+}
+
+/// A response of the agent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Response {
+    /// The base64-encoded quote.
+    Quote {
+        /// The base64-encoded quote.
+        quote: String,
+    },
+    /// The base64-encoded raw `TDREPORT`.
+    Report {
+        /// The base64-encoded raw `TDREPORT`.
+        report: String,
+    },
+    /// The reason the request failed.
+    Error {
+        /// The reason the request failed.
+        error: String,
+        /// The time after which a rate limited request may be retried, in
+        /// milliseconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry_after_ms: Option<u64>,
+    },
+}
+
+impl Response {
+    fn error(e: &Error) -> Self {
+        Response::Error {
+            error: e.to_string(),
+            retry_after_ms: e
+                .retry_after()
+                .map(|d| d.as_millis().try_into().unwrap_or(u64::MAX)),
+        }
+    }
+}
+
+/// An attestation agent serving quotes from `Q` to the clients allowed by
+/// its access policy, within its rate limits.
+pub struct Agent<Q: QuoteSource> {
+    source: Q,
+    access: AccessPolicy,
+    limiter: RateLimiter,
+    bindings: Option<BindingRegistry>,
+    session: Option<BootSession>,
+    #[cfg(feature = "alerts")]
+    alert_sink: Option<Box<dyn AlertSink>>,
+}
+
+impl<Q: QuoteSource> Agent<Q> {
+    /// Creates a new agent serving quotes from `source` to the clients
+    /// allowed by `access`, without rate limits.
+    pub fn new(source: Q, access: AccessPolicy) -> Self {
+        Self {
+            source,
+            access,
+            limiter: RateLimiter::new(),
+            bindings: None,
+            session: None,
+            #[cfg(feature = "alerts")]
+            alert_sink: None,
+        }
+    }
+
+    /// Rate limits the quote requests of the agent's clients.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Binds the agent's quotes to its clients with `registry`, i.e., only
+    /// serves bound quotes, over the keys claimed by each client.
+    pub fn with_bindings(mut self, registry: BindingRegistry) -> Self {
+        self.bindings = Some(registry);
+        self
+    }
+
+    /// Identifies the TD's boot `session` in the recorded bindings and the
+    /// alerts (e.g., `BootSession::current()`).
+    pub fn with_session(mut self, session: BootSession) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Sends a `quote_failure` alert to `sink` whenever a quote cannot be
+    /// generated for a client (but not when a client's request is rejected).
+    #[cfg(feature = "alerts")]
+    pub fn with_alert_sink(mut self, sink: Box<dyn AlertSink>) -> Self {
+        self.alert_sink = Some(sink);
+        self
+    }
+
+    /// Returns the agent's binding registry, if its quotes are bound to its
+    /// clients.
+    pub fn bindings(&self) -> Option<&BindingRegistry> {
+        self.bindings.as_ref()
+    }
+
+    /// Serves the clients connecting to `listener`, each on its own thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if a connection cannot be accepted.
+    /// Failed connections are closed without stopping the agent.
+    pub fn serve(&self, listener: &UnixListener) -> Result<()> {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                scope.spawn(move || {
+                    // the client is sent the errors it can act on, others
+                    // only close its connection
+                    let _ = self.handle_connection(stream);
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Serves the requests of the client connected on `stream`, after
+    /// checking its credentials.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client isn't allowed, or its connection
+    /// fails.
+    pub fn handle_connection(&self, stream: UnixStream) -> Result<()> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+
+        let peer = PeerCredentials::from_stream(&stream)?;
+        if let Err(e) = self.access.check(&peer, &stream) {
+            write_response(&mut writer, &Response::error(&e))?;
+            return Err(e);
+        }
+
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut line = String::new();
+            let len = (&mut reader).take(MAX_REQUEST_LEN).read_line(&mut line)?;
+            if len == 0 {
+                return Ok(());
+            }
+            if !line.ends_with('\n') && len as u64 == MAX_REQUEST_LEN {
+                let e = Error::ParseError("Request is too long".to_string());
+                write_response(&mut writer, &Response::error(&e))?;
+                return Err(e);
+            }
+
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle_request(&peer, &request),
+                Err(e) => Response::error(&Error::ParseError(format!("Invalid request: {}", e))),
+            };
+            write_response(&mut writer, &response)?;
+        }
+    }
+
+    /// Serves a request from the allowed client `peer`.
+    pub fn handle_request(&self, peer: &PeerCredentials, request: &Request) -> Response {
+        let quote = |quote: Vec<u8>| Response::Quote {
+            quote: STANDARD.encode(quote),
+        };
+        let result = match request {
+            Request::Quote { report_data } => self.quote(peer, report_data).map(quote),
+            Request::BoundQuote { public_key, nonce } => {
+                self.bound_quote(peer, public_key, nonce).map(quote)
+            }
+            Request::Report { report_data } => {
+                self.report(peer, report_data)
+                    .map(|report| Response::Report {
+                        report: STANDARD.encode(report.to_bytes()),
+                    })
+            }
+        };
+        result.unwrap_or_else(|e| Response::error(&e))
+    }
+
+    fn quote(&self, peer: &PeerCredentials, report_data: &str) -> Result<Vec<u8>> {
+        let bytes = self.unbound_report_data(peer, report_data)?;
+        self.get_quote(peer, &bytes)
+    }
+
+    fn report(&self, peer: &PeerCredentials, report_data: &str) -> Result<TdReportV15> {
+        let bytes = self.unbound_report_data(peer, report_data)?;
+        self.source.get_tdreport(&bytes)
+    }
+
+    /// Decodes the `report_data` of a request that isn't bound to a client
+    /// key, within the client's rate limit.
+    fn unbound_report_data(
+        &self,
+        peer: &PeerCredentials,
+        report_data: &str,
+    ) -> Result<[u8; TDX_REPORT_DATA_LEN]> {
+        if self.bindings.is_some() {
+            return Err(Error::NotSupported(
+                "The agent only serves quotes bound to a client key".to_string(),
+            ));
+        }
+        let mut bytes = [0u8; TDX_REPORT_DATA_LEN];
+        hex::decode_to_slice(report_data, &mut bytes)
+            .map_err(|e| Error::ParseError(format!("Invalid report_data: {}", e)))?;
+        self.limiter.check(peer.uid)?;
+        Ok(bytes)
+    }
+
+    fn bound_quote(
+        &self,
+        peer: &PeerCredentials,
+        public_key: &str,
+        nonce: &str,
+    ) -> Result<Vec<u8>> {
+        let Some(bindings) = &self.bindings else {
+            return Err(Error::NotSupported(
+                "The agent doesn't bind quotes to client keys".to_string(),
+            ));
+        };
+        let public_key = STANDARD
+            .decode(public_key)
+            .map_err(|e| Error::ParseError(format!("Invalid public_key: {}", e)))?;
+        let nonce =
+            hex::decode(nonce).map_err(|e| Error::ParseError(format!("Invalid nonce: {}", e)))?;
+
+        // rate limit before binding, which bounds the keys a client can claim
+        self.limiter.check(peer.uid)?;
+        let mut binding = bindings.bind(peer, &public_key, &nonce)?;
+        binding.session_id = self.session.as_ref().map(|s| s.id.clone());
+        let quote = self.get_quote(peer, &binding.report_data)?;
+        bindings.record(binding);
+        Ok(quote)
+    }
+
+    /// Gets a quote over `report_data` for `peer` from the agent's source,
+    /// alerting on failure.
+    #[cfg_attr(not(feature = "alerts"), allow(unused_variables))]
+    fn get_quote(
+        &self,
+        peer: &PeerCredentials,
+        report_data: &[u8; TDX_REPORT_DATA_LEN],
+    ) -> Result<Vec<u8>> {
+        let result = self.source.get_quote(report_data);
+        #[cfg(feature = "alerts")]
+        if let (Err(e), Some(sink)) = (&result, &self.alert_sink) {
+            let summary = format!("Failed to generate a quote for uid {}: {}", peer.uid, e);
+            // the client is sent the error regardless of the alert's delivery
+            let _ = Alert::new(AlertKind::QuoteFailure, &summary).and_then(|mut alert| {
+                if let Some(session) = &self.session {
+                    alert = alert.with_session_id(&session.id);
+                }
+                sink.send(&alert)
+            });
+        }
+        result
+    }
+}
+
+/// Binds the agent's socket at `path`, with the permissions `mode` (e.g.,
+/// `DEFAULT_SOCKET_MODE`), replacing a stale socket.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if `path` exists and isn't a socket, or
+/// an `Error::IoError` if the socket cannot be bound.
+pub fn bind<P: AsRef<Path>>(path: P, mode: u32) -> Result<UnixListener> {
+    let path = path.as_ref();
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(Error::NotSupported(format!(
+                "Path {} exists and is not a socket",
+                path.display()
+            )));
+        }
+        fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+/// Writes a response line to a client.
+fn write_response<W: Write>(writer: &mut W, response: &Response) -> Result<()> {
+    let mut line =
+        serde_json::to_vec(response).map_err(|e| Error::SerializationError(e.to_string()))?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use limit::RateLimit;
+
+    /// A quote source echoing the report data.
+    struct EchoSource;
+
+    impl QuoteSource for EchoSource {
+        fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+            Ok(report_data.to_vec())
+        }
+
+        fn get_tdreport(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TdReportV15> {
+            Ok(TdReportV15::builder().with_report_data(report_data).build())
+        }
+    }
+
+    /// Sends request lines to `agent` over a socket pair, and returns its
+    /// response lines.
+    fn exchange(agent: &Agent<EchoSource>, requests: &str) -> (Result<()>, Vec<Response>) {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        client.write_all(requests.as_bytes()).unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+
+        let result = agent.handle_connection(server);
+        let responses = BufReader::new(client)
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        (result, responses)
+    }
+
+    #[test]
+    fn test_serve_quotes() {
+        // the test runs as the same user on both ends
+        let uid = unsafe { libc::geteuid() };
+        let agent = Agent::new(EchoSource, AccessPolicy::new().with_uid(uid));
+
+        let requests = format!(
+            "{}\n{}\nnot json\n",
+            serde_json::json!({"method": "quote", "report_data": "ab".repeat(64)}),
+            serde_json::json!({"method": "quote", "report_data": "ab"}),
+        );
+        let (result, responses) = exchange(&agent, &requests);
+        assert!(result.is_ok());
+        assert_eq!(responses.len(), 3);
+        assert_eq!(
+            responses[0],
+            Response::Quote {
+                quote: STANDARD.encode([0xab; 64])
+            }
+        );
+        assert!(matches!(responses[1], Response::Error { .. }));
+        assert!(matches!(responses[2], Response::Error { .. }));
+    }
+
+    #[test]
+    fn test_serve_reports() {
+        let uid = unsafe { libc::geteuid() };
+        let agent = Agent::new(EchoSource, AccessPolicy::new().with_uid(uid));
+
+        let request = serde_json::json!({"method": "report", "report_data": "cd".repeat(64)});
+        let (result, responses) = exchange(&agent, &format!("{}\n", request));
+        assert!(result.is_ok());
+        let Response::Report { report } = &responses[0] else {
+            panic!("Unexpected response {:?}", responses[0]);
+        };
+        let report = TdReportV15::from_bytes(&STANDARD.decode(report).unwrap()).unwrap();
+        assert_eq!(report.get_report_data(), [0xcd; 64]);
+
+        // agents binding quotes to client keys don't serve unbound reports
+        let agent = agent.with_bindings(BindingRegistry::new());
+        let (_, responses) = exchange(&agent, &format!("{}\n", request));
+        assert!(matches!(responses[0], Response::Error { .. }));
+    }
+
+    #[cfg(feature = "alerts")]
+    #[test]
+    fn test_quote_failure_alert() {
+        use crate::alert::tests::RecordingSink;
+        use std::sync::Arc;
+
+        /// A quote source failing to generate quotes.
+        struct FailingSource;
+
+        impl QuoteSource for FailingSource {
+            fn get_quote(&self, _: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+                Err(Error::QuoteError("QGS is unreachable".to_string()))
+            }
+        }
+
+        let uid = unsafe { libc::geteuid() };
+        let sink = Arc::new(RecordingSink::default());
+        let agent = Agent::new(FailingSource, AccessPolicy::new().with_uid(uid))
+            .with_session(BootSession {
+                id: "0011".to_string(),
+                boot_time: 1000,
+            })
+            .with_alert_sink(Box::new(sink.clone()));
+        let peer = PeerCredentials {
+            pid: 1,
+            uid,
+            gid: uid,
+        };
+
+        // rejected requests aren't alerted
+        let request = Request::Quote {
+            report_data: "ab".to_string(),
+        };
+        assert!(matches!(
+            agent.handle_request(&peer, &request),
+            Response::Error { .. }
+        ));
+        assert!(sink.alerts.lock().unwrap().is_empty());
+
+        let request = Request::Quote {
+            report_data: "ab".repeat(64),
+        };
+        assert!(matches!(
+            agent.handle_request(&peer, &request),
+            Response::Error { .. }
+        ));
+        let alerts = sink.alerts.lock().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::QuoteFailure);
+        assert!(alerts[0].summary.contains("QGS is unreachable"));
+        assert_eq!(alerts[0].session_id.as_deref(), Some("0011"));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let uid = unsafe { libc::geteuid() };
+        let limiter = RateLimiter::new().with_client_limit(RateLimit::per_minute(1));
+        let agent =
+            Agent::new(EchoSource, AccessPolicy::new().with_uid(uid)).with_rate_limiter(limiter);
+
+        let request = serde_json::json!({"method": "quote", "report_data": "ab".repeat(64)});
+        let (result, responses) = exchange(&agent, &format!("{}\n{}\n", request, request));
+        assert!(result.is_ok());
+        assert!(matches!(responses[0], Response::Quote { .. }));
+        assert!(matches!(
+            responses[1],
+            Response::Error {
+                retry_after_ms: Some(ms),
+                ..
+            } if ms > 0
+        ));
+    }
+
+    #[test]
+    fn test_bound_quotes() {
+        let uid = unsafe { libc::geteuid() };
+        let agent = Agent::new(EchoSource, AccessPolicy::new().with_uid(uid))
+            .with_bindings(BindingRegistry::new())
+            .with_session(BootSession {
+                id: "0011".to_string(),
+                boot_time: 1000,
+            });
+
+        let requests = format!(
+            "{}\n{}\n",
+            serde_json::json!({"method": "bound_quote", "public_key": STANDARD.encode("key"), "nonce": "00ff"}),
+            serde_json::json!({"method": "quote", "report_data": "ab".repeat(64)}),
+        );
+        let (result, responses) = exchange(&agent, &requests);
+        assert!(result.is_ok());
+        let report_data = binding::report_data_for_client(b"key", &[0x00, 0xff]);
+        assert_eq!(
+            responses[0],
+            Response::Quote {
+                quote: STANDARD.encode(report_data)
+            }
+        );
+        // unbound quotes are refused
+        assert!(matches!(responses[1], Response::Error { .. }));
+
+        let binding = agent.bindings().unwrap().lookup(&report_data).unwrap();
+        assert_eq!(binding.client.uid, uid);
+        assert_eq!(binding.session_id.as_deref(), Some("0011"));
+    }
+
+    #[test]
+    fn test_reject_peer() {
+        // even root is rejected outside the allowed cgroups
+        let access = AccessPolicy::new().with_cgroup("/tdx-attest-test.slice");
+        let agent = Agent::new(EchoSource, access);
+
+        // the client is rejected as soon as it connects
+        let (result, responses) = exchange(&agent, "");
+        assert!(result.is_err_and(|e| e.is_verification_failure()));
+        assert!(matches!(responses[..], [Response::Error { .. }]));
+    }
+
+    #[test]
+    fn test_bind() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-agent-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("agent.sock");
+
+        drop(bind(&path, 0o600)?);
+        // a stale socket is replaced
+        drop(bind(&path, DEFAULT_SOCKET_MODE)?);
+        let mode = fs::metadata(&path)?.permissions().mode();
+        assert_eq!(mode & 0o777, DEFAULT_SOCKET_MODE);
+
+        fs::remove_file(&path)?;
+        fs::write(&path, b"not a socket")?;
+        assert!(bind(&path, DEFAULT_SOCKET_MODE).is_err_and(|e| e.is_not_supported()));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
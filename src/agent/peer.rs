@@ -0,0 +1,332 @@
+//! # Agent Peer Credentials
+//!
+//! This module implements the access control of the attestation agent: the
+//! credentials of a client connected to the agent's Unix socket are read
+//! with `SO_PEERCRED`, i.e., from the kernel rather than from the client,
+//! and checked against the agent's `AccessPolicy`.
+//!
+//! A client is allowed if it runs as root, or as one of the allowed users
+//! or (primary) groups, and, if any cgroups are allowed, if it runs in one of
+//! them or their descendants (e.g., `/system.slice/app.service`), which
+//! restricts access to designated workloads regardless of their users.
+//!
+//! # Notes
+//! - A client's cgroup is read from `/proc/<pid>/cgroup` (on the unified
+//!   cgroup v2 hierarchy) when it connects. `SO_PEERCRED` records the PID of
+//!   the process that connected, so a client that hands its connection to
+//!   another process is still matched by its own cgroup.
+//! - The client is pinned with a pidfd (from `SO_PEERPIDFD` on Linux 6.5 and
+//!   later, or else `pidfd_open()`), and must still be running once its
+//!   cgroup is read, so that the cgroup of another process reusing its PID is
+//!   never matched. Clients that exited are denied, as are clients whose
+//!   cgroup cannot be read. Without `SO_PEERPIDFD`, the pidfd is only opened
+//!   after the client connects, so a client that hands its connection over
+//!   and exits right away can still have its PID reused before then.
+
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+
+/// The credentials of a process connected to a Unix socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerCredentials {
+    /// The process ID of the peer.
+    pub pid: i32,
+    /// The effective user ID of the peer.
+    pub uid: u32,
+    /// The effective group ID of the peer.
+    pub gid: u32,
+}
+
+impl PeerCredentials {
+    /// Reads the credentials of the peer of `stream` with `SO_PEERCRED`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the credentials cannot be read.
+    pub fn from_stream(stream: &UnixStream) -> Result<Self> {
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+        // SAFETY: `cred` and `len` are valid for writes of the option's size,
+        // and the descriptor is owned by `stream`
+        let ret = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self {
+            pid: cred.pid,
+            uid: cred.uid,
+            gid: cred.gid,
+        })
+    }
+
+    /// Returns the cgroup on the unified (v2) hierarchy, if any, of the peer
+    /// of `stream`, whose credentials these are.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the peer's cgroup cannot be read, e.g.,
+    /// because it exited (even if its PID was reused since).
+    pub fn cgroup(&self, stream: &UnixStream) -> Result<Option<String>> {
+        let pidfd = peer_pidfd(stream, self.pid)?;
+        let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", self.pid))?;
+
+        // the PID cannot have been reused if the peer is still running
+        if !is_running(&pidfd) {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH).into());
+        }
+        Ok(parse_unified_cgroup(&cgroups))
+    }
+}
+
+/// Returns a pidfd of the peer of `stream`, whose PID is `pid`.
+fn peer_pidfd(stream: &UnixStream, pid: i32) -> Result<OwnedFd> {
+    let mut fd: libc::c_int = -1;
+    let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+
+    // SAFETY: `fd` and `len` are valid for writes of the option's size, and
+    // the descriptor is owned by `stream`
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERPIDFD,
+            &mut fd as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret == 0 {
+        // SAFETY: the kernel returned a new descriptor, owned by the caller
+        return Ok(unsafe { OwnedFd::from_raw_fd(fd) });
+    }
+
+    // kernels before 6.5 don't support SO_PEERPIDFD
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() != Some(libc::ENOPROTOOPT) {
+        return Err(e.into());
+    }
+    // SAFETY: pidfd_open() takes a PID and flags, and returns a descriptor
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the kernel returned a new descriptor, owned by the caller
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
+
+/// Returns whether the process of `pidfd` is still running (or not yet
+/// reaped), i.e., whether its PID still refers to it.
+fn is_running(pidfd: &OwnedFd) -> bool {
+    // SAFETY: signal 0 only checks that the process exists, and a null
+    // `siginfo` is allowed
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            0,
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    // processes the agent may not signal are still running
+    ret == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Which clients may use the agent.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessPolicy {
+    /// The user IDs allowed to use the agent, besides root.
+    pub allowed_uids: Vec<u32>,
+    /// The (primary) group IDs allowed to use the agent.
+    pub allowed_gids: Vec<u32>,
+    /// The cgroups whose processes (or their descendants') may use the
+    /// agent, if any are set.
+    pub allowed_cgroups: Vec<String>,
+}
+
+impl AccessPolicy {
+    /// Creates a policy that only allows root.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the user `uid`.
+    pub fn with_uid(mut self, uid: u32) -> Self {
+        self.allowed_uids.push(uid);
+        self
+    }
+
+    /// Allows the group `gid`.
+    pub fn with_gid(mut self, gid: u32) -> Self {
+        self.allowed_gids.push(gid);
+        self
+    }
+
+    /// Restricts the agent to the processes in `cgroup` (or its
+    /// descendants), e.g., `/system.slice/app.service`.
+    pub fn with_cgroup(mut self, cgroup: &str) -> Self {
+        self.allowed_cgroups.push(cgroup.to_string());
+        self
+    }
+
+    /// Checks that `peer`, the peer of `stream`, is allowed to use the agent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the peer isn't allowed, or
+    /// if any cgroups are allowed and its cgroup cannot be read.
+    pub fn check(&self, peer: &PeerCredentials, stream: &UnixStream) -> Result<()> {
+        let cgroup = match self.allowed_cgroups.is_empty() {
+            true => None,
+            false => peer.cgroup(stream).map_err(|e| {
+                Error::VerificationError(format!(
+                    "Cannot read the cgroup of process {}: {}",
+                    peer.pid, e
+                ))
+            })?,
+        };
+        self.check_with_cgroup(peer, cgroup.as_deref())
+    }
+
+    /// Checks that `peer`, running in `cgroup`, is allowed to use the agent.
+    fn check_with_cgroup(&self, peer: &PeerCredentials, cgroup: Option<&str>) -> Result<()> {
+        if peer.uid != 0
+            && !self.allowed_uids.contains(&peer.uid)
+            && !self.allowed_gids.contains(&peer.gid)
+        {
+            return Err(Error::VerificationError(format!(
+                "User {} (group {}) is not allowed to use the agent",
+                peer.uid, peer.gid
+            )));
+        }
+
+        if !self.allowed_cgroups.is_empty()
+            && !cgroup.is_some_and(|cgroup| {
+                self.allowed_cgroups
+                    .iter()
+                    .any(|allowed| is_cgroup_within(cgroup, allowed))
+            })
+        {
+            return Err(Error::VerificationError(format!(
+                "Process {} is not in an allowed cgroup",
+                peer.pid
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the cgroup of the unified hierarchy (the `0::<path>` entry) in
+/// the contents of a `/proc/<pid>/cgroup` file.
+fn parse_unified_cgroup(cgroups: &str) -> Option<String> {
+    cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::to_string)
+}
+
+/// Returns whether `cgroup` is `allowed` or one of its descendants.
+fn is_cgroup_within(cgroup: &str, allowed: &str) -> bool {
+    let allowed = allowed.trim_end_matches('/');
+    match cgroup.strip_prefix(allowed) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || allowed.is_empty(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(uid: u32, gid: u32) -> PeerCredentials {
+        PeerCredentials { pid: 1, uid, gid }
+    }
+
+    #[test]
+    fn test_peer_credentials() -> Result<()> {
+        let (client, _server) = UnixStream::pair()?;
+        let cred = PeerCredentials::from_stream(&client)?;
+        assert_eq!(cred.pid, std::process::id() as i32);
+        assert_eq!(cred.uid, unsafe { libc::geteuid() });
+        Ok(())
+    }
+
+    #[test]
+    fn test_peer_cgroup() -> Result<()> {
+        let (client, _server) = UnixStream::pair()?;
+        let cred = PeerCredentials::from_stream(&client)?;
+        let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+        assert_eq!(cred.cgroup(&client)?, parse_unified_cgroup(&cgroups));
+
+        // a peer whose cgroup cannot be read is denied
+        let mut child = std::process::Command::new("true").spawn()?;
+        child.wait()?;
+        let exited = PeerCredentials {
+            pid: child.id() as i32,
+            ..cred
+        };
+        let policy = AccessPolicy::new().with_cgroup("/");
+        assert_eq!(
+            policy.check(&cred, &client).is_ok(),
+            parse_unified_cgroup(&cgroups).is_some()
+        );
+        assert!(
+            policy
+                .check(&exited, &client)
+                .is_err_and(|e| e.is_verification_failure())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_policy() {
+        let policy = AccessPolicy::new().with_uid(1000).with_gid(2000);
+        assert!(policy.check_with_cgroup(&peer(0, 0), None).is_ok());
+        assert!(policy.check_with_cgroup(&peer(1000, 1000), None).is_ok());
+        assert!(policy.check_with_cgroup(&peer(1001, 2000), None).is_ok());
+        assert!(
+            policy
+                .check_with_cgroup(&peer(1001, 1001), None)
+                .is_err_and(|e| e.is_verification_failure())
+        );
+
+        // cgroups restrict even allowed users
+        let policy = policy.with_cgroup("/system.slice/app.service");
+        let allowed = |cgroup| policy.check_with_cgroup(&peer(1000, 1000), cgroup).is_ok();
+        assert!(allowed(Some("/system.slice/app.service")));
+        assert!(allowed(Some("/system.slice/app.service/worker")));
+        assert!(!allowed(Some("/system.slice/app.service2")));
+        assert!(!allowed(Some("/user.slice")));
+        assert!(!allowed(None));
+    }
+
+    #[test]
+    fn test_parse_unified_cgroup() {
+        let cgroups = "12:cpu,cpuacct:/legacy\n0::/system.slice/app.service\n";
+        assert_eq!(
+            parse_unified_cgroup(cgroups).as_deref(),
+            Some("/system.slice/app.service")
+        );
+        assert_eq!(parse_unified_cgroup("12:cpu:/legacy\n"), None);
+    }
+}
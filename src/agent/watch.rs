@@ -0,0 +1,319 @@
+//! # Continuous Attestation Watcher
+//!
+//! This module detects the runtime drift of long-running TDs: a `Watcher`
+//! periodically re-collects the TD's `TDREPORT` (see `tdx-attest watch`),
+//! compares it with the previous one (see `core::report::diff`), and emits a
+//! `WatchEvent` for each kind of change:
+//! - `measurement`: the TD's measurement registers (e.g., an RTMR extended
+//!   by a runtime measurement), attributes, XFAM or bound service TDs
+//!   changed.
+//! - `tcb`: the platform's TCB changed, i.e., its SVNs (e.g., after a
+//!   microcode update or a TD-preserving TDX module update) or TDX module
+//!   identity, which changes its TCB status for relying parties.
+//!
+//! The report data, which isn't a measurement, is ignored.
+//!
+//! Events are handed to a callback, which e.g. logs them, sends them to an
+//! alert sink (see `WatchEvent::to_alert()`, with the `alerts` feature) or
+//! stops watching.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tdx_workload_attestation::agent::watch::Watcher;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let provider = LinuxTdxProvider::new();
+//! let mut watcher = Watcher::new(|| Ok(provider.get_tdreport()?.into_report()));
+//! watcher
+//!     .run(Duration::from_secs(60), |event| {
+//!         println!("{}", serde_json::to_string(event).unwrap());
+//!         Ok(true)
+//!     })
+//!     .unwrap();
+//! ```
+
+#[cfg(feature = "alerts")]
+use crate::alert::{Alert, AlertKind};
+use crate::clock::{Clock, SystemClock};
+use crate::core::report::TdReportV15;
+use crate::core::report::diff::{FieldChange, Fields, diff};
+use crate::error::Result;
+
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+
+/// The report fields that make up the platform's TCB.
+const TCB_FIELDS: [&str; 6] = [
+    "CPUSVN",
+    "TEE_TCB_SVN",
+    "TEE_TCB_SVN2",
+    "MRSEAM",
+    "MRSIGNERSEAM",
+    "SEAM ATTRIBUTES",
+];
+
+/// The report fields that aren't watched.
+const IGNORED_FIELDS: [&str; 1] = ["REPORTDATA"];
+
+/// The kind of a change detected by a `Watcher`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// The TD's measurements or attributes changed.
+    Measurement,
+    /// The platform's TCB changed.
+    Tcb,
+}
+
+/// A field that changed between two collections.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Drift {
+    /// The name of the field (e.g., `RTMR2`).
+    pub field: String,
+    /// The hex-encoded previous value.
+    pub old: String,
+    /// The hex-encoded new value.
+    pub new: String,
+    /// What the change means, if the field can be decoded (see
+    /// `FieldChange::describe()`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl From<&FieldChange> for Drift {
+    fn from(change: &FieldChange) -> Self {
+        Drift {
+            field: change.field.to_string(),
+            old: hex::encode(&change.old),
+            new: hex::encode(&change.new),
+            description: change.describe(),
+        }
+    }
+}
+
+/// A change detected by a `Watcher`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WatchEvent {
+    /// When the change was detected, in seconds since the Unix epoch.
+    pub time: u64,
+    /// The kind of the change.
+    pub kind: ChangeKind,
+    /// The fields that changed, in report layout order.
+    pub changes: Vec<Drift>,
+}
+
+#[cfg(feature = "alerts")]
+impl WatchEvent {
+    /// Returns the `drift` alert of the event, detailing the changed fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if the changes cannot be
+    /// encoded.
+    pub fn to_alert(&self) -> Result<Alert> {
+        let fields: Vec<_> = self.changes.iter().map(|c| c.field.as_str()).collect();
+        let summary = match self.kind {
+            ChangeKind::Measurement => "The TD's measurements changed",
+            ChangeKind::Tcb => "The platform's TCB changed",
+        };
+        Alert::new(
+            AlertKind::Drift,
+            &format!("{}: {}", summary, fields.join(", ")),
+        )?
+        .with_time(self.time)
+        .with_details(self)
+    }
+}
+
+/// Periodically re-collects the TD's report and detects its changes.
+pub struct Watcher<F> {
+    collect: F,
+    clock: Box<dyn Clock>,
+    last: Option<Fields>,
+}
+
+impl<F> Watcher<F>
+where
+    F: FnMut() -> Result<TdReportV15>,
+{
+    /// Creates a watcher of the reports returned by `collect` (e.g.,
+    /// `LinuxTdxProvider::get_tdreport()`).
+    pub fn new(collect: F) -> Self {
+        Watcher {
+            collect,
+            clock: Box::new(SystemClock),
+            last: None,
+        }
+    }
+
+    /// Timestamps events with `clock`, instead of the system clock.
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Collects the report and returns the changes since the previous one,
+    /// grouped by kind.
+    ///
+    /// The first poll only records the baseline, and returns no events.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the collection or the clock.
+    pub fn poll(&mut self) -> Result<Vec<WatchEvent>> {
+        let fields = Fields::from(&(self.collect)()?);
+        let Some(last) = self.last.replace(fields.clone()) else {
+            return Ok(vec![]);
+        };
+
+        let (tcb, measurements): (Vec<_>, Vec<_>) = diff(&last, &fields)
+            .into_iter()
+            .filter(|change| !IGNORED_FIELDS.contains(&change.field))
+            .partition(|change| TCB_FIELDS.contains(&change.field));
+
+        let mut events = vec![];
+        for (kind, changes) in [
+            (ChangeKind::Measurement, measurements),
+            (ChangeKind::Tcb, tcb),
+        ] {
+            if !changes.is_empty() {
+                events.push(WatchEvent {
+                    time: self.clock.now()?,
+                    kind,
+                    changes: changes.iter().map(Drift::from).collect(),
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    /// Polls every `interval` and hands the events to `on_event`, until it
+    /// returns `false`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of `poll()` or `on_event`.
+    pub fn run<E>(&mut self, interval: Duration, mut on_event: E) -> Result<()>
+    where
+        E: FnMut(&WatchEvent) -> Result<bool>,
+    {
+        loop {
+            for event in self.poll()? {
+                if !on_event(&event)? {
+                    return Ok(());
+                }
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::core::report::TdReportBuilder;
+    use crate::error::Error;
+    use crate::tdx::TDX_MR_REG_LEN;
+
+    fn report(rtmr3: u8, tee_tcb_svn: u8, report_data: u8) -> TdReportV15 {
+        let mut rtmrs = [[0; TDX_MR_REG_LEN]; 4];
+        rtmrs[3] = [rtmr3; TDX_MR_REG_LEN];
+        let mut svn = [0; 16];
+        svn[0] = tee_tcb_svn;
+        TdReportBuilder::new()
+            .with_rtmrs(&rtmrs)
+            .with_tee_tcb_svn(&svn)
+            .with_report_data(&[report_data; 64])
+            .build()
+    }
+
+    #[test]
+    fn test_poll() -> Result<()> {
+        let mut reports = vec![
+            report(1, 3, 0),
+            report(1, 3, 1),
+            report(2, 3, 0),
+            report(3, 4, 0),
+        ]
+        .into_iter();
+        let mut watcher = Watcher::new(|| {
+            reports
+                .next()
+                .ok_or(Error::NotSupported("done".to_string()))
+        })
+        .with_clock(Box::new(FixedClock::new(1000)));
+
+        // the baseline, and a change of the report data only
+        assert!(watcher.poll()?.is_empty());
+        assert!(watcher.poll()?.is_empty());
+
+        let events = watcher.poll()?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ChangeKind::Measurement);
+        assert_eq!(events[0].time, 1000);
+        assert_eq!(events[0].changes.len(), 1);
+        assert_eq!(events[0].changes[0].field, "RTMR3");
+        assert_eq!(events[0].changes[0].new, "02".repeat(TDX_MR_REG_LEN));
+
+        let events = watcher.poll()?;
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ChangeKind::Measurement, ChangeKind::Tcb]);
+        assert_eq!(events[1].changes[0].field, "TEE_TCB_SVN");
+        assert_eq!(
+            events[1].changes[0].description.as_deref(),
+            Some("TDX module SVN 3 -> 4")
+        );
+        assert!(
+            serde_json::to_string(&events[1])
+                .unwrap()
+                .starts_with(r#"{"time":1000,"kind":"tcb","changes":[{"field":"TEE_TCB_SVN""#)
+        );
+
+        assert!(watcher.poll().unwrap_err().is_not_supported());
+        Ok(())
+    }
+
+    #[cfg(feature = "alerts")]
+    #[test]
+    fn test_to_alert() -> Result<()> {
+        let event = WatchEvent {
+            time: 1000,
+            kind: ChangeKind::Measurement,
+            changes: vec![Drift {
+                field: "RTMR3".to_string(),
+                old: "00".to_string(),
+                new: "01".to_string(),
+                description: None,
+            }],
+        };
+        let alert = event.to_alert()?;
+        assert_eq!(alert.kind, AlertKind::Drift);
+        assert_eq!(alert.time, 1000);
+        assert_eq!(alert.summary, "The TD's measurements changed: RTMR3");
+        assert_eq!(alert.details["changes"][0]["field"], "RTMR3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        let mut reports = vec![report(1, 3, 0), report(1, 3, 0), report(2, 3, 0)].into_iter();
+        let mut watcher = Watcher::new(|| {
+            reports
+                .next()
+                .ok_or(Error::NotSupported("done".to_string()))
+        });
+
+        let mut events = vec![];
+        watcher.run(Duration::ZERO, |event| {
+            events.push(event.clone());
+            Ok(false)
+        })?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].changes[0].field, "RTMR3");
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! # Alert Sinks
+//!
+//! This module notifies operators of the attestation events that need their
+//! attention, by sending `Alert`s to an `AlertSink`:
+//! - `verification_failure`: evidence failed appraisal (see
+//!   `tdx-attest appraise`).
+//! - `endorsement_mismatch`: evidence failed appraisal because the TD's
+//!   launch endorsement didn't match its measurements.
+//! - `drift`: a long-running TD's measurements or TCB changed (see
+//!   `agent::watch`, and `tdx-attest watch`).
+//! - `quote_failure`: the attestation agent failed to generate a quote for a
+//!   client (see `agent::Agent::with_alert_sink()`).
+//!
+//! The `WebhookSink` posts each alert, as JSON, to an operator URL (e.g., of
+//! an incident management system), optionally signed with a shared key, so
+//! that the receiver can authenticate it: the `X-Tdx-Attest-Signature`
+//! header then carries `sha384=<hex>`, the HMAC-SHA384 of the request body
+//! (see `verify_alert_signature()`).
+//!
+//! Sinks are configured with the `alert_webhook` and `alert_key_path`
+//! settings (see `config::Config::alert_sink()`).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::alert::{Alert, AlertKind, AlertSink, WebhookSink};
+//!
+//! let sink = WebhookSink::new("https://alerts.example.com/tdx")
+//!     .with_hmac_key(b"a secret shared with the receiver");
+//! let alert = Alert::new(AlertKind::VerificationFailure, "Bundle failed appraisal").unwrap();
+//! sink.send(&alert).unwrap();
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::http::{http_client, send};
+use crate::retry::RetryPolicy;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha384;
+use std::sync::Arc;
+
+/// The header carrying the signature of signed alerts.
+pub const SIGNATURE_HEADER: &str = "X-Tdx-Attest-Signature";
+
+/// The prefix of the signature header's value.
+const SIGNATURE_PREFIX: &str = "sha384=";
+
+/// The kind of an alert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// Evidence failed appraisal.
+    VerificationFailure,
+    /// The TD's launch endorsement didn't match its measurements.
+    EndorsementMismatch,
+    /// The TD's measurements or the platform's TCB changed.
+    Drift,
+    /// The attestation agent failed to generate a quote.
+    QuoteFailure,
+    /// The TD rebooted, starting a new boot session.
+    NewBootSession,
+}
+
+/// An event for operators.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Alert {
+    /// The kind of the event.
+    pub kind: AlertKind,
+    /// When the event happened, in seconds since the Unix epoch.
+    pub time: u64,
+    /// A human-readable summary of the event.
+    pub summary: String,
+    /// The details of the event, e.g., the failed checks or the changed
+    /// fields.
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub details: serde_json::Value,
+    /// The boot session of the TD the event happened on, if known (see
+    /// `evidence::boot_session`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl Alert {
+    /// Creates an alert of `kind` happening now, without details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the system time is unavailable.
+    pub fn new(kind: AlertKind, summary: &str) -> Result<Self> {
+        Ok(Alert {
+            kind,
+            time: SystemClock.now()?,
+            summary: summary.to_string(),
+            details: serde_json::Value::Null,
+            session_id: None,
+        })
+    }
+
+    /// Sets the alert's time.
+    pub fn with_time(mut self, time: u64) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Sets the boot session ID of the TD the event happened on.
+    pub fn with_session_id(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Sets the alert's details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if `details` cannot be encoded
+    /// in JSON.
+    pub fn with_details<T: Serialize>(mut self, details: &T) -> Result<Self> {
+        self.details =
+            serde_json::to_value(details).map_err(|e| Error::SerializationError(e.to_string()))?;
+        Ok(self)
+    }
+
+    /// Encodes the alert in JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if the alert cannot be encoded.
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// A destination for alerts.
+pub trait AlertSink: Send + Sync {
+    /// Sends `alert`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the alert cannot be delivered.
+    fn send(&self, alert: &Alert) -> Result<()>;
+}
+
+impl<S: AlertSink + ?Sized> AlertSink for Arc<S> {
+    fn send(&self, alert: &Alert) -> Result<()> {
+        self.as_ref().send(alert)
+    }
+}
+
+/// Posts alerts, as JSON, to a webhook.
+#[derive(Clone, Debug)]
+pub struct WebhookSink {
+    url: String,
+    key: Option<Vec<u8>>,
+    retry_policy: RetryPolicy,
+}
+
+impl WebhookSink {
+    /// Creates a sink posting unsigned alerts to `url`, with the default
+    /// `RetryPolicy`.
+    pub fn new(url: &str) -> Self {
+        WebhookSink {
+            url: url.to_string(),
+            key: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Signs the alerts with `key` (see `sign_alert()`).
+    pub fn with_hmac_key(mut self, key: &[u8]) -> Self {
+        self.key = Some(key.to_vec());
+        self
+    }
+
+    /// Retries failed posts according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Returns the webhook's URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl AlertSink for WebhookSink {
+    /// Posts `alert` to the webhook.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::SerializationError` if the alert cannot be encoded.
+    /// - `Error::NetworkError` if the webhook rejects the alert, or cannot be
+    ///   reached after exhausting the `RetryPolicy`.
+    fn send(&self, alert: &Alert) -> Result<()> {
+        let client = http_client(&self.retry_policy)?;
+        let body = alert.to_json()?;
+        let signature = self.key.as_ref().map(|key| sign_alert(key, &body));
+
+        self.retry_policy.run(|| {
+            let mut req = client
+                .post(&self.url)
+                .header("Content-Type", "application/json");
+            if let Some(signature) = &signature {
+                req = req.header(SIGNATURE_HEADER, signature);
+            }
+            send(req.body(body.clone()))
+        })?;
+        Ok(())
+    }
+}
+
+/// Returns the signature header value of the alert encoded in `body`, i.e.,
+/// `sha384=` followed by the hex-encoded HMAC-SHA384 of `body` with `key`.
+pub fn sign_alert(key: &[u8], body: &[u8]) -> String {
+    let mac = hmac_sha384(key, body).finalize().into_bytes();
+    format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac))
+}
+
+/// Checks, in constant time, the signature header value `signature` of the
+/// alert encoded in `body`, e.g., in a webhook receiver.
+pub fn verify_alert_signature(key: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(mac) = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|mac| hex::decode(mac).ok())
+    else {
+        return false;
+    };
+    hmac_sha384(key, body).verify_slice(&mac).is_ok()
+}
+
+/// Computes the HMAC-SHA384 of `data` with `key`.
+fn hmac_sha384(key: &[u8], data: &[u8]) -> Hmac<Sha384> {
+    let mut mac = Hmac::<Sha384>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A sink recording the alerts sent to it.
+    #[derive(Default)]
+    pub(crate) struct RecordingSink {
+        pub(crate) alerts: Mutex<Vec<Alert>>,
+    }
+
+    impl AlertSink for RecordingSink {
+        fn send(&self, alert: &Alert) -> Result<()> {
+            self.alerts.lock().unwrap().push(alert.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_alert_json() -> Result<()> {
+        let alert =
+            Alert::new(AlertKind::EndorsementMismatch, "Endorsement mismatch")?.with_time(1000);
+        assert_eq!(
+            alert.to_json()?,
+            br#"{"kind":"endorsement_mismatch","time":1000,"summary":"Endorsement mismatch"}"#
+        );
+
+        // shared sinks record the alerts sent through any of their handles
+        let sink = Arc::new(RecordingSink::default());
+        let shared: Box<dyn AlertSink> = Box::new(sink.clone());
+        shared.send(&alert)?;
+        assert_eq!(sink.alerts.lock().unwrap()[0], alert);
+
+        let alert = alert
+            .with_details(&["endorsement"])?
+            .with_session_id("0011");
+        assert!(
+            String::from_utf8(alert.to_json()?)
+                .unwrap()
+                .ends_with(r#""details":["endorsement"],"session_id":"0011"}"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_alert_signature() {
+        let signature = sign_alert(b"key", b"{}");
+        assert!(signature.starts_with("sha384="));
+        assert_eq!(signature.len(), 7 + 96);
+
+        assert!(verify_alert_signature(b"key", b"{}", &signature));
+        assert!(!verify_alert_signature(b"other key", b"{}", &signature));
+        assert!(!verify_alert_signature(b"key", b"{ }", &signature));
+        assert!(!verify_alert_signature(
+            b"key",
+            b"{}",
+            signature.trim_start_matches("sha384=")
+        ));
+        assert!(!verify_alert_signature(b"key", b"{}", "sha384=nothex"));
+    }
+
+    #[test]
+    fn test_webhook_unreachable() {
+        let sink = WebhookSink::new("http://127.0.0.1:1/alerts")
+            .with_hmac_key(b"key")
+            .with_retry_policy(RetryPolicy::no_retry());
+        assert_eq!(sink.url(), "http://127.0.0.1:1/alerts");
+        let alert = Alert::new(AlertKind::Drift, "RTMR3 changed").unwrap();
+        assert!(sink.send(&alert).unwrap_err().is_network());
+    }
+}
@@ -0,0 +1,33 @@
+use clap::{Command, Subcommand};
+use clap_complete::Shell;
+use std::path::Path;
+
+use tdx_workload_attestation::error::Result;
+
+#[derive(Subcommand)]
+pub enum DocsCommands {
+    /// Generate the man pages of the CLI and each of its subcommands
+    Gen {
+        /// The directory to write the man pages (tdx-attest.1,
+        /// tdx-attest-<SUBCOMMAND>.1, ...) to
+        #[arg(short, long, default_value = "man")]
+        out_dir: String,
+    },
+}
+
+pub fn handle(cli: Command, cmd: DocsCommands) -> Result<()> {
+    match cmd {
+        DocsCommands::Gen { out_dir } => {
+            std::fs::create_dir_all(&out_dir)?;
+            clap_mangen::generate_to(cli, Path::new(&out_dir))?;
+            println!("Saved man pages to {}", out_dir);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the completion script of the CLI for `shell`.
+pub fn print_completions(mut cli: Command, shell: Shell) {
+    let name = cli.get_name().to_string();
+    clap_complete::generate(shell, &mut cli, name, &mut std::io::stdout());
+}
@@ -0,0 +1,71 @@
+//! The exit codes of the CLI, a stable contract for scripts and CI jobs
+//! gating on its commands:
+//! - `0`: the command succeeded.
+//! - `1`: the command failed for any other reason (e.g., an I/O error).
+//! - `2`: verification failed (e.g., the evidence didn't pass appraisal).
+//! - `3`: the platform doesn't support the command (e.g., it isn't a TD),
+//!   which commands only report as a failure with `--strict` if they'd
+//!   otherwise just print it.
+//! - `4`: a transient failure (e.g., a network error or rate limit), which
+//!   may succeed when retried.
+//! - `5`: the appraisal policy (or the configuration it's loaded with) is
+//!   invalid.
+
+use std::process::ExitCode;
+
+use tdx_workload_attestation::error::{Error, ErrorKind};
+
+/// The command failed for a reason without a dedicated exit code.
+pub const FAILURE: u8 = 1;
+/// Verification failed.
+pub const VERIFICATION_FAILED: u8 = 2;
+/// The platform doesn't support the command.
+pub const NOT_SUPPORTED: u8 = 3;
+/// A transient failure, which may succeed when retried.
+pub const TRANSIENT: u8 = 4;
+/// The appraisal policy or configuration is invalid.
+pub const POLICY_ERROR: u8 = 5;
+
+/// The error of a command, and the exit code it maps to.
+#[derive(Debug)]
+pub struct CliError {
+    pub error: Error,
+    pub code: u8,
+}
+
+/// The result of a command.
+pub type CliResult<T = ()> = std::result::Result<T, CliError>;
+
+impl CliError {
+    /// Wraps an error loading the appraisal policy or configuration.
+    pub fn policy(error: Error) -> Self {
+        Self {
+            error,
+            code: POLICY_ERROR,
+        }
+    }
+
+    /// Returns the exit code of the error.
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.code)
+    }
+}
+
+impl From<Error> for CliError {
+    /// Maps the error to its exit code by its kind.
+    fn from(error: Error) -> Self {
+        let code = match error.kind() {
+            ErrorKind::Signature | ErrorKind::Verification => VERIFICATION_FAILED,
+            ErrorKind::NotSupported => NOT_SUPPORTED,
+            ErrorKind::Network | ErrorKind::RateLimited => TRANSIENT,
+            _ => FAILURE,
+        };
+        Self { error, code }
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(error: std::io::Error) -> Self {
+        Error::from(error).into()
+    }
+}
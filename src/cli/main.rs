@@ -1,21 +1,61 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use std::fs::File;
 use std::io::Write;
+use std::process::ExitCode;
+use std::time::Duration;
+#[cfg(feature = "alerts")]
+use tdx_workload_attestation::alert::{Alert, AlertSink};
+#[cfg(feature = "host-gcp-tdx")]
+use tdx_workload_attestation::verify_launch_endorsement;
 use tdx_workload_attestation::{
+    agent::binding::BindingRegistry,
+    agent::epoch::{AgentState, BootEpoch, DEFAULT_STATE_PATH},
+    agent::health::HealthChecker,
+    agent::limit::{RateLimit, RateLimiter},
+    agent::peer::AccessPolicy,
+    agent::watch::Watcher,
+    agent::{Agent, DEFAULT_SOCKET_PATH},
+    config::Config,
     error::{Error, Result},
+    evidence::Bundle,
+    evidence::boot_session::BootSession,
+    evidence::signed::{SignedFile, signature_path},
+    measure::boot_hook::{
+        BootManifest, DEFAULT_EVENT_LOG_PATH, DEFAULT_MANIFEST_PATH, run_boot_hook,
+    },
+    measure::event_log::EventLog,
+    measure::launch::{check_launch, measure_launch, resolve_program},
+    metrics,
     provider::AttestationProvider,
     tdx::LinuxTdxProvider,
+    tdx::report::CollectedEvidence,
 };
-#[cfg(feature = "host-gcp-tdx")]
-use tdx_workload_attestation::{gcp::GcpTdxHost, host::TeeHost};
 
+mod docs;
+mod exit;
 mod platform;
+mod policy;
+mod report;
+
+use exit::{CliError, CliResult};
 
 #[derive(Parser)]
-#[command(version, about)]
+#[command(name = "tdx-attest", version, about)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// The configuration file (defaults to $TDX_ATTEST_CONFIG, or
+    /// /etc/tdx-attest/config.toml if it exists)
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// Write the attestation metrics (in the Prometheus text format) to this
+    /// file after the command, e.g., for node_exporter's textfile collector
+    #[arg(long = "metrics-file", global = true)]
+    metrics_file: Option<String>,
+    /// Fail (with exit code 3) rather than succeed when the platform doesn't
+    /// support TDX, e.g., to gate CI jobs on attestation
+    #[arg(long, global = true, default_value = "false")]
+    strict: bool,
 }
 
 #[derive(Subcommand)]
@@ -26,6 +66,27 @@ enum Commands {
         #[command(subcommand)]
         command: platform::PlatformCommands,
     },
+    /// Validate, test and explain appraisal policies
+    Policy {
+        #[command(subcommand)]
+        command: policy::PolicyCommands,
+    },
+    /// Inspect saved TD reports and quotes
+    Report {
+        #[command(subcommand)]
+        command: report::ReportCommands,
+    },
+    /// Print the shell completion script, e.g., `tdx-attest completions bash
+    /// > /etc/bash_completion.d/tdx-attest`
+    Completions {
+        /// The shell to complete commands in
+        shell: clap_complete::Shell,
+    },
+    /// Generate the CLI's documentation
+    Docs {
+        #[command(subcommand)]
+        command: docs::DocsCommands,
+    },
     /// Quote the TD, if available
     #[command(alias = "q")]
     Quote {
@@ -43,6 +104,168 @@ enum Commands {
         /// Save the JSON-encoded TD quote to a file
         #[arg(short, long = "save", default_value = "false")]
         save: bool,
+        #[command(flatten)]
+        sign: SignArgs,
+    },
+    /// Measure the files, directories and kernel command line configured in
+    /// the boot hook manifest into RTMR3
+    BootHook {
+        /// The path to the boot hook manifest
+        #[arg(short, long, default_value = DEFAULT_MANIFEST_PATH)]
+        manifest: String,
+        /// Only parse and validate the manifest, without measuring anything
+        #[arg(short, long, default_value = "false")]
+        check: bool,
+    },
+    /// Measure a program and its arguments into RTMR3, optionally check the
+    /// TD's measurements against a policy, and only then execute the program,
+    /// e.g., `tdx-attest exec -- /usr/bin/app --config app.toml`
+    Exec {
+        /// The event log to record the launch in (defaults to the boot
+        /// hook's, so that both replay RTMR3 together)
+        #[arg(short, long = "event-log", default_value = DEFAULT_EVENT_LOG_PATH)]
+        event_log: String,
+        /// Only execute the program if the TD's measurements (including the
+        /// program's) pass this TOML appraisal policy
+        #[arg(short, long)]
+        policy: Option<String>,
+        /// The program to execute (looked up in PATH if it has no `/`), and
+        /// its arguments
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Collect the TD's quote, event logs and platform info into an evidence
+    /// bundle
+    #[command(alias = "c")]
+    Collect {
+        /// The filename to save the CBOR-encoded evidence bundle
+        #[arg(short, long)]
+        out: String,
+        /// The hex-encoded nonce to bind into the quote
+        #[arg(short, long)]
+        nonce: String,
+        /// The application event log to include (e.g., the boot hook's)
+        #[arg(short, long = "event-log")]
+        event_log: Option<String>,
+        /// Also archive the bundle in this directory, grouped by boot (see
+        /// `evidence::store`)
+        #[arg(long)]
+        archive: Option<String>,
+        /// Include GCP's launch endorsement of the TD's MRTD
+        #[cfg(feature = "host-gcp-tdx")]
+        #[arg(short, long = "gcp-endorsement", default_value = "false")]
+        gcp_endorsement: bool,
+        #[command(flatten)]
+        sign: SignArgs,
+    },
+    /// Check a saved report or evidence bundle against its signature
+    VerifyFile {
+        /// The saved file (its signature is read from <FILE>.sig)
+        #[arg(short, long)]
+        file: String,
+        /// The key file the file was signed with
+        #[arg(short, long)]
+        key: Option<String>,
+        /// The directory holding the trust anchors for verifying the quote
+        /// of a TD signature (root_ca.der or root_ca.pem)
+        #[cfg(feature = "host-verification")]
+        #[arg(short, long)]
+        collateral: Option<String>,
+    },
+    #[cfg(feature = "host-verification")]
+    /// Appraise an evidence bundle against a policy, and print the verdict
+    #[command(alias = "a")]
+    Appraise {
+        /// The CBOR-encoded evidence bundle to appraise
+        #[arg(short, long)]
+        bundle: String,
+        /// The TOML appraisal policy (defaults to the configured policy, or an
+        /// empty policy)
+        #[arg(short, long)]
+        policy: Option<String>,
+        /// The directory holding the trust anchors and TCB collateral
+        /// (root_ca.der or root_ca.pem, and optionally other trust anchors,
+        /// tcb_info.json, qe_identity.json and tcb_signing_chain.pem),
+        /// defaults to the configured one
+        #[arg(short, long)]
+        collateral: Option<String>,
+        /// The hex-encoded nonce the bundle must bind (overrides the policy's)
+        #[arg(short, long)]
+        nonce: Option<String>,
+        /// Sign an AR4SI attestation result with the key in this file (ES256
+        /// for a PEM EC private key, HMAC-SHA384 otherwise)
+        #[arg(long = "ar4si-key", requires = "ar4si_out")]
+        ar4si_key: Option<String>,
+        /// Save the signed AR4SI attestation result (an EAR JWT) to this file
+        #[arg(long = "ar4si-out", requires = "ar4si_key")]
+        ar4si_out: Option<String>,
+        /// The number of threads to run the checks on (defaults to the
+        /// configured number, or a thread per CPU)
+        #[cfg(feature = "parallel-verification")]
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Serve quotes to the TD's workloads over a Unix socket
+    Serve {
+        /// The path of the agent's socket
+        #[arg(short, long, default_value = DEFAULT_SOCKET_PATH)]
+        socket: String,
+        /// The octal permissions of the agent's socket
+        #[arg(short, long, default_value = "660")]
+        mode: String,
+        /// Allow the user with this ID to request quotes (root is always
+        /// allowed)
+        #[arg(long = "allow-uid")]
+        allow_uids: Vec<u32>,
+        /// Allow the users whose primary group has this ID to request quotes
+        #[arg(long = "allow-gid")]
+        allow_gids: Vec<u32>,
+        /// Only allow the processes in this cgroup (or its descendants) to
+        /// request quotes, e.g., /system.slice/app.service
+        #[arg(long = "allow-cgroup")]
+        allow_cgroups: Vec<String>,
+        /// Limit the quotes served to all clients to this many per minute
+        #[arg(long = "rate-limit")]
+        rate_limit: Option<u32>,
+        /// Limit the quotes served to each client (user) to this many per
+        /// minute
+        #[arg(long = "client-rate-limit")]
+        client_rate_limit: Option<u32>,
+        /// Only serve quotes bound to the client's public key and a nonce,
+        /// over keys claimed by that client
+        #[arg(long = "bind-client-keys")]
+        bind_client_keys: bool,
+        /// Also serve the /healthz and /readyz endpoints over HTTP on this
+        /// address, e.g., 127.0.0.1:8080 (readiness also checks the
+        /// configured trust anchors, if any)
+        #[arg(long = "health-addr")]
+        health_addr: Option<String>,
+        /// The file persisting the boot the agent last ran in, to detect
+        /// reboots of the TD
+        #[arg(long, default_value = DEFAULT_STATE_PATH)]
+        state: String,
+        /// On a reboot of the TD, rotate the evidence archive in this
+        /// directory (as written by `collect --archive`)
+        #[arg(long)]
+        archive: Option<String>,
+        /// The number of boots whose bundles the rotated archive keeps
+        #[arg(long = "keep-boots", default_value = "1", requires = "archive")]
+        keep_boots: usize,
+    },
+    /// Periodically re-collect the TD's report, and print an event (in JSON)
+    /// for each change of its measurements or the platform's TCB
+    Watch {
+        /// The number of seconds between collections
+        #[arg(short, long, default_value = "60")]
+        interval: u64,
+        #[cfg(feature = "alerts")]
+        /// Also send each change as an alert to this webhook (defaults to the
+        /// configured alert webhook)
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Stop watching and exit with an error at the first change
+        #[arg(long = "exit-on-change", default_value = "false")]
+        exit_on_change: bool,
     },
     #[cfg(feature = "host-gcp-tdx")]
     /// Verify the TD, if available
@@ -54,10 +277,24 @@ enum Commands {
     },
 }
 
-fn handle_not_supported(e: Error) -> Result<()> {
+/// How to sign a saved file.
+#[derive(clap::Args)]
+struct SignArgs {
+    /// Sign the saved file with the key in this file (HMAC-SHA384), saving
+    /// the signature to <FILE>.sig
+    #[arg(long = "sign-key", conflicts_with = "sign_td")]
+    sign_key: Option<String>,
+    /// Sign the saved file with a quote binding its digest, saving the
+    /// signature to <FILE>.sig
+    #[arg(long = "sign-td", default_value = "false")]
+    sign_td: bool,
+}
+
+fn handle_not_supported(e: Error, strict: bool) -> Result<()> {
     match e {
-        Error::NotSupported(_) => {
-            // we don't actually want the CLI to error when TDX isn't supported
+        Error::NotSupported(_) if !strict => {
+            // we don't actually want the CLI to error when TDX isn't supported,
+            // unless asked to
             println!("This platform does not support TDX 1.5!");
             Ok(())
         }
@@ -65,15 +302,34 @@ fn handle_not_supported(e: Error) -> Result<()> {
     }
 }
 
-fn handle_quote(mrtd_only: bool, out_file: String, save: bool) -> Result<()> {
-    let provider = LinuxTdxProvider::new();
+/// Signs the file saved at `path` with `data`, if requested.
+fn sign_file(path: &str, data: &[u8], sign: &SignArgs) -> Result<()> {
+    let signed = match (&sign.sign_key, sign.sign_td) {
+        (Some(key), _) => SignedFile::sign_with_key(data, &std::fs::read(key)?),
+        (None, true) => SignedFile::sign_with_td(data)?,
+        (None, false) => return Ok(()),
+    };
+    signed.save(path)?;
+    println!("Saved signature to {}", signature_path(path).display());
+    Ok(())
+}
+
+fn handle_quote(
+    config: &Config,
+    mrtd_only: bool,
+    out_file: String,
+    save: bool,
+    sign: SignArgs,
+    strict: bool,
+) -> Result<()> {
+    let provider = LinuxTdxProvider::from_config(config);
     if mrtd_only {
         match provider.get_launch_measurement() {
             Ok(mrtd) => {
-                println!("Launch measurement (MRTD): {}", hex::encode(mrtd));
+                println!("Launch measurement (MRTD): {}", mrtd);
                 Ok(())
             }
-            Err(e) => handle_not_supported(e),
+            Err(e) => handle_not_supported(e, strict),
         }
     } else {
         match provider.get_attestation_report() {
@@ -82,57 +338,638 @@ fn handle_quote(mrtd_only: bool, out_file: String, save: bool) -> Result<()> {
                     let mut file = File::create(&out_file)?;
                     file.write_all(report.as_bytes())?;
                     println!("Saved TD report (JSON-encoded) to {}", out_file);
+                    sign_file(&out_file, report.as_bytes(), &sign)?;
                 } else {
                     println!("TD Report: {}", report);
                 }
                 Ok(())
             }
-            Err(e) => handle_not_supported(e),
+            Err(e) => handle_not_supported(e, strict),
         }
     }
 }
 
-#[cfg(feature = "host-gcp-tdx")]
-fn handle_verification(launch_only: bool) -> Result<()> {
-    let provider = LinuxTdxProvider::new();
+fn handle_watch(
+    config: &Config,
+    interval: u64,
+    #[cfg(feature = "alerts")] webhook: Option<String>,
+    exit_on_change: bool,
+) -> Result<()> {
+    #[cfg(feature = "alerts")]
+    let sink = config
+        .clone()
+        .merge(Config {
+            alert_webhook: webhook,
+            ..Default::default()
+        })
+        .alert_sink()?;
 
-    if launch_only {
-        let mrtd = provider.get_launch_measurement()?;
+    #[cfg(feature = "alerts")]
+    let session = BootSession::current().ok();
+
+    let provider = LinuxTdxProvider::from_config(config);
+    let mut watcher = Watcher::new(|| provider.get_tdreport().map(CollectedEvidence::into_report));
+    // only returns once a change is detected with --exit-on-change
+    watcher.run(Duration::from_secs(interval), |event| {
+        let json =
+            serde_json::to_string(event).map_err(|e| Error::SerializationError(e.to_string()))?;
+        println!("{}", json);
+        #[cfg(feature = "alerts")]
+        if let Some(sink) = &sink {
+            send_alert(sink, event.to_alert(), session.as_ref());
+        }
+        Ok(!exit_on_change)
+    })?;
+    Err(Error::VerificationError(
+        "The TD's measurements or TCB changed".to_string(),
+    ))
+}
+
+/// Sends `alert` to `sink`, identifying the TD's boot `session` (if known),
+/// and only reporting failures, so that an outage of the sink doesn't fail
+/// the command.
+#[cfg(feature = "alerts")]
+fn send_alert(sink: &dyn AlertSink, alert: Result<Alert>, session: Option<&BootSession>) {
+    let alert = alert.map(|alert| match session {
+        Some(session) => alert.with_session_id(&session.id),
+        None => alert,
+    });
+    if let Err(e) = alert.and_then(|alert| sink.send(&alert)) {
+        eprintln!("Failed to send alert: {}", e);
+    }
+}
+
+fn handle_boot_hook(manifest: String, check: bool) -> Result<()> {
+    let manifest = BootManifest::from_file(&manifest)?;
+    if check {
+        println!("Boot hook manifest is valid");
+        return Ok(());
+    }
+
+    // unlike other commands, fail when TDX isn't supported, so that the
+    // boot hook's service fails too
+    let events = run_boot_hook(&mut LinuxTdxProvider::new(), &manifest)?;
+    for event in &events {
+        println!(
+            "Measured {} into RTMR{}: {}",
+            event.payload.event_type(),
+            event.rtmr,
+            event.payload.measured_data()
+        );
+    }
+    println!(
+        "Recorded {} events in {}",
+        events.len(),
+        manifest.event_log().path().display()
+    );
+    Ok(())
+}
+
+fn handle_exec(
+    config: &Config,
+    event_log: String,
+    policy: Option<String>,
+    command: Vec<String>,
+) -> CliResult {
+    use std::os::unix::process::CommandExt;
 
-        let gcp_host = GcpTdxHost::new(&mrtd)?;
+    let program = resolve_program(&command[0])?;
+    let args = &command[1..];
 
-        let passed = gcp_host.verify_launch_endorsement()?;
+    // like the boot hook, fail when TDX isn't supported, rather than launch
+    // an unmeasured workload
+    let mut provider = LinuxTdxProvider::from_config(config);
+    let event = measure_launch(&mut provider, &EventLog::new(&event_log), &program, args)?;
+    eprintln!(
+        "Measured {} into RTMR{}: {}",
+        event.payload.event_type(),
+        event.rtmr,
+        event.payload.measured_data()
+    );
+
+    if let Some(path) = policy {
+        let policy = config
+            .clone()
+            .merge(Config {
+                policy_path: Some(path),
+                ..Default::default()
+            })
+            .policy()
+            .map_err(CliError::policy)?;
+        check_launch(&policy, provider.get_tdreport()?.report())?;
+        eprintln!("The TD passes the launch policy");
+    }
+
+    // only returns if the program can't be executed
+    let e = std::process::Command::new(&program)
+        .arg0(&command[0])
+        .args(args)
+        .exec();
+    Err(Error::IoError(e).into())
+}
 
-        if passed {
-            println!("TD launch measurement (MRTD) verification passed!");
+#[cfg_attr(not(feature = "host-gcp-tdx"), allow(unused_variables))]
+fn handle_collect(
+    out: String,
+    nonce: String,
+    event_log: Option<String>,
+    archive: Option<String>,
+    gcp_endorsement: bool,
+    sign: SignArgs,
+    strict: bool,
+) -> Result<()> {
+    let nonce = hex::decode(nonce.trim())
+        .map_err(|e| Error::ParseError(format!("Invalid nonce: {}", e)))?;
+    let event_log = event_log.map(EventLog::new);
+
+    let bundle = match Bundle::collect(&nonce, event_log.as_ref()) {
+        Ok(bundle) => bundle,
+        Err(e) => return handle_not_supported(e, strict),
+    };
+
+    #[cfg(feature = "host-gcp-tdx")]
+    let bundle = if gcp_endorsement {
+        use tdx_workload_attestation::evidence::{Endorsement, GCP_ENDORSEMENT_PROVIDER};
+        use tdx_workload_attestation::gcp::GcpTdxHost;
+
+        let mrtd = bundle.parse_quote()?.body.mrtd;
+        let data = GcpTdxHost::new()?.launch_endorsement(&mrtd)?;
+        bundle.with_endorsement(Endorsement {
+            provider: GCP_ENDORSEMENT_PROVIDER.to_string(),
+            data,
+            log_entry: None,
+        })
+    } else {
+        bundle
+    };
+
+    let bytes = bundle.to_bytes()?;
+    let mut file = File::create(&out)?;
+    file.write_all(&bytes)?;
+    println!(
+        "Saved evidence bundle ({} events{}) to {}",
+        bundle.event_log.len(),
+        if bundle.endorsement.is_some() {
+            ", with launch endorsement"
         } else {
-            println!(
-                "TD launch measurement (MRTD) verification failed: TD did not match GCP's endorsed measurement"
-            );
+            ""
+        },
+        out
+    );
+    if let Some(session) = &bundle.session {
+        println!("Boot session {}", session);
+    }
+
+    if let Some(dir) = archive {
+        use tdx_workload_attestation::evidence::store::{EvidenceArchive, FsStore};
+
+        let key = EvidenceArchive::new(FsStore::new(&dir)).archive(&bundle)?;
+        println!("Archived evidence bundle as {} in {}", key, dir);
+    }
+    sign_file(&out, &bytes, &sign)
+}
+
+#[cfg_attr(not(feature = "host-verification"), allow(unused_variables))]
+fn handle_verify_file(file: String, key: Option<String>, collateral: Option<String>) -> Result<()> {
+    let signed = SignedFile::load(&file)?;
+    let key = key.map(std::fs::read).transpose()?;
+    if !signed.verify(&std::fs::read(&file)?, key.as_deref())? {
+        return Err(Error::VerificationError(format!(
+            "{} does not match its signature",
+            file
+        )));
+    }
+
+    if signed.quote()?.is_none() {
+        println!("{} matches its signature", file);
+        return Ok(());
+    }
+
+    #[cfg(feature = "host-verification")]
+    if let Some(collateral) = collateral {
+        use tdx_workload_attestation::trust::TrustAnchors;
+
+        if !signed.verify_quote_chain(&TrustAnchors::from_dir(&collateral)?)? {
+            return Err(Error::VerificationError(format!(
+                "The quote signing {} is invalid",
+                file
+            )));
+        }
+        println!("{} matches its signature, and was signed by a TD", file);
+        return Ok(());
+    }
+
+    println!(
+        "{} matches its signature's quote, but the quote's signature chain was not verified",
+        file
+    );
+    Ok(())
+}
+
+#[cfg(feature = "host-verification")]
+fn handle_appraise(
+    config: &Config,
+    bundle: String,
+    policy: Option<String>,
+    collateral: Option<String>,
+    nonce: Option<String>,
+    ar4si_key: Option<String>,
+    ar4si_out: Option<String>,
+) -> CliResult {
+    use tdx_workload_attestation::trust::DEFAULT_EXPIRY_WARNING_SECS;
+
+    let config = config.clone().merge(Config {
+        policy_path: policy,
+        collateral_dir: collateral,
+        ..Default::default()
+    });
+    if config.collateral_dir.is_none() {
+        return Err(CliError::policy(Error::NotSupported(
+            "No collateral directory (--collateral or collateral_dir) configured".to_string(),
+        )));
+    }
+
+    #[cfg(feature = "parallel-verification")]
+    if let Some(jobs) = config.jobs {
+        tdx_workload_attestation::verification::parallel::set_jobs(jobs)?;
+    }
+
+    let bundle = Bundle::from_bytes(&std::fs::read(&bundle)?)?;
+    let mut policy = config.policy().map_err(CliError::policy)?;
+    if let Some(nonce) = nonce {
+        let nonce = hex::decode(nonce.trim())
+            .map_err(|e| Error::ParseError(format!("Invalid nonce: {}", e)))?;
+        policy = policy.with_nonce(&nonce);
+    }
+
+    let now = policy.clock.now()?;
+    for warning in policy
+        .trust_anchors
+        .expiry_warnings(now, DEFAULT_EXPIRY_WARNING_SECS)
+    {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let verdict = bundle.verify(&policy)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&verdict)
+            .map_err(|e| Error::SerializationError(e.to_string()))?
+    );
+
+    if let (Some(key), Some(out_file)) = (ar4si_key, ar4si_out) {
+        save_attestation_result(&verdict, &bundle, now, &key, &out_file)?;
+    }
+
+    #[cfg(feature = "alerts")]
+    if !verdict.passed()
+        && let Some(sink) = config.alert_sink()?
+    {
+        use tdx_workload_attestation::alert::AlertKind;
+
+        let failed = verdict.failed_checks();
+        let kind = match failed.contains(&"endorsement") {
+            true => AlertKind::EndorsementMismatch,
+            false => AlertKind::VerificationFailure,
+        };
+        let summary = format!("Evidence bundle failed appraisal: {}", failed.join(", "));
+        send_alert(
+            &sink,
+            Alert::new(kind, &summary).and_then(|alert| alert.with_details(&verdict)),
+            bundle.session.as_ref(),
+        );
+    }
+
+    if verdict.passed() {
+        Ok(())
+    } else {
+        Err(Error::VerificationError("Evidence bundle did not pass appraisal".to_string()).into())
+    }
+}
+
+/// Saves the signed AR4SI attestation result of `verdict` to `out_file`.
+#[cfg(feature = "host-verification")]
+fn save_attestation_result(
+    verdict: &tdx_workload_attestation::evidence::Verdict,
+    bundle: &Bundle,
+    now: u64,
+    key_file: &str,
+    out_file: &str,
+) -> Result<()> {
+    use openssl::ec::EcKey;
+    use tdx_workload_attestation::evidence::ar4si::AttestationResult;
+
+    let key = std::fs::read(key_file)?;
+    let mut result = AttestationResult::from_verdict(verdict, Some(&bundle.nonce), now);
+    if let Some(session) = &bundle.session {
+        result = result.with_session_id(&session.id);
+    }
+    let token = match EcKey::private_key_from_pem(&key) {
+        Ok(ec_key) => result.sign_es256(&ec_key)?,
+        Err(_) => result.sign_hs384(&key)?,
+    };
+
+    let mut file = File::create(out_file)?;
+    file.write_all(token.as_bytes())?;
+    println!(
+        "Saved AR4SI attestation result ({}) to {}",
+        result.status(),
+        out_file
+    );
+    Ok(())
+}
+
+fn handle_serve(
+    config: &Config,
+    socket: String,
+    mode: String,
+    access: AccessPolicy,
+    limiter: RateLimiter,
+    bind_client_keys: bool,
+    health_addr: Option<String>,
+) -> CliResult {
+    let mode = u32::from_str_radix(&mode, 8)
+        .map_err(|e| Error::ParseError(format!("Invalid socket mode {}: {}", mode, e)))?;
+
+    if let Some(addr) = health_addr {
+        let mut checker = HealthChecker::new(&LinuxTdxProvider::from_config(config));
+        if config.collateral_dir.is_some()
+            || !config.trust_anchor_dirs.is_empty()
+            || !config.intel_root_paths.is_empty()
+        {
+            checker = checker
+                .with_trust_anchors(config.policy().map_err(CliError::policy)?.trust_anchors);
+        }
+        let listener = std::net::TcpListener::bind(&addr)?;
+        println!("Serving health endpoints on http://{}", addr);
+        std::thread::spawn(move || {
+            if let Err(e) = checker.serve(&listener) {
+                eprintln!("Health endpoints failed: {}", e);
+            }
+        });
+    }
+
+    let listener = tdx_workload_attestation::agent::bind(&socket, mode)?;
+    println!("Serving quotes on {}", socket);
+    let mut agent =
+        Agent::new(LinuxTdxProvider::from_config(config), access).with_rate_limiter(limiter);
+    if bind_client_keys {
+        agent = agent.with_bindings(BindingRegistry::new());
+    }
+    match BootSession::current() {
+        Ok(session) => {
+            println!("Boot session {}", session);
+            agent = agent.with_session(session);
+        }
+        Err(e) => eprintln!("Failed to identify the boot session: {}", e),
+    }
+    #[cfg(feature = "alerts")]
+    if let Some(sink) = config.alert_sink()? {
+        agent = agent.with_alert_sink(Box::new(sink));
+    }
+    Ok(agent.serve(&listener)?)
+}
+
+/// Detects whether the TD rebooted since the agent last ran, in which case
+/// the state persisted in the previous boot is invalidated: the PCK
+/// certificate cache is cleared, the evidence archive (if any) is rotated,
+/// and a new boot session alert is sent (if alerts are configured).
+///
+/// Failures to identify the boot are only reported, so that the agent still
+/// serves quotes.
+fn start_boot_epoch(
+    config: &Config,
+    state: &str,
+    archive: Option<&str>,
+    keep_boots: usize,
+) -> Result<()> {
+    let epoch = match BootEpoch::current(&LinuxTdxProvider::from_config(config)) {
+        Ok(epoch) => epoch,
+        Err(e) => {
+            eprintln!("Failed to identify the boot: {}", e);
+            return Ok(());
         }
+    };
+    let Some(new_boot) = AgentState::new(state).advance(&epoch)? else {
+        return Ok(());
+    };
+    println!(
+        "New boot session: {}",
+        serde_json::to_string(&new_boot).map_err(|e| Error::SerializationError(e.to_string()))?
+    );
+
+    if let Some(cache) = config.pck_cache() {
+        let removed = cache.clear()?;
+        println!("Invalidated {} cached PCK certificate chains", removed);
+    }
+    if let Some(dir) = archive {
+        use tdx_workload_attestation::evidence::store::{
+            EvidenceArchive, FsStore, RetentionPolicy,
+        };
+
+        let pruned = EvidenceArchive::new(FsStore::new(dir))
+            .with_retention(RetentionPolicy::new().with_max_groups(keep_boots))
+            .prune()?;
+        println!("Rotated {} archived bundles out of {}", pruned.len(), dir);
+    }
+    #[cfg(feature = "alerts")]
+    if let Some(sink) = config.alert_sink()? {
+        // the agent starts regardless of the alert's delivery
+        if let Err(e) = new_boot.to_alert().and_then(|alert| sink.send(&alert)) {
+            eprintln!("Failed to send the new boot session alert: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "host-gcp-tdx")]
+fn handle_verification(launch_only: bool) -> Result<()> {
+    if launch_only {
+        let passed = verify_launch_endorsement("gcp-tdx")?;
+
+        if !passed {
+            return Err(Error::VerificationError(
+                "TD launch measurement (MRTD) verification failed: TD did not match GCP's endorsed measurement".to_string(),
+            ));
+        }
+        println!("TD launch measurement (MRTD) verification passed!");
         Ok(())
     } else {
         // TODO: implement workload attestation
-        return Err(Error::NotSupported(
+        Err(Error::NotSupported(
             "Only TD launch measurement verification is currently supported on GCP".to_string(),
-        ));
+        ))
     }
 }
 
-fn main() -> Result<()> {
+/// Writes the attestation metrics to `path`, atomically so that collectors
+/// never read a partial file.
+fn write_metrics(path: &str) -> Result<()> {
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, metrics::global().render_prometheus())?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
     // Parse command line arguments
     let args = Cli::parse();
 
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e.error);
+            e.exit_code()
+        }
+    }
+}
+
+fn run(args: Cli) -> CliResult {
+    let config = Config::load_from(args.config.as_deref()).map_err(CliError::policy)?;
+    let strict = args.strict;
+
     // Handle commands
 
-    match args.command {
-        Commands::Platform { command } => platform::handle(command),
+    let result = match args.command {
+        Commands::Platform { command } => {
+            platform::handle(&config, command, strict).map_err(CliError::from)
+        }
+        Commands::Policy { command } => policy::handle(&config, command),
+        Commands::Report { command } => report::handle(command).map_err(CliError::from),
+        Commands::Completions { shell } => {
+            docs::print_completions(Cli::command(), shell);
+            Ok(())
+        }
+        Commands::Docs { command } => docs::handle(Cli::command(), command).map_err(CliError::from),
         Commands::Quote {
             mrtd_only,
             out_file,
             save,
-        } => handle_quote(mrtd_only, out_file, save),
+            sign,
+        } => handle_quote(&config, mrtd_only, out_file, save, sign, strict).map_err(CliError::from),
+        Commands::BootHook { manifest, check } => {
+            handle_boot_hook(manifest, check).map_err(CliError::from)
+        }
+        Commands::Exec {
+            event_log,
+            policy,
+            command,
+        } => handle_exec(&config, event_log, policy, command),
         #[cfg(feature = "host-gcp-tdx")]
-        Commands::Verify { launch_only } => handle_verification(launch_only),
+        Commands::Collect {
+            out,
+            nonce,
+            event_log,
+            archive,
+            gcp_endorsement,
+            sign,
+        } => handle_collect(
+            out,
+            nonce,
+            event_log,
+            archive,
+            gcp_endorsement,
+            sign,
+            strict,
+        )
+        .map_err(CliError::from),
+        #[cfg(not(feature = "host-gcp-tdx"))]
+        Commands::Collect {
+            out,
+            nonce,
+            event_log,
+            archive,
+            sign,
+        } => handle_collect(out, nonce, event_log, archive, false, sign, strict)
+            .map_err(CliError::from),
+        #[cfg(feature = "host-verification")]
+        Commands::VerifyFile {
+            file,
+            key,
+            collateral,
+        } => handle_verify_file(file, key, collateral).map_err(CliError::from),
+        #[cfg(not(feature = "host-verification"))]
+        Commands::VerifyFile { file, key } => {
+            handle_verify_file(file, key, None).map_err(CliError::from)
+        }
+        #[cfg(feature = "host-verification")]
+        Commands::Appraise {
+            bundle,
+            policy,
+            collateral,
+            nonce,
+            ar4si_key,
+            ar4si_out,
+            #[cfg(feature = "parallel-verification")]
+            jobs,
+        } => {
+            #[cfg(feature = "parallel-verification")]
+            let config = config.clone().merge(Config {
+                jobs,
+                ..Default::default()
+            });
+            handle_appraise(
+                &config, bundle, policy, collateral, nonce, ar4si_key, ar4si_out,
+            )
+        }
+        Commands::Serve {
+            socket,
+            mode,
+            allow_uids,
+            allow_gids,
+            allow_cgroups,
+            rate_limit,
+            client_rate_limit,
+            bind_client_keys,
+            health_addr,
+            state,
+            archive,
+            keep_boots,
+        } => {
+            start_boot_epoch(&config, &state, archive.as_deref(), keep_boots)?;
+            let access = AccessPolicy {
+                allowed_uids: allow_uids,
+                allowed_gids: allow_gids,
+                allowed_cgroups: allow_cgroups,
+            };
+            let mut limiter = RateLimiter::new();
+            if let Some(n) = rate_limit {
+                limiter = limiter.with_global_limit(RateLimit::per_minute(n));
+            }
+            if let Some(n) = client_rate_limit {
+                limiter = limiter.with_client_limit(RateLimit::per_minute(n));
+            }
+            handle_serve(
+                &config,
+                socket,
+                mode,
+                access,
+                limiter,
+                bind_client_keys,
+                health_addr,
+            )
+        }
+        Commands::Watch {
+            interval,
+            #[cfg(feature = "alerts")]
+            webhook,
+            exit_on_change,
+        } => handle_watch(
+            &config,
+            interval,
+            #[cfg(feature = "alerts")]
+            webhook,
+            exit_on_change,
+        )
+        .map_err(CliError::from),
+        #[cfg(feature = "host-gcp-tdx")]
+        Commands::Verify { launch_only } => {
+            handle_verification(launch_only).map_err(CliError::from)
+        }
+    };
+
+    if let Some(path) = args.metrics_file {
+        write_metrics(&path)?;
     }
+    result
 }
@@ -1,6 +1,12 @@
 use clap::Subcommand;
 
-use tdx_workload_attestation::{error::Result, get_platform_name};
+use tdx_workload_attestation::{
+    config::Config,
+    error::{Error, Result},
+    get_platform_name,
+    platform::detect_capabilities,
+    tdx::linux::{device, qgs},
+};
 
 #[derive(Subcommand)]
 pub enum PlatformCommands {
@@ -8,9 +14,14 @@ pub enum PlatformCommands {
     Name,
     /// Check if TDX is supported
     IsTdxAvailable,
+    /// Print a JSON report of the platform's attestation capabilities
+    Capabilities,
+    /// Print a JSON report of whether the TDX guest device is usable, and
+    /// how to fix it if it isn't (e.g., its permissions or an LSM policy)
+    Probe,
 }
 
-pub fn handle(cmd: PlatformCommands) -> Result<()> {
+pub fn handle(config: &Config, cmd: PlatformCommands, strict: bool) -> Result<()> {
     match cmd {
         PlatformCommands::Name => {
             let name = get_platform_name()?;
@@ -24,6 +35,31 @@ pub fn handle(cmd: PlatformCommands) -> Result<()> {
                 available = true;
             }
             println!("TDX 1.5 available: {}", available);
+            if strict && !available {
+                return Err(Error::NotSupported(format!(
+                    "TDX 1.5 is not available on platform {}",
+                    name
+                )));
+            }
+        }
+        PlatformCommands::Capabilities => {
+            let mut caps = detect_capabilities()?;
+            if let Some(port) = config.qgs_vsock_port {
+                caps.qgs_vsock_port = Some(port);
+                caps.qgs_reachable = Some(qgs::is_vsock_reachable(port));
+            }
+            let caps_str = serde_json::to_string_pretty(&caps)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            println!("{}", caps_str);
+        }
+        PlatformCommands::Probe => {
+            let path = config
+                .device_path
+                .as_deref()
+                .unwrap_or(device::TDX15_DEV_PATH);
+            let report = serde_json::to_string_pretty(&device::probe_at(path))
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            println!("{}", report);
         }
     }
     Ok(())
@@ -0,0 +1,133 @@
+use clap::Subcommand;
+
+#[cfg(feature = "host-verification")]
+use tdx_workload_attestation::evidence::Bundle;
+use tdx_workload_attestation::{config::Config, error::Error, evidence::Policy};
+
+use crate::exit::{CliError, CliResult};
+
+#[derive(Subcommand)]
+pub enum PolicyCommands {
+    /// Check that a policy (and its collateral) loads, and print warnings
+    /// about settings that weaken appraisal
+    Validate {
+        /// The TOML appraisal policy
+        file: String,
+        /// The directory holding the trust anchors and TCB collateral to load
+        /// with the policy (defaults to the configured one)
+        #[arg(short, long)]
+        collateral: Option<String>,
+    },
+    #[cfg(feature = "host-verification")]
+    /// Dry-run a policy against an evidence bundle, and print the result of
+    /// each of its rules
+    Test {
+        /// The CBOR-encoded evidence bundle to appraise
+        #[arg(short, long)]
+        bundle: String,
+        /// The TOML appraisal policy (defaults to the configured policy, or an
+        /// empty policy)
+        #[arg(short, long)]
+        policy: Option<String>,
+        /// The directory holding the trust anchors and TCB collateral
+        /// (defaults to the configured one)
+        #[arg(short, long)]
+        collateral: Option<String>,
+        /// The hex-encoded nonce the bundle must bind (overrides the policy's)
+        #[arg(short, long)]
+        nonce: Option<String>,
+    },
+    /// Print the rules a policy enforces
+    Explain {
+        /// The TOML appraisal policy (defaults to the configured policy, or an
+        /// empty policy)
+        file: Option<String>,
+        /// The directory holding the trust anchors and TCB collateral
+        /// (defaults to the configured one)
+        #[arg(short, long)]
+        collateral: Option<String>,
+        /// Print the rules in JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+}
+
+/// Loads the policy at `path` (or the configured one), with the collateral
+/// in `collateral` (or the configured one).
+fn load_policy(
+    config: &Config,
+    path: Option<String>,
+    collateral: Option<String>,
+) -> CliResult<Policy> {
+    config
+        .clone()
+        .merge(Config {
+            policy_path: path,
+            collateral_dir: collateral,
+            ..Default::default()
+        })
+        .policy()
+        .map_err(CliError::policy)
+}
+
+pub fn handle(config: &Config, cmd: PolicyCommands) -> CliResult {
+    match cmd {
+        PolicyCommands::Validate { file, collateral } => {
+            let policy = load_policy(config, Some(file.clone()), collateral)?;
+            for warning in policy.warnings() {
+                eprintln!("Warning: {}", warning);
+            }
+            println!("Policy {} is valid ({} rules)", file, policy.rules().len());
+        }
+        #[cfg(feature = "host-verification")]
+        PolicyCommands::Test {
+            bundle,
+            policy,
+            collateral,
+            nonce,
+        } => {
+            let mut policy = load_policy(config, policy, collateral)?;
+            if let Some(nonce) = nonce {
+                let nonce = hex::decode(nonce.trim())
+                    .map_err(|e| Error::ParseError(format!("Invalid nonce: {}", e)))?;
+                policy = policy.with_nonce(&nonce);
+            }
+
+            let verdict = Bundle::from_bytes(&std::fs::read(&bundle)?)?.verify(&policy)?;
+            for rule in policy.rules() {
+                let result = match verdict.check(rule.check) {
+                    Some(check) if check.passed => "PASS".to_string(),
+                    Some(check) => format!("FAIL ({})", check.detail.as_deref().unwrap_or("")),
+                    None => "SKIP".to_string(),
+                };
+                println!("{:<16} {}: {}", rule.check, result, rule.requirement);
+            }
+
+            if !verdict.passed() {
+                return Err(Error::VerificationError(format!(
+                    "The policy would reject {}",
+                    bundle
+                ))
+                .into());
+            }
+            println!("The policy would accept {}", bundle);
+        }
+        PolicyCommands::Explain {
+            file,
+            collateral,
+            json,
+        } => {
+            let rules = load_policy(config, file, collateral)?.rules();
+            if json {
+                let rules = serde_json::to_string_pretty(&rules)
+                    .map_err(|e| Error::SerializationError(e.to_string()))?;
+                println!("{}", rules);
+            } else {
+                for rule in rules {
+                    println!("{:<16} {}", rule.check, rule.requirement);
+                }
+            }
+        }
+    }
+    Ok(())
+}
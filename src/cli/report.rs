@@ -0,0 +1,159 @@
+use clap::{Subcommand, ValueEnum};
+
+use tdx_workload_attestation::{
+    core::quote::Quote,
+    core::report::TdReportV15,
+    core::report::diff::{Fields, diff},
+    core::report::render::{render_quote, render_report},
+    error::{Error, Result},
+    evidence::Bundle,
+    evidence::interop::{AzureMaaRequest, to_intel_quote_dat},
+    schema::{Migrate, VersionedReport},
+};
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Print a saved TD report, raw quote or evidence bundle
+    Print {
+        /// The JSON-encoded TD report (as saved by `quote --save`), raw quote
+        /// or CBOR-encoded evidence bundle
+        file: String,
+        /// Print the fields aligned and annotated with their meaning, instead
+        /// of JSON
+        #[arg(long, default_value = "false")]
+        pretty: bool,
+    },
+    /// Print the measurement registers, SVNs and attributes that differ
+    /// between two saved TD reports, raw quotes or evidence bundles
+    Diff {
+        /// The old report, quote or bundle
+        old: String,
+        /// The new report, quote or bundle
+        new: String,
+        /// Print the changed fields in JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+    /// Convert a saved raw quote or evidence bundle into another
+    /// ecosystem's evidence format
+    Export {
+        /// The raw quote or CBOR-encoded evidence bundle
+        file: String,
+        /// The format to convert into
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// The file to write the converted evidence to
+        #[arg(long)]
+        out: String,
+    },
+}
+
+/// The formats the evidence can be exported in.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// The raw quote, as read by Intel's DCAP sample tools
+    IntelDat,
+    /// A request body of Azure Attestation's TDX VM API
+    AzureMaa,
+}
+
+/// A saved report or quote.
+enum Saved {
+    Report(Box<TdReportV15>),
+    Quote(Box<Quote>),
+}
+
+/// Reads the report or quote saved at `path`, which is either a JSON-encoded
+/// report, a raw quote or an evidence bundle.
+fn read_saved(path: &str) -> Result<Saved> {
+    let bytes = std::fs::read(path)?;
+    if let Ok(report) = VersionedReport::migrate(&bytes) {
+        return Ok(Saved::Report(Box::new(report.report)));
+    }
+    if let Ok(quote) = Quote::from_bytes(&bytes) {
+        return Ok(Saved::Quote(Box::new(quote)));
+    }
+    match Bundle::from_bytes(&bytes) {
+        Ok(bundle) => Ok(Saved::Quote(Box::new(bundle.parse_quote()?))),
+        Err(_) => Err(Error::ParseError(format!(
+            "{} is not a TD report, quote or evidence bundle",
+            path
+        ))),
+    }
+}
+
+/// Reads the evidence bundle or raw quote saved at `path`, the latter
+/// wrapped in a bundle without a nonce.
+fn read_bundle(path: &str) -> Result<Bundle> {
+    let bytes = std::fs::read(path)?;
+    if Quote::from_bytes(&bytes).is_ok() {
+        return Ok(Bundle::new(&[], bytes));
+    }
+    Bundle::from_bytes(&bytes)
+        .map_err(|_| Error::ParseError(format!("{} is not a quote or evidence bundle", path)))
+}
+
+impl Saved {
+    fn fields(&self) -> Fields {
+        match self {
+            Saved::Report(report) => Fields::from(report.as_ref()),
+            Saved::Quote(quote) => Fields::from(quote.as_ref()),
+        }
+    }
+}
+
+pub fn handle(cmd: ReportCommands) -> Result<()> {
+    match cmd {
+        ReportCommands::Print { file, pretty } => match (read_saved(&file)?, pretty) {
+            (Saved::Report(report), true) => print!("{}", render_report(&report)),
+            (Saved::Quote(quote), true) => print!("{}", render_quote(&quote)),
+            (Saved::Report(report), false) => {
+                let report = serde_json::to_string_pretty(&VersionedReport::new(*report))
+                    .map_err(|e| Error::SerializationError(e.to_string()))?;
+                println!("{}", report);
+            }
+            (Saved::Quote(_), false) => {
+                return Err(Error::NotSupported(
+                    "Quotes can only be printed with --pretty".to_string(),
+                ));
+            }
+        },
+        ReportCommands::Diff { old, new, json } => {
+            let changes = diff(&read_saved(&old)?.fields(), &read_saved(&new)?.fields());
+            if json {
+                let changes: Vec<_> = changes
+                    .iter()
+                    .map(|change| {
+                        serde_json::json!({
+                            "field": change.field,
+                            "old": hex::encode(&change.old),
+                            "new": hex::encode(&change.new),
+                            "description": change.describe(),
+                        })
+                    })
+                    .collect();
+                let changes = serde_json::to_string_pretty(&changes)
+                    .map_err(|e| Error::SerializationError(e.to_string()))?;
+                println!("{}", changes);
+            } else if changes.is_empty() {
+                println!("No differences");
+            } else {
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+        }
+        ReportCommands::Export { file, format, out } => {
+            let bundle = read_bundle(&file)?;
+            let exported = match format {
+                ExportFormat::IntelDat => to_intel_quote_dat(&bundle.quote)?,
+                ExportFormat::AzureMaa => AzureMaaRequest::from_bundle(&bundle)?
+                    .to_json()?
+                    .into_bytes(),
+            };
+            std::fs::write(&out, exported)?;
+            println!("Evidence exported to {}", out);
+        }
+    }
+    Ok(())
+}
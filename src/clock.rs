@@ -0,0 +1,113 @@
+//! # Time Sources
+//!
+//! This module provides the `Clock` trait, which supplies the current time to
+//! the time-dependent checks of the verification paths: certificate and
+//! collateral expiry, JWT validity, and anchor expiry warnings.
+//!
+//! Verifiers use the `SystemClock` by default. A `FixedClock` appraises
+//! archived evidence against the time it was collected at, rather than the
+//! time it's verified at, and makes tests deterministic.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::clock::{Clock, FixedClock, SystemClock};
+//!
+//! // appraise evidence collected on 2025-06-01
+//! let clock = FixedClock::new(1_748_736_000);
+//! assert_eq!(clock.now().unwrap(), 1_748_736_000);
+//!
+//! assert!(SystemClock.now().unwrap() > 1_748_736_000);
+//! ```
+
+use crate::error::Result;
+
+use std::fmt;
+
+/// A source of the current time.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current time, in seconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the time is unavailable.
+    fn now(&self) -> Result<u64>;
+}
+
+/// The system clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    /// Returns the current system time.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::VerificationError` if the system time is before the Unix
+    ///   epoch.
+    /// - `Error::NotSupported` on targets without a system clock (e.g.,
+    ///   `wasm32-unknown-unknown`), where callers must use a `FixedClock`.
+    fn now(&self) -> Result<u64> {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .map_err(|e| {
+                    crate::error::Error::VerificationError(format!("Invalid system time: {}", e))
+                })
+        }
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            Err(crate::error::Error::NotSupported(
+                "This target has no system clock".to_string(),
+            ))
+        }
+    }
+}
+
+/// A clock stopped at a fixed time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedClock {
+    unix_time: u64,
+}
+
+impl FixedClock {
+    /// Creates a clock stopped at `unix_time`, in seconds since the Unix
+    /// epoch.
+    pub fn new(unix_time: u64) -> Self {
+        Self { unix_time }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Result<u64> {
+        Ok(self.unix_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fixed_clock() -> Result<()> {
+        let clock = FixedClock::new(1_000_000_000);
+        assert_eq!(clock.now()?, 1_000_000_000);
+        assert_eq!(clock.now()?, 1_000_000_000);
+
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        assert_eq!(clock.now()?, 1_000_000_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_system_clock() -> Result<()> {
+        let before = SystemClock.now()?;
+        // 2025-01-01
+        assert!(before > 1_735_689_600);
+        assert!(SystemClock.now()? >= before);
+        Ok(())
+    }
+}
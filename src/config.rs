@@ -0,0 +1,444 @@
+//! # Configuration
+//!
+//! This module provides the `Config` type, which gathers the settings shared
+//! by the CLI and library consumers: the TDX guest device, the Quote
+//! Generation Service (QGS) endpoint, the trust anchor and collateral
+//! directories, the cache location, the appraisal policy, the number of
+//! verification threads and the alert webhook.
+//!
+//! Settings are layered, each layer overriding the previous ones:
+//! 1. the defaults of each module (e.g., `/dev/tdx_guest`),
+//! 2. the TOML configuration file (`/etc/tdx-attest/config.toml`, or the
+//!    file named by `$TDX_ATTEST_CONFIG`),
+//! 3. the `TDX_ATTEST_*` environment variables (e.g.,
+//!    `TDX_ATTEST_DEVICE_PATH`), and
+//! 4. explicit overrides, such as command line flags (see `merge()`).
+//!
+//! The configuration file uses the field names of `Config`, e.g.:
+//!
+//! ```toml
+//! device_path = "/dev/tdx_guest"
+//! qgs_vsock_port = 4050
+//! collateral_dir = "/etc/tdx-attest/collateral"
+//! trust_anchor_dirs = ["/etc/tdx-attest/anchors.d"]
+//! intel_root_paths = ["/etc/tdx-attest/intel_sgx_root_cn.pem"]
+//! pcs_urls = ["https://pcs.example.cn", "https://api.trustedservices.intel.com"]
+//! cache_dir = "/var/cache/tdx-attest"
+//! policy_path = "/etc/tdx-attest/policy.toml"
+//! jobs = 4
+//! alert_webhook = "https://alerts.example.com/tdx"
+//! alert_key_path = "/etc/tdx-attest/alert.key"
+//! ```
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::config::Config;
+//!
+//! let config = Config::load().unwrap().merge(Config {
+//!     policy_path: Some("policy.toml".to_string()),
+//!     ..Default::default()
+//! });
+//! let policy = config.policy().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::evidence::Policy;
+use crate::evidence::pck::PckCache;
+use crate::trust::TrustAnchorKind;
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The default path of the configuration file.
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/tdx-attest/config.toml";
+
+/// The environment variable naming the configuration file.
+pub const CONFIG_PATH_ENV: &str = "TDX_ATTEST_CONFIG";
+
+/// The prefix of the environment variables overriding the configuration.
+pub const ENV_PREFIX: &str = "TDX_ATTEST_";
+
+/// The settings shared by the CLI and library consumers.
+///
+/// Unset fields fall back to the defaults of the modules they configure.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The TDX guest device (`TDX_ATTEST_DEVICE_PATH`, see
+    /// `tdx::LinuxTdxProvider::from_config()`).
+    pub device_path: Option<String>,
+    /// The vsock port of the QGS (`TDX_ATTEST_QGS_VSOCK_PORT`), overriding
+    /// the one configured for `libtdx-attest`.
+    pub qgs_vsock_port: Option<u32>,
+    /// The directory holding the trust anchors and TCB collateral
+    /// (`TDX_ATTEST_COLLATERAL_DIR`, see `Policy::with_collateral_dir()`).
+    pub collateral_dir: Option<String>,
+    /// Additional directories of trust anchors (`TDX_ATTEST_TRUST_ANCHOR_DIRS`,
+    /// separated by `:`, see `trust::TrustAnchors::load_dir()`).
+    pub trust_anchor_dirs: Vec<String>,
+    /// Additional Intel SGX root certificates to trust, e.g., the roots of
+    /// regional provisioning infrastructures (`TDX_ATTEST_INTEL_ROOT_PATHS`,
+    /// separated by `:`, see `trust::TrustAnchors::load_file()`).
+    pub intel_root_paths: Vec<String>,
+    /// The PCS endpoints to retrieve PCK certificates from, e.g., the PCS of
+    /// the platforms' region, in the order they're tried
+    /// (`TDX_ATTEST_PCS_URLS`, separated by `,`; Intel's PCS by default, see
+    /// `pcs_client()`).
+    pub pcs_urls: Vec<String>,
+    /// The directory of the PCK certificate cache's `pck/` subdirectory
+    /// (`TDX_ATTEST_CACHE_DIR`).
+    pub cache_dir: Option<String>,
+    /// The TOML appraisal policy (`TDX_ATTEST_POLICY_PATH`).
+    pub policy_path: Option<String>,
+    /// The number of threads appraisals run on (`TDX_ATTEST_JOBS`, see
+    /// `verification::parallel::set_jobs()`).
+    pub jobs: Option<usize>,
+    /// The URL of the webhook to send alerts to (`TDX_ATTEST_ALERT_WEBHOOK`,
+    /// see `alert_sink()`).
+    pub alert_webhook: Option<String>,
+    /// The file holding the key signing the alerts
+    /// (`TDX_ATTEST_ALERT_KEY_PATH`).
+    pub alert_key_path: Option<String>,
+}
+
+impl Config {
+    /// Parses a TOML configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the configuration is malformed or
+    /// has unknown fields.
+    pub fn from_toml(config: &str) -> Result<Self> {
+        toml::from_str(config).map_err(|e| Error::ParseError(format!("Invalid config: {}", e)))
+    }
+
+    /// Reads a TOML configuration file.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the file is a symlink.
+    /// - `Error::IoError` if the file cannot be read.
+    /// - `Error::ParseError` if the configuration is malformed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
+            )));
+        }
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Loads the configuration from the configuration file (if it exists)
+    /// and the environment.
+    ///
+    /// See `load_from()` for errors.
+    pub fn load() -> Result<Self> {
+        Self::load_from(None)
+    }
+
+    /// Loads the configuration from the file at `path` (which must exist),
+    /// or else from the default configuration file (if it exists), and the
+    /// environment.
+    ///
+    /// # Errors
+    ///
+    /// Same as `from_file()` and `with_env()`.
+    pub fn load_from(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => Some(PathBuf::from(path)),
+            None => match env::var_os(CONFIG_PATH_ENV).filter(|v| !v.is_empty()) {
+                Some(path) => Some(PathBuf::from(path)),
+                None => Some(PathBuf::from(DEFAULT_CONFIG_PATH)).filter(|p| p.exists()),
+            },
+        };
+
+        let config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        // skip variables that aren't valid UTF-8, which are never ours
+        config.with_env(env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }))
+    }
+
+    /// Overrides the configuration with the `TDX_ATTEST_*` variables in
+    /// `vars`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if a variable is malformed or unknown.
+    pub fn with_env<I: IntoIterator<Item = (String, String)>>(mut self, vars: I) -> Result<Self> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            match key {
+                "CONFIG" => {}
+                "DEVICE_PATH" => self.device_path = Some(value),
+                "QGS_VSOCK_PORT" => {
+                    let port = value
+                        .parse()
+                        .map_err(|_| Error::ParseError(format!("Invalid {}: {}", name, value)))?;
+                    self.qgs_vsock_port = Some(port);
+                }
+                "COLLATERAL_DIR" => self.collateral_dir = Some(value),
+                "TRUST_ANCHOR_DIRS" => self.trust_anchor_dirs = split_list(&value, ':'),
+                "INTEL_ROOT_PATHS" => self.intel_root_paths = split_list(&value, ':'),
+                "PCS_URLS" => self.pcs_urls = split_list(&value, ','),
+                "CACHE_DIR" => self.cache_dir = Some(value),
+                "POLICY_PATH" => self.policy_path = Some(value),
+                "JOBS" => {
+                    let jobs = value
+                        .parse()
+                        .map_err(|_| Error::ParseError(format!("Invalid {}: {}", name, value)))?;
+                    self.jobs = Some(jobs);
+                }
+                "ALERT_WEBHOOK" => self.alert_webhook = Some(value),
+                "ALERT_KEY_PATH" => self.alert_key_path = Some(value),
+                _ => {
+                    return Err(Error::ParseError(format!(
+                        "Unknown configuration variable {}",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Overrides the configuration with the set fields of `overrides`.
+    pub fn merge(self, overrides: Config) -> Self {
+        Self {
+            device_path: overrides.device_path.or(self.device_path),
+            qgs_vsock_port: overrides.qgs_vsock_port.or(self.qgs_vsock_port),
+            collateral_dir: overrides.collateral_dir.or(self.collateral_dir),
+            trust_anchor_dirs: match overrides.trust_anchor_dirs.is_empty() {
+                true => self.trust_anchor_dirs,
+                false => overrides.trust_anchor_dirs,
+            },
+            intel_root_paths: match overrides.intel_root_paths.is_empty() {
+                true => self.intel_root_paths,
+                false => overrides.intel_root_paths,
+            },
+            pcs_urls: match overrides.pcs_urls.is_empty() {
+                true => self.pcs_urls,
+                false => overrides.pcs_urls,
+            },
+            cache_dir: overrides.cache_dir.or(self.cache_dir),
+            policy_path: overrides.policy_path.or(self.policy_path),
+            jobs: overrides.jobs.or(self.jobs),
+            alert_webhook: overrides.alert_webhook.or(self.alert_webhook),
+            alert_key_path: overrides.alert_key_path.or(self.alert_key_path),
+        }
+    }
+
+    /// Returns the PCK certificate cache, in the `pck/` subdirectory of the
+    /// cache directory, or in the default one (see `PckCache::default_dir()`).
+    pub fn pck_cache(&self) -> Option<PckCache> {
+        match &self.cache_dir {
+            Some(dir) => Some(PckCache::new(Path::new(dir).join("pck"))),
+            None => PckCache::default_dir().map(PckCache::new),
+        }
+    }
+
+    /// Returns a client for the configured PCS endpoints, which falls back to
+    /// each endpoint in turn, or for Intel's PCS if none is configured.
+    #[cfg(feature = "pck-retrieval")]
+    pub fn pcs_client(&self) -> crate::evidence::pck::PcsClient {
+        use crate::evidence::pck::PcsClient;
+
+        let mut urls = self.pcs_urls.iter();
+        let client = match urls.next() {
+            Some(url) => PcsClient::new().with_url(url),
+            None => PcsClient::new(),
+        };
+        urls.fold(client, |client, url| {
+            client.with_fallback(PcsClient::new().with_url(url))
+        })
+    }
+
+    /// Returns the configured appraisal policy, with the trust anchors and
+    /// collateral of the configured directories, the configured Intel SGX
+    /// roots, and the PCK certificate cache of the cache directory (if set).
+    ///
+    /// # Errors
+    ///
+    /// Same as `Policy::from_file()`, `Policy::with_collateral_dir()`,
+    /// `TrustAnchors::load_dir()` and `TrustAnchors::load_file()`.
+    pub fn policy(&self) -> Result<Policy> {
+        let mut policy = match &self.policy_path {
+            Some(path) => Policy::from_file(path)?,
+            None => Policy::new(),
+        };
+        if let Some(dir) = &self.collateral_dir {
+            policy = policy.with_collateral_dir(dir)?;
+        }
+        for dir in &self.trust_anchor_dirs {
+            policy.trust_anchors.load_dir(dir)?;
+        }
+        for path in &self.intel_root_paths {
+            policy
+                .trust_anchors
+                .load_file(TrustAnchorKind::IntelSgxRoot, path)?;
+        }
+        if self.cache_dir.is_some()
+            && let Some(cache) = self.pck_cache()
+        {
+            policy = policy.with_pck_cache(cache);
+        }
+        Ok(policy)
+    }
+
+    /// Returns the sink of the configured alert webhook, signing the alerts
+    /// with the key in the configured key file (if set), or `None` if no
+    /// webhook is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the key file cannot be read.
+    #[cfg(feature = "alerts")]
+    pub fn alert_sink(&self) -> Result<Option<crate::alert::WebhookSink>> {
+        let Some(url) = &self.alert_webhook else {
+            return Ok(None);
+        };
+        let mut sink = crate::alert::WebhookSink::new(url);
+        if let Some(path) = &self.alert_key_path {
+            sink = sink.with_hmac_key(&fs::read(path)?);
+        }
+        Ok(Some(sink))
+    }
+
+    /// Returns the configured QGS vsock port, or else the one configured for
+    /// `libtdx-attest` (see `tdx::linux::qgs::configured_vsock_port()`).
+    ///
+    /// # Errors
+    ///
+    /// Same as `configured_vsock_port()`.
+    #[cfg(feature = "tdx-linux")]
+    pub fn qgs_vsock_port(&self) -> Result<Option<u32>> {
+        match self.qgs_vsock_port {
+            Some(port) => Ok(Some(port)),
+            None => crate::tdx::linux::qgs::configured_vsock_port(),
+        }
+    }
+}
+
+/// Splits a list of values separated by `separator`, skipping empty ones.
+fn split_list(list: &str, separator: char) -> Vec<String> {
+    list.split(separator)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_layers() -> Result<()> {
+        let file = Config::from_toml(
+            r#"
+            device_path = "/dev/tdx_guest"
+            qgs_vsock_port = 4050
+            trust_anchor_dirs = ["/etc/anchors.d"]
+            policy_path = "/etc/policy.toml"
+            "#,
+        )?;
+        assert_eq!(file.qgs_vsock_port, Some(4050));
+        assert_eq!(file.cache_dir, None);
+
+        // environment variables override the file, and other variables are
+        // ignored
+        let env = file.with_env(vars(&[
+            ("TDX_ATTEST_QGS_VSOCK_PORT", "4051"),
+            ("TDX_ATTEST_TRUST_ANCHOR_DIRS", "/a:/b"),
+            ("TDX_ATTEST_CACHE_DIR", "/var/cache/tdx-attest"),
+            ("TDX_ATTEST_JOBS", "4"),
+            ("TDX_ATTEST_ALERT_WEBHOOK", "https://alerts.example.com/tdx"),
+            ("TDX_ATTEST_INTEL_ROOT_PATHS", "/roots/cn.pem"),
+            (
+                "TDX_ATTEST_PCS_URLS",
+                "https://pcs.example.cn,https://api.trustedservices.intel.com",
+            ),
+            ("HOME", "/root"),
+        ]))?;
+        assert_eq!(env.qgs_vsock_port, Some(4051));
+        assert_eq!(env.jobs, Some(4));
+        assert_eq!(env.trust_anchor_dirs, ["/a", "/b"]);
+        assert_eq!(env.intel_root_paths, ["/roots/cn.pem"]);
+        assert_eq!(env.pcs_urls.len(), 2);
+        assert_eq!(
+            env.alert_webhook.as_deref(),
+            Some("https://alerts.example.com/tdx")
+        );
+        assert_eq!(env.device_path.as_deref(), Some("/dev/tdx_guest"));
+        assert_eq!(
+            env.pck_cache().unwrap().dir(),
+            Path::new("/var/cache/tdx-attest/pck")
+        );
+
+        // and flags override both
+        let flags = env.merge(Config {
+            policy_path: Some("policy.toml".to_string()),
+            jobs: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(flags.policy_path.as_deref(), Some("policy.toml"));
+        assert_eq!(flags.jobs, Some(2));
+        assert_eq!(flags.qgs_vsock_port, Some(4051));
+        assert_eq!(flags.trust_anchor_dirs, ["/a", "/b"]);
+        assert_eq!(flags.pcs_urls[0], "https://pcs.example.cn");
+        Ok(())
+    }
+
+    #[cfg(feature = "pck-retrieval")]
+    #[test]
+    fn test_pcs_client() {
+        use crate::evidence::pck::PcsClient;
+
+        assert_eq!(Config::default().pcs_client(), PcsClient::new());
+
+        let config = Config {
+            pcs_urls: vec![
+                "https://pcs.example.cn/".to_string(),
+                "https://api.trustedservices.intel.com".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.pcs_client(),
+            PcsClient::new()
+                .with_url("https://pcs.example.cn")
+                .with_fallback(PcsClient::new())
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Config::from_toml("device = \"/dev/tdx_guest\"").is_err());
+        assert!(Config::from_toml("qgs_vsock_port = \"4050\"").is_err());
+        assert!(
+            Config::default()
+                .with_env(vars(&[("TDX_ATTEST_QGS_VSOCK_PORT", "port")]))
+                .is_err()
+        );
+        assert!(
+            Config::default()
+                .with_env(vars(&[("TDX_ATTEST_DEVICE", "/dev/tdx_guest")]))
+                .is_err()
+        );
+    }
+}
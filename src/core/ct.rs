@@ -0,0 +1,51 @@
+//! # Constant-Time Comparisons
+//!
+//! This module provides constant-time equality checks of byte strings, backed
+//! by the `subtle` crate, for values a verifier compares against
+//! attacker-supplied ones, such as measurements, report data bindings, MACs
+//! and digests. Unlike `==`, they don't return early at the first differing
+//! byte, so their timing doesn't reveal how much of a value an attacker
+//! guessed right.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::core::ct;
+//!
+//! assert!(ct::eq(b"report data", b"report data"));
+//! assert!(!ct::eq(b"report data", b"other data"));
+//! ```
+//!
+//! # Notes
+//! - Only the contents are compared in constant time: the lengths of the
+//!   values, which are public (e.g., a digest's), are compared first.
+
+use subtle::ConstantTimeEq;
+
+/// Returns whether `a` and `b` are equal, in time independent of their
+/// contents.
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Returns whether `bytes` are all zero, in time independent of their
+/// contents.
+pub fn is_zero(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0u8, |acc, b| acc | b).ct_eq(&0).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq() {
+        assert!(eq(&[1; 48], &[1; 48]));
+        assert!(!eq(&[1; 48], &[2; 48]));
+        assert!(!eq(&[1; 48], &[1; 47]));
+        assert!(eq(&[], &[]));
+
+        assert!(is_zero(&[0; 48]));
+        assert!(!is_zero(&[0, 0, 1]));
+    }
+}
@@ -0,0 +1,62 @@
+//! # `no_std` TDX Report and Quote Parsing Core
+//!
+//! This module holds the parsing logic for the Intel TDX attestation
+//! structures, the `TDREPORT` (see the `report` module) and the TD quote (see
+//! the `quote` module), so that it can be shared by the guest, the relying
+//! party, and any other component that needs to read them.
+//!
+//! Unlike the rest of the library, this module only depends on `core` and
+//! `alloc`, and is the only module compiled without the `std` feature (i.e.,
+//! with `default-features = false`), so embedded verifiers and
+//! kernel-adjacent components can reuse the exact same parsing logic.
+//!
+//! Measurement registers are represented by the `MeasurementRegister` type
+//! (see the `register` module), and secret-dependent values are compared in
+//! constant time (see the `ct` module).
+//!
+//! Since the library's `Error` type wraps `std` errors, this module has its
+//! own `Error` type, which converts into the library's `Error` with `?`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::core::quote::Quote;
+//!
+//! let bytes = std::fs::read("quote.bin").unwrap();
+//! match Quote::from_bytes(&bytes) {
+//!     Ok(quote) => println!("Quote version {}", quote.version),
+//!     Err(e) => eprintln!("Error parsing quote: {}", e),
+//! }
+//! ```
+
+pub mod ct;
+pub mod quote;
+pub mod register;
+pub mod report;
+
+use alloc::string::String;
+use core::fmt;
+
+/// Represents the errors that can occur while parsing TDX attestation
+/// structures.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The structure is malformed or truncated.
+    ParseError(String),
+    /// The structure is well-formed, but its version or type isn't supported.
+    NotSupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            Error::NotSupported(msg) => write!(f, "Not supported: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// A specialized `Result` type for parsing TDX attestation structures.
+pub type Result<T> = core::result::Result<T, Error>;
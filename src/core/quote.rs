@@ -0,0 +1,579 @@
+//! # TD Quote Parsing
+//!
+//! This module parses Intel TDX DCAP quotes (versions 4 and 5), which are
+//! generated by the Quote Generation Service (QGS) from a `TDREPORT`, and
+//! signed by the Quoting Enclave (QE) with an ECDSA P-256 attestation key.
+//!
+//! A quote consists of:
+//! - a header identifying the quote version and TEE type,
+//! - the TD quote body, which holds the TD's measurements and `report_data`,
+//! - the quote signature by the attestation key, and
+//! - the QE certification data, which binds the attestation key to the QE's
+//!   report, signed by the platform's Provisioning Certification Key (PCK),
+//!   and (usually) the PCK certificate chain up to the Intel SGX Root CA.
+//!
+//! Parsing does not verify any signatures (see the `verification::quote`
+//! module, when compiled with the `host-verification` feature).
+//!
+//! The module is `no_std` compatible (see the `core` module), and is
+//! re-exported by the `evidence::quote` module.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::core::quote::Quote;
+//!
+//! let bytes = std::fs::read("quote.bin").unwrap();
+//! let quote = Quote::from_bytes(&bytes).unwrap();
+//! println!("MRTD: {}", quote.body.mrtd);
+//! println!("PCK chain has {} certs", quote.pck_chain().unwrap().len());
+//! ```
+
+use crate::core::ct;
+use crate::core::register::MeasurementRegister;
+use crate::core::report::NO_SERVTD_HASH;
+use crate::core::{Error, Result};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha2::{Digest, Sha256};
+
+/// The length of the quote header.
+pub const QUOTE_HEADER_LEN: usize = 48;
+
+/// The length of the TDX 1.0 TD quote body.
+pub const TD_QUOTE_BODY_V10_LEN: usize = 584;
+
+/// The length of the TDX 1.5 TD quote body.
+pub const TD_QUOTE_BODY_V15_LEN: usize = 648;
+
+/// The TEE type of TDX quotes.
+pub const TDX_TEE_TYPE: u32 = 0x81;
+
+/// The attestation key type of ECDSA P-256 quotes.
+pub const ECDSA_P256_KEY_TYPE: u16 = 2;
+
+/// The length of the QE report (an SGX report body).
+pub const QE_REPORT_LEN: usize = 384;
+
+/// The certification data type of the platform's cleartext PPID, with its
+/// raw TCB.
+pub const CERT_DATA_PPID_CLEARTEXT: u16 = 1;
+
+/// The certification data type of the platform's PPID encrypted with
+/// RSA-2048-OAEP, with its raw TCB.
+pub const CERT_DATA_PPID_RSA2048: u16 = 2;
+
+/// The certification data type of the platform's PPID encrypted with
+/// RSA-3072-OAEP, with its raw TCB.
+pub const CERT_DATA_PPID_RSA3072: u16 = 3;
+
+/// The certification data type of a PEM-encoded PCK certificate chain.
+pub const CERT_DATA_PCK_CHAIN: u16 = 5;
+
+/// The certification data type of the QE report certification data.
+pub const CERT_DATA_QE_REPORT: u16 = 6;
+
+// The TD attribute bit indicating a debug TD
+pub(crate) const TD_ATTRIBUTES_DEBUG: u8 = 0x01;
+
+// The quote v5 body types
+const BODY_TYPE_TD10: u16 = 2;
+const BODY_TYPE_TD15: u16 = 3;
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// The TD quote body, which holds the TD's measurements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TdQuoteBody {
+    /// The TCB SVN of the TDX module.
+    pub tee_tcb_svn: [u8; 16],
+    /// The measurement of the TDX module.
+    pub mrseam: MeasurementRegister,
+    /// The measurement of the TDX module's signer.
+    pub mrsignerseam: MeasurementRegister,
+    /// The attributes of the TDX module.
+    pub seam_attributes: [u8; 8],
+    /// The attributes of the TD.
+    pub td_attributes: [u8; 8],
+    /// The extended features available to the TD.
+    pub xfam: [u8; 8],
+    /// The build-time measurement of the TD.
+    pub mrtd: MeasurementRegister,
+    /// The software-defined ID for non-owner-defined configuration.
+    pub mrconfigid: MeasurementRegister,
+    /// The software-defined ID for the TD's owner.
+    pub mrowner: MeasurementRegister,
+    /// The software-defined ID for owner-defined configuration.
+    pub mrownerconfig: MeasurementRegister,
+    /// The runtime measurement registers.
+    pub rtmrs: [MeasurementRegister; 4],
+    /// The data bound into the quote by the TD.
+    pub report_data: [u8; 64],
+    /// The TCB SVN of the TDX module servicing a migrated TD (TDX 1.5 only).
+    pub tee_tcb_svn2: Option<[u8; 16]>,
+    /// The measurement of the service TDs bound to the TD (TDX 1.5 only),
+    /// i.e., its `SERVTD_HASH` (see `TdReportV15::get_servtd_hash()`).
+    pub mrservicetd: Option<MeasurementRegister>,
+}
+
+impl TdQuoteBody {
+    /// Returns whether the TD is a debug TD, whose memory and state are
+    /// accessible to the host.
+    pub fn is_debug(&self) -> bool {
+        self.td_attributes[0] & TD_ATTRIBUTES_DEBUG != 0
+    }
+
+    /// Returns whether any service TDs (e.g., a migration TD) are bound to
+    /// the TD, i.e., whether the quote has a non-zero `MRSERVICETD`.
+    ///
+    /// Returns `false` for quotes with a TDX 1.0 body, which don't carry
+    /// `MRSERVICETD`, so verifiers that constrain service TDs must require a
+    /// TDX 1.5 body.
+    pub fn is_servtd_bound(&self) -> bool {
+        self.mrservicetd.is_some_and(|h| h != NO_SERVTD_HASH)
+    }
+
+    fn parse(reader: &mut Reader, v15: bool) -> Result<Self> {
+        Ok(Self {
+            tee_tcb_svn: reader.array()?,
+            mrseam: reader.register()?,
+            mrsignerseam: reader.register()?,
+            seam_attributes: reader.array()?,
+            td_attributes: reader.array()?,
+            xfam: reader.array()?,
+            mrtd: reader.register()?,
+            mrconfigid: reader.register()?,
+            mrowner: reader.register()?,
+            mrownerconfig: reader.register()?,
+            rtmrs: [
+                reader.register()?,
+                reader.register()?,
+                reader.register()?,
+                reader.register()?,
+            ],
+            report_data: reader.array()?,
+            tee_tcb_svn2: if v15 { Some(reader.array()?) } else { None },
+            mrservicetd: if v15 { Some(reader.register()?) } else { None },
+        })
+    }
+}
+
+/// A parsed TD quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quote {
+    /// The quote version (4 or 5).
+    pub version: u16,
+    /// The TD quote body.
+    pub body: TdQuoteBody,
+    /// The ECDSA P-256 signature over the header and body, by the attestation
+    /// key.
+    pub signature: [u8; 64],
+    /// The raw ECDSA P-256 attestation public key.
+    pub attestation_key: [u8; 64],
+    /// The QE report.
+    pub qe_report: [u8; QE_REPORT_LEN],
+    /// The ECDSA P-256 signature over the QE report, by the PCK.
+    pub qe_report_signature: [u8; 64],
+    /// The QE authentication data.
+    pub qe_auth_data: Vec<u8>,
+    /// The type of the PCK certification data.
+    pub cert_data_type: u16,
+    /// The PCK certification data (e.g., the PEM-encoded PCK certificate
+    /// chain).
+    pub cert_data: Vec<u8>,
+    signed_len: usize,
+    raw: Vec<u8>,
+}
+
+impl Quote {
+    /// Parses a TD quote.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the quote is malformed or truncated,
+    /// or an `Error::NotSupported` if it isn't an ECDSA P-256 TDX quote.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+
+        // header
+        let version = reader.u16()?;
+        let key_type = reader.u16()?;
+        let tee_type = reader.u32()?;
+        reader.take(QUOTE_HEADER_LEN - 8)?;
+
+        if tee_type != TDX_TEE_TYPE {
+            return Err(Error::NotSupported(format!(
+                "Quote TEE type {:#x} is not TDX",
+                tee_type
+            )));
+        }
+        if key_type != ECDSA_P256_KEY_TYPE {
+            return Err(Error::NotSupported(format!(
+                "Quote attestation key type {} is not supported",
+                key_type
+            )));
+        }
+
+        // body
+        let body = match version {
+            4 => TdQuoteBody::parse(&mut reader, false)?,
+            5 => {
+                let body_type = reader.u16()?;
+                let body_len = reader.u32()? as usize;
+                let v15 = match (body_type, body_len) {
+                    (BODY_TYPE_TD10, TD_QUOTE_BODY_V10_LEN) => false,
+                    (BODY_TYPE_TD15, TD_QUOTE_BODY_V15_LEN) => true,
+                    _ => {
+                        return Err(Error::NotSupported(format!(
+                            "Quote body type {} ({} bytes) is not supported",
+                            body_type, body_len
+                        )));
+                    }
+                };
+                TdQuoteBody::parse(&mut reader, v15)?
+            }
+            _ => {
+                return Err(Error::NotSupported(format!(
+                    "Quote version {} is not supported",
+                    version
+                )));
+            }
+        };
+        let signed_len = reader.offset;
+
+        // signature data
+        let sig_len = reader.u32()? as usize;
+        let mut sig_reader = Reader::new(reader.take(sig_len)?);
+        let signature = sig_reader.array()?;
+        let attestation_key = sig_reader.array()?;
+
+        let outer_type = sig_reader.u16()?;
+        let outer_len = sig_reader.u32()? as usize;
+        if outer_type != CERT_DATA_QE_REPORT {
+            return Err(Error::NotSupported(format!(
+                "Quote certification data type {} is not supported",
+                outer_type
+            )));
+        }
+        let mut qe_reader = Reader::new(sig_reader.take(outer_len)?);
+        let qe_report = qe_reader.array()?;
+        let qe_report_signature = qe_reader.array()?;
+        let auth_len = qe_reader.u16()? as usize;
+        let qe_auth_data = qe_reader.take(auth_len)?.to_vec();
+        let cert_data_type = qe_reader.u16()?;
+        let cert_len = qe_reader.u32()? as usize;
+        let cert_data = qe_reader.take(cert_len)?.to_vec();
+
+        Ok(Self {
+            version,
+            body,
+            signature,
+            attestation_key,
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            cert_data_type,
+            cert_data,
+            signed_len,
+            raw: bytes[..reader.offset].to_vec(),
+        })
+    }
+
+    /// Returns the raw quote.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Returns the part of the quote signed by the attestation key (the
+    /// header and the body).
+    pub fn signed_data(&self) -> &[u8] {
+        &self.raw[..self.signed_len]
+    }
+
+    /// Returns the `report_data` field of the QE report, which binds the
+    /// attestation key.
+    pub fn qe_report_data(&self) -> &[u8] {
+        &self.qe_report[QE_REPORT_LEN - 64..]
+    }
+
+    /// Returns whether the QE report's `report_data` binds the attestation
+    /// key and QE authentication data, i.e., it is their SHA-256 digest
+    /// followed by zeros.
+    pub fn qe_report_binds_attestation_key(&self) -> bool {
+        let mut hasher = Sha256::new();
+        hasher.update(self.attestation_key);
+        hasher.update(&self.qe_auth_data);
+
+        let report_data = self.qe_report_data();
+        ct::eq(&report_data[..32], &hasher.finalize()) && ct::is_zero(&report_data[32..])
+    }
+
+    /// Returns the DER-encoded PCK certificate chain embedded in the quote,
+    /// starting with the PCK certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the quote doesn't embed the chain
+    /// (i.e., the PCK certificate must be retrieved from Intel's PCS, see the
+    /// `evidence::pck` module), or an `Error::ParseError` if the chain is
+    /// malformed.
+    pub fn pck_chain(&self) -> Result<Vec<Vec<u8>>> {
+        if self.cert_data_type != CERT_DATA_PCK_CHAIN {
+            return Err(Error::NotSupported(format!(
+                "Quote certification data type {} does not embed the PCK chain",
+                self.cert_data_type
+            )));
+        }
+
+        let pem = core::str::from_utf8(&self.cert_data)
+            .map_err(|e| Error::ParseError(format!("Invalid PCK chain: {}", e)))?;
+        pem_to_der(pem)
+    }
+}
+
+/// Decodes a chain of PEM-encoded certificates into DER.
+pub fn pem_to_der(pem: &str) -> Result<Vec<Vec<u8>>> {
+    let mut certs = Vec::new();
+    let mut rest = pem;
+    while let Some(start) = rest.find(PEM_BEGIN) {
+        let body = &rest[start + PEM_BEGIN.len()..];
+        let end = body
+            .find(PEM_END)
+            .ok_or_else(|| Error::ParseError("Unterminated PEM certificate".to_string()))?;
+
+        let b64: String = body[..end].split_whitespace().collect();
+        let der = STANDARD
+            .decode(b64)
+            .map_err(|e| Error::ParseError(format!("Invalid PEM certificate: {}", e)))?;
+        certs.push(der);
+
+        rest = &body[end + PEM_END.len()..];
+    }
+
+    if certs.is_empty() {
+        return Err(Error::ParseError("No PEM certificates found".to_string()));
+    }
+    Ok(certs)
+}
+
+/// A bounds-checked little-endian reader over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| Error::ParseError("Quote is truncated".to_string()))?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn register(&mut self) -> Result<MeasurementRegister> {
+        Ok(self.array()?.into())
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::report::TDX_MR_REG_LEN;
+
+    /// The parts of a test quote.
+    pub(crate) struct QuoteParts {
+        pub(crate) version: u16,
+        pub(crate) tee_tcb_svn: [u8; 16],
+        pub(crate) mrtd: [u8; TDX_MR_REG_LEN],
+        pub(crate) rtmrs: [[u8; TDX_MR_REG_LEN]; 4],
+        pub(crate) report_data: [u8; 64],
+        pub(crate) td_attributes: [u8; 8],
+        pub(crate) attestation_key: [u8; 64],
+        pub(crate) qe_report: [u8; QE_REPORT_LEN],
+        pub(crate) qe_auth_data: Vec<u8>,
+        pub(crate) pck_chain: String,
+        /// The certification data type of `pck_chain` (the PCK chain by
+        /// default).
+        pub(crate) cert_data_type: u16,
+        /// The `MRSERVICETD` of a TDX 1.5 body (v5 quotes only).
+        pub(crate) mrservicetd: Option<[u8; TDX_MR_REG_LEN]>,
+    }
+
+    impl Default for QuoteParts {
+        fn default() -> Self {
+            Self {
+                version: 4,
+                tee_tcb_svn: [0; 16],
+                mrtd: [1; TDX_MR_REG_LEN],
+                rtmrs: [[0; TDX_MR_REG_LEN]; 4],
+                report_data: [2; 64],
+                td_attributes: [0; 8],
+                attestation_key: [3; 64],
+                qe_report: [4; QE_REPORT_LEN],
+                qe_auth_data: vec![5; 32],
+                pck_chain: String::new(),
+                cert_data_type: CERT_DATA_PCK_CHAIN,
+                mrservicetd: None,
+            }
+        }
+    }
+
+    impl QuoteParts {
+        /// Returns the header and body of the quote.
+        pub(crate) fn signed_data(&self) -> Vec<u8> {
+            let mut bytes = vec![];
+            bytes.extend(self.version.to_le_bytes());
+            bytes.extend(ECDSA_P256_KEY_TYPE.to_le_bytes());
+            bytes.extend(TDX_TEE_TYPE.to_le_bytes());
+            bytes.resize(QUOTE_HEADER_LEN, 0);
+            if self.version == 5 {
+                let (body_type, body_len) = match self.mrservicetd {
+                    Some(_) => (BODY_TYPE_TD15, TD_QUOTE_BODY_V15_LEN),
+                    None => (BODY_TYPE_TD10, TD_QUOTE_BODY_V10_LEN),
+                };
+                bytes.extend(body_type.to_le_bytes());
+                bytes.extend((body_len as u32).to_le_bytes());
+            }
+
+            bytes.extend(self.tee_tcb_svn);
+            bytes.resize(bytes.len() + 2 * TDX_MR_REG_LEN + 8, 0);
+            bytes.extend(self.td_attributes);
+            bytes.extend([0; 8]);
+            bytes.extend(self.mrtd);
+            bytes.resize(bytes.len() + 3 * TDX_MR_REG_LEN, 0);
+            for rtmr in &self.rtmrs {
+                bytes.extend(rtmr);
+            }
+            bytes.extend(self.report_data);
+            if let (5, Some(mrservicetd)) = (self.version, self.mrservicetd) {
+                bytes.extend([0; 16]);
+                bytes.extend(mrservicetd);
+            }
+            bytes
+        }
+
+        /// Assembles the quote from its signed data and signatures.
+        pub(crate) fn assemble(&self, signature: &[u8; 64], qe_signature: &[u8; 64]) -> Vec<u8> {
+            let mut qe_data = vec![];
+            qe_data.extend(self.qe_report);
+            qe_data.extend(qe_signature);
+            qe_data.extend((self.qe_auth_data.len() as u16).to_le_bytes());
+            qe_data.extend(&self.qe_auth_data);
+            qe_data.extend(self.cert_data_type.to_le_bytes());
+            qe_data.extend((self.pck_chain.len() as u32).to_le_bytes());
+            qe_data.extend(self.pck_chain.as_bytes());
+
+            let mut sig_data = vec![];
+            sig_data.extend(signature);
+            sig_data.extend(self.attestation_key);
+            sig_data.extend(CERT_DATA_QE_REPORT.to_le_bytes());
+            sig_data.extend((qe_data.len() as u32).to_le_bytes());
+            sig_data.extend(qe_data);
+
+            let mut quote = self.signed_data();
+            quote.extend((sig_data.len() as u32).to_le_bytes());
+            quote.extend(sig_data);
+            quote
+        }
+    }
+
+    fn pem(der: &[u8]) -> String {
+        format!("{}\n{}\n{}\n", PEM_BEGIN, STANDARD.encode(der), PEM_END)
+    }
+
+    #[test]
+    fn test_parse_quote() -> Result<()> {
+        let mut parts = QuoteParts {
+            pck_chain: format!("{}{}", pem(b"leaf"), pem(b"root")),
+            ..Default::default()
+        };
+        parts.rtmrs[3] = [9; TDX_MR_REG_LEN];
+
+        for version in [4, 5] {
+            parts.version = version;
+            let bytes = parts.assemble(&[6; 64], &[7; 64]);
+            let quote = Quote::from_bytes(&bytes)?;
+
+            assert_eq!(quote.version, version);
+            assert_eq!(quote.body.mrtd, parts.mrtd);
+            assert_eq!(quote.body.rtmrs, parts.rtmrs);
+            assert_eq!(quote.body.report_data, parts.report_data);
+            assert!(!quote.body.is_debug());
+            assert_eq!(quote.signature, [6; 64]);
+            assert_eq!(quote.qe_report_signature, [7; 64]);
+            assert_eq!(quote.qe_auth_data, parts.qe_auth_data);
+            assert_eq!(quote.signed_data(), parts.signed_data());
+            assert_eq!(quote.as_bytes(), bytes);
+            assert_eq!(quote.pck_chain()?, vec![b"leaf".to_vec(), b"root".to_vec()]);
+            assert_eq!(quote.body.mrservicetd, None);
+            assert!(!quote.body.is_servtd_bound());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_quote_td15_body() -> Result<()> {
+        let parts = QuoteParts {
+            version: 5,
+            mrservicetd: Some([8; TDX_MR_REG_LEN]),
+            ..Default::default()
+        };
+        let quote = Quote::from_bytes(&parts.assemble(&[6; 64], &[7; 64]))?;
+
+        assert_eq!(quote.body.tee_tcb_svn2, Some([0; 16]));
+        assert_eq!(quote.body.mrservicetd, Some([8; TDX_MR_REG_LEN].into()));
+        assert!(quote.body.is_servtd_bound());
+        assert_eq!(quote.signed_data(), parts.signed_data());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_truncated_quote() {
+        let bytes = QuoteParts::default().assemble(&[0; 64], &[0; 64]);
+        for len in [0, QUOTE_HEADER_LEN, bytes.len() - 1] {
+            assert!(Quote::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_unsupported_quote() {
+        let mut bytes = QuoteParts::default().assemble(&[0; 64], &[0; 64]);
+        bytes[4] = 0; // SGX TEE type
+        assert!(matches!(
+            Quote::from_bytes(&bytes),
+            Err(Error::NotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_pem_to_der() {
+        assert!(pem_to_der("").is_err());
+        assert!(pem_to_der(&format!("{}\nAAAA", PEM_BEGIN)).is_err());
+        assert!(pem_to_der(&format!("{}\n!!!\n{}", PEM_BEGIN, PEM_END)).is_err());
+    }
+}
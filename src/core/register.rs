@@ -0,0 +1,321 @@
+//! # Measurement Registers
+//!
+//! This module provides `MeasurementRegister`, the 48-byte SHA-384 value of
+//! a TDX measurement register (`MRTD`, `MRCONFIGID`, `MROWNER`,
+//! `MROWNERCONFIG`, the RTMRs, ...), which the library passes around instead
+//! of raw `[u8; 48]` arrays. `Digest384` is an alias for other SHA-384
+//! digests of the same width (e.g., the digests extended into an RTMR).
+//!
+//! Registers are displayed, parsed (see `str::parse()`) and, in
+//! human-readable formats such as JSON or TOML, serialized as lowercase hex,
+//! and serialized as bytes in binary formats such as CBOR. Both deserialize
+//! from a sequence of 48 bytes as well, as registers were serialized before
+//! this type existed.
+//!
+//! Registers are compared in constant time, since verifiers compare them
+//! against secret-dependent values (e.g., key-release bindings).
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::core::register::MeasurementRegister;
+//!
+//! let mrtd: MeasurementRegister = "ab".repeat(48).parse().unwrap();
+//! assert_eq!(mrtd, [0xab; 48]);
+//! assert_eq!(mrtd.to_string(), "ab".repeat(48));
+//! ```
+
+use crate::core::ct;
+use crate::core::report::TDX_MR_REG_LEN;
+use crate::core::{Error, Result};
+
+use alloc::format;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::str::FromStr;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The value of a 48-byte TDX measurement register.
+#[derive(Clone, Copy, Eq)]
+pub struct MeasurementRegister([u8; TDX_MR_REG_LEN]);
+
+/// A 48-byte SHA-384 digest.
+pub type Digest384 = MeasurementRegister;
+
+impl MeasurementRegister {
+    /// The all-zero register, e.g., of an unset `MRCONFIGID`.
+    pub const ZERO: Self = Self([0; TDX_MR_REG_LEN]);
+
+    /// Creates a register holding `bytes`.
+    pub const fn new(bytes: [u8; TDX_MR_REG_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Creates a register from a slice of exactly 48 bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if `bytes` isn't 48 bytes long.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        bytes.try_into().map(Self).map_err(|_| {
+            Error::ParseError(format!(
+                "Measurement register has {} bytes, not {}",
+                bytes.len(),
+                TDX_MR_REG_LEN
+            ))
+        })
+    }
+
+    /// Returns the register's bytes.
+    pub const fn to_bytes(self) -> [u8; TDX_MR_REG_LEN] {
+        self.0
+    }
+
+    /// Returns whether the register is all-zero.
+    pub fn is_zero(&self) -> bool {
+        ct::is_zero(&self.0)
+    }
+}
+
+impl Default for MeasurementRegister {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Deref for MeasurementRegister {
+    type Target = [u8; TDX_MR_REG_LEN];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for MeasurementRegister {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; TDX_MR_REG_LEN]> for MeasurementRegister {
+    fn from(bytes: [u8; TDX_MR_REG_LEN]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<MeasurementRegister> for [u8; TDX_MR_REG_LEN] {
+    fn from(register: MeasurementRegister) -> Self {
+        register.0
+    }
+}
+
+impl TryFrom<&[u8]> for MeasurementRegister {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_slice(bytes)
+    }
+}
+
+impl PartialEq for MeasurementRegister {
+    /// Compares the registers in constant time.
+    fn eq(&self, other: &Self) -> bool {
+        ct::eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for MeasurementRegister {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl PartialEq<[u8; TDX_MR_REG_LEN]> for MeasurementRegister {
+    fn eq(&self, other: &[u8; TDX_MR_REG_LEN]) -> bool {
+        *self == Self(*other)
+    }
+}
+
+impl PartialEq<MeasurementRegister> for [u8; TDX_MR_REG_LEN] {
+    fn eq(&self, other: &MeasurementRegister) -> bool {
+        *other == *self
+    }
+}
+
+impl fmt::LowerHex for MeasurementRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MeasurementRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::Debug for MeasurementRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MeasurementRegister({:x})", self)
+    }
+}
+
+impl FromStr for MeasurementRegister {
+    type Err = Error;
+
+    /// Parses a hex-encoded register.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if `s` isn't 96 hex digits.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::ParseError(format!("Invalid measurement register {:?}", s));
+        if s.len() != TDX_MR_REG_LEN * 2 || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let mut bytes = [0u8; TDX_MR_REG_LEN];
+        for (byte, digits) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let digits = core::str::from_utf8(digits).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for MeasurementRegister {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MeasurementRegister {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        struct RegisterVisitor;
+
+        impl<'de> Visitor<'de> for RegisterVisitor {
+            type Value = MeasurementRegister;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    "a hex-encoded or {}-byte measurement register",
+                    TDX_MR_REG_LEN
+                )
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> core::result::Result<Self::Value, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_bytes<E: de::Error>(self, b: &[u8]) -> core::result::Result<Self::Value, E> {
+                MeasurementRegister::from_slice(b).map_err(E::custom)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> core::result::Result<Self::Value, A::Error> {
+                let mut bytes = [0u8; TDX_MR_REG_LEN];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                }
+                if seq.next_element::<u8>()?.is_some() {
+                    return Err(de::Error::invalid_length(TDX_MR_REG_LEN + 1, &self));
+                }
+                Ok(MeasurementRegister(bytes))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(RegisterVisitor)
+        } else {
+            deserializer.deserialize_bytes(RegisterVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_hex() -> Result<()> {
+        let register = MeasurementRegister::new([0xab; TDX_MR_REG_LEN]);
+        assert_eq!(register.to_string(), "ab".repeat(TDX_MR_REG_LEN));
+        assert_eq!(
+            "ab".repeat(TDX_MR_REG_LEN).parse::<MeasurementRegister>()?,
+            register
+        );
+        assert_eq!(
+            "AB".repeat(TDX_MR_REG_LEN).parse::<MeasurementRegister>()?,
+            register
+        );
+
+        assert!("ab".parse::<MeasurementRegister>().is_err());
+        assert!(
+            "zz".repeat(TDX_MR_REG_LEN)
+                .parse::<MeasurementRegister>()
+                .is_err()
+        );
+        assert!(
+            "é".repeat(TDX_MR_REG_LEN)
+                .parse::<MeasurementRegister>()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conversions() -> Result<()> {
+        let register = MeasurementRegister::from_slice(&[1; TDX_MR_REG_LEN])?;
+        assert_eq!(register, [1; TDX_MR_REG_LEN]);
+        assert_eq!([1; TDX_MR_REG_LEN], register);
+        assert_eq!(register.to_bytes(), [1; TDX_MR_REG_LEN]);
+        assert_eq!(register.as_ref(), &[1; TDX_MR_REG_LEN][..]);
+        assert_ne!(register, MeasurementRegister::ZERO);
+        assert!(MeasurementRegister::default().is_zero());
+        assert!(MeasurementRegister::from_slice(&[1; 32]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_serde() {
+        let register = MeasurementRegister::new([0xab; TDX_MR_REG_LEN]);
+
+        let json = serde_json::to_string(&register).unwrap();
+        assert_eq!(json, format!("\"{}\"", "ab".repeat(TDX_MR_REG_LEN)));
+        assert_eq!(
+            serde_json::from_str::<MeasurementRegister>(&json).unwrap(),
+            register
+        );
+        // registers serialized as arrays
+        let json = serde_json::to_string(&vec![0xab; TDX_MR_REG_LEN]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MeasurementRegister>(&json).unwrap(),
+            register
+        );
+        let json = serde_json::to_string(&vec![0xab; TDX_MR_REG_LEN + 1]).unwrap();
+        assert!(serde_json::from_str::<MeasurementRegister>(&json).is_err());
+
+        let mut cbor = vec![];
+        ciborium::into_writer(&register, &mut cbor).unwrap();
+        assert_eq!(
+            ciborium::from_reader::<MeasurementRegister, _>(&cbor[..]).unwrap(),
+            register
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! # Report Diffs
+//!
+//! This module compares two `TDREPORT`s or TD quotes (e.g., of a TD before
+//! and after an image update) field by field, and describes which
+//! measurement registers, SVNs and attributes changed, e.g., to debug why a
+//! golden value stopped matching (see `tdx-attest report diff`).
+//!
+//! Reports and quotes can be compared with each other: only the fields both
+//! have are compared, so the report-only fields (e.g., `CPUSVN`) and the
+//! TDX 1.5 fields of quotes with a TDX 1.0 body are skipped.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::core::quote::Quote;
+//! use tdx_workload_attestation::core::report::diff::{Fields, diff};
+//!
+//! let old = Quote::from_bytes(&std::fs::read("old.bin").unwrap()).unwrap();
+//! let new = Quote::from_bytes(&std::fs::read("new.bin").unwrap()).unwrap();
+//! for change in diff(&Fields::from(&old), &Fields::from(&new)) {
+//!     println!("{}", change);
+//! }
+//! ```
+
+use crate::core::quote::Quote;
+use crate::core::register::MeasurementRegister;
+use crate::core::report::TdReportV15;
+use crate::core::report::render::{
+    TEE_TCB_SVN_COMPONENTS, hex, td_attribute_names, xfam_feature_names,
+};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The named fields of a report or quote, in layout order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fields(Vec<(&'static str, Vec<u8>)>);
+
+impl Fields {
+    /// Returns the value of the field `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_slice())
+    }
+}
+
+impl From<&TdReportV15> for Fields {
+    fn from(report: &TdReportV15) -> Self {
+        let tcb = &report.tee_tcb_info;
+        let mut fields = vec![
+            ("CPUSVN", report.report_mac_struct.cpusvn.to_vec()),
+            ("TEE_TCB_SVN", tcb.tee_tcb_svn.to_vec()),
+            ("MRSEAM", tcb.mrseam.to_vec()),
+            ("MRSIGNERSEAM", tcb.mrsignerseam.to_vec()),
+            ("SEAM ATTRIBUTES", tcb.attributes.to_vec()),
+            ("ATTRIBUTES", report.get_td_attributes().to_vec()),
+            ("XFAM", report.get_xfam().to_vec()),
+            ("MRTD", report.get_mrtd().to_vec()),
+            ("MRCONFIGID", report.get_mrconfigid().to_vec()),
+            ("MROWNER", report.get_mrowner().to_vec()),
+            ("MROWNERCONFIG", report.get_mrownerconfig().to_vec()),
+        ];
+        fields.extend(rtmr_fields(&report.get_rtmrs()));
+        fields.extend([
+            ("REPORTDATA", report.get_report_data().to_vec()),
+            ("TEE_TCB_SVN2", tcb.tee_tcb_svn2.to_vec()),
+            ("SERVTD_HASH", report.get_servtd_hash().to_vec()),
+        ]);
+        Fields(fields)
+    }
+}
+
+impl From<&Quote> for Fields {
+    fn from(quote: &Quote) -> Self {
+        let body = &quote.body;
+        let mut fields = vec![
+            ("TEE_TCB_SVN", body.tee_tcb_svn.to_vec()),
+            ("MRSEAM", body.mrseam.to_vec()),
+            ("MRSIGNERSEAM", body.mrsignerseam.to_vec()),
+            ("SEAM ATTRIBUTES", body.seam_attributes.to_vec()),
+            ("ATTRIBUTES", body.td_attributes.to_vec()),
+            ("XFAM", body.xfam.to_vec()),
+            ("MRTD", body.mrtd.to_vec()),
+            ("MRCONFIGID", body.mrconfigid.to_vec()),
+            ("MROWNER", body.mrowner.to_vec()),
+            ("MROWNERCONFIG", body.mrownerconfig.to_vec()),
+        ];
+        fields.extend(rtmr_fields(&body.rtmrs));
+        fields.push(("REPORTDATA", body.report_data.to_vec()));
+        if let Some(tee_tcb_svn2) = &body.tee_tcb_svn2 {
+            fields.push(("TEE_TCB_SVN2", tee_tcb_svn2.to_vec()));
+        }
+        // MRSERVICETD is the SERVTD_HASH of the quoted report
+        if let Some(mrservicetd) = &body.mrservicetd {
+            fields.push(("SERVTD_HASH", mrservicetd.to_vec()));
+        }
+        Fields(fields)
+    }
+}
+
+/// A field that differs between two reports or quotes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// The name of the field (e.g., `RTMR2`).
+    pub field: &'static str,
+    /// The field's value in the first report.
+    pub old: Vec<u8>,
+    /// The field's value in the second report.
+    pub new: Vec<u8>,
+}
+
+impl FieldChange {
+    /// Describes what the change means, for the fields whose values can be
+    /// decoded: the attribute or XFAM bits set (`+`) and cleared (`-`), and
+    /// the `TEE_TCB_SVN` components that changed.
+    pub fn describe(&self) -> Option<String> {
+        let bits = |names: fn(&[u8; 8]) -> Vec<String>| {
+            let old = names(&self.old.as_slice().try_into().ok()?);
+            let new = names(&self.new.as_slice().try_into().ok()?);
+            let mut changes: Vec<String> = new
+                .iter()
+                .filter(|name| !old.contains(name))
+                .map(|name| format!("+{}", name))
+                .collect();
+            changes.extend(
+                old.iter()
+                    .filter(|name| !new.contains(name))
+                    .map(|name| format!("-{}", name)),
+            );
+            Some(changes.join(", "))
+        };
+
+        match self.field {
+            "ATTRIBUTES" => bits(td_attribute_names),
+            "XFAM" => bits(xfam_feature_names),
+            "TEE_TCB_SVN" | "TEE_TCB_SVN2" => {
+                let changes: Vec<String> = TEE_TCB_SVN_COMPONENTS
+                    .iter()
+                    .zip(self.old.iter().zip(&self.new))
+                    .filter(|(_, (old, new))| old != new)
+                    .map(|(meaning, (old, new))| format!("{} {} -> {}", meaning, old, new))
+                    .collect();
+                (!changes.is_empty()).then(|| changes.join(", "))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.field)?;
+        if let Some(description) = self.describe() {
+            write!(f, " ({})", description)?;
+        }
+        write!(f, "\n  - {}\n  + {}", hex(&self.old), hex(&self.new))
+    }
+}
+
+/// Returns the fields that differ between `old` and `new`, in layout order.
+///
+/// Only the fields both have are compared.
+pub fn diff(old: &Fields, new: &Fields) -> Vec<FieldChange> {
+    old.0
+        .iter()
+        .filter_map(|(field, old)| {
+            let new = new.get(field)?;
+            (old.as_slice() != new).then(|| FieldChange {
+                field,
+                old: old.clone(),
+                new: new.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the fields of the RTMRs.
+fn rtmr_fields(rtmrs: &[MeasurementRegister; 4]) -> [(&'static str, Vec<u8>); 4] {
+    [
+        ("RTMR0", rtmrs[0].to_vec()),
+        ("RTMR1", rtmrs[1].to_vec()),
+        ("RTMR2", rtmrs[2].to_vec()),
+        ("RTMR3", rtmrs[3].to_vec()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::core::report::{TDX_MR_REG_LEN, TdReportBuilder};
+
+    #[test]
+    fn test_diff_reports() {
+        let old = TdReportBuilder::new()
+            .with_tee_tcb_svn(&[3, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+            .with_td_attributes(&[0x01, 0, 0, 0, 0, 0, 0, 0], &[0x03, 0, 0, 0, 0, 0, 0, 0])
+            .with_mrtd(&[1; TDX_MR_REG_LEN])
+            .build();
+        let new = TdReportBuilder::new()
+            .with_tee_tcb_svn(&[4, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+            .with_td_attributes(&[0, 0, 0, 0x10, 0, 0, 0, 0], &[0x03, 0, 0, 0, 0, 0, 0, 0])
+            .with_mrtd(&[1; TDX_MR_REG_LEN])
+            .with_rtmrs(&[
+                [0; TDX_MR_REG_LEN],
+                [0; TDX_MR_REG_LEN],
+                [2; TDX_MR_REG_LEN],
+                [0; TDX_MR_REG_LEN],
+            ])
+            .build();
+
+        assert!(diff(&Fields::from(&old), &Fields::from(&old)).is_empty());
+
+        let changes = diff(&Fields::from(&old), &Fields::from(&new));
+        let fields: Vec<_> = changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["TEE_TCB_SVN", "ATTRIBUTES", "RTMR2"]);
+        assert_eq!(changes[0].describe().unwrap(), "TDX module SVN 3 -> 4");
+        assert_eq!(changes[1].describe().unwrap(), "+SEPT_VE_DISABLE, -DEBUG");
+        assert_eq!(changes[2].describe(), None);
+        assert_eq!(
+            format!("{}", changes[2]),
+            format!(
+                "RTMR2\n  - {}\n  + {}",
+                "00".repeat(TDX_MR_REG_LEN),
+                "02".repeat(TDX_MR_REG_LEN)
+            )
+        );
+    }
+
+    #[test]
+    fn test_diff_report_and_quote() {
+        let quote = Quote::from_bytes(&QuoteParts::default().assemble(&[6; 64], &[7; 64])).unwrap();
+        let report = TdReportBuilder::new()
+            .with_mrtd(&[1; TDX_MR_REG_LEN])
+            .with_report_data(&[2; 64])
+            .build();
+
+        // The report's CPUSVN, TEE_TCB_SVN2 and SERVTD_HASH aren't in the
+        // TDX 1.0 quote body
+        assert!(diff(&Fields::from(&report), &Fields::from(&quote)).is_empty());
+        assert!(Fields::from(&report).get("CPUSVN").is_some());
+        assert!(Fields::from(&quote).get("CPUSVN").is_none());
+    }
+}
@@ -0,0 +1,1011 @@
+//! # Intel TDX `TDREPORT` Structures
+//!
+//! This module provides data structures and utilities for working with TDX
+//! attestation reports, specifically the `TDREPORT` and its associated
+//! sub-fields. These structures are used to parse and manipulate the raw
+//! attestation data retrieved from a TDX device.
+//!
+//! The module currently only supports the TDX 1.5 report format.
+//!
+//! ## Overview
+//!
+//! The `TDREPORT` is a 1024-byte structure that contains various fields
+//! containing information about the TDX guest (i.e., the Trust Domain, or TD)
+//! as well as the CPU.
+//! Specifically, the `TDREPORT` consists of the `ReportMacStruct`,
+//! `TeeTcbInfo`, and `TdInfo`.
+//!
+//! Reports can also be constructed with the `TdReportBuilder` and serialized
+//! back into the raw layout with `TdReportV15::to_bytes()`, e.g., to test
+//! verifiers without a TDX device.
+//!
+//! The `TEE_TCB_INFO_HASH` and `TEE_INFO_HASH` fields of a report, which its
+//! MAC covers, are the SHA-384 digests of its `TeeTcbInfo` and `TdInfo`.
+//! `TdReportV15::check_info_hashes()` checks them against the sections, e.g.,
+//! to catch corrupted or truncated reports early.
+//!
+//! Reports retrieved over caller-provided `report_data` are wrapped in
+//! `CollectedEvidence`, which also checks that their `REPORTDATA` field
+//! echoes the requested data, e.g., to catch a device or driver that returned
+//! the report of another request.
+//!
+//! The `render` submodule renders reports and quotes as annotated text for
+//! humans, and the `diff` submodule compares two of them field by field.
+//!
+//! # Notes
+//! - The module is currently designed to work specifically with Intel TDX 1.5 devices.
+//! - The `TDREPORT` structure and its substructures are based on the TDX 1.5 specification.
+//! - The module is `no_std` compatible (see the `core` module).
+
+pub mod diff;
+pub mod render;
+
+use crate::core::ct;
+use crate::core::quote::TD_ATTRIBUTES_DEBUG;
+use crate::core::register::MeasurementRegister;
+use crate::core::{Error, Result};
+
+use alloc::string::ToString;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sha2::{Digest, Sha384};
+
+/// The length of the `report_data` field in the TDX report.
+pub const TDX_REPORT_DATA_LEN: usize = 64_usize;
+
+/// The length of the TDX measurement registers.
+pub const TDX_MR_REG_LEN: usize = 48_usize;
+
+/// The `SERVTD_HASH` of a TD with no bound service TDs.
+pub const NO_SERVTD_HASH: MeasurementRegister = MeasurementRegister::ZERO;
+
+/// The length of the `REPORTMACSTRUCT` at the start of the `TDREPORT`.
+pub const REPORT_MAC_STRUCT_LEN: usize = 256_usize;
+
+// constants for report struct sizes
+const TEE_TCB_INFO_LEN: usize = 239_usize;
+const TDREPORT_RESERVED_LEN: usize = 17_usize;
+const TD_INFO_LEN: usize = 512_usize;
+
+/// The length of the raw `TDREPORT` (1024 bytes).
+pub const TDREPORT_LEN: usize =
+    REPORT_MAC_STRUCT_LEN + TEE_TCB_INFO_LEN + TDREPORT_RESERVED_LEN + TD_INFO_LEN;
+
+// The length of a TDREPORT request
+const TDREPORT_REQ_LEN: usize = TDX_REPORT_DATA_LEN + TDREPORT_LEN;
+
+/// A trait that defines a method for populating a structure from raw bytes.
+/// All TDX attestation-related data structures should implement this trait.
+trait BinaryBlob {
+    /// Populates the structure from a slice of raw bytes.
+    fn populate_from_bytes(&mut self, raw_bytes: &[u8]) -> Result<()>;
+
+    /// Writes the structure into a slice of raw bytes of the structure's
+    /// length, using the same layout as `populate_from_bytes()`.
+    fn write_to_bytes(&self, raw_bytes: &mut [u8]);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ReportMacStruct {
+    //
+    //   Struct REPORTMACSTRUCT's layout:
+    //   offset, len
+    //   0x0,    0x8     report_type
+    //   0x8,    0x8     reserverd1
+    //   0x10,   0x10    cpusvn
+    //   0x20,   0x30    tee_tcb_info_hash
+    //   0x50,   0x30    tee_info_hash
+    //   0x80,   0x40    report_data
+    //   0xc0,   0x20    reserverd2
+    //   0xe0,   0x20    mac
+    //
+    report_type: [u8; 8], // [8 bytes]
+    reserved1: [u8; 8],   // [8 bytes]
+    cpusvn: [u8; 16],     // [16 bytes]
+    #[serde(with = "BigArray")]
+    tee_tcb_info_hash: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    tee_info_hash: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    report_data: [u8; 64], // [64 bytes]
+    reserved2: [u8; 32],  // [32 bytes]
+    mac: [u8; 32],        // [32 bytes]
+}
+
+impl ReportMacStruct {
+    fn new() -> ReportMacStruct {
+        ReportMacStruct {
+            report_type: [0; 8],
+            reserved1: [0; 8],
+            cpusvn: [0; 16],
+            tee_tcb_info_hash: [0; 48],
+            tee_info_hash: [0; 48],
+            report_data: [0; 64],
+            reserved2: [0; 32],
+            mac: [0; 32],
+        }
+    }
+}
+
+impl BinaryBlob for ReportMacStruct {
+    fn populate_from_bytes(&mut self, raw_bytes: &[u8]) -> Result<()> {
+        if raw_bytes.len() != REPORT_MAC_STRUCT_LEN {
+            return Err(Error::ParseError(
+                "ReportMacStruct length is wrong".to_string(),
+            ));
+        }
+
+        // copy the bytes into the struct
+        let mut offset: usize = 0;
+        self.report_type.copy_from_slice(&raw_bytes[offset..8]);
+        offset += 8;
+        self.reserved1
+            .copy_from_slice(&raw_bytes[offset..offset + 8]);
+        offset += 8;
+        self.cpusvn.copy_from_slice(&raw_bytes[offset..offset + 16]);
+        offset += 16;
+        self.tee_tcb_info_hash
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.tee_info_hash
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.report_data
+            .copy_from_slice(&raw_bytes[offset..offset + TDX_REPORT_DATA_LEN]);
+        offset += TDX_REPORT_DATA_LEN;
+        self.reserved2
+            .copy_from_slice(&raw_bytes[offset..offset + 32]);
+        offset += 32;
+        self.mac.copy_from_slice(&raw_bytes[offset..offset + 32]);
+
+        Ok(())
+    }
+
+    fn write_to_bytes(&self, raw_bytes: &mut [u8]) {
+        let mut offset: usize = 0;
+        raw_bytes[offset..8].copy_from_slice(&self.report_type);
+        offset += 8;
+        raw_bytes[offset..offset + 8].copy_from_slice(&self.reserved1);
+        offset += 8;
+        raw_bytes[offset..offset + 16].copy_from_slice(&self.cpusvn);
+        offset += 16;
+        raw_bytes[offset..offset + 48].copy_from_slice(&self.tee_tcb_info_hash);
+        offset += 48;
+        raw_bytes[offset..offset + 48].copy_from_slice(&self.tee_info_hash);
+        offset += 48;
+        raw_bytes[offset..offset + TDX_REPORT_DATA_LEN].copy_from_slice(&self.report_data);
+        offset += TDX_REPORT_DATA_LEN;
+        raw_bytes[offset..offset + 32].copy_from_slice(&self.reserved2);
+        offset += 32;
+        raw_bytes[offset..offset + 32].copy_from_slice(&self.mac);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TeeTcbInfo {
+    //
+    //   Struct TEE_TCB_INFO's layout:
+    //   offset, len
+    //   0x0,    0x8     valid
+    //   0x8,    0x10    tee_tcb_svn
+    //   0x18,   0x30    mrseam
+    //   0x48,   0x30    mrsignerseam
+    //   0x78,   0x8     attributes (set to all 0s)
+    //   0x80,   0x10    tee_tcb_svn2
+    //   0x90,   0x5f    reserverd
+    //
+    valid: [u8; 8],        // [8 bytes]
+    tee_tcb_svn: [u8; 16], // [16 bytes]
+    #[serde(with = "BigArray")]
+    mrseam: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    mrsignerseam: [u8; 48], // [48 bytes]
+    attributes: [u8; 8],   // [8 bytes]
+    tee_tcb_svn2: [u8; 16], // [16 bytes]
+    #[serde(with = "BigArray")]
+    reserved: [u8; 95], // [95 bytes]
+}
+
+impl TeeTcbInfo {
+    fn new() -> TeeTcbInfo {
+        TeeTcbInfo {
+            valid: [0; 8],
+            tee_tcb_svn: [0; 16],
+            mrseam: [0; 48],
+            mrsignerseam: [0; 48],
+            attributes: [0; 8],
+            tee_tcb_svn2: [0; 16],
+            reserved: [0; 95],
+        }
+    }
+}
+
+impl BinaryBlob for TeeTcbInfo {
+    fn populate_from_bytes(&mut self, raw_bytes: &[u8]) -> Result<()> {
+        if raw_bytes.len() != TEE_TCB_INFO_LEN {
+            return Err(Error::ParseError("TeeTcbInfo length is wrong".to_string()));
+        }
+
+        // copy the bytes into the struct
+        let mut offset: usize = 0;
+        self.valid.copy_from_slice(&raw_bytes[offset..8]);
+        offset += 8;
+        self.tee_tcb_svn
+            .copy_from_slice(&raw_bytes[offset..offset + 16]);
+        offset += 16;
+        self.mrseam.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.mrsignerseam
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.attributes
+            .copy_from_slice(&raw_bytes[offset..offset + 8]);
+        offset += 8;
+        self.tee_tcb_svn2
+            .copy_from_slice(&raw_bytes[offset..offset + 16]);
+        offset += 16;
+        self.reserved
+            .copy_from_slice(&raw_bytes[offset..offset + 95]);
+
+        Ok(())
+    }
+
+    fn write_to_bytes(&self, raw_bytes: &mut [u8]) {
+        let mut offset: usize = 0;
+        raw_bytes[offset..8].copy_from_slice(&self.valid);
+        offset += 8;
+        raw_bytes[offset..offset + 16].copy_from_slice(&self.tee_tcb_svn);
+        offset += 16;
+        raw_bytes[offset..offset + 48].copy_from_slice(&self.mrseam);
+        offset += 48;
+        raw_bytes[offset..offset + 48].copy_from_slice(&self.mrsignerseam);
+        offset += 48;
+        raw_bytes[offset..offset + 8].copy_from_slice(&self.attributes);
+        offset += 8;
+        raw_bytes[offset..offset + 16].copy_from_slice(&self.tee_tcb_svn2);
+        offset += 16;
+        raw_bytes[offset..offset + 95].copy_from_slice(&self.reserved);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TdInfo {
+    //
+    //   Struct TDINFO's layout:
+    //   offset, len
+    //   0x0,     0x8     attributes
+    //   0x8,     0x8     xfam
+    //   0x10,    0x30    mrtd
+    //   0x40,    0x30    mrconfigid
+    //   0x70,    0x30    mrowner
+    //   0xa0,    0x30    mrownerconfig
+    //   0xd0,    0x30    rtmr0
+    //   0x100,   0x30    rtmr1
+    //   0x130,   0x30    rtmr2
+    //   0x160,   0x30    rtmr3
+    //   0x190,   0x30    servtd_hash
+    //   0x1c0,   0x40    reserved
+    //
+    attributes: [u8; 8], // [8 bytes]
+    xfam: [u8; 8],       // [8 bytes]
+    #[serde(with = "BigArray")]
+    mrtd: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    mrconfigid: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    mrowner: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    mrownerconfig: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    rtmr0: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    rtmr1: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    rtmr2: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    rtmr3: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    servtd_hash: [u8; 48], // [48 bytes]
+    #[serde(with = "BigArray")]
+    reserved: [u8; 64], // [64 bytes]
+}
+
+impl TdInfo {
+    fn new() -> TdInfo {
+        TdInfo {
+            attributes: [0; 8],
+            xfam: [0; 8],
+            mrtd: [0; 48],
+            mrconfigid: [0; 48],
+            mrowner: [0; 48],
+            mrownerconfig: [0; 48],
+            rtmr0: [0; 48],
+            rtmr1: [0; 48],
+            rtmr2: [0; 48],
+            rtmr3: [0; 48],
+            servtd_hash: [0; 48],
+            reserved: [0; 64],
+        }
+    }
+}
+
+impl BinaryBlob for TdInfo {
+    fn populate_from_bytes(&mut self, raw_bytes: &[u8]) -> Result<()> {
+        if raw_bytes.len() != TD_INFO_LEN {
+            return Err(Error::ParseError("TdInfo length is wrong".to_string()));
+        }
+
+        // copy the bytes into the struct
+        let mut offset: usize = 0;
+        self.attributes.copy_from_slice(&raw_bytes[offset..8]);
+        offset += 8;
+        self.xfam.copy_from_slice(&raw_bytes[offset..offset + 8]);
+        offset += 8;
+        self.mrtd.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.mrconfigid
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.mrowner
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.mrownerconfig
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.rtmr0.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.rtmr1.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.rtmr2.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.rtmr3.copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.servtd_hash
+            .copy_from_slice(&raw_bytes[offset..offset + 48]);
+        offset += 48;
+        self.reserved
+            .copy_from_slice(&raw_bytes[offset..offset + 64]);
+
+        Ok(())
+    }
+
+    fn write_to_bytes(&self, raw_bytes: &mut [u8]) {
+        let mut offset: usize = 0;
+        raw_bytes[offset..8].copy_from_slice(&self.attributes);
+        offset += 8;
+        raw_bytes[offset..offset + 8].copy_from_slice(&self.xfam);
+        offset += 8;
+        for field in [
+            &self.mrtd,
+            &self.mrconfigid,
+            &self.mrowner,
+            &self.mrownerconfig,
+            &self.rtmr0,
+            &self.rtmr1,
+            &self.rtmr2,
+            &self.rtmr3,
+            &self.servtd_hash,
+        ] {
+            raw_bytes[offset..offset + 48].copy_from_slice(field);
+            offset += 48;
+        }
+        raw_bytes[offset..offset + 64].copy_from_slice(&self.reserved);
+    }
+}
+
+/// Represents the full `TDREPORT` structure, which includes the internal
+/// `ReportMacStruct`, `TeeTcbInfo`, `TdInfo` structs and reserved fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TdReportV15 {
+    //
+    //   Struct TDREPORT's layout:
+    //   offset, len
+    //   0x0,     0x100   ReportMacStruct
+    //   0x100,   0xef    TeeTcbInfo
+    //   0x1ef,   0x11    Reserved
+    //   0x200,   0x200   TdInfo
+    //
+    report_mac_struct: ReportMacStruct,    // [256 bytes]
+    tee_tcb_info: TeeTcbInfo,              // [239 bytes]
+    reserved: [u8; TDREPORT_RESERVED_LEN], // [17 bytes]
+    td_info: TdInfo,                       // [512 bytes]
+}
+
+impl BinaryBlob for TdReportV15 {
+    /// Populates the `TdReportV15` structure from a slice of raw bytes.
+    fn populate_from_bytes(&mut self, raw_bytes: &[u8]) -> Result<()> {
+        if raw_bytes.len() != TDREPORT_LEN {
+            return Err(Error::ParseError("TdReport length is wrong".to_string()));
+        }
+
+        // copy the bytes into the struct
+        let mut offset: usize = 0;
+        self.report_mac_struct
+            .populate_from_bytes(&raw_bytes[offset..REPORT_MAC_STRUCT_LEN])?;
+        offset += REPORT_MAC_STRUCT_LEN;
+        self.tee_tcb_info
+            .populate_from_bytes(&raw_bytes[offset..offset + TEE_TCB_INFO_LEN])?;
+        offset += TEE_TCB_INFO_LEN;
+        self.reserved
+            .copy_from_slice(&raw_bytes[offset..offset + TDREPORT_RESERVED_LEN]);
+        offset += TDREPORT_RESERVED_LEN;
+        self.td_info
+            .populate_from_bytes(&raw_bytes[offset..offset + TD_INFO_LEN])?;
+
+        Ok(())
+    }
+
+    /// Writes the `TdReportV15` structure into a slice of raw bytes.
+    fn write_to_bytes(&self, raw_bytes: &mut [u8]) {
+        let mut offset: usize = 0;
+        self.report_mac_struct
+            .write_to_bytes(&mut raw_bytes[offset..REPORT_MAC_STRUCT_LEN]);
+        offset += REPORT_MAC_STRUCT_LEN;
+        self.tee_tcb_info
+            .write_to_bytes(&mut raw_bytes[offset..offset + TEE_TCB_INFO_LEN]);
+        offset += TEE_TCB_INFO_LEN;
+        raw_bytes[offset..offset + TDREPORT_RESERVED_LEN].copy_from_slice(&self.reserved);
+        offset += TDREPORT_RESERVED_LEN;
+        self.td_info
+            .write_to_bytes(&mut raw_bytes[offset..offset + TD_INFO_LEN]);
+    }
+}
+
+impl Default for TdReportV15 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TdReportV15 {
+    /// Creates a new `TdReportV15` instance with default values.
+    pub fn new() -> TdReportV15 {
+        TdReportV15 {
+            report_mac_struct: ReportMacStruct::new(),
+            tee_tcb_info: TeeTcbInfo::new(),
+            reserved: [0; TDREPORT_RESERVED_LEN],
+            td_info: TdInfo::new(),
+        }
+    }
+
+    /// Creates a request for retrieving a TDX report from the CPU.
+    pub fn create_request(report_data: &[u8; TDX_REPORT_DATA_LEN]) -> [u8; TDREPORT_REQ_LEN] {
+        let mut req: [u8; TDREPORT_REQ_LEN] = [0; TDREPORT_REQ_LEN];
+        req[..TDX_REPORT_DATA_LEN].copy_from_slice(report_data);
+
+        //println!("sending report request (len={}): {:?}", raw_req.len(), raw_req);
+        req
+    }
+
+    /// Creates a new `TdReportV15` instance from raw bytes.
+    pub fn get_tdreport_from_bytes(raw_bytes: &[u8; TDREPORT_REQ_LEN]) -> Result<TdReportV15> {
+        Self::from_bytes(&raw_bytes[TDX_REPORT_DATA_LEN..])
+    }
+
+    /// Creates a new `TdReportV15` instance from a raw 1024-byte `TDREPORT`
+    /// (e.g., one received from another TD, which must not be trusted).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if `raw_bytes` isn't 1024 bytes long.
+    pub fn from_bytes(raw_bytes: &[u8]) -> Result<TdReportV15> {
+        let mut tdreport = TdReportV15::new();
+        tdreport.populate_from_bytes(raw_bytes)?;
+        Ok(tdreport)
+    }
+
+    /// Returns a builder for a `TdReportV15` with all-zero fields.
+    pub fn builder() -> TdReportBuilder {
+        TdReportBuilder::new()
+    }
+
+    /// Serializes the report into the raw 1024-byte `TDREPORT` layout, which
+    /// `from_bytes()` parses.
+    pub fn to_bytes(&self) -> [u8; TDREPORT_LEN] {
+        let mut raw_bytes = [0; TDREPORT_LEN];
+        self.write_to_bytes(&mut raw_bytes);
+        raw_bytes
+    }
+
+    /// Serializes the report's `REPORTMACSTRUCT`, which the TDX module
+    /// verifies the MAC of (see `TdxDeviceKvmV15::verify_report_raw()`).
+    pub fn report_mac_struct_bytes(&self) -> [u8; REPORT_MAC_STRUCT_LEN] {
+        let mut raw_bytes = [0; REPORT_MAC_STRUCT_LEN];
+        self.report_mac_struct.write_to_bytes(&mut raw_bytes);
+        raw_bytes
+    }
+
+    /// Returns the `REPORTDATA` field from the TDX report, which is the
+    /// 64-byte user data the report was requested with.
+    pub fn get_report_data(&self) -> [u8; TDX_REPORT_DATA_LEN] {
+        self.report_mac_struct.report_data
+    }
+
+    /// Returns the `ATTRIBUTES` field from the TDX report, which holds the
+    /// TD's attributes (e.g., whether it's a debug TD).
+    pub fn get_td_attributes(&self) -> [u8; 8] {
+        self.td_info.attributes
+    }
+
+    /// Returns the `XFAM` field from the TDX report, which holds the
+    /// extended CPU features enabled for the TD.
+    pub fn get_xfam(&self) -> [u8; 8] {
+        self.td_info.xfam
+    }
+
+    /// Returns the `MRTD` field from the TDX report, which is a 48-byte
+    /// SHA-3 hash of the TD memory and configuration.
+    pub fn get_mrtd(&self) -> MeasurementRegister {
+        self.td_info.mrtd.into()
+    }
+
+    /// Returns the `MRCONFIGID` field from the TDX report, which is a 48-byte
+    /// software-defined ID for non-owner-defined configuration of the TD
+    /// (e.g., run-time or OS configuration), provided by the host at TD
+    /// creation.
+    pub fn get_mrconfigid(&self) -> MeasurementRegister {
+        self.td_info.mrconfigid.into()
+    }
+
+    /// Returns the `MROWNER` field from the TDX report, which is a 48-byte
+    /// software-defined ID for the TD's owner, provided by the host at TD
+    /// creation.
+    pub fn get_mrowner(&self) -> MeasurementRegister {
+        self.td_info.mrowner.into()
+    }
+
+    /// Returns the `MROWNERCONFIG` field from the TDX report, which is a
+    /// 48-byte software-defined ID for owner-defined configuration of the TD,
+    /// provided by the host at TD creation.
+    pub fn get_mrownerconfig(&self) -> MeasurementRegister {
+        self.td_info.mrownerconfig.into()
+    }
+
+    /// Returns the runtime measurement registers `RTMR[0..3]` from the TDX
+    /// report, which hold 48-byte SHA-384 digests extended by the TD's
+    /// firmware, bootloader, kernel and workload.
+    pub fn get_rtmrs(&self) -> [MeasurementRegister; 4] {
+        [
+            self.td_info.rtmr0.into(),
+            self.td_info.rtmr1.into(),
+            self.td_info.rtmr2.into(),
+            self.td_info.rtmr3.into(),
+        ]
+    }
+
+    /// Returns the `SERVTD_HASH` field from the TDX report, which is the
+    /// 48-byte SHA-384 hash of the `TDINFO` structures of the service TDs
+    /// (e.g., a migration TD, or a paravisor in a partitioned TD) bound to
+    /// the TD by the host, or `NO_SERVTD_HASH` if none are bound.
+    ///
+    /// Service TDs can access the TD's state (e.g., to migrate it), so
+    /// verifiers of TDs with bound service TDs must also check that the
+    /// service TDs are the expected ones. The hash is reported as
+    /// `MRSERVICETD` in TD quotes with a TDX 1.5 body.
+    pub fn get_servtd_hash(&self) -> MeasurementRegister {
+        self.td_info.servtd_hash.into()
+    }
+
+    /// Returns whether any service TDs are bound to the TD, i.e., whether
+    /// its `SERVTD_HASH` is non-zero.
+    pub fn is_servtd_bound(&self) -> bool {
+        self.td_info.servtd_hash != NO_SERVTD_HASH
+    }
+
+    /// Returns whether the TD is a debug TD, whose memory and state are
+    /// accessible to the host.
+    pub fn is_debug(&self) -> bool {
+        self.td_info.attributes[0] & TD_ATTRIBUTES_DEBUG != 0
+    }
+
+    /// Computes the SHA-384 digests of the report's `TEE_TCB_INFO` and
+    /// `TDINFO` sections, which the TDX module reports as the
+    /// `TEE_TCB_INFO_HASH` and `TEE_INFO_HASH` fields.
+    pub fn compute_info_hashes(&self) -> ([u8; TDX_MR_REG_LEN], [u8; TDX_MR_REG_LEN]) {
+        let mut tee_tcb_info = [0; TEE_TCB_INFO_LEN];
+        self.tee_tcb_info.write_to_bytes(&mut tee_tcb_info);
+        let mut td_info = [0; TD_INFO_LEN];
+        self.td_info.write_to_bytes(&mut td_info);
+        (
+            Sha384::digest(tee_tcb_info).into(),
+            Sha384::digest(td_info).into(),
+        )
+    }
+
+    /// Checks that the report's `TEE_TCB_INFO_HASH` and `TEE_INFO_HASH`
+    /// fields match the digests of its `TEE_TCB_INFO` and `TDINFO` sections,
+    /// e.g., to catch corrupted or truncated reports retrieved from the TDX
+    /// guest device.
+    ///
+    /// The hashes are covered by the report's MAC, but the sections aren't:
+    /// this check is what binds the measurements to the MAC.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` naming the mismatching hash.
+    pub fn check_info_hashes(&self) -> Result<()> {
+        let (tee_tcb_info_hash, tee_info_hash) = self.compute_info_hashes();
+        if !ct::eq(
+            &tee_tcb_info_hash,
+            &self.report_mac_struct.tee_tcb_info_hash,
+        ) {
+            return Err(Error::ParseError(
+                "TEE_TCB_INFO_HASH doesn't match the TeeTcbInfo".to_string(),
+            ));
+        }
+        if !ct::eq(&tee_info_hash, &self.report_mac_struct.tee_info_hash) {
+            return Err(Error::ParseError(
+                "TEE_INFO_HASH doesn't match the TdInfo".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A `TDREPORT` retrieved over caller-provided `report_data`, which is only
+/// constructed if the report's info hashes match its sections (see
+/// `TdReportV15::check_info_hashes()`), and its `REPORTDATA` field echoes
+/// the requested data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectedEvidence {
+    report: TdReportV15,
+}
+
+impl CollectedEvidence {
+    /// Checks that `report` was retrieved over `report_data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the report's info hashes don't match
+    /// its sections, or its `REPORTDATA` isn't `report_data`.
+    pub fn new(report: TdReportV15, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Self> {
+        report.check_info_hashes()?;
+        if !ct::eq(&report.get_report_data(), report_data) {
+            return Err(Error::ParseError(
+                "REPORTDATA doesn't match the requested report data".to_string(),
+            ));
+        }
+        Ok(Self { report })
+    }
+
+    /// Returns the report.
+    pub fn report(&self) -> &TdReportV15 {
+        &self.report
+    }
+
+    /// Returns the report data the report was retrieved over.
+    pub fn report_data(&self) -> [u8; TDX_REPORT_DATA_LEN] {
+        self.report.get_report_data()
+    }
+
+    /// Unwraps the report.
+    pub fn into_report(self) -> TdReportV15 {
+        self.report
+    }
+}
+
+/// A builder for `TdReportV15` structures, e.g., to simulate the reports of
+/// a TD in tests.
+///
+/// Fields that aren't set are all-zero. The built report's MAC isn't
+/// computed, so it won't pass verification by the TDX module.
+#[derive(Clone, Debug, Default)]
+pub struct TdReportBuilder {
+    report: TdReportV15,
+}
+
+impl TdReportBuilder {
+    /// Creates a builder for a report with all-zero fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `REPORTTYPE` field.
+    pub fn with_report_type(mut self, report_type: &[u8; 8]) -> Self {
+        self.report.report_mac_struct.report_type = *report_type;
+        self
+    }
+
+    /// Sets the `CPUSVN` field.
+    pub fn with_cpusvn(mut self, cpusvn: &[u8; 16]) -> Self {
+        self.report.report_mac_struct.cpusvn = *cpusvn;
+        self
+    }
+
+    /// Sets the `TEE_TCB_INFO_HASH` and `TEE_INFO_HASH` fields.
+    pub fn with_info_hashes(
+        mut self,
+        tee_tcb_info_hash: &[u8; TDX_MR_REG_LEN],
+        tee_info_hash: &[u8; TDX_MR_REG_LEN],
+    ) -> Self {
+        self.report.report_mac_struct.tee_tcb_info_hash = *tee_tcb_info_hash;
+        self.report.report_mac_struct.tee_info_hash = *tee_info_hash;
+        self
+    }
+
+    /// Sets the `TEE_TCB_INFO_HASH` and `TEE_INFO_HASH` fields to the
+    /// digests of the report's sections as set so far, so that the built
+    /// report passes `TdReportV15::check_info_hashes()`.
+    ///
+    /// This must be called after the sections' fields are set.
+    pub fn with_computed_info_hashes(self) -> Self {
+        let (tee_tcb_info_hash, tee_info_hash) = self.report.compute_info_hashes();
+        self.with_info_hashes(&tee_tcb_info_hash, &tee_info_hash)
+    }
+
+    /// Sets the `REPORTDATA` field.
+    pub fn with_report_data(mut self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Self {
+        self.report.report_mac_struct.report_data = *report_data;
+        self
+    }
+
+    /// Sets the `MAC` field.
+    pub fn with_mac(mut self, mac: &[u8; 32]) -> Self {
+        self.report.report_mac_struct.mac = *mac;
+        self
+    }
+
+    /// Sets the `TEE_TCB_SVN` field.
+    pub fn with_tee_tcb_svn(mut self, tee_tcb_svn: &[u8; 16]) -> Self {
+        self.report.tee_tcb_info.tee_tcb_svn = *tee_tcb_svn;
+        self
+    }
+
+    /// Sets the `MRSEAM` and `MRSIGNERSEAM` fields, i.e., the measurement
+    /// and signer of the TDX module.
+    pub fn with_seam(
+        mut self,
+        mrseam: &[u8; TDX_MR_REG_LEN],
+        mrsignerseam: &[u8; TDX_MR_REG_LEN],
+    ) -> Self {
+        self.report.tee_tcb_info.mrseam = *mrseam;
+        self.report.tee_tcb_info.mrsignerseam = *mrsignerseam;
+        self
+    }
+
+    /// Sets the TD's `ATTRIBUTES` and `XFAM` fields.
+    pub fn with_td_attributes(mut self, attributes: &[u8; 8], xfam: &[u8; 8]) -> Self {
+        self.report.td_info.attributes = *attributes;
+        self.report.td_info.xfam = *xfam;
+        self
+    }
+
+    /// Sets the `MRTD` field.
+    pub fn with_mrtd(mut self, mrtd: &[u8; TDX_MR_REG_LEN]) -> Self {
+        self.report.td_info.mrtd = *mrtd;
+        self
+    }
+
+    /// Sets the `MRCONFIGID` field.
+    pub fn with_mrconfigid(mut self, mrconfigid: &[u8; TDX_MR_REG_LEN]) -> Self {
+        self.report.td_info.mrconfigid = *mrconfigid;
+        self
+    }
+
+    /// Sets the `MROWNER` field.
+    pub fn with_mrowner(mut self, mrowner: &[u8; TDX_MR_REG_LEN]) -> Self {
+        self.report.td_info.mrowner = *mrowner;
+        self
+    }
+
+    /// Sets the `MROWNERCONFIG` field.
+    pub fn with_mrownerconfig(mut self, mrownerconfig: &[u8; TDX_MR_REG_LEN]) -> Self {
+        self.report.td_info.mrownerconfig = *mrownerconfig;
+        self
+    }
+
+    /// Sets the runtime measurement registers `RTMR[0..3]`.
+    pub fn with_rtmrs(mut self, rtmrs: &[[u8; TDX_MR_REG_LEN]; 4]) -> Self {
+        let td_info = &mut self.report.td_info;
+        [td_info.rtmr0, td_info.rtmr1, td_info.rtmr2, td_info.rtmr3] = *rtmrs;
+        self
+    }
+
+    /// Sets the `SERVTD_HASH` field.
+    pub fn with_servtd_hash(mut self, servtd_hash: &[u8; TDX_MR_REG_LEN]) -> Self {
+        self.report.td_info.servtd_hash = *servtd_hash;
+        self
+    }
+
+    /// Builds the report.
+    pub fn build(self) -> TdReportV15 {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::array::uniform;
+    use proptest::prelude::*;
+    use rand::prelude::SliceRandom;
+
+    #[test]
+    fn test_create_request() -> Result<()> {
+        let report_data: [u8; TDX_REPORT_DATA_LEN] = [1; TDX_REPORT_DATA_LEN];
+
+        let request = TdReportV15::create_request(&report_data);
+
+        assert!(request[0..TDX_REPORT_DATA_LEN] == [1; TDX_REPORT_DATA_LEN]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collected_evidence() -> Result<()> {
+        let report = TdReportV15::builder()
+            .with_report_data(&[1; TDX_REPORT_DATA_LEN])
+            .with_computed_info_hashes()
+            .build();
+
+        let evidence = CollectedEvidence::new(report, &[1; TDX_REPORT_DATA_LEN])?;
+        assert_eq!(evidence.report_data(), [1; TDX_REPORT_DATA_LEN]);
+        assert_eq!(evidence.into_report(), report);
+
+        // the report of another request
+        assert!(matches!(
+            CollectedEvidence::new(report, &[2; TDX_REPORT_DATA_LEN]),
+            Err(Error::ParseError(_))
+        ));
+
+        // a corrupted report
+        let report = TdReportV15::builder()
+            .with_report_data(&[1; TDX_REPORT_DATA_LEN])
+            .build();
+        assert!(CollectedEvidence::new(report, &[1; TDX_REPORT_DATA_LEN]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tdreport_from_bytes() -> Result<()> {
+        let mut rng = rand::rng();
+        let mut rand_bytes: Vec<u8> = (0..127).collect();
+        rand_bytes.resize(TDREPORT_REQ_LEN, 0);
+        rand_bytes.shuffle(&mut rng);
+
+        let rand_req: [u8; TDREPORT_REQ_LEN] = rand_bytes.try_into().unwrap();
+
+        // this should not throw an error
+        match TdReportV15::get_tdreport_from_bytes(&rand_req) {
+            Ok(_r) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() -> Result<()> {
+        let mut raw = [0u8; TDREPORT_LEN];
+        raw[0x200 + 0x10..0x200 + 0x40].fill(0xaa);
+
+        let tdreport = TdReportV15::from_bytes(&raw)?;
+        assert_eq!(tdreport.get_mrtd(), [0xaa; TDX_MR_REG_LEN]);
+
+        for len in [0, TDREPORT_LEN - 1, TDREPORT_LEN + 1, TDREPORT_REQ_LEN] {
+            assert!(TdReportV15::from_bytes(&vec![0; len]).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tdreport_from_bytes_wrong_size() -> Result<()> {
+        let mut tdreport = TdReportV15::new();
+
+        let mut rng = rand::rng();
+        let mut rand_bytes: Vec<u8> = (0..127).collect();
+        rand_bytes.shuffle(&mut rng);
+
+        match tdreport.populate_from_bytes(&rand_bytes) {
+            Err(e) => match e {
+                Error::ParseError(_) => {
+                    println!("{}", e);
+                    Ok(())
+                }
+                // any other error is unexpected
+                _ => Err(e),
+            },
+            _ => Err(Error::NotSupported(
+                "Wrong buffer size should throw an error".to_string(),
+            )),
+        }
+    }
+
+    #[test]
+    fn test_check_info_hashes() -> Result<()> {
+        let report = TdReportBuilder::new()
+            .with_tee_tcb_svn(&[5; 16])
+            .with_mrtd(&[1; TDX_MR_REG_LEN])
+            .with_computed_info_hashes()
+            .build();
+        report.check_info_hashes()?;
+
+        // the builder's reports have all-zero hashes by default
+        assert!(TdReportV15::new().check_info_hashes().is_err());
+
+        // a corrupted TEE_TCB_INFO or TDINFO section is caught
+        for offset in [0x100 + 0x18, TD_INFO_OFFSET + 0x10, TD_INFO_OFFSET + 0x1c0] {
+            let mut raw = report.to_bytes();
+            raw[offset] ^= 0xff;
+            let corrupted = TdReportV15::from_bytes(&raw)?;
+            assert!(matches!(
+                corrupted.check_info_hashes(),
+                Err(Error::ParseError(_))
+            ));
+        }
+
+        // a corrupted hash, too
+        let mut raw = report.to_bytes();
+        raw[0x50] ^= 0xff;
+        assert!(TdReportV15::from_bytes(&raw)?.check_info_hashes().is_err());
+
+        assert_eq!(
+            report.report_mac_struct_bytes(),
+            report.to_bytes()[..REPORT_MAC_STRUCT_LEN]
+        );
+        Ok(())
+    }
+
+    // The offset of TDINFO in the TDREPORT
+    const TD_INFO_OFFSET: usize = 0x200;
+
+    proptest! {
+        #[test]
+        fn test_builder_round_trip(
+            report_data in uniform::<_, TDX_REPORT_DATA_LEN>(any::<u8>()),
+            cpusvn in uniform::<_, 16>(any::<u8>()),
+            tee_tcb_svn in uniform::<_, 16>(any::<u8>()),
+            mrseam in uniform::<_, TDX_MR_REG_LEN>(any::<u8>()),
+            attributes in uniform::<_, 8>(any::<u8>()),
+            mrs in uniform::<_, 4>(uniform::<_, TDX_MR_REG_LEN>(any::<u8>())),
+            rtmrs in uniform::<_, 4>(uniform::<_, TDX_MR_REG_LEN>(any::<u8>())),
+            servtd_hash in uniform::<_, TDX_MR_REG_LEN>(any::<u8>()),
+        ) {
+            let [mrtd, mrconfigid, mrowner, mrownerconfig] = mrs;
+            let report = TdReportV15::builder()
+                .with_report_data(&report_data)
+                .with_cpusvn(&cpusvn)
+                .with_tee_tcb_svn(&tee_tcb_svn)
+                .with_seam(&mrseam, &mrseam)
+                .with_td_attributes(&attributes, &attributes)
+                .with_mrtd(&mrtd)
+                .with_mrconfigid(&mrconfigid)
+                .with_mrowner(&mrowner)
+                .with_mrownerconfig(&mrownerconfig)
+                .with_rtmrs(&rtmrs)
+                .with_servtd_hash(&servtd_hash)
+                .build();
+
+            let raw = report.to_bytes();
+            let parsed = TdReportV15::from_bytes(&raw).unwrap();
+            prop_assert_eq!(parsed, report);
+            prop_assert_eq!(parsed.get_report_data(), report_data);
+            prop_assert_eq!(parsed.get_td_attributes(), attributes);
+            prop_assert_eq!(parsed.get_xfam(), attributes);
+            prop_assert_eq!(parsed.is_debug(), attributes[0] & 0x01 != 0);
+            prop_assert_eq!(parsed.get_mrtd(), mrtd);
+            prop_assert_eq!(parsed.get_mrconfigid(), mrconfigid);
+            prop_assert_eq!(parsed.get_mrowner(), mrowner);
+            prop_assert_eq!(parsed.get_mrownerconfig(), mrownerconfig);
+            prop_assert_eq!(parsed.get_rtmrs(), rtmrs);
+            prop_assert_eq!(parsed.get_servtd_hash(), servtd_hash);
+
+            // the fields are at their offsets in the TDX 1.5 layout
+            prop_assert_eq!(&raw[0x80..0xc0], &report_data[..]);
+            prop_assert_eq!(&raw[0x10..0x20], &cpusvn[..]);
+            prop_assert_eq!(&raw[0x108..0x118], &tee_tcb_svn[..]);
+            prop_assert_eq!(&raw[0x118..0x148], &mrseam[..]);
+            prop_assert_eq!(&raw[TD_INFO_OFFSET..TD_INFO_OFFSET + 8], &attributes[..]);
+            let mr_offsets = [0x10, 0x40, 0x70, 0xa0, 0xd0, 0x100, 0x130, 0x160, 0x190];
+            let mr_values = [mrs.as_slice(), rtmrs.as_slice(), &[servtd_hash]].concat();
+            for (offset, value) in mr_offsets.iter().zip(mr_values) {
+                let offset = TD_INFO_OFFSET + offset;
+                prop_assert_eq!(&raw[offset..offset + TDX_MR_REG_LEN], &value[..]);
+            }
+        }
+
+        #[test]
+        fn test_raw_round_trip(raw in uniform::<_, TDREPORT_LEN>(any::<u8>())) {
+            let report = TdReportV15::from_bytes(&raw).unwrap();
+            prop_assert_eq!(report.to_bytes(), raw);
+        }
+    }
+}
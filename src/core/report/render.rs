@@ -0,0 +1,432 @@
+//! # Human-Readable Report Rendering
+//!
+//! This module renders `TDREPORT`s and TD quotes as aligned, annotated text
+//! for humans (e.g., `tdx-attest report print --pretty`), instead of raw
+//! JSON dumps: each field is printed with its name and hex value, and
+//! annotated with its meaning where the value can be decoded, such as the
+//! TD attribute and XFAM bits, the components of `TEE_TCB_SVN`, and unset
+//! or all-zero registers.
+//!
+//! The rendering is meant for humans only: its layout may change between
+//! releases, so tools should parse the structures themselves.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::core::quote::Quote;
+//! use tdx_workload_attestation::core::report::render::render_quote;
+//!
+//! let quote = Quote::from_bytes(&std::fs::read("quote.bin").unwrap()).unwrap();
+//! print!("{}", render_quote(&quote));
+//! ```
+
+use crate::core::quote::{
+    CERT_DATA_PCK_CHAIN, CERT_DATA_PPID_CLEARTEXT, CERT_DATA_PPID_RSA2048, CERT_DATA_PPID_RSA3072,
+    Quote,
+};
+use crate::core::register::MeasurementRegister;
+use crate::core::report::{NO_SERVTD_HASH, TDX_MR_REG_LEN, TdReportV15};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+// The width of the field name column
+const NAME_WIDTH: usize = 17;
+
+// The number of bytes rendered per line of hex
+const HEX_LINE_LEN: usize = 48;
+
+// The names of the TD attribute bits (TDX module ABI 1.5)
+const TD_ATTRIBUTE_BITS: [(u32, &str); 6] = [
+    (0, "DEBUG"),
+    (28, "SEPT_VE_DISABLE"),
+    (29, "MIGRATABLE"),
+    (30, "PKS"),
+    (31, "KL"),
+    (63, "PERFMON"),
+];
+
+// The names of the XFAM bits (the XSAVE feature sets enabled for the TD)
+const XFAM_BITS: [(u32, &str); 16] = [
+    (0, "x87"),
+    (1, "SSE"),
+    (2, "AVX"),
+    (3, "MPX_BNDREGS"),
+    (4, "MPX_BNDCSR"),
+    (5, "AVX512_OPMASK"),
+    (6, "AVX512_ZMM_HI256"),
+    (7, "AVX512_HI16_ZMM"),
+    (8, "PT"),
+    (9, "PKRU"),
+    (11, "CET_U"),
+    (12, "CET_S"),
+    (14, "ULI"),
+    (15, "LBR"),
+    (17, "AMX_TILECFG"),
+    (18, "AMX_TILEDATA"),
+];
+
+// The meanings of the TEE_TCB_SVN components, which the other components
+// don't have
+pub(super) const TEE_TCB_SVN_COMPONENTS: [&str; 3] = [
+    "TDX module SVN",
+    "TDX module major version",
+    "TDX late microcode update SVN",
+];
+
+/// Returns the names of the bits set in the TD `attributes`, with unnamed
+/// bits as `bit<N>`.
+pub fn td_attribute_names(attributes: &[u8; 8]) -> Vec<String> {
+    bit_names(u64::from_le_bytes(*attributes), &TD_ATTRIBUTE_BITS)
+}
+
+/// Returns the names of the feature sets enabled in `xfam`, with unnamed
+/// bits as `bit<N>`.
+pub fn xfam_feature_names(xfam: &[u8; 8]) -> Vec<String> {
+    bit_names(u64::from_le_bytes(*xfam), &XFAM_BITS)
+}
+
+/// Renders a `TDREPORT` as annotated text.
+pub fn render_report(report: &TdReportV15) -> String {
+    let mac = &report.report_mac_struct;
+    let tcb = &report.tee_tcb_info;
+    let mut out = Renderer::default();
+
+    out.section("TDREPORT");
+    out.hex(
+        "REPORTTYPE",
+        &mac.report_type[..4],
+        Some(if mac.report_type[0] == 0x81 {
+            "TDX"
+        } else {
+            "unknown TEE type"
+        }),
+    );
+    out.hex("CPUSVN", &mac.cpusvn, None);
+    out.hex("TEE_TCB_INFO_HASH", &mac.tee_tcb_info_hash, None);
+    out.hex("TEE_INFO_HASH", &mac.tee_info_hash, None);
+    out.hex("REPORTDATA", &mac.report_data, zero_note(&mac.report_data));
+    out.hex("MAC", &mac.mac, None);
+
+    out.section("TEE TCB Info");
+    tee_tcb_svn(&mut out, "TEE_TCB_SVN", &tcb.tee_tcb_svn);
+    out.hex("MRSEAM", &tcb.mrseam, None);
+    out.hex(
+        "MRSIGNERSEAM",
+        &tcb.mrsignerseam,
+        Some(if tcb.mrsignerseam == [0; TDX_MR_REG_LEN] {
+            "Intel-signed TDX module"
+        } else {
+            "TDX module not signed by Intel"
+        }),
+    );
+    out.hex("SEAM ATTRIBUTES", &tcb.attributes, None);
+    if tcb.tee_tcb_svn2 != [0; 16] {
+        tee_tcb_svn(&mut out, "TEE_TCB_SVN2", &tcb.tee_tcb_svn2);
+    }
+
+    out.section("TD Info");
+    td_fields(
+        &mut out,
+        &report.get_td_attributes(),
+        &report.get_xfam(),
+        [
+            ("MRTD", &report.get_mrtd()),
+            ("MRCONFIGID", &report.get_mrconfigid()),
+            ("MROWNER", &report.get_mrowner()),
+            ("MROWNERCONFIG", &report.get_mrownerconfig()),
+        ],
+        &report.get_rtmrs(),
+    );
+    servtd_hash(&mut out, "SERVTD_HASH", &report.get_servtd_hash());
+    out.finish()
+}
+
+/// Renders a TD quote as annotated text.
+pub fn render_quote(quote: &Quote) -> String {
+    let body = &quote.body;
+    let mut out = Renderer::default();
+
+    out.section("Quote");
+    out.field(
+        "Version",
+        &format!("{}", quote.version),
+        Some(if body.mrservicetd.is_some() {
+            "TDX 1.5 body"
+        } else {
+            "TDX 1.0 body"
+        }),
+    );
+
+    out.section("TD Quote Body");
+    tee_tcb_svn(&mut out, "TEE_TCB_SVN", &body.tee_tcb_svn);
+    out.hex("MRSEAM", body.mrseam.as_ref(), None);
+    out.hex("MRSIGNERSEAM", body.mrsignerseam.as_ref(), None);
+    out.hex("SEAM ATTRIBUTES", &body.seam_attributes, None);
+    td_fields(
+        &mut out,
+        &body.td_attributes,
+        &body.xfam,
+        [
+            ("MRTD", &body.mrtd),
+            ("MRCONFIGID", &body.mrconfigid),
+            ("MROWNER", &body.mrowner),
+            ("MROWNERCONFIG", &body.mrownerconfig),
+        ],
+        &body.rtmrs,
+    );
+    out.hex(
+        "REPORTDATA",
+        &body.report_data,
+        zero_note(&body.report_data),
+    );
+    if let Some(tee_tcb_svn2) = &body.tee_tcb_svn2 {
+        tee_tcb_svn(&mut out, "TEE_TCB_SVN2", tee_tcb_svn2);
+    }
+    if let Some(mrservicetd) = &body.mrservicetd {
+        servtd_hash(&mut out, "MRSERVICETD", mrservicetd);
+    }
+
+    out.section("Signature");
+    out.hex("Signature", &quote.signature, None);
+    out.hex("Attestation key", &quote.attestation_key, None);
+    out.hex("QE auth data", &quote.qe_auth_data, None);
+    let cert_data = match quote.cert_data_type {
+        CERT_DATA_PPID_CLEARTEXT => String::from("PPID (cleartext)"),
+        CERT_DATA_PPID_RSA2048 => String::from("encrypted PPID (RSA-2048)"),
+        CERT_DATA_PPID_RSA3072 => String::from("encrypted PPID (RSA-3072)"),
+        CERT_DATA_PCK_CHAIN => match quote.pck_chain() {
+            Ok(chain) => format!("PCK certificate chain ({} certificates)", chain.len()),
+            Err(_) => String::from("malformed PCK certificate chain"),
+        },
+        _ => String::from("unknown"),
+    };
+    out.field(
+        "Cert data type",
+        &format!("{}", quote.cert_data_type),
+        Some(&cert_data),
+    );
+    out.finish()
+}
+
+/// A builder of the aligned text rendering.
+#[derive(Default)]
+struct Renderer {
+    out: String,
+}
+
+impl Renderer {
+    fn section(&mut self, title: &str) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out.push_str(title);
+        self.out.push('\n');
+    }
+
+    fn field(&mut self, name: &str, value: &str, note: Option<&str>) {
+        let _ = write!(
+            self.out,
+            "  {:<width$} : {}",
+            name,
+            value,
+            width = NAME_WIDTH
+        );
+        if let Some(note) = note {
+            let _ = write!(self.out, "  ({})", note);
+        }
+        self.out.push('\n');
+    }
+
+    /// Renders `value` in hex, wrapped across lines, with the note on the
+    /// first one.
+    fn hex(&mut self, name: &str, value: &[u8], note: Option<&str>) {
+        if value.is_empty() {
+            return self.field(name, "(empty)", note);
+        }
+        for (i, chunk) in value.chunks(HEX_LINE_LEN).enumerate() {
+            if i == 0 {
+                self.field(name, &hex(chunk), note);
+            } else {
+                self.field("", &hex(chunk), None);
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// Renders the TD's attributes, XFAM, static measurement registers and
+/// RTMRs.
+fn td_fields(
+    out: &mut Renderer,
+    attributes: &[u8; 8],
+    xfam: &[u8; 8],
+    registers: [(&str, &MeasurementRegister); 4],
+    rtmrs: &[MeasurementRegister; 4],
+) {
+    let names = td_attribute_names(attributes);
+    out.hex(
+        "ATTRIBUTES",
+        attributes,
+        Some(&if names.is_empty() {
+            String::from("no attributes set")
+        } else {
+            names.join(", ")
+        }),
+    );
+    out.hex("XFAM", xfam, Some(&xfam_feature_names(xfam).join(", ")));
+
+    for (name, value) in registers {
+        let note = if name == "MRTD" {
+            None
+        } else {
+            zero_note(value.as_ref()).or(Some("set by the host"))
+        };
+        out.hex(name, value.as_ref(), note);
+    }
+    for (i, rtmr) in rtmrs.iter().enumerate() {
+        out.hex(
+            &format!("RTMR{}", i),
+            rtmr.as_ref(),
+            zero_note(rtmr.as_ref()),
+        );
+    }
+}
+
+/// Renders a `TEE_TCB_SVN` with the meanings of its components.
+fn tee_tcb_svn(out: &mut Renderer, name: &str, svn: &[u8; 16]) {
+    let components: Vec<String> = TEE_TCB_SVN_COMPONENTS
+        .iter()
+        .zip(svn)
+        .map(|(meaning, value)| format!("{} {}", meaning, value))
+        .collect();
+    out.hex(name, svn, Some(&components.join(", ")));
+}
+
+/// Renders the hash of the service TDs bound to the TD.
+fn servtd_hash(out: &mut Renderer, name: &str, hash: &MeasurementRegister) {
+    out.hex(
+        name,
+        hash.as_ref(),
+        Some(if *hash == NO_SERVTD_HASH {
+            "no bound service TDs"
+        } else {
+            "service TDs are bound"
+        }),
+    );
+}
+
+/// Returns a note for all-zero values.
+fn zero_note(value: &[u8]) -> Option<&'static str> {
+    value.iter().all(|b| *b == 0).then_some("all zero")
+}
+
+/// Returns the names of the bits set in `value`.
+fn bit_names(value: u64, names: &[(u32, &str)]) -> Vec<String> {
+    (0..64)
+        .filter(|bit| value & (1 << bit) != 0)
+        .map(|bit| match names.iter().find(|(b, _)| *b == bit) {
+            Some((_, name)) => String::from(*name),
+            None => format!("bit{}", bit),
+        })
+        .collect()
+}
+
+/// Encodes `bytes` in lowercase hex.
+pub(super) fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(hex, "{:02x}", b);
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::core::report::TdReportBuilder;
+
+    #[test]
+    fn test_bit_names() {
+        assert_eq!(
+            td_attribute_names(&[0x01, 0, 0, 0x10, 0, 0, 0, 0x80]),
+            ["DEBUG", "SEPT_VE_DISABLE", "PERFMON"]
+        );
+        assert_eq!(td_attribute_names(&[0x02, 0, 0, 0, 0, 0, 0, 0]), ["bit1"]);
+        assert!(td_attribute_names(&[0; 8]).is_empty());
+        assert_eq!(
+            xfam_feature_names(&[0xe7, 0x02, 0x06, 0, 0, 0, 0, 0]),
+            [
+                "x87",
+                "SSE",
+                "AVX",
+                "AVX512_OPMASK",
+                "AVX512_ZMM_HI256",
+                "AVX512_HI16_ZMM",
+                "PKRU",
+                "AMX_TILECFG",
+                "AMX_TILEDATA"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_report() {
+        let report = TdReportBuilder::new()
+            .with_report_type(&[0x81, 0, 0, 0, 0, 0, 0, 0])
+            .with_tee_tcb_svn(&[3, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+            .with_td_attributes(
+                &[0x01, 0, 0, 0x10, 0, 0, 0, 0],
+                &[0x03, 0, 0, 0, 0, 0, 0, 0],
+            )
+            .with_mrtd(&[0xab; TDX_MR_REG_LEN])
+            .build();
+        let text = render_report(&report);
+
+        assert!(text.starts_with("TDREPORT\n"));
+        assert!(text.contains("  REPORTTYPE        : 81000000  (TDX)\n"));
+        assert!(text.contains(
+            "(TDX module SVN 3, TDX module major version 1, TDX late microcode update SVN 2)"
+        ));
+        assert!(text.contains(&format!(
+            "  MRTD              : {}\n",
+            "ab".repeat(TDX_MR_REG_LEN)
+        )));
+        assert!(text.contains("(DEBUG, SEPT_VE_DISABLE)\n"));
+        assert!(text.contains("(x87, SSE)\n"));
+        assert!(text.contains("(no bound service TDs)\n"));
+
+        // 64-byte values are wrapped
+        let report_data = text
+            .lines()
+            .skip_while(|l| !l.contains("REPORTDATA"))
+            .take(2)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            report_data,
+            [
+                format!(
+                    "  REPORTDATA        : {}  (all zero)",
+                    "00".repeat(HEX_LINE_LEN)
+                ),
+                format!("                    : {}", "00".repeat(16)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_quote() {
+        let quote = Quote::from_bytes(&QuoteParts::default().assemble(&[6; 64], &[7; 64])).unwrap();
+        let text = render_quote(&quote);
+        assert!(text.starts_with("Quote\n  Version           : "));
+        assert!(text.contains("TD Quote Body\n"));
+        assert!(text.contains("  ATTRIBUTES        : 0000000000000000  (no attributes set)\n"));
+        assert!(text.contains("Cert data type"));
+    }
+}
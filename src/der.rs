@@ -0,0 +1,144 @@
+//! # DER Utilities
+//!
+//! This module provides the minimal DER reader and encoder shared by the
+//! modules that parse or build ASN.1 structures that OpenSSL doesn't expose
+//! (or without OpenSSL), such as RFC 3161 timestamp tokens (see the
+//! `verification::timestamp` module), the parameters of certificate
+//! signature algorithms (see the `verification::signature` module) and
+//! certificate extensions (e.g., the SGX extensions of PCK certificates, see
+//! the `evidence::tcb` module).
+
+use crate::error::{Error, Result};
+
+// The DER-encoded OIDs of the SHA-2 digest algorithms
+pub(crate) const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+pub(crate) const SHA384_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+pub(crate) const SHA512_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+
+// DER tags
+pub(crate) const TAG_BOOLEAN: u8 = 0x01;
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+pub(crate) const TAG_CONTEXT_0: u8 = 0xa0;
+pub(crate) const TAG_CONTEXT_1: u8 = 0xa1;
+pub(crate) const TAG_CONTEXT_2: u8 = 0xa2;
+pub(crate) const TAG_CONTEXT_3: u8 = 0xa3;
+
+/// A reader of consecutive DER TLVs.
+pub(crate) struct DerReader<'a> {
+    der: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(der: &'a [u8]) -> Self {
+        Self { der }
+    }
+
+    /// Returns whether all TLVs have been read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.der.is_empty()
+    }
+
+    /// Reads the next TLV, and returns its tag, value and encoding.
+    pub(crate) fn read_tlv(&mut self) -> Result<(u8, &'a [u8], &'a [u8])> {
+        let malformed = || Error::ParseError("Malformed DER encoding".to_string());
+
+        let tag = *self.der.first().ok_or_else(malformed)?;
+        let len = *self.der.get(1).ok_or_else(malformed)?;
+        let (header_len, value_len) = if len < 0x80 {
+            (2, len as usize)
+        } else {
+            // only lengths of up to 4 bytes are expected
+            let n = (len & 0x7f) as usize;
+            if n == 0 || n > 4 {
+                return Err(malformed());
+            }
+            let bytes = self.der.get(2..2 + n).ok_or_else(malformed)?;
+            (
+                2 + n,
+                bytes.iter().fold(0usize, |l, b| (l << 8) | *b as usize),
+            )
+        };
+
+        let end = header_len.checked_add(value_len).ok_or_else(malformed)?;
+        let encoding = self.der.get(..end).ok_or_else(malformed)?;
+        self.der = &self.der[end..];
+        Ok((tag, &encoding[header_len..], encoding))
+    }
+
+    /// Reads the next TLV's value, which must have the given tag.
+    pub(crate) fn read(&mut self, tag: u8) -> Result<&'a [u8]> {
+        match self.read_tlv()? {
+            (t, value, _) if t == tag => Ok(value),
+            (t, _, _) => Err(Error::ParseError(format!(
+                "Unexpected DER tag {:#04x} (expected {:#04x})",
+                t, tag
+            ))),
+        }
+    }
+
+    /// Reads the next TLV's value if it has the given tag.
+    pub(crate) fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        match self.der.first() {
+            Some(t) if *t == tag => self.read(tag).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads a sequence, and returns a reader of its elements.
+    pub(crate) fn read_sequence(&mut self) -> Result<Self> {
+        self.read(TAG_SEQUENCE).map(Self::new)
+    }
+}
+
+/// Returns the extensions of the DER-encoded X.509 certificate `der`, as the
+/// DER-encoded OID and the value of each.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the certificate is malformed.
+pub(crate) fn certificate_extensions(der: &[u8]) -> Result<Vec<(&[u8], &[u8])>> {
+    let mut certificate = DerReader::new(der).read_sequence()?;
+    let mut tbs = certificate.read_sequence()?;
+
+    // the extensions are the last (optional) field of the TBS certificate
+    let mut extensions = None;
+    while !tbs.is_empty() {
+        if let (TAG_CONTEXT_3, value, _) = tbs.read_tlv()? {
+            extensions = Some(value);
+        }
+    }
+    let Some(extensions) = extensions else {
+        return Ok(Vec::new());
+    };
+
+    let mut extensions = DerReader::new(extensions).read_sequence()?;
+    let mut parsed = Vec::new();
+    while !extensions.is_empty() {
+        let mut extension = extensions.read_sequence()?;
+        let oid = extension.read(TAG_OID)?;
+        extension.read_optional(TAG_BOOLEAN)?;
+        parsed.push((oid, extension.read(TAG_OCTET_STRING)?));
+    }
+    Ok(parsed)
+}
+
+/// Encodes a DER TLV.
+pub(crate) fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut der = vec![tag];
+    if value.len() < 0x80 {
+        der.push(value.len() as u8);
+    } else {
+        let len = value.len().to_be_bytes();
+        let len = &len[len.iter().position(|b| *b != 0).unwrap_or(0)..];
+        der.push(0x80 | len.len() as u8);
+        der.extend(len);
+    }
+    der.extend(value);
+    der
+}
@@ -7,13 +7,18 @@
 //! different kinds of errors that can occur in the application, such as I/O errors,
 //! parsing errors, serialization errors, and cryptographic verification errors.
 //!
+//! Each variant maps to a machine-readable `ErrorKind`, which library consumers
+//! can use to branch on failures programmatically without matching on (or
+//! parsing) error messages. Errors originating from underlying libraries (I/O,
+//! OpenSSL, protobuf) are preserved as the error's `source()`.
+//!
 //! The `Result` type alias simplifies function signatures by using the custom `Error` type
 //! as the error variant in `std::result::Result`.
 //!
 //! ## Example Usage
 //!
 //! ```
-//! use tdx_workload_attestation::error::{Error, Result};
+//! use tdx_workload_attestation::error::{Error, ErrorKind, Result};
 //!
 //! fn example_function() -> Result<()> {
 //!     Err(Error::NotSupported("This operation is not supported".to_string()))
@@ -21,28 +26,109 @@
 //!
 //! match example_function() {
 //!     Ok(_) => println!("Operation succeeded"),
-//!     Err(e) => eprintln!("Error occurred: {}", e),
+//!     Err(e) if e.is_not_supported() => println!("Skipping: {}", e),
+//!     Err(e) => eprintln!("Error occurred ({}): {}", e.kind(), e),
 //! }
+//!
+//! assert_eq!(example_function().unwrap_err().kind(), ErrorKind::NotSupported);
 //! ```
 
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
+/// A machine-readable classification of an `Error`.
+///
+/// Every `Error` variant maps to exactly one `ErrorKind`. The string codes
+/// returned by `ErrorKind::as_str()` are stable and suitable for logging or
+/// for exposing across process boundaries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// An I/O error.
+    Io,
+    /// An error related to network operations.
+    Network,
+    /// An operation or feature that is not supported.
+    NotSupported,
+    /// An error reported by OpenSSL.
+    OpenSsl,
+    /// Access to a resource (e.g., the TDX guest device) denied by its file
+    /// permissions.
+    PermissionDenied,
+    /// Access to a resource denied by a Linux Security Module (e.g., SELinux
+    /// or AppArmor).
+    LsmDenied,
+    /// An error reported by the protobuf runtime.
+    Protobuf,
+    /// An error that occurs during parsing of serialized data.
+    Parse,
+    /// An error related to quote generation or processing.
+    Quote,
+    /// A request rejected by a rate limiter, which may be retried later.
+    RateLimited,
+    /// An error that occurs during data serialization.
+    Serialization,
+    /// An error related to cryptographic signature verification.
+    Signature,
+    /// A general verification error.
+    Verification,
+}
+
+impl ErrorKind {
+    /// Returns the stable string code for this kind of error.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Io => "io",
+            ErrorKind::Network => "network",
+            ErrorKind::NotSupported => "not_supported",
+            ErrorKind::OpenSsl => "openssl",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::LsmDenied => "lsm_denied",
+            ErrorKind::Protobuf => "protobuf",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Quote => "quote",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Serialization => "serialization",
+            ErrorKind::Signature => "signature",
+            ErrorKind::Verification => "verification",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Represents the various errors that can occur in the application.
 ///
 /// # Variants
 ///
 /// - `IoError`: Represents an I/O error, wrapping a `std::io::Error`.
+/// - `NetworkError`: Represents an error related to network operations.
 /// - `NotSupported`: Represents an operation or feature that is not supported.
+/// - `OpenSslError`: Represents an OpenSSL error, wrapping an `openssl::error::ErrorStack`.
+/// - `CertificateError`: Represents an X.509 certificate that cannot be parsed or verified.
+/// - `PermissionDenied`: Represents access denied by a resource's file permissions.
+/// - `LsmDenied`: Represents access denied by a Linux Security Module.
+/// - `ProtobufError`: Represents a protobuf error, wrapping a `protobuf::Error`.
 /// - `ParseError`: Represents an error that occurs during parsing of serialized data.
 /// - `QuoteError`: Represents an error related to quote generation or processing.
+/// - `RateLimited`: Represents a request rejected by a rate limiter.
 /// - `SerializationError`: Represents an error that occurs during data serialization.
 /// - `SignatureError`: Represents an error related to cryptographic signature verification.
 /// - `VerificationError`: Represents a general verification error.
+///
+/// New variants may be added in minor releases, and the feature-gated
+/// variants only exist with their features, so matches on `Error` need a
+/// wildcard arm; branch on `Error::kind()` instead, where possible.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Represents an I/O error.
     ///
-    /// This variant wraps a `std::io::Error` and provides additional context.
+    /// This variant wraps a `std::io::Error` and preserves it as the source.
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -60,10 +146,48 @@ pub enum Error {
 
     /// Represents an OpenSSL error.
     ///
-    /// This variant wraps a `openssl::error::ErrorStack` and provides additional context.
+    /// This variant wraps a `openssl::error::ErrorStack` and preserves it as the source.
+    #[cfg(feature = "host-verification")]
     #[error("OpenSSL error: {0}")]
     OpenSslError(#[from] openssl::error::ErrorStack),
 
+    /// Represents an X.509 certificate that cannot be parsed, or whose key
+    /// or signature cannot be verified.
+    ///
+    /// This variant includes the kind of the failure (`ErrorKind::Parse`,
+    /// `ErrorKind::Signature` or `ErrorKind::Verification`), and wraps the
+    /// `openssl::error::ErrorStack` and preserves it as the source.
+    #[cfg(feature = "host-verification")]
+    #[error("X.509 certificate {kind} error: {source}")]
+    CertificateError {
+        kind: ErrorKind,
+        #[source]
+        source: openssl::error::ErrorStack,
+    },
+
+    /// Represents access to a resource (e.g., the TDX guest device) denied
+    /// by its file permissions.
+    ///
+    /// This variant includes a string describing the denial and how to
+    /// remediate it.
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// Represents access to a resource denied by a Linux Security Module
+    /// (e.g., SELinux or AppArmor), although its file permissions allow it.
+    ///
+    /// This variant includes a string describing the denial and how to
+    /// remediate it.
+    #[error("Denied by a Linux Security Module: {0}")]
+    LsmDenied(String),
+
+    /// Represents a protobuf error.
+    ///
+    /// This variant wraps a `protobuf::Error` and preserves it as the source.
+    #[cfg(any(feature = "host-gcp-tdx", feature = "proto"))]
+    #[error("Protobuf error: {0}")]
+    ProtobufError(#[from] protobuf::Error),
+
     /// Represents an error that occurs during parsing of serialized data.
     ///
     /// This variant includes a string describing the parsing error.
@@ -76,6 +200,12 @@ pub enum Error {
     #[error("Quote error: {0}")]
     QuoteError(String),
 
+    /// Represents a request rejected by a rate limiter.
+    ///
+    /// This variant includes the time after which the request may be retried.
+    #[error("Rate limited: retry after {}ms", .0.as_millis())]
+    RateLimited(Duration),
+
     /// Represents an error that occurs during data serialization.
     ///
     /// This variant includes a string describing the serialization error.
@@ -95,8 +225,146 @@ pub enum Error {
     VerificationError(String),
 }
 
+impl Error {
+    /// Returns the machine-readable kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::IoError(_) => ErrorKind::Io,
+            Error::NetworkError(_) => ErrorKind::Network,
+            Error::NotSupported(_) => ErrorKind::NotSupported,
+            #[cfg(feature = "host-verification")]
+            Error::OpenSslError(_) => ErrorKind::OpenSsl,
+            #[cfg(feature = "host-verification")]
+            Error::CertificateError { kind, .. } => *kind,
+            Error::PermissionDenied(_) => ErrorKind::PermissionDenied,
+            Error::LsmDenied(_) => ErrorKind::LsmDenied,
+            #[cfg(any(feature = "host-gcp-tdx", feature = "proto"))]
+            Error::ProtobufError(_) => ErrorKind::Protobuf,
+            Error::ParseError(_) => ErrorKind::Parse,
+            Error::QuoteError(_) => ErrorKind::Quote,
+            Error::RateLimited(_) => ErrorKind::RateLimited,
+            Error::SerializationError(_) => ErrorKind::Serialization,
+            Error::SignatureError(_) => ErrorKind::Signature,
+            Error::VerificationError(_) => ErrorKind::Verification,
+        }
+    }
+
+    /// Returns `true` if the operation or feature is not supported on this
+    /// platform, e.g., when running outside of a TDX guest.
+    pub fn is_not_supported(&self) -> bool {
+        self.kind() == ErrorKind::NotSupported
+    }
+
+    /// Returns `true` if the error was caused by a network operation.
+    pub fn is_network(&self) -> bool {
+        self.kind() == ErrorKind::Network
+    }
+
+    /// Returns `true` if access to a resource was denied, by its file
+    /// permissions or a Linux Security Module.
+    pub fn is_access_denied(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::PermissionDenied | ErrorKind::LsmDenied
+        )
+    }
+
+    /// Returns the time after which a rate-limited request may be retried, if
+    /// the error is `Error::RateLimited`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimited(retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the error was caused by a failed signature or
+    /// verification check.
+    pub fn is_verification_failure(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Signature | ErrorKind::Verification)
+    }
+}
+
+impl From<crate::core::Error> for Error {
+    /// Converts a parsing error from the `no_std` core into the matching
+    /// variant.
+    fn from(e: crate::core::Error) -> Self {
+        match e {
+            crate::core::Error::ParseError(msg) => Error::ParseError(msg),
+            crate::core::Error::NotSupported(msg) => Error::NotSupported(msg),
+        }
+    }
+}
+
 /// A type alias for results that use the custom `Error` type.
 ///
 /// This alias simplifies function signatures by using the `Error` enum as the
 /// error type in `std::result::Result`.
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_error_kind() {
+        let e = Error::NotSupported("test".to_string());
+        assert_eq!(e.kind(), ErrorKind::NotSupported);
+        assert_eq!(e.kind().as_str(), "not_supported");
+        assert!(e.is_not_supported());
+        assert!(!e.is_network());
+
+        let e = Error::SignatureError("test".to_string());
+        assert!(e.is_verification_failure());
+        assert_eq!(e.retry_after(), None);
+
+        let e = Error::RateLimited(Duration::from_millis(1500));
+        assert_eq!(e.kind().as_str(), "rate_limited");
+        assert_eq!(e.retry_after(), Some(Duration::from_millis(1500)));
+        assert_eq!(e.to_string(), "Rate limited: retry after 1500ms");
+
+        let e = Error::LsmDenied("test".to_string());
+        assert_eq!(e.kind().as_str(), "lsm_denied");
+        assert!(e.is_access_denied());
+        assert!(!Error::NotSupported("test".to_string()).is_access_denied());
+    }
+
+    #[test]
+    fn test_error_source_preserved() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let e = Error::from(io_err);
+        assert_eq!(e.kind(), ErrorKind::Io);
+
+        let source = e.source().expect("I/O error should be preserved as source");
+        assert_eq!(source.to_string(), "missing");
+    }
+
+    #[cfg(feature = "host-verification")]
+    #[test]
+    fn test_certificate_error_kind() {
+        let e = crate::verification::x509::x509_from_der_bytes(b"not a certificate").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Parse);
+        assert!(!e.is_verification_failure());
+        assert!(e.source().is_some());
+
+        let e = Error::CertificateError {
+            kind: ErrorKind::Verification,
+            source: openssl::error::ErrorStack::get(),
+        };
+        assert!(e.is_verification_failure());
+        assert!(
+            e.to_string()
+                .starts_with("X.509 certificate verification error")
+        );
+    }
+
+    #[test]
+    fn test_core_error_kind() {
+        let e = Error::from(crate::core::Error::NotSupported("test".to_string()));
+        assert!(e.is_not_supported());
+
+        let e = Error::from(crate::core::Error::ParseError("test".to_string()));
+        assert_eq!(e.kind(), ErrorKind::Parse);
+    }
+}
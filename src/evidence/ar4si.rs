@@ -0,0 +1,613 @@
+//! # AR4SI Attestation Results
+//!
+//! This module turns the `Verdict` of an appraised evidence bundle into a
+//! signed attestation result in the IETF AR4SI format (Attestation Results
+//! for Secure Interactions), encoded as an EAT Attestation Result (EAR) JWT,
+//! so that downstream policy enforcement points (e.g., gateways and service
+//! meshes) can act on a compact verdict instead of raw evidence.
+//!
+//! The result appraises the TD as the `tdx` submodule, with an AR4SI
+//! trustworthiness vector whose claims are derived from the verdict's checks:
+//! - `hardware`: the quote signature, TCB, TDX module SVN, QE identity and
+//!   launch endorsement,
+//! - `configuration`: the debug and service TD checks, and
+//! - `executables`: the event log replay and reference values.
+//!
+//! Each claim is `affirming` if all its checks passed, and `contraindicated`
+//! otherwise. The overall status is that of the worst claim, and is also
+//! `contraindicated` if any other check (e.g., the nonce) failed.
+//!
+//! Results of bundles with a boot session carry its ID in the private
+//! `tdx.session-id` claim (see `AttestationResult::with_session_id()`), so
+//! that enforcement points can correlate the results of a TD's boot.
+//!
+//! Results are signed with HMAC-SHA384 (`HS384`) with a key shared with the
+//! enforcement points or, when compiled with the `host-verification`
+//! feature, with an ECDSA P-256 key (`ES256`).
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::evidence::{Bundle, Policy};
+//! use tdx_workload_attestation::evidence::ar4si::AttestationResult;
+//!
+//! let verdict = bundle.verify(&policy).unwrap();
+//! let result = AttestationResult::from_verdict(&verdict, Some(&bundle.nonce), now)
+//!     .with_policy_id("tdx-default")
+//!     .with_expiry(now + 300);
+//! let token = result.sign_hs384(b"a key shared with the gateway").unwrap();
+//!
+//! // On the gateway
+//! let result = AttestationResult::verify_hs384(&token, b"a key shared with the gateway", now).unwrap();
+//! println!("TD is {}", result.status());
+//! ```
+
+use super::Verdict;
+use crate::error::{Error, Result};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha384;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The EAT profile of EAR attestation results.
+pub const EAR_PROFILE: &str = "tag:github.com,2023:veraison/ear";
+
+/// The name of the submodule appraising the TD.
+pub const TDX_SUBMOD: &str = "tdx";
+
+/// The trust tier of a claim with no assertion.
+pub const TIER_NONE: i8 = 0;
+
+/// The trust tier of an affirming claim.
+pub const TIER_AFFIRMING: i8 = 2;
+
+/// The lowest trust tier of a warning claim.
+pub const TIER_WARNING: i8 = 32;
+
+/// The lowest trust tier of a contraindicated claim.
+pub const TIER_CONTRAINDICATED: i8 = 96;
+
+// The developer of the verifier, in the result's verifier ID
+const VERIFIER_DEVELOPER: &str = "https://github.com/IntelLabs/tdx-workload-attestation";
+
+/// The status of an attestation result, or of a trustworthiness claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArStatus {
+    /// The verifier makes no assertion.
+    None,
+    /// The verifier affirms the attester's trustworthiness.
+    Affirming,
+    /// The verifier warns about the attester.
+    Warning,
+    /// The verifier contraindicates the attester's trustworthiness.
+    Contraindicated,
+}
+
+impl ArStatus {
+    /// Returns the status of the trust tier `tier`.
+    pub fn from_tier(tier: i8) -> Self {
+        match tier {
+            TIER_CONTRAINDICATED.. => ArStatus::Contraindicated,
+            TIER_WARNING.. => ArStatus::Warning,
+            TIER_AFFIRMING.. => ArStatus::Affirming,
+            _ => ArStatus::None,
+        }
+    }
+}
+
+impl fmt::Display for ArStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            ArStatus::None => "none",
+            ArStatus::Affirming => "affirming",
+            ArStatus::Warning => "warning",
+            ArStatus::Contraindicated => "contraindicated",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// An AR4SI trustworthiness vector, with the trust tier of each claim the
+/// verifier makes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TrustVector {
+    /// The attester's identity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_identity: Option<i8>,
+    /// The attester's configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configuration: Option<i8>,
+    /// The attester's boot-time and runtime executables.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executables: Option<i8>,
+    /// The attester's file system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_system: Option<i8>,
+    /// The attester's hardware and firmware.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<i8>,
+    /// The opacity of the attester's runtime to unauthorized parties.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_opaque: Option<i8>,
+    /// The opacity of the attester's storage to unauthorized parties.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_opaque: Option<i8>,
+    /// The data the attester sources.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sourced_data: Option<i8>,
+}
+
+impl TrustVector {
+    /// Derives the trustworthiness vector from an appraisal verdict.
+    pub fn from_verdict(verdict: &Verdict) -> Self {
+        let mut vector = Self::default();
+        for check in &verdict.checks {
+            let tier = match check.passed {
+                true => TIER_AFFIRMING,
+                false => TIER_CONTRAINDICATED,
+            };
+            if let Some(claim) = vector.claim_mut(&check.name) {
+                *claim = Some(claim.map_or(tier, |t| t.max(tier)));
+            }
+        }
+        vector
+    }
+
+    /// Returns the worst status of the vector's claims.
+    pub fn status(&self) -> ArStatus {
+        [
+            self.instance_identity,
+            self.configuration,
+            self.executables,
+            self.file_system,
+            self.hardware,
+            self.runtime_opaque,
+            self.storage_opaque,
+            self.sourced_data,
+        ]
+        .into_iter()
+        .flatten()
+        .map(ArStatus::from_tier)
+        .max()
+        .unwrap_or(ArStatus::None)
+    }
+
+    /// Returns the claim a verdict check contributes to, if any.
+    fn claim_mut(&mut self, check: &str) -> Option<&mut Option<i8>> {
+        match check {
+            "quote-signature" | "tcb" | "tdx-module" | "qe-identity" | "endorsement" => {
+                Some(&mut self.hardware)
+            }
+            "debug" | "servtd" => Some(&mut self.configuration),
+            "event-log" | "reference-values" => Some(&mut self.executables),
+            _ => None,
+        }
+    }
+}
+
+/// The appraisal of an attester (or of one of its submodules).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Appraisal {
+    /// The overall status of the appraisal.
+    #[serde(rename = "ear.status")]
+    pub status: ArStatus,
+    /// The trustworthiness vector.
+    #[serde(rename = "ear.trustworthiness-vector", default)]
+    pub trust_vector: TrustVector,
+    /// The ID of the appraisal policy, if any.
+    #[serde(
+        rename = "ear.appraisal-policy-id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub policy_id: Option<String>,
+}
+
+/// The verifier that produced an attestation result.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifierId {
+    /// The developer of the verifier.
+    pub developer: String,
+    /// The build of the verifier.
+    pub build: String,
+}
+
+impl Default for VerifierId {
+    fn default() -> Self {
+        Self {
+            developer: VERIFIER_DEVELOPER.to_string(),
+            build: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// An AR4SI attestation result, in the EAR claims format.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationResult {
+    /// The EAT profile (`EAR_PROFILE`).
+    pub eat_profile: String,
+    /// The time the result was issued at, in seconds since the Unix epoch.
+    pub iat: u64,
+    /// The time the result expires, in seconds since the Unix epoch, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    /// The verifier that produced the result.
+    #[serde(rename = "ear.verifier-id")]
+    pub verifier_id: VerifierId,
+    /// The base64url-encoded nonce the evidence was bound to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eat_nonce: Option<String>,
+    /// The appraisals of the attester's submodules, by name.
+    pub submods: BTreeMap<String, Appraisal>,
+    /// The boot session of the appraised TD, if known (see the
+    /// `boot_session` module).
+    #[serde(
+        rename = "tdx.session-id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub session_id: Option<String>,
+}
+
+/// The header of a JWT.
+#[derive(Debug, Serialize, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    typ: String,
+}
+
+impl AttestationResult {
+    /// Creates the attestation result of an appraisal verdict, for evidence
+    /// bound to `nonce`, issued at `iat` (in seconds since the Unix epoch).
+    pub fn from_verdict(verdict: &Verdict, nonce: Option<&[u8]>, iat: u64) -> Self {
+        let trust_vector = TrustVector::from_verdict(verdict);
+        let mut status = trust_vector.status();
+        if verdict.checks.iter().any(|c| !c.passed) {
+            status = ArStatus::Contraindicated;
+        }
+
+        let appraisal = Appraisal {
+            status,
+            trust_vector,
+            policy_id: None,
+        };
+        Self {
+            eat_profile: EAR_PROFILE.to_string(),
+            iat,
+            exp: None,
+            verifier_id: VerifierId::default(),
+            eat_nonce: nonce.map(|n| URL_SAFE_NO_PAD.encode(n)),
+            submods: BTreeMap::from([(TDX_SUBMOD.to_string(), appraisal)]),
+            session_id: None,
+        }
+    }
+
+    /// Sets the ID of the appraisal policy the TD was appraised against.
+    pub fn with_policy_id(mut self, policy_id: &str) -> Self {
+        for appraisal in self.submods.values_mut() {
+            appraisal.policy_id = Some(policy_id.to_string());
+        }
+        self
+    }
+
+    /// Sets the boot session ID of the appraised TD, so that enforcement
+    /// points can correlate the results of the same boot.
+    pub fn with_session_id(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Sets the time the result expires, in seconds since the Unix epoch.
+    pub fn with_expiry(mut self, exp: u64) -> Self {
+        self.exp = Some(exp);
+        self
+    }
+
+    /// Returns the status of the TD's appraisal.
+    pub fn status(&self) -> ArStatus {
+        self.submods
+            .get(TDX_SUBMOD)
+            .map_or(ArStatus::None, |a| a.status)
+    }
+
+    /// Encodes the result as a JWT signed with HMAC-SHA384 (`HS384`) with
+    /// `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if the result cannot be
+    /// encoded.
+    pub fn sign_hs384(&self, key: &[u8]) -> Result<String> {
+        let signing_input = self.signing_input("HS384")?;
+        let mac = hmac_sha384(key, signing_input.as_bytes())
+            .finalize()
+            .into_bytes();
+        Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(mac)))
+    }
+
+    /// Validates a result JWT signed with HMAC-SHA384 (`HS384`) with `key`
+    /// at `unix_time`, and returns the result.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::ParseError` if the token is malformed.
+    /// - `Error::NotSupported` if the token isn't signed with `HS384`.
+    /// - `Error::VerificationError` if the signature is invalid, or the
+    ///   result has expired at `unix_time`.
+    pub fn verify_hs384(token: &str, key: &[u8], unix_time: u64) -> Result<Self> {
+        let (signing_input, signature) = split_token(token, "HS384")?;
+        if hmac_sha384(key, signing_input.as_bytes())
+            .verify_slice(&signature)
+            .is_err()
+        {
+            return Err(Error::VerificationError(
+                "Invalid attestation result signature".to_string(),
+            ));
+        }
+        Self::from_signing_input(signing_input, unix_time)
+    }
+
+    /// Encodes the result as a JWT signed with the ECDSA P-256 key `key`
+    /// (`ES256`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if the result cannot be
+    /// encoded, or an `Error::OpenSslError` if it cannot be signed.
+    #[cfg(feature = "host-verification")]
+    pub fn sign_es256(&self, key: &openssl::ec::EcKey<openssl::pkey::Private>) -> Result<String> {
+        use openssl::ecdsa::EcdsaSig;
+        use openssl::sha::sha256;
+
+        let signing_input = self.signing_input("ES256")?;
+        let sig =
+            EcdsaSig::sign(&sha256(signing_input.as_bytes()), key).map_err(Error::OpenSslError)?;
+        let mut raw = sig.r().to_vec_padded(32).map_err(Error::OpenSslError)?;
+        raw.extend(sig.s().to_vec_padded(32).map_err(Error::OpenSslError)?);
+        Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(raw)))
+    }
+
+    /// Encodes the result as a JWT signed with `signer` (e.g., a key held in a
+    /// KMS or HSM, see the `verification::signature` module).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SerializationError` if the result cannot be
+    /// encoded, or any error of the signer.
+    #[cfg(feature = "host-verification")]
+    pub fn sign_with(&self, signer: &dyn crate::verification::signature::Signer) -> Result<String> {
+        let signing_input = self.signing_input(signer.algorithm())?;
+        let signature = signer.sign(signing_input.as_bytes())?;
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Validates a result JWT signed with the ECDSA P-256 key `key` (`ES256`)
+    /// at `unix_time`, and returns the result.
+    ///
+    /// # Errors
+    ///
+    /// Same as `verify_hs384()`, or an `Error::OpenSslError` if the key is
+    /// malformed.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_es256(
+        token: &str,
+        key: &openssl::pkey::PKey<openssl::pkey::Public>,
+        unix_time: u64,
+    ) -> Result<Self> {
+        use crate::verification::quote::verify_ecdsa_p256;
+
+        let (signing_input, signature) = split_token(token, "ES256")?;
+        let signature: [u8; 64] = signature.try_into().map_err(|_| {
+            Error::VerificationError("Invalid attestation result signature".to_string())
+        })?;
+        if !verify_ecdsa_p256(signing_input.as_bytes(), &signature, key)? {
+            return Err(Error::VerificationError(
+                "Invalid attestation result signature".to_string(),
+            ));
+        }
+        Self::from_signing_input(signing_input, unix_time)
+    }
+
+    /// Returns the JWT signing input (the encoded header and claims) for the
+    /// signature algorithm `alg`.
+    fn signing_input(&self, alg: &str) -> Result<String> {
+        let header = JwtHeader {
+            alg: alg.to_string(),
+            typ: "JWT".to_string(),
+        };
+        Ok(format!("{}.{}", encode_json(&header)?, encode_json(self)?))
+    }
+
+    /// Decodes the claims of a verified JWT signing input, and checks that
+    /// the result hasn't expired at `unix_time`.
+    fn from_signing_input(signing_input: &str, unix_time: u64) -> Result<Self> {
+        let (_, claims) = signing_input.split_once('.').unwrap_or_default();
+        let result: Self = decode_json(claims, "attestation result claims")?;
+        if result.exp.is_some_and(|exp| unix_time >= exp) {
+            return Err(Error::VerificationError(
+                "Attestation result has expired".to_string(),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+/// Encodes a JWT segment as base64url-encoded JSON.
+fn encode_json<T: Serialize>(value: &T) -> Result<String> {
+    let json = serde_json::to_vec(value).map_err(|e| Error::SerializationError(e.to_string()))?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Computes the HMAC-SHA384 of `data` with `key`.
+fn hmac_sha384(key: &[u8], data: &[u8]) -> Hmac<Sha384> {
+    let mut mac = Hmac::<Sha384>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac
+}
+
+/// Splits a JWT signed with `alg` into its signing input and decoded
+/// signature.
+fn split_token<'a>(token: &'a str, alg: &str) -> Result<(&'a str, Vec<u8>)> {
+    let Some((signing_input, signature)) = token.rsplit_once('.') else {
+        return Err(Error::ParseError(
+            "Attestation result is not a JWT".to_string(),
+        ));
+    };
+    let Some((header, _)) = signing_input.split_once('.') else {
+        return Err(Error::ParseError(
+            "Attestation result is not a JWT".to_string(),
+        ));
+    };
+
+    let header: JwtHeader = decode_json(header, "attestation result header")?;
+    if header.alg != alg {
+        return Err(Error::NotSupported(format!(
+            "Attestation result signature algorithm {} is not {}",
+            header.alg, alg
+        )));
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| Error::ParseError(format!("Invalid attestation result signature: {}", e)))?;
+    Ok((signing_input, signature))
+}
+
+/// Decodes a base64url-encoded JSON JWT segment.
+fn decode_json<T: for<'de> Deserialize<'de>>(segment: &str, what: &str) -> Result<T> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| Error::ParseError(format!("Invalid {}: {}", what, e)))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::ParseError(format!("Invalid {}: {}", what, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(failed: &[&str]) -> Verdict {
+        let mut verdict = Verdict::default();
+        for name in ["quote-signature", "tcb", "nonce", "debug", "event-log"] {
+            match failed.contains(&name) {
+                true => verdict.fail(name, "failed"),
+                false => verdict.pass(name),
+            }
+        }
+        verdict
+    }
+
+    #[test]
+    fn test_result_from_verdict() {
+        let result = AttestationResult::from_verdict(&verdict(&[]), Some(b"nonce"), 1000);
+        assert_eq!(result.status(), ArStatus::Affirming);
+        assert_eq!(result.eat_nonce.as_deref(), Some("bm9uY2U"));
+
+        let vector = &result.submods[TDX_SUBMOD].trust_vector;
+        assert_eq!(vector.hardware, Some(TIER_AFFIRMING));
+        assert_eq!(vector.configuration, Some(TIER_AFFIRMING));
+        assert_eq!(vector.executables, Some(TIER_AFFIRMING));
+        assert_eq!(vector.instance_identity, None);
+
+        // a failed check contraindicates its claim
+        let result = AttestationResult::from_verdict(&verdict(&["tcb"]), None, 1000);
+        let vector = &result.submods[TDX_SUBMOD].trust_vector;
+        assert_eq!(vector.hardware, Some(TIER_CONTRAINDICATED));
+        assert_eq!(vector.executables, Some(TIER_AFFIRMING));
+        assert_eq!(result.status(), ArStatus::Contraindicated);
+
+        // as does a failed check outside the vector
+        let result = AttestationResult::from_verdict(&verdict(&["nonce"]), None, 1000);
+        assert_eq!(
+            result.submods[TDX_SUBMOD].trust_vector.status(),
+            ArStatus::Affirming
+        );
+        assert_eq!(result.status(), ArStatus::Contraindicated);
+
+        assert_eq!(ArStatus::from_tier(TIER_WARNING + 1), ArStatus::Warning);
+        assert_eq!(ArStatus::from_tier(TIER_NONE), ArStatus::None);
+    }
+
+    #[test]
+    fn test_sign_hs384() -> Result<()> {
+        let result = AttestationResult::from_verdict(&verdict(&[]), Some(b"nonce"), 1000)
+            .with_policy_id("default")
+            .with_session_id("00112233")
+            .with_expiry(2000);
+        let token = result.sign_hs384(b"key")?;
+
+        // the claims use the EAR names
+        let claims: serde_json::Value = decode_json(token.split('.').nth(1).unwrap(), "claims")?;
+        assert_eq!(claims["eat_profile"], EAR_PROFILE);
+        assert_eq!(claims["submods"]["tdx"]["ear.status"], "affirming");
+        assert_eq!(
+            claims["submods"]["tdx"]["ear.appraisal-policy-id"],
+            "default"
+        );
+        assert_eq!(claims["tdx.session-id"], "00112233");
+        assert_eq!(
+            claims["submods"]["tdx"]["ear.trustworthiness-vector"]["hardware"],
+            TIER_AFFIRMING
+        );
+
+        assert_eq!(
+            AttestationResult::verify_hs384(&token, b"key", 1500)?,
+            result
+        );
+        assert!(AttestationResult::verify_hs384(&token, b"other key", 1500).is_err());
+        assert!(AttestationResult::verify_hs384(&token, b"key", 2000).is_err());
+
+        let mut tampered = token.clone();
+        tampered.insert(token.rfind('.').unwrap() - 1, 'x');
+        assert!(AttestationResult::verify_hs384(&tampered, b"key", 1500).is_err());
+        assert!(AttestationResult::verify_hs384("not a token", b"key", 1500).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "host-verification")]
+    #[test]
+    fn test_sign_es256() -> Result<()> {
+        use crate::verification::signature::KeySigner;
+        use openssl::ec::{EcGroup, EcKey};
+        use openssl::nid::Nid;
+        use openssl::pkey::PKey;
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let public =
+            PKey::from_ec_key(EcKey::from_public_key(&group, key.public_key()).unwrap()).unwrap();
+
+        let result = AttestationResult::from_verdict(&verdict(&["debug"]), None, 1000);
+        let token = result.sign_es256(&key)?;
+        assert_eq!(
+            AttestationResult::verify_es256(&token, &public, 1000)?,
+            result
+        );
+
+        // an HS384 result isn't accepted as ES256
+        let hs384 = result.sign_hs384(b"key")?;
+        assert!(
+            AttestationResult::verify_es256(&hs384, &public, 1000)
+                .is_err_and(|e| e.is_not_supported())
+        );
+
+        // with a `Signer`
+        let signer = KeySigner::new(PKey::from_ec_key(key).unwrap())?;
+        let token = result.sign_with(&signer)?;
+        assert_eq!(
+            AttestationResult::verify_es256(&token, &public, 1000)?,
+            result
+        );
+        Ok(())
+    }
+}
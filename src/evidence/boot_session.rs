@@ -0,0 +1,169 @@
+//! # Boot Sessions
+//!
+//! A TD instance may be attested many times during its lifetime, e.g., by
+//! each workload it serves, or periodically by an agent. This module
+//! identifies the instance's current boot by a `BootSession` ID, so that
+//! verifiers can correlate the quotes, attestation results and audit
+//! records emitted during the same boot.
+//!
+//! The ID is derived from the TD's static measurements (its attributes,
+//! XFAM, `MRTD`, `MRCONFIGID`, `MROWNER` and `MROWNERCONFIG`, which don't
+//! change during the TD's lifetime) and the time it booted at: it's the
+//! hex-encoded first 16 bytes of the SHA-384 digest of a domain separator
+//! followed by the measurements and the big-endian boot time. Runtime
+//! measurements (the RTMRs) and the report data are not included, so the ID
+//! stays the same across the quotes of a boot, but changes on reboot.
+//!
+//! Collected bundles carry their session (see `Bundle::collect()`), which
+//! `Bundle::verify()` checks against the quote's measurements.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::boot_session::BootSession;
+//!
+//! let session = BootSession::current().unwrap();
+//! println!("Boot session {}", session);
+//! ```
+//!
+//! # Notes
+//! - The boot time isn't authenticated by the quote: a session ID ties
+//!   evidence to the measurements of a TD, but the TD itself (not the
+//!   platform) vouches for the boot it was collected in.
+//! - The kernel reports the boot time relative to the wall clock, so a TD
+//!   whose clock is stepped by more than a second gets a new session ID.
+
+use crate::core::report::diff::Fields;
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+use std::fmt;
+use std::fs;
+
+/// The file the kernel reports its boot time in (as `btime`).
+pub const BOOT_TIME_PATH: &str = "/proc/stat";
+
+/// The length of a session ID, in bytes.
+pub const SESSION_ID_LEN: usize = 16;
+
+/// The static measurements a session ID is derived from.
+pub const SESSION_FIELDS: [&str; 6] = [
+    "ATTRIBUTES",
+    "XFAM",
+    "MRTD",
+    "MRCONFIGID",
+    "MROWNER",
+    "MROWNERCONFIG",
+];
+
+// The domain separator of session IDs
+const SESSION_ID_CONTEXT: &[u8] = b"tdx-workload-attestation/boot-session/v1";
+
+/// The identity of a boot of a TD instance.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BootSession {
+    /// The hex-encoded session ID.
+    pub id: String,
+    /// The time the TD booted at, in seconds since the Unix epoch.
+    pub boot_time: u64,
+}
+
+impl BootSession {
+    /// Derives the session of the TD with the measurements `fields` (e.g.,
+    /// of its report or a quote), booted at `boot_time`.
+    pub fn derive(fields: &Fields, boot_time: u64) -> Self {
+        let mut hasher = Sha384::new();
+        hasher.update(SESSION_ID_CONTEXT);
+        for field in SESSION_FIELDS {
+            hasher.update(fields.get(field).unwrap_or_default());
+        }
+        hasher.update(boot_time.to_be_bytes());
+        Self {
+            id: hex::encode(&hasher.finalize()[..SESSION_ID_LEN]),
+            boot_time,
+        }
+    }
+
+    /// Returns the session of the current boot of the TD.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TD's report or the boot time cannot be read.
+    #[cfg(feature = "tdx-linux")]
+    pub fn current() -> Result<Self> {
+        use crate::tdx::LinuxTdxProvider;
+
+        let report = LinuxTdxProvider::new().get_tdreport()?.into_report();
+        Ok(Self::derive(&Fields::from(&report), boot_time()?))
+    }
+
+    /// Checks that the session belongs to the TD with the measurements
+    /// `fields`.
+    pub fn matches(&self, fields: &Fields) -> bool {
+        Self::derive(fields, self.boot_time).id == self.id
+    }
+}
+
+impl fmt::Display for BootSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Returns the time the system booted at, in seconds since the Unix epoch.
+///
+/// # Errors
+///
+/// Returns an error if `BOOT_TIME_PATH` cannot be read, or doesn't report
+/// the boot time.
+pub fn boot_time() -> Result<u64> {
+    parse_boot_time(&fs::read_to_string(BOOT_TIME_PATH)?)
+}
+
+/// Parses the `btime` line of `/proc/stat`.
+fn parse_boot_time(stat: &str) -> Result<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|time| time.trim().parse().ok())
+        .ok_or_else(|| Error::ParseError(format!("No boot time in {}", BOOT_TIME_PATH)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::{TDX_MR_REG_LEN, TdReportBuilder, TdReportV15};
+
+    fn report(mrtd: u8, rtmr3: u8) -> TdReportV15 {
+        let mut rtmrs = [[0; TDX_MR_REG_LEN]; 4];
+        rtmrs[3] = [rtmr3; TDX_MR_REG_LEN];
+        TdReportBuilder::new()
+            .with_mrtd(&[mrtd; TDX_MR_REG_LEN])
+            .with_rtmrs(&rtmrs)
+            .build()
+    }
+
+    #[test]
+    fn test_derive_session() {
+        let session = BootSession::derive(&Fields::from(&report(1, 0)), 1000);
+        assert_eq!(session.id.len(), 2 * SESSION_ID_LEN);
+        assert_eq!(session.to_string(), session.id);
+
+        // runtime measurements don't change the session, but the static
+        // measurements and the boot do
+        assert!(session.matches(&Fields::from(&report(1, 1))));
+        assert!(!session.matches(&Fields::from(&report(2, 0))));
+        assert_ne!(
+            BootSession::derive(&Fields::from(&report(1, 0)), 1001),
+            session
+        );
+    }
+
+    #[test]
+    fn test_parse_boot_time() {
+        let stat = "cpu  1 2 3 4\nintr 5\nctxt 6\nbtime 1700000000\nprocesses 7\n";
+        assert_eq!(parse_boot_time(stat).unwrap(), 1_700_000_000);
+        assert!(parse_boot_time("cpu  1 2 3 4\n").is_err());
+        assert!(parse_boot_time("btime soon\n").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+//! # Verification Cache
+//!
+//! Verifiers that appraise many quotes from the same platforms redo the same
+//! expensive checks for each of them: the PCK certificate chain of each
+//! platform, and the signatures of the TCB Info of its platform family
+//! (FMSPC) and of the QE Identity, are verified again for every quote. This
+//! module provides a `VerificationCache` of these intermediate results,
+//! which can be shared by the policies of a verifier (see
+//! `Policy::with_verification_cache()`).
+//!
+//! Certificate chains are keyed by the digest of their certificates and
+//! trusted root, and collateral by its kind, FMSPC and version (its issue
+//! and next update dates), along with the digest of its signed data,
+//! signature, signing chain and trusted root.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use tdx_workload_attestation::evidence::Policy;
+//! use tdx_workload_attestation::evidence::cache::VerificationCache;
+//!
+//! let cache = Arc::new(VerificationCache::new());
+//! let policy = Policy::from_file("policy.toml")
+//!     .unwrap()
+//!     .with_collateral_dir("/etc/tdx-workload-attestation")
+//!     .unwrap()
+//!     .with_verification_cache(cache.clone());
+//! // ... appraise bundles with the policy
+//! println!("{:?}", cache.stats());
+//! ```
+//!
+//! # Notes
+//! - Only successful verifications are cached, and only for the period in
+//!   which all the certificates involved are valid (and, for collateral,
+//!   before its next update): verifications at times outside of it (e.g.,
+//!   with a policy's `FixedClock`) are performed again.
+//! - The signatures of the quote itself (and of its QE report) are always
+//!   verified, and the TCB status is always evaluated against the platform's
+//!   PCK certificate.
+//! - When a table reaches its maximum number of entries, its expired entries
+//!   are evicted, or all of its entries if none has expired.
+
+use crate::error::Result;
+use crate::trust::parse_validity;
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// The default maximum number of entries of each table of the cache.
+pub const DEFAULT_MAX_ENTRIES: usize = 4096;
+
+/// The kinds of collateral whose verification is cached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollateralKind {
+    /// A TDX TCB Info.
+    TcbInfo,
+    /// A TD QE Identity.
+    QeIdentity,
+}
+
+/// The identity of a version of some collateral.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CollateralKey {
+    /// The kind of collateral.
+    pub kind: CollateralKind,
+    /// The hex-encoded FMSPC of the platform family the collateral is for
+    /// (empty for collateral that isn't specific to a platform family).
+    pub fmspc: String,
+    /// The date the collateral was issued.
+    pub issue_date: String,
+    /// The time the collateral will be updated by, in seconds since the Unix
+    /// epoch.
+    pub next_update: u64,
+}
+
+/// Statistics on the use of a `VerificationCache`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of verifications answered by the cache.
+    pub hits: u64,
+    /// The number of verifications performed.
+    pub misses: u64,
+    /// The number of cached results.
+    pub entries: usize,
+}
+
+// A successful verification, and the period it holds for
+#[derive(Debug)]
+struct Entry {
+    digest: [u8; 32],
+    not_before: u64,
+    not_after: u64,
+}
+
+/// A cache of the successful verifications of certificate chains and
+/// collateral.
+#[derive(Debug)]
+pub struct VerificationCache {
+    chains: Mutex<HashMap<[u8; 32], Entry>>,
+    collateral: Mutex<HashMap<CollateralKey, Entry>>,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for VerificationCache {
+    fn default() -> Self {
+        Self {
+            chains: Mutex::new(HashMap::new()),
+            collateral: Mutex::new(HashMap::new()),
+            max_entries: DEFAULT_MAX_ENTRIES,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl VerificationCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of entries of each table of the cache
+    /// (`DEFAULT_MAX_ENTRIES` by default).
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns the cache's statistics.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: lock(&self.chains).len() + lock(&self.collateral).len(),
+        }
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        lock(&self.chains).clear();
+        lock(&self.collateral).clear();
+    }
+
+    /// Verifies the DER-encoded certificate `chain` up to the DER-encoded
+    /// `root` at `now` (in seconds since the Unix epoch) with `verify`,
+    /// unless the chain was verified before and all its certificates are
+    /// valid at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `verify`.
+    pub fn verify_chain<F>(
+        &self,
+        chain: &[Vec<u8>],
+        root: &[u8],
+        now: u64,
+        verify: F,
+    ) -> Result<bool>
+    where
+        F: FnOnce() -> Result<bool>,
+    {
+        let digest = digest(&[], chain, root);
+        let validity = validity(chain, root);
+        self.verify(&self.chains, digest, digest, validity, now, verify)
+    }
+
+    /// Verifies the collateral `key`, whose signed data and signature are
+    /// `signed`, signed with the DER-encoded `signing_chain` up to the
+    /// DER-encoded `root`, at `now` (in seconds since the Unix epoch) with
+    /// `verify`, unless the same collateral was verified before, its next
+    /// update is after `now` and all the certificates are valid at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `verify`.
+    pub fn verify_collateral<F>(
+        &self,
+        key: CollateralKey,
+        signed: &[&[u8]],
+        signing_chain: &[Vec<u8>],
+        root: &[u8],
+        now: u64,
+        verify: F,
+    ) -> Result<bool>
+    where
+        F: FnOnce() -> Result<bool>,
+    {
+        let digest = digest(signed, signing_chain, root);
+        let validity = validity(signing_chain, root)
+            .map(|(not_before, not_after)| (not_before, not_after.min(key.next_update)));
+        self.verify(&self.collateral, key, digest, validity, now, verify)
+    }
+
+    /// Looks up `key` in `table`, or verifies it with `verify` and caches the
+    /// result for the `validity` period, if it's successful.
+    fn verify<K, F>(
+        &self,
+        table: &Mutex<HashMap<K, Entry>>,
+        key: K,
+        digest: [u8; 32],
+        validity: Option<(u64, u64)>,
+        now: u64,
+        verify: F,
+    ) -> Result<bool>
+    where
+        K: Eq + Hash,
+        F: FnOnce() -> Result<bool>,
+    {
+        let cached = lock(table).get(&key).is_some_and(|entry| {
+            entry.digest == digest && entry.not_before <= now && now < entry.not_after
+        });
+        if cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(true);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let verified = verify()?;
+        if let (true, Some((not_before, not_after))) = (verified, validity) {
+            let mut table = lock(table);
+            if table.len() >= self.max_entries && !table.contains_key(&key) {
+                table.retain(|_, entry| now < entry.not_after);
+                if table.len() >= self.max_entries {
+                    table.clear();
+                }
+            }
+            table.insert(
+                key,
+                Entry {
+                    digest,
+                    not_before,
+                    not_after,
+                },
+            );
+        }
+        Ok(verified)
+    }
+}
+
+/// Locks a table of the cache, which remains consistent if a thread
+/// panicked while holding the lock.
+fn lock<T>(table: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    table.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the SHA-256 digest of the length-prefixed `data`, certificate
+/// `chain` and `root`.
+fn digest(data: &[&[u8]], chain: &[Vec<u8>], root: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    let parts = data
+        .iter()
+        .copied()
+        .chain(chain.iter().map(Vec::as_slice))
+        .chain([root]);
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Returns the period in which all the DER-encoded certificates of `chain`
+/// and `root` are valid, or `None` if a validity period cannot be parsed.
+fn validity(chain: &[Vec<u8>], root: &[u8]) -> Option<(u64, u64)> {
+    chain
+        .iter()
+        .map(Vec::as_slice)
+        .chain([root])
+        .map(parse_validity)
+        .try_fold((0, u64::MAX), |(not_before, not_after), validity| {
+            let (cert_not_before, cert_not_after) = validity?;
+            Some((
+                not_before.max(cert_not_before),
+                not_after.min(cert_not_after),
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::test_utils::make_der_cert;
+
+    // 2023-11-14T22:13:20Z, in the validity period of the test certificates
+    const NOW: u64 = 1_700_000_000;
+
+    fn cert(subject: u8) -> Vec<u8> {
+        let mut cert = make_der_cert("230101000000Z", "20331231235959Z", &[]);
+        // vary the (zeroed) signature to tell the certificates apart
+        let len = cert.len();
+        cert[len - 1] = subject;
+        cert
+    }
+
+    fn key(issue_date: &str) -> CollateralKey {
+        CollateralKey {
+            kind: CollateralKind::TcbInfo,
+            fmspc: "00906ed50000".to_string(),
+            issue_date: issue_date.to_string(),
+            next_update: NOW + 86400,
+        }
+    }
+
+    #[test]
+    fn test_verify_chain() -> Result<()> {
+        let chain = vec![cert(1)];
+        let root = make_der_cert("180521104550Z", "20491231235959Z", &[]);
+
+        let cache = VerificationCache::new();
+        assert!(cache.verify_chain(&chain, &root, NOW, || Ok(true))?);
+        assert!(cache.verify_chain(&chain, &root, NOW + 1, || unreachable!())?);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+
+        // another root, or a time outside the certificates' validity period
+        assert!(!cache.verify_chain(&chain, &cert(0), NOW, || Ok(false))?);
+        assert!(!cache.verify_chain(&chain, &root, 0, || Ok(false))?);
+        assert!(!cache.verify_chain(&chain, &root, u64::MAX, || Ok(false))?);
+
+        // failures, errors and unparsable certificates aren't cached
+        let other = vec![cert(2)];
+        assert!(!cache.verify_chain(&other, &root, NOW, || Ok(false))?);
+        let error = || Err(Error::VerificationError("Invalid chain".to_string()));
+        assert!(cache.verify_chain(&other, &root, NOW, error).is_err());
+        assert!(cache.verify_chain(&[vec![1, 2, 3]], &root, NOW, || Ok(true))?);
+        assert_eq!(cache.stats().entries, 1);
+
+        cache.clear();
+        assert!(!cache.verify_chain(&chain, &root, NOW, || Ok(false))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_collateral() -> Result<()> {
+        let chain = vec![cert(1)];
+        let root = cert(0);
+
+        let cache = VerificationCache::new();
+        let verify = |key, signed: &[u8], now, verified| {
+            cache.verify_collateral(key, &[signed], &chain, &root, now, || Ok(verified))
+        };
+        assert!(verify(key("2025-01-01T00:00:00Z"), b"tcb info", NOW, true)?);
+        assert!(verify(
+            key("2025-01-01T00:00:00Z"),
+            b"tcb info",
+            NOW,
+            false
+        )?);
+
+        // other collateral with the same version, another version, or a time
+        // past the next update
+        assert!(!verify(key("2025-01-01T00:00:00Z"), b"forged", NOW, false)?);
+        assert!(!verify(
+            key("2025-02-01T00:00:00Z"),
+            b"tcb info",
+            NOW,
+            false
+        )?);
+        assert!(!verify(
+            key("2025-01-01T00:00:00Z"),
+            b"tcb info",
+            NOW + 86400,
+            false
+        )?);
+        assert_eq!(cache.stats().hits, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_eviction() -> Result<()> {
+        let root = cert(0);
+        let cache = VerificationCache::new().with_max_entries(2);
+        for subject in 1..=3 {
+            assert!(cache.verify_chain(&[cert(subject)], &root, NOW, || Ok(true))?);
+        }
+        assert_eq!(cache.stats().entries, 1);
+        Ok(())
+    }
+}
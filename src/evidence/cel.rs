@@ -0,0 +1,1082 @@
+//! # CEL Policy Rules
+//!
+//! Users coming from Kubernetes and other Common Expression Language (CEL)
+//! tooling can write policy rules as CEL predicates over the evidence, as an
+//! alternative to the native TOML settings, e.g.:
+//!
+//! ```toml
+//! [[cel_rules]]
+//! name = "production"
+//! expression = 'report.td_info.attributes.debug == false && tcb.status == "UpToDate"'
+//! ```
+//!
+//! Every rule of a policy must evaluate to `true` for `Bundle::verify()`'s
+//! `cel` check to pass. Rules are parsed when the policy is loaded, so that
+//! malformed rules are rejected before any appraisal.
+//!
+//! The expressions can refer to the following variables (see
+//! `activation()`), with measurements and other binary fields hex-encoded:
+//! - `report.td_info`: the TD's `attributes` (`debug`, and the `raw`
+//!   attributes), `xfam`, `mrtd`, `mrconfigid`, `mrowner`, `mrownerconfig`,
+//!   `rtmr0` to `rtmr3` and `servtd_hash` (`null` with a TDX 1.0 quote),
+//! - `report.tee_tcb_info`: the TDX module's `tee_tcb_svn`, `tdx_module_svn`
+//!   (the first byte of its TCB SVN), `mrseam`, `mrsignerseam` and
+//!   `attributes`,
+//! - `report.report_data` and `quote.version`,
+//! - `tcb.status`: the platform's TCB status (`null` unless the `tcb` check
+//!   passed), and
+//!   `tcb.advisories`: the IDs of the advisories affecting it,
+//! - `checks`: the results of the native checks, by name (e.g.,
+//!   `checks["quote-signature"]`),
+//! - `nonce`: the bundle's nonce, and
+//! - `claims`: the custom claims extracted by the policy's claim extractors
+//!   (see the `claims` module).
+//!
+//! A practical subset of CEL is supported: `null`, boolean, integer, double,
+//! string and list literals, field selection and indexing, the arithmetic,
+//! comparison, `in`, logical and conditional operators, the `has()`,
+//! `all()`, `exists()`, `exists_one()`, `filter()` and `map()` macros, and
+//! the `size()`, `int()`, `string()`, `startsWith()`, `endsWith()`,
+//! `contains()` and `lowerAscii()` functions. Regular expressions
+//! (`matches()`), map literals, bytes, unsigned integers and timestamps are
+//! not supported.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::evidence::cel::Expression;
+//!
+//! let expression = Expression::parse(r#"tcb.status in ["UpToDate", "SWHardeningNeeded"]"#).unwrap();
+//! let activation = serde_json::json!({"tcb": {"status": "UpToDate"}});
+//! assert!(expression.evaluate(&activation).unwrap());
+//! ```
+
+use crate::error::{Error, Result};
+use crate::evidence::{Verdict, quote::Quote};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Number, Value, json};
+use std::fmt;
+
+/// A named CEL rule of a policy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CelRule {
+    /// The name of the rule, reported when it doesn't hold.
+    pub name: String,
+    /// The CEL predicate that must hold.
+    pub expression: Expression,
+}
+
+impl CelRule {
+    /// Parses the rule `name`, whose predicate is `expression`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the expression is malformed.
+    pub fn new(name: &str, expression: &str) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            expression: Expression::parse(expression)
+                .map_err(|e| Error::ParseError(format!("Invalid CEL rule {}: {}", name, e)))?,
+        })
+    }
+}
+
+/// A parsed CEL expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    source: String,
+    ast: Expr,
+}
+
+impl Expression {
+    /// Parses the CEL expression `source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the expression is malformed, or
+    /// uses unsupported syntax.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let ast = parser.expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(Self {
+            source: source.to_string(),
+            ast,
+        })
+    }
+
+    /// Returns the source of the expression.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression with the variables of `activation` (a JSON
+    /// object), and returns its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the evaluation fails, e.g.,
+    /// on an unknown variable or field, or mismatched operand types.
+    pub fn eval(&self, activation: &Value) -> Result<Value> {
+        let mut scope = Scope {
+            activation,
+            bindings: vec![],
+        };
+        scope.eval(&self.ast)
+    }
+
+    /// Evaluates the expression as a predicate with the variables of
+    /// `activation`.
+    ///
+    /// # Errors
+    ///
+    /// Same as `eval()`, and returns an `Error::VerificationError` if the
+    /// expression's value isn't a boolean.
+    pub fn evaluate(&self, activation: &Value) -> Result<bool> {
+        match self.eval(activation)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(eval_error(format!(
+                "Expression evaluates to {}, not a boolean",
+                value
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the variables of CEL rules appraising `quote`, from a platform
+/// with the TCB status `tcb_status` (if known), which binds `nonce`, with the
+/// custom `claims`, after the native checks of `verdict`.
+pub fn activation(
+    quote: &Quote,
+    tcb_status: Option<&str>,
+    nonce: &[u8],
+    claims: &Map<String, Value>,
+    verdict: &Verdict,
+) -> Value {
+    let body = &quote.body;
+    let checks: Map<String, Value> = verdict
+        .checks
+        .iter()
+        .map(|check| (check.name.clone(), Value::Bool(check.passed)))
+        .collect();
+    json!({
+        "report": {
+            "td_info": {
+                "attributes": {
+                    "debug": body.is_debug(),
+                    "raw": hex::encode(body.td_attributes),
+                },
+                "xfam": hex::encode(body.xfam),
+                "mrtd": body.mrtd,
+                "mrconfigid": body.mrconfigid,
+                "mrowner": body.mrowner,
+                "mrownerconfig": body.mrownerconfig,
+                "rtmr0": body.rtmrs[0],
+                "rtmr1": body.rtmrs[1],
+                "rtmr2": body.rtmrs[2],
+                "rtmr3": body.rtmrs[3],
+                "servtd_hash": body.mrservicetd,
+            },
+            "tee_tcb_info": {
+                "tee_tcb_svn": hex::encode(body.tee_tcb_svn),
+                "tdx_module_svn": body.tee_tcb_svn[0],
+                "mrseam": body.mrseam,
+                "mrsignerseam": body.mrsignerseam,
+                "attributes": hex::encode(body.seam_attributes),
+            },
+            "report_data": hex::encode(body.report_data),
+        },
+        "quote": {
+            "version": quote.version,
+        },
+        "tcb": {
+            "status": tcb_status,
+            "advisories": verdict.advisories.iter().map(|a| &a.id).collect::<Vec<_>>(),
+        },
+        "checks": checks,
+        "nonce": hex::encode(nonce),
+        "claims": claims,
+    })
+}
+
+fn parse_error(detail: String) -> Error {
+    Error::ParseError(format!("Invalid CEL expression: {}", detail))
+}
+
+fn eval_error(detail: String) -> Error {
+    Error::VerificationError(detail)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Str(String),
+    Ident(String),
+    In,
+    Punct(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Double(d) => write!(f, "{}", d),
+            Self::Str(s) => write!(f, "{:?}", s),
+            Self::Ident(name) => f.write_str(name),
+            Self::In => f.write_str("in"),
+            Self::Punct(p) => f.write_str(p),
+        }
+    }
+}
+
+// the punctuation, longest first
+const PUNCTUATION: [&str; 22] = [
+    "&&", "||", "==", "!=", "<=", ">=", "(", ")", "[", "]", "{", "}", ".", ",", "?", ":", "!", "-",
+    "+", "*", "/", "%",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                // a selection on a number literal isn't valid CEL anyway
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push(parse_number(&literal)?);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+            tokens.push(match ident.as_str() {
+                "null" => Token::Null,
+                "true" => Token::Bool(true),
+                "false" => Token::Bool(false),
+                "in" => Token::In,
+                _ => Token::Ident(ident),
+            });
+        } else if c == '"' || c == '\'' {
+            let (literal, end) = parse_string(&chars, i)?;
+            tokens.push(Token::Str(literal));
+            i = end;
+        } else if c == '<' || c == '>' {
+            let op = match (c, chars.get(i + 1)) {
+                ('<', Some('=')) => "<=",
+                ('>', Some('=')) => ">=",
+                ('<', _) => "<",
+                _ => ">",
+            };
+            tokens.push(Token::Punct(op));
+            i += op.len();
+        } else {
+            let rest: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let Some(punct) = PUNCTUATION.iter().find(|p| rest.starts_with(*p)) else {
+                return Err(parse_error(format!("unexpected character '{}'", c)));
+            };
+            tokens.push(Token::Punct(punct));
+            i += punct.len();
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(literal: &str) -> Result<Token> {
+    let invalid = || parse_error(format!("invalid number {}", literal));
+    if let Some(hex) = literal.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16)
+            .map(Token::Int)
+            .map_err(|_| invalid());
+    }
+    if literal.contains(['.', 'e', 'E']) {
+        return literal.parse().map(Token::Double).map_err(|_| invalid());
+    }
+    // unsigned integers are treated as integers
+    literal
+        .strip_suffix(['u', 'U'])
+        .unwrap_or(literal)
+        .parse()
+        .map(Token::Int)
+        .map_err(|_| invalid())
+}
+
+// Parses the string literal starting at `chars[start]`, and returns it with
+// the index after its closing quote
+fn parse_string(chars: &[char], start: usize) -> Result<(String, usize)> {
+    let quote = chars[start];
+    let mut literal = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            c if c == quote => return Ok((literal, i + 1)),
+            '\\' => {
+                let escaped = match chars.get(i + 1) {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(c @ ('\\' | '"' | '\'')) => *c,
+                    _ => return Err(parse_error("invalid escape sequence".to_string())),
+                };
+                literal.push(escaped);
+                i += 2;
+            }
+            c => {
+                literal.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(parse_error("unterminated string".to_string()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Macro {
+    All,
+    Exists,
+    ExistsOne,
+    Filter,
+    Map,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    Ident(String),
+    Select(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Has(Box<Expr>, String),
+    Call(String, Option<Box<Expr>>, Vec<Expr>),
+    Macro(Macro, Box<Expr>, String, Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+// A recursive descent parser of CEL's grammar
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        match self.peek() {
+            Some(token) => parse_error(format!("unexpected '{}'", token)),
+            None => parse_error("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let condition = self.or()?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.or()?;
+        self.expect(":")?;
+        let otherwise = self.expr()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat("||") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut lhs = self.relation()?;
+        while self.eat("&&") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.relation()?));
+        }
+        Ok(lhs)
+    }
+
+    fn relation(&mut self) -> Result<Expr> {
+        let mut lhs = self.addition()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::In) => BinaryOp::In,
+                Some(Token::Punct("==")) => BinaryOp::Eq,
+                Some(Token::Punct("!=")) => BinaryOp::Ne,
+                Some(Token::Punct("<")) => BinaryOp::Lt,
+                Some(Token::Punct("<=")) => BinaryOp::Le,
+                Some(Token::Punct(">")) => BinaryOp::Gt,
+                Some(Token::Punct(">=")) => BinaryOp::Ge,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.addition()?));
+        }
+    }
+
+    fn addition(&mut self) -> Result<Expr> {
+        let mut lhs = self.multiplication()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("+")) => BinaryOp::Add,
+                Some(Token::Punct("-")) => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.multiplication()?));
+        }
+    }
+
+    fn multiplication(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("*")) => BinaryOp::Mul,
+                Some(Token::Punct("/")) => BinaryOp::Div,
+                Some(Token::Punct("%")) => BinaryOp::Rem,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.member()
+    }
+
+    fn member(&mut self) -> Result<Expr> {
+        let mut operand = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let name = self.ident()?;
+                operand = match self.eat("(") {
+                    true => self.method(operand, name)?,
+                    false => Expr::Select(Box::new(operand), name),
+                };
+            } else if self.eat("[") {
+                let index = self.expr()?;
+                self.expect("]")?;
+                operand = Expr::Index(Box::new(operand), Box::new(index));
+            } else {
+                return Ok(operand);
+            }
+        }
+    }
+
+    // Parses the arguments of the method `name` of `target`, after its `(`
+    fn method(&mut self, target: Expr, name: String) -> Result<Expr> {
+        let kind = match name.as_str() {
+            "all" => Some(Macro::All),
+            "exists" => Some(Macro::Exists),
+            "exists_one" => Some(Macro::ExistsOne),
+            "filter" => Some(Macro::Filter),
+            "map" => Some(Macro::Map),
+            _ => None,
+        };
+        match kind {
+            Some(kind) => {
+                let var = self.ident()?;
+                self.expect(",")?;
+                let body = self.expr()?;
+                self.expect(")")?;
+                Ok(Expr::Macro(kind, Box::new(target), var, Box::new(body)))
+            }
+            None => Ok(Expr::Call(name, Some(Box::new(target)), self.args()?)),
+        }
+    }
+
+    // Parses comma-separated arguments, up to and including `)`
+    fn args(&mut self) -> Result<Vec<Expr>> {
+        self.list(")")
+    }
+
+    fn list(&mut self, close: &str) -> Result<Vec<Expr>> {
+        let mut items = vec![];
+        if self.eat(close) {
+            return Ok(items);
+        }
+        loop {
+            items.push(self.expr()?);
+            if self.eat(close) {
+                return Ok(items);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected());
+        };
+        self.pos += 1;
+        match token {
+            Token::Null => Ok(Expr::Literal(Value::Null)),
+            Token::Bool(b) => Ok(Expr::Literal(Value::Bool(b))),
+            Token::Int(i) => Ok(Expr::Literal(Value::from(i))),
+            Token::Double(d) => Ok(Expr::Literal(Value::from(d))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Punct("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Punct("[") => Ok(Expr::List(self.list("]")?)),
+            Token::Ident(name) if name == "has" => {
+                self.expect("(")?;
+                let Expr::Select(operand, field) = self.expr()? else {
+                    return Err(parse_error("has() requires a field selection".to_string()));
+                };
+                self.expect(")")?;
+                Ok(Expr::Has(operand, field))
+            }
+            Token::Ident(name) => match self.eat("(") {
+                true => Ok(Expr::Call(name, None, self.args()?)),
+                false => Ok(Expr::Ident(name)),
+            },
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+// The variables in scope: the activation, and the variables bound by macros
+struct Scope<'a> {
+    activation: &'a Value,
+    bindings: Vec<(String, Value)>,
+}
+
+impl Scope<'_> {
+    fn eval(&mut self, expr: &Expr) -> Result<Value> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::List(items) => Ok(Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.eval(item))
+                    .collect::<Result<_>>()?,
+            )),
+            Expr::Ident(name) => self.lookup(name),
+            Expr::Select(operand, field) => match self.eval(operand)? {
+                Value::Object(mut fields) => fields
+                    .remove(field)
+                    .ok_or_else(|| eval_error(format!("No such field {}", field))),
+                value => Err(eval_error(format!(
+                    "Cannot select field {} of {}",
+                    field, value
+                ))),
+            },
+            Expr::Index(operand, index) => index_value(self.eval(operand)?, self.eval(index)?),
+            Expr::Has(operand, field) => match self.eval(operand)? {
+                Value::Object(fields) => Ok(Value::Bool(fields.contains_key(field))),
+                value => Err(eval_error(format!(
+                    "Cannot test field {} of {}",
+                    field, value
+                ))),
+            },
+            Expr::Call(name, target, args) => {
+                let target = target.as_ref().map(|t| self.eval(t)).transpose()?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                call(name, target, args)
+            }
+            Expr::Macro(kind, target, var, body) => self.eval_macro(*kind, target, var, body),
+            Expr::Not(operand) => Ok(Value::Bool(!as_bool(&self.eval(operand)?)?)),
+            Expr::Neg(operand) => match self.eval(operand)? {
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => i
+                        .checked_neg()
+                        .map(Value::from)
+                        .ok_or_else(|| eval_error("Integer overflow".to_string())),
+                    None => Ok(Value::from(-n.as_f64().unwrap_or_default())),
+                },
+                value => Err(eval_error(format!("Cannot negate {}", value))),
+            },
+            Expr::Binary(op, lhs, rhs) => binary(*op, self.eval(lhs)?, self.eval(rhs)?),
+            Expr::And(lhs, rhs) => self.logical(lhs, rhs, false),
+            Expr::Or(lhs, rhs) => self.logical(lhs, rhs, true),
+            Expr::Conditional(condition, then, otherwise) => {
+                match as_bool(&self.eval(condition)?)? {
+                    true => self.eval(then),
+                    false => self.eval(otherwise),
+                }
+            }
+        }
+    }
+
+    // Evaluates `lhs && rhs` (or `lhs || rhs` if `absorbing` is true): like
+    // CEL's, the logical operators are commutative, and an `absorbing`
+    // operand absorbs the other's error
+    fn logical(&mut self, lhs: &Expr, rhs: &Expr, absorbing: bool) -> Result<Value> {
+        let lhs = self.eval(lhs).and_then(|v| as_bool(&v));
+        if matches!(lhs, Ok(b) if b == absorbing) {
+            return Ok(Value::Bool(absorbing));
+        }
+        let rhs = self.eval(rhs).and_then(|v| as_bool(&v));
+        if matches!(rhs, Ok(b) if b == absorbing) {
+            return Ok(Value::Bool(absorbing));
+        }
+        lhs?;
+        rhs?;
+        Ok(Value::Bool(!absorbing))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Value> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| self.activation.get(name).cloned())
+            .ok_or_else(|| eval_error(format!("Unknown variable {}", name)))
+    }
+
+    fn eval_macro(&mut self, kind: Macro, target: &Expr, var: &str, body: &Expr) -> Result<Value> {
+        // macros iterate over the elements of lists, and the keys of maps
+        let items = match self.eval(target)? {
+            Value::Array(items) => items,
+            Value::Object(fields) => fields
+                .into_iter()
+                .map(|(key, _)| Value::String(key))
+                .collect(),
+            value => return Err(eval_error(format!("Cannot iterate over {}", value))),
+        };
+
+        let mut results = vec![];
+        for item in &items {
+            self.bindings.push((var.to_string(), item.clone()));
+            let result = self.eval(body);
+            self.bindings.pop();
+            results.push(match kind {
+                Macro::Map => result?,
+                _ => Value::Bool(as_bool(&result?)?),
+            });
+        }
+
+        let passed = |value: &Value| *value == Value::Bool(true);
+        Ok(match kind {
+            Macro::All => Value::Bool(results.iter().all(passed)),
+            Macro::Exists => Value::Bool(results.iter().any(passed)),
+            Macro::ExistsOne => Value::Bool(results.iter().filter(|v| passed(v)).count() == 1),
+            Macro::Filter => Value::Array(
+                items
+                    .into_iter()
+                    .zip(&results)
+                    .filter(|(_, result)| passed(result))
+                    .map(|(item, _)| item)
+                    .collect(),
+            ),
+            Macro::Map => Value::Array(results),
+        })
+    }
+}
+
+fn as_bool(value: &Value) -> Result<bool> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        value => Err(eval_error(format!("Expected a boolean, got {}", value))),
+    }
+}
+
+fn index_value(operand: Value, index: Value) -> Result<Value> {
+    match (operand, &index) {
+        (Value::Array(items), Value::Number(n)) => n
+            .as_u64()
+            .and_then(|i| items.into_iter().nth(i as usize))
+            .ok_or_else(|| eval_error(format!("Index {} out of range", n))),
+        (Value::Object(mut fields), Value::String(key)) => fields
+            .remove(key)
+            .ok_or_else(|| eval_error(format!("No such key {}", key))),
+        (operand, _) => Err(eval_error(format!(
+            "Cannot index {} with {}",
+            operand, index
+        ))),
+    }
+}
+
+fn call(name: &str, target: Option<Value>, args: Vec<Value>) -> Result<Value> {
+    // global functions take their target as their first argument
+    let mut operands: Vec<Value> = target.into_iter().chain(args).collect();
+    let arity = operands.len();
+    let no_overload = |operands: &[Value]| {
+        let types: Vec<&str> = operands.iter().map(type_name).collect();
+        eval_error(format!(
+            "No overload of {}() for ({})",
+            name,
+            types.join(", ")
+        ))
+    };
+
+    match (name, operands.as_mut_slice()) {
+        ("size", [Value::String(s)]) => Ok(Value::from(s.chars().count())),
+        ("size", [Value::Array(items)]) => Ok(Value::from(items.len())),
+        ("size", [Value::Object(fields)]) => Ok(Value::from(fields.len())),
+        ("int", [Value::Number(n)]) => match n.as_i64() {
+            Some(i) => Ok(Value::from(i)),
+            None => Ok(Value::from(n.as_f64().unwrap_or_default().trunc() as i64)),
+        },
+        ("int", [Value::String(s)]) => s
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| eval_error(format!("Cannot convert {:?} to int", s))),
+        ("string", [Value::String(s)]) => Ok(Value::String(std::mem::take(s))),
+        ("string", [value @ (Value::Number(_) | Value::Bool(_))]) => {
+            Ok(Value::String(value.to_string()))
+        }
+        ("startsWith", [Value::String(s), Value::String(prefix)]) => {
+            Ok(Value::Bool(s.starts_with(prefix.as_str())))
+        }
+        ("endsWith", [Value::String(s), Value::String(suffix)]) => {
+            Ok(Value::Bool(s.ends_with(suffix.as_str())))
+        }
+        ("contains", [Value::String(s), Value::String(substring)]) => {
+            Ok(Value::Bool(s.contains(substring.as_str())))
+        }
+        ("lowerAscii", [Value::String(s)]) => Ok(Value::String(s.to_ascii_lowercase())),
+        ("size" | "int" | "string" | "lowerAscii", _) if arity != 1 => Err(eval_error(format!(
+            "{}() takes 1 argument, got {}",
+            name, arity
+        ))),
+        ("size" | "int" | "string" | "startsWith" | "endsWith" | "contains" | "lowerAscii", _) => {
+            Err(no_overload(&operands))
+        }
+        _ => Err(eval_error(format!("Unknown function {}()", name))),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "double",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "map",
+    }
+}
+
+fn binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value> {
+    let mismatch = |lhs: &Value, rhs: &Value| {
+        eval_error(format!(
+            "No overload of {:?} for ({}, {})",
+            op,
+            type_name(lhs),
+            type_name(rhs)
+        ))
+    };
+
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(equals(&lhs, &rhs))),
+        BinaryOp::Ne => Ok(Value::Bool(!equals(&lhs, &rhs))),
+        BinaryOp::In => match &rhs {
+            Value::Array(items) => Ok(Value::Bool(items.iter().any(|item| equals(&lhs, item)))),
+            Value::Object(fields) => match &lhs {
+                Value::String(key) => Ok(Value::Bool(fields.contains_key(key))),
+                _ => Err(mismatch(&lhs, &rhs)),
+            },
+            _ => Err(mismatch(&lhs, &rhs)),
+        },
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&lhs, &rhs) {
+                (Value::Number(a), Value::Number(b)) => compare_numbers(a, b),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+                _ => return Err(mismatch(&lhs, &rhs)),
+            };
+            let Some(ordering) = ordering else {
+                // NaN
+                return Ok(Value::Bool(false));
+            };
+            Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        BinaryOp::Add => match (lhs, rhs) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+            (Value::Array(mut a), Value::Array(b)) => {
+                a.extend(b);
+                Ok(Value::Array(a))
+            }
+            (Value::Number(a), Value::Number(b)) => arithmetic(op, &a, &b),
+            (lhs, rhs) => Err(mismatch(&lhs, &rhs)),
+        },
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => match (&lhs, &rhs) {
+            (Value::Number(a), Value::Number(b)) => arithmetic(op, a, b),
+            _ => Err(mismatch(&lhs, &rhs)),
+        },
+    }
+}
+
+fn equals(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => compare_numbers(a, b).is_some_and(|o| o.is_eq()),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equals(a, b))
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn compare_numbers(a: &Number, b: &Number) -> Option<std::cmp::Ordering> {
+    match (a.as_i64(), b.as_i64()) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+fn arithmetic(op: BinaryOp, a: &Number, b: &Number) -> Result<Value> {
+    // integer arithmetic is checked, and double arithmetic is IEEE 754's
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        let result = match op {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Sub => a.checked_sub(b),
+            BinaryOp::Mul => a.checked_mul(b),
+            BinaryOp::Div if b == 0 => return Err(eval_error("Division by zero".to_string())),
+            BinaryOp::Div => a.checked_div(b),
+            BinaryOp::Rem if b == 0 => return Err(eval_error("Modulus by zero".to_string())),
+            _ => a.checked_rem(b),
+        };
+        return result
+            .map(Value::from)
+            .ok_or_else(|| eval_error("Integer overflow".to_string()));
+    }
+
+    let (a, b) = (
+        a.as_f64().unwrap_or_default(),
+        b.as_f64().unwrap_or_default(),
+    );
+    let result = match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        _ => return Err(eval_error("No overload of Rem for doubles".to_string())),
+    };
+    Number::from_f64(result)
+        .map(Value::Number)
+        .ok_or_else(|| eval_error(format!("Result {} is not a finite number", result)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> Result<Value> {
+        let activation = json!({
+            "report": {"td_info": {"attributes": {"debug": false}, "mrtd": "ab"}},
+            "tcb": {"status": "UpToDate", "advisories": ["INTEL-SA-00837"]},
+            "checks": {"quote-signature": true, "nonce": false},
+        });
+        Expression::parse(source)?.eval(&activation)
+    }
+
+    #[test]
+    fn test_evaluate() -> Result<()> {
+        let cases = [
+            (
+                r#"report.td_info.attributes.debug == false && tcb.status == "UpToDate""#,
+                json!(true),
+            ),
+            ("1 + 2 * 3 - 8 / 4 % 3", json!(5)),
+            ("-(1.5 * 2.0)", json!(-3.0)),
+            ("1 == 1.0 && 2 < 2.5", json!(true)),
+            ("'a' + \"b\" == 'ab' && 'abc' < 'abd'", json!(true)),
+            (
+                r#"tcb.status in ["UpToDate", "SWHardeningNeeded"]"#,
+                json!(true),
+            ),
+            (r#""INTEL-SA-00837" in tcb.advisories"#, json!(true)),
+            (r#"checks["quote-signature"] && !checks.nonce"#, json!(true)),
+            ("'nonce' in checks", json!(true)),
+            ("has(tcb.status) && !has(tcb.date)", json!(true)),
+            (
+                "size(tcb.advisories) == 1 && tcb.advisories.size() == 1",
+                json!(true),
+            ),
+            ("checks.all(c, c == 'nonce' || checks[c])", json!(true)),
+            ("checks.exists_one(c, checks[c])", json!(true)),
+            ("[1, 2, 3].filter(x, x > 1).map(x, x * 10)", json!([20, 30])),
+            ("[1, 2].exists(x, x == 3)", json!(false)),
+            (
+                "tcb.status.startsWith('Up') && tcb.status.lowerAscii().endsWith('date')",
+                json!(true),
+            ),
+            (
+                "int('42') + int(1.9) == 43 && string(42) == '42'",
+                json!(true),
+            ),
+            (
+                "report.td_info.mrtd.contains('b') ? 'yes' : 'no'",
+                json!("yes"),
+            ),
+            ("0x10 == 16u && null == null", json!(true)),
+            // a false operand absorbs the other's error
+            ("false && unknown.field", json!(false)),
+            ("unknown.field || true", json!(true)),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(eval(source)?, expected, "{}", source);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        for source in [
+            "unknown",
+            "tcb.date",
+            "true && unknown",
+            "1 / 0",
+            "1 + 'a'",
+            "tcb.status < 1",
+            "[1][5]",
+            "matches('a', 'b')",
+            "size(1)",
+            "!1",
+            "9223372036854775807 + 1",
+        ] {
+            assert!(eval(source).is_err(), "{}", source);
+        }
+
+        let expression = Expression::parse("1 + 1").unwrap();
+        assert!(expression.evaluate(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in [
+            "",
+            "1 +",
+            "(1",
+            "a.",
+            "'unterminated",
+            "a ? b",
+            "has(a)",
+            "1 2",
+            "a # b",
+            "{}",
+            "[1,]",
+        ] {
+            assert!(Expression::parse(source).is_err(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_cel_rule() -> Result<()> {
+        let rule = CelRule::new("no-debug", "!report.td_info.attributes.debug")?;
+        assert_eq!(rule.expression.source(), "!report.td_info.attributes.debug");
+        let toml: CelRule = toml::from_str(
+            "name = \"no-debug\"\nexpression = \"!report.td_info.attributes.debug\"",
+        )
+        .unwrap();
+        assert_eq!(toml, rule);
+
+        assert!(CelRule::new("broken", "a &&").is_err());
+        assert!(toml::from_str::<CelRule>("name = \"broken\"\nexpression = \"a &&\"").is_err());
+        Ok(())
+    }
+}
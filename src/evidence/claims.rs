@@ -0,0 +1,186 @@
+//! # Custom Claims
+//!
+//! Relying parties can register claim extractors with a `Policy`, which pull
+//! application-specific values out of an evidence bundle (e.g., a workload
+//! identity bound into the `report_data`, or the version of a container image
+//! in the event log) and expose them to the policy engine as named claims.
+//! This enables application-specific rules without forking the crate.
+//!
+//! `Bundle::verify()` runs the extractors as its `claims` check, which fails
+//! if any of them fails, and exposes the claims to CEL rules as the `claims`
+//! variable (see the `cel` module), and to Rego policies as `input.claims`
+//! (see the `rego` module). Extractors that find no claim in a bundle return
+//! `None`, and their claim is left out, so that rules can test for it (e.g.,
+//! `has(claims.workload)`).
+//!
+//! # Notes
+//! - The quote, and thus its `report_data`, is authenticated by the
+//!   `quote-signature` check, and the event log by the `event-log` check:
+//!   rules over claims should require these checks to pass (e.g.,
+//!   `checks["event-log"]`), unless all checks are required anyway.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::evidence::cel::CelRule;
+//! use tdx_workload_attestation::evidence::claims::report_data_field;
+//! use tdx_workload_attestation::evidence::{Bundle, Policy, quote::Quote};
+//! use tdx_workload_attestation::measure::event_log::EventPayload;
+//!
+//! let policy = Policy::new()
+//!     // the digest of the workload's public key, bound after the nonce's
+//!     .with_claim_extractor("key_digest", report_data_field(32..64))
+//!     // the references of the measured container images
+//!     .with_claim_extractor("images", |bundle: &Bundle, _: &Quote| {
+//!         let images: Vec<&str> = bundle
+//!             .event_log
+//!             .iter()
+//!             .filter_map(|event| match &event.payload {
+//!                 EventPayload::ContainerImage(image) => Some(image.reference.as_str()),
+//!                 _ => None,
+//!             })
+//!             .collect();
+//!         Ok(Some(serde_json::json!(images)))
+//!     })
+//!     .with_cel_rule(
+//!         CelRule::new("images", r#"claims.images.all(i, i.startsWith("registry.example.com/"))"#)
+//!             .unwrap(),
+//!     );
+//! assert_eq!(policy.claim_extractors.names(), vec!["images", "key_digest"]);
+//! ```
+
+use crate::core::quote::Quote;
+use crate::error::{Error, Result};
+use crate::evidence::Bundle;
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Extracts a named claim from evidence bundles.
+pub trait ClaimExtractor: Send + Sync {
+    /// Returns the claim of `bundle`, whose parsed quote is `quote`, or
+    /// `None` if the bundle has no such claim.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim is malformed, which fails the `claims`
+    /// check of `Bundle::verify()`.
+    fn extract(&self, bundle: &Bundle, quote: &Quote) -> Result<Option<Value>>;
+}
+
+impl<F> ClaimExtractor for F
+where
+    F: Fn(&Bundle, &Quote) -> Result<Option<Value>> + Send + Sync,
+{
+    fn extract(&self, bundle: &Bundle, quote: &Quote) -> Result<Option<Value>> {
+        self(bundle, quote)
+    }
+}
+
+/// Returns an extractor of the hex-encoded `range` of the quote's
+/// `report_data` (e.g., a digest the workload binds after the nonce's).
+pub fn report_data_field(range: Range<usize>) -> impl ClaimExtractor {
+    move |_: &Bundle, quote: &Quote| match quote.body.report_data.get(range.clone()) {
+        Some(field) => Ok(Some(Value::String(hex::encode(field)))),
+        None => Err(Error::ParseError(format!(
+            "Range {:?} is outside the 64-byte report_data",
+            range
+        ))),
+    }
+}
+
+/// The claim extractors of a policy, by claim name.
+#[derive(Clone, Default)]
+pub struct ClaimExtractors(BTreeMap<String, Arc<dyn ClaimExtractor>>);
+
+impl ClaimExtractors {
+    /// Creates an empty set of extractors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `extractor` for the claim `name`, replacing any previous
+    /// extractor of that claim.
+    pub fn register(&mut self, name: &str, extractor: impl ClaimExtractor + 'static) {
+        self.0.insert(name.to_string(), Arc::new(extractor));
+    }
+
+    /// Returns whether no extractors are registered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the names of the claims, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+
+    /// Extracts the claims of `bundle`, whose parsed quote is `quote`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` naming the first claim whose
+    /// extractor fails.
+    pub fn extract(&self, bundle: &Bundle, quote: &Quote) -> Result<Map<String, Value>> {
+        let mut claims = Map::new();
+        for (name, extractor) in &self.0 {
+            let claim = extractor.extract(bundle, quote).map_err(|e| {
+                Error::VerificationError(format!("Cannot extract claim {}: {}", name, e))
+            })?;
+            if let Some(claim) = claim {
+                claims.insert(name.clone(), claim);
+            }
+        }
+        Ok(claims)
+    }
+}
+
+impl fmt::Debug for ClaimExtractors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+
+    fn bundle() -> (Bundle, Quote) {
+        let bytes = QuoteParts::default().assemble(&[6; 64], &[7; 64]);
+        let quote = Quote::from_bytes(&bytes).unwrap();
+        (Bundle::new(b"nonce", bytes), quote)
+    }
+
+    #[test]
+    fn test_extract_claims() -> Result<()> {
+        let (bundle, quote) = bundle();
+        let mut extractors = ClaimExtractors::new();
+        extractors.register("key_digest", report_data_field(32..64));
+        extractors.register("version", |_: &Bundle, quote: &Quote| {
+            Ok(Some(Value::from(quote.version)))
+        });
+        extractors.register("absent", |_: &Bundle, _: &Quote| Ok(None));
+        assert_eq!(extractors.names(), vec!["absent", "key_digest", "version"]);
+        assert_eq!(
+            format!("{:?}", extractors),
+            r#"{"absent", "key_digest", "version"}"#
+        );
+
+        let claims = extractors.extract(&bundle, &quote)?;
+        assert_eq!(claims.len(), 2);
+        assert_eq!(
+            claims["key_digest"],
+            Value::String(hex::encode(&quote.body.report_data[32..]))
+        );
+        assert_eq!(claims["version"], Value::from(quote.version));
+
+        extractors.register("broken", report_data_field(60..70));
+        let e = extractors.extract(&bundle, &quote).unwrap_err();
+        assert!(e.to_string().contains("broken"), "{}", e);
+        Ok(())
+    }
+}
@@ -0,0 +1,631 @@
+//! # Evidence Exchange Schema
+//!
+//! This module converts evidence bundles and appraisal verdicts to and from
+//! the versioned protobuf schema published in
+//! `proto/evidence/v1/evidence.proto`, so that this crate and Go-based
+//! verifiers (e.g., go-tdx-guest consumers, using the schema's generated Go
+//! package) can exchange evidence over gRPC or as JSON.
+//!
+//! The `v1` module, which holds the schema's message types, is generated from
+//! the schema at build time.
+//!
+//! Messages are encoded either in the protobuf binary encoding, which this
+//! crate emits in field number order (as Go's deterministic marshaling does),
+//! or in the canonical proto3 JSON mapping (as Go's `protojson` does).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Bundle;
+//!
+//! let bytes = std::fs::read("bundle.cbor").unwrap();
+//! let bundle = Bundle::from_bytes(&bytes).unwrap();
+//!
+//! // Send the bundle to a Go verifier
+//! let json = bundle.to_proto_json().unwrap();
+//! println!("{}", json);
+//!
+//! // Round-trip through the protobuf binary encoding
+//! let proto = bundle.to_proto_bytes().unwrap();
+//! assert_eq!(Bundle::from_proto_bytes(&proto).unwrap(), bundle);
+//! ```
+
+#[path = "evidence.rs"]
+pub mod v1;
+
+use crate::error::{Error, Result};
+use crate::evidence::boot_session::BootSession;
+use crate::evidence::tcb::{Advisory, Mitigation};
+use crate::evidence::{BUNDLE_VERSION, Bundle, Check, Endorsement, Verdict};
+use crate::measure::SHA384_LEN;
+use crate::measure::container::ImageMeasurement;
+use crate::measure::event_log::{Event, EventPayload};
+use crate::platform::{CloudProvider, PlatformCapabilities, VirtualizationEnvironment};
+
+use protobuf::{EnumOrUnknown, Message, MessageFull};
+
+impl From<&Bundle> for v1::Bundle {
+    fn from(bundle: &Bundle) -> Self {
+        Self {
+            version: bundle.version,
+            nonce: bundle.nonce.clone(),
+            quote: bundle.quote.clone(),
+            ccel: bundle.ccel.clone(),
+            event_log: bundle.event_log.iter().map(v1::Event::from).collect(),
+            pck_chain: bundle.pck_chain.clone(),
+            endorsement: bundle
+                .endorsement
+                .as_ref()
+                .map(|e| v1::Endorsement {
+                    provider: e.provider.clone(),
+                    data: e.data.clone(),
+                    log_entry: e.log_entry.clone(),
+                    ..Default::default()
+                })
+                .into(),
+            platform: bundle
+                .platform
+                .as_ref()
+                .map(v1::PlatformCapabilities::from)
+                .into(),
+            timestamp: bundle.timestamp.clone(),
+            session: bundle
+                .session
+                .as_ref()
+                .map(|s| v1::BootSession {
+                    id: s.id.clone(),
+                    boot_time: s.boot_time,
+                    ..Default::default()
+                })
+                .into(),
+            aux_blob: bundle.aux_blob.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&v1::Bundle> for Bundle {
+    type Error = Error;
+
+    /// Converts a protobuf bundle, checking its version and event digests'
+    /// lengths.
+    fn try_from(bundle: &v1::Bundle) -> Result<Self> {
+        if bundle.version != BUNDLE_VERSION {
+            return Err(Error::ParseError(format!(
+                "Unsupported evidence bundle version {}",
+                bundle.version
+            )));
+        }
+
+        Ok(Self {
+            version: bundle.version,
+            nonce: bundle.nonce.clone(),
+            quote: bundle.quote.clone(),
+            ccel: bundle.ccel.clone(),
+            event_log: bundle
+                .event_log
+                .iter()
+                .map(Event::try_from)
+                .collect::<Result<_>>()?,
+            pck_chain: bundle.pck_chain.clone(),
+            endorsement: bundle.endorsement.as_ref().map(|e| Endorsement {
+                provider: e.provider.clone(),
+                data: e.data.clone(),
+                log_entry: e.log_entry.clone(),
+            }),
+            platform: bundle
+                .platform
+                .as_ref()
+                .map(PlatformCapabilities::try_from)
+                .transpose()?,
+            timestamp: bundle.timestamp.clone(),
+            session: bundle.session.as_ref().map(|s| BootSession {
+                id: s.id.clone(),
+                boot_time: s.boot_time,
+            }),
+            aux_blob: bundle.aux_blob.clone(),
+        })
+    }
+}
+
+impl From<&Event> for v1::Event {
+    fn from(event: &Event) -> Self {
+        use v1::event::Payload;
+
+        let payload = match &event.payload {
+            EventPayload::Ima { line } => Payload::Ima(v1::ImaPayload {
+                line: line.clone(),
+                ..Default::default()
+            }),
+            EventPayload::ContainerImage(image) => {
+                Payload::ContainerImage(v1::ContainerImagePayload {
+                    reference: image.reference.clone(),
+                    manifest: image.manifest.clone(),
+                    config: image.config.clone(),
+                    layers: image.layers.clone(),
+                    ..Default::default()
+                })
+            }
+            EventPayload::BootCmdline { cmdline } => Payload::BootCmdline(v1::BootCmdlinePayload {
+                cmdline: cmdline.clone(),
+                ..Default::default()
+            }),
+            EventPayload::BootFile { path, digest } => Payload::BootFile(v1::BootFilePayload {
+                path: path.clone(),
+                digest: digest.clone(),
+                ..Default::default()
+            }),
+            EventPayload::BootDirectory { path, digest } => {
+                Payload::BootDirectory(v1::BootDirectoryPayload {
+                    path: path.clone(),
+                    digest: digest.clone(),
+                    ..Default::default()
+                })
+            }
+            EventPayload::Exec { path, digest, args } => Payload::Exec(v1::ExecPayload {
+                path: path.clone(),
+                digest: digest.clone(),
+                args: args.clone(),
+                ..Default::default()
+            }),
+            EventPayload::Custom { event_type, data } => Payload::Custom(v1::CustomPayload {
+                event_type: event_type.clone(),
+                data: data.clone(),
+                ..Default::default()
+            }),
+        };
+
+        Self {
+            index: event.index,
+            rtmr: event.rtmr.into(),
+            digest: event.digest.to_vec(),
+            payload: Some(payload),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&v1::Event> for Event {
+    type Error = Error;
+
+    fn try_from(event: &v1::Event) -> Result<Self> {
+        use v1::event::Payload;
+
+        let rtmr = u8::try_from(event.rtmr)
+            .map_err(|_| Error::ParseError(format!("Invalid event RTMR {}", event.rtmr)))?;
+        let digest: [u8; SHA384_LEN] = event.digest.as_slice().try_into().map_err(|_| {
+            Error::ParseError(format!(
+                "Invalid event digest length {}",
+                event.digest.len()
+            ))
+        })?;
+
+        let payload = match &event.payload {
+            Some(Payload::Ima(p)) => EventPayload::Ima {
+                line: p.line.clone(),
+            },
+            Some(Payload::ContainerImage(p)) => EventPayload::ContainerImage(ImageMeasurement {
+                reference: p.reference.clone(),
+                manifest: p.manifest.clone(),
+                config: p.config.clone(),
+                layers: p.layers.clone(),
+            }),
+            Some(Payload::BootCmdline(p)) => EventPayload::BootCmdline {
+                cmdline: p.cmdline.clone(),
+            },
+            Some(Payload::BootFile(p)) => EventPayload::BootFile {
+                path: p.path.clone(),
+                digest: p.digest.clone(),
+            },
+            Some(Payload::BootDirectory(p)) => EventPayload::BootDirectory {
+                path: p.path.clone(),
+                digest: p.digest.clone(),
+            },
+            Some(Payload::Exec(p)) => EventPayload::Exec {
+                path: p.path.clone(),
+                digest: p.digest.clone(),
+                args: p.args.clone(),
+            },
+            Some(Payload::Custom(p)) => EventPayload::Custom {
+                event_type: p.event_type.clone(),
+                data: p.data.clone(),
+            },
+            None => {
+                return Err(Error::ParseError(format!(
+                    "Event {} has no supported payload",
+                    event.index
+                )));
+            }
+        };
+
+        Ok(Self {
+            index: event.index,
+            rtmr,
+            digest,
+            payload,
+        })
+    }
+}
+
+impl From<&PlatformCapabilities> for v1::PlatformCapabilities {
+    fn from(caps: &PlatformCapabilities) -> Self {
+        let cloud_provider = match caps.cloud_provider {
+            None => v1::CloudProvider::CLOUD_PROVIDER_UNSPECIFIED,
+            Some(CloudProvider::Alibaba) => v1::CloudProvider::CLOUD_PROVIDER_ALIBABA,
+            Some(CloudProvider::Aws) => v1::CloudProvider::CLOUD_PROVIDER_AWS,
+            Some(CloudProvider::Azure) => v1::CloudProvider::CLOUD_PROVIDER_AZURE,
+            Some(CloudProvider::Gcp) => v1::CloudProvider::CLOUD_PROVIDER_GCP,
+        };
+        let virtualization = match caps.virtualization {
+            None => v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_UNSPECIFIED,
+            Some(VirtualizationEnvironment::Direct) => {
+                v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_DIRECT
+            }
+            Some(VirtualizationEnvironment::Paravisor) => {
+                v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_PARAVISOR
+            }
+            Some(VirtualizationEnvironment::Nested) => {
+                v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_NESTED
+            }
+        };
+
+        Self {
+            platform: caps.platform.clone(),
+            tdx_guest: caps.tdx_guest,
+            tdx_version: caps.tdx_version.clone(),
+            tdx_device_path: caps.tdx_device_path.clone(),
+            configfs_tsm: caps.configfs_tsm,
+            qgs_vsock_port: caps.qgs_vsock_port,
+            qgs_reachable: caps.qgs_reachable,
+            cloud_provider: EnumOrUnknown::new(cloud_provider),
+            vtpm: caps.vtpm,
+            virtualization: EnumOrUnknown::new(virtualization),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&v1::PlatformCapabilities> for PlatformCapabilities {
+    type Error = Error;
+
+    fn try_from(caps: &v1::PlatformCapabilities) -> Result<Self> {
+        let cloud_provider = match caps.cloud_provider.enum_value() {
+            Ok(v1::CloudProvider::CLOUD_PROVIDER_UNSPECIFIED) => None,
+            Ok(v1::CloudProvider::CLOUD_PROVIDER_ALIBABA) => Some(CloudProvider::Alibaba),
+            Ok(v1::CloudProvider::CLOUD_PROVIDER_AWS) => Some(CloudProvider::Aws),
+            Ok(v1::CloudProvider::CLOUD_PROVIDER_AZURE) => Some(CloudProvider::Azure),
+            Ok(v1::CloudProvider::CLOUD_PROVIDER_GCP) => Some(CloudProvider::Gcp),
+            Err(value) => {
+                return Err(Error::ParseError(format!(
+                    "Unknown cloud provider {}",
+                    value
+                )));
+            }
+        };
+        let virtualization = match caps.virtualization.enum_value() {
+            Ok(v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_UNSPECIFIED) => None,
+            Ok(v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_DIRECT) => {
+                Some(VirtualizationEnvironment::Direct)
+            }
+            Ok(v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_PARAVISOR) => {
+                Some(VirtualizationEnvironment::Paravisor)
+            }
+            Ok(v1::VirtualizationEnvironment::VIRTUALIZATION_ENVIRONMENT_NESTED) => {
+                Some(VirtualizationEnvironment::Nested)
+            }
+            Err(value) => {
+                return Err(Error::ParseError(format!(
+                    "Unknown virtualization environment {}",
+                    value
+                )));
+            }
+        };
+
+        Ok(Self {
+            platform: caps.platform.clone(),
+            tdx_guest: caps.tdx_guest,
+            tdx_version: caps.tdx_version.clone(),
+            tdx_device_path: caps.tdx_device_path.clone(),
+            configfs_tsm: caps.configfs_tsm,
+            qgs_vsock_port: caps.qgs_vsock_port,
+            qgs_reachable: caps.qgs_reachable,
+            cloud_provider,
+            vtpm: caps.vtpm,
+            virtualization,
+        })
+    }
+}
+
+impl From<&Verdict> for v1::Verdict {
+    fn from(verdict: &Verdict) -> Self {
+        Self {
+            checks: verdict
+                .checks
+                .iter()
+                .map(|c| v1::Check {
+                    name: c.name.clone(),
+                    passed: c.passed,
+                    detail: c.detail.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            passed: verdict.passed(),
+            advisories: verdict.advisories.iter().map(v1::Advisory::from).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&v1::Verdict> for Verdict {
+    /// Converts a protobuf verdict. The verdict's `passed` field is
+    /// recomputed from its checks, and not trusted.
+    fn from(verdict: &v1::Verdict) -> Self {
+        Self {
+            checks: verdict
+                .checks
+                .iter()
+                .map(|c| Check {
+                    name: c.name.clone(),
+                    passed: c.passed,
+                    detail: c.detail.clone(),
+                })
+                .collect(),
+            advisories: verdict.advisories.iter().map(Advisory::from).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Advisory> for v1::Advisory {
+    fn from(advisory: &Advisory) -> Self {
+        let mitigation = match advisory.mitigation {
+            None => v1::Mitigation::MITIGATION_UNSPECIFIED,
+            Some(Mitigation::PlatformUpdate) => v1::Mitigation::MITIGATION_PLATFORM_UPDATE,
+            Some(Mitigation::SoftwareHardening) => v1::Mitigation::MITIGATION_SOFTWARE_HARDENING,
+            Some(Mitigation::Configuration) => v1::Mitigation::MITIGATION_CONFIGURATION,
+            Some(Mitigation::ConfigurationAndSoftwareHardening) => {
+                v1::Mitigation::MITIGATION_CONFIGURATION_AND_SOFTWARE_HARDENING
+            }
+        };
+
+        Self {
+            id: advisory.id.clone(),
+            tcb_status: advisory.tcb_status.clone(),
+            title: advisory.title.clone(),
+            cves: advisory.cves.clone(),
+            mitigation: EnumOrUnknown::new(mitigation),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&v1::Advisory> for Advisory {
+    /// Converts a protobuf advisory. Unknown mitigations are dropped, since
+    /// advisories are informational.
+    fn from(advisory: &v1::Advisory) -> Self {
+        let mitigation = match advisory.mitigation.enum_value() {
+            Ok(v1::Mitigation::MITIGATION_PLATFORM_UPDATE) => Some(Mitigation::PlatformUpdate),
+            Ok(v1::Mitigation::MITIGATION_SOFTWARE_HARDENING) => {
+                Some(Mitigation::SoftwareHardening)
+            }
+            Ok(v1::Mitigation::MITIGATION_CONFIGURATION) => Some(Mitigation::Configuration),
+            Ok(v1::Mitigation::MITIGATION_CONFIGURATION_AND_SOFTWARE_HARDENING) => {
+                Some(Mitigation::ConfigurationAndSoftwareHardening)
+            }
+            Ok(v1::Mitigation::MITIGATION_UNSPECIFIED) | Err(_) => None,
+        };
+
+        Self {
+            id: advisory.id.clone(),
+            tcb_status: advisory.tcb_status.clone(),
+            title: advisory.title.clone(),
+            cves: advisory.cves.clone(),
+            mitigation,
+        }
+    }
+}
+
+impl Bundle {
+    /// Encodes the bundle in the protobuf binary encoding of the `v1` schema.
+    pub fn to_proto_bytes(&self) -> Result<Vec<u8>> {
+        Ok(v1::Bundle::from(self).write_to_bytes()?)
+    }
+
+    /// Decodes a bundle from the protobuf binary encoding of the `v1` schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ProtobufError` if the message is malformed, or an
+    /// `Error::ParseError` if the bundle is invalid or has an unsupported
+    /// version.
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::try_from(&v1::Bundle::parse_from_bytes(bytes)?)
+    }
+
+    /// Encodes the bundle in the proto3 JSON mapping of the `v1` schema.
+    pub fn to_proto_json(&self) -> Result<String> {
+        to_json(&v1::Bundle::from(self))
+    }
+
+    /// Decodes a bundle from the proto3 JSON mapping of the `v1` schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the JSON is malformed, or the bundle
+    /// is invalid or has an unsupported version.
+    pub fn from_proto_json(json: &str) -> Result<Self> {
+        Self::try_from(&from_json::<v1::Bundle>(json)?)
+    }
+}
+
+impl Verdict {
+    /// Encodes the verdict in the protobuf binary encoding of the `v1`
+    /// schema.
+    pub fn to_proto_bytes(&self) -> Result<Vec<u8>> {
+        Ok(v1::Verdict::from(self).write_to_bytes()?)
+    }
+
+    /// Decodes a verdict from the protobuf binary encoding of the `v1`
+    /// schema.
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::from(&v1::Verdict::parse_from_bytes(bytes)?))
+    }
+
+    /// Encodes the verdict in the proto3 JSON mapping of the `v1` schema.
+    pub fn to_proto_json(&self) -> Result<String> {
+        to_json(&v1::Verdict::from(self))
+    }
+
+    /// Decodes a verdict from the proto3 JSON mapping of the `v1` schema.
+    pub fn from_proto_json(json: &str) -> Result<Self> {
+        Ok(Self::from(&from_json::<v1::Verdict>(json)?))
+    }
+}
+
+/// Encodes a message in the proto3 JSON mapping.
+fn to_json(message: &dyn protobuf::MessageDyn) -> Result<String> {
+    protobuf_json_mapping::print_to_string(message)
+        .map_err(|e| Error::SerializationError(e.to_string()))
+}
+
+/// Decodes a message from the proto3 JSON mapping.
+fn from_json<M: MessageFull>(json: &str) -> Result<M> {
+    protobuf_json_mapping::parse_from_str(json)
+        .map_err(|e| Error::ParseError(format!("Invalid protobuf JSON: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::GCP_ENDORSEMENT_PROVIDER;
+    use protobuf::MessageField;
+
+    fn make_bundle() -> Bundle {
+        let mut bundle = Bundle::new(b"nonce", vec![1, 2, 3]).with_endorsement(Endorsement {
+            provider: GCP_ENDORSEMENT_PROVIDER.to_string(),
+            data: vec![4, 5],
+            log_entry: Some("{}".to_string()),
+        });
+        bundle.ccel = Some(vec![6]);
+        bundle.pck_chain = Some("pem".to_string());
+        bundle.event_log = vec![
+            Event::new(
+                3,
+                EventPayload::Custom {
+                    event_type: "test".to_string(),
+                    data: "app".to_string(),
+                },
+            ),
+            ImageMeasurement {
+                reference: "image".to_string(),
+                manifest: "sha256:00".to_string(),
+                config: "sha256:01".to_string(),
+                layers: vec!["sha256:02".to_string()],
+            }
+            .to_event(),
+        ];
+        bundle.event_log[1].index = 1;
+        bundle.platform = Some(PlatformCapabilities {
+            platform: "tdx-linux".to_string(),
+            tdx_guest: true,
+            tdx_version: Some("1.5".to_string()),
+            tdx_device_path: None,
+            configfs_tsm: true,
+            qgs_vsock_port: Some(4050),
+            qgs_reachable: None,
+            cloud_provider: Some(CloudProvider::Gcp),
+            vtpm: false,
+            virtualization: Some(VirtualizationEnvironment::Paravisor),
+        });
+        bundle.timestamp = Some(vec![7]);
+        bundle.aux_blob = Some(vec![8]);
+        bundle.with_session(BootSession {
+            id: "00".repeat(16),
+            boot_time: 1000,
+        })
+    }
+
+    #[test]
+    fn test_bundle_proto_round_trip() -> Result<()> {
+        let bundle = make_bundle();
+
+        let bytes = bundle.to_proto_bytes()?;
+        assert_eq!(Bundle::from_proto_bytes(&bytes)?, bundle);
+
+        let json = bundle.to_proto_json()?;
+        assert!(json.contains(r#""cloudProvider": "CLOUD_PROVIDER_GCP""#));
+        assert_eq!(Bundle::from_proto_json(&json)?, bundle);
+
+        // the encoding is deterministic
+        assert_eq!(Bundle::from_proto_json(&json)?.to_proto_bytes()?, bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_proto_invalid() {
+        let mut proto = v1::Bundle::from(&make_bundle());
+        proto.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::try_from(&proto).is_err());
+
+        let mut proto = v1::Bundle::from(&make_bundle());
+        proto.event_log[0].digest.pop();
+        assert!(Bundle::try_from(&proto).is_err());
+
+        let mut proto = v1::Bundle::from(&make_bundle());
+        proto.event_log[0].payload = None;
+        assert!(Bundle::try_from(&proto).is_err());
+
+        assert!(Bundle::from_proto_bytes(&[0xff]).is_err());
+        assert!(Bundle::from_proto_json("{").is_err());
+    }
+
+    #[test]
+    fn test_verdict_proto_round_trip() -> Result<()> {
+        let verdict = Verdict {
+            checks: vec![
+                Check {
+                    name: "nonce".to_string(),
+                    passed: true,
+                    detail: None,
+                },
+                Check {
+                    name: "debug".to_string(),
+                    passed: false,
+                    detail: Some("TD is a debug TD".to_string()),
+                },
+            ],
+            advisories: vec![Advisory {
+                id: "INTEL-SA-00837".to_string(),
+                tcb_status: "OutOfDate".to_string(),
+                title: None,
+                cves: vec!["CVE-2022-41804".to_string()],
+                mitigation: Some(Mitigation::PlatformUpdate),
+            }],
+            ..Default::default()
+        };
+
+        let proto = v1::Verdict::from(&verdict);
+        assert!(!proto.passed);
+
+        assert_eq!(
+            Verdict::from_proto_bytes(&verdict.to_proto_bytes()?)?,
+            verdict
+        );
+        assert_eq!(
+            Verdict::from_proto_json(&verdict.to_proto_json()?)?,
+            verdict
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proto_message_field() {
+        let mut bundle = make_bundle();
+        bundle.endorsement = None;
+        bundle.platform = None;
+
+        let proto = v1::Bundle::from(&bundle);
+        assert_eq!(proto.endorsement, MessageField::none());
+        assert_eq!(Bundle::try_from(&proto).unwrap(), bundle);
+    }
+}
@@ -0,0 +1,846 @@
+//! # Evidence Interoperability
+//!
+//! This module converts TD quotes and evidence bundles to and from the JSON
+//! formats of other TDX attestation tooling, so that fleets mixing this crate
+//! with Go-based tooling can share evidence artifacts:
+//! - `GoTdxGuestQuote`: the protojson encoding of go-tdx-guest's `QuoteV4`
+//!   message, as marshaled with `protojson` by its consumers, and
+//! - `TrustAuthorityEvidence`: the TDX evidence submitted by the Intel Trust
+//!   Authority client to the ITA appraisal API, and
+//! - `AzureMaaRequest`: the request body of Microsoft Azure Attestation's
+//!   (MAA) TDX VM attestation API.
+//!
+//! Raw quotes, as emitted by go-tdx-guest's `attest` tool by default, can be
+//! parsed directly with `Quote::from_bytes()`, and written in the raw format
+//! of Intel's DCAP sample tools (e.g., the `quote.dat` files of the Quote
+//! Verification Library's sample) with `to_intel_quote_dat()`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Bundle;
+//! use tdx_workload_attestation::evidence::interop::{GoTdxGuestQuote, TrustAuthorityEvidence};
+//!
+//! // Import a quote from go-tdx-guest
+//! let json = std::fs::read_to_string("quote.json").unwrap();
+//! let quote = GoTdxGuestQuote::from_json(&json).unwrap().to_quote().unwrap();
+//! println!("MRTD: {}", quote.body.mrtd);
+//!
+//! // Export a bundle as Intel Trust Authority evidence
+//! let bundle = Bundle::from_bytes(&std::fs::read("bundle.cbor").unwrap()).unwrap();
+//! let evidence = TrustAuthorityEvidence::from_bundle(&bundle);
+//! println!("{}", evidence.to_json().unwrap());
+//! ```
+//!
+//! # Notes
+//! - go-tdx-guest's `QuoteV4` message only supports version 4 quotes.
+//! - MAA checks that the quote's `report_data` starts with the SHA-256 digest
+//!   of the request's runtime data, so bundles exported for MAA must have been
+//!   collected with such a nonce (see `report_data::ReportDataBuilder`).
+
+use crate::error::{Error, Result};
+use crate::evidence::Bundle;
+use crate::evidence::quote::{
+    CERT_DATA_QE_REPORT, ECDSA_P256_KEY_TYPE, QE_REPORT_LEN, QUOTE_HEADER_LEN, Quote, TDX_TEE_TYPE,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The header of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestHeader {
+    /// The quote version.
+    pub version: u32,
+    /// The attestation key type.
+    pub attestation_key_type: u32,
+    /// The TEE type.
+    pub tee_type: u32,
+    /// The security version of the QE.
+    #[serde(with = "base64_bytes")]
+    pub qe_svn: Vec<u8>,
+    /// The security version of the Provisioning Certification Enclave (PCE).
+    #[serde(with = "base64_bytes")]
+    pub pce_svn: Vec<u8>,
+    /// The ID of the QE vendor.
+    #[serde(with = "base64_bytes")]
+    pub qe_vendor_id: Vec<u8>,
+    /// Custom data of the QE vendor.
+    #[serde(with = "base64_bytes")]
+    pub user_data: Vec<u8>,
+}
+
+/// The TD quote body of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestTdQuoteBody {
+    /// The TCB SVN of the TDX module.
+    #[serde(with = "base64_bytes")]
+    pub tee_tcb_svn: Vec<u8>,
+    /// The measurement of the TDX module.
+    #[serde(with = "base64_bytes")]
+    pub mr_seam: Vec<u8>,
+    /// The measurement of the TDX module's signer.
+    #[serde(with = "base64_bytes")]
+    pub mr_signer_seam: Vec<u8>,
+    /// The attributes of the TDX module.
+    #[serde(with = "base64_bytes")]
+    pub seam_attributes: Vec<u8>,
+    /// The attributes of the TD.
+    #[serde(with = "base64_bytes")]
+    pub td_attributes: Vec<u8>,
+    /// The extended features available to the TD.
+    #[serde(with = "base64_bytes")]
+    pub xfam: Vec<u8>,
+    /// The build-time measurement of the TD.
+    #[serde(with = "base64_bytes")]
+    pub mr_td: Vec<u8>,
+    /// The software-defined ID for non-owner-defined configuration.
+    #[serde(with = "base64_bytes")]
+    pub mr_config_id: Vec<u8>,
+    /// The software-defined ID for the TD's owner.
+    #[serde(with = "base64_bytes")]
+    pub mr_owner: Vec<u8>,
+    /// The software-defined ID for owner-defined configuration.
+    #[serde(with = "base64_bytes")]
+    pub mr_owner_config: Vec<u8>,
+    /// The runtime measurement registers.
+    #[serde(with = "base64_bytes_list")]
+    pub rtmrs: Vec<Vec<u8>>,
+    /// The data bound into the quote by the TD.
+    #[serde(with = "base64_bytes")]
+    pub report_data: Vec<u8>,
+}
+
+/// The QE report of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestEnclaveReport {
+    /// The CPU SVN.
+    #[serde(with = "base64_bytes")]
+    pub cpu_svn: Vec<u8>,
+    /// The extended features of the QE.
+    pub misc_select: u32,
+    /// Reserved.
+    #[serde(with = "base64_bytes")]
+    pub reserved1: Vec<u8>,
+    /// The attributes of the QE.
+    #[serde(with = "base64_bytes")]
+    pub attributes: Vec<u8>,
+    /// The measurement of the QE.
+    #[serde(with = "base64_bytes")]
+    pub mr_enclave: Vec<u8>,
+    /// Reserved.
+    #[serde(with = "base64_bytes")]
+    pub reserved2: Vec<u8>,
+    /// The measurement of the QE's signer.
+    #[serde(with = "base64_bytes")]
+    pub mr_signer: Vec<u8>,
+    /// Reserved.
+    #[serde(with = "base64_bytes")]
+    pub reserved3: Vec<u8>,
+    /// The product ID of the QE.
+    pub isv_prod_id: u32,
+    /// The security version of the QE.
+    pub isv_svn: u32,
+    /// Reserved.
+    #[serde(with = "base64_bytes")]
+    pub reserved4: Vec<u8>,
+    /// The data bound into the QE report (binding the attestation key).
+    #[serde(with = "base64_bytes")]
+    pub report_data: Vec<u8>,
+}
+
+/// The QE authentication data of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestQeAuthData {
+    /// The length of the authentication data.
+    pub parsed_data_size: u32,
+    /// The authentication data.
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// The PCK certificate chain data of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestPckCertificateChainData {
+    /// The type of the certification data.
+    pub certificate_data_type: u32,
+    /// The length of the certification data.
+    pub size: u32,
+    /// The certification data (e.g., the PEM-encoded PCK certificate chain).
+    #[serde(with = "base64_bytes")]
+    pub pck_cert_chain: Vec<u8>,
+}
+
+/// The QE report certification data of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestQeReportCertificationData {
+    /// The QE report.
+    pub qe_report: GoTdxGuestEnclaveReport,
+    /// The signature over the QE report, by the PCK.
+    #[serde(with = "base64_bytes")]
+    pub qe_report_signature: Vec<u8>,
+    /// The QE authentication data.
+    pub qe_auth_data: GoTdxGuestQeAuthData,
+    /// The PCK certification data.
+    pub pck_certificate_chain_data: GoTdxGuestPckCertificateChainData,
+}
+
+/// The certification data of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestCertificationData {
+    /// The type of the certification data.
+    pub certificate_data_type: u32,
+    /// The length of the certification data.
+    pub size: u32,
+    /// The QE report certification data.
+    pub qe_report_certification_data: GoTdxGuestQeReportCertificationData,
+}
+
+/// The signature data of a go-tdx-guest quote.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestSignedData {
+    /// The signature over the header and body, by the attestation key.
+    #[serde(with = "base64_bytes")]
+    pub signature: Vec<u8>,
+    /// The raw ECDSA P-256 attestation public key.
+    #[serde(with = "base64_bytes")]
+    pub ecdsa_attestation_key: Vec<u8>,
+    /// The certification data.
+    pub certification_data: GoTdxGuestCertificationData,
+}
+
+/// A TD quote, in the protojson encoding of go-tdx-guest's `QuoteV4`
+/// message.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GoTdxGuestQuote {
+    /// The quote header.
+    pub header: GoTdxGuestHeader,
+    /// The TD quote body.
+    pub td_quote_body: GoTdxGuestTdQuoteBody,
+    /// The length of the signature data.
+    pub signed_data_size: u32,
+    /// The signature data.
+    pub signed_data: GoTdxGuestSignedData,
+    /// Any bytes following the signature data.
+    #[serde(with = "base64_bytes")]
+    pub extra_bytes: Vec<u8>,
+}
+
+impl GoTdxGuestQuote {
+    /// Converts a parsed quote.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the quote isn't a version 4 quote.
+    pub fn from_quote(quote: &Quote) -> Result<Self> {
+        if quote.version != 4 {
+            return Err(Error::NotSupported(format!(
+                "go-tdx-guest quotes must be version 4, not {}",
+                quote.version
+            )));
+        }
+
+        let raw = quote.as_bytes();
+        let header = &raw[..QUOTE_HEADER_LEN];
+        let qe_report = &quote.qe_report;
+        let body = &quote.body;
+        let signed_data_size = raw.len() - quote.signed_data().len() - 4;
+        let qe_data_size =
+            QE_REPORT_LEN + 64 + 2 + quote.qe_auth_data.len() + 6 + quote.cert_data.len();
+
+        Ok(Self {
+            header: GoTdxGuestHeader {
+                version: quote.version.into(),
+                attestation_key_type: ECDSA_P256_KEY_TYPE.into(),
+                tee_type: TDX_TEE_TYPE,
+                qe_svn: header[8..10].to_vec(),
+                pce_svn: header[10..12].to_vec(),
+                qe_vendor_id: header[12..28].to_vec(),
+                user_data: header[28..48].to_vec(),
+            },
+            td_quote_body: GoTdxGuestTdQuoteBody {
+                tee_tcb_svn: body.tee_tcb_svn.to_vec(),
+                mr_seam: body.mrseam.to_vec(),
+                mr_signer_seam: body.mrsignerseam.to_vec(),
+                seam_attributes: body.seam_attributes.to_vec(),
+                td_attributes: body.td_attributes.to_vec(),
+                xfam: body.xfam.to_vec(),
+                mr_td: body.mrtd.to_vec(),
+                mr_config_id: body.mrconfigid.to_vec(),
+                mr_owner: body.mrowner.to_vec(),
+                mr_owner_config: body.mrownerconfig.to_vec(),
+                rtmrs: body.rtmrs.iter().map(|r| r.to_vec()).collect(),
+                report_data: body.report_data.to_vec(),
+            },
+            signed_data_size: signed_data_size as u32,
+            signed_data: GoTdxGuestSignedData {
+                signature: quote.signature.to_vec(),
+                ecdsa_attestation_key: quote.attestation_key.to_vec(),
+                certification_data: GoTdxGuestCertificationData {
+                    certificate_data_type: CERT_DATA_QE_REPORT.into(),
+                    size: qe_data_size as u32,
+                    qe_report_certification_data: GoTdxGuestQeReportCertificationData {
+                        qe_report: GoTdxGuestEnclaveReport {
+                            cpu_svn: qe_report[0..16].to_vec(),
+                            misc_select: u32::from_le_bytes(
+                                qe_report[16..20].try_into().expect("4 bytes"),
+                            ),
+                            reserved1: qe_report[20..48].to_vec(),
+                            attributes: qe_report[48..64].to_vec(),
+                            mr_enclave: qe_report[64..96].to_vec(),
+                            reserved2: qe_report[96..128].to_vec(),
+                            mr_signer: qe_report[128..160].to_vec(),
+                            reserved3: qe_report[160..256].to_vec(),
+                            isv_prod_id: u16::from_le_bytes(
+                                qe_report[256..258].try_into().expect("2 bytes"),
+                            )
+                            .into(),
+                            isv_svn: u16::from_le_bytes(
+                                qe_report[258..260].try_into().expect("2 bytes"),
+                            )
+                            .into(),
+                            reserved4: qe_report[260..320].to_vec(),
+                            report_data: qe_report[320..].to_vec(),
+                        },
+                        qe_report_signature: quote.qe_report_signature.to_vec(),
+                        qe_auth_data: GoTdxGuestQeAuthData {
+                            parsed_data_size: quote.qe_auth_data.len() as u32,
+                            data: quote.qe_auth_data.clone(),
+                        },
+                        pck_certificate_chain_data: GoTdxGuestPckCertificateChainData {
+                            certificate_data_type: quote.cert_data_type.into(),
+                            size: quote.cert_data.len() as u32,
+                            pck_cert_chain: quote.cert_data.clone(),
+                        },
+                    },
+                },
+            },
+            extra_bytes: vec![],
+        })
+    }
+
+    /// Reassembles the raw quote.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if a field has the wrong length, or an
+    /// `Error::NotSupported` if the quote isn't a version 4 ECDSA P-256 TDX
+    /// quote.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let header = &self.header;
+        let body = &self.td_quote_body;
+        let signed = &self.signed_data;
+        let qe_data = &signed.certification_data.qe_report_certification_data;
+        let qe_report = &qe_data.qe_report;
+
+        if header.version != 4 {
+            return Err(Error::NotSupported(format!(
+                "go-tdx-guest quotes must be version 4, not {}",
+                header.version
+            )));
+        }
+        if body.rtmrs.len() != 4 {
+            return Err(Error::ParseError(format!(
+                "go-tdx-guest quote has {} RTMRs",
+                body.rtmrs.len()
+            )));
+        }
+
+        let mut quote = vec![];
+        put_u16(&mut quote, "version", header.version)?;
+        put_u16(
+            &mut quote,
+            "attestationKeyType",
+            header.attestation_key_type,
+        )?;
+        quote.extend(header.tee_type.to_le_bytes());
+        put(&mut quote, "qeSvn", &header.qe_svn, 2)?;
+        put(&mut quote, "pceSvn", &header.pce_svn, 2)?;
+        put(&mut quote, "qeVendorId", &header.qe_vendor_id, 16)?;
+        put(&mut quote, "userData", &header.user_data, 20)?;
+
+        put(&mut quote, "teeTcbSvn", &body.tee_tcb_svn, 16)?;
+        put(&mut quote, "mrSeam", &body.mr_seam, 48)?;
+        put(&mut quote, "mrSignerSeam", &body.mr_signer_seam, 48)?;
+        put(&mut quote, "seamAttributes", &body.seam_attributes, 8)?;
+        put(&mut quote, "tdAttributes", &body.td_attributes, 8)?;
+        put(&mut quote, "xfam", &body.xfam, 8)?;
+        put(&mut quote, "mrTd", &body.mr_td, 48)?;
+        put(&mut quote, "mrConfigId", &body.mr_config_id, 48)?;
+        put(&mut quote, "mrOwner", &body.mr_owner, 48)?;
+        put(&mut quote, "mrOwnerConfig", &body.mr_owner_config, 48)?;
+        for rtmr in &body.rtmrs {
+            put(&mut quote, "rtmrs", rtmr, 48)?;
+        }
+        put(&mut quote, "reportData", &body.report_data, 64)?;
+
+        let mut qe_bytes = vec![];
+        put(&mut qe_bytes, "cpuSvn", &qe_report.cpu_svn, 16)?;
+        qe_bytes.extend(qe_report.misc_select.to_le_bytes());
+        put(&mut qe_bytes, "reserved1", &qe_report.reserved1, 28)?;
+        put(&mut qe_bytes, "attributes", &qe_report.attributes, 16)?;
+        put(&mut qe_bytes, "mrEnclave", &qe_report.mr_enclave, 32)?;
+        put(&mut qe_bytes, "reserved2", &qe_report.reserved2, 32)?;
+        put(&mut qe_bytes, "mrSigner", &qe_report.mr_signer, 32)?;
+        put(&mut qe_bytes, "reserved3", &qe_report.reserved3, 96)?;
+        put_u16(&mut qe_bytes, "isvProdId", qe_report.isv_prod_id)?;
+        put_u16(&mut qe_bytes, "isvSvn", qe_report.isv_svn)?;
+        put(&mut qe_bytes, "reserved4", &qe_report.reserved4, 60)?;
+        put(&mut qe_bytes, "reportData", &qe_report.report_data, 64)?;
+        put(
+            &mut qe_bytes,
+            "qeReportSignature",
+            &qe_data.qe_report_signature,
+            64,
+        )?;
+        let auth_data = &qe_data.qe_auth_data.data;
+        put_u16(&mut qe_bytes, "parsedDataSize", auth_data.len() as u32)?;
+        qe_bytes.extend(auth_data);
+        let chain = &qe_data.pck_certificate_chain_data;
+        put_u16(
+            &mut qe_bytes,
+            "certificateDataType",
+            chain.certificate_data_type,
+        )?;
+        qe_bytes.extend((chain.pck_cert_chain.len() as u32).to_le_bytes());
+        qe_bytes.extend(&chain.pck_cert_chain);
+
+        let mut sig_bytes = vec![];
+        put(&mut sig_bytes, "signature", &signed.signature, 64)?;
+        put(
+            &mut sig_bytes,
+            "ecdsaAttestationKey",
+            &signed.ecdsa_attestation_key,
+            64,
+        )?;
+        put_u16(
+            &mut sig_bytes,
+            "certificateDataType",
+            signed.certification_data.certificate_data_type,
+        )?;
+        sig_bytes.extend((qe_bytes.len() as u32).to_le_bytes());
+        sig_bytes.extend(qe_bytes);
+
+        quote.extend((sig_bytes.len() as u32).to_le_bytes());
+        quote.extend(sig_bytes);
+        quote.extend(&self.extra_bytes);
+        Ok(quote)
+    }
+
+    /// Reassembles and parses the quote.
+    pub fn to_quote(&self) -> Result<Quote> {
+        Ok(Quote::from_bytes(&self.to_bytes()?)?)
+    }
+
+    /// Encodes the quote in go-tdx-guest's protojson format.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Decodes a quote from go-tdx-guest's protojson format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the JSON is malformed.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid go-tdx-guest quote: {}", e)))
+    }
+}
+
+/// The verifier nonce issued by Intel Trust Authority.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustAuthorityNonce {
+    /// The nonce value.
+    #[serde(with = "base64_bytes")]
+    pub val: Vec<u8>,
+    /// The time the nonce was issued at.
+    #[serde(with = "base64_bytes")]
+    pub iat: Vec<u8>,
+    /// ITA's signature over the nonce.
+    #[serde(with = "base64_bytes")]
+    pub signature: Vec<u8>,
+}
+
+/// TDX evidence, in the format submitted by the Intel Trust Authority (ITA)
+/// client to ITA's appraisal API.
+///
+/// ITA expects the quote's `report_data` to be the SHA-512 digest of the
+/// verifier nonce's value and issue time (if any) followed by the runtime
+/// data, which corresponds to a bundle's nonce.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustAuthorityEvidence {
+    /// The raw TD quote.
+    #[serde(with = "base64_bytes")]
+    pub quote: Vec<u8>,
+    /// The data bound into the quote by the TD.
+    #[serde(default, with = "base64_bytes")]
+    pub runtime_data: Vec<u8>,
+    /// The verifier nonce bound into the quote, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier_nonce: Option<TrustAuthorityNonce>,
+    /// The raw CCEL, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "base64_bytes")]
+    pub event_log: Vec<u8>,
+}
+
+impl TrustAuthorityEvidence {
+    /// Converts an evidence bundle, whose nonce becomes the runtime data.
+    ///
+    /// The bundle's application event log, PCK chain, endorsement and
+    /// platform capabilities have no ITA equivalent, and are dropped.
+    pub fn from_bundle(bundle: &Bundle) -> Self {
+        Self {
+            quote: bundle.quote.clone(),
+            runtime_data: bundle.nonce.clone(),
+            verifier_nonce: None,
+            event_log: bundle.ccel.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Converts the evidence into a bundle, whose nonce is the verifier
+    /// nonce's value and issue time (if any) followed by the runtime data.
+    pub fn to_bundle(&self) -> Bundle {
+        let mut nonce = vec![];
+        if let Some(verifier_nonce) = &self.verifier_nonce {
+            nonce.extend(&verifier_nonce.val);
+            nonce.extend(&verifier_nonce.iat);
+        }
+        nonce.extend(&self.runtime_data);
+
+        let mut bundle = Bundle::new(&nonce, self.quote.clone());
+        if !self.event_log.is_empty() {
+            bundle.ccel = Some(self.event_log.clone());
+        }
+        bundle
+    }
+
+    /// Encodes the evidence in ITA's JSON format.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Decodes evidence from ITA's JSON format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the JSON is malformed.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid Trust Authority evidence: {}", e)))
+    }
+}
+
+/// Returns `quote` in the raw format of Intel's DCAP sample tools: exactly
+/// the quote's bytes, without the trailing padding of the buffer it was
+/// returned in (e.g., by the QGS).
+///
+/// # Errors
+///
+/// Returns the errors of `Quote::from_bytes()` if the quote is malformed.
+pub fn to_intel_quote_dat(quote: &[u8]) -> Result<Vec<u8>> {
+    Ok(Quote::from_bytes(quote)?.as_bytes().to_vec())
+}
+
+/// The type of the data in an MAA attestation request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AzureMaaDataType {
+    /// Opaque binary data.
+    #[default]
+    Binary,
+    /// A JSON document, whose claims MAA includes in its token.
+    #[serde(rename = "JSON")]
+    Json,
+}
+
+/// Data sent along with the quote in an MAA attestation request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzureMaaData {
+    /// The data.
+    #[serde(with = "base64url_bytes")]
+    pub data: Vec<u8>,
+    /// The type of the data.
+    pub data_type: AzureMaaDataType,
+}
+
+/// A TDX VM attestation request to Microsoft Azure Attestation (MAA), i.e.,
+/// the body of its `attest/TdxVm` API.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzureMaaRequest {
+    /// The raw TD quote.
+    #[serde(with = "base64url_bytes")]
+    pub quote: Vec<u8>,
+    /// The data bound into the quote by the TD, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_data: Option<AzureMaaData>,
+    /// The data the TD was launched with (e.g., bound into its `MRCONFIGID`),
+    /// if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_time_data: Option<AzureMaaData>,
+    /// A nonce that MAA echoes in its token, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+impl AzureMaaRequest {
+    /// Converts an evidence bundle, whose nonce becomes the (binary) runtime
+    /// data.
+    ///
+    /// The bundle's event logs, PCK chain, endorsement and platform
+    /// capabilities have no MAA equivalent, and are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Quote::from_bytes()` if the bundle's quote is
+    /// malformed.
+    pub fn from_bundle(bundle: &Bundle) -> Result<Self> {
+        Ok(Self {
+            quote: to_intel_quote_dat(&bundle.quote)?,
+            runtime_data: Some(AzureMaaData {
+                data: bundle.nonce.clone(),
+                data_type: AzureMaaDataType::Binary,
+            }),
+            init_time_data: None,
+            nonce: None,
+        })
+    }
+
+    /// Converts the request into a bundle, whose nonce is the runtime data
+    /// (if any).
+    pub fn to_bundle(&self) -> Bundle {
+        let nonce = self
+            .runtime_data
+            .as_ref()
+            .map(|runtime_data| runtime_data.data.clone())
+            .unwrap_or_default();
+        Bundle::new(&nonce, self.quote.clone())
+    }
+
+    /// Encodes the request in MAA's JSON format.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Decodes a request from MAA's JSON format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the JSON is malformed.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid MAA attestation request: {}", e)))
+    }
+}
+
+/// Appends a fixed-length field, checking its length.
+fn put(bytes: &mut Vec<u8>, name: &str, field: &[u8], len: usize) -> Result<()> {
+    if field.len() != len {
+        return Err(Error::ParseError(format!(
+            "go-tdx-guest quote field {} has length {}, expected {}",
+            name,
+            field.len(),
+            len
+        )));
+    }
+    bytes.extend(field);
+    Ok(())
+}
+
+/// Appends a 16-bit field, checking its range.
+fn put_u16(bytes: &mut Vec<u8>, name: &str, field: u32) -> Result<()> {
+    let field = u16::try_from(field).map_err(|_| {
+        Error::ParseError(format!("go-tdx-guest quote field {} is out of range", name))
+    })?;
+    bytes.extend(field.to_le_bytes());
+    Ok(())
+}
+
+/// (De)serializes a byte string as standard base64, as Go's `encoding/json`
+/// and `protojson` do.
+mod base64_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        STANDARD.decode(s).map_err(de::Error::custom)
+    }
+}
+
+/// (De)serializes a byte string as unpadded base64url, as MAA does.
+mod base64url_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        URL_SAFE_NO_PAD
+            .decode(s.trim_end_matches('='))
+            .map_err(de::Error::custom)
+    }
+}
+
+/// (De)serializes a list of byte strings as standard base64.
+mod base64_bytes_list {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(list: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(list.iter().map(|bytes| STANDARD.encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| STANDARD.decode(s).map_err(de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+
+    fn make_quote() -> Vec<u8> {
+        let mut parts = QuoteParts {
+            pck_chain: "pem".to_string(),
+            ..Default::default()
+        };
+        parts.rtmrs[3] = [9; 48];
+        parts.assemble(&[6; 64], &[7; 64])
+    }
+
+    #[test]
+    fn test_go_tdx_guest_round_trip() -> Result<()> {
+        let bytes = make_quote();
+        let quote = Quote::from_bytes(&bytes)?;
+
+        let go_quote = GoTdxGuestQuote::from_quote(&quote)?;
+        assert_eq!(
+            go_quote.signed_data_size as usize,
+            bytes.len() - 4 - quote.signed_data().len()
+        );
+        assert_eq!(go_quote.to_bytes()?, bytes);
+
+        let json = go_quote.to_json()?;
+        assert!(json.contains("\"mrTd\""));
+        assert_eq!(GoTdxGuestQuote::from_json(&json)?.to_quote()?, quote);
+        Ok(())
+    }
+
+    #[test]
+    fn test_go_tdx_guest_invalid() -> Result<()> {
+        let quote = Quote::from_bytes(&make_quote())?;
+
+        let mut go_quote = GoTdxGuestQuote::from_quote(&quote)?;
+        go_quote.td_quote_body.mr_td.pop();
+        assert!(
+            go_quote
+                .to_bytes()
+                .unwrap_err()
+                .to_string()
+                .contains("mrTd")
+        );
+
+        let mut go_quote = GoTdxGuestQuote::from_quote(&quote)?;
+        go_quote.header.version = 5;
+        assert!(go_quote.to_bytes().unwrap_err().is_not_supported());
+
+        // protojson omits empty fields
+        assert!(GoTdxGuestQuote::from_json("{}")?.to_bytes().is_err());
+        assert!(GoTdxGuestQuote::from_json(r#"{"extraBytes": "!"}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trust_authority_round_trip() -> Result<()> {
+        let mut bundle = Bundle::new(b"nonce", make_quote());
+        bundle.ccel = Some(vec![1, 2, 3]);
+
+        let evidence = TrustAuthorityEvidence::from_bundle(&bundle);
+        let json = evidence.to_json()?;
+        assert!(json.contains(r#""runtime_data": "bm9uY2U=""#));
+        assert!(!json.contains("verifier_nonce"));
+
+        let imported = TrustAuthorityEvidence::from_json(&json)?;
+        assert_eq!(imported, evidence);
+        assert_eq!(imported.to_bundle(), bundle);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trust_authority_verifier_nonce() -> Result<()> {
+        let evidence = TrustAuthorityEvidence::from_json(
+            r#"{
+                "quote": "AQID",
+                "runtime_data": "cnVudGltZQ==",
+                "verifier_nonce": {"val": "dmFs", "iat": "aWF0", "signature": "c2ln"}
+            }"#,
+        )?;
+
+        let bundle = evidence.to_bundle();
+        assert_eq!(bundle.nonce, b"valiatruntime");
+        assert!(bundle.ccel.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_intel_quote_dat() -> Result<()> {
+        let bytes = make_quote();
+        let mut padded = bytes.clone();
+        padded.extend([0; 100]);
+        assert_eq!(to_intel_quote_dat(&padded)?, bytes);
+        assert!(to_intel_quote_dat(&bytes[..100]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_azure_maa_round_trip() -> Result<()> {
+        let mut bundle = Bundle::new(b"nonce?", make_quote());
+        bundle.ccel = Some(vec![1, 2, 3]);
+
+        let request = AzureMaaRequest::from_bundle(&bundle)?;
+        let json = request.to_json()?;
+        assert!(json.contains(r#""data": "bm9uY2U_""#));
+        assert!(json.contains(r#""dataType": "Binary""#));
+        assert!(!json.contains("initTimeData"));
+
+        let imported = AzureMaaRequest::from_json(&json)?;
+        assert_eq!(imported, request);
+        let imported = imported.to_bundle();
+        assert_eq!(imported.nonce, bundle.nonce);
+        assert_eq!(imported.quote, bundle.quote);
+
+        let request = AzureMaaRequest::from_json(
+            r#"{"quote": "AQID", "initTimeData": {"data": "e30=", "dataType": "JSON"}}"#,
+        )?;
+        assert_eq!(request.init_time_data.unwrap().data, b"{}");
+        assert!(request.runtime_data.is_none());
+        assert!(AzureMaaRequest::from_json(r#"{"quote": "!"}"#).is_err());
+        Ok(())
+    }
+}
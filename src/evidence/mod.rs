@@ -0,0 +1,3182 @@
+//! # Attestation Evidence Bundles
+//!
+//! This module packages all the evidence a relying party needs to appraise a
+//! TD into a single serializable artifact, the evidence `Bundle`:
+//! - the signed TD quote, which binds a caller-provided nonce,
+//! - the Confidential Computing Event Log (CCEL), recording the boot-time
+//!   measurements in `RTMR0`-`RTMR2`,
+//! - the application event log, recording the runtime measurements made with
+//!   this crate (see the `measure::event_log` module),
+//! - the PCK certificate chain that certifies the quote's signing key, and
+//! - optionally, a cloud provider's launch endorsement of the TD's MRTD,
+//! - optionally, an RFC 3161 timestamp over the quote, proving when the
+//!   evidence was collected (see the `verification::timestamp` module),
+//! - the guest's platform capabilities (see the `platform` module), and
+//! - the TD's boot session, which correlates the evidence collected during
+//!   the same boot of the TD (see the `boot_session` module).
+//!
+//! The bundle is signed by the quote: the quote's `report_data` is the
+//! SHA-512 digest of the nonce (see `report_data_for_nonce()`), its RTMRs
+//! authenticate both event logs (which are replayed against them), and the
+//! PCK chain and endorsement carry their own signatures. The platform
+//! capabilities are not authenticated, and are only included for
+//! diagnostics. Workloads that bind more than a nonce into their quotes
+//! (e.g., a public key and claims) can commit them with a
+//! `ReportDataBuilder` (see the `report_data` module).
+//!
+//! Collected bundles can be archived for later audit, grouped by boot or
+//! epoch and with a retention policy, in a local directory or an
+//! S3-compatible bucket (see the `store` module).
+//!
+//! Bundles are collected on the guest with `Bundle::collect()` (when compiled
+//! with the `tdx-linux` feature), and appraised by the relying party against
+//! a `Policy` with `Bundle::verify()` (when compiled with the
+//! `host-verification` feature, or with the `rustcrypto-verification` feature
+//! for targets without OpenSSL, such as WebAssembly), which returns a
+//! `Verdict` listing the result of each check.
+//!
+//! Bundles are encoded in CBOR, and versioned by `BUNDLE_VERSION`. When
+//! compiled with the `proto` feature, bundles and verdicts can also be
+//! exchanged with Go-based verifiers in a versioned protobuf schema (see the
+//! `exchange` module). Quotes and bundles can also be converted to and from
+//! the JSON formats of go-tdx-guest and the Intel Trust Authority client (see
+//! the `interop` module). Saved evidence files can be signed, so that they
+//! can be checked for tampering before appraisal (see the `signed` module).
+//! Verdicts can be emitted as signed AR4SI attestation results for
+//! downstream policy enforcement points (see the `ar4si` module). With the
+//! `rego-policy` feature, bundles can also be appraised against Rego
+//! policies (see the `rego` module). Policies can also include CEL
+//! predicates over the evidence (see the `cel` module), and custom claims
+//! extracted from it by the relying party (see the `claims` module).
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::evidence::{Bundle, Policy};
+//! use tdx_workload_attestation::measure::event_log::EventLog;
+//!
+//! // On the guest
+//! let log = EventLog::new("/var/lib/tdx-workload-attestation/events.cbor");
+//! let bundle = Bundle::collect(b"verifier nonce", Some(&log)).unwrap();
+//! let bytes = bundle.to_bytes().unwrap();
+//!
+//! // On the relying party
+//! let root = std::fs::read("Intel_SGX_Provisioning_Certification_RootCA.cer").unwrap();
+//! let policy = Policy::new()
+//!     .with_nonce(b"verifier nonce")
+//!     .with_trusted_root(&root);
+//! let verdict = Bundle::from_bytes(&bytes).unwrap().verify(&policy).unwrap();
+//! println!("Bundle is {}", if verdict.passed() { "trusted" } else { "untrusted" });
+//! ```
+//!
+//! # Notes
+//! - `Bundle::verify()` checks the platform's TCB status and the QE's
+//!   identity only if the policy includes Intel's TCB Info and QE Identity
+//!   (see the `tcb` module).
+
+pub mod ar4si;
+pub mod boot_session;
+pub mod cache;
+pub mod cel;
+pub mod claims;
+#[cfg(feature = "proto")]
+pub mod exchange;
+pub mod interop;
+pub mod pck;
+pub mod quote;
+#[cfg(feature = "rego-policy")]
+pub mod rego;
+pub mod report_data;
+pub mod signed;
+pub mod store;
+pub mod tcb;
+
+use crate::clock::{Clock, FixedClock, SystemClock};
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+use crate::core::ct;
+use crate::core::register::{Digest384, MeasurementRegister};
+use crate::core::report::TdReportV15;
+use crate::error::{Error, Result};
+use crate::measure::ReferenceValues;
+use crate::measure::allowlist::SharedAllowList;
+use crate::measure::event_log::Event;
+#[cfg(feature = "tdx-linux")]
+use crate::measure::event_log::EventLog;
+use crate::platform::PlatformCapabilities;
+use crate::trust::{TrustAnchorKind, TrustAnchors};
+use boot_session::BootSession;
+use cache::VerificationCache;
+use pck::PckCache;
+use tcb::{Advisory, AdvisoryCatalog, SignedQeIdentity, SignedTcbInfo};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The version of the evidence bundle format.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// The provider name of GCP launch endorsements.
+pub const GCP_ENDORSEMENT_PROVIDER: &str = "gcp";
+
+/// A cloud provider's launch endorsement of the TD.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endorsement {
+    /// The provider that issued the endorsement (e.g., `gcp`).
+    pub provider: String,
+    /// The raw endorsement, in the provider's format.
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+    /// The Rekor log entry recording the endorsement in a transparency log,
+    /// in JSON, if any (see the `verification::transparency` module).
+    #[serde(default)]
+    pub log_entry: Option<String>,
+}
+
+/// A bundle of attestation evidence for a TD.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bundle {
+    /// The version of the bundle format.
+    pub version: u32,
+    /// The nonce bound into the quote.
+    #[serde(with = "serde_bytes")]
+    pub nonce: Vec<u8>,
+    /// The raw TD quote.
+    #[serde(with = "serde_bytes")]
+    pub quote: Vec<u8>,
+    /// The raw CCEL, if the guest exposes one.
+    #[serde(default, with = "serde_bytes")]
+    pub ccel: Option<Vec<u8>>,
+    /// The application event log.
+    #[serde(default)]
+    pub event_log: Vec<Event>,
+    /// The PEM-encoded PCK certificate chain, if known.
+    #[serde(default)]
+    pub pck_chain: Option<String>,
+    /// A cloud provider's launch endorsement, if any.
+    #[serde(default)]
+    pub endorsement: Option<Endorsement>,
+    /// The guest's (unauthenticated) platform capabilities, if collected.
+    #[serde(default)]
+    pub platform: Option<PlatformCapabilities>,
+    /// A DER-encoded RFC 3161 timestamp token over the quote, if any.
+    #[serde(default, with = "serde_bytes")]
+    pub timestamp: Option<Vec<u8>>,
+    /// The TD's boot session, if known.
+    #[serde(default)]
+    pub session: Option<BootSession>,
+    /// The auxiliary blob configfs-tsm returned with the quote (e.g., a
+    /// certificate chain), if any.
+    #[serde(default, with = "serde_bytes")]
+    pub aux_blob: Option<Vec<u8>>,
+}
+
+/// Returns the PEM-encoded certificate chain in a configfs-tsm auxiliary
+/// blob, if the blob is one.
+pub fn pck_chain_from_aux_blob(aux_blob: &[u8]) -> Option<String> {
+    let pem = std::str::from_utf8(aux_blob).ok()?.trim_end_matches('\0');
+    match quote::pem_to_der(pem) {
+        Ok(chain) if !chain.is_empty() => Some(pem.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the `report_data` that binds `nonce` into a quote.
+pub fn report_data_for_nonce(nonce: &[u8]) -> [u8; 64] {
+    Sha512::digest(nonce).into()
+}
+
+impl Bundle {
+    /// Creates a new bundle from a quote over `nonce`.
+    pub fn new(nonce: &[u8], quote: Vec<u8>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            nonce: nonce.to_vec(),
+            quote,
+            ccel: None,
+            event_log: vec![],
+            pck_chain: None,
+            endorsement: None,
+            platform: None,
+            timestamp: None,
+            session: None,
+            aux_blob: None,
+        }
+    }
+
+    /// Collects the evidence for the current TD, binding `nonce` into the
+    /// quote and including the events in `event_log` (if any), the
+    /// platform's capabilities and the TD's boot session.
+    ///
+    /// Quotes generated through configfs-tsm come with any auxiliary blob
+    /// the kernel returned, which also provides the PCK certificate chain if
+    /// the quote doesn't embed it, but the blob does.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` or `Error::QuoteError` if a quote cannot be
+    ///   generated.
+    /// - `Error::ParseError` if the quote or event log is malformed.
+    #[cfg(feature = "tdx-linux")]
+    pub fn collect(nonce: &[u8], event_log: Option<&EventLog>) -> Result<Self> {
+        use crate::core::report::diff::Fields;
+        use crate::measure::ccel::CCEL_DATA_PATH;
+        use crate::tdx::{LinuxTdxProvider, TdxBackend};
+
+        let provider = LinuxTdxProvider::new();
+        let report_data = report_data_for_nonce(nonce);
+        let mut bundle = match provider.backend() {
+            TdxBackend::HyperV => Self::new(nonce, provider.get_quote(&report_data)?),
+            TdxBackend::Kvm | TdxBackend::Tsm => {
+                let report = provider.get_tsm_report(&report_data)?;
+                let mut bundle = Self::new(nonce, report.outblob);
+                bundle.aux_blob = report.auxblob;
+                bundle
+            }
+        };
+
+        // quotes usually embed the PCK chain, but not always
+        let parsed = bundle.parse_quote()?;
+        if parsed.cert_data_type == quote::CERT_DATA_PCK_CHAIN {
+            bundle.pck_chain = Some(
+                String::from_utf8_lossy(&parsed.cert_data)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            );
+        } else {
+            bundle.pck_chain = bundle.aux_blob.as_deref().and_then(pck_chain_from_aux_blob);
+        }
+
+        if std::fs::exists(CCEL_DATA_PATH)? {
+            bundle.ccel = Some(std::fs::read(CCEL_DATA_PATH)?);
+        }
+        if let Some(log) = event_log {
+            bundle.event_log = log.events()?;
+        }
+        bundle.platform = crate::platform::detect_capabilities().ok();
+        bundle.session = boot_session::boot_time()
+            .ok()
+            .map(|boot_time| BootSession::derive(&Fields::from(&parsed), boot_time));
+
+        Ok(bundle)
+    }
+
+    /// Adds the PEM-encoded PCK certificate chain to the bundle, e.g., when
+    /// the quote doesn't embed it (see the `pck` module).
+    pub fn with_pck_chain(mut self, pem: &str) -> Self {
+        self.pck_chain = Some(pem.to_string());
+        self
+    }
+
+    /// Adds a cloud provider's launch endorsement to the bundle.
+    pub fn with_endorsement(mut self, endorsement: Endorsement) -> Self {
+        self.endorsement = Some(endorsement);
+        self
+    }
+
+    /// Adds a DER-encoded RFC 3161 timestamp token over the quote to the
+    /// bundle.
+    pub fn with_timestamp(mut self, token: Vec<u8>) -> Self {
+        self.timestamp = Some(token);
+        self
+    }
+
+    /// Timestamps the bundle's quote with the timestamp authority `tsa`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TSA doesn't return a matching timestamp token
+    /// (see `TsaClient::timestamp()`).
+    #[cfg(feature = "tsa-timestamping")]
+    pub fn request_timestamp(
+        self,
+        tsa: &crate::verification::timestamp::TsaClient,
+    ) -> Result<Self> {
+        let token = tsa.timestamp(&self.quote)?;
+        Ok(self.with_timestamp(token))
+    }
+
+    /// Adds the TD's boot session to the bundle.
+    pub fn with_session(mut self, session: BootSession) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Parses the bundle's quote.
+    pub fn parse_quote(&self) -> Result<quote::Quote> {
+        Ok(quote::Quote::from_bytes(&self.quote)?)
+    }
+
+    /// Encodes the bundle in CBOR.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a CBOR-encoded bundle, upgrading bundles of older versions
+    /// (see `schema::Migrate`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the bundle is malformed or has an
+    /// unsupported version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        <Self as crate::schema::Migrate>::migrate(bytes)
+    }
+
+    /// Appraises the bundle against `policy`.
+    ///
+    /// The verdict records the result of each of these checks:
+    /// - `quote-signature`: the quote is signed by a PCK chaining up to the
+    ///   policy's trusted root.
+    /// - `crl` (if the policy has CRLs): no certificate of the PCK chain or
+    ///   the TCB signing chain is revoked, and each of their issuers has a
+    ///   current CRL (see the `verification::crl` module).
+    /// - `tcb` (if the policy has a TCB Info, or constrains the TCB level):
+    ///   the TCB Info is validly signed and current, is for the platform's
+    ///   FMSPC, and the platform's TCB level has one of the policy's accepted
+    ///   statuses, is no older than its minimum TCB date (if any), and isn't
+    ///   affected by any of its disallowed advisories.
+    /// - `tdx-module` (if the policy has a minimum TDX module SVN): the
+    ///   quote's TDX module SVN is at least the minimum.
+    /// - `nonce`: the quote binds the bundle's nonce, which matches the
+    ///   policy's nonce (if any).
+    /// - `debug`: the TD is not a debug TD, unless the policy allows it.
+    /// - `servtd` (if the policy has accepted ServTD hashes): the quote's
+    ///   `MRSERVICETD` is one of the policy's accepted hashes.
+    /// - `event-log`: replaying the CCEL and application event log yields the
+    ///   quote's RTMRs.
+    /// - `reference-values`: the quote's measurements match the policy's
+    ///   reference values.
+    /// - `allow-list` (if the policy has an allow-list): the quote's
+    ///   measurements match an entry of the allow-list that is valid at the
+    ///   verification time.
+    /// - `endorsement`: the launch endorsement (if any, or if required by the
+    ///   policy) endorses the quote's MRTD, and firmware with at least the
+    ///   policy's minimum firmware SVN (if any), isn't older than the
+    ///   policy's maximum endorsement age (if any), and doesn't endorse
+    ///   revoked firmware. A minimum firmware SVN or maximum age requires an
+    ///   endorsement.
+    /// - `transparency` (if the bundle has a launch endorsement and the policy
+    ///   has transparency log keys): the endorsement is recorded in one of
+    ///   the policy's transparency logs.
+    /// - `timestamp`: the timestamp (if any, or if required by the policy) is
+    ///   over the quote, and signed by a TSA chaining up to one of the
+    ///   policy's TSA roots.
+    /// - `session` (if the bundle has a boot session): the session ID derives
+    ///   from the quote's measurements and the session's boot time.
+    /// - `claims` (if the policy has claim extractors): the policy's claim
+    ///   extractors succeed (see the `claims` module).
+    /// - `rego` (if the policy has a Rego policy): the Rego policy's query
+    ///   holds for the quote, nonce, custom claims and the results of the
+    ///   other checks (see the `rego` module, which requires the
+    ///   `rego-policy` feature).
+    /// - `cel` (if the policy has CEL rules): every CEL rule holds for the
+    ///   quote, the platform's TCB, the nonce, custom claims and the results
+    ///   of the other checks (see the `cel` module).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quote, PCK chain or CCEL cannot be parsed.
+    /// Failed checks are reported in the verdict instead.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    pub fn verify(&self, policy: &Policy) -> Result<Verdict> {
+        let start = std::time::Instant::now();
+        let verdict = self.appraise(policy);
+        crate::metrics::global().record_verification(start.elapsed(), &verdict);
+        verdict
+    }
+
+    /// Performs the checks of `verify()`.
+    ///
+    /// The independent checks of the quote signature, CRLs, platform, event
+    /// logs, endorsement and timestamp run in parallel with the
+    /// `parallel-verification` feature (see `verification::parallel`).
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    fn appraise(&self, policy: &Policy) -> Result<Verdict> {
+        use crate::core::report::diff::Fields;
+
+        let quote = self.parse_quote()?;
+        let body = &quote.body;
+
+        let pck_chain = match &self.pck_chain {
+            Some(pem) => quote::pem_to_der(pem)?,
+            None => cached_pck_chain(&quote, policy)?,
+        };
+
+        let mut signature = Verdict::default();
+        let mut revocation = Verdict::default();
+        let mut platform = Verdict::default();
+        let mut event_log = Ok(Verdict::default());
+        let mut endorsement = Verdict::default();
+        let mut timestamp = Verdict::default();
+        run_all(vec![
+            Box::new(|| appraise_signature(&quote, &pck_chain, policy, &mut signature)),
+            Box::new(|| appraise_revocation(&pck_chain, policy, &mut revocation)),
+            Box::new(|| appraise_platform(&quote, &pck_chain, policy, &mut platform)),
+            Box::new(|| event_log = self.appraise_event_logs(body)),
+            Box::new(|| self.appraise_endorsement(body, policy, &mut endorsement)),
+            Box::new(|| self.appraise_timestamp(policy, &mut timestamp)),
+        ]);
+
+        // the checks are reported in the same order, however they ran
+        let mut verdict = signature;
+        verdict.merge(revocation);
+        verdict.merge(platform);
+
+        // nonce
+        if !ct::eq(&body.report_data, &report_data_for_nonce(&self.nonce)) {
+            verdict.fail("nonce", "Quote does not bind the bundle's nonce");
+        } else if policy.nonce.as_ref().is_some_and(|n| *n != self.nonce) {
+            verdict.fail("nonce", "Bundle's nonce does not match the expected nonce");
+        } else {
+            verdict.pass("nonce");
+        }
+
+        appraise_td(body, policy, &mut verdict);
+        verdict.merge(event_log?);
+        appraise_measurements(body, policy, &mut verdict);
+        verdict.merge(endorsement);
+        verdict.merge(timestamp);
+
+        // boot session
+        if let Some(session) = &self.session {
+            if session.matches(&Fields::from(&quote)) {
+                verdict.pass("session");
+            } else {
+                verdict.fail(
+                    "session",
+                    "Boot session does not match the quote's measurements",
+                );
+            }
+        }
+
+        // custom claims
+        let mut claims = serde_json::Map::new();
+        if !policy.claim_extractors.is_empty() {
+            match policy.claim_extractors.extract(self, &quote) {
+                Ok(extracted) => {
+                    claims = extracted;
+                    verdict.pass("claims");
+                }
+                Err(e) => verdict.fail("claims", &e.to_string()),
+            }
+        }
+
+        // Rego policy
+        if let Some(path) = &policy.rego_policy {
+            match evaluate_rego(path, policy, &quote, &self.nonce, &claims, &verdict) {
+                Ok(true) => verdict.pass("rego"),
+                Ok(false) => verdict.fail("rego", "Rego policy denied the bundle"),
+                Err(e) => verdict.fail("rego", &e.to_string()),
+            }
+        }
+
+        // CEL rules
+        if !policy.cel_rules.is_empty() {
+            let tcb_status = platform_tcb_status(&quote, &pck_chain, policy, &verdict);
+            let activation = cel::activation(
+                &quote,
+                tcb_status.as_deref(),
+                &self.nonce,
+                &claims,
+                &verdict,
+            );
+            match evaluate_cel_rules(&policy.cel_rules, &activation) {
+                None => verdict.pass("cel"),
+                Some(detail) => verdict.fail("cel", &detail),
+            }
+        }
+
+        Ok(verdict)
+    }
+
+    /// Replays the bundle's event logs, i.e., the `event-log` check of
+    /// `verify()`.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    fn appraise_event_logs(&self, body: &quote::TdQuoteBody) -> Result<Verdict> {
+        use crate::measure::SHA384_LEN;
+        use crate::measure::ccel::{parse_ccel, replay_ccel};
+        use crate::measure::event_log::NUM_RTMRS;
+        use crate::measure::predict::extend_rtmr;
+
+        let mut verdict = Verdict::default();
+        let mut rtmrs = match &self.ccel {
+            Some(ccel) => replay_ccel(&parse_ccel(ccel)?),
+            None => [[0u8; SHA384_LEN]; NUM_RTMRS],
+        };
+        let mut log_error = None;
+        for (i, event) in self.event_log.iter().enumerate() {
+            if event.index != i as u64 || !event.verify_digest() {
+                log_error = Some(format!("Event {} is out of order or tampered", i));
+                break;
+            }
+            match rtmrs.get_mut(event.rtmr as usize) {
+                Some(rtmr) => *rtmr = extend_rtmr(rtmr, &event.digest),
+                None => {
+                    log_error = Some(format!("Event {} has invalid RTMR {}", i, event.rtmr));
+                    break;
+                }
+            }
+        }
+        match log_error {
+            Some(e) => verdict.fail("event-log", &e),
+            None => match (0..NUM_RTMRS).find(|i| rtmrs[*i] != body.rtmrs[*i]) {
+                Some(i) => verdict.fail(
+                    "event-log",
+                    &format!("Replayed event logs do not match RTMR{}", i),
+                ),
+                None => verdict.pass("event-log"),
+            },
+        }
+        Ok(verdict)
+    }
+
+    /// Appraises the bundle's launch endorsement, i.e., the `endorsement`
+    /// and `transparency` checks of `verify()`.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    fn appraise_endorsement(
+        &self,
+        body: &quote::TdQuoteBody,
+        policy: &Policy,
+        verdict: &mut Verdict,
+    ) {
+        // endorsement
+        match &self.endorsement {
+            Some(endorsement) => match verify_endorsement(endorsement, &body.mrtd, policy) {
+                Ok(None) => verdict.pass("endorsement"),
+                Ok(Some(detail)) => verdict.fail("endorsement", &detail),
+                Err(e) => verdict.fail("endorsement", &e.to_string()),
+            },
+            None if policy.require_endorsement || policy.constrains_endorsement() => {
+                verdict.fail("endorsement", "Bundle has no launch endorsement")
+            }
+            None => {}
+        }
+
+        // transparency
+        if let Some(endorsement) = &self.endorsement
+            && !policy.transparency_log_keys.is_empty()
+        {
+            match verify_transparency(endorsement, policy) {
+                Ok(true) => verdict.pass("transparency"),
+                Ok(false) => verdict.fail(
+                    "transparency",
+                    "Launch endorsement is not recorded in a trusted transparency log",
+                ),
+                Err(e) => verdict.fail("transparency", &e.to_string()),
+            }
+        }
+    }
+
+    /// Appraises the bundle's timestamp, i.e., the `timestamp` check of
+    /// `verify()`.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    fn appraise_timestamp(&self, policy: &Policy, verdict: &mut Verdict) {
+        match &self.timestamp {
+            Some(token) => match verify_timestamp(token, &self.quote, policy) {
+                Some(Ok(true)) => verdict.pass("timestamp"),
+                Some(Ok(false)) => verdict.fail("timestamp", "Invalid timestamp"),
+                Some(Err(e)) => verdict.fail("timestamp", &e.to_string()),
+                None => verdict.fail("timestamp", "No trusted TSA root configured"),
+            },
+            None if policy.require_timestamp => {
+                verdict.fail("timestamp", "Bundle has no timestamp")
+            }
+            None => {}
+        }
+    }
+}
+
+/// Appraises the signature of `quote`, i.e., the `quote-signature` check of
+/// `Bundle::verify()`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn appraise_signature(
+    quote: &quote::Quote,
+    pck_chain: &[Vec<u8>],
+    policy: &Policy,
+    verdict: &mut Verdict,
+) {
+    let verified = policy
+        .trust_anchors
+        .verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+            verify_quote_chain(quote, pck_chain, &root.der, policy)
+        });
+    match verified {
+        Some(Ok(true)) => verdict.pass("quote-signature"),
+        Some(Ok(false)) => verdict.fail("quote-signature", "Invalid quote signature chain"),
+        Some(Err(e)) => verdict.fail("quote-signature", &e.to_string()),
+        None => verdict.fail("quote-signature", "No trusted root certificate configured"),
+    }
+}
+
+/// Checks the PCK chain and the TCB signing chain against the policy's CRLs,
+/// i.e., the `crl` check of `Bundle::verify()`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn appraise_revocation(pck_chain: &[Vec<u8>], policy: &Policy, verdict: &mut Verdict) {
+    if policy.crls.is_empty() {
+        return;
+    }
+    let mut chains = vec![pck_chain];
+    if !policy.tcb_signing_chain.is_empty() {
+        chains.push(&policy.tcb_signing_chain);
+    }
+    match chains
+        .iter()
+        .try_for_each(|chain| check_revocation(chain, policy))
+    {
+        Ok(()) => verdict.pass("crl"),
+        Err(e) => verdict.fail("crl", &e.to_string()),
+    }
+}
+
+/// Checks `chain` against the policy's CRLs, up to any of the trusted Intel
+/// SGX roots.
+#[cfg(feature = "host-verification")]
+fn check_revocation(chain: &[Vec<u8>], policy: &Policy) -> Result<()> {
+    use crate::verification::crl::check_chain;
+
+    let now = policy.verification_time()?;
+    let checked = policy
+        .trust_anchors
+        .verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+            // only the root the chain was issued under can vouch for it
+            if chain.last().is_some_and(|last| *last != root.der) && !issued_by(chain, &root.der)? {
+                return Ok(false);
+            }
+            check_chain(chain, &root.der, &policy.crls, now).map(|()| true)
+        });
+    match checked {
+        Some(Ok(true)) => Ok(()),
+        Some(Err(e)) => Err(e),
+        _ => Err(Error::VerificationError(
+            "No trusted root certificate issued the chain".to_string(),
+        )),
+    }
+}
+
+/// Returns whether the last certificate of `chain` is issued by `root`.
+#[cfg(feature = "host-verification")]
+fn issued_by(chain: &[Vec<u8>], root: &[u8]) -> Result<bool> {
+    use crate::verification::x509::x509_from_der_bytes;
+    use openssl::x509::X509VerifyResult;
+
+    let Some(last) = chain.last() else {
+        return Ok(false);
+    };
+    let last = x509_from_der_bytes(last)?;
+    Ok(x509_from_der_bytes(root)?.issued(&last) == X509VerifyResult::OK)
+}
+
+/// CRLs can't be checked with the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn check_revocation(_chain: &[Vec<u8>], _policy: &Policy) -> Result<()> {
+    Err(Error::NotSupported(
+        "CRLs can only be checked with the host-verification feature".to_string(),
+    ))
+}
+
+/// Evaluates the Rego policy at `path` against `quote`, which binds `nonce`,
+/// and the custom `claims`, after the checks of `verdict`.
+#[cfg(all(
+    feature = "rego-policy",
+    any(feature = "host-verification", feature = "rustcrypto-verification")
+))]
+fn evaluate_rego(
+    path: &str,
+    policy: &Policy,
+    quote: &quote::Quote,
+    nonce: &[u8],
+    claims: &serde_json::Map<String, serde_json::Value>,
+    verdict: &Verdict,
+) -> Result<bool> {
+    let mut rego = rego::RegoPolicy::new(path);
+    if let Some(query) = &policy.rego_query {
+        rego = rego.with_query(query);
+    }
+    rego.evaluate(&rego::input(quote, nonce, claims, verdict))
+}
+
+/// Fails the Rego policy, which cannot be evaluated without the
+/// `rego-policy` feature.
+#[cfg(all(
+    not(feature = "rego-policy"),
+    any(feature = "host-verification", feature = "rustcrypto-verification")
+))]
+fn evaluate_rego(
+    _path: &str,
+    _policy: &Policy,
+    _quote: &quote::Quote,
+    _nonce: &[u8],
+    _claims: &serde_json::Map<String, serde_json::Value>,
+    _verdict: &Verdict,
+) -> Result<bool> {
+    Err(Error::NotSupported(
+        "Rego policies require the rego-policy feature".to_string(),
+    ))
+}
+
+/// Returns the platform's TCB status per the policy's TCB Info, if the `tcb`
+/// check of `verdict` passed, i.e., if the TCB Info is trusted.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn platform_tcb_status(
+    quote: &quote::Quote,
+    pck_chain: &[Vec<u8>],
+    policy: &Policy,
+    verdict: &Verdict,
+) -> Option<String> {
+    if !verdict.check("tcb").is_some_and(|check| check.passed) {
+        return None;
+    }
+    let platform = tcb::pck_platform_tcb(pck_chain.first()?, &quote.body.tee_tcb_svn).ok()?;
+    let tcb_info = &policy.tcb_info.as_ref()?.tcb_info;
+    Some(tcb_info.tcb_level(&platform)?.tcb_status.clone())
+}
+
+/// Evaluates the CEL `rules` with the variables of `activation`, and returns
+/// why they don't all hold, if they don't.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn evaluate_cel_rules(rules: &[cel::CelRule], activation: &serde_json::Value) -> Option<String> {
+    let failures: Vec<String> = rules
+        .iter()
+        .filter_map(|rule| match rule.expression.evaluate(activation) {
+            Ok(true) => None,
+            Ok(false) => Some(format!("{} does not hold", rule.name)),
+            Err(e) => Some(format!("{} cannot be evaluated: {}", rule.name, e)),
+        })
+        .collect();
+    (!failures.is_empty()).then(|| format!("CEL rules failed: {}", failures.join("; ")))
+}
+
+/// Runs the independent `tasks`, in parallel on the verification pool with
+/// the `parallel-verification` feature.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn run_all(tasks: Vec<Box<dyn FnOnce() + Send + '_>>) {
+    #[cfg(feature = "parallel-verification")]
+    crate::verification::parallel::install(|| {
+        rayon::scope(|scope| {
+            for task in tasks {
+                scope.spawn(move |_| task());
+            }
+        })
+    });
+    #[cfg(not(feature = "parallel-verification"))]
+    tasks.into_iter().for_each(|task| task());
+}
+
+/// Appraises the platform that generated `quote`, i.e., the `tcb`,
+/// `tdx-module` and `qe-identity` checks of `Bundle::verify()`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+pub(crate) fn appraise_platform(
+    quote: &quote::Quote,
+    pck_chain: &[Vec<u8>],
+    policy: &Policy,
+    verdict: &mut Verdict,
+) {
+    let body = &quote.body;
+
+    // TCB status
+    match &policy.tcb_info {
+        Some(tcb_info) => match appraise_tcb(tcb_info, policy, pck_chain, &body.tee_tcb_svn) {
+            Ok((detail, advisories)) => {
+                match detail {
+                    None => verdict.pass("tcb"),
+                    Some(detail) => verdict.fail("tcb", &detail),
+                }
+                verdict.advisories = advisories;
+            }
+            Err(e) => verdict.fail("tcb", &e.to_string()),
+        },
+        None if policy.constrains_tcb_level() => verdict.fail(
+            "tcb",
+            "Policy constrains the TCB level, but has no TCB Info",
+        ),
+        None => {}
+    }
+
+    // TDX module SVN
+    if let Some(min) = policy.min_tdx_module_svn {
+        let svn = body.tee_tcb_svn[0];
+        if svn < min {
+            verdict.fail(
+                "tdx-module",
+                &format!("TDX module SVN {} is below the minimum {}", svn, min),
+            );
+        } else {
+            verdict.pass("tdx-module");
+        }
+    }
+
+    // QE identity
+    if let Some(qe_identity) = &policy.qe_identity {
+        match appraise_qe_identity(qe_identity, policy, quote) {
+            Ok(None) => verdict.pass("qe-identity"),
+            Ok(Some(detail)) => verdict.fail("qe-identity", &detail),
+            Err(e) => verdict.fail("qe-identity", &e.to_string()),
+        }
+    }
+}
+
+/// Appraises the TD's attributes, i.e., the `debug` and `servtd` checks of
+/// `Bundle::verify()`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+pub(crate) fn appraise_td(body: &quote::TdQuoteBody, policy: &Policy, verdict: &mut Verdict) {
+    // debug
+    if body.is_debug() && !policy.allow_debug {
+        verdict.fail("debug", "TD is a debug TD");
+    } else {
+        verdict.pass("debug");
+    }
+
+    // service TDs
+    if !policy.accepted_servtd_hashes.is_empty() {
+        match body.mrservicetd {
+            Some(hash) if policy.accepted_servtd_hashes.contains(&hash) => verdict.pass("servtd"),
+            Some(hash) => verdict.fail(
+                "servtd",
+                &format!("Service TD hash {} is not accepted", hex::encode(hash)),
+            ),
+            None => verdict.fail("servtd", "Quote has no MRSERVICETD (TDX 1.0 body)"),
+        }
+    }
+}
+
+/// Appraises the TD's measurements, i.e., the `reference-values` and
+/// `allow-list` checks of `Bundle::verify()`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+pub(crate) fn appraise_measurements(
+    body: &quote::TdQuoteBody,
+    policy: &Policy,
+    verdict: &mut Verdict,
+) {
+    // reference values
+    let mismatches = policy.reference_values.mismatches(&body.mrtd, &body.rtmrs);
+    if mismatches.is_empty() {
+        verdict.pass("reference-values");
+    } else {
+        verdict.fail(
+            "reference-values",
+            &format!("{} do not match", mismatches.join(", ")),
+        );
+    }
+
+    // allow-list
+    if let Some(allow_list) = &policy.allow_list {
+        let entry = policy.verification_time().map(|now| {
+            allow_list
+                .current()
+                .find(&body.mrtd, &body.rtmrs, now)
+                .is_some()
+        });
+        match entry {
+            Ok(true) => verdict.pass("allow-list"),
+            Ok(false) => verdict.fail(
+                "allow-list",
+                "No valid allow-list entry matches the measurements",
+            ),
+            Err(e) => verdict.fail("allow-list", &e.to_string()),
+        }
+    }
+}
+
+/// Returns the PCK chain embedded in the quote or, if it identifies the
+/// platform instead, the platform's chain from the policy's PCK cache.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+pub(crate) fn cached_pck_chain(quote: &quote::Quote, policy: &Policy) -> Result<Vec<Vec<u8>>> {
+    match (quote.pck_chain(), &policy.pck_cache) {
+        (Err(crate::core::Error::NotSupported(_)), Some(cache)) => {
+            match cache.get(&pck::PlatformId::from_quote(quote)?)? {
+                Some(pem) => Ok(quote::pem_to_der(&pem)?),
+                None => Err(Error::NotSupported(
+                    "Quote's PCK certificate chain is not cached".to_string(),
+                )),
+            }
+        }
+        (chain, _) => Ok(chain?),
+    }
+}
+
+/// Verifies the quote's signature chain up to the DER-encoded `root` with
+/// OpenSSL.
+#[cfg(feature = "host-verification")]
+fn verify_quote_chain(
+    quote: &quote::Quote,
+    pck_chain: &[Vec<u8>],
+    root: &[u8],
+    policy: &Policy,
+) -> Result<bool> {
+    use crate::verification::quote::{verify_cert_chain, verify_quote_with_pck};
+    use crate::verification::x509::x509_from_der_bytes;
+
+    let now = policy.verification_time()?;
+    let chain = pck_chain
+        .iter()
+        .map(|der| x509_from_der_bytes(der))
+        .collect::<Result<Vec<_>>>()?;
+    let verified = verify_chain_cached(policy, pck_chain, root, now, || {
+        verify_cert_chain(&chain, &x509_from_der_bytes(root)?, now)
+    })?;
+    Ok(verified && verify_quote_with_pck(quote, &chain[0])?)
+}
+
+/// Verifies the quote's signature chain up to the DER-encoded `root` with
+/// the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn verify_quote_chain(
+    quote: &quote::Quote,
+    pck_chain: &[Vec<u8>],
+    root: &[u8],
+    policy: &Policy,
+) -> Result<bool> {
+    use crate::verification::rustcrypto::{verify_cert_chain, verify_quote_with_pck};
+
+    let now = policy.verification_time()?;
+    let verified = verify_chain_cached(policy, pck_chain, root, now, || {
+        verify_cert_chain(pck_chain, root, now)
+    })?;
+    Ok(verified && verify_quote_with_pck(quote, &pck_chain[0])?)
+}
+
+/// Verifies a certificate chain with `verify`, unless the policy's
+/// verification cache has verified it before.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn verify_chain_cached(
+    policy: &Policy,
+    chain: &[Vec<u8>],
+    root: &[u8],
+    now: u64,
+    verify: impl FnOnce() -> Result<bool>,
+) -> Result<bool> {
+    match &policy.verification_cache {
+        Some(cache) => cache.verify_chain(chain, root, now, verify),
+        None => verify(),
+    }
+}
+
+/// Verifies the collateral `key` (whose signed data and signature are
+/// `signed`) with `verify`, unless the policy's verification cache has
+/// verified it before, with the same TCB signing chain and `root`.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn verify_collateral_cached(
+    policy: &Policy,
+    key: cache::CollateralKey,
+    signed: &[&[u8]],
+    root: &[u8],
+    verify: impl FnOnce() -> Result<bool>,
+) -> Result<bool> {
+    match &policy.verification_cache {
+        Some(cache) => cache.verify_collateral(
+            key,
+            signed,
+            &policy.tcb_signing_chain,
+            root,
+            policy.verification_time()?,
+            verify,
+        ),
+        None => verify(),
+    }
+}
+
+/// Verifies the TCB Info's signature chain up to the DER-encoded `root` with
+/// OpenSSL.
+#[cfg(feature = "host-verification")]
+fn verify_tcb_info_chain(tcb_info: &SignedTcbInfo, root: &[u8], policy: &Policy) -> Result<bool> {
+    use crate::verification::x509::x509_from_der_bytes;
+
+    tcb_info.verify_signature_at(
+        &policy.tcb_signing_chain,
+        &x509_from_der_bytes(root)?,
+        policy.verification_time()?,
+    )
+}
+
+/// Verifies the TCB Info's signature chain up to the DER-encoded `root` with
+/// the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn verify_tcb_info_chain(tcb_info: &SignedTcbInfo, root: &[u8], policy: &Policy) -> Result<bool> {
+    use crate::verification::rustcrypto::verify_tcb_info_signature;
+
+    verify_tcb_info_signature(
+        tcb_info,
+        &policy.tcb_signing_chain,
+        root,
+        policy.verification_time()?,
+    )
+}
+
+/// Verifies the QE Identity's signature chain up to the DER-encoded `root`
+/// with OpenSSL.
+#[cfg(feature = "host-verification")]
+fn verify_qe_identity_chain(
+    qe_identity: &SignedQeIdentity,
+    root: &[u8],
+    policy: &Policy,
+) -> Result<bool> {
+    use crate::verification::x509::x509_from_der_bytes;
+
+    qe_identity.verify_signature_at(
+        &policy.tcb_signing_chain,
+        &x509_from_der_bytes(root)?,
+        policy.verification_time()?,
+    )
+}
+
+/// Verifies the QE Identity's signature chain up to the DER-encoded `root`
+/// with the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn verify_qe_identity_chain(
+    qe_identity: &SignedQeIdentity,
+    root: &[u8],
+    policy: &Policy,
+) -> Result<bool> {
+    use crate::verification::rustcrypto::verify_qe_identity_signature;
+
+    verify_qe_identity_signature(
+        qe_identity,
+        &policy.tcb_signing_chain,
+        root,
+        policy.verification_time()?,
+    )
+}
+
+/// Verifies a timestamp token over `data` against the policy's TSA roots,
+/// or returns `None` if it has none.
+#[cfg(feature = "host-verification")]
+fn verify_timestamp(token: &[u8], data: &[u8], policy: &Policy) -> Option<Result<bool>> {
+    use crate::verification::timestamp::verify_timestamp;
+    use crate::verification::x509::x509_from_der_bytes;
+
+    policy
+        .trust_anchors
+        .verify_any(TrustAnchorKind::TsaRoot, |root| {
+            verify_timestamp(token, data, &x509_from_der_bytes(&root.der)?)
+        })
+}
+
+/// Verifies that the endorsement's log entry records it in one of the
+/// policy's transparency logs.
+#[cfg(feature = "host-verification")]
+fn verify_transparency(endorsement: &Endorsement, policy: &Policy) -> Result<bool> {
+    use crate::verification::transparency::{LogEntry, verify_logged};
+
+    let Some(log_entry) = &endorsement.log_entry else {
+        return Err(Error::VerificationError(
+            "Launch endorsement has no transparency log entry".to_string(),
+        ));
+    };
+    let entry = LogEntry::from_json(log_entry)?;
+    for log_key in &policy.transparency_log_keys {
+        if verify_logged(&endorsement.data, &entry, log_key)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Transparency log entries can't be verified with the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn verify_transparency(_endorsement: &Endorsement, _policy: &Policy) -> Result<bool> {
+    Err(Error::NotSupported(
+        "Transparency log entries can only be verified with the host-verification feature"
+            .to_string(),
+    ))
+}
+
+/// Timestamps can't be verified with the pure-Rust backend.
+#[cfg(all(
+    feature = "rustcrypto-verification",
+    not(feature = "host-verification")
+))]
+fn verify_timestamp(_token: &[u8], _data: &[u8], _policy: &Policy) -> Option<Result<bool>> {
+    Some(Err(Error::NotSupported(
+        "Timestamps can only be verified with the host-verification feature".to_string(),
+    )))
+}
+
+/// Checks the quote's QE report against the QE Identity, and returns why it
+/// isn't acceptable, if it isn't.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn appraise_qe_identity(
+    qe_identity: &SignedQeIdentity,
+    policy: &Policy,
+    quote: &quote::Quote,
+) -> Result<Option<String>> {
+    use crate::verification::qe::{QeReport, verify_qe_identity};
+
+    let verified = policy
+        .trust_anchors
+        .verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+            let key = cache::CollateralKey {
+                kind: cache::CollateralKind::QeIdentity,
+                fmspc: String::new(),
+                issue_date: qe_identity.qe_identity.issue_date.clone(),
+                next_update: qe_identity.qe_identity.next_update_timestamp()?,
+            };
+            let signed = [
+                qe_identity.signed_data(),
+                qe_identity.signature().as_slice(),
+            ];
+            verify_collateral_cached(policy, key, &signed, &root.der, || {
+                verify_qe_identity_chain(qe_identity, &root.der, policy)
+            })
+        });
+    match verified {
+        Some(Ok(true)) => {}
+        Some(Ok(false)) => return Ok(Some("QE Identity is invalid or expired".to_string())),
+        Some(Err(e)) => return Err(e),
+        None => return Ok(Some("No trusted root certificate configured".to_string())),
+    }
+
+    let qe_identity = &qe_identity.qe_identity;
+    if !verify_qe_identity(quote, qe_identity)? {
+        return Ok(Some("QE report does not match the QE Identity".to_string()));
+    }
+
+    let isvsvn = QeReport::from_quote(quote).isvsvn;
+    Ok(match qe_identity.tcb_level(isvsvn) {
+        Some(level) if policy.accepted_tcb_statuses.contains(&level.tcb_status) => None,
+        Some(level) => Some(format!("QE TCB status is {}", level.tcb_status)),
+        None => Some("QE TCB is not recognized".to_string()),
+    })
+}
+
+/// Evaluates the platform's TCB status, and returns why it isn't acceptable,
+/// if it isn't, along with the advisories affecting the platform.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn appraise_tcb(
+    tcb_info: &SignedTcbInfo,
+    policy: &Policy,
+    pck_chain: &[Vec<u8>],
+    tee_tcb_svn: &[u8; 16],
+) -> Result<(Option<String>, Vec<Advisory>)> {
+    let verified = policy
+        .trust_anchors
+        .verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+            let key = cache::CollateralKey {
+                kind: cache::CollateralKind::TcbInfo,
+                fmspc: tcb_info.tcb_info.fmspc.to_ascii_lowercase(),
+                issue_date: tcb_info.tcb_info.issue_date.clone(),
+                next_update: tcb_info.tcb_info.next_update_timestamp()?,
+            };
+            let signed = [tcb_info.signed_data(), tcb_info.signature().as_slice()];
+            verify_collateral_cached(policy, key, &signed, &root.der, || {
+                verify_tcb_info_chain(tcb_info, &root.der, policy)
+            })
+        });
+    match verified {
+        Some(Ok(true)) => {}
+        Some(Ok(false)) => {
+            return Ok((Some("TCB Info is invalid or expired".to_string()), vec![]));
+        }
+        Some(Err(e)) => return Err(e),
+        None => {
+            return Ok((
+                Some("No trusted root certificate configured".to_string()),
+                vec![],
+            ));
+        }
+    }
+
+    let pck = pck_chain
+        .first()
+        .ok_or_else(|| Error::VerificationError("Empty PCK chain".to_string()))?;
+    let platform = tcb::pck_platform_tcb(pck, tee_tcb_svn)?;
+    let tcb_info = &tcb_info.tcb_info;
+    if !tcb_info
+        .fmspc
+        .eq_ignore_ascii_case(&hex::encode(platform.fmspc))
+    {
+        return Ok((
+            Some(format!(
+                "TCB Info is for FMSPC {}, not the platform's {}",
+                tcb_info.fmspc,
+                hex::encode(platform.fmspc)
+            )),
+            vec![],
+        ));
+    }
+
+    let Some(level) = tcb_info.tcb_level(&platform) else {
+        return Ok((Some("Platform TCB is not recognized".to_string()), vec![]));
+    };
+    let advisories = policy.advisories.resolve(level);
+    Ok((appraise_tcb_level(level, policy)?, advisories))
+}
+
+/// Evaluates the platform's TCB level against the policy's accepted statuses,
+/// minimum TCB date and disallowed advisories, and returns why it isn't
+/// acceptable, if it isn't.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+fn appraise_tcb_level(level: &tcb::TcbLevel, policy: &Policy) -> Result<Option<String>> {
+    if !policy.accepted_tcb_statuses.contains(&level.tcb_status) {
+        return Ok(Some(format!("Platform TCB status is {}", level.tcb_status)));
+    }
+
+    let tcb_date = tcb::parse_utc_timestamp(&level.tcb_date)?;
+    if policy.min_tcb_time()?.is_some_and(|min| tcb_date < min) {
+        return Ok(Some(format!(
+            "Platform TCB date {} is before the minimum {}",
+            level.tcb_date,
+            policy.min_tcb_date.as_deref().unwrap_or_default()
+        )));
+    }
+
+    let disallowed: Vec<&str> = level
+        .advisory_ids
+        .iter()
+        .filter(|id| {
+            policy
+                .disallow_advisories
+                .iter()
+                .any(|disallowed| disallowed.eq_ignore_ascii_case(id))
+        })
+        .map(String::as_str)
+        .collect();
+    if !disallowed.is_empty() {
+        return Ok(Some(format!(
+            "Platform is affected by disallowed advisories: {}",
+            disallowed.join(", ")
+        )));
+    }
+    Ok(None)
+}
+
+/// Verifies a launch endorsement against the TD's MRTD, with the policy's
+/// trust anchors, and returns why it isn't acceptable, if it isn't.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+#[cfg_attr(not(feature = "host-gcp-tdx"), allow(unused_variables))]
+fn verify_endorsement(
+    endorsement: &Endorsement,
+    mrtd: &[u8; 48],
+    policy: &Policy,
+) -> Result<Option<String>> {
+    match endorsement.provider.as_str() {
+        #[cfg(feature = "host-gcp-tdx")]
+        GCP_ENDORSEMENT_PROVIDER => {
+            use crate::host::{Evidence, TeeHost, VerificationContext};
+
+            let evidence = Evidence::new(mrtd).with_endorsement(&endorsement.data);
+            let mut context =
+                VerificationContext::new().with_trust_anchors(policy.trust_anchors.clone());
+            context.min_firmware_svn = policy.min_firmware_svn;
+            context.max_endorsement_age = policy
+                .max_endorsement_age_days
+                .map(|days| std::time::Duration::from_secs(days.saturating_mul(86400)));
+            context.revoked_firmware_digests = policy.revoked_firmware_digests.clone();
+            context.clock = policy.clock.clone();
+            let verdict = crate::gcp::GcpTdxHost::builder()
+                .trust_anchors(policy.trust_anchors.clone())
+                .build()?
+                .verify(&evidence, &context)?;
+            Ok(verdict
+                .checks
+                .into_iter()
+                .find(|check| !check.passed)
+                .map(|check| check.detail.unwrap_or(check.name)))
+        }
+        provider => Err(Error::NotSupported(format!(
+            "Endorsements from provider {} are not supported",
+            provider
+        ))),
+    }
+}
+
+/// An appraisal policy for evidence bundles.
+///
+/// Policies can be written in TOML (see `Policy::from_toml()`), e.g.:
+///
+/// ```toml
+/// nonce = "6e6f6e6365"
+/// allow_debug = false
+/// require_endorsement = true
+/// require_timestamp = false
+/// accepted_tcb_statuses = ["UpToDate", "SWHardeningNeeded"]
+/// min_tcb_date = "2024-11-13"
+/// min_tdx_module_svn = 3
+/// min_firmware_svn = 2
+/// max_endorsement_age_days = 365
+/// revoked_firmware_digests = ["..."]
+/// disallow_advisories = ["INTEL-SA-00837"]
+/// accepted_servtd_hashes = ["..."]
+///
+/// [reference_values]
+/// mrtd = "..."
+/// ```
+///
+/// The trusted root certificate and Intel's TCB collateral are not part of
+/// the policy file, and are loaded from a collateral directory with
+/// `Policy::with_collateral_dir()`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// The nonce the bundle must bind, if any.
+    #[serde(with = "hex_bytes")]
+    pub nonce: Option<Vec<u8>>,
+    /// The expected measurement register values.
+    pub reference_values: ReferenceValues,
+    /// The allow-list of golden measurements the TD must match an entry
+    /// of, if any (see the `measure::allowlist` module).
+    #[serde(skip)]
+    pub allow_list: Option<SharedAllowList>,
+    /// Whether debug TDs are acceptable.
+    pub allow_debug: bool,
+    /// Whether the bundle must include a launch endorsement.
+    pub require_endorsement: bool,
+    /// Whether the bundle must include a timestamp.
+    pub require_timestamp: bool,
+    /// The platform TCB statuses that are acceptable (`UpToDate` by
+    /// default).
+    pub accepted_tcb_statuses: Vec<String>,
+    /// The earliest acceptable TCB date of the platform's TCB level, as an
+    /// ISO 8601 UTC date (`YYYY-MM-DD`, or `YYYY-MM-DDThh:mm:ssZ` like the
+    /// TCB Info's), if any. Requires a TCB Info.
+    pub min_tcb_date: Option<String>,
+    /// The minimum SVN of the TDX module (the first byte of the quote's
+    /// `TEE_TCB_SVN`), if any.
+    pub min_tdx_module_svn: Option<u8>,
+    /// The minimum security version number (SVN) of the TD's firmware, as
+    /// stated by its launch endorsement, if any. If set, bundles must include
+    /// a launch endorsement, so that old (but still validly endorsed)
+    /// firmware is rejected.
+    pub min_firmware_svn: Option<u32>,
+    /// The maximum age of the launch endorsement, in days, at the
+    /// verification time, if any. If set, bundles must include a launch
+    /// endorsement.
+    pub max_endorsement_age_days: Option<u64>,
+    /// The SHA-384 digests of firmware whose launch endorsements were
+    /// withdrawn, e.g., after its golden measurements were compromised.
+    pub revoked_firmware_digests: Vec<Digest384>,
+    /// The security advisories (e.g., `INTEL-SA-00837`) the platform must
+    /// not be affected by, whatever its TCB status. Requires a TCB Info.
+    pub disallow_advisories: Vec<String>,
+    /// The accepted hashes of the service TDs bound to the TD (its
+    /// `MRSERVICETD`), e.g., one per trusted migration TD release. If set,
+    /// quotes must have a TDX 1.5 body, and TDs with no bound service TDs
+    /// are only accepted if `NO_SERVTD_HASH` is included.
+    pub accepted_servtd_hashes: Vec<MeasurementRegister>,
+    /// The Rego policy (an OPA bundle built with `opa build -t wasm`, or its
+    /// `policy.wasm` module) the bundle must also satisfy, if any (see the
+    /// `rego` module, which requires the `rego-policy` feature). Relative
+    /// paths are relative to the TOML policy file.
+    pub rego_policy: Option<String>,
+    /// The Rego query deciding whether the bundle satisfies the Rego policy
+    /// (`data.tdx.allow` by default).
+    pub rego_query: Option<String>,
+    /// The CEL predicates over the evidence that must all hold (see the
+    /// `cel` module).
+    pub cel_rules: Vec<cel::CelRule>,
+    /// The extractors of the custom claims exposed to the CEL rules and Rego
+    /// policy (see the `claims` module).
+    #[serde(skip)]
+    pub claim_extractors: claims::ClaimExtractors,
+    /// The trust anchors the PCK chain, TCB Info and QE Identity must chain
+    /// up to (any of the Intel SGX roots).
+    #[serde(skip)]
+    pub trust_anchors: TrustAnchors,
+    /// The TCB Info of the platform family, against which the platform's TCB
+    /// status is evaluated, if any.
+    #[serde(skip)]
+    pub tcb_info: Option<SignedTcbInfo>,
+    /// The metadata of the security advisories listed by the TCB Info, with
+    /// which the advisories affecting the platform are reported.
+    #[serde(skip)]
+    pub advisories: AdvisoryCatalog,
+    /// The identity of the TD QE, against which the quote's QE report is
+    /// checked, if any.
+    #[serde(skip)]
+    pub qe_identity: Option<SignedQeIdentity>,
+    /// The DER-encoded certificate chain of the TCB Info's and QE Identity's
+    /// signing key.
+    #[serde(skip)]
+    pub tcb_signing_chain: Vec<Vec<u8>>,
+    /// The cache of PCK certificate chains, for quotes that don't embed
+    /// theirs, if any.
+    #[serde(skip)]
+    pub pck_cache: Option<PckCache>,
+    /// The DER- or PEM-encoded CRLs the PCK chain and TCB signing chain are
+    /// checked against, if any (see the `verification::crl` module).
+    #[serde(skip)]
+    pub crls: Vec<Vec<u8>>,
+    /// The DER-encoded public keys of the transparency logs that launch
+    /// endorsements must be recorded in, if any.
+    #[serde(skip)]
+    pub transparency_log_keys: Vec<Vec<u8>>,
+    /// The cache of verified certificate chains and collateral, shared with
+    /// the other policies of the verifier, if any.
+    #[serde(skip)]
+    pub verification_cache: Option<Arc<VerificationCache>>,
+    /// The clock at which certificates and collateral are checked for
+    /// expiry (the `SystemClock` by default; a `FixedClock` is required on
+    /// targets without a system clock).
+    #[serde(skip)]
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            nonce: None,
+            reference_values: ReferenceValues::default(),
+            allow_list: None,
+            allow_debug: false,
+            require_endorsement: false,
+            require_timestamp: false,
+            accepted_tcb_statuses: vec![tcb::TCB_STATUS_UP_TO_DATE.to_string()],
+            min_tcb_date: None,
+            min_tdx_module_svn: None,
+            min_firmware_svn: None,
+            max_endorsement_age_days: None,
+            revoked_firmware_digests: vec![],
+            disallow_advisories: vec![],
+            accepted_servtd_hashes: vec![],
+            rego_policy: None,
+            rego_query: None,
+            cel_rules: vec![],
+            claim_extractors: claims::ClaimExtractors::new(),
+            trust_anchors: TrustAnchors::new(),
+            tcb_info: None,
+            advisories: AdvisoryCatalog::new(),
+            qe_identity: None,
+            tcb_signing_chain: vec![],
+            pck_cache: None,
+            crls: vec![],
+            transparency_log_keys: vec![],
+            verification_cache: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl Policy {
+    /// Creates a new, empty policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a TOML policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the policy is malformed or has
+    /// unknown fields.
+    pub fn from_toml(policy: &str) -> Result<Self> {
+        let policy: Self = toml::from_str(policy)
+            .map_err(|e| Error::ParseError(format!("Invalid policy: {}", e)))?;
+        policy.min_tcb_time()?;
+        Ok(policy)
+    }
+
+    /// Reads a TOML policy from `path` or, if it is an OPA bundle
+    /// (`.tar.gz`) or WebAssembly module (`.wasm`), creates a policy whose
+    /// only constraints are those of the Rego policy at `path` (see
+    /// `rego_policy`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the file cannot be read, an
+    /// `Error::ParseError` if the policy is malformed, or an
+    /// `Error::NotSupported` if it is a Rego source file (`.rego`), which
+    /// must be built with `opa build -t wasm` first.
+    pub fn from_file(path: &str) -> Result<Self> {
+        let path = Path::new(path);
+        if path.extension().is_some_and(|ext| ext == "rego") {
+            return Err(Error::NotSupported(format!(
+                "{} must be built with opa build -t wasm",
+                path.display()
+            )));
+        }
+        if path.extension().is_some_and(|ext| ext == "wasm")
+            || path.to_string_lossy().ends_with(".tar.gz")
+        {
+            return Ok(Self::new().with_rego_policy(&path.to_string_lossy()));
+        }
+
+        let mut policy = Self::from_toml(&read_text_file(path)?)?;
+        if let (Some(rego), Some(dir)) = (&policy.rego_policy, path.parent())
+            && Path::new(rego).is_relative()
+        {
+            policy.rego_policy = Some(dir.join(rego).to_string_lossy().into_owned());
+        }
+        Ok(policy)
+    }
+
+    /// Reads a TOML policy from `path`, once its Sigstore bundle (see the
+    /// `verification::sigstore` module) is verified with `verifier`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the policy doesn't have a
+    /// trusted signature, the errors of `ArtifactVerifier::read_verified()`,
+    /// and an `Error::ParseError` if the policy is malformed.
+    #[cfg(feature = "host-verification")]
+    pub fn from_signed_file(
+        path: &str,
+        verifier: &crate::verification::sigstore::ArtifactVerifier,
+    ) -> Result<Self> {
+        let policy = String::from_utf8(verifier.read_verified(path)?)
+            .map_err(|_| Error::ParseError(format!("{} is not valid UTF-8", path)))?;
+        Self::from_toml(&policy)
+    }
+
+    /// Loads the trust anchors and TCB collateral from `dir`:
+    /// - `root_ca.der`, `root_ca.pem` or `intel_sgx_root*`: the trusted Intel
+    ///   SGX root certificates (at least one is required), along with any
+    ///   other trust anchors (see the `trust` module),
+    /// - `tcb_info.json`: the PCS TDX TCB Info response for the platform
+    ///   family (optional),
+    /// - `advisories.json`: the metadata of the TCB Info's security
+    ///   advisories (optional, see `tcb::AdvisoryCatalog`),
+    /// - `qe_identity.json`: the PCS TD QE Identity response (optional),
+    /// - `tcb_signing_chain.pem`: the TCB Info's and QE Identity's signing
+    ///   certificate chain (required with either),
+    /// - `pck/`: a cache of PCK certificate chains (optional, see
+    ///   `with_pck_cache()`), and
+    /// - `crls/`: the CRLs of the PCK and TCB signing chains' issuers
+    ///   (optional, see `with_crl()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if there is no trusted Intel SGX root
+    /// certificate or a file is malformed, an `Error::IoError` if a file
+    /// cannot be read, or an `Error::NotSupported` if a file is a symlink.
+    pub fn with_collateral_dir(mut self, dir: &str) -> Result<Self> {
+        let dir = Path::new(dir);
+
+        self.trust_anchors.load_dir(dir)?;
+        if !self.trust_anchors.has_roots(TrustAnchorKind::IntelSgxRoot) {
+            return Err(Error::ParseError(format!(
+                "No root_ca.der or root_ca.pem in {}",
+                dir.display()
+            )));
+        }
+
+        let tcb_info = dir.join("tcb_info.json");
+        if tcb_info.exists() {
+            self.tcb_info = Some(SignedTcbInfo::parse(&read_text_file(&tcb_info)?)?);
+        }
+        let advisories = dir.join("advisories.json");
+        if advisories.exists() {
+            self.advisories = AdvisoryCatalog::parse(&read_text_file(&advisories)?)?;
+        }
+        let qe_identity = dir.join("qe_identity.json");
+        if qe_identity.exists() {
+            self.qe_identity = Some(SignedQeIdentity::parse(&read_text_file(&qe_identity)?)?);
+        }
+        if self.tcb_info.is_some() || self.qe_identity.is_some() {
+            self.tcb_signing_chain =
+                quote::pem_to_der(&read_text_file(&dir.join("tcb_signing_chain.pem"))?)?;
+        }
+
+        let pck_cache = dir.join("pck");
+        if pck_cache.is_dir() {
+            self.pck_cache = Some(PckCache::new(pck_cache));
+        }
+
+        let crls = dir.join("crls");
+        if crls.is_dir() {
+            let mut paths = std::fs::read_dir(&crls)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?;
+            paths.sort();
+            for path in paths {
+                self.crls.push(read_file(&path)?);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Requires the bundle to bind `nonce`.
+    pub fn with_nonce(mut self, nonce: &[u8]) -> Self {
+        self.nonce = Some(nonce.to_vec());
+        self
+    }
+
+    /// Requires the TD's measurements to match `values`.
+    pub fn with_reference_values(mut self, values: ReferenceValues) -> Self {
+        self.reference_values = values;
+        self
+    }
+
+    /// Trusts the DER-encoded Intel SGX root certificate `der` for the PCK
+    /// chain.
+    pub fn with_trusted_root(mut self, der: &[u8]) -> Self {
+        self.trust_anchors = self
+            .trust_anchors
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, der);
+        self
+    }
+
+    /// Sets the trust anchors for the PCK chain and TCB Info.
+    pub fn with_trust_anchors(mut self, anchors: TrustAnchors) -> Self {
+        self.trust_anchors = anchors;
+        self
+    }
+
+    /// Evaluates the platform's TCB status against `tcb_info`, whose
+    /// signature is verified with the DER-encoded `signing_chain`.
+    pub fn with_tcb_info(mut self, tcb_info: SignedTcbInfo, signing_chain: &[Vec<u8>]) -> Self {
+        self.tcb_info = Some(tcb_info);
+        self.tcb_signing_chain = signing_chain.to_vec();
+        self
+    }
+
+    /// Reports the security advisories affecting the platform with their
+    /// metadata in `catalog`.
+    pub fn with_advisories(mut self, catalog: AdvisoryCatalog) -> Self {
+        self.advisories = catalog;
+        self
+    }
+
+    /// Looks up the PCK certificate chains of quotes that don't embed theirs
+    /// (and whose bundle doesn't include it) in `cache`.
+    pub fn with_pck_cache(mut self, cache: PckCache) -> Self {
+        self.pck_cache = Some(cache);
+        self
+    }
+
+    /// Checks the quote's QE report against `qe_identity`, whose signature is
+    /// verified with the DER-encoded `signing_chain` (the same as the TCB
+    /// Info's).
+    pub fn with_qe_identity(
+        mut self,
+        qe_identity: SignedQeIdentity,
+        signing_chain: &[Vec<u8>],
+    ) -> Self {
+        self.qe_identity = Some(qe_identity);
+        self.tcb_signing_chain = signing_chain.to_vec();
+        self
+    }
+
+    /// Caches the verifications of PCK certificate chains and collateral in
+    /// `cache` (see the `cache` module), e.g., to share them across the
+    /// appraisals of a verifier.
+    pub fn with_verification_cache(mut self, cache: Arc<VerificationCache>) -> Self {
+        self.verification_cache = Some(cache);
+        self
+    }
+
+    /// Sets the clock at which certificates and collateral are checked for
+    /// expiry (e.g., a `FixedClock` at the time archived evidence was
+    /// collected).
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Checks certificates and collateral for expiry at `unix_time`, in
+    /// seconds since the Unix epoch (see `with_clock()`).
+    pub fn with_verification_time(self, unix_time: u64) -> Self {
+        self.with_clock(FixedClock::new(unix_time))
+    }
+
+    /// Returns the verification time, in seconds since the Unix epoch.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    pub(crate) fn verification_time(&self) -> Result<u64> {
+        self.clock.now()
+    }
+
+    /// Sets the Rego policy the bundle must also satisfy.
+    pub fn with_rego_policy(mut self, path: &str) -> Self {
+        self.rego_policy = Some(path.to_string());
+        self
+    }
+
+    /// Adds a CEL rule the bundle must satisfy.
+    pub fn with_cel_rule(mut self, rule: cel::CelRule) -> Self {
+        self.cel_rules.push(rule);
+        self
+    }
+
+    /// Registers `extractor` for the custom claim `name`.
+    pub fn with_claim_extractor(
+        mut self,
+        name: &str,
+        extractor: impl claims::ClaimExtractor + 'static,
+    ) -> Self {
+        self.claim_extractors.register(name, extractor);
+        self
+    }
+
+    /// Sets the allow-list of golden measurements, e.g., the `SharedAllowList`
+    /// of an `AllowListWatcher`.
+    pub fn with_allow_list(mut self, allow_list: impl Into<SharedAllowList>) -> Self {
+        self.allow_list = Some(allow_list.into());
+        self
+    }
+
+    /// Checks the certificates of the PCK chain and TCB signing chain against
+    /// the DER- or PEM-encoded `crl`, along with the policy's other CRLs. If
+    /// the policy has CRLs, each issuer in the chains must have a current one
+    /// (e.g., the Intel SGX Root CA's and the PCK Platform CA's).
+    pub fn with_crl(mut self, crl: &[u8]) -> Self {
+        self.crls.push(crl.to_vec());
+        self
+    }
+
+    /// Requires launch endorsements to be recorded in the transparency log
+    /// with the DER-encoded public key (`SubjectPublicKeyInfo`) `der`, or in
+    /// any other of the policy's transparency logs.
+    pub fn with_transparency_log_key(mut self, der: &[u8]) -> Self {
+        self.transparency_log_keys.push(der.to_vec());
+        self
+    }
+
+    /// Sets whether debug TDs are acceptable.
+    pub fn allow_debug(mut self, allow: bool) -> Self {
+        self.allow_debug = allow;
+        self
+    }
+
+    /// Accepts service TDs bound to the TD with the `SERVTD_HASH` `hash`
+    /// (`NO_SERVTD_HASH` accepts TDs with no bound service TDs).
+    pub fn with_accepted_servtd_hash(mut self, hash: MeasurementRegister) -> Self {
+        self.accepted_servtd_hashes.push(hash);
+        self
+    }
+
+    /// Requires the platform's TCB level to be dated `date` or later, an ISO
+    /// 8601 UTC date (see `min_tcb_date`).
+    pub fn with_min_tcb_date(mut self, date: &str) -> Self {
+        self.min_tcb_date = Some(date.to_string());
+        self
+    }
+
+    /// Requires the TDX module's SVN to be at least `svn`.
+    pub fn with_min_tdx_module_svn(mut self, svn: u8) -> Self {
+        self.min_tdx_module_svn = Some(svn);
+        self
+    }
+
+    /// Requires the launch endorsement to endorse firmware with an SVN of at
+    /// least `svn`.
+    pub fn with_min_firmware_svn(mut self, svn: u32) -> Self {
+        self.min_firmware_svn = Some(svn);
+        self
+    }
+
+    /// Requires the launch endorsement to be at most `days` days old at the
+    /// verification time.
+    pub fn with_max_endorsement_age_days(mut self, days: u64) -> Self {
+        self.max_endorsement_age_days = Some(days);
+        self
+    }
+
+    /// Rejects launch endorsements of the firmware with the SHA-384 digest
+    /// `digest`.
+    pub fn with_revoked_firmware_digest(mut self, digest: Digest384) -> Self {
+        self.revoked_firmware_digests.push(digest);
+        self
+    }
+
+    /// Returns whether the policy constrains the launch endorsement beyond
+    /// the MRTD it endorses, which requires one.
+    fn constrains_endorsement(&self) -> bool {
+        self.min_firmware_svn.is_some() || self.max_endorsement_age_days.is_some()
+    }
+
+    /// Rejects platforms affected by the security advisory `id`.
+    pub fn with_disallowed_advisory(mut self, id: &str) -> Self {
+        self.disallow_advisories.push(id.to_string());
+        self
+    }
+
+    /// Returns whether the policy constrains the platform's TCB level beyond
+    /// its status (which requires a TCB Info).
+    fn constrains_tcb_level(&self) -> bool {
+        self.min_tcb_date.is_some() || !self.disallow_advisories.is_empty()
+    }
+
+    /// Returns the earliest acceptable TCB date, in seconds since the Unix
+    /// epoch.
+    fn min_tcb_time(&self) -> Result<Option<u64>> {
+        self.min_tcb_date
+            .as_deref()
+            .map(|date| match date.len() {
+                10 => tcb::parse_utc_timestamp(&format!("{}T00:00:00Z", date)),
+                _ => tcb::parse_utc_timestamp(date),
+            })
+            .transpose()
+    }
+
+    /// Sets whether the bundle must include a launch endorsement.
+    pub fn require_endorsement(mut self, require: bool) -> Self {
+        self.require_endorsement = require;
+        self
+    }
+
+    /// Sets whether the bundle must include a timestamp.
+    pub fn require_timestamp(mut self, require: bool) -> Self {
+        self.require_timestamp = require;
+        self
+    }
+
+    /// Returns the rules the policy enforces, in the order of the checks of
+    /// `Bundle::verify()`.
+    pub fn rules(&self) -> Vec<PolicyRule> {
+        let mut rules = vec![];
+        let mut rule = |check, requirement: String| rules.push(PolicyRule { check, requirement });
+
+        let roots = self
+            .trust_anchors
+            .roots(TrustAnchorKind::IntelSgxRoot)
+            .count();
+        rule(
+            "quote-signature",
+            if roots == 0 {
+                "The quote must be signed under a trusted Intel SGX root (none is trusted)"
+                    .to_string()
+            } else {
+                format!(
+                    "The quote must be signed under one of the {} trusted Intel SGX roots",
+                    roots
+                )
+            },
+        );
+        if !self.crls.is_empty() {
+            rule(
+                "crl",
+                "No certificate of the PCK or TCB signing chains may be revoked by the CRLs of its issuer"
+                    .to_string(),
+            );
+        }
+        if self.tcb_info.is_some() || self.constrains_tcb_level() {
+            let mut requirement = format!(
+                "The platform's TCB status must be one of: {}",
+                self.accepted_tcb_statuses.join(", ")
+            );
+            if let Some(date) = &self.min_tcb_date {
+                requirement.push_str(&format!("; its TCB date must be {} or later", date));
+            }
+            if !self.disallow_advisories.is_empty() {
+                requirement.push_str(&format!(
+                    "; it must not be affected by: {}",
+                    self.disallow_advisories.join(", ")
+                ));
+            }
+            rule("tcb", requirement);
+        }
+        if let Some(svn) = self.min_tdx_module_svn {
+            rule(
+                "tdx-module",
+                format!("The TDX module's SVN must be at least {}", svn),
+            );
+        }
+        if self.qe_identity.is_some() {
+            rule(
+                "qe-identity",
+                "The quote's QE report must match the TD QE Identity".to_string(),
+            );
+        }
+        rule(
+            "nonce",
+            match &self.nonce {
+                Some(nonce) => format!("The quote must bind the nonce {}", hex::encode(nonce)),
+                None => "The quote must bind the bundle's nonce".to_string(),
+            },
+        );
+        rule(
+            "debug",
+            if self.allow_debug {
+                "Debug TDs are accepted".to_string()
+            } else {
+                "The TD must not be a debug TD".to_string()
+            },
+        );
+        if !self.accepted_servtd_hashes.is_empty() {
+            rule(
+                "servtd",
+                format!(
+                    "The TD's MRSERVICETD must be one of: {}",
+                    self.accepted_servtd_hashes
+                        .iter()
+                        .map(hex::encode)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+        }
+        rule(
+            "event-log",
+            "The replayed event logs must match the quote's RTMRs".to_string(),
+        );
+
+        let values = &self.reference_values;
+        let registers: Vec<String> = [
+            ("MRTD", values.mrtd),
+            ("RTMR0", values.rtmr0),
+            ("RTMR1", values.rtmr1),
+            ("RTMR2", values.rtmr2),
+            ("RTMR3", values.rtmr3),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| format!("{} = {}", name, hex::encode(v))))
+        .collect();
+        rule(
+            "reference-values",
+            if registers.is_empty() {
+                "No measurement register is constrained".to_string()
+            } else {
+                format!("The measurements must be: {}", registers.join(", "))
+            },
+        );
+
+        if let Some(allow_list) = &self.allow_list {
+            rule(
+                "allow-list",
+                format!(
+                    "The measurements must match one of the {} allow-list entries, at the verification time",
+                    allow_list.current().entries.len()
+                ),
+            );
+        }
+        let mut requirement = if self.require_endorsement || self.constrains_endorsement() {
+            "The bundle must have a launch endorsement of the MRTD".to_string()
+        } else {
+            "A launch endorsement, if any, must endorse the MRTD".to_string()
+        };
+        if let Some(svn) = self.min_firmware_svn {
+            requirement.push_str(&format!(
+                ", and of firmware with an SVN of at least {}",
+                svn
+            ));
+        }
+        rule("endorsement", requirement);
+        if !self.transparency_log_keys.is_empty() {
+            rule(
+                "transparency",
+                format!(
+                    "A launch endorsement, if any, must be recorded in one of the {} trusted transparency logs",
+                    self.transparency_log_keys.len()
+                ),
+            );
+        }
+        rule(
+            "timestamp",
+            if self.require_timestamp {
+                "The bundle must have a timestamp from a trusted TSA".to_string()
+            } else {
+                "A timestamp, if any, must be from a trusted TSA".to_string()
+            },
+        );
+        if !self.claim_extractors.is_empty() {
+            rule(
+                "claims",
+                format!(
+                    "The custom claims {} must be extracted from the evidence",
+                    self.claim_extractors.names().join(", ")
+                ),
+            );
+        }
+        if let Some(path) = &self.rego_policy {
+            rule(
+                "rego",
+                format!(
+                    "The Rego query {} of {} must hold",
+                    self.rego_query.as_deref().unwrap_or("data.tdx.allow"),
+                    path
+                ),
+            );
+        }
+        for cel_rule in &self.cel_rules {
+            rule(
+                "cel",
+                format!(
+                    "The CEL rule {} ({}) must hold",
+                    cel_rule.name, cel_rule.expression
+                ),
+            );
+        }
+        rules
+    }
+
+    /// Returns a warning for each setting of the policy that weakens
+    /// appraisal, or that evidence cannot satisfy.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        if !self.trust_anchors.has_roots(TrustAnchorKind::IntelSgxRoot) {
+            warnings.push("No Intel SGX root is trusted: every quote will be rejected".to_string());
+        }
+        if self.reference_values == ReferenceValues::default() && self.allow_list.is_none() {
+            warnings.push(
+                "No reference values or allow-list: TDs are accepted regardless of their measurements"
+                    .to_string(),
+            );
+        }
+        if self.allow_debug {
+            warnings.push("Debug TDs are accepted".to_string());
+        }
+        if self.tcb_info.is_none() {
+            warnings.push(if self.constrains_tcb_level() {
+                "The TCB level is constrained, but there is no TCB Info: every bundle will be rejected"
+                    .to_string()
+            } else {
+                "No TCB Info: the platform's TCB status is not checked".to_string()
+            });
+        }
+        let out_of_date: Vec<&str> = self
+            .accepted_tcb_statuses
+            .iter()
+            .map(String::as_str)
+            .filter(|status| *status != tcb::TCB_STATUS_UP_TO_DATE)
+            .collect();
+        if !out_of_date.is_empty() {
+            warnings.push(format!(
+                "Platforms that are not up to date are accepted: {}",
+                out_of_date.join(", ")
+            ));
+        }
+        if self.require_timestamp && !self.trust_anchors.has_roots(TrustAnchorKind::TsaRoot) {
+            warnings.push(
+                "Timestamps are required, but no TSA root is trusted: every bundle will be rejected"
+                    .to_string(),
+            );
+        }
+        warnings
+    }
+
+    /// Checks the TD's own `TDREPORT` against the policy's measurement
+    /// checks (`debug`, `servtd`, `reference-values` and `allow-list`, see
+    /// `Bundle::verify()`), and returns a description of each failed check.
+    ///
+    /// Unlike `Bundle::verify()`, this check needs no collateral, but only
+    /// gates actions in the TD itself (e.g., launching a workload, see the
+    /// `measure::launch` module), since a local report proves nothing to a
+    /// relying party.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy has an allow-list, and its clock fails.
+    pub fn check_tdreport(&self, report: &TdReportV15) -> Result<Vec<String>> {
+        let mut failures = vec![];
+        if report.is_debug() && !self.allow_debug {
+            failures.push("debug: TD is a debug TD".to_string());
+        }
+
+        let servtd_hash = report.get_servtd_hash();
+        if !self.accepted_servtd_hashes.is_empty()
+            && !self.accepted_servtd_hashes.contains(&servtd_hash)
+        {
+            failures.push(format!(
+                "servtd: Service TD hash {} is not accepted",
+                hex::encode(servtd_hash)
+            ));
+        }
+
+        let (mrtd, rtmrs) = (report.get_mrtd(), report.get_rtmrs());
+        let mismatches = self.reference_values.mismatches(&mrtd, &rtmrs);
+        if !mismatches.is_empty() {
+            failures.push(format!(
+                "reference-values: {} do not match",
+                mismatches.join(", ")
+            ));
+        }
+
+        if let Some(allow_list) = &self.allow_list {
+            let now = self.clock.now()?;
+            if allow_list.current().find(&mrtd, &rtmrs, now).is_none() {
+                failures.push(
+                    "allow-list: No valid allow-list entry matches the measurements".to_string(),
+                );
+            }
+        }
+        Ok(failures)
+    }
+}
+
+/// A rule of a policy: a check of `Bundle::verify()`, and what it requires
+/// under the policy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PolicyRule {
+    /// The name of the check.
+    pub check: &'static str,
+    /// What the check requires of the evidence.
+    pub requirement: String,
+}
+
+/// Reads a file, rejecting symlinks.
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+    Ok(std::fs::read(path)?)
+}
+
+/// Reads a text file, rejecting symlinks.
+fn read_text_file(path: &Path) -> Result<String> {
+    String::from_utf8(read_file(path)?)
+        .map_err(|_| Error::ParseError(format!("{} is not valid UTF-8", path.display())))
+}
+
+/// Deserializes an optional hex-encoded byte string.
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, de};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| hex::decode(s).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+/// The result of a single appraisal check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Check {
+    /// The name of the check.
+    pub name: String,
+    /// Whether the check passed.
+    pub passed: bool,
+    /// Why the check failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// The result of appraising an evidence bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    /// The schema version of the verdict (see the `schema` module).
+    #[serde(default = "crate::schema::legacy_schema_version")]
+    pub schema_version: u32,
+    /// The results of the individual checks, in order.
+    pub checks: Vec<Check>,
+    /// The security advisories affecting the platform, if its TCB isn't up
+    /// to date (whether or not the policy accepts its TCB status).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advisories: Vec<Advisory>,
+}
+
+impl Default for Verdict {
+    fn default() -> Self {
+        Self {
+            schema_version: crate::schema::VERDICT_SCHEMA_VERSION,
+            checks: vec![],
+            advisories: vec![],
+        }
+    }
+}
+
+impl Verdict {
+    /// Returns whether all checks passed.
+    pub fn passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|c| c.passed)
+    }
+
+    /// Returns the result of the check named `name`, if it was performed.
+    pub fn check(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|c| c.name == name)
+    }
+
+    /// Appends the checks and advisories of `other`.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    pub(crate) fn merge(&mut self, other: Verdict) {
+        self.checks.extend(other.checks);
+        self.advisories.extend(other.advisories);
+    }
+
+    /// Returns the names of the checks that failed, in order.
+    pub fn failed_checks(&self) -> Vec<&str> {
+        self.checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    #[cfg_attr(
+        not(any(feature = "host-verification", feature = "rustcrypto-verification")),
+        allow(dead_code)
+    )]
+    pub(crate) fn pass(&mut self, name: &str) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            passed: true,
+            detail: None,
+        });
+    }
+
+    #[cfg_attr(
+        not(any(feature = "host-verification", feature = "rustcrypto-verification")),
+        allow(dead_code)
+    )]
+    pub(crate) fn fail(&mut self, name: &str, detail: &str) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            passed: false,
+            detail: Some(detail.to_string()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::event_log::EventPayload;
+
+    fn custom_event(index: u64, data: &str) -> Event {
+        let mut event = Event::new(
+            3,
+            EventPayload::Custom {
+                event_type: "test".to_string(),
+                data: data.to_string(),
+            },
+        );
+        event.index = index;
+        event
+    }
+
+    #[test]
+    fn test_bundle_serde() -> Result<()> {
+        let mut bundle = Bundle::new(b"nonce", vec![1, 2, 3]).with_endorsement(Endorsement {
+            provider: GCP_ENDORSEMENT_PROVIDER.to_string(),
+            data: vec![4, 5],
+            log_entry: None,
+        });
+        bundle.ccel = Some(vec![6]);
+        bundle.event_log = vec![custom_event(0, "app")];
+        bundle.session = Some(BootSession {
+            id: "00".repeat(16),
+            boot_time: 1000,
+        });
+        bundle.aux_blob = Some(vec![7]);
+
+        let bytes = bundle.to_bytes()?;
+        assert_eq!(Bundle::from_bytes(&bytes)?, bundle);
+
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::from_bytes(&bundle.to_bytes()?).is_err());
+        assert!(Bundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pck_chain_from_aux_blob() {
+        let pem = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n";
+        assert_eq!(
+            pck_chain_from_aux_blob(format!("{}\0", pem).as_bytes()),
+            Some(pem.to_string())
+        );
+        assert_eq!(pck_chain_from_aux_blob(&[0xff, 0, 1]), None);
+        assert_eq!(pck_chain_from_aux_blob(b""), None);
+    }
+
+    #[test]
+    fn test_policy_from_toml() -> Result<()> {
+        let policy = Policy::from_toml(&format!(
+            r#"
+            nonce = "6e6f6e6365"
+            allow_debug = true
+            accepted_tcb_statuses = ["UpToDate", "SWHardeningNeeded"]
+            min_tcb_date = "2024-11-13"
+            min_tdx_module_svn = 3
+            min_firmware_svn = 2
+            max_endorsement_age_days = 365
+            revoked_firmware_digests = ["{}"]
+            disallow_advisories = ["INTEL-SA-00837"]
+            accepted_servtd_hashes = ["{}"]
+
+            [reference_values]
+            mrtd = "{}"
+            "#,
+            "dd".repeat(48),
+            "cd".repeat(48),
+            "ab".repeat(48)
+        ))?;
+        assert_eq!(policy.nonce.as_deref(), Some(b"nonce".as_slice()));
+        assert!(policy.allow_debug);
+        assert!(!policy.require_endorsement);
+        assert_eq!(policy.accepted_tcb_statuses.len(), 2);
+        assert_eq!(policy.min_tcb_date.as_deref(), Some("2024-11-13"));
+        assert_eq!(policy.min_tdx_module_svn, Some(3));
+        assert_eq!(policy.min_firmware_svn, Some(2));
+        assert_eq!(policy.max_endorsement_age_days, Some(365));
+        assert_eq!(policy.revoked_firmware_digests, vec![[0xdd; 48]]);
+        assert_eq!(policy.disallow_advisories, ["INTEL-SA-00837"]);
+        assert_eq!(policy.accepted_servtd_hashes, vec![[0xcd; 48]]);
+        assert_eq!(policy.reference_values.mrtd, Some([0xab; 48].into()));
+
+        let empty = Policy::from_toml("")?;
+        assert_eq!(empty.accepted_tcb_statuses, vec!["UpToDate"]);
+        assert!(empty.accepted_servtd_hashes.is_empty());
+        assert!(Policy::from_toml(r#"accepted_servtd_hashes = ["abcd"]"#).is_err());
+        assert!(Policy::from_toml(r#"min_tcb_date = "13/11/2024""#).is_err());
+
+        assert!(Policy::from_toml("unknown = true").is_err());
+        assert!(Policy::from_toml(r#"nonce = "xyz""#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_rules() -> Result<()> {
+        let checks = |policy: &Policy| -> Vec<&'static str> {
+            policy.rules().iter().map(|rule| rule.check).collect()
+        };
+
+        let policy = Policy::new();
+        assert_eq!(
+            checks(&policy),
+            [
+                "quote-signature",
+                "nonce",
+                "debug",
+                "event-log",
+                "reference-values",
+                "endorsement",
+                "timestamp"
+            ]
+        );
+        assert_eq!(policy.warnings().len(), 3);
+
+        let policy = Policy::from_toml(&format!(
+            r#"
+            allow_debug = true
+            accepted_servtd_hashes = ["{}"]
+            [reference_values]
+            rtmr1 = "{}"
+            "#,
+            "cd".repeat(48),
+            "ab".repeat(48)
+        ))?
+        .with_transparency_log_key(b"key");
+        let rules = policy.rules();
+        assert_eq!(
+            checks(&policy),
+            [
+                "quote-signature",
+                "nonce",
+                "debug",
+                "servtd",
+                "event-log",
+                "reference-values",
+                "endorsement",
+                "transparency",
+                "timestamp"
+            ]
+        );
+        assert_eq!(rules[2].requirement, "Debug TDs are accepted");
+        assert_eq!(
+            rules[5].requirement,
+            format!("The measurements must be: RTMR1 = {}", "ab".repeat(48))
+        );
+
+        let warnings = policy.require_timestamp(true).warnings();
+        assert!(warnings.contains(&"Debug TDs are accepted".to_string()));
+        assert!(warnings.iter().any(|w| w.contains("no TSA root")));
+        assert!(!warnings.iter().any(|w| w.contains("regardless")));
+
+        let policy = Policy::new()
+            .with_min_tcb_date("2024-11-13")
+            .with_disallowed_advisory("INTEL-SA-00837")
+            .with_min_tdx_module_svn(3);
+        let rules = policy.rules();
+        assert_eq!(&checks(&policy)[1..3], ["tcb", "tdx-module"]);
+        assert_eq!(
+            rules[1].requirement,
+            "The platform's TCB status must be one of: UpToDate; its TCB date must be 2024-11-13 or later; it must not be affected by: INTEL-SA-00837"
+        );
+        assert!(
+            policy
+                .warnings()
+                .iter()
+                .any(|w| w.contains("every bundle will be rejected"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_rego() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-rego-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let toml = dir.join("policy.toml");
+        std::fs::write(
+            &toml,
+            "rego_policy = \"bundle.tar.gz\"\nrego_query = \"data.acme.allow\"\n",
+        )?;
+
+        // relative Rego policies are relative to the TOML policy
+        let policy = Policy::from_file(toml.to_str().unwrap())?;
+        let rego = dir.join("bundle.tar.gz").to_string_lossy().into_owned();
+        assert_eq!(policy.rego_policy.as_deref(), Some(rego.as_str()));
+        let rules = policy.rules();
+        assert_eq!(rules.last().unwrap().check, "rego");
+        assert!(
+            rules
+                .last()
+                .unwrap()
+                .requirement
+                .contains("data.acme.allow")
+        );
+
+        // Rego policies can be used on their own
+        let policy = Policy::from_file(&rego)?;
+        assert_eq!(policy.rego_policy.as_deref(), Some(rego.as_str()));
+        assert_eq!(policy.rego_query, None);
+
+        // Rego sources must be built first
+        let source = dir.join("policy.rego");
+        assert!(Policy::from_file(source.to_str().unwrap()).is_err_and(|e| e.is_not_supported()));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_cel_rules() -> Result<()> {
+        let policy = Policy::from_toml(
+            r#"
+            [[cel_rules]]
+            name = "production"
+            expression = 'report.td_info.attributes.debug == false && tcb.status == "UpToDate"'
+            "#,
+        )?;
+        assert_eq!(policy.cel_rules.len(), 1);
+        assert_eq!(policy.cel_rules[0].name, "production");
+        let rules = policy.rules();
+        assert_eq!(rules.last().unwrap().check, "cel");
+        assert!(rules.last().unwrap().requirement.contains("production"));
+
+        // malformed rules are rejected when the policy is loaded
+        let malformed = "[[cel_rules]]\nname = \"broken\"\nexpression = \"tcb.status ==\"\n";
+        assert!(Policy::from_toml(malformed).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_with_collateral_dir() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-collateral-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.to_str().unwrap();
+
+        assert!(Policy::new().with_collateral_dir(path).is_err());
+
+        std::fs::write(dir.join("root_ca.der"), [1, 2, 3])?;
+        let policy = Policy::new().with_collateral_dir(path)?;
+        let roots: Vec<_> = policy
+            .trust_anchors
+            .roots(TrustAnchorKind::IntelSgxRoot)
+            .map(|root| root.der.clone())
+            .collect();
+        assert_eq!(roots, vec![vec![1, 2, 3]]);
+        assert!(policy.tcb_info.is_none());
+
+        // the TCB Info requires its signing chain
+        let tcb_info = format!(
+            r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+            tcb::tests::make_tcb_info_json([0; 6]),
+            "00".repeat(64)
+        );
+        std::fs::write(dir.join("tcb_info.json"), tcb_info)?;
+        assert!(Policy::new().with_collateral_dir(path).is_err());
+        std::fs::remove_file(dir.join("tcb_info.json"))?;
+
+        // and so does the QE Identity
+        let qe_identity = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            tcb::tests::make_qe_identity_json([0; 32]),
+            "00".repeat(64)
+        );
+        std::fs::write(dir.join("qe_identity.json"), qe_identity)?;
+        assert!(Policy::new().with_collateral_dir(path).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "tdx-linux")]
+    #[test]
+    fn test_collect() -> Result<()> {
+        use crate::tdx::test_utils::handle_expected_tdx_error;
+
+        match Bundle::collect(b"nonce", None) {
+            Ok(bundle) => {
+                assert_eq!(
+                    bundle.parse_quote()?.body.report_data,
+                    report_data_for_nonce(b"nonce")
+                );
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
+
+    #[cfg(feature = "host-verification")]
+    mod verify {
+        use super::*;
+        use crate::core::quote::QUOTE_HEADER_LEN;
+        use crate::core::quote::tests::QuoteParts;
+        use crate::core::report::TDX_MR_REG_LEN;
+        use crate::evidence::tcb::tests::{make_pck_extensions, make_tcb_info_json};
+        use crate::measure::SHA384_LEN;
+        use crate::measure::ccel::tests::make_ccel;
+        use crate::measure::ccel::{parse_ccel, replay_ccel};
+        use crate::measure::predict::extend_rtmr;
+        use crate::verification::quote::tests::{TestSigner, sign};
+
+        const FMSPC: [u8; 6] = [0x00, 0x80, 0x6f, 0x05, 0x00, 0x00];
+
+        struct Fixture {
+            signer: TestSigner,
+            bundle: Bundle,
+        }
+
+        fn fixture(td_attributes: [u8; 8]) -> Fixture {
+            let ccel = make_ccel(&[(1, 1, [1; SHA384_LEN]), (3, 1, [2; SHA384_LEN])]);
+            let events = vec![custom_event(0, "app"), custom_event(1, "config")];
+
+            let mut rtmrs = replay_ccel(&parse_ccel(&ccel).unwrap());
+            for event in &events {
+                rtmrs[3] = extend_rtmr(&rtmrs[3], &event.digest);
+            }
+
+            let signer = TestSigner::new().with_sgx_extensions(&make_pck_extensions(FMSPC, 5, 13));
+            let quote = signer.sign_quote(QuoteParts {
+                tee_tcb_svn: [5; 16],
+                rtmrs,
+                report_data: report_data_for_nonce(b"nonce"),
+                td_attributes,
+                ..Default::default()
+            });
+
+            let mut bundle = Bundle::new(b"nonce", quote);
+            bundle.ccel = Some(ccel);
+            bundle.event_log = events;
+            Fixture { signer, bundle }
+        }
+
+        fn policy(fixture: &Fixture) -> Policy {
+            Policy::new()
+                .with_nonce(b"nonce")
+                .with_trusted_root(&fixture.signer.root.to_der().unwrap())
+        }
+
+        fn failed(verdict: &Verdict) -> Vec<&str> {
+            verdict
+                .checks
+                .iter()
+                .filter(|c| !c.passed)
+                .map(|c| c.name.as_str())
+                .collect()
+        }
+
+        #[test]
+        fn test_verify() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let quote = fixture.bundle.parse_quote()?;
+            let policy = policy(&fixture).with_reference_values(ReferenceValues {
+                mrtd: Some(quote.body.mrtd),
+                rtmr3: Some(quote.body.rtmrs[3]),
+                ..Default::default()
+            });
+
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("endorsement").is_none());
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_rego() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let policy = policy(&fixture).with_rego_policy("/nonexistent/policy.wasm");
+
+            // the Rego policy fails closed if it cannot be evaluated
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert_eq!(failed(&verdict), vec!["rego"]);
+            assert_eq!(verdict.checks.last().unwrap().name, "rego");
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_cel() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let policy = policy(&fixture).with_cel_rule(cel::CelRule::new(
+                "production",
+                r#"!report.td_info.attributes.debug && checks["nonce"] && quote.version == 4"#,
+            )?);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert_eq!(verdict.checks.last().unwrap().name, "cel");
+
+            // the TCB status is unknown without a TCB Info
+            let policy = policy.with_cel_rule(cel::CelRule::new(
+                "up-to-date",
+                r#"tcb.status == "UpToDate""#,
+            )?);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert_eq!(failed(&verdict), vec!["cel"]);
+            let detail = verdict.check("cel").unwrap().detail.as_deref().unwrap();
+            assert!(detail.contains("up-to-date"), "{}", detail);
+            assert!(!detail.contains("production"), "{}", detail);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_claims() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let quote = fixture.bundle.parse_quote()?;
+            let tail = hex::encode(&quote.body.report_data[32..]);
+            let policy = policy(&fixture)
+                .with_claim_extractor("tail", claims::report_data_field(32..64))
+                .with_cel_rule(cel::CelRule::new(
+                    "tail",
+                    &format!("claims.tail == '{}' && !has(claims.absent)", tail),
+                )?);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("claims").is_some());
+
+            // failing extractors fail the claims check
+            let policy = policy.with_claim_extractor(
+                "broken",
+                |_: &Bundle, _: &quote::Quote| -> Result<Option<serde_json::Value>> {
+                    Err(Error::ParseError("malformed".to_string()))
+                },
+            );
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert_eq!(failed(&verdict), vec!["claims", "cel"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_cached_pck_chain() -> Result<()> {
+            use crate::core::quote::CERT_DATA_PPID_RSA3072;
+
+            let fixture = fixture([0; 8]);
+            let quote = fixture.bundle.parse_quote()?;
+            let pem = String::from_utf8(quote.cert_data.clone()).unwrap();
+
+            // replace the quote's PCK chain with the platform's identity
+            let identity = [vec![b'p'; 384], vec![b's'; 16], vec![13, 0, 0, 0]].concat();
+            let parts = QuoteParts {
+                tee_tcb_svn: quote.body.tee_tcb_svn,
+                mrtd: quote.body.mrtd.into(),
+                rtmrs: quote.body.rtmrs.map(Into::into),
+                report_data: quote.body.report_data,
+                attestation_key: quote.attestation_key,
+                qe_report: quote.qe_report,
+                qe_auth_data: quote.qe_auth_data.clone(),
+                pck_chain: String::from_utf8(identity).unwrap(),
+                cert_data_type: CERT_DATA_PPID_RSA3072,
+                ..Default::default()
+            };
+            let mut bundle = fixture.bundle.clone();
+            bundle.quote = parts.assemble(&quote.signature, &quote.qe_report_signature);
+
+            let policy = policy(&fixture);
+            assert!(bundle.verify(&policy).is_err_and(|e| e.is_not_supported()));
+            assert!(
+                bundle
+                    .clone()
+                    .with_pck_chain(&pem)
+                    .verify(&policy)?
+                    .passed()
+            );
+
+            let dir = std::env::temp_dir().join(format!("tdx-verify-pck-{}", std::process::id()));
+            let cache = PckCache::new(&dir);
+            let policy = policy.with_pck_cache(cache.clone());
+            assert!(bundle.verify(&policy).is_err_and(|e| e.is_not_supported()));
+
+            cache.put(&pck::PlatformId::from_quote(&bundle.parse_quote()?)?, &pem)?;
+            let verdict = bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+
+            std::fs::remove_dir_all(&dir)?;
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_failures() -> Result<()> {
+            let fixture = fixture([0; 8]);
+
+            // wrong nonce, wrong reference values, and a required endorsement
+            let strict = policy(&fixture)
+                .with_nonce(b"other nonce")
+                .with_reference_values(ReferenceValues {
+                    rtmr0: Some([0; SHA384_LEN].into()),
+                    ..Default::default()
+                })
+                .require_endorsement(true);
+            let verdict = fixture.bundle.verify(&strict)?;
+            assert_eq!(
+                failed(&verdict),
+                vec!["nonce", "reference-values", "endorsement"]
+            );
+
+            // a minimum firmware SVN or maximum age requires an endorsement
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_min_firmware_svn(1))?;
+            assert_eq!(failed(&verdict), vec!["endorsement"]);
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_max_endorsement_age_days(30))?;
+            assert_eq!(failed(&verdict), vec!["endorsement"]);
+
+            // a tampered event log
+            let mut bundle = fixture.bundle.clone();
+            bundle.event_log[1] = custom_event(1, "other config");
+            assert_eq!(
+                failed(&bundle.verify(&policy(&fixture))?),
+                vec!["event-log"]
+            );
+
+            // an event left out of the log
+            bundle.event_log.pop();
+            assert_eq!(
+                failed(&bundle.verify(&policy(&fixture))?),
+                vec!["event-log"]
+            );
+
+            // no trusted root
+            let verdict = fixture.bundle.verify(&Policy::new())?;
+            assert_eq!(failed(&verdict), vec!["quote-signature"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_session() -> Result<()> {
+            use crate::core::report::diff::Fields;
+
+            let fixture = fixture([0; 8]);
+            let fields = Fields::from(&fixture.bundle.parse_quote()?);
+            let session = BootSession::derive(&fields, 1000);
+            let bundle = fixture.bundle.clone().with_session(session.clone());
+            let verdict = bundle.verify(&policy(&fixture))?;
+            assert!(verdict.check("session").is_some_and(|c| c.passed));
+
+            // a session of another boot (or TD)
+            let bundle = fixture.bundle.clone().with_session(BootSession {
+                boot_time: 1001,
+                ..session
+            });
+            assert_eq!(failed(&bundle.verify(&policy(&fixture))?), vec!["session"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_allow_list() -> Result<()> {
+            use crate::measure::allowlist::{AllowList, SharedAllowList};
+
+            let fixture = fixture([0; 8]);
+            let mrtd = hex::encode(fixture.bundle.parse_quote()?.body.mrtd);
+            let allow_list = |mrtd: &str| {
+                AllowList::from_toml(&format!(
+                    "[[entry]]\nname = \"a\"\nmrtd = \"{}\"\nnot_after = \"2030-01-01T00:00:00Z\"\n",
+                    mrtd
+                ))
+            };
+
+            let shared = SharedAllowList::new(allow_list(&mrtd)?);
+            let policy = policy(&fixture).with_allow_list(shared.clone());
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+
+            // the allow-list is rotated
+            shared.replace(allow_list(&"ff".repeat(48))?);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert_eq!(failed(&verdict), ["allow-list"]);
+
+            // the entry expired
+            let policy = policy
+                .with_allow_list(allow_list(&mrtd)?)
+                .with_verification_time(1_900_000_000);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert!(failed(&verdict).contains(&"allow-list"));
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_transparency() -> Result<()> {
+            use crate::verification::transparency::tests::TestLog;
+
+            let log = TestLog::new(&[b"other", b"endorsement"], 5);
+            let fixture = fixture([0; 8]);
+            let policy = policy(&fixture);
+            let mut bundle = fixture.bundle.with_endorsement(Endorsement {
+                provider: "test".to_string(),
+                data: b"endorsement".to_vec(),
+                log_entry: None,
+            });
+            assert!(bundle.verify(&policy)?.check("transparency").is_none());
+
+            // the endorsement isn't logged
+            let policy = policy.with_transparency_log_key(&log.public_key());
+            assert!(failed(&bundle.verify(&policy)?).contains(&"transparency"));
+
+            // the endorsement is logged
+            let endorsement = bundle.endorsement.as_mut().unwrap();
+            endorsement.log_entry = Some(log.entry_json(1));
+            assert!(!failed(&bundle.verify(&policy)?).contains(&"transparency"));
+
+            // the entry logs another artifact
+            let endorsement = bundle.endorsement.as_mut().unwrap();
+            endorsement.log_entry = Some(log.entry_json(0));
+            assert!(failed(&bundle.verify(&policy)?).contains(&"transparency"));
+
+            // the entry is from another log
+            let other = TestLog::new(&[b"endorsement"], 1);
+            let policy = Policy::new().with_transparency_log_key(&other.public_key());
+            let endorsement = bundle.endorsement.as_mut().unwrap();
+            endorsement.log_entry = Some(log.entry_json(1));
+            assert!(failed(&bundle.verify(&policy)?).contains(&"transparency"));
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_crl() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let policy = policy(&fixture);
+            let now = policy.verification_time()?;
+            let signer = &fixture.signer;
+            assert!(fixture.bundle.verify(&policy)?.check("crl").is_none());
+
+            let current = policy
+                .clone()
+                .with_crl(&signer.crl(&[], now - 60, now + 3600));
+            let verdict = fixture.bundle.verify(&current)?;
+            assert!(
+                verdict.check("crl").is_some_and(|c| c.passed),
+                "{:?}",
+                verdict
+            );
+            assert_eq!(current.rules()[1].check, "crl");
+
+            // the PCK certificate is revoked
+            let revoked =
+                policy
+                    .clone()
+                    .with_crl(&signer.crl(&[signer.pck()], now - 60, now + 3600));
+            assert_eq!(failed(&fixture.bundle.verify(&revoked)?), vec!["crl"]);
+
+            // the root's only CRL is stale
+            let stale = policy.with_crl(&signer.crl(&[], now - 3600, now - 60));
+            assert_eq!(failed(&fixture.bundle.verify(&stale)?), vec!["crl"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_timestamp() -> Result<()> {
+            use crate::verification::timestamp::tests::TestTsa;
+
+            let fixture = fixture([0; 8]);
+            let tsa = TestTsa::new();
+            let policy = policy(&fixture);
+            assert!(fixture.bundle.verify(&policy)?.check("timestamp").is_none());
+
+            let bundle = fixture
+                .bundle
+                .clone()
+                .with_timestamp(tsa.timestamp(&fixture.bundle.quote, 1_640_000_000));
+            assert_eq!(failed(&bundle.verify(&policy)?), vec!["timestamp"]);
+
+            let mut anchors = policy.trust_anchors.clone();
+            anchors = anchors.with_anchor(TrustAnchorKind::TsaRoot, &tsa.root.to_der().unwrap());
+            let policy = policy.with_trust_anchors(anchors).require_timestamp(true);
+            let verdict = bundle.verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("timestamp").is_some());
+
+            // a timestamp over another quote, and a missing timestamp
+            let other = fixture
+                .bundle
+                .clone()
+                .with_timestamp(tsa.timestamp(b"quote", 1_640_000_000));
+            assert_eq!(failed(&other.verify(&policy)?), vec!["timestamp"]);
+            assert_eq!(failed(&fixture.bundle.verify(&policy)?), vec!["timestamp"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_servtd() -> Result<()> {
+            use crate::core::report::NO_SERVTD_HASH;
+
+            let fixture = fixture([0; 8]);
+            let body = fixture.bundle.parse_quote()?.body;
+            let quote_with_servtd = |mrservicetd| {
+                fixture.signer.sign_quote(QuoteParts {
+                    version: 5,
+                    tee_tcb_svn: body.tee_tcb_svn,
+                    rtmrs: body.rtmrs.map(Into::into),
+                    report_data: body.report_data,
+                    mrservicetd,
+                    ..Default::default()
+                })
+            };
+            let migtd = [9; TDX_MR_REG_LEN];
+            let strict = policy(&fixture).with_accepted_servtd_hash(migtd.into());
+
+            // not checked unless the policy accepts some hashes
+            let verdict = fixture.bundle.verify(&policy(&fixture))?;
+            assert!(verdict.check("servtd").is_none());
+
+            let mut bundle = fixture.bundle.clone();
+            bundle.quote = quote_with_servtd(Some(migtd));
+            let verdict = bundle.verify(&strict)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("servtd").is_some());
+
+            // an unexpected service TD, or none bound
+            bundle.quote = quote_with_servtd(Some([8; TDX_MR_REG_LEN]));
+            assert_eq!(failed(&bundle.verify(&strict)?), vec!["servtd"]);
+            bundle.quote = quote_with_servtd(Some(NO_SERVTD_HASH.into()));
+            assert_eq!(failed(&bundle.verify(&strict)?), vec!["servtd"]);
+            let strict = strict.with_accepted_servtd_hash(NO_SERVTD_HASH);
+            assert!(bundle.verify(&strict)?.passed());
+
+            // a TDX 1.0 body can't be checked
+            assert_eq!(failed(&fixture.bundle.verify(&strict)?), vec!["servtd"]);
+            Ok(())
+        }
+
+        fn signed_tcb_info(fixture: &Fixture, tcb_info: &str) -> (SignedTcbInfo, Vec<u8>) {
+            let (cert, key) = fixture.signer.issue("Test TCB Signing");
+            let json = format!(
+                r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+                tcb_info,
+                hex::encode(sign(tcb_info.as_bytes(), &key))
+            );
+            (SignedTcbInfo::parse(&json).unwrap(), cert)
+        }
+
+        #[test]
+        fn test_verify_tcb() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let (tcb_info, cert) = signed_tcb_info(&fixture, &make_tcb_info_json(FMSPC));
+            let with_tcb = policy(&fixture).with_tcb_info(tcb_info.clone(), &[cert]);
+
+            let verdict = fixture.bundle.verify(&with_tcb)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("tcb").is_some());
+
+            // an unsigned TCB Info
+            let unsigned = policy(&fixture).with_tcb_info(tcb_info, &[]);
+            assert_eq!(failed(&fixture.bundle.verify(&unsigned)?), vec!["tcb"]);
+
+            // a TCB Info for another platform family
+            let (other, cert) = signed_tcb_info(&fixture, &make_tcb_info_json([0; 6]));
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_tcb_info(other, &[cert]))?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+
+            // a platform TCB status the policy doesn't accept
+            let mut strict = with_tcb;
+            strict.accepted_tcb_statuses = vec!["OutOfDate".to_string()];
+            let verdict = fixture.bundle.verify(&strict)?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+            assert_eq!(
+                verdict.check("tcb").unwrap().detail.as_deref(),
+                Some("Platform TCB status is UpToDate")
+            );
+            assert!(verdict.advisories.is_empty());
+
+            // a platform needing software hardening, whose advisories are
+            // reported whether or not the policy accepts its status
+            let json = make_tcb_info_json(FMSPC).replace(
+                r#""tcbStatus":"UpToDate""#,
+                r#""tcbStatus":"SWHardeningNeeded","advisoryIDs":["INTEL-SA-00615"]"#,
+            );
+            let (hardening, cert) = signed_tcb_info(&fixture, &json);
+            let catalog = tcb::AdvisoryCatalog::new().with_advisory(
+                "INTEL-SA-00615",
+                tcb::AdvisoryInfo {
+                    cves: vec!["CVE-2022-21123".to_string()],
+                    ..Default::default()
+                },
+            );
+            let policy = policy(&fixture)
+                .with_tcb_info(hardening, &[cert])
+                .with_advisories(catalog);
+            let verdict = fixture.bundle.verify(&policy)?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+            assert_eq!(verdict.advisories.len(), 1);
+            assert_eq!(verdict.advisories[0].cves, ["CVE-2022-21123"]);
+            assert_eq!(
+                verdict.advisories[0].mitigation,
+                Some(tcb::Mitigation::SoftwareHardening)
+            );
+
+            let mut lenient = policy;
+            lenient
+                .accepted_tcb_statuses
+                .push(tcb::TCB_STATUS_SW_HARDENING_NEEDED.to_string());
+            let verdict = fixture.bundle.verify(&lenient)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert_eq!(verdict.advisories[0].id, "INTEL-SA-00615");
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_with_cache() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let (tcb_info, cert) = signed_tcb_info(&fixture, &make_tcb_info_json(FMSPC));
+            let cache = Arc::new(VerificationCache::new());
+            let policy = policy(&fixture)
+                .with_tcb_info(tcb_info, &[cert])
+                .with_verification_cache(cache.clone());
+
+            // the PCK chain and TCB Info are verified once
+            for _ in 0..2 {
+                let verdict = fixture.bundle.verify(&policy)?;
+                assert!(verdict.passed(), "{:?}", verdict);
+            }
+            let stats = cache.stats();
+            assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 2));
+
+            // but the quote's signature is still verified
+            let mut bundle = fixture.bundle.clone();
+            bundle.quote[QUOTE_HEADER_LEN + 20] ^= 1;
+            let verdict = bundle.verify(&policy)?;
+            assert!(!verdict.check("quote-signature").unwrap().passed);
+            assert_eq!(cache.stats().hits, 4);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_tcb_constraints() -> Result<()> {
+            let fixture = fixture([0; 8]);
+            let json = make_tcb_info_json(FMSPC).replace(
+                r#""tcbStatus":"UpToDate""#,
+                r#""tcbStatus":"UpToDate","advisoryIDs":["INTEL-SA-00837"]"#,
+            );
+            let (tcb_info, cert) = signed_tcb_info(&fixture, &json);
+            let with_tcb = policy(&fixture).with_tcb_info(tcb_info, &[cert]);
+
+            // the platform's level is dated 2025-01-01
+            let verdict = fixture
+                .bundle
+                .verify(&with_tcb.clone().with_min_tcb_date("2025-01-01"))?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            let verdict = fixture
+                .bundle
+                .verify(&with_tcb.clone().with_min_tcb_date("2025-01-01T00:00:01Z"))?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+            assert_eq!(
+                verdict.check("tcb").unwrap().detail.as_deref(),
+                Some(
+                    "Platform TCB date 2025-01-01T00:00:00Z is before the minimum 2025-01-01T00:00:01Z"
+                )
+            );
+
+            // the platform is affected by INTEL-SA-00837
+            let verdict = fixture
+                .bundle
+                .verify(&with_tcb.clone().with_disallowed_advisory("INTEL-SA-00615"))?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            let verdict = fixture
+                .bundle
+                .verify(&with_tcb.with_disallowed_advisory("INTEL-SA-00837"))?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+
+            // constraining the TCB level requires a TCB Info
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_min_tcb_date("2025-01-01"))?;
+            assert_eq!(failed(&verdict), vec!["tcb"]);
+
+            // the TDX module SVN is 5
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_min_tdx_module_svn(5))?;
+            assert!(verdict.check("tdx-module").unwrap().passed);
+            let verdict = fixture
+                .bundle
+                .verify(&policy(&fixture).with_min_tdx_module_svn(6))?;
+            assert_eq!(failed(&verdict), vec!["tdx-module"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_qe_identity() -> Result<()> {
+            use crate::evidence::tcb::tests::make_qe_identity_json;
+            use crate::verification::qe::tests::{QE_MRSIGNER, make_qe_report};
+
+            let signer = TestSigner::new();
+            let bundle = |isvsvn| {
+                let quote = signer.sign_quote(QuoteParts {
+                    qe_report: make_qe_report(0x11, isvsvn),
+                    report_data: report_data_for_nonce(b"nonce"),
+                    ..Default::default()
+                });
+                Bundle::new(b"nonce", quote)
+            };
+
+            let (cert, key) = signer.issue("Test TCB Signing");
+            let signed = |qe_identity: &str| {
+                let json = format!(
+                    r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+                    qe_identity,
+                    hex::encode(sign(qe_identity.as_bytes(), &key))
+                );
+                SignedQeIdentity::parse(&json).unwrap()
+            };
+            let policy = Policy::new()
+                .with_trusted_root(&signer.root.to_der().unwrap())
+                .with_qe_identity(
+                    signed(&make_qe_identity_json(QE_MRSIGNER)),
+                    std::slice::from_ref(&cert),
+                );
+
+            let verdict = bundle(4).verify(&policy)?;
+            assert!(verdict.passed(), "{:?}", verdict);
+            assert!(verdict.check("qe-identity").is_some());
+
+            // an out-of-date QE, and a QE below every TCB level
+            let verdict = bundle(2).verify(&policy)?;
+            assert_eq!(failed(&verdict), vec!["qe-identity"]);
+            assert_eq!(
+                verdict.check("qe-identity").unwrap().detail.as_deref(),
+                Some("QE TCB status is OutOfDate")
+            );
+            assert_eq!(failed(&bundle(1).verify(&policy)?), vec!["qe-identity"]);
+
+            // a QE Identity of another signer, and an unsigned QE Identity
+            let other = signed(&make_qe_identity_json([0; 32]));
+            let verdict = bundle(4).verify(&policy.clone().with_qe_identity(other, &[cert]))?;
+            assert_eq!(failed(&verdict), vec!["qe-identity"]);
+            let unsigned =
+                policy.with_qe_identity(signed(&make_qe_identity_json(QE_MRSIGNER)), &[]);
+            assert_eq!(failed(&bundle(4).verify(&unsigned)?), vec!["qe-identity"]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_verify_debug() -> Result<()> {
+            let fixture = fixture([1, 0, 0, 0, 0, 0, 0, 0]);
+            assert_eq!(
+                failed(&fixture.bundle.verify(&policy(&fixture))?),
+                vec!["debug"]
+            );
+            assert!(
+                fixture
+                    .bundle
+                    .verify(&policy(&fixture).allow_debug(true))?
+                    .passed()
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,686 @@
+//! # PCK Certificate Retrieval
+//!
+//! This module retrieves and caches the Provisioning Certification Key (PCK)
+//! certificates of TDX platforms, for quotes that don't embed their PCK
+//! certificate chain.
+//!
+//! Platforms registered with Intel's registration flow (e.g., in datacenters
+//! without a quote provider library) generate quotes whose certification
+//! data is the platform's identity instead: its Platform Provisioning ID
+//! (PPID), usually encrypted for Intel's Provisioning Certification Service
+//! (PCS), and its raw TCB (CPUSVN, PCE SVN and PCE ID). The same identity is
+//! reported by Intel's `PCKIDRetrievalTool`, as one CSV line per platform.
+//!
+//! Given a `PlatformId` (from a quote, with `PlatformId::from_quote()`, or
+//! from the tool's output, with `PlatformId::parse_csv()`), the PCK
+//! certificate chain can be:
+//! - retrieved from the PCS, or from a local Provisioning Certificate Caching
+//!   Service (PCCS), with a `PcsClient` (when compiled with the
+//!   `pck-retrieval` feature), and
+//! - cached on disk with a `PckCache`, so that relying parties can appraise
+//!   the platform's quotes offline (see `Policy::with_pck_cache()`).
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::evidence::pck::{PckCache, PcsClient, PlatformId};
+//!
+//! // On the host, after running PCKIDRetrievalTool
+//! let csv = std::fs::read_to_string("pckid_retrieval.csv").unwrap();
+//! let cache = PckCache::new("/var/cache/tdx-workload-attestation/pck");
+//! let client = PcsClient::new();
+//! for platform in PlatformId::parse_csv(&csv).unwrap() {
+//!     let chain = client.get_pck_chain_cached(&platform, &cache).unwrap();
+//!     println!("Cached PCK chain:\n{}", chain);
+//! }
+//! ```
+//!
+//! # Notes
+//! - Cache entries are keyed by the platform's (encrypted) PPID and raw TCB.
+//!   Since RSA-OAEP encryption is randomized, the PPID encrypted by
+//!   `PCKIDRetrievalTool` generally differs from the one in the platform's
+//!   quotes: the chains retrieved for the tool's output should be added to
+//!   the platform's bundles instead (see `Bundle::with_pck_chain()`).
+//! - The PCS only accepts PPIDs encrypted with RSA-3072, and the cleartext
+//!   PPID is never sent to it.
+
+use crate::core::quote::{
+    CERT_DATA_PPID_CLEARTEXT, CERT_DATA_PPID_RSA2048, CERT_DATA_PPID_RSA3072, Quote, pem_to_der,
+};
+use crate::error::{Error, Result};
+#[cfg(feature = "pck-retrieval")]
+use crate::retry::RetryPolicy;
+
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The URL of Intel's Provisioning Certification Service.
+pub const DEFAULT_PCS_URL: &str = "https://api.trustedservices.intel.com";
+
+/// The length of a cleartext PPID.
+pub const PPID_LEN: usize = 16;
+
+/// The length of the raw CPUSVN.
+pub const CPUSVN_LEN: usize = 16;
+
+/// The length of a QE ID.
+pub const QE_ID_LEN: usize = 16;
+
+// The PCK certificate endpoint, relative to the PCS or PCCS URL
+#[cfg(feature = "pck-retrieval")]
+const PCK_CERT_PATH: &str = "/sgx/certification/v4/pckcert";
+
+// The header carrying the PCS API key
+#[cfg(feature = "pck-retrieval")]
+const API_KEY_HEADER: &str = "Ocp-Apim-Subscription-Key";
+
+// The header carrying the URL-encoded PCK certificate's issuer chain
+#[cfg(feature = "pck-retrieval")]
+const ISSUER_CHAIN_HEADER: &str = "SGX-PCK-Certificate-Issuer-Chain";
+
+// The name of this library's directory within the user's cache directory
+const CACHE_DIR_NAME: &str = "tdx-workload-attestation";
+
+// The extension of a cached PCK certificate chain
+const CHAIN_EXT: &str = "pem";
+
+/// A platform's Platform Provisioning ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ppid {
+    /// The cleartext PPID.
+    Cleartext([u8; PPID_LEN]),
+    /// The PPID encrypted for the PCS with RSA-OAEP.
+    Encrypted(Vec<u8>),
+}
+
+impl Ppid {
+    /// Returns the raw (possibly encrypted) PPID.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Ppid::Cleartext(ppid) => ppid,
+            Ppid::Encrypted(ppid) => ppid,
+        }
+    }
+}
+
+/// The identity of a platform, which determines its PCK certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlatformId {
+    /// The platform's PPID.
+    pub ppid: Ppid,
+    /// The platform's raw CPUSVN.
+    pub cpusvn: [u8; CPUSVN_LEN],
+    /// The PCE's ISV SVN.
+    pub pcesvn: u16,
+    /// The PCE's ID.
+    pub pce_id: u16,
+    /// The platform's QE ID, if known (it isn't reported in quotes).
+    pub qe_id: Option<[u8; QE_ID_LEN]>,
+}
+
+impl PlatformId {
+    /// Extracts the identity of the platform from a quote that doesn't embed
+    /// its PCK certificate chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the quote's certification data
+    /// isn't the platform's identity (e.g., it embeds the PCK chain), or an
+    /// `Error::ParseError` if it is truncated.
+    pub fn from_quote(quote: &Quote) -> Result<Self> {
+        let data = &quote.cert_data;
+        let ppid_len = match quote.cert_data_type {
+            CERT_DATA_PPID_CLEARTEXT => PPID_LEN,
+            CERT_DATA_PPID_RSA2048 => 256,
+            CERT_DATA_PPID_RSA3072 => 384,
+            cert_data_type => {
+                return Err(Error::NotSupported(format!(
+                    "Quote certification data type {} does not identify the platform",
+                    cert_data_type
+                )));
+            }
+        };
+        if data.len() < ppid_len + CPUSVN_LEN + 4 {
+            return Err(Error::ParseError(
+                "Quote platform identity is truncated".to_string(),
+            ));
+        }
+
+        let (ppid, tcb) = data.split_at(ppid_len);
+        let ppid = match quote.cert_data_type {
+            CERT_DATA_PPID_CLEARTEXT => Ppid::Cleartext(ppid.try_into().unwrap()),
+            _ => Ppid::Encrypted(ppid.to_vec()),
+        };
+        let le_u16 = |offset: usize| u16::from_le_bytes([tcb[offset], tcb[offset + 1]]);
+
+        Ok(Self {
+            ppid,
+            cpusvn: tcb[..CPUSVN_LEN].try_into().unwrap(),
+            pcesvn: le_u16(CPUSVN_LEN),
+            pce_id: le_u16(CPUSVN_LEN + 2),
+            qe_id: None,
+        })
+    }
+
+    /// Parses the output of Intel's `PCKIDRetrievalTool`, with one platform
+    /// per line:
+    /// `<(encrypted) PPID>,<PCE ID>,<CPUSVN>,<PCE ISV SVN>,<QE ID>[,<platform manifest>]`,
+    /// with all fields hex-encoded (and the PCE ID and SVN little-endian).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if a line is malformed.
+    pub fn parse_csv(csv: &str) -> Result<Vec<Self>> {
+        csv.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let invalid = |field: &str| {
+                    Error::ParseError(format!("Invalid {} in PCK ID line {}", field, line))
+                };
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                if fields.len() < 5 {
+                    return Err(invalid("number of fields"));
+                }
+
+                let ppid = hex::decode(fields[0]).map_err(|_| invalid("PPID"))?;
+                let le_u16 = |field: &str, name: &str| -> Result<u16> {
+                    let mut bytes = [0u8; 2];
+                    hex::decode_to_slice(field, &mut bytes).map_err(|_| invalid(name))?;
+                    Ok(u16::from_le_bytes(bytes))
+                };
+                let mut cpusvn = [0u8; CPUSVN_LEN];
+                hex::decode_to_slice(fields[2], &mut cpusvn).map_err(|_| invalid("CPUSVN"))?;
+                let mut qe_id = [0u8; QE_ID_LEN];
+                hex::decode_to_slice(fields[4], &mut qe_id).map_err(|_| invalid("QE ID"))?;
+
+                Ok(Self {
+                    ppid: match <[u8; PPID_LEN]>::try_from(ppid.as_slice()) {
+                        Ok(ppid) => Ppid::Cleartext(ppid),
+                        Err(_) if !ppid.is_empty() => Ppid::Encrypted(ppid),
+                        Err(_) => return Err(invalid("PPID")),
+                    },
+                    cpusvn,
+                    pcesvn: le_u16(fields[3], "PCE ISV SVN")?,
+                    pce_id: le_u16(fields[1], "PCE ID")?,
+                    qe_id: Some(qe_id),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the key of the platform's entry in a `PckCache`, the
+    /// hex-encoded SHA-256 digest of its PPID and raw TCB.
+    pub fn cache_key(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.ppid.as_bytes());
+        hasher.update(self.cpusvn);
+        hasher.update(self.pcesvn.to_le_bytes());
+        hasher.update(self.pce_id.to_le_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// A directory-backed cache of PEM-encoded PCK certificate chains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PckCache {
+    dir: PathBuf,
+}
+
+impl PckCache {
+    /// Creates a new `PckCache` rooted at `dir`.
+    ///
+    /// The directory is created on the first write.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        PckCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the default cache directory for PCK certificates.
+    ///
+    /// This is `$XDG_CACHE_HOME/tdx-workload-attestation/pck`, falling back to
+    /// `$HOME/.cache/tdx-workload-attestation/pck`. Returns `None` if neither
+    /// environment variable is set.
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+            Some(xdg) => PathBuf::from(xdg),
+            None => PathBuf::from(env::var_os("HOME").filter(|v| !v.is_empty())?).join(".cache"),
+        };
+
+        Some(base.join(CACHE_DIR_NAME).join("pck"))
+    }
+
+    /// Returns the directory backing this cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Retrieves the cached PEM-encoded PCK certificate chain of `platform`.
+    ///
+    /// Returns `Ok(None)` if the entry is missing or isn't a valid PEM chain,
+    /// in which case the corrupted entry is removed.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the entry is a symbolic link.
+    /// - `Error::IoError` if the entry exists but cannot be read.
+    pub fn get(&self, platform: &PlatformId) -> Result<Option<String>> {
+        let path = self.entry_path(platform);
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
+            )));
+        }
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let chain = String::from_utf8(fs::read(&path)?)
+            .ok()
+            .filter(|c| is_pem_chain(c));
+        if chain.is_none() {
+            fs::remove_file(&path)?;
+        }
+
+        Ok(chain)
+    }
+
+    /// Stores the PEM-encoded PCK certificate chain of `platform`, replacing
+    /// any existing entry.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::ParseError` if `chain` isn't a PEM certificate chain.
+    /// - `Error::IoError` if the entry cannot be written.
+    pub fn put(&self, platform: &PlatformId, chain: &str) -> Result<()> {
+        if !is_pem_chain(chain) {
+            return Err(Error::ParseError(
+                "PCK certificate chain is not PEM-encoded".to_string(),
+            ));
+        }
+
+        // write to a temporary file first, so a crash can't leave a truncated entry
+        let path = self.entry_path(platform);
+        let tmp = path.with_extension("tmp");
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&tmp, chain)?;
+        fs::rename(&tmp, &path)?;
+
+        Ok(())
+    }
+
+    /// Removes all the cached certificate chains, e.g., after the platform's
+    /// TCB may have changed, and returns the number removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if an entry cannot be removed.
+    pub fn clear(&self) -> Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == CHAIN_EXT) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn entry_path(&self, platform: &PlatformId) -> PathBuf {
+        self.dir
+            .join(platform.cache_key())
+            .with_extension(CHAIN_EXT)
+    }
+}
+
+/// Returns whether `chain` holds at least one PEM-encoded certificate.
+fn is_pem_chain(chain: &str) -> bool {
+    pem_to_der(chain).is_ok_and(|certs| !certs.is_empty())
+}
+
+/// A client for the PCK certificate API of Intel's PCS, or of a PCCS.
+#[cfg(feature = "pck-retrieval")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcsClient {
+    url: String,
+    api_key: Option<String>,
+    send_qe_id: bool,
+    retry_policy: RetryPolicy,
+    fallbacks: Vec<PcsClient>,
+}
+
+#[cfg(feature = "pck-retrieval")]
+impl Default for PcsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "pck-retrieval")]
+impl PcsClient {
+    /// Creates a new client for Intel's PCS.
+    pub fn new() -> Self {
+        Self {
+            url: DEFAULT_PCS_URL.to_string(),
+            api_key: None,
+            send_qe_id: false,
+            retry_policy: RetryPolicy::default(),
+            fallbacks: vec![],
+        }
+    }
+
+    /// Creates a new client for the PCCS at `url` (e.g.,
+    /// `https://localhost:8081`), which also requires the platform's QE ID.
+    pub fn pccs(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            send_qe_id: true,
+            ..Self::new()
+        }
+    }
+
+    /// Sets the URL of the PCS, e.g., of the PCS of a regional provisioning
+    /// infrastructure, whose PCK certificates chain up to a regional Intel SGX
+    /// root (see the `trust` module).
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Falls back to `client` (e.g., for the PCS of another region) if this
+    /// client's PCS doesn't recognize a platform, or cannot be reached.
+    /// Fallbacks are tried in the order they're added.
+    pub fn with_fallback(mut self, client: PcsClient) -> Self {
+        self.fallbacks.push(client);
+        self
+    }
+
+    /// Sets the PCS API subscription key.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Sets the retry policy for requests to the PCS.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Retrieves the PEM-encoded PCK certificate chain of `platform`,
+    /// starting with the PCK certificate, from the PCS or else from its
+    /// fallbacks.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the platform's PPID is in cleartext, or the
+    ///   PCCS requires a QE ID that isn't known.
+    /// - `Error::NetworkError` if neither the PCS nor its fallbacks recognize
+    ///   the platform, or can be reached after exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the response cannot be parsed.
+    pub fn get_pck_chain(&self, platform: &PlatformId) -> Result<String> {
+        let mut result = self.request_pck_chain(platform);
+        for fallback in &self.fallbacks {
+            if !result.as_ref().is_err_and(Error::is_network) {
+                break;
+            }
+            result = fallback.get_pck_chain(platform);
+        }
+        result
+    }
+
+    /// Retrieves the PCK certificate chain of `platform` from this client's
+    /// PCS only.
+    fn request_pck_chain(&self, platform: &PlatformId) -> Result<String> {
+        use crate::http::{http_client, send_with_headers};
+
+        let Ppid::Encrypted(ppid) = &platform.ppid else {
+            return Err(Error::NotSupported(
+                "PCK certificates can only be retrieved with an encrypted PPID".to_string(),
+            ));
+        };
+        let mut query = vec![
+            ("encrypted_ppid", hex::encode(ppid)),
+            ("cpusvn", hex::encode(platform.cpusvn)),
+            ("pcesvn", hex::encode(platform.pcesvn.to_le_bytes())),
+            ("pceid", hex::encode(platform.pce_id.to_le_bytes())),
+        ];
+        if self.send_qe_id {
+            let qe_id = platform.qe_id.ok_or_else(|| {
+                Error::NotSupported("The PCCS requires the platform's QE ID".to_string())
+            })?;
+            query.push(("qeid", hex::encode(qe_id)));
+        }
+
+        let query: Vec<String> = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+
+        let client = http_client(&self.retry_policy)?;
+        let url = format!("{}{}?{}", self.url, PCK_CERT_PATH, query.join("&"));
+        let (pck, headers) = self.retry_policy.run(|| {
+            let mut req = client.get(&url);
+            if let Some(api_key) = &self.api_key {
+                req = req.header(API_KEY_HEADER, api_key);
+            }
+            send_with_headers(req)
+        })?;
+
+        let pck = String::from_utf8(pck)
+            .map_err(|_| Error::ParseError("PCK certificate is not PEM-encoded".to_string()))?;
+        let issuer_chain = headers
+            .get(ISSUER_CHAIN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Error::ParseError("Missing PCK certificate issuer chain".to_string()))?;
+
+        let chain = format!("{}\n{}", pck.trim_end(), percent_decode(issuer_chain)?);
+        if !is_pem_chain(&chain) {
+            return Err(Error::ParseError(
+                "PCK certificate chain is not PEM-encoded".to_string(),
+            ));
+        }
+        Ok(chain)
+    }
+
+    /// Retrieves the PEM-encoded PCK certificate chain of `platform` from
+    /// `cache`, or from the PCS on a cache miss, in which case it is cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be accessed, or the chain cannot
+    /// be retrieved (see `get_pck_chain()`).
+    pub fn get_pck_chain_cached(&self, platform: &PlatformId, cache: &PckCache) -> Result<String> {
+        if let Some(chain) = cache.get(platform)? {
+            return Ok(chain);
+        }
+
+        let chain = self.get_pck_chain(platform)?;
+        cache.put(platform, &chain)?;
+        Ok(chain)
+    }
+}
+
+/// Decodes a URL-encoded (percent-encoded) string.
+#[cfg_attr(not(feature = "pck-retrieval"), allow(dead_code))]
+fn percent_decode(encoded: &str) -> Result<String> {
+    let invalid = || Error::ParseError("Invalid URL-encoded string".to_string());
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'%' => {
+                let digits = rest.get(..2).ok_or_else(invalid)?;
+                let digits = core::str::from_utf8(digits).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(digits, 16).map_err(|_| invalid())?);
+                rest = &rest[2..];
+            }
+            _ => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+
+    const CSV_LINE: &str =
+        "0102,0000,0a0b0c0d000000000000000000000000,0d00,00112233445566778899aabbccddeeff";
+
+    fn chain() -> String {
+        "-----BEGIN CERTIFICATE-----\nbGVhZg==\n-----END CERTIFICATE-----\n\
+         -----BEGIN CERTIFICATE-----\ncm9vdA==\n-----END CERTIFICATE-----\n"
+            .to_string()
+    }
+
+    #[test]
+    fn test_platform_id_from_quote() -> Result<()> {
+        let mut quote = Quote::from_bytes(&QuoteParts::default().assemble(&[6; 64], &[7; 64]))?;
+        assert!(PlatformId::from_quote(&quote).is_err_and(|e| e.is_not_supported()));
+
+        quote.cert_data_type = CERT_DATA_PPID_RSA3072;
+        quote.cert_data = [vec![1; 384], vec![2; CPUSVN_LEN], vec![13, 0, 0, 0]].concat();
+        let platform = PlatformId::from_quote(&quote)?;
+        assert_eq!(platform.ppid, Ppid::Encrypted(vec![1; 384]));
+        assert_eq!(platform.cpusvn, [2; CPUSVN_LEN]);
+        assert_eq!((platform.pcesvn, platform.pce_id), (13, 0));
+        assert!(quote.pck_chain().is_err());
+
+        quote.cert_data.truncate(390);
+        assert!(PlatformId::from_quote(&quote).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pckid_csv() -> Result<()> {
+        let cleartext =
+            "00112233445566778899aabbccddeeff,0000,00,0d00,00112233445566778899aabbccddeeff";
+        let csv = format!("{}\n\n{},deadbeef\n", CSV_LINE, CSV_LINE);
+        let platforms = PlatformId::parse_csv(&csv)?;
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[0], platforms[1]);
+        assert_eq!(platforms[0].ppid, Ppid::Encrypted(vec![1, 2]));
+        assert_eq!(platforms[0].cpusvn[..4], [10, 11, 12, 13]);
+        assert_eq!((platforms[0].pcesvn, platforms[0].pce_id), (13, 0));
+        assert_eq!(platforms[0].qe_id.unwrap()[15], 0xff);
+
+        assert!(PlatformId::parse_csv("0102,0000,0a0b").is_err());
+        assert!(PlatformId::parse_csv(cleartext).is_err());
+        assert!(
+            PlatformId::parse_csv(&cleartext.replace(",00,", ",0a0b0c0d000000000000000000000000,"))
+                .is_ok_and(|p| matches!(p[0].ppid, Ppid::Cleartext(_)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pck_cache() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-pck-cache-{}", std::process::id()));
+        let cache = PckCache::new(&dir);
+        let platform = &PlatformId::parse_csv(CSV_LINE)?[0];
+        assert_eq!(cache.get(platform)?, None);
+
+        cache.put(platform, &chain())?;
+        assert_eq!(cache.get(platform)?, Some(chain()));
+        assert!(cache.put(platform, "not a chain").is_err());
+
+        // corrupted entries are removed
+        let other = PlatformId {
+            pcesvn: 14,
+            ..platform.clone()
+        };
+        assert_ne!(platform.cache_key(), other.cache_key());
+        fs::write(
+            dir.join(other.cache_key()).with_extension(CHAIN_EXT),
+            "garbage",
+        )?;
+        assert_eq!(cache.get(&other)?, None);
+        assert!(
+            !dir.join(other.cache_key())
+                .with_extension(CHAIN_EXT)
+                .exists()
+        );
+
+        assert_eq!(cache.clear()?, 1);
+        assert_eq!(cache.get(platform)?, None);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_percent_decode() -> Result<()> {
+        assert_eq!(
+            percent_decode("-----BEGIN%20CERTIFICATE-----%0Aab%2Bc")?,
+            "-----BEGIN CERTIFICATE-----\nab+c"
+        );
+        assert!(percent_decode("%2").is_err());
+        assert!(percent_decode("%zz").is_err());
+        Ok(())
+    }
+    #[cfg(feature = "pck-retrieval")]
+    #[test]
+    fn test_pcs_fallback() -> Result<()> {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        // a regional PCS serving a single PCK certificate chain
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request)?;
+            let chain = chain();
+            let (leaf, issuers) = chain.split_at(chain.find("-----END").unwrap() + 26);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n{}: {}\r\nConnection: close\r\n\r\n{}",
+                leaf.len(),
+                ISSUER_CHAIN_HEADER,
+                issuers.replace('\n', "%0A").replace(' ', "%20"),
+                leaf
+            )?;
+            Ok(String::from_utf8_lossy(&request[..len]).to_string())
+        });
+
+        let platform = &PlatformId::parse_csv(CSV_LINE)?[0];
+        let unreachable = PcsClient::new()
+            .with_url("http://127.0.0.1:1/")
+            .with_retry_policy(RetryPolicy::no_retry());
+        assert!(
+            unreachable
+                .get_pck_chain(platform)
+                .unwrap_err()
+                .is_network()
+        );
+
+        let client = unreachable.with_fallback(
+            PcsClient::new()
+                .with_url(&url)
+                .with_retry_policy(RetryPolicy::no_retry()),
+        );
+        assert_eq!(
+            client.get_pck_chain(platform)?.trim_end(),
+            chain().trim_end()
+        );
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("GET /sgx/certification/v4/pckcert?encrypted_ppid=0102&"));
+        Ok(())
+    }
+}
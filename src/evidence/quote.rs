@@ -0,0 +1,28 @@
+//! # TD Quote Parsing
+//!
+//! This module parses Intel TDX DCAP quotes (versions 4 and 5), which are
+//! generated by the Quote Generation Service (QGS) from a `TDREPORT`, and
+//! signed by the Quoting Enclave (QE) with an ECDSA P-256 attestation key.
+//!
+//! The parsing logic lives in the `no_std` compatible `core::quote` module,
+//! and is re-exported here for use with the rest of the evidence tooling.
+//! Parsing errors convert into the library's `Error` with `?`.
+//!
+//! Parsing does not verify any signatures (see the `verification::quote`
+//! module, when compiled with the `host-verification` feature).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//!
+//! let bytes = std::fs::read("quote.bin").unwrap();
+//! let quote = Quote::from_bytes(&bytes).unwrap();
+//! println!("MRTD: {}", quote.body.mrtd);
+//! println!("PCK chain has {} certs", quote.pck_chain().unwrap().len());
+//! ```
+
+pub use crate::core::quote::{
+    CERT_DATA_PCK_CHAIN, CERT_DATA_QE_REPORT, ECDSA_P256_KEY_TYPE, QE_REPORT_LEN, QUOTE_HEADER_LEN,
+    Quote, TD_QUOTE_BODY_V10_LEN, TD_QUOTE_BODY_V15_LEN, TDX_TEE_TYPE, TdQuoteBody, pem_to_der,
+};
@@ -0,0 +1,625 @@
+//! # Rego Policies
+//!
+//! Platform teams that already maintain their attestation policies in Rego
+//! can appraise evidence bundles against them, in addition to (or instead
+//! of) the native TOML policy: a `Policy` with a `rego_policy` (e.g., read
+//! from a bundle with `Policy::from_file()`) has `Bundle::verify()` evaluate
+//! it as its `rego` check.
+//!
+//! Policies are evaluated in process by an embedded evaluator for the
+//! WebAssembly modules the Open Policy Agent (OPA) compiles Rego to, so that
+//! verifiers don't depend on the `opa` binary. They must be built with
+//! `opa build -t wasm`, with the query as an entrypoint (e.g.,
+//! `-e tdx/allow`), and are read from the resulting bundle (`.tar.gz`, whose
+//! `data.json` files are the policy's `data`) or its `policy.wasm` module.
+//! The built-in functions that OPA implements in WebAssembly can be used,
+//! but not those it leaves to the host (e.g., `time.now_ns` or `http.send`),
+//! which are rejected. The Rego query (`data.tdx.allow` by default) must be
+//! a boolean, and the check fails if it is false or undefined.
+//!
+//! The policy's `input` document holds:
+//! - `quote`: the fields of the quote body (e.g., `mrtd`, `rtmrs` and
+//!   `report_data`), hex-encoded, and its `version` and `debug` attribute,
+//! - `nonce`: the bundle's nonce, hex-encoded,
+//! - `claims`: the custom claims extracted by the policy's claim extractors
+//!   (see the `claims` module), and
+//! - `checks` and `advisories`: the results of the native checks that ran
+//!   before it, and the advisories affecting the platform (see `Verdict`),
+//!   so that Rego policies can build on them (e.g., accept some TCB
+//!   statuses for some workloads).
+//!
+//! ## Example Usage
+//!
+//! ```rego
+//! package tdx
+//!
+//! default allow := false
+//!
+//! allow if {
+//!     every check in input.checks { check.passed }
+//!     input.quote.mrtd in data.tdx.golden_mrtds
+//! }
+//! ```
+//!
+//! ```sh
+//! opa build -t wasm -e tdx/allow -o bundle.tar.gz policy.rego data.json
+//! ```
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::rego::RegoPolicy;
+//!
+//! let rego = RegoPolicy::new("bundle.tar.gz").with_query("data.tdx.allow");
+//! let allowed = rego.evaluate(&serde_json::json!({"checks": []})).unwrap();
+//! ```
+
+use crate::core::quote::Quote;
+use crate::error::{Error, Result};
+use crate::evidence::Verdict;
+
+use flate2::read::GzDecoder;
+use serde_json::{Map, Value, json};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use wasmi::{Caller, Engine, ExternType, Func, Instance, Linker, Memory, Module, Store};
+
+/// The default Rego query deciding whether the evidence passes.
+pub const DEFAULT_REGO_QUERY: &str = "data.tdx.allow";
+
+/// A Rego policy, compiled to WebAssembly by OPA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegoPolicy {
+    path: PathBuf,
+    query: String,
+}
+
+impl RegoPolicy {
+    /// Creates a policy from the OPA bundle (`.tar.gz`) built with
+    /// `opa build -t wasm` at `path`, or from its `policy.wasm` module.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            query: DEFAULT_REGO_QUERY.to_string(),
+        }
+    }
+
+    /// Sets the query deciding whether the evidence passes, which must be
+    /// one of the policy's entrypoints.
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.query = query.to_string();
+        self
+    }
+
+    /// Returns the query deciding whether the evidence passes.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Evaluates the policy's query with the `input` document.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::IoError` if the policy cannot be read.
+    /// - `Error::ParseError` if the bundle has no WebAssembly module, or the
+    ///   query's result isn't a boolean.
+    /// - `Error::NotSupported` if the policy needs built-in functions that
+    ///   must be provided by the host.
+    /// - `Error::VerificationError` if the query isn't an entrypoint of the
+    ///   policy, or the evaluation fails.
+    pub fn evaluate(&self, input: &Value) -> Result<bool> {
+        let (wasm, data) = match self.path.to_string_lossy().ends_with(".tar.gz") {
+            true => read_bundle(&self.path)?,
+            false => (std::fs::read(&self.path)?, json!({})),
+        };
+        let mut policy = OpaPolicy::load(&wasm)?;
+
+        let builtins = policy.id_map("builtins")?;
+        if !builtins.is_empty() {
+            let names: Vec<&str> = builtins.keys().map(String::as_str).collect();
+            return Err(Error::NotSupported(format!(
+                "The Rego policy needs built-in functions provided by the host: {}",
+                names.join(", ")
+            )));
+        }
+        let entrypoint = entrypoint_name(&self.query);
+        let entrypoint = policy
+            .id_map("entrypoints")?
+            .get(&entrypoint)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| {
+                Error::VerificationError(format!(
+                    "The Rego query {} is not an entrypoint of the policy (see opa build -e)",
+                    self.query
+                ))
+            })?;
+        parse_result(&policy.eval(entrypoint as i32, &data, input)?)
+    }
+}
+
+/// An instance of a policy compiled to WebAssembly, which follows OPA's
+/// WebAssembly ABI.
+struct OpaPolicy {
+    store: Store<()>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl OpaPolicy {
+    /// Instantiates the policy module `wasm`, with the functions it imports
+    /// from the host.
+    fn load(wasm: &[u8]) -> Result<Self> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm)
+            .map_err(|e| Error::ParseError(format!("Invalid Rego policy module: {}", e)))?;
+        let mut store = Store::new(&engine, ());
+
+        let memory_type = module
+            .imports()
+            .find_map(|import| match import.ty() {
+                ExternType::Memory(ty) if import.name() == "memory" => Some(*ty),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                Error::ParseError("The Rego policy module doesn't import its memory".to_string())
+            })?;
+        let memory = Memory::new(&mut store, memory_type).map_err(evaluation_error)?;
+
+        let mut linker = Linker::new(&engine);
+        let abort = Func::wrap(&mut store, move |caller: Caller<'_, ()>, addr: i32| {
+            let message = c_string(memory.data(&caller), addr).unwrap_or_default();
+            Err::<(), _>(wasmi::Error::new(format!(
+                "Rego policy aborted: {}",
+                message
+            )))
+        });
+        let println = Func::wrap(&mut store, |_: Caller<'_, ()>, _: i32| {});
+        linker
+            .define("env", "memory", memory)
+            .and_then(|l| l.define("env", "opa_abort", abort))
+            .and_then(|l| l.define("env", "opa_println", println))
+            .map_err(evaluation_error)?;
+        // the built-in functions left to the host are rejected before the
+        // evaluation, so these are never called
+        let builtin = Func::wrap(&mut store, |_: i32, _: i32| -> i32 { 0 });
+        let builtin1 = Func::wrap(&mut store, |_: i32, _: i32, _: i32| -> i32 { 0 });
+        let builtin2 = Func::wrap(&mut store, |_: i32, _: i32, _: i32, _: i32| -> i32 { 0 });
+        let builtin3 = Func::wrap(
+            &mut store,
+            |_: i32, _: i32, _: i32, _: i32, _: i32| -> i32 { 0 },
+        );
+        let builtin4 = Func::wrap(
+            &mut store,
+            |_: i32, _: i32, _: i32, _: i32, _: i32, _: i32| -> i32 { 0 },
+        );
+        linker
+            .define("env", "opa_builtin0", builtin)
+            .and_then(|l| l.define("env", "opa_builtin1", builtin1))
+            .and_then(|l| l.define("env", "opa_builtin2", builtin2))
+            .and_then(|l| l.define("env", "opa_builtin3", builtin3))
+            .and_then(|l| l.define("env", "opa_builtin4", builtin4))
+            .map_err(evaluation_error)?;
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(evaluation_error)?;
+        Ok(Self {
+            store,
+            instance,
+            memory,
+        })
+    }
+
+    /// Calls the policy's exported function `name`.
+    fn call<P: wasmi::WasmParams, R: wasmi::WasmResults>(
+        &mut self,
+        name: &str,
+        params: P,
+    ) -> Result<R> {
+        self.instance
+            .get_typed_func::<P, R>(&self.store, name)
+            .and_then(|func| func.call(&mut self.store, params))
+            .map_err(evaluation_error)
+    }
+
+    /// Returns the address of `value`, parsed by the policy.
+    fn value(&mut self, value: &Value) -> Result<i32> {
+        let json =
+            serde_json::to_vec(value).map_err(|e| Error::SerializationError(e.to_string()))?;
+        let addr: i32 = self.call("opa_malloc", json.len() as i32)?;
+        self.memory
+            .write(&mut self.store, addr as usize, &json)
+            .map_err(evaluation_error)?;
+        match self.call("opa_json_parse", (addr, json.len() as i32))? {
+            0 => Err(Error::SerializationError(
+                "The Rego policy failed to parse a JSON document".to_string(),
+            )),
+            value => Ok(value),
+        }
+    }
+
+    /// Returns the JSON document at `addr`, serialized by the policy.
+    fn dump(&mut self, addr: i32) -> Result<Value> {
+        let json: i32 = self.call("opa_json_dump", addr)?;
+        let json = c_string(self.memory.data(&self.store), json).ok_or_else(|| {
+            Error::ParseError("The Rego policy returned an invalid string".to_string())
+        })?;
+        serde_json::from_str(&json)
+            .map_err(|e| Error::ParseError(format!("Invalid Rego policy output: {}", e)))
+    }
+
+    /// Returns the object that the policy's exported function `name` (i.e.,
+    /// `builtins` or `entrypoints`) maps names to IDs with.
+    fn id_map(&mut self, name: &str) -> Result<Map<String, Value>> {
+        let addr = self.call(name, ())?;
+        match self.dump(addr)? {
+            Value::Object(map) => Ok(map),
+            value => Err(Error::ParseError(format!(
+                "The Rego policy's {} are not an object: {}",
+                name, value
+            ))),
+        }
+    }
+
+    /// Evaluates the policy's `entrypoint` with `data` and `input`, and
+    /// returns its result set.
+    fn eval(&mut self, entrypoint: i32, data: &Value, input: &Value) -> Result<Value> {
+        let data = self.value(data)?;
+        let input = self.value(input)?;
+        let ctx: i32 = self.call("opa_eval_ctx_new", ())?;
+        self.call::<_, ()>("opa_eval_ctx_set_data", (ctx, data))?;
+        self.call::<_, ()>("opa_eval_ctx_set_input", (ctx, input))?;
+        self.call::<_, ()>("opa_eval_ctx_set_entrypoint", (ctx, entrypoint))?;
+        match self.call::<_, i32>("eval", ctx)? {
+            0 => {}
+            code => {
+                return Err(Error::VerificationError(format!(
+                    "Rego evaluation failed with error code {}",
+                    code
+                )));
+            }
+        }
+        let result = self.call("opa_eval_ctx_get_result", ctx)?;
+        self.dump(result)
+    }
+}
+
+// Returns the policy module and `data` document of the OPA bundle at `path`,
+// whose `data.json` files hold the data under their directory
+fn read_bundle(path: &Path) -> Result<(Vec<u8>, Value)> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut wasm = None;
+    let mut data = json!({});
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path: Vec<String> = entry
+            .path()?
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(c) => Some(c.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        match entry_path.split_last() {
+            Some((file, [])) if file == "policy.wasm" => {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                wasm = Some(bytes);
+            }
+            Some((file, dirs)) if file == "data.json" => {
+                let value = serde_json::from_reader(&mut entry)
+                    .map_err(|e| Error::ParseError(format!("Invalid Rego policy data: {}", e)))?;
+                merge_data(&mut data, dirs, value);
+            }
+            _ => {}
+        }
+    }
+    let wasm = wasm.ok_or_else(|| {
+        Error::ParseError(format!(
+            "{} has no policy.wasm (see opa build -t wasm)",
+            path.display()
+        ))
+    })?;
+    Ok((wasm, data))
+}
+
+// Merges `value` into `data`, under the keys of `path`
+fn merge_data(data: &mut Value, path: &[String], value: Value) {
+    let mut node = data;
+    for key in path {
+        if !node.is_object() {
+            *node = json!({});
+        }
+        node = node
+            .as_object_mut()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| json!({}));
+    }
+    match (node, value) {
+        (Value::Object(node), Value::Object(value)) => node.extend(value),
+        (node, value) => *node = value,
+    }
+}
+
+// Returns the name of the entrypoint of `query` (e.g., `tdx/allow` for
+// `data.tdx.allow`), as passed to `opa build -e`
+fn entrypoint_name(query: &str) -> String {
+    query
+        .strip_prefix("data.")
+        .unwrap_or(query)
+        .replace('.', "/")
+}
+
+// Returns the NUL-terminated string at `addr` of the policy's memory
+fn c_string(memory: &[u8], addr: i32) -> Option<String> {
+    let bytes = memory.get(usize::try_from(addr).ok()?..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+fn evaluation_error(e: impl std::fmt::Display) -> Error {
+    Error::VerificationError(format!("Rego evaluation failed: {}", e))
+}
+
+/// Returns the `input` document of a Rego policy appraising `quote`, which
+/// binds `nonce`, with the custom `claims`, after the native checks of
+/// `verdict`.
+pub fn input(quote: &Quote, nonce: &[u8], claims: &Map<String, Value>, verdict: &Verdict) -> Value {
+    let body = &quote.body;
+    json!({
+        "quote": {
+            "version": quote.version,
+            "tee_tcb_svn": hex::encode(body.tee_tcb_svn),
+            "mrseam": body.mrseam,
+            "mrsignerseam": body.mrsignerseam,
+            "seam_attributes": hex::encode(body.seam_attributes),
+            "td_attributes": hex::encode(body.td_attributes),
+            "xfam": hex::encode(body.xfam),
+            "mrtd": body.mrtd,
+            "mrconfigid": body.mrconfigid,
+            "mrowner": body.mrowner,
+            "mrownerconfig": body.mrownerconfig,
+            "rtmrs": body.rtmrs,
+            "report_data": hex::encode(body.report_data),
+            "tee_tcb_svn2": body.tee_tcb_svn2.map(hex::encode),
+            "mrservicetd": body.mrservicetd,
+            "debug": body.is_debug(),
+        },
+        "nonce": hex::encode(nonce),
+        "claims": claims,
+        "checks": verdict.checks,
+        "advisories": verdict.advisories,
+    })
+}
+
+// Returns the boolean value of the result set of an evaluation, which is
+// empty if the query is undefined
+fn parse_result(result: &Value) -> Result<bool> {
+    match result.pointer("/0/result") {
+        None => Ok(false),
+        Some(Value::Bool(allowed)) => Ok(*allowed),
+        Some(value) => Err(Error::ParseError(format!(
+            "Rego query result {} is not a boolean",
+            value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::Check;
+    use flate2::{Compression, write::GzEncoder};
+
+    // A stand-in for a policy compiled by OPA, following its WebAssembly
+    // ABI, whose JSON values are their NUL-terminated text. `tdx/allow`
+    // holds unless a check failed, `tdx/undefined` is undefined, `tdx/count`
+    // isn't a boolean and `tdx/abort` aborts.
+    const POLICY: &str = r#"
+        (module
+          (import "env" "memory" (memory 2))
+          (import "env" "opa_abort" (func $abort (param i32)))
+          (import "env" "opa_println" (func (param i32)))
+          (import "env" "opa_builtin0" (func (param i32 i32) (result i32)))
+          (import "env" "opa_builtin1" (func (param i32 i32 i32) (result i32)))
+          (global $heap (mut i32) (i32.const 4096))
+          (global $input (mut i32) (i32.const 0))
+          (global $entrypoint (mut i32) (i32.const 0))
+          (global $result (mut i32) (i32.const 0))
+          (data (i32.const 16) "{\"tdx/allow\":0,\"tdx/undefined\":1,\"tdx/count\":2,\"tdx/abort\":3}\00")
+          (data (i32.const 128) "BUILTINS\00")
+          (data (i32.const 160) "[{\"result\":true}]\00")
+          (data (i32.const 192) "[{\"result\":false}]\00")
+          (data (i32.const 224) "[]\00")
+          (data (i32.const 240) "[{\"result\":3}]\00")
+          (data (i32.const 256) "\"passed\":false")
+          (data (i32.const 288) "policy aborted\00")
+          (func (export "entrypoints") (result i32) (i32.const 16))
+          (func (export "builtins") (result i32) (i32.const 128))
+          (func (export "opa_malloc") (param $len i32) (result i32)
+            (global.get $heap)
+            (global.set $heap (i32.add (global.get $heap) (i32.add (local.get $len) (i32.const 1)))))
+          (func (export "opa_json_parse") (param $addr i32) (param $len i32) (result i32)
+            (i32.store8 (i32.add (local.get $addr) (local.get $len)) (i32.const 0))
+            (local.get $addr))
+          (func (export "opa_json_dump") (param $value i32) (result i32) (local.get $value))
+          (func (export "opa_eval_ctx_new") (result i32) (i32.const 8))
+          (func (export "opa_eval_ctx_set_data") (param i32 i32))
+          (func (export "opa_eval_ctx_set_input") (param i32 i32) (global.set $input (local.get 1)))
+          (func (export "opa_eval_ctx_set_entrypoint") (param i32 i32)
+            (global.set $entrypoint (local.get 1)))
+          (func (export "opa_eval_ctx_get_result") (param i32) (result i32) (global.get $result))
+          (func (export "eval") (param i32) (result i32)
+            (block $done
+              (if (i32.eq (global.get $entrypoint) (i32.const 1))
+                (then (global.set $result (i32.const 224)) (br $done)))
+              (if (i32.eq (global.get $entrypoint) (i32.const 2))
+                (then (global.set $result (i32.const 240)) (br $done)))
+              (if (i32.eq (global.get $entrypoint) (i32.const 3))
+                (then (call $abort (i32.const 288))))
+              (global.set $result
+                (select (i32.const 192) (i32.const 160) (call $failed (global.get $input)))))
+            (i32.const 0))
+          ;; whether the input at $p has a failed check
+          (func $failed (param $p i32) (result i32) (local $i i32)
+            (block $no
+              (loop $scan
+                (br_if $no (i32.eqz (i32.load8_u (local.get $p))))
+                (local.set $i (i32.const 0))
+                (block $mismatch
+                  (loop $cmp
+                    (br_if $mismatch
+                      (i32.ne (i32.load8_u (i32.add (local.get $p) (local.get $i)))
+                              (i32.load8_u (i32.add (i32.const 256) (local.get $i)))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $cmp (i32.lt_u (local.get $i) (i32.const 14))))
+                  (return (i32.const 1)))
+                (local.set $p (i32.add (local.get $p) (i32.const 1)))
+                (br $scan)))
+            (i32.const 0)))
+    "#;
+
+    fn policy_wasm(builtins: &str) -> Vec<u8> {
+        let builtins = builtins.replace('"', "\\\"");
+        wat::parse_str(POLICY.replace("BUILTINS", &builtins)).unwrap()
+    }
+
+    fn write_bundle(path: &Path, files: &[(&str, &[u8])]) -> Result<()> {
+        let mut bundle =
+            tar::Builder::new(GzEncoder::new(File::create(path)?, Compression::default()));
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            bundle.append_data(&mut header, name, *contents)?;
+        }
+        bundle.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_result() -> Result<()> {
+        assert!(parse_result(&json!([{"result": true}]))?);
+        assert!(!parse_result(&json!([{"result": false}]))?);
+        // undefined
+        assert!(!parse_result(&json!([]))?);
+
+        assert!(parse_result(&json!([{"result": ["reason"]}])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_entrypoint_name() {
+        assert_eq!(entrypoint_name("data.tdx.allow"), "tdx/allow");
+        assert_eq!(entrypoint_name("acme.allow"), "acme/allow");
+    }
+
+    #[test]
+    fn test_merge_data() {
+        let mut data = json!({});
+        merge_data(&mut data, &[], json!({"a": 1}));
+        merge_data(&mut data, &["tdx".to_string()], json!({"golden_mrtds": []}));
+        merge_data(&mut data, &["tdx".to_string()], json!({"b": 2}));
+        assert_eq!(data, json!({"a": 1, "tdx": {"golden_mrtds": [], "b": 2}}));
+    }
+
+    #[test]
+    fn test_evaluate() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-rego-eval-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let wasm = dir.join("policy.wasm");
+        std::fs::write(&wasm, policy_wasm("{}"))?;
+
+        let rego = RegoPolicy::new(wasm.to_str().unwrap());
+        assert_eq!(rego.query(), "data.tdx.allow");
+
+        let quote = Quote::from_bytes(&QuoteParts::default().assemble(&[6; 64], &[7; 64]))?;
+        let mut verdict = Verdict::default();
+        verdict.pass("quote-signature");
+        let input = input(&quote, b"nonce", &Map::new(), &verdict);
+        assert_eq!(input["nonce"], "6e6f6e6365");
+        assert_eq!(input["quote"]["debug"], false);
+        assert_eq!(input["quote"]["rtmrs"].as_array().unwrap().len(), 4);
+        assert!(rego.evaluate(&input)?);
+
+        verdict.checks.push(Check {
+            name: "nonce".to_string(),
+            passed: false,
+            detail: None,
+        });
+        let denied = super::input(&quote, b"nonce", &Map::new(), &verdict);
+        assert!(!rego.evaluate(&denied)?);
+
+        // bundles built by opa build -t wasm
+        let bundle = dir.join("bundle.tar.gz");
+        write_bundle(
+            &bundle,
+            &[
+                (".manifest", b"{}"),
+                ("data.json", b"{}"),
+                ("tdx/data.json", b"{\"golden_mrtds\":[]}"),
+                ("policy.wasm", &policy_wasm("{}")),
+            ],
+        )?;
+        let rego = RegoPolicy::new(bundle.to_str().unwrap());
+        assert!(rego.evaluate(&input)?);
+        assert!(!rego.evaluate(&denied)?);
+
+        assert!(
+            !rego
+                .clone()
+                .with_query("data.tdx.undefined")
+                .evaluate(&input)?
+        );
+        assert!(
+            rego.clone()
+                .with_query("data.tdx.count")
+                .evaluate(&input)
+                .is_err_and(|e| matches!(e, Error::ParseError(_)))
+        );
+        assert!(
+            rego.clone()
+                .with_query("data.tdx.abort")
+                .evaluate(&input)
+                .is_err_and(|e| e.to_string().contains("policy aborted"))
+        );
+        assert!(
+            rego.clone()
+                .with_query("data.acme.allow")
+                .evaluate(&input)
+                .is_err_and(|e| matches!(e, Error::VerificationError(_)))
+        );
+
+        // built-in functions left to the host aren't provided
+        std::fs::write(&wasm, policy_wasm("{\"time.now_ns\":0}"))?;
+        assert!(
+            RegoPolicy::new(wasm.to_str().unwrap())
+                .evaluate(&input)
+                .is_err_and(|e| e.is_not_supported())
+        );
+
+        write_bundle(&bundle, &[("data.json", b"{}")])?;
+        assert!(
+            RegoPolicy::new(bundle.to_str().unwrap())
+                .evaluate(&input)
+                .is_err_and(|e| matches!(e, Error::ParseError(_)))
+        );
+        std::fs::write(&wasm, b"not wasm")?;
+        assert!(
+            RegoPolicy::new(wasm.to_str().unwrap())
+                .evaluate(&input)
+                .is_err()
+        );
+        assert!(
+            RegoPolicy::new("/nonexistent/policy.wasm")
+                .evaluate(&input)
+                .is_err()
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
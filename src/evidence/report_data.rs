@@ -0,0 +1,359 @@
+//! # Report Data Commitments
+//!
+//! A TD quote's 64-byte `report_data` is the only caller-controlled field it
+//! signs, so workloads use it to commit to the values a relying party must
+//! tie to the TD: typically a public key, the relying party's nonce, and
+//! application-specific claims. This module standardizes that commitment, so
+//! that TDs and relying parties don't need to agree on an ad-hoc encoding.
+//!
+//! A `ReportDataBuilder` commits a public key, a nonce and any number of
+//! named claims, all optional, to the SHA-512 digest of a domain separator
+//! followed by each field's tag, length and value. Claims are committed in
+//! the order of their names, so builders with the same fields always produce
+//! the same `report_data`. Relying parties rebuild the commitment from the
+//! values they were given, and check it against the quote with
+//! `ReportDataBuilder::verify()`.
+//!
+//! Workloads can also commit their identity, so that quotes carry it rather
+//! than just a nonce: a `WorkloadIdentity` (the image digest, pod UID and
+//! service account) is serialized into a compact JSON document, whose
+//! SHA-384 digest is committed as the `WORKLOAD_IDENTITY_CLAIM` claim (see
+//! `ReportDataBuilder::with_workload_identity()`). The document travels with
+//! the quote, and relying parties extract the identity from it once they've
+//! checked it against the quote's `report_data` (see
+//! `ReportDataBuilder::extract_workload_identity()`).
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::evidence::report_data::ReportDataBuilder;
+//!
+//! // On the TD
+//! let report_data = ReportDataBuilder::new()
+//!     .with_public_key(b"workload public key")
+//!     .with_nonce(b"verifier nonce")
+//!     .with_claim("image", b"sha256:1234")
+//!     .build();
+//! // ... get a quote over `report_data`
+//!
+//! // On the relying party, for the quote's `report_data`
+//! let builder = ReportDataBuilder::new()
+//!     .with_claim("image", b"sha256:1234")
+//!     .with_nonce(b"verifier nonce")
+//!     .with_public_key(b"workload public key");
+//! assert!(builder.verify(&report_data));
+//! ```
+//!
+//! Committing a workload identity:
+//!
+//! ```
+//! use tdx_workload_attestation::evidence::report_data::{ReportDataBuilder, WorkloadIdentity};
+//!
+//! // On the TD
+//! let identity = WorkloadIdentity::new()
+//!     .with_image_digest("sha256:1234")
+//!     .with_service_account("default/web");
+//! let document = identity.to_document();
+//! let report_data = ReportDataBuilder::new()
+//!     .with_nonce(b"verifier nonce")
+//!     .with_workload_identity(&identity)
+//!     .build();
+//! // ... get a quote over `report_data`, and send it with `document`
+//!
+//! // On the relying party, for the quote's `report_data`
+//! let identity = ReportDataBuilder::new()
+//!     .with_nonce(b"verifier nonce")
+//!     .extract_workload_identity(&document, &report_data)
+//!     .unwrap();
+//! assert_eq!(identity.service_account.as_deref(), Some("default/web"));
+//! ```
+
+use crate::core::ct;
+use crate::core::report::TDX_REPORT_DATA_LEN;
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384, Sha512};
+use std::collections::BTreeMap;
+
+// The domain separator of report data commitments
+const REPORT_DATA_CONTEXT: &[u8] = b"tdx-workload-attestation/report-data/v1";
+
+// The tags of the committed fields
+const TAG_PUBLIC_KEY: u8 = 1;
+const TAG_NONCE: u8 = 2;
+const TAG_CLAIM: u8 = 3;
+
+/// The claim under which the digest of a workload identity document is
+/// committed.
+pub const WORKLOAD_IDENTITY_CLAIM: &str = "workload-identity";
+
+/// The identity of the workload running in a TD, committed into
+/// `report_data` as the digest of its compact JSON document.
+///
+/// All fields are optional, and omitted from the document if unset.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadIdentity {
+    /// The digest of the workload's container image, e.g.,
+    /// `sha256:<hex>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_digest: Option<String>,
+    /// The UID of the workload's Kubernetes pod.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod_uid: Option<String>,
+    /// The workload's service account, e.g., `<namespace>/<name>` for
+    /// Kubernetes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account: Option<String>,
+}
+
+impl WorkloadIdentity {
+    /// Creates a new, empty workload identity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the digest of the workload's container image.
+    pub fn with_image_digest(mut self, image_digest: &str) -> Self {
+        self.image_digest = Some(image_digest.to_string());
+        self
+    }
+
+    /// Sets the UID of the workload's pod.
+    pub fn with_pod_uid(mut self, pod_uid: &str) -> Self {
+        self.pod_uid = Some(pod_uid.to_string());
+        self
+    }
+
+    /// Sets the workload's service account.
+    pub fn with_service_account(mut self, service_account: &str) -> Self {
+        self.service_account = Some(service_account.to_string());
+        self
+    }
+
+    /// Serializes the identity into its compact JSON document, with its
+    /// fields in a fixed order and no whitespace.
+    pub fn to_document(&self) -> Vec<u8> {
+        // serializing a struct of strings can't fail
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// Parses an identity document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the document isn't a valid identity.
+    pub fn from_document(document: &[u8]) -> Result<Self> {
+        serde_json::from_slice(document)
+            .map_err(|e| Error::ParseError(format!("Invalid workload identity document: {}", e)))
+    }
+}
+
+/// A builder of `report_data` that commits a public key, a nonce and named
+/// claims.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportDataBuilder {
+    public_key: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+    claims: BTreeMap<String, Vec<u8>>,
+}
+
+impl ReportDataBuilder {
+    /// Creates a new builder that commits no fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commits the (encoded) `public_key`, e.g., a DER-encoded
+    /// `SubjectPublicKeyInfo`.
+    pub fn with_public_key(mut self, public_key: &[u8]) -> Self {
+        self.public_key = Some(public_key.to_vec());
+        self
+    }
+
+    /// Commits the relying party's `nonce`.
+    pub fn with_nonce(mut self, nonce: &[u8]) -> Self {
+        self.nonce = Some(nonce.to_vec());
+        self
+    }
+
+    /// Commits the claim `name` with `value`, replacing any previous value
+    /// of the claim.
+    pub fn with_claim(mut self, name: &str, value: &[u8]) -> Self {
+        self.claims.insert(name.to_string(), value.to_vec());
+        self
+    }
+
+    /// Commits the SHA-384 digest of `identity`'s document as the
+    /// `WORKLOAD_IDENTITY_CLAIM` claim.
+    pub fn with_workload_identity(self, identity: &WorkloadIdentity) -> Self {
+        self.with_workload_identity_document(&identity.to_document())
+    }
+
+    // Commits the digest of a raw identity document, so that relying
+    // parties check the exact document they were given
+    fn with_workload_identity_document(self, document: &[u8]) -> Self {
+        self.with_claim(WORKLOAD_IDENTITY_CLAIM, &Sha384::digest(document))
+    }
+
+    /// Computes the `report_data` committing the builder's fields.
+    pub fn build(&self) -> [u8; TDX_REPORT_DATA_LEN] {
+        let mut hasher = Sha512::new();
+        hasher.update(REPORT_DATA_CONTEXT);
+        if let Some(public_key) = &self.public_key {
+            hasher.update([TAG_PUBLIC_KEY]);
+            update_field(&mut hasher, public_key);
+        }
+        if let Some(nonce) = &self.nonce {
+            hasher.update([TAG_NONCE]);
+            update_field(&mut hasher, nonce);
+        }
+        for (name, value) in &self.claims {
+            hasher.update([TAG_CLAIM]);
+            update_field(&mut hasher, name.as_bytes());
+            update_field(&mut hasher, value);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Returns whether `report_data` (e.g., from a quote) commits exactly
+    /// the builder's fields.
+    pub fn verify(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> bool {
+        ct::eq(&self.build(), report_data)
+    }
+
+    /// Extracts the workload identity from `document`, after checking that
+    /// `report_data` (e.g., from a quote) commits exactly the builder's
+    /// fields and the document's digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if `report_data` doesn't commit
+    /// the document, or an `Error::ParseError` if the document is malformed.
+    pub fn extract_workload_identity(
+        &self,
+        document: &[u8],
+        report_data: &[u8; TDX_REPORT_DATA_LEN],
+    ) -> Result<WorkloadIdentity> {
+        if !self
+            .clone()
+            .with_workload_identity_document(document)
+            .verify(report_data)
+        {
+            return Err(Error::VerificationError(
+                "report_data doesn't commit the workload identity document".to_string(),
+            ));
+        }
+        WorkloadIdentity::from_document(document)
+    }
+}
+
+// Hashes a length-prefixed field
+fn update_field(hasher: &mut Sha512, value: &[u8]) {
+    hasher.update((value.len() as u64).to_be_bytes());
+    hasher.update(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_is_canonical() {
+        let a = ReportDataBuilder::new()
+            .with_claim("a", b"1")
+            .with_claim("b", b"2")
+            .with_nonce(b"nonce");
+        let b = ReportDataBuilder::new()
+            .with_nonce(b"nonce")
+            .with_claim("b", b"2")
+            .with_claim("a", b"0")
+            .with_claim("a", b"1");
+        assert_eq!(a.build(), b.build());
+        assert!(b.verify(&a.build()));
+    }
+
+    #[test]
+    fn test_build_separates_fields() {
+        let builds = [
+            ReportDataBuilder::new(),
+            ReportDataBuilder::new().with_public_key(b"value"),
+            ReportDataBuilder::new().with_nonce(b"value"),
+            ReportDataBuilder::new().with_claim("value", b""),
+            ReportDataBuilder::new().with_claim("", b"value"),
+            ReportDataBuilder::new().with_claim("val", b"ue"),
+            ReportDataBuilder::new()
+                .with_public_key(b"value")
+                .with_nonce(b""),
+            ReportDataBuilder::new()
+                .with_public_key(b"")
+                .with_nonce(b"value"),
+        ]
+        .map(|builder| builder.build());
+
+        for (i, a) in builds.iter().enumerate() {
+            for b in &builds[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let report_data = ReportDataBuilder::new()
+            .with_public_key(b"key")
+            .with_nonce(b"nonce")
+            .build();
+
+        let builder = ReportDataBuilder::new().with_public_key(b"key");
+        assert!(!builder.verify(&report_data));
+        let builder = builder.with_nonce(b"nonce");
+        assert!(builder.verify(&report_data));
+        assert!(!builder.with_claim("extra", b"").verify(&report_data));
+    }
+
+    #[test]
+    fn test_workload_identity() -> Result<()> {
+        let identity = WorkloadIdentity::new()
+            .with_image_digest("sha256:1234")
+            .with_pod_uid("0b4e7f3a")
+            .with_service_account("default/web");
+        let document = identity.to_document();
+        assert_eq!(
+            document,
+            br#"{"image_digest":"sha256:1234","pod_uid":"0b4e7f3a","service_account":"default/web"}"#
+        );
+        assert_eq!(WorkloadIdentity::from_document(&document)?, identity);
+        assert_eq!(WorkloadIdentity::new().to_document(), b"{}");
+
+        let builder = ReportDataBuilder::new().with_nonce(b"nonce");
+        let report_data = builder.clone().with_workload_identity(&identity).build();
+        assert_eq!(
+            builder.extract_workload_identity(&document, &report_data)?,
+            identity
+        );
+
+        // another identity, or another nonce
+        let other = identity.clone().with_pod_uid("9c1d2e5f").to_document();
+        assert!(matches!(
+            builder.extract_workload_identity(&other, &report_data),
+            Err(Error::VerificationError(_))
+        ));
+        assert!(
+            ReportDataBuilder::new()
+                .extract_workload_identity(&document, &report_data)
+                .is_err()
+        );
+
+        // a committed, but malformed document
+        let report_data = builder
+            .clone()
+            .with_workload_identity_document(b"garbage")
+            .build();
+        assert!(matches!(
+            builder.extract_workload_identity(b"garbage", &report_data),
+            Err(Error::ParseError(_))
+        ));
+        Ok(())
+    }
+}
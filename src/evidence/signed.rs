@@ -0,0 +1,383 @@
+//! # Signed Evidence Files
+//!
+//! This module protects the integrity of evidence files saved to disk (e.g.,
+//! TD reports and evidence bundles saved by the CLI), so that they can be
+//! checked for tampering before they're appraised.
+//!
+//! A `SignedFile` is a detached signature over the SHA-384 digest of a file,
+//! saved next to it with the `.sig` extension, made either:
+//! - with a caller-provided key, as an HMAC-SHA384 (`FileSignature::HmacSha384`), or
+//! - by the TD itself, as a TD quote whose `report_data` binds the file's
+//!   digest (`FileSignature::TdQuote`, see `report_data_for_file()`), which
+//!   needs no key management, but can only be fully validated by verifying
+//!   the quote's signature chain.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::signed::SignedFile;
+//!
+//! let key = b"a secret shared with the relying party";
+//!
+//! // On the TD
+//! let report = std::fs::read("report.json").unwrap();
+//! SignedFile::sign_with_key(&report, key).save("report.json").unwrap();
+//!
+//! // Before appraisal
+//! let signed = SignedFile::load("report.json").unwrap();
+//! match signed.verify(&std::fs::read("report.json").unwrap(), Some(key)) {
+//!     Ok(true) => println!("File is intact."),
+//!     Ok(false) => println!("File has been tampered with."),
+//!     Err(e) => eprintln!("Error verifying file: {}", e),
+//! }
+//! ```
+
+use crate::core::ct;
+use crate::error::{Error, Result};
+use crate::evidence::quote::Quote;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384, Sha512};
+use std::path::{Path, PathBuf};
+
+/// The version of the signed file format.
+pub const SIGNED_FILE_VERSION: u32 = 1;
+
+/// The extension of the detached signature saved next to a file.
+pub const SIGNATURE_FILE_EXT: &str = "sig";
+
+// The domain separator of the report data binding a file's digest
+const FILE_BINDING_CONTEXT: &[u8] = b"tdx-workload-attestation/signed-file/v1";
+
+/// The signature of a `SignedFile`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "kebab-case")]
+pub enum FileSignature {
+    /// An HMAC-SHA384 over the file's digest, with a caller-provided key.
+    HmacSha384 {
+        /// The hex-encoded MAC.
+        mac: String,
+    },
+    /// A TD quote whose `report_data` binds the file's digest.
+    TdQuote {
+        /// The hex-encoded raw quote.
+        quote: String,
+    },
+}
+
+/// A detached signature over an evidence file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedFile {
+    /// The version of the signed file format.
+    pub version: u32,
+    /// The hex-encoded SHA-384 digest of the file.
+    pub digest: String,
+    /// The signature over the digest.
+    pub signature: FileSignature,
+}
+
+/// Returns the SHA-384 digest of `data`.
+pub fn file_digest(data: &[u8]) -> [u8; 48] {
+    Sha384::digest(data).into()
+}
+
+/// Returns the `report_data` that binds a file's SHA-384 `digest` into a TD
+/// quote, i.e., the SHA-512 digest of a domain separator followed by the
+/// file's digest.
+pub fn report_data_for_file(digest: &[u8; 48]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(FILE_BINDING_CONTEXT);
+    hasher.update(digest);
+    hasher.finalize().into()
+}
+
+/// Returns the path of the detached signature of the file at `path`.
+pub fn signature_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut sig = path.as_ref().as_os_str().to_owned();
+    sig.push(".");
+    sig.push(SIGNATURE_FILE_EXT);
+    PathBuf::from(sig)
+}
+
+/// Computes the HMAC-SHA384 of `digest` with `key`.
+fn hmac_sha384(key: &[u8], digest: &[u8]) -> Hmac<Sha384> {
+    let mut mac = Hmac::<Sha384>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(digest);
+    mac
+}
+
+impl SignedFile {
+    /// Signs `data` with the caller-provided `key`.
+    pub fn sign_with_key(data: &[u8], key: &[u8]) -> Self {
+        let digest = file_digest(data);
+        let mac = hmac_sha384(key, &digest).finalize().into_bytes();
+
+        Self {
+            version: SIGNED_FILE_VERSION,
+            digest: hex::encode(digest),
+            signature: FileSignature::HmacSha384 {
+                mac: hex::encode(mac),
+            },
+        }
+    }
+
+    /// Signs `data` with a quote of the current TD, which binds the data's
+    /// digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` or `Error::QuoteError` if a quote
+    /// cannot be generated.
+    #[cfg(feature = "tdx-linux")]
+    pub fn sign_with_td(data: &[u8]) -> Result<Self> {
+        use crate::tdx::LinuxTdxProvider;
+
+        let digest = file_digest(data);
+        let quote = LinuxTdxProvider::new().get_quote(&report_data_for_file(&digest))?;
+
+        Ok(Self::from_quote(&digest, &quote))
+    }
+
+    /// Creates a TD signature from a quote that binds `digest`.
+    #[cfg(any(feature = "tdx-linux", test))]
+    fn from_quote(digest: &[u8; 48], quote: &[u8]) -> Self {
+        Self {
+            version: SIGNED_FILE_VERSION,
+            digest: hex::encode(digest),
+            signature: FileSignature::TdQuote {
+                quote: hex::encode(quote),
+            },
+        }
+    }
+
+    /// Verifies the signature over `data`, with `key` for signatures made
+    /// with a caller-provided key.
+    ///
+    /// Returns `Ok(false)` if `data` doesn't match the signed digest, or the
+    /// signature is invalid. For TD signatures, this only checks that the
+    /// quote binds the digest: the quote's signature chain must also be
+    /// verified (see `verify_quote_chain()`).
+    ///
+    /// # Errors
+    ///
+    /// - `Error::VerificationError` if the signature was made with a key but
+    ///   `key` is `None`.
+    /// - `Error::ParseError` if the signature or quote is malformed.
+    pub fn verify(&self, data: &[u8], key: Option<&[u8]>) -> Result<bool> {
+        let digest = file_digest(data);
+        if !ct::eq(self.digest.as_bytes(), hex::encode(digest).as_bytes()) {
+            return Ok(false);
+        }
+
+        match &self.signature {
+            FileSignature::HmacSha384 { mac } => {
+                let key = key.ok_or_else(|| {
+                    Error::VerificationError("File was signed with a key".to_string())
+                })?;
+                let mac = decode_hex(mac, "MAC")?;
+                Ok(hmac_sha384(key, &digest).verify_slice(&mac).is_ok())
+            }
+            FileSignature::TdQuote { quote } => {
+                let quote = Quote::from_bytes(&decode_hex(quote, "quote")?)?;
+                Ok(ct::eq(
+                    &quote.body.report_data,
+                    &report_data_for_file(&digest),
+                ))
+            }
+        }
+    }
+
+    /// Returns the parsed quote of a TD signature, if it is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the quote is malformed.
+    pub fn quote(&self) -> Result<Option<Quote>> {
+        match &self.signature {
+            FileSignature::TdQuote { quote } => {
+                Ok(Some(Quote::from_bytes(&decode_hex(quote, "quote")?)?))
+            }
+            FileSignature::HmacSha384 { .. } => Ok(None),
+        }
+    }
+
+    /// Verifies the signature chain of a TD signature's quote up to one of the
+    /// Intel SGX roots in `anchors`.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the signature isn't a TD signature, or the
+    ///   quote doesn't embed its PCK chain.
+    /// - `Error::VerificationError` if `anchors` has no trusted Intel SGX
+    ///   root.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_quote_chain(&self, anchors: &crate::trust::TrustAnchors) -> Result<bool> {
+        use crate::trust::TrustAnchorKind;
+        use crate::verification::quote::verify_quote_signature;
+        use crate::verification::x509::x509_from_der_bytes;
+
+        let quote = self
+            .quote()?
+            .ok_or_else(|| Error::NotSupported("File was not signed by a TD".to_string()))?;
+        let pck_chain = quote.pck_chain()?;
+
+        anchors
+            .verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+                verify_quote_signature(&quote, &pck_chain, &x509_from_der_bytes(&root.der)?)
+            })
+            .unwrap_or_else(|| {
+                Err(Error::VerificationError(
+                    "No trusted root certificate configured".to_string(),
+                ))
+            })
+    }
+
+    /// Encodes the signature in JSON.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Decodes a JSON-encoded signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the signature is malformed or has an
+    /// unsupported version.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let signed: Self = serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid file signature: {}", e)))?;
+        if signed.version != SIGNED_FILE_VERSION {
+            return Err(Error::ParseError(format!(
+                "Unsupported file signature version {}",
+                signed.version
+            )));
+        }
+        Ok(signed)
+    }
+
+    /// Saves the signature next to the file at `path` (see
+    /// `signature_path()`).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(std::fs::write(signature_path(path), self.to_json()?)?)
+    }
+
+    /// Loads the signature saved next to the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the signature cannot be read, or an
+    /// `Error::ParseError` if it is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(signature_path(path))?)
+    }
+}
+
+/// Decodes a hex-encoded signature field.
+fn decode_hex(value: &str, what: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|e| Error::ParseError(format!("Invalid {}: {}", what, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+
+    #[test]
+    fn test_sign_with_key() -> Result<()> {
+        let signed = SignedFile::sign_with_key(b"report", b"key");
+        assert!(signed.verify(b"report", Some(b"key"))?);
+        assert!(!signed.verify(b"tampered", Some(b"key"))?);
+        assert!(!signed.verify(b"report", Some(b"other key"))?);
+        assert!(signed.verify(b"report", None).is_err());
+        assert!(signed.quote()?.is_none());
+
+        // the digest can't be replaced without the key
+        let mut forged = SignedFile::sign_with_key(b"tampered", b"key");
+        forged.signature = signed.signature.clone();
+        assert!(!forged.verify(b"tampered", Some(b"key"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_td_signature() -> Result<()> {
+        let digest = file_digest(b"bundle");
+        let quote = QuoteParts {
+            report_data: report_data_for_file(&digest),
+            ..Default::default()
+        }
+        .assemble(&[6; 64], &[7; 64]);
+
+        let signed = SignedFile::from_quote(&digest, &quote);
+        assert!(signed.verify(b"bundle", None)?);
+        assert!(!signed.verify(b"tampered", None)?);
+        assert_eq!(
+            signed.quote()?.unwrap().body.report_data,
+            report_data_for_file(&digest)
+        );
+
+        // a quote that doesn't bind the file
+        let signed = SignedFile::from_quote(&file_digest(b"tampered"), &quote);
+        assert!(!signed.verify(b"tampered", None)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "host-verification")]
+    #[test]
+    fn test_verify_quote_chain() -> Result<()> {
+        use crate::trust::{TrustAnchorKind, TrustAnchors};
+        use crate::verification::quote::tests::TestSigner;
+
+        let signer = TestSigner::new();
+        let digest = file_digest(b"bundle");
+        let quote = signer.sign_quote(QuoteParts {
+            report_data: report_data_for_file(&digest),
+            ..Default::default()
+        });
+        let signed = SignedFile::from_quote(&digest, &quote);
+
+        let anchors =
+            TrustAnchors::new().with_anchor(TrustAnchorKind::IntelSgxRoot, &signer.root.to_der()?);
+        assert!(signed.verify(b"bundle", None)?);
+        assert!(signed.verify_quote_chain(&anchors)?);
+
+        let other = TestSigner::new();
+        let anchors =
+            TrustAnchors::new().with_anchor(TrustAnchorKind::IntelSgxRoot, &other.root.to_der()?);
+        assert!(!matches!(signed.verify_quote_chain(&anchors), Ok(true)));
+        assert!(signed.verify_quote_chain(&TrustAnchors::new()).is_err());
+
+        let keyed = SignedFile::sign_with_key(b"bundle", b"key");
+        assert!(
+            keyed
+                .verify_quote_chain(&anchors)
+                .unwrap_err()
+                .is_not_supported()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tdx-signed-{}.json", std::process::id()));
+        std::fs::write(&path, b"report")?;
+
+        let signed = SignedFile::sign_with_key(b"report", b"key");
+        signed.save(&path)?;
+        assert!(
+            signature_path(&path)
+                .to_string_lossy()
+                .ends_with(".json.sig")
+        );
+        assert_eq!(SignedFile::load(&path)?, signed);
+
+        let json = signed.to_json()?;
+        assert!(json.contains(r#""method": "hmac-sha384""#));
+        let json = json.replace(r#""version": 1"#, r#""version": 2"#);
+        assert!(SignedFile::from_json(&json).is_err());
+
+        std::fs::remove_file(signature_path(&path))?;
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
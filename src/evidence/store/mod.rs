@@ -0,0 +1,529 @@
+//! # Evidence Storage
+//!
+//! This module archives collected evidence bundles for later audit, e.g.,
+//! by agents collecting evidence periodically (see `tdx-attest collect
+//! --archive`).
+//!
+//! Bundles are stored in an `EvidenceStore`, a flat key-value store of
+//! `/`-separated keys, with two backends:
+//! - `FsStore`, a directory of the local filesystem, and
+//! - `s3::S3Store`, a bucket of an S3-compatible object store (e.g., AWS S3,
+//!   MinIO, or Google Cloud Storage with HMAC keys), with the `s3-store`
+//!   feature.
+//!
+//! An `EvidenceArchive` names each bundle after the group it was collected
+//! in, i.e., the boot of the TD (by default) or a fixed-length epoch, and
+//! the time it was collected at:
+//!
+//! ```text
+//! <prefix>/boot-<boot ID>/<unix time>-<digest>.cbor
+//! <prefix>/epoch-<epoch number>/<unix time>-<digest>.cbor
+//! ```
+//!
+//! After archiving a bundle, the archive applies its `RetentionPolicy`,
+//! deleting the bundles older than its maximum age, and the groups beyond
+//! its maximum number of groups (oldest first).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tdx_workload_attestation::evidence::Bundle;
+//! use tdx_workload_attestation::evidence::store::{EvidenceArchive, FsStore, RetentionPolicy};
+//!
+//! let archive = EvidenceArchive::new(FsStore::new("/var/lib/tdx-attest/evidence"))
+//!     .with_retention(RetentionPolicy::new().with_max_groups(10));
+//! let bundle = Bundle::collect(b"nonce", None).unwrap();
+//! let key = archive.archive(&bundle).unwrap();
+//! println!("Archived {}", key);
+//! ```
+
+#[cfg(feature = "s3-store")]
+pub mod s3;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::evidence::Bundle;
+
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The file containing the Linux kernel's ID of the current boot.
+pub const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// The default prefix of the archive's keys.
+pub const DEFAULT_PREFIX: &str = "evidence";
+
+// The extension of archived bundles
+const BUNDLE_EXT: &str = ".cbor";
+
+// The number of digest bytes in archived bundles' names
+const NAME_DIGEST_LEN: usize = 8;
+
+/// An object of an `EvidenceStore`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredObject {
+    /// The object's key.
+    pub key: String,
+    /// The object's size, in bytes.
+    pub size: u64,
+}
+
+/// A key-value store for evidence.
+///
+/// Keys are relative, `/`-separated paths without empty, `.` or `..`
+/// components.
+pub trait EvidenceStore: Send + Sync {
+    /// Stores `data` under `key`, replacing any previous object.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object cannot be stored.
+    fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+
+    /// Returns the object stored under `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object doesn't exist or cannot be read.
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Returns the objects whose keys start with `prefix`, sorted by key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objects cannot be listed.
+    fn list(&self, prefix: &str) -> Result<Vec<StoredObject>>;
+
+    /// Deletes the object stored under `key`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object cannot be deleted.
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Checks that `key` is a valid store key.
+fn check_key(key: &str) -> Result<()> {
+    if key
+        .split('/')
+        .any(|c| c.is_empty() || c == "." || c == "..")
+    {
+        return Err(Error::ParseError(format!("Invalid store key: {}", key)));
+    }
+    Ok(())
+}
+
+/// A store of files in a directory of the local filesystem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    /// Creates a store of the files under `root`, which is created when
+    /// the first object is stored.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the path of the file storing `key`.
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    /// Appends the files under `dir` (whose key is `key`) to `objects`.
+    fn walk(&self, dir: &Path, key: &str, objects: &mut Vec<StoredObject>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            // skip the temporary files of interrupted writes
+            if name.starts_with('.') {
+                continue;
+            }
+            let key = match key {
+                "" => name,
+                _ => format!("{}/{}", key, name),
+            };
+
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.walk(&entry.path(), &key, objects)?;
+            } else if file_type.is_file() {
+                objects.push(StoredObject {
+                    key,
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EvidenceStore for FsStore {
+    /// Writes `data` to the file of `key`, atomically replacing any previous
+    /// one.
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        let dir = path.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)?;
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = dir.join(format!(".{}.tmp", name));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(key)?)?)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        let mut objects = vec![];
+        match self.walk(&self.root, "", &mut objects) {
+            Err(Error::IoError(e)) if e.kind() == io::ErrorKind::NotFound => {}
+            result => result?,
+        }
+        objects.retain(|object| object.key.starts_with(prefix));
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How an `EvidenceArchive` groups bundles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grouping {
+    /// By the boot of the TD they were collected in.
+    PerBoot,
+    /// By the epoch of the given length they were collected in, counted
+    /// from the Unix epoch.
+    PerEpoch(Duration),
+}
+
+/// Which archived bundles to keep.
+///
+/// The default policy keeps all of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// The maximum age of the bundles to keep.
+    pub max_age: Option<Duration>,
+    /// The maximum number of groups (i.e., boots or epochs) to keep, with
+    /// the most recent bundles.
+    pub max_groups: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy keeping all bundles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes the bundles older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Only keeps the `max_groups` most recent groups.
+    pub fn with_max_groups(mut self, max_groups: usize) -> Self {
+        self.max_groups = Some(max_groups);
+        self
+    }
+}
+
+/// A bundle in an `EvidenceArchive`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedBundle {
+    /// The bundle's key in the store.
+    pub key: String,
+    /// The group the bundle was collected in (e.g., `boot-<boot ID>`).
+    pub group: String,
+    /// When the bundle was archived, in seconds since the Unix epoch.
+    pub time: u64,
+}
+
+/// An archive of evidence bundles in an `EvidenceStore`, grouped by boot
+/// or epoch.
+pub struct EvidenceArchive<S: EvidenceStore> {
+    store: S,
+    prefix: String,
+    grouping: Grouping,
+    retention: RetentionPolicy,
+    boot_id: Option<String>,
+    clock: Box<dyn Clock>,
+}
+
+impl<S: EvidenceStore> EvidenceArchive<S> {
+    /// Creates an archive of the bundles under `DEFAULT_PREFIX` in `store`,
+    /// grouped by boot, keeping all bundles.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            prefix: DEFAULT_PREFIX.to_string(),
+            grouping: Grouping::PerBoot,
+            retention: RetentionPolicy::default(),
+            boot_id: None,
+            clock: Box::new(SystemClock),
+        }
+    }
+
+    /// Archives the bundles under `prefix` (e.g., the TD's name, when
+    /// several TDs share a bucket).
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_matches('/').to_string();
+        self
+    }
+
+    /// Groups the bundles by `grouping`.
+    pub fn with_grouping(mut self, grouping: Grouping) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Applies `retention` after archiving bundles.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Uses `boot_id` as the ID of the current boot, instead of reading
+    /// `BOOT_ID_PATH`.
+    pub fn with_boot_id(mut self, boot_id: &str) -> Self {
+        self.boot_id = Some(boot_id.to_string());
+        self
+    }
+
+    /// Uses `clock` as the time of the archived bundles, instead of the
+    /// system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Returns the archive's store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Stores `bundle` in the group of the current boot or epoch, applies
+    /// the retention policy, and returns the bundle's key.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::IoError` if the boot ID cannot be read.
+    /// - `Error::SerializationError` if the bundle cannot be encoded.
+    /// - The store's errors.
+    pub fn archive(&self, bundle: &Bundle) -> Result<String> {
+        let bytes = bundle.to_bytes()?;
+        let now = self.clock.now()?;
+        let group = match self.grouping {
+            Grouping::PerBoot => match &self.boot_id {
+                Some(id) => format!("boot-{}", id),
+                None => format!("boot-{}", fs::read_to_string(BOOT_ID_PATH)?.trim()),
+            },
+            Grouping::PerEpoch(length) => format!("epoch-{}", now / length.as_secs().max(1)),
+        };
+        let digest = Sha384::digest(&bytes);
+        let key = format!(
+            "{}/{}/{:020}-{}{}",
+            self.prefix,
+            group,
+            now,
+            hex::encode(&digest[..NAME_DIGEST_LEN]),
+            BUNDLE_EXT
+        );
+
+        self.store.put(&key, &bytes)?;
+        self.prune()?;
+        Ok(key)
+    }
+
+    /// Returns the archived bundles, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's errors.
+    pub fn list(&self) -> Result<Vec<ArchivedBundle>> {
+        let prefix = format!("{}/", self.prefix);
+        let mut bundles: Vec<_> = self
+            .store
+            .list(&prefix)?
+            .into_iter()
+            .filter_map(|object| {
+                let (group, name) = object.key.strip_prefix(&prefix)?.split_once('/')?;
+                let time = name.strip_suffix(BUNDLE_EXT)?.split_once('-')?.0;
+                Some(ArchivedBundle {
+                    group: group.to_string(),
+                    time: time.parse().ok()?,
+                    key: object.key,
+                })
+            })
+            .collect();
+        bundles.sort_by(|a, b| (a.time, &a.key).cmp(&(b.time, &b.key)));
+        Ok(bundles)
+    }
+
+    /// Loads the archived bundle `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's errors, or an `Error::ParseError` if the bundle
+    /// is malformed.
+    pub fn load(&self, key: &str) -> Result<Bundle> {
+        Bundle::from_bytes(&self.store.get(key)?)
+    }
+
+    /// Deletes the bundles the retention policy doesn't keep, and returns
+    /// their keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns the clock's or the store's errors.
+    pub fn prune(&self) -> Result<Vec<String>> {
+        let bundles = self.list()?;
+        let now = self.clock.now()?;
+
+        // the groups, most recent first
+        let mut groups: Vec<&str> = vec![];
+        for bundle in bundles.iter().rev() {
+            if !groups.contains(&bundle.group.as_str()) {
+                groups.push(&bundle.group);
+            }
+        }
+        let kept_groups = &groups[..self
+            .retention
+            .max_groups
+            .unwrap_or(usize::MAX)
+            .min(groups.len())];
+
+        let mut deleted = vec![];
+        for bundle in &bundles {
+            let expired = self
+                .retention
+                .max_age
+                .is_some_and(|max_age| now.saturating_sub(bundle.time) > max_age.as_secs());
+            if expired || !kept_groups.contains(&bundle.group.as_str()) {
+                self.store.delete(&bundle.key)?;
+                deleted.push(bundle.key.clone());
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    fn temp_store() -> FsStore {
+        FsStore::new(std::env::temp_dir().join(format!("store-{}", rand::random::<u64>())))
+    }
+
+    fn bundle(nonce: &[u8]) -> Bundle {
+        Bundle::new(nonce, vec![0; 8])
+    }
+
+    #[test]
+    fn test_fs_store() -> Result<()> {
+        let store = temp_store();
+        assert!(store.list("")?.is_empty());
+
+        store.put("a/b/c.cbor", b"abc")?;
+        store.put("a/d.cbor", b"d")?;
+        store.put("a/d.cbor", b"dd")?;
+        assert_eq!(store.get("a/d.cbor")?, b"dd");
+        assert_eq!(
+            store.list("a/")?,
+            [
+                StoredObject {
+                    key: "a/b/c.cbor".to_string(),
+                    size: 3
+                },
+                StoredObject {
+                    key: "a/d.cbor".to_string(),
+                    size: 2
+                },
+            ]
+        );
+        assert_eq!(store.list("a/b")?.len(), 1);
+
+        store.delete("a/b/c.cbor")?;
+        store.delete("a/b/c.cbor")?;
+        assert_eq!(store.list("")?.len(), 1);
+        assert!(store.get("a/b/c.cbor").is_err());
+
+        for key in ["", "/a", "a//b", "a/../b", "./a", "a/"] {
+            assert!(store.put(key, b"").is_err(), "{}", key);
+        }
+        fs::remove_dir_all(&store.root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive() -> Result<()> {
+        let store = temp_store();
+        let root = store.root.clone();
+        let archive = |boot_id: &str, time: u64| {
+            EvidenceArchive::new(FsStore::new(&root))
+                .with_prefix("/td-1/")
+                .with_boot_id(boot_id)
+                .with_retention(RetentionPolicy::new().with_max_groups(2))
+                .with_clock(FixedClock::new(time))
+        };
+
+        let key = archive("a", 100).archive(&bundle(b"1"))?;
+        assert!(key.starts_with("td-1/boot-a/00000000000000000100-"));
+        assert!(key.ends_with(".cbor"));
+        assert_eq!(archive("a", 100).load(&key)?, bundle(b"1"));
+
+        archive("a", 200).archive(&bundle(b"2"))?;
+        archive("b", 300).archive(&bundle(b"3"))?;
+        assert_eq!(archive("b", 300).list()?.len(), 3);
+
+        // a third boot drops the oldest one
+        archive("c", 400).archive(&bundle(b"4"))?;
+        let bundles = archive("c", 400).list()?;
+        let groups: Vec<_> = bundles.iter().map(|b| b.group.as_str()).collect();
+        assert_eq!(groups, ["boot-b", "boot-c"]);
+        assert_eq!(bundles[1].time, 400);
+
+        // bundles older than the maximum age are dropped
+        let archive = archive("c", 1000)
+            .with_retention(RetentionPolicy::new().with_max_age(Duration::from_secs(650)));
+        assert_eq!(archive.prune()?, [bundles[0].key.clone()]);
+        assert_eq!(archive.list()?.len(), 1);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_per_epoch() -> Result<()> {
+        let store = temp_store();
+        let archive = EvidenceArchive::new(store)
+            .with_grouping(Grouping::PerEpoch(Duration::from_secs(3600)))
+            .with_clock(FixedClock::new(7300));
+        let key = archive.archive(&bundle(b"1"))?;
+        assert!(key.starts_with("evidence/epoch-2/"));
+        assert_eq!(archive.list()?[0].group, "epoch-2");
+        fs::remove_dir_all(&archive.store().root)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,1058 @@
+//! # TDX TCB Evaluation
+//!
+//! This module evaluates the Trusted Computing Base (TCB) status of the
+//! platform that generated a TD quote, using the TDX TCB Info collateral
+//! published by Intel's Provisioning Certification Service (PCS).
+//!
+//! The platform's TCB is made up of:
+//! - the SGX TCB component SVNs and PCE SVN, from the PCK certificate's SGX
+//!   extensions (see `pck_platform_tcb()`), and
+//! - the TDX TCB component SVNs, from the quote's `TEE_TCB_SVN`.
+//!
+//! The TCB Info lists TCB levels in descending order, each with a status
+//! (e.g., `UpToDate` or `OutOfDate`); the platform's status is that of the
+//! first level whose component SVNs are all lower than or equal to the
+//! platform's.
+//!
+//! The TCB Info is signed by Intel's TCB signing key, whose certificate
+//! chains up to the Intel SGX Root CA (see `SignedTcbInfo::verify_signature()`,
+//! when compiled with the `host-verification` feature).
+//!
+//! TCB levels below `UpToDate` list the Intel security advisories (e.g.,
+//! `INTEL-SA-00837`) affecting their platforms. An `AdvisoryCatalog` (e.g.,
+//! the `advisories.json` collateral, see `Policy::with_collateral_dir()`)
+//! maps them to their CVEs and mitigation, so that appraisal can tell
+//! operators what action is required (see `Verdict::advisories`):
+//!
+//! ```json
+//! {
+//!   "INTEL-SA-00837": {
+//!     "title": "2023.3 IPU - Intel Processor Advisory",
+//!     "cves": ["CVE-2022-41804"],
+//!     "mitigation": "platform-update"
+//!   }
+//! }
+//! ```
+//!
+//! Advisories missing from the catalog are still reported, with the
+//! mitigation implied by the TCB status (e.g., `software-hardening` for
+//! `SWHardeningNeeded`).
+//!
+//! The QE Identity collateral, signed by the same key, identifies Intel's TD
+//! Quoting Enclave (QE) and lists its TCB levels by ISV SVN. The QE report in
+//! quotes is checked against it by `verification::qe::verify_qe_identity()`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::tcb::{SignedTcbInfo, pck_platform_tcb};
+//!
+//! // e.g., from https://api.trustedservices.intel.com/tdx/certification/v4/tcb
+//! let tcb_info = SignedTcbInfo::parse(&std::fs::read_to_string("tcb_info.json").unwrap()).unwrap();
+//!
+//! let pck = std::fs::read("pck.der").unwrap();
+//! let platform = pck_platform_tcb(&pck, &[0; 16]).unwrap();
+//! match tcb_info.tcb_info.tcb_level(&platform) {
+//!     Some(level) => println!("TCB status: {}", level.tcb_status),
+//!     None => println!("TCB is not recognized"),
+//! }
+//! ```
+
+use crate::der::{DerReader, TAG_OID, TAG_SEQUENCE, certificate_extensions};
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+
+/// The number of SGX or TDX TCB components.
+pub const TCB_COMPONENTS_LEN: usize = 16;
+
+/// The TCB status of an up-to-date platform.
+pub const TCB_STATUS_UP_TO_DATE: &str = "UpToDate";
+
+/// The TCB status of a platform whose TCB must be updated.
+pub const TCB_STATUS_OUT_OF_DATE: &str = "OutOfDate";
+
+/// The TCB status of an up-to-date platform whose TEE software must be
+/// hardened against the level's advisories.
+pub const TCB_STATUS_SW_HARDENING_NEEDED: &str = "SWHardeningNeeded";
+
+// The DER-encoded OID of Intel's SGX PCK certificate extensions
+// (1.2.840.113741.1.13.1)
+const SGX_EXTENSIONS_OID: [u8; 9] = [0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+
+// The sub-OIDs of the TCB (2) and FMSPC (4) extensions
+pub(crate) const SGX_TCB_ARC: u8 = 2;
+pub(crate) const SGX_FMSPC_ARC: u8 = 4;
+
+// The TCB sub-OID of the PCE SVN (the component SVNs are 1-16)
+pub(crate) const SGX_PCESVN_ARC: u8 = 17;
+
+/// A component of a TCB level.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TcbComponent {
+    /// The component's SVN.
+    pub svn: u8,
+}
+
+/// The component SVNs of a TCB level.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Tcb {
+    /// The SGX TCB component SVNs.
+    pub sgxtcbcomponents: Vec<TcbComponent>,
+    /// The PCE SVN.
+    pub pcesvn: u16,
+    /// The TDX TCB component SVNs.
+    pub tdxtcbcomponents: Vec<TcbComponent>,
+}
+
+/// A TCB level, and the status of platforms at that level.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbLevel {
+    /// The component SVNs of the level.
+    pub tcb: Tcb,
+    /// The date of the level.
+    pub tcb_date: String,
+    /// The status of platforms at the level (e.g., `UpToDate`).
+    pub tcb_status: String,
+    /// The Intel security advisories affecting platforms at the level.
+    #[serde(default, rename = "advisoryIDs")]
+    pub advisory_ids: Vec<String>,
+}
+
+/// The TDX TCB Info for a platform family (FMSPC).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbInfo {
+    /// The TEE the TCB Info is for (`TDX`).
+    pub id: String,
+    /// The version of the TCB Info format.
+    pub version: u32,
+    /// The date the TCB Info was issued.
+    pub issue_date: String,
+    /// The date by which the TCB Info will be updated.
+    pub next_update: String,
+    /// The hex-encoded FMSPC of the platform family.
+    pub fmspc: String,
+    /// The TCB levels, in descending order.
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+impl TcbInfo {
+    /// Returns the next update date of the TCB Info, in seconds since the
+    /// Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the date isn't an ISO 8601 UTC date
+    /// (`YYYY-MM-DDThh:mm:ssZ`).
+    pub fn next_update_timestamp(&self) -> Result<u64> {
+        parse_utc_timestamp(&self.next_update)
+    }
+
+    /// Returns the highest TCB level the platform satisfies, if any.
+    pub fn tcb_level(&self, platform: &PlatformTcb) -> Option<&TcbLevel> {
+        self.tcb_levels.iter().find(|level| {
+            let tcb = &level.tcb;
+            svns_satisfy(&platform.sgx_svns, &tcb.sgxtcbcomponents)
+                && platform.pcesvn >= tcb.pcesvn
+                && svns_satisfy(&platform.tee_tcb_svn, &tcb.tdxtcbcomponents)
+        })
+    }
+}
+
+/// The action that mitigates a security advisory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mitigation {
+    /// Update the platform's TCB (e.g., its microcode, BIOS or TDX module).
+    PlatformUpdate,
+    /// Harden the TEE's software (e.g., against a side channel).
+    SoftwareHardening,
+    /// Change the platform's configuration (e.g., disable a CPU feature).
+    Configuration,
+    /// Change the platform's configuration and harden the TEE's software.
+    ConfigurationAndSoftwareHardening,
+}
+
+impl Mitigation {
+    /// Returns the mitigation implied by the TCB status `status`, if any
+    /// (i.e., none for `UpToDate` and `Revoked` platforms).
+    pub fn for_status(status: &str) -> Option<Self> {
+        match status {
+            TCB_STATUS_OUT_OF_DATE | "OutOfDateConfigurationNeeded" => {
+                Some(Mitigation::PlatformUpdate)
+            }
+            TCB_STATUS_SW_HARDENING_NEEDED => Some(Mitigation::SoftwareHardening),
+            "ConfigurationNeeded" => Some(Mitigation::Configuration),
+            "ConfigurationAndSWHardeningNeeded" => {
+                Some(Mitigation::ConfigurationAndSoftwareHardening)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The metadata of a security advisory, from an `AdvisoryCatalog`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdvisoryInfo {
+    /// The title of the advisory.
+    pub title: Option<String>,
+    /// The CVEs the advisory addresses.
+    pub cves: Vec<String>,
+    /// The action that mitigates the advisory, if known.
+    pub mitigation: Option<Mitigation>,
+}
+
+/// The metadata of Intel's security advisories, by ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct AdvisoryCatalog(BTreeMap<String, AdvisoryInfo>);
+
+impl AdvisoryCatalog {
+    /// Creates an empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a JSON catalog, an object mapping advisory IDs to their
+    /// `AdvisoryInfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the catalog is malformed.
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid advisory catalog: {}", e)))
+    }
+
+    /// Adds (or replaces) the metadata of the advisory `id`.
+    pub fn with_advisory(mut self, id: &str, info: AdvisoryInfo) -> Self {
+        self.0.insert(id.to_string(), info);
+        self
+    }
+
+    /// Returns the metadata of the advisory `id`, if known.
+    pub fn get(&self, id: &str) -> Option<&AdvisoryInfo> {
+        self.0.get(id)
+    }
+
+    /// Returns the advisories affecting the platforms at TCB `level`, with
+    /// their metadata.
+    pub fn resolve(&self, level: &TcbLevel) -> Vec<Advisory> {
+        level
+            .advisory_ids
+            .iter()
+            .map(|id| {
+                let info = self.get(id).cloned().unwrap_or_default();
+                Advisory {
+                    id: id.clone(),
+                    tcb_status: level.tcb_status.clone(),
+                    title: info.title,
+                    cves: info.cves,
+                    mitigation: info
+                        .mitigation
+                        .or_else(|| Mitigation::for_status(&level.tcb_status)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A security advisory affecting an appraised platform.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Advisory {
+    /// The advisory ID (e.g., `INTEL-SA-00837`).
+    pub id: String,
+    /// The TCB status of the platform (e.g., `OutOfDate`).
+    pub tcb_status: String,
+    /// The title of the advisory, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The CVEs the advisory addresses, if known.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cves: Vec<String>,
+    /// The action that mitigates the advisory, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mitigation: Option<Mitigation>,
+}
+
+/// A TCB Info and Intel's signature over it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedTcbInfo {
+    /// The parsed TCB Info.
+    pub tcb_info: TcbInfo,
+    raw: String,
+    signature: [u8; 64],
+}
+
+#[derive(Deserialize)]
+struct TcbInfoResponse<'a> {
+    #[serde(borrow, rename = "tcbInfo")]
+    tcb_info: &'a RawValue,
+    signature: String,
+}
+
+impl SignedTcbInfo {
+    /// Parses a PCS TCB Info response (`{"tcbInfo": {...}, "signature":
+    /// "..."}`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the response is malformed, or an
+    /// `Error::NotSupported` if it isn't a TDX TCB Info.
+    pub fn parse(json: &str) -> Result<Self> {
+        let invalid =
+            |e: &dyn std::fmt::Display| Error::ParseError(format!("Invalid TCB Info: {}", e));
+
+        let response: TcbInfoResponse = serde_json::from_str(json).map_err(|e| invalid(&e))?;
+        let tcb_info: TcbInfo =
+            serde_json::from_str(response.tcb_info.get()).map_err(|e| invalid(&e))?;
+        let mut signature = [0u8; 64];
+        hex::decode_to_slice(&response.signature, &mut signature).map_err(|e| invalid(&e))?;
+
+        if tcb_info.id != "TDX" {
+            return Err(Error::NotSupported(format!(
+                "TCB Info for {} is not supported",
+                tcb_info.id
+            )));
+        }
+
+        Ok(Self {
+            tcb_info,
+            raw: response.tcb_info.get().to_string(),
+            signature,
+        })
+    }
+
+    /// Returns the signed bytes of the TCB Info.
+    pub fn signed_data(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    /// Returns the raw (`r || s`) ECDSA P-256 signature over the TCB Info.
+    pub fn signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    /// Verifies Intel's signature over the TCB Info, given the DER-encoded
+    /// TCB signing certificate chain (starting with the signing certificate)
+    /// and the trusted root certificate, and checks that the TCB Info hasn't
+    /// passed its next update date.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the chain doesn't end in the
+    /// trusted root, or an `Error::OpenSslError` if a certificate cannot be
+    /// parsed.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_signature(
+        &self,
+        signing_chain: &[Vec<u8>],
+        root: &openssl::x509::X509,
+    ) -> Result<bool> {
+        use crate::clock::{Clock, SystemClock};
+
+        self.verify_signature_at(signing_chain, root, SystemClock.now()?)
+    }
+
+    /// Verifies Intel's signature over the TCB Info, like
+    /// `verify_signature()`, at `unix_time` (in seconds since the Unix
+    /// epoch) rather than the current time.
+    ///
+    /// # Errors
+    ///
+    /// Same as `verify_signature()`.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_signature_at(
+        &self,
+        signing_chain: &[Vec<u8>],
+        root: &openssl::x509::X509,
+        unix_time: u64,
+    ) -> Result<bool> {
+        verify_collateral_signature(
+            self.signed_data(),
+            &self.signature,
+            self.tcb_info.next_update_timestamp()?,
+            signing_chain,
+            root,
+            unix_time,
+        )
+    }
+}
+
+/// A QE Identity TCB level, and the status of QEs at that level.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QeTcbLevel {
+    /// The minimum ISV SVN of the QE at the level.
+    pub tcb: QeTcb,
+    /// The date of the level.
+    pub tcb_date: String,
+    /// The status of QEs at the level (e.g., `UpToDate`).
+    pub tcb_status: String,
+    /// The Intel security advisories affecting QEs at the level.
+    #[serde(default, rename = "advisoryIDs")]
+    pub advisory_ids: Vec<String>,
+}
+
+/// The SVN of a QE Identity TCB level.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct QeTcb {
+    /// The QE's ISV SVN.
+    pub isvsvn: u16,
+}
+
+/// The identity of Intel's TD Quoting Enclave (QE), against which the QE
+/// report in quotes is checked.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QeIdentity {
+    /// The enclave the identity is for (`TD_QE`).
+    pub id: String,
+    /// The version of the QE Identity format.
+    pub version: u32,
+    /// The date the QE Identity was issued.
+    pub issue_date: String,
+    /// The date by which the QE Identity will be updated.
+    pub next_update: String,
+    /// The hex-encoded expected `MISCSELECT`, under `miscselect_mask`.
+    pub miscselect: String,
+    /// The hex-encoded mask of the `MISCSELECT` bits to check.
+    pub miscselect_mask: String,
+    /// The hex-encoded expected attributes, under `attributes_mask`.
+    pub attributes: String,
+    /// The hex-encoded mask of the attribute bits to check.
+    pub attributes_mask: String,
+    /// The hex-encoded `MRSIGNER` of the QE.
+    pub mrsigner: String,
+    /// The product ID of the QE.
+    pub isvprodid: u16,
+    /// The TCB levels, in descending order.
+    pub tcb_levels: Vec<QeTcbLevel>,
+}
+
+impl QeIdentity {
+    /// Returns the next update date of the QE Identity, in seconds since the
+    /// Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the date isn't an ISO 8601 UTC date
+    /// (`YYYY-MM-DDThh:mm:ssZ`).
+    pub fn next_update_timestamp(&self) -> Result<u64> {
+        parse_utc_timestamp(&self.next_update)
+    }
+
+    /// Returns the highest TCB level a QE with the ISV SVN `isvsvn`
+    /// satisfies, if any.
+    pub fn tcb_level(&self, isvsvn: u16) -> Option<&QeTcbLevel> {
+        self.tcb_levels
+            .iter()
+            .find(|level| isvsvn >= level.tcb.isvsvn)
+    }
+}
+
+/// A QE Identity and Intel's signature over it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedQeIdentity {
+    /// The parsed QE Identity.
+    pub qe_identity: QeIdentity,
+    raw: String,
+    signature: [u8; 64],
+}
+
+#[derive(Deserialize)]
+struct QeIdentityResponse<'a> {
+    #[serde(borrow, rename = "enclaveIdentity")]
+    qe_identity: &'a RawValue,
+    signature: String,
+}
+
+impl SignedQeIdentity {
+    /// Parses a PCS QE Identity response (`{"enclaveIdentity": {...},
+    /// "signature": "..."}`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the response is malformed, or an
+    /// `Error::NotSupported` if it isn't the identity of the TD QE.
+    pub fn parse(json: &str) -> Result<Self> {
+        let invalid =
+            |e: &dyn std::fmt::Display| Error::ParseError(format!("Invalid QE Identity: {}", e));
+
+        let response: QeIdentityResponse = serde_json::from_str(json).map_err(|e| invalid(&e))?;
+        let qe_identity: QeIdentity =
+            serde_json::from_str(response.qe_identity.get()).map_err(|e| invalid(&e))?;
+        let mut signature = [0u8; 64];
+        hex::decode_to_slice(&response.signature, &mut signature).map_err(|e| invalid(&e))?;
+
+        if qe_identity.id != "TD_QE" {
+            return Err(Error::NotSupported(format!(
+                "QE Identity for {} is not supported",
+                qe_identity.id
+            )));
+        }
+
+        Ok(Self {
+            qe_identity,
+            raw: response.qe_identity.get().to_string(),
+            signature,
+        })
+    }
+
+    /// Returns the signed bytes of the QE Identity.
+    pub fn signed_data(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    /// Returns the raw (`r || s`) ECDSA P-256 signature over the QE Identity.
+    pub fn signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    /// Verifies Intel's signature over the QE Identity, given the DER-encoded
+    /// TCB signing certificate chain (starting with the signing certificate)
+    /// and the trusted root certificate, and checks that the QE Identity
+    /// hasn't passed its next update date.
+    ///
+    /// # Errors
+    ///
+    /// Same as `SignedTcbInfo::verify_signature()`.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_signature(
+        &self,
+        signing_chain: &[Vec<u8>],
+        root: &openssl::x509::X509,
+    ) -> Result<bool> {
+        use crate::clock::{Clock, SystemClock};
+
+        self.verify_signature_at(signing_chain, root, SystemClock.now()?)
+    }
+
+    /// Verifies Intel's signature over the QE Identity, like
+    /// `verify_signature()`, at `unix_time` (in seconds since the Unix
+    /// epoch) rather than the current time.
+    ///
+    /// # Errors
+    ///
+    /// Same as `verify_signature()`.
+    #[cfg(feature = "host-verification")]
+    pub fn verify_signature_at(
+        &self,
+        signing_chain: &[Vec<u8>],
+        root: &openssl::x509::X509,
+        unix_time: u64,
+    ) -> Result<bool> {
+        verify_collateral_signature(
+            self.signed_data(),
+            &self.signature,
+            self.qe_identity.next_update_timestamp()?,
+            signing_chain,
+            root,
+            unix_time,
+        )
+    }
+}
+
+/// Verifies Intel's signature over PCS collateral, and checks that it hasn't
+/// passed its next update date, at `unix_time`.
+#[cfg(feature = "host-verification")]
+fn verify_collateral_signature(
+    signed_data: &[u8],
+    signature: &[u8; 64],
+    next_update: u64,
+    signing_chain: &[Vec<u8>],
+    root: &openssl::x509::X509,
+    unix_time: u64,
+) -> Result<bool> {
+    use crate::verification::quote::{verify_cert_chain, verify_ecdsa_p256};
+    use crate::verification::x509::{get_x509_pubkey, x509_from_der_bytes};
+
+    let chain = signing_chain
+        .iter()
+        .map(|der| x509_from_der_bytes(der))
+        .collect::<Result<Vec<_>>>()?;
+    if !verify_cert_chain(&chain, root, unix_time)? {
+        return Ok(false);
+    }
+
+    let key = get_x509_pubkey(&chain[0])?;
+    if !verify_ecdsa_p256(signed_data, signature, &key)? {
+        return Ok(false);
+    }
+
+    Ok(unix_time < next_update)
+}
+
+/// The TCB of a platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlatformTcb {
+    /// The platform family (FMSPC).
+    pub fmspc: [u8; 6],
+    /// The SGX TCB component SVNs.
+    pub sgx_svns: [u8; TCB_COMPONENTS_LEN],
+    /// The PCE SVN.
+    pub pcesvn: u16,
+    /// The TDX TCB component SVNs (the quote's `TEE_TCB_SVN`).
+    pub tee_tcb_svn: [u8; TCB_COMPONENTS_LEN],
+}
+
+/// Extracts the platform's TCB from its DER-encoded PCK certificate and the
+/// quote's `TEE_TCB_SVN`.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the PCK certificate is malformed, or
+/// doesn't have the SGX extensions.
+pub fn pck_platform_tcb(
+    pck_der: &[u8],
+    tee_tcb_svn: &[u8; TCB_COMPONENTS_LEN],
+) -> Result<PlatformTcb> {
+    let missing =
+        |name: &str| Error::ParseError(format!("PCK certificate is missing the {}", name));
+    let extensions = SgxExtensions::from_cert(pck_der)?;
+
+    let fmspc = extensions
+        .get(&[SGX_FMSPC_ARC], 0x04)
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| missing("FMSPC"))?;
+
+    let mut sgx_svns = [0u8; TCB_COMPONENTS_LEN];
+    for (i, svn) in sgx_svns.iter_mut().enumerate() {
+        *svn = extensions
+            .get(&[SGX_TCB_ARC, i as u8 + 1], 0x02)
+            .and_then(|v| der_uint(v).and_then(|n| u8::try_from(n).ok()))
+            .ok_or_else(|| missing("SGX TCB component SVNs"))?;
+    }
+
+    let pcesvn = extensions
+        .get(&[SGX_TCB_ARC, SGX_PCESVN_ARC], 0x02)
+        .and_then(|v| der_uint(v).and_then(|n| u16::try_from(n).ok()))
+        .ok_or_else(|| missing("PCE SVN"))?;
+
+    Ok(PlatformTcb {
+        fmspc,
+        sgx_svns,
+        pcesvn,
+        tee_tcb_svn: *tee_tcb_svn,
+    })
+}
+
+/// Parses an ISO 8601 UTC date (`YYYY-MM-DDThh:mm:ssZ`, with optional
+/// fractional seconds) into seconds since the Unix epoch.
+pub(crate) fn parse_utc_timestamp(date: &str) -> Result<u64> {
+    let invalid = || Error::ParseError(format!("Invalid UTC date {}", date));
+
+    let field = |range: std::ops::Range<usize>| -> Result<u64> {
+        date.get(range)
+            .filter(|f| f.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|f| f.parse().ok())
+            .ok_or_else(invalid)
+    };
+    let bytes = date.as_bytes();
+    if bytes.len() < 20
+        || [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')]
+            .iter()
+            .any(|(i, c)| bytes[*i] != *c)
+        || !date.ends_with('Z')
+    {
+        return Err(invalid());
+    }
+    match &bytes[19..bytes.len() - 1] {
+        [] => {}
+        [b'.', fraction @ ..] if fraction.iter().all(|b| b.is_ascii_digit()) => {}
+        _ => return Err(invalid()),
+    }
+
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // days since the epoch of the civil date (from Howard Hinnant's
+    // days_from_civil algorithm)
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Ok(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+fn svns_satisfy(svns: &[u8; TCB_COMPONENTS_LEN], components: &[TcbComponent]) -> bool {
+    components.len() == TCB_COMPONENTS_LEN && svns.iter().zip(components).all(|(s, c)| *s >= c.svn)
+}
+
+/// The entries of the SGX extensions (OID `1.2.840.113741.1.13.1`) of a PCK
+/// certificate, i.e., the values of their sub-OIDs, including those of the
+/// entries nested in the TCB and configuration entries.
+pub(crate) struct SgxExtensions<'a> {
+    // the sub-OID arcs, DER tag and value of each entry
+    entries: Vec<(&'a [u8], u8, &'a [u8])>,
+}
+
+impl<'a> SgxExtensions<'a> {
+    /// Parses the SGX extensions of the DER-encoded PCK certificate `der`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the certificate or its SGX extensions
+    /// are malformed, or it doesn't have them.
+    pub(crate) fn from_cert(der: &'a [u8]) -> Result<Self> {
+        let extensions = certificate_extensions(der)?
+            .into_iter()
+            .find(|(oid, _)| *oid == SGX_EXTENSIONS_OID)
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                Error::ParseError("PCK certificate is missing the SGX extensions".to_string())
+            })?;
+
+        let mut entries = Vec::new();
+        parse_sgx_entries(extensions, true, &mut entries)?;
+        Ok(Self { entries })
+    }
+
+    /// Returns the value of the entry with the sub-OID `arcs`, if it has the
+    /// expected DER tag.
+    pub(crate) fn get(&self, arcs: &[u8], tag: u8) -> Option<&'a [u8]> {
+        self.entries
+            .iter()
+            .find(|(entry_arcs, _, _)| *entry_arcs == arcs)
+            .filter(|(_, entry_tag, _)| *entry_tag == tag)
+            .map(|(_, _, value)| *value)
+    }
+}
+
+/// Parses the SGX extension entries of the DER-encoded
+/// `SEQUENCE OF SEQUENCE { OID, value }` `der`, and, if `top_level`, of the
+/// sequences nested in them.
+fn parse_sgx_entries<'a>(
+    der: &'a [u8],
+    top_level: bool,
+    entries: &mut Vec<(&'a [u8], u8, &'a [u8])>,
+) -> Result<()> {
+    let mut sequence = DerReader::new(der).read_sequence()?;
+    while !sequence.is_empty() {
+        let mut entry = sequence.read_sequence()?;
+        let oid = entry.read(TAG_OID)?;
+        let (tag, value, encoding) = entry.read_tlv()?;
+        let Some(arcs) = oid.strip_prefix(SGX_EXTENSIONS_OID.as_slice()) else {
+            continue;
+        };
+        if top_level && tag == TAG_SEQUENCE {
+            parse_sgx_entries(encoding, false, entries)?;
+        }
+        entries.push((arcs, tag, value));
+    }
+    Ok(())
+}
+
+/// Decodes the contents of a DER `INTEGER` or `ENUMERATED` as an unsigned
+/// integer.
+pub(crate) fn der_uint(bytes: &[u8]) -> Option<u32> {
+    if bytes.is_empty() || bytes.len() > 5 {
+        return None;
+    }
+    Some(bytes.iter().fold(0u32, |n, b| (n << 8) | *b as u32))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::der::tlv;
+    use crate::test_utils::make_der_cert;
+
+    fn sgx_entry(arcs: &[u8], value: Vec<u8>) -> Vec<u8> {
+        let mut oid = SGX_EXTENSIONS_OID.to_vec();
+        oid.extend(arcs);
+        let mut entry = tlv(0x06, &oid);
+        entry.extend(value);
+        tlv(0x30, &entry)
+    }
+
+    fn der_int(n: u16) -> Vec<u8> {
+        let bytes = n.to_be_bytes();
+        let mut value: Vec<u8> = bytes.iter().skip_while(|b| **b == 0).copied().collect();
+        if value.first().is_none_or(|b| *b >= 0x80) {
+            value.insert(0, 0);
+        }
+        tlv(0x02, &value)
+    }
+
+    /// Builds a DER-encoded PCK certificate with the SGX extensions
+    /// `extensions` (see `make_pck_extensions()`), and nothing else.
+    pub(crate) fn make_pck_cert(extensions: &[u8]) -> Vec<u8> {
+        make_der_cert(
+            "180521104550Z",
+            "20491231235959Z",
+            &[(&SGX_EXTENSIONS_OID, extensions)],
+        )
+    }
+
+    /// Builds the DER-encoded SGX extensions of a PCK certificate, with all
+    /// SGX TCB component SVNs set to `sgx_svn`.
+    pub(crate) fn make_pck_extensions(fmspc: [u8; 6], sgx_svn: u8, pcesvn: u16) -> Vec<u8> {
+        let mut tcb = vec![];
+        for i in 1..=TCB_COMPONENTS_LEN as u8 {
+            tcb.extend(sgx_entry(&[SGX_TCB_ARC, i], der_int(sgx_svn as u16)));
+        }
+        tcb.extend(sgx_entry(&[SGX_TCB_ARC, SGX_PCESVN_ARC], der_int(pcesvn)));
+        tcb.extend(sgx_entry(&[SGX_TCB_ARC, 18], tlv(0x04, &[sgx_svn; 16]))); // CPUSVN
+
+        let mut extensions = sgx_entry(&[1], tlv(0x04, &[0xaa; 16])); // PPID
+        extensions.extend(sgx_entry(&[SGX_TCB_ARC], tlv(0x30, &tcb)));
+        extensions.extend(sgx_entry(&[3], tlv(0x04, &[0, 0]))); // PCE ID
+        extensions.extend(sgx_entry(&[5], tlv(0x0a, &[1]))); // SGX type
+        extensions.extend(sgx_entry(&[SGX_FMSPC_ARC], tlv(0x04, &fmspc)));
+        tlv(0x30, &extensions)
+    }
+
+    /// Builds the JSON of a TCB Info with an up-to-date and an out-of-date
+    /// level.
+    pub(crate) fn make_tcb_info_json(fmspc: [u8; 6]) -> String {
+        let level = |sgx: u8, pce: u16, tdx: u8, status: &str| {
+            let comps = |svn: u8| {
+                let comps: Vec<String> = (0..TCB_COMPONENTS_LEN)
+                    .map(|_| format!(r#"{{"svn":{}}}"#, svn))
+                    .collect();
+                format!("[{}]", comps.join(","))
+            };
+            format!(
+                r#"{{"tcb":{{"sgxtcbcomponents":{},"pcesvn":{},"tdxtcbcomponents":{}}},"tcbDate":"2025-01-01T00:00:00Z","tcbStatus":"{}"}}"#,
+                comps(sgx),
+                pce,
+                comps(tdx),
+                status
+            )
+        };
+        format!(
+            r#"{{"id":"TDX","version":3,"issueDate":"2025-01-01T00:00:00Z","nextUpdate":"2999-01-01T00:00:00Z","fmspc":"{}","tcbLevels":[{},{}]}}"#,
+            hex::encode(fmspc),
+            level(5, 13, 5, TCB_STATUS_UP_TO_DATE),
+            level(2, 10, 2, TCB_STATUS_OUT_OF_DATE),
+        )
+    }
+
+    /// Builds the JSON of a TD QE Identity with an up-to-date (ISV SVN 4) and
+    /// an out-of-date (ISV SVN 2) level.
+    pub(crate) fn make_qe_identity_json(mrsigner: [u8; 32]) -> String {
+        let level = |isvsvn: u16, status: &str| {
+            format!(
+                r#"{{"tcb":{{"isvsvn":{}}},"tcbDate":"2025-01-01T00:00:00Z","tcbStatus":"{}"}}"#,
+                isvsvn, status
+            )
+        };
+        format!(
+            r#"{{"id":"TD_QE","version":2,"issueDate":"2025-01-01T00:00:00Z","nextUpdate":"2999-01-01T00:00:00Z","tcbEvaluationDataNumber":17,"miscselect":"00000000","miscselectMask":"FFFFFFFF","attributes":"11000000000000000000000000000000","attributesMask":"FBFFFFFFFFFFFFFF0000000000000000","mrsigner":"{}","isvprodid":2,"tcbLevels":[{},{}]}}"#,
+            hex::encode(mrsigner),
+            level(4, TCB_STATUS_UP_TO_DATE),
+            level(2, "OutOfDate"),
+        )
+    }
+
+    #[test]
+    fn test_pck_platform_tcb() -> Result<()> {
+        let extensions = make_pck_extensions([1, 2, 3, 4, 5, 6], 7, 300);
+        let der = make_pck_cert(&extensions);
+        let tcb = pck_platform_tcb(&der, &[9; TCB_COMPONENTS_LEN])?;
+        assert_eq!(tcb.fmspc, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(tcb.sgx_svns, [7; TCB_COMPONENTS_LEN]);
+        assert_eq!(tcb.pcesvn, 300);
+
+        assert!(pck_platform_tcb(&der[..der.len() - 20], &[0; 16]).is_err());
+        assert!(pck_platform_tcb(&extensions, &[0; 16]).is_err());
+
+        // the SGX extensions are only read from their own extension, even if
+        // another embeds their encoding
+        let decoy = make_pck_extensions([6, 6, 6, 6, 6, 6], 0, 0);
+        let other_oid = [0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x02];
+        let der = make_der_cert(
+            "180521104550Z",
+            "20491231235959Z",
+            &[(&other_oid, &decoy), (&SGX_EXTENSIONS_OID, &extensions)],
+        );
+        let tcb = pck_platform_tcb(&der, &[9; TCB_COMPONENTS_LEN])?;
+        assert_eq!(tcb.fmspc, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(tcb.pcesvn, 300);
+        assert!(
+            pck_platform_tcb(
+                &make_der_cert("180521104550Z", "20491231235959Z", &[(&other_oid, &decoy)]),
+                &[0; 16]
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcb_level() -> Result<()> {
+        let json = format!(
+            r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+            make_tcb_info_json([0; 6]),
+            "00".repeat(64)
+        );
+        let tcb_info = SignedTcbInfo::parse(&json)?.tcb_info;
+
+        let mut platform = PlatformTcb {
+            fmspc: [0; 6],
+            sgx_svns: [5; TCB_COMPONENTS_LEN],
+            pcesvn: 13,
+            tee_tcb_svn: [6; TCB_COMPONENTS_LEN],
+        };
+        let status = |p: &PlatformTcb| tcb_info.tcb_level(p).map(|l| l.tcb_status.clone());
+        assert_eq!(status(&platform).as_deref(), Some(TCB_STATUS_UP_TO_DATE));
+
+        platform.tee_tcb_svn[3] = 4;
+        assert_eq!(status(&platform).as_deref(), Some("OutOfDate"));
+
+        platform.pcesvn = 1;
+        assert_eq!(status(&platform), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_advisories() -> Result<()> {
+        let catalog = AdvisoryCatalog::parse(
+            r#"{
+                "INTEL-SA-00837": {"cves": ["CVE-2022-41804"], "mitigation": "platform-update"},
+                "INTEL-SA-00615": {"title": "MMIO Stale Data"}
+            }"#,
+        )?;
+        let json = make_tcb_info_json([0; 6]).replace(
+            r#""tcbStatus":"OutOfDate""#,
+            r#""tcbStatus":"SWHardeningNeeded","advisoryIDs":["INTEL-SA-00837","INTEL-SA-00615","INTEL-SA-00999"]"#,
+        );
+        let json = format!(
+            r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+            json,
+            "00".repeat(64)
+        );
+        let levels = SignedTcbInfo::parse(&json)?.tcb_info.tcb_levels;
+        assert!(catalog.resolve(&levels[0]).is_empty());
+
+        let advisories = catalog.resolve(&levels[1]);
+        let ids: Vec<_> = advisories.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["INTEL-SA-00837", "INTEL-SA-00615", "INTEL-SA-00999"]);
+        assert_eq!(advisories[0].cves, ["CVE-2022-41804"]);
+        assert_eq!(advisories[0].mitigation, Some(Mitigation::PlatformUpdate));
+        // the status implies the mitigation of the others
+        assert_eq!(advisories[1].title.as_deref(), Some("MMIO Stale Data"));
+        assert_eq!(
+            advisories[1].mitigation,
+            Some(Mitigation::SoftwareHardening)
+        );
+        assert_eq!(advisories[2].tcb_status, TCB_STATUS_SW_HARDENING_NEEDED);
+        assert!(advisories[2].cves.is_empty());
+
+        assert_eq!(Mitigation::for_status("Revoked"), None);
+        assert!(AdvisoryCatalog::parse(r#"{"INTEL-SA-00837": {"cve": []}}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_qe_tcb_level() -> Result<()> {
+        let json = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            make_qe_identity_json([1; 32]),
+            "00".repeat(64)
+        );
+        let qe_identity = SignedQeIdentity::parse(&json)?.qe_identity;
+        assert_eq!(qe_identity.isvprodid, 2);
+
+        let status = |isvsvn| qe_identity.tcb_level(isvsvn).map(|l| l.tcb_status.as_str());
+        assert_eq!(status(5), Some(TCB_STATUS_UP_TO_DATE));
+        assert_eq!(status(3), Some("OutOfDate"));
+        assert_eq!(status(1), None);
+
+        // other enclaves' identities are rejected
+        let qe = json.replace("TD_QE", "QE");
+        assert!(SignedQeIdentity::parse(&qe).is_err_and(|e| e.is_not_supported()));
+        assert!(SignedQeIdentity::parse(&json.replace("enclaveIdentity", "tcbInfo")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_utc_timestamp("2025-03-01T12:30:15Z").unwrap(),
+            1_740_832_215
+        );
+        assert_eq!(
+            parse_utc_timestamp("2024-02-29T00:00:00.000Z").unwrap(),
+            1_709_164_800
+        );
+
+        assert!(parse_utc_timestamp("2025-03-01").is_err());
+        assert!(parse_utc_timestamp("2025-13-01T00:00:00Z").is_err());
+        assert!(parse_utc_timestamp("2025-03-01T00:00:00+01:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_tcb_info() {
+        assert!(SignedTcbInfo::parse("{}").is_err());
+        assert!(
+            SignedTcbInfo::parse(&format!(
+                r#"{{"tcbInfo":{},"signature":"abcd"}}"#,
+                make_tcb_info_json([0; 6])
+            ))
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "host-verification")]
+    #[test]
+    fn test_verify_signature() -> Result<()> {
+        use crate::verification::quote::tests::{TestSigner, sign};
+
+        let signer = TestSigner::new();
+        let (cert, key) = signer.issue("Test TCB Signing");
+        let signed = |tcb_info: &str| {
+            let signature = hex::encode(sign(tcb_info.as_bytes(), &key));
+            let json = format!(r#"{{"tcbInfo":{},"signature":"{}"}}"#, tcb_info, signature);
+            SignedTcbInfo::parse(&json).unwrap()
+        };
+
+        let tcb_info = make_tcb_info_json([0; 6]);
+        assert!(signed(&tcb_info).verify_signature(std::slice::from_ref(&cert), &signer.root)?);
+
+        // a TCB Info past its next update
+        let expired = tcb_info.replace("2999-01-01", "2001-01-01");
+        assert!(!signed(&expired).verify_signature(std::slice::from_ref(&cert), &signer.root)?);
+
+        // a tampered TCB Info
+        let mut tampered = signed(&tcb_info);
+        tampered.raw = tampered.raw.replace("OutOfDate", "UpToDate");
+        assert!(!tampered.verify_signature(std::slice::from_ref(&cert), &signer.root)?);
+
+        // a TCB Info signed under a different root
+        assert!(!matches!(
+            signed(&tcb_info).verify_signature(&[signer.issue("Other").0], &TestSigner::new().root),
+            Ok(true)
+        ));
+
+        // a QE Identity signed by the same key
+        let qe_identity = make_qe_identity_json([1; 32]);
+        let json = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            qe_identity,
+            hex::encode(sign(qe_identity.as_bytes(), &key))
+        );
+        let mut signed = SignedQeIdentity::parse(&json)?;
+        assert!(signed.verify_signature(std::slice::from_ref(&cert), &signer.root)?);
+        signed.raw = signed.raw.replace("\"isvprodid\":2", "\"isvprodid\":3");
+        assert!(!signed.verify_signature(&[cert], &signer.root)?);
+        Ok(())
+    }
+}
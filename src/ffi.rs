@@ -0,0 +1,490 @@
+//! # C FFI Bindings
+//!
+//! This module exposes the library's evidence collection and verification to
+//! C and C++ workloads through `extern "C"` functions, so they can link
+//! against this crate instead of reimplementing the TDX ioctls and the quote
+//! parsing logic.
+//!
+//! The following functions are available:
+//! - `tdx_attest_get_quote()`: Retrieves a signed TD quote
+//! - `tdx_attest_get_launch_measurement()`: Retrieves the TD's MRTD
+//! - `tdx_attest_collect_bundle()`: Collects a CBOR-encoded evidence bundle
+//! - `tdx_attest_verify_bundle()`: Appraises an evidence bundle against a
+//!   TOML policy (requires the `host-verification` or
+//!   `rustcrypto-verification` feature)
+//!
+//! Every function returns a `TdxAttestStatus`, which is `TDX_ATTEST_STATUS_OK`
+//! on success, and otherwise maps the `ErrorKind` of the underlying error. The
+//! error message of the last failed call on the current thread is available
+//! from `tdx_attest_last_error()`.
+//!
+//! Buffers and strings returned by the library are owned by the caller, and
+//! must be released with `tdx_attest_free_buffer()` and
+//! `tdx_attest_free_string()`, respectively.
+//!
+//! When compiled with the `ffi` feature, the build generates the C header at
+//! `target/include/tdx_workload_attestation.h` with cbindgen.
+//!
+//! ## Example Usage
+//!
+//! ```c
+//! #include "tdx_workload_attestation.h"
+//!
+//! uint8_t report_data[64] = {0};
+//! uint8_t *quote;
+//! size_t quote_len;
+//!
+//! if (tdx_attest_get_quote(report_data, &quote, &quote_len) != TDX_ATTEST_STATUS_OK) {
+//!     fprintf(stderr, "Error getting quote: %s\n", tdx_attest_last_error());
+//!     return 1;
+//! }
+//! // Do something with the quote
+//! tdx_attest_free_buffer(quote, quote_len);
+//! ```
+
+use crate::error::{ErrorKind, Result};
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The status returned by the FFI functions.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TdxAttestStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required argument is null or invalid.
+    InvalidArgument = 1,
+    /// An I/O error (`ErrorKind::Io`).
+    Io = 2,
+    /// A network error (`ErrorKind::Network`).
+    Network = 3,
+    /// The operation is not supported (`ErrorKind::NotSupported`).
+    NotSupported = 4,
+    /// An OpenSSL error (`ErrorKind::OpenSsl`).
+    Openssl = 5,
+    /// A protobuf error (`ErrorKind::Protobuf`).
+    Protobuf = 6,
+    /// A parsing error (`ErrorKind::Parse`).
+    Parse = 7,
+    /// A quote error (`ErrorKind::Quote`).
+    Quote = 8,
+    /// A serialization error (`ErrorKind::Serialization`).
+    Serialization = 9,
+    /// A signature error (`ErrorKind::Signature`).
+    Signature = 10,
+    /// A verification error (`ErrorKind::Verification`).
+    Verification = 11,
+    /// The request was rate limited (`ErrorKind::RateLimited`).
+    RateLimited = 12,
+    /// Access was denied by file permissions (`ErrorKind::PermissionDenied`).
+    PermissionDenied = 13,
+    /// Access was denied by a Linux Security Module (`ErrorKind::LsmDenied`).
+    LsmDenied = 14,
+}
+
+impl From<ErrorKind> for TdxAttestStatus {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Io => TdxAttestStatus::Io,
+            ErrorKind::Network => TdxAttestStatus::Network,
+            ErrorKind::NotSupported => TdxAttestStatus::NotSupported,
+            ErrorKind::OpenSsl => TdxAttestStatus::Openssl,
+            ErrorKind::Protobuf => TdxAttestStatus::Protobuf,
+            ErrorKind::Parse => TdxAttestStatus::Parse,
+            ErrorKind::Quote => TdxAttestStatus::Quote,
+            ErrorKind::Serialization => TdxAttestStatus::Serialization,
+            ErrorKind::Signature => TdxAttestStatus::Signature,
+            ErrorKind::Verification => TdxAttestStatus::Verification,
+            ErrorKind::RateLimited => TdxAttestStatus::RateLimited,
+            ErrorKind::PermissionDenied => TdxAttestStatus::PermissionDenied,
+            ErrorKind::LsmDenied => TdxAttestStatus::LsmDenied,
+        }
+    }
+}
+
+/// An invalid argument passed across the FFI boundary.
+struct InvalidArgument(String);
+
+/// Runs `f`, recording its error (if any) as the last error.
+fn run(f: impl FnOnce() -> std::result::Result<Result<()>, InvalidArgument>) -> TdxAttestStatus {
+    let (status, msg) = match f() {
+        Ok(Ok(())) => (TdxAttestStatus::Ok, None),
+        Ok(Err(e)) => (e.kind().into(), Some(e.to_string())),
+        Err(InvalidArgument(msg)) => (
+            TdxAttestStatus::InvalidArgument,
+            Some(format!("Invalid argument: {}", msg)),
+        ),
+    };
+
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = msg.map(|m| CString::new(m.replace('\0', "")).unwrap_or_default());
+    });
+    status
+}
+
+/// Returns the `len` bytes at `ptr`, rejecting null pointers.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` readable bytes.
+unsafe fn slice_arg<'a>(
+    name: &str,
+    ptr: *const u8,
+    len: usize,
+) -> std::result::Result<&'a [u8], InvalidArgument> {
+    if ptr.is_null() {
+        return Err(InvalidArgument(format!("{} is null", name)));
+    }
+    // SAFETY: guaranteed by the caller
+    Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+/// Returns the UTF-8 string at `ptr`, or `None` if it is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(
+    name: &str,
+    ptr: *const c_char,
+) -> std::result::Result<Option<&'a str>, InvalidArgument> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: guaranteed by the caller
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| InvalidArgument(format!("{} is not valid UTF-8", name)))
+}
+
+/// Hands `bytes` over to the caller through `out` and `out_len`.
+///
+/// # Safety
+///
+/// `out` and `out_len` must be valid for writes.
+unsafe fn write_buffer(bytes: Vec<u8>, out: *mut *mut u8, out_len: *mut usize) {
+    let bytes = bytes.into_boxed_slice();
+    // SAFETY: guaranteed by the caller
+    unsafe {
+        *out_len = bytes.len();
+        *out = Box::into_raw(bytes) as *mut u8;
+    }
+}
+
+/// Returns the error message of the last failed call on the current thread,
+/// or null if the last call succeeded.
+///
+/// The message is owned by the library, and is valid until the next call on
+/// the current thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn tdx_attest_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(msg) => msg.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Releases a buffer returned by the library.
+///
+/// # Safety
+///
+/// `buf` must be null, or a buffer of `len` bytes returned by the library
+/// that hasn't been released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_free_buffer(buf: *mut u8, len: usize) {
+    if !buf.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buf, len)) });
+    }
+}
+
+/// Releases a string returned by the library.
+///
+/// # Safety
+///
+/// `s` must be null, or a string returned by the library that hasn't been
+/// released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Retrieves a signed TD quote over the 64-byte `report_data`.
+///
+/// On success, the quote is returned in `quote_out` and `quote_len_out`, and
+/// must be released with `tdx_attest_free_buffer()`.
+///
+/// # Safety
+///
+/// `report_data` must point to 64 readable bytes, and `quote_out` and
+/// `quote_len_out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_get_quote(
+    report_data: *const u8,
+    quote_out: *mut *mut u8,
+    quote_len_out: *mut usize,
+) -> TdxAttestStatus {
+    use crate::tdx::{LinuxTdxProvider, TDX_REPORT_DATA_LEN};
+
+    run(|| {
+        // SAFETY: guaranteed by the caller
+        let report_data = unsafe { slice_arg("report_data", report_data, TDX_REPORT_DATA_LEN)? };
+        if quote_out.is_null() || quote_len_out.is_null() {
+            return Err(InvalidArgument("quote_out is null".to_string()));
+        }
+
+        let report_data: &[u8; TDX_REPORT_DATA_LEN] = report_data
+            .try_into()
+            .expect("slice has the report data length");
+        Ok(LinuxTdxProvider::new().get_quote(report_data).map(|quote| {
+            // SAFETY: checked above
+            unsafe { write_buffer(quote, quote_out, quote_len_out) }
+        }))
+    })
+}
+
+/// Retrieves the launch measurement (MRTD) of the TD into the 48-byte
+/// `mrtd_out`.
+///
+/// # Safety
+///
+/// `mrtd_out` must point to 48 writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_get_launch_measurement(mrtd_out: *mut u8) -> TdxAttestStatus {
+    use crate::provider::AttestationProvider;
+    use crate::tdx::LinuxTdxProvider;
+
+    run(|| {
+        if mrtd_out.is_null() {
+            return Err(InvalidArgument("mrtd_out is null".to_string()));
+        }
+
+        Ok(LinuxTdxProvider::new()
+            .get_launch_measurement()
+            .map(|mrtd| {
+                // SAFETY: guaranteed by the caller
+                unsafe { ptr::copy_nonoverlapping(mrtd.as_ptr(), mrtd_out, mrtd.len()) }
+            }))
+    })
+}
+
+/// Collects the evidence bundle for the current TD, binding the `nonce_len`
+/// bytes of `nonce` into the quote and including the application event log
+/// at `event_log_path` (if not null).
+///
+/// On success, the CBOR-encoded bundle is returned in `bundle_out` and
+/// `bundle_len_out`, and must be released with `tdx_attest_free_buffer()`.
+///
+/// # Safety
+///
+/// `nonce` must point to `nonce_len` readable bytes, `event_log_path` must be
+/// null or a NUL-terminated string, and `bundle_out` and `bundle_len_out`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_collect_bundle(
+    nonce: *const u8,
+    nonce_len: usize,
+    event_log_path: *const c_char,
+    bundle_out: *mut *mut u8,
+    bundle_len_out: *mut usize,
+) -> TdxAttestStatus {
+    use crate::evidence::Bundle;
+    use crate::measure::event_log::EventLog;
+
+    run(|| {
+        // SAFETY: guaranteed by the caller
+        let nonce = unsafe { slice_arg("nonce", nonce, nonce_len)? };
+        // SAFETY: guaranteed by the caller
+        let event_log = unsafe { str_arg("event_log_path", event_log_path)? }.map(EventLog::new);
+        if bundle_out.is_null() || bundle_len_out.is_null() {
+            return Err(InvalidArgument("bundle_out is null".to_string()));
+        }
+
+        Ok(Bundle::collect(nonce, event_log.as_ref())
+            .and_then(|bundle| bundle.to_bytes())
+            .map(|bytes| {
+                // SAFETY: checked above
+                unsafe { write_buffer(bytes, bundle_out, bundle_len_out) }
+            }))
+    })
+}
+
+/// Appraises the `bundle_len` bytes of the CBOR-encoded evidence bundle
+/// `bundle` against the TOML policy at `policy_path` (or an empty policy, if
+/// null), with the trusted root certificate and TCB collateral in
+/// `collateral_dir` (see `Policy::with_collateral_dir()`).
+///
+/// On success, `passed_out` is set to whether all checks passed, and, if
+/// `verdict_json_out` isn't null, the JSON-encoded verdict is returned in it,
+/// and must be released with `tdx_attest_free_string()`. Failed checks are
+/// reported in the verdict, not as errors.
+///
+/// # Safety
+///
+/// `bundle` must point to `bundle_len` readable bytes, `policy_path` must be
+/// null or a NUL-terminated string, `collateral_dir` must be a
+/// NUL-terminated string, `passed_out` must be valid for writes, and
+/// `verdict_json_out` must be null or valid for writes.
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tdx_attest_verify_bundle(
+    bundle: *const u8,
+    bundle_len: usize,
+    policy_path: *const c_char,
+    collateral_dir: *const c_char,
+    passed_out: *mut bool,
+    verdict_json_out: *mut *mut c_char,
+) -> TdxAttestStatus {
+    use crate::error::Error;
+    use crate::evidence::{Bundle, Policy};
+
+    run(|| {
+        // SAFETY: guaranteed by the caller
+        let bundle = unsafe { slice_arg("bundle", bundle, bundle_len)? };
+        // SAFETY: guaranteed by the caller
+        let policy_path = unsafe { str_arg("policy_path", policy_path)? };
+        // SAFETY: guaranteed by the caller
+        let collateral_dir = unsafe { str_arg("collateral_dir", collateral_dir)? }
+            .ok_or_else(|| InvalidArgument("collateral_dir is null".to_string()))?;
+        if passed_out.is_null() {
+            return Err(InvalidArgument("passed_out is null".to_string()));
+        }
+
+        let verdict = (|| {
+            let policy = match policy_path {
+                Some(path) => Policy::from_file(path)?,
+                None => Policy::new(),
+            }
+            .with_collateral_dir(collateral_dir)?;
+            Bundle::from_bytes(bundle)?.verify(&policy)
+        })();
+
+        Ok(verdict.and_then(|verdict| {
+            let json = if verdict_json_out.is_null() {
+                None
+            } else {
+                let json = serde_json::to_string(&verdict)
+                    .map_err(|e| Error::SerializationError(e.to_string()))?;
+                Some(CString::new(json).map_err(|e| Error::SerializationError(e.to_string()))?)
+            };
+
+            // SAFETY: checked above
+            unsafe {
+                *passed_out = verdict.passed();
+                if let Some(json) = json {
+                    *verdict_json_out = json.into_raw();
+                }
+            }
+            Ok(())
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn last_error() -> Option<String> {
+        let msg = tdx_attest_last_error();
+        if msg.is_null() {
+            return None;
+        }
+        // SAFETY: the last error is a valid string until the next call
+        Some(
+            unsafe { CStr::from_ptr(msg) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        let status = run(|| Ok(Err(Error::NotSupported("test".to_string()))));
+        assert_eq!(status, TdxAttestStatus::NotSupported);
+        assert_eq!(last_error().unwrap(), "Not supported: test");
+
+        let status = run(|| Err(InvalidArgument("arg is null".to_string())));
+        assert_eq!(status, TdxAttestStatus::InvalidArgument);
+        assert!(last_error().unwrap().contains("arg is null"));
+
+        let status = run(|| Ok(Ok(())));
+        assert_eq!(status, TdxAttestStatus::Ok);
+        assert!(last_error().is_none());
+    }
+
+    #[test]
+    fn test_free_buffer() {
+        let mut buf = ptr::null_mut();
+        let mut len = 0;
+        // SAFETY: the out pointers are valid
+        unsafe { write_buffer(vec![1, 2, 3], &mut buf, &mut len) };
+        assert_eq!(len, 3);
+        // SAFETY: the buffer was returned by the library
+        unsafe {
+            assert_eq!(std::slice::from_raw_parts(buf, len), [1, 2, 3]);
+            tdx_attest_free_buffer(buf, len);
+            tdx_attest_free_buffer(ptr::null_mut(), 0);
+        }
+    }
+
+    #[test]
+    fn test_get_quote_null_arguments() {
+        let mut quote = ptr::null_mut();
+        let mut len = 0;
+        // SAFETY: null pointers are rejected
+        let status = unsafe { tdx_attest_get_quote(ptr::null(), &mut quote, &mut len) };
+        assert_eq!(status, TdxAttestStatus::InvalidArgument);
+        assert!(quote.is_null());
+    }
+
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    #[test]
+    fn test_verify_bundle_invalid() {
+        let dir = std::env::temp_dir().join(format!("tdx-ffi-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("root_ca.der"), [1, 2, 3]).unwrap();
+        let dir_str = CString::new(dir.to_str().unwrap()).unwrap();
+
+        let mut passed = true;
+        let mut verdict = ptr::null_mut();
+        // SAFETY: all pointers are valid
+        let status = unsafe {
+            tdx_attest_verify_bundle(
+                [0xff].as_ptr(),
+                1,
+                ptr::null(),
+                dir_str.as_ptr(),
+                &mut passed,
+                &mut verdict,
+            )
+        };
+        assert_eq!(status, TdxAttestStatus::Parse);
+        assert!(verdict.is_null());
+
+        // SAFETY: null pointers are rejected
+        let status = unsafe {
+            tdx_attest_verify_bundle(
+                [0xff].as_ptr(),
+                1,
+                ptr::null(),
+                ptr::null(),
+                &mut passed,
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, TdxAttestStatus::InvalidArgument);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! # GCP Endorsement Disk Cache
+//!
+//! This module provides a simple on-disk cache for artifacts that the GCP host
+//! interface downloads, such as launch endorsements.
+//!
+//! Launch endorsements are immutable per MRTD, so caching them allows
+//! `GcpTdxHost` to verify the launch endorsement without network access after
+//! the first successful fetch.
+//!
+//! Every cached entry is stored together with its SHA-256 digest. Entries
+//! whose digest does not match (e.g., due to a truncated write) are treated as
+//! cache misses and removed.
+//!
+//! # Notes
+//! - The digest only detects corruption: anyone who can write an entry can
+//!   also rewrite its digest. Cached entries must therefore be verified like
+//!   downloaded ones (as `GcpTdxHost` does for endorsements, against the GCE
+//!   TCB root), and trust anchors must never be cached.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::gcp::cache::EndorsementCache;
+//!
+//! let cache = EndorsementCache::new("/tmp/tdx-attest-cache");
+//! cache.put("example.binarypb", b"endorsement bytes").unwrap();
+//!
+//! match cache.get("example.binarypb").unwrap() {
+//!     Some(bytes) => println!("Cache hit: {} bytes", bytes.len()),
+//!     None => println!("Cache miss"),
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+
+use openssl::sha::sha256;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The name of this library's directory within the user's cache directory
+const CACHE_DIR_NAME: &str = "tdx-workload-attestation";
+
+// The extension of the file holding a cached entry's digest
+const DIGEST_EXT: &str = "sha256";
+
+// The number of names tried for an entry's temporary file
+const TMP_FILE_ATTEMPTS: usize = 16;
+
+/// A directory-backed cache for downloaded GCP attestation artifacts.
+#[derive(Clone, Debug)]
+pub struct EndorsementCache {
+    dir: PathBuf,
+}
+
+impl EndorsementCache {
+    /// Creates a new `EndorsementCache` rooted at `dir`.
+    ///
+    /// The directory is created on the first write.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        EndorsementCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the default cache directory for GCP artifacts.
+    ///
+    /// This is `$XDG_CACHE_HOME/tdx-workload-attestation/gcp`, falling back to
+    /// `$HOME/.cache/tdx-workload-attestation/gcp`. Returns `None` if neither
+    /// environment variable is set.
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+            Some(xdg) => PathBuf::from(xdg),
+            None => PathBuf::from(env::var_os("HOME").filter(|v| !v.is_empty())?).join(".cache"),
+        };
+
+        Some(base.join(CACHE_DIR_NAME).join("gcp"))
+    }
+
+    /// Returns the directory backing this cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Retrieves the cached entry for `key`.
+    ///
+    /// Returns `Ok(None)` if the entry is missing or fails its integrity
+    /// check, in which case the corrupted entry is removed.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the entry is a symbolic link.
+    /// - `Error::IoError` if the entry exists but cannot be read.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let (data_path, digest_path) = self.entry_paths(key)?;
+
+        if !data_path.exists() || !digest_path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&data_path)?;
+        let digest = fs::read_to_string(&digest_path)?;
+
+        if digest.trim() != hex::encode(sha256(&data)) {
+            self.remove(key)?;
+            return Ok(None);
+        }
+
+        Ok(Some(data))
+    }
+
+    /// Stores `data` as the entry for `key`, replacing any existing entry.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the entry is a symbolic link.
+    /// - `Error::IoError` if the entry cannot be written.
+    pub fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let (data_path, digest_path) = self.entry_paths(key)?;
+
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&data_path, data)?;
+        write_atomic(&digest_path, hex::encode(sha256(data)).as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Removes the entry for `key`, if present.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::IoError` if an existing entry cannot be removed.
+    pub fn remove(&self, key: &str) -> Result<()> {
+        let (data_path, digest_path) = self.entry_paths(key)?;
+
+        for path in [data_path, digest_path] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry_paths(&self, key: &str) -> Result<(PathBuf, PathBuf)> {
+        // keys are plain file names, so reject anything that could escape the cache dir
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(Error::NotSupported(format!("Invalid cache key {}", key)));
+        }
+
+        let data_path = self.dir.join(key);
+        let digest_path = self.dir.join(format!("{}.{}", key, DIGEST_EXT));
+
+        // throw an error if either file is a symlink
+        for path in [&data_path, &digest_path] {
+            if path.is_symlink() {
+                return Err(Error::NotSupported(format!(
+                    "Path {} is a symlink",
+                    path.display()
+                )));
+            }
+        }
+
+        Ok((data_path, digest_path))
+    }
+}
+
+/// Writes `data` to a temporary file next to `path` and renames it into
+/// place, so readers never observe a partially written entry.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let (tmp_path, mut file) = create_tmp_file(path)?;
+    let written = file
+        .write_all(data)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    Ok(written?)
+}
+
+/// Creates a new temporary file next to `path`, with a name unique to this
+/// write, so that concurrent writers (e.g., two processes filling the cache)
+/// don't collide. The file must not exist, so an existing file or symlink
+/// at its path is never followed.
+fn create_tmp_file(path: &Path) -> Result<(PathBuf, fs::File)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    for _ in 0..TMP_FILE_ATTEMPTS {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(format!(
+            ".{}-{}-{}.tmp",
+            std::process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path = PathBuf::from(tmp_path);
+
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(Error::IoError(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("No free temporary file name for {}", path.display()),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache() -> EndorsementCache {
+        let dir = env::temp_dir().join(format!("tdx-attest-cache-{}", rand::random::<u64>()));
+        EndorsementCache::new(dir)
+    }
+
+    #[test]
+    fn test_put_get() -> Result<()> {
+        let cache = test_cache();
+        assert!(cache.get("entry.binarypb")?.is_none());
+
+        cache.put("entry.binarypb", b"endorsement")?;
+        assert_eq!(cache.get("entry.binarypb")?, Some(b"endorsement".to_vec()));
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tampered() -> Result<()> {
+        let cache = test_cache();
+        cache.put("entry.binarypb", b"endorsement")?;
+
+        fs::write(cache.dir().join("entry.binarypb"), b"tampered")?;
+        assert!(cache.get("entry.binarypb")?.is_none());
+
+        // the corrupted entry should have been evicted
+        assert!(!cache.dir().join("entry.binarypb").exists());
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_put_concurrent() -> Result<()> {
+        // e.g., processes filling the cache with the same endorsement
+        let cache = test_cache();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| cache.put("entry.binarypb", b"endorsement")))
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().unwrap())
+        })?;
+
+        // no temporary file is left behind
+        assert_eq!(cache.get("entry.binarypb")?, Some(b"endorsement".to_vec()));
+        assert_eq!(fs::read_dir(cache.dir())?.count(), 2);
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_put_ignores_symlinks() -> Result<()> {
+        let cache = test_cache();
+        fs::create_dir_all(cache.dir())?;
+        let target = cache.dir().join("target");
+        fs::write(&target, b"target")?;
+        std::os::unix::fs::symlink(&target, cache.dir().join("entry.binarypb.tmp"))?;
+
+        cache.put("entry.binarypb", b"endorsement")?;
+        assert_eq!(fs::read(&target)?, b"target");
+
+        fs::remove_dir_all(cache.dir())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        let cache = test_cache();
+        match cache.put("../escape", b"data") {
+            Err(Error::NotSupported(_)) => {}
+            other => panic!("Expected NotSupported error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! # GCP Launch Endorsements
+//!
+//! This module parses the launch endorsements that GCP publishes for the
+//! UEFI firmware of its Intel TDX VMs: a golden measurement of the firmware,
+//! which holds the MRTDs of TDs launched with it, signed by Google.
+//!
+//! The endorsement schema is vendored from Google's `gce-tcb-verifier` in
+//! `proto/gcp/endorsement.proto`, so that builds are reproducible, and the
+//! `proto` module is generated from it at build time (into Cargo's `OUT_DIR`,
+//! with a pure-Rust parser). To update the vendored schema, run the
+//! `update_gcp_protos` example, and bump `ENDORSEMENT_SCHEMA_VERSION` and
+//! `ENDORSEMENT_SCHEMA_SHA256`.
+//!
+//! # Notes
+//! - Endorsements are signed over their serialized golden measurement, so
+//!   fields added to the schema upstream are kept as unknown fields when
+//!   parsing, and don't affect verification.
+//! - If an endorsement is missing the TDX measurements but has unknown fields,
+//!   it likely uses an incompatible newer schema, and is rejected with an
+//!   `Error::NotSupported` rather than an `Error::ParseError`.
+
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/gcp/mod.rs"));
+}
+
+pub(crate) use proto::endorsement::{VMGoldenMeasurement, VMLaunchEndorsement};
+
+use crate::core::register::MeasurementRegister;
+use crate::error::{Error, Result};
+
+use protobuf::Message;
+
+/// The version of the vendored endorsement schema, bumped whenever it's
+/// updated from upstream.
+pub const ENDORSEMENT_SCHEMA_VERSION: u32 = 1;
+
+/// The SHA-256 digest of the vendored endorsement schema.
+pub const ENDORSEMENT_SCHEMA_SHA256: &str =
+    "526c046ad804c896daf52a50f1b3227a73650a3b054e020436d0c2981de166c0";
+
+/// A launch endorsement and its golden measurement.
+pub(crate) struct LaunchEndorsement {
+    pub(crate) endorsement: VMLaunchEndorsement,
+    pub(crate) golden: VMGoldenMeasurement,
+}
+
+impl LaunchEndorsement {
+    /// Parses a raw (serialized) launch endorsement and its golden
+    /// measurement. The signature isn't verified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ProtobufError` if either message is malformed.
+    pub(crate) fn parse(raw_endorsement: &[u8]) -> Result<Self> {
+        let endorsement = VMLaunchEndorsement::parse_from_bytes(raw_endorsement)?;
+        let golden = VMGoldenMeasurement::parse_from_bytes(&endorsement.serialized_uefi_golden)?;
+        Ok(Self {
+            endorsement,
+            golden,
+        })
+    }
+
+    /// Returns whether the endorsement has fields that are unknown to the
+    /// vendored schema, i.e., it was produced with a newer schema.
+    pub(crate) fn has_unknown_fields(&self) -> bool {
+        let tdx = self.golden.tdx.as_ref();
+        has_unknown_fields(&self.endorsement)
+            || has_unknown_fields(&self.golden)
+            || tdx.is_some_and(|tdx| {
+                has_unknown_fields(tdx) || tdx.measurements.iter().any(has_unknown_fields)
+            })
+    }
+
+    /// Returns the endorsed MRTD, i.e., the MRTD of the first TDX
+    /// measurement.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the TDX measurements are missing
+    /// and the endorsement has unknown fields, or an `Error::ParseError` if
+    /// they're missing otherwise.
+    pub(crate) fn endorsed_mrtd(&self) -> Result<&[u8]> {
+        let mrtd = self
+            .golden
+            .tdx
+            .as_ref()
+            .and_then(|tdx| tdx.measurements.first())
+            .map(|measurement| measurement.mrtd.as_slice())
+            .filter(|mrtd| !mrtd.is_empty());
+
+        match mrtd {
+            Some(mrtd) => Ok(mrtd),
+            None if self.has_unknown_fields() => Err(Error::NotSupported(format!(
+                "Launch endorsement is missing the TDX measurements, and has fields unknown to endorsement schema version {}",
+                ENDORSEMENT_SCHEMA_VERSION
+            ))),
+            None => Err(Error::ParseError(
+                "Expected TDX measurement structure missing".to_string(),
+            )),
+        }
+    }
+
+    /// Returns whether `mrtd` is endorsed for any of the endorsement's memory
+    /// configurations, or for a TD memory size of `ram_gib` GiB, if given.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `endorsed_mrtd()` if the TDX measurements are
+    /// missing.
+    pub(crate) fn endorses(
+        &self,
+        mrtd: &MeasurementRegister,
+        ram_gib: Option<u32>,
+    ) -> Result<bool> {
+        self.endorsed_mrtd()?;
+
+        Ok(self
+            .golden
+            .tdx
+            .iter()
+            .flat_map(|tdx| &tdx.measurements)
+            .filter(|measurement| ram_gib.is_none_or(|gib| measurement.ram_gib == gib))
+            .any(|measurement| {
+                MeasurementRegister::from_slice(&measurement.mrtd).is_ok_and(|m| m == *mrtd)
+            }))
+    }
+
+    /// Returns the security version number (SVN) of the endorsed firmware,
+    /// from its TDX measurements.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `endorsed_mrtd()` if the TDX measurements are
+    /// missing.
+    pub(crate) fn firmware_svn(&self) -> Result<u32> {
+        self.endorsed_mrtd()?;
+        Ok(self.golden.tdx.as_ref().map_or(0, |tdx| tdx.svn))
+    }
+
+    /// Returns the time the golden measurement was created, in seconds since
+    /// the Unix epoch, if it's set.
+    pub(crate) fn issued_at(&self) -> Option<u64> {
+        self.golden
+            .timestamp
+            .as_ref()
+            .and_then(|timestamp| u64::try_from(timestamp.seconds).ok())
+            .filter(|seconds| *seconds > 0)
+    }
+
+    /// Returns the SHA-384 digest of the endorsed firmware binary.
+    pub(crate) fn firmware_digest(&self) -> &[u8] {
+        &self.golden.digest
+    }
+}
+
+fn has_unknown_fields<M: Message>(message: &M) -> bool {
+    message
+        .special_fields()
+        .unknown_fields()
+        .iter()
+        .next()
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    // An unknown varint field (number 127), as added by a newer schema
+    const UNKNOWN_FIELD: [u8; 3] = [0xf8, 0x07, 0x01];
+
+    fn make_endorsement(golden: &VMGoldenMeasurement, extra: &[u8]) -> Vec<u8> {
+        let mut serialized_uefi_golden = golden.write_to_bytes().unwrap();
+        serialized_uefi_golden.extend(extra);
+
+        VMLaunchEndorsement {
+            serialized_uefi_golden,
+            signature: vec![1; 256],
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schema_version() {
+        // update ENDORSEMENT_SCHEMA_VERSION and ENDORSEMENT_SCHEMA_SHA256
+        // together with the vendored schema
+        let schema = include_bytes!("../../../proto/gcp/endorsement.proto");
+        assert_eq!(
+            hex::encode(Sha256::digest(schema)),
+            ENDORSEMENT_SCHEMA_SHA256
+        );
+    }
+
+    #[test]
+    fn test_parse_endorsement() -> Result<()> {
+        let mut golden = VMGoldenMeasurement::new();
+        golden.tdx.mut_or_insert_default().measurements.push(
+            proto::endorsement::vmtdx::Measurement {
+                mrtd: vec![0xaa; 48],
+                ..Default::default()
+            },
+        );
+        golden.timestamp.mut_or_insert_default().seconds = 1_700_000_000;
+        golden.digest = vec![0xdd; 48];
+
+        let endorsement = LaunchEndorsement::parse(&make_endorsement(&golden, &[]))?;
+        assert!(!endorsement.has_unknown_fields());
+        assert_eq!(endorsement.endorsed_mrtd()?, [0xaa; 48]);
+        assert_eq!(endorsement.endorsement.signature, [1; 256]);
+        assert_eq!(endorsement.issued_at(), Some(1_700_000_000));
+        assert_eq!(endorsement.firmware_digest(), [0xdd; 48]);
+
+        // fields from newer schemas are tolerated
+        let endorsement = LaunchEndorsement::parse(&make_endorsement(&golden, &UNKNOWN_FIELD))?;
+        assert!(endorsement.has_unknown_fields());
+        assert_eq!(endorsement.endorsed_mrtd()?, [0xaa; 48]);
+
+        assert!(LaunchEndorsement::parse(b"\xff").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_endorses() -> Result<()> {
+        let mut golden = VMGoldenMeasurement::new();
+        golden.tdx.mut_or_insert_default().svn = 7;
+        let measurements = &mut golden.tdx.mut_or_insert_default().measurements;
+        for (ram_gib, mrtd) in [(4, 0xaa), (8, 0xbb)] {
+            measurements.push(proto::endorsement::vmtdx::Measurement {
+                ram_gib,
+                mrtd: vec![mrtd; 48],
+                ..Default::default()
+            });
+        }
+
+        let endorsement = LaunchEndorsement::parse(&make_endorsement(&golden, &[]))?;
+        assert!(endorsement.endorses(&[0xbb; 48].into(), None)?);
+        assert!(endorsement.endorses(&[0xbb; 48].into(), Some(8))?);
+        assert!(!endorsement.endorses(&[0xbb; 48].into(), Some(4))?);
+        assert!(!endorsement.endorses(&[0xcc; 48].into(), None)?);
+        assert_eq!(endorsement.firmware_svn()?, 7);
+        assert_eq!(endorsement.issued_at(), None);
+
+        let endorsement =
+            LaunchEndorsement::parse(&make_endorsement(&VMGoldenMeasurement::new(), &[]))?;
+        assert!(endorsement.endorses(&[0xaa; 48].into(), None).is_err());
+        assert!(endorsement.firmware_svn().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_measurements() -> Result<()> {
+        let golden = VMGoldenMeasurement::new();
+
+        let endorsement = LaunchEndorsement::parse(&make_endorsement(&golden, &[]))?;
+        assert!(matches!(
+            endorsement.endorsed_mrtd(),
+            Err(Error::ParseError(_))
+        ));
+
+        let endorsement = LaunchEndorsement::parse(&make_endorsement(&golden, &UNKNOWN_FIELD))?;
+        assert!(
+            endorsement
+                .endorsed_mrtd()
+                .is_err_and(|e| e.is_not_supported())
+        );
+        Ok(())
+    }
+}
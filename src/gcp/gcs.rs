@@ -0,0 +1,279 @@
+//! # Google Cloud Storage (GCS) HTTP Fetcher
+//!
+//! This module implements a native HTTP client for downloading objects, such
+//! as TDX launch endorsements, from Google Cloud Storage without relying on
+//! the `gcloud` CLI.
+//!
+//! Requests can be authenticated with an OAuth2 access token obtained from
+//! either:
+//! - the GCE metadata server, i.e., the service account attached to the VM or
+//!   mapped via Workload Identity, or
+//! - a service account key file, using the OAuth2 JWT bearer flow.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::gcp::GcpTdxHost;
+//! use tdx_workload_attestation::gcp::gcs::GcsAuth;
+//!
+//! // Fetch the endorsement with the VM's attached service account
+//! let host = GcpTdxHost::builder()
+//!     .gcs_auth(GcsAuth::MetadataServer)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::http::{http_client, send};
+use crate::retry::RetryPolicy;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The GCS JSON API endpoint for downloading objects
+const GCS_DOWNLOAD_URL: &str = "https://storage.googleapis.com/storage/v1/b";
+
+// The GCE metadata server endpoint for the default service account's token
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+// The OAuth2 scope needed to read GCS objects
+const GCS_READ_ONLY_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_only";
+
+// The OAuth2 grant type for exchanging a signed JWT for an access token
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+// The lifetime of the JWT assertion sent to the token endpoint
+const JWT_LIFETIME_SECS: u64 = 3600;
+
+/// The method used to authenticate requests for GCS objects.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GcsAuth {
+    /// Use the `gcloud` CLI, and the credentials it is configured with.
+    #[default]
+    GcloudCli,
+    /// Send unauthenticated requests, for publicly readable buckets.
+    Anonymous,
+    /// Use an access token for the VM's service account, obtained from the
+    /// GCE metadata server (this also covers Workload Identity).
+    MetadataServer,
+    /// Use an access token obtained with the given service account key file.
+    ServiceAccountKey(PathBuf),
+}
+
+/// An OAuth2 access token response.
+#[derive(Debug, Deserialize)]
+struct AccessToken {
+    access_token: String,
+}
+
+/// The subset of a service account key file needed for the JWT bearer flow.
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+/// Downloads the object `object` from the GCS bucket `bucket`, authenticating
+/// with `auth`.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if `auth` is `GcsAuth::GcloudCli`, which is not
+///   handled by the native fetcher.
+/// - `Error::NetworkError` if the access token or the object cannot be
+///   retrieved after exhausting the `RetryPolicy`.
+/// - `Error::ParseError` if the access token response or service account key
+///   cannot be parsed.
+pub fn fetch_object(
+    bucket: &str,
+    object: &str,
+    auth: &GcsAuth,
+    policy: &RetryPolicy,
+) -> Result<Vec<u8>> {
+    let client = http_client(policy)?;
+
+    let token = match auth {
+        GcsAuth::GcloudCli => {
+            return Err(Error::NotSupported(
+                "The gcloud CLI is not supported by the native GCS fetcher".to_string(),
+            ));
+        }
+        GcsAuth::Anonymous => None,
+        GcsAuth::MetadataServer => Some(policy.run(|| metadata_server_token(&client))?),
+        GcsAuth::ServiceAccountKey(path) => {
+            Some(policy.run(|| service_account_token(&client, path))?)
+        }
+    };
+
+    let url = format!(
+        "{}/{}/o/{}?alt=media",
+        GCS_DOWNLOAD_URL,
+        percent_encode(bucket),
+        percent_encode(object)
+    );
+
+    policy.run(|| {
+        let mut req = client.get(&url);
+        if let Some(token) = &token {
+            req = req.bearer_auth(token);
+        }
+        send(req)
+    })
+}
+
+/// Retrieves an access token for the VM's service account from the GCE
+/// metadata server.
+fn metadata_server_token(client: &Client) -> Result<String> {
+    let resp = send(
+        client
+            .get(METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google"),
+    )?;
+
+    parse_access_token(&resp)
+}
+
+/// Exchanges a JWT signed with the service account key at `key_path` for an
+/// access token.
+fn service_account_token(client: &Client, key_path: &Path) -> Result<String> {
+    // throw an error if the key file is a symlink
+    if key_path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            key_path.display()
+        )));
+    }
+
+    let key: ServiceAccountKey = serde_json::from_slice(&fs::read(key_path)?)
+        .map_err(|e| Error::ParseError(format!("Invalid service account key: {}", e)))?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::NotSupported(e.to_string()))?
+        .as_secs();
+    let assertion = sign_jwt_assertion(&key, now)?;
+
+    let body = format!(
+        "grant_type={}&assertion={}",
+        percent_encode(JWT_BEARER_GRANT_TYPE),
+        assertion
+    );
+    let resp = send(
+        client
+            .post(&key.token_uri)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body),
+    )?;
+
+    parse_access_token(&resp)
+}
+
+/// Creates an RS256-signed JWT assertion for the OAuth2 JWT bearer flow.
+fn sign_jwt_assertion(key: &ServiceAccountKey, issued_at: u64) -> Result<String> {
+    let header = serde_json::json!({"alg": "RS256", "typ": "JWT"});
+    let claims = serde_json::json!({
+        "iss": key.client_email,
+        "scope": GCS_READ_ONLY_SCOPE,
+        "aud": key.token_uri,
+        "iat": issued_at,
+        "exp": issued_at + JWT_LIFETIME_SECS,
+    });
+
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let pkey = PKey::private_key_from_pem(key.private_key.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+    signer.update(signing_input.as_bytes())?;
+    let signature = signer.sign_to_vec()?;
+
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+fn parse_access_token(resp: &[u8]) -> Result<String> {
+    let token: AccessToken = serde_json::from_slice(resp)
+        .map_err(|e| Error::ParseError(format!("Invalid access token response: {}", e)))?;
+
+    Ok(token.access_token)
+}
+
+/// Percent-encodes all characters outside of the URL unreserved set.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::rsa::Rsa;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("ovmf_x64_csm/tdx/ab01.binarypb"),
+            "ovmf_x64_csm%2Ftdx%2Fab01.binarypb"
+        );
+        assert_eq!(
+            percent_encode(JWT_BEARER_GRANT_TYPE),
+            "urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer"
+        );
+    }
+
+    #[test]
+    fn test_parse_access_token() -> Result<()> {
+        let resp = br#"{"access_token":"ya29.token","expires_in":3599,"token_type":"Bearer"}"#;
+        assert_eq!(parse_access_token(resp)?, "ya29.token");
+
+        assert!(parse_access_token(b"not json").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_jwt_assertion() -> Result<()> {
+        let rsa = Rsa::generate(2048)?;
+        let pkey = PKey::from_rsa(rsa)?;
+        let private_key = String::from_utf8(pkey.private_key_to_pem_pkcs8()?).unwrap();
+
+        let key = ServiceAccountKey {
+            client_email: "verifier@example.iam.gserviceaccount.com".to_string(),
+            private_key,
+            token_uri: "https://oauth2.googleapis.com/token".to_string(),
+        };
+
+        let assertion = sign_jwt_assertion(&key, 1_700_000_000)?;
+        let parts: Vec<&str> = assertion.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[1]).unwrap()).unwrap();
+        assert_eq!(claims["iss"], key.client_email);
+        assert_eq!(claims["exp"], 1_700_000_000 + JWT_LIFETIME_SECS);
+        Ok(())
+    }
+}
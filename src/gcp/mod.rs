@@ -4,199 +4,748 @@
 //! TDX VM _guests_ to verify the TDX attestations against expected values
 //! endorsed by GCP hosts.
 //!
-//! This module assumes that the `gcp:endorsement` module, which is created at
-//! build time from Google-provided protobufs, exists.
+//! Launch endorsements are parsed with the `endorsement` module, which is
+//! generated at build time from the vendored Google-provided protobufs.
 //!
 //! ## Example Usage
 //!
 //! ```no_run
 //! use tdx_workload_attestation::gcp::GcpTdxHost;
-//! use tdx_workload_attestation::host::TeeHost;
+//! use tdx_workload_attestation::host::{Evidence, TeeHost, VerificationContext};
 //!
-//! // Example host interface setup with dummy TDX MRTD value
-//! let mrtd = [0u8; 48];
-//! let host = GcpTdxHost::new(&mrtd).unwrap();
+//! let host = GcpTdxHost::new().unwrap();
 //!
-//! // Verify a TDX guest's MRTD against the GCP host's launch endorsement
-//! match host.verify_launch_endorsement() {
-//!     Ok(true) => println!("Launch endorsement is valid."),
-//!     Ok(false) => println!("Launch endorsement is invalid."),
+//! // Verify a TDX guest's MRTD (here a dummy value) against the GCP host's
+//! // launch endorsement
+//! let evidence = Evidence::new(&[0u8; 48]);
+//! match host.verify(&evidence, &VerificationContext::new()) {
+//!     Ok(verdict) if verdict.passed() => println!("Launch endorsement is valid."),
+//!     Ok(_) => println!("Launch endorsement is invalid."),
 //!     Err(e) => eprintln!("Error verifying launch endorsement: {}", e),
 //! }
 //! ```
+//!
+//! Network fetches are retried according to a `RetryPolicy`, which can be
+//! customized via `GcpTdxHost::builder()`:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tdx_workload_attestation::gcp::GcpTdxHost;
+//! use tdx_workload_attestation::retry::RetryPolicy;
+//!
+//! let host = GcpTdxHost::builder()
+//!     .retry_policy(RetryPolicy::default().with_max_attempts(5).with_timeout(Duration::from_secs(10)))
+//!     .build()
+//!     .unwrap();
+//! ```
+//!
+//! Downloaded endorsements are cached on disk (see the `cache` module), so
+//! that verification can work offline after the first successful fetch. The
+//! GCE root cert is a trust anchor, so it's never cached: unless it's among
+//! the builder's trust anchors, it's downloaded whenever a host is built.
+//! The cache location can be changed with `GcpTdxHostBuilder::cache_dir()`,
+//! or caching disabled with `GcpTdxHostBuilder::without_cache()`.
+//!
+//! By default, endorsements are fetched with the `gcloud` CLI. Environments
+//! that restrict the CLI can instead use the native GCS fetcher with
+//! `GcpTdxHostBuilder::gcs_auth()` (see the `gcs` module), or any other
+//! `EndorsementSource` (e.g., a local file) with
+//! `GcpTdxHostBuilder::endorsement_source()` (see the `source` module).
+//!
+//! If the evidence includes the TD's full attestation report or quote, the
+//! host also rejects debug TDs and checks the report's RTMRs against the
+//! context's reference values. If the context sets the TD's memory size, the
+//! MRTD must be endorsed for that memory configuration.
 
-mod endorsement;
+pub mod cache;
+pub mod endorsement;
+pub mod gcs;
+pub mod source;
 
 use crate::error::{Error, Result};
-use crate::host::TeeHost;
+use crate::evidence::Verdict;
+use crate::gcp::cache::EndorsementCache;
+use crate::gcp::endorsement::{LaunchEndorsement, VMGoldenMeasurement, VMLaunchEndorsement};
+use crate::gcp::gcs::GcsAuth;
+use crate::gcp::source::{EndorsementSource, GcsCli, GcsHttp};
+use crate::host::{Evidence, TeeHost, VerificationContext, appraise_report};
+use crate::retry::RetryPolicy;
 use crate::tdx::TDX_MR_REG_LEN;
+use crate::trust::{GCE_TCB_ROOT_URL, TrustAnchor, TrustAnchorKind, TrustAnchors};
 use crate::verification;
 
-use protobuf::Message;
-use reqwest;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::Path;
+
+// The name of the GCE Confidential Computing TCB root certificate
+const GCE_TCB_ROOT_CERT_NAME: &str = "GCE-cc-tcb-root_1.crt";
 
-/// Represents a GCP TDX host.
-///
-/// The `mrtd` field holds the MRTD (Measurement Register TD) obtained
-/// from an Intel TDX guest environment.
+/// Represents a GCP TDX host, which verifies the launch endorsements of its
+/// TDX guests.
 pub struct GcpTdxHost {
-    tcb_root_cert: Vec<u8>,
-    mrtd: [u8; TDX_MR_REG_LEN],
+    trust_anchors: TrustAnchors,
+    retry_policy: RetryPolicy,
+    cache: Option<EndorsementCache>,
+    source: Box<dyn EndorsementSource>,
 }
 
-impl GcpTdxHost {
-    /// Creates a new `GcpTdxHost` instance with the given guest MRTD.
+/// A builder for configuring a `GcpTdxHost`.
+pub struct GcpTdxHostBuilder {
+    retry_policy: RetryPolicy,
+    cache: Option<EndorsementCache>,
+    source: Box<dyn EndorsementSource>,
+    trust_anchors: TrustAnchors,
+}
+
+impl Default for GcpTdxHostBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GcpTdxHostBuilder {
+    /// Creates a new builder for a `GcpTdxHost` with the default
+    /// `RetryPolicy`, and the default cache directory (if any).
+    pub fn new() -> Self {
+        GcpTdxHostBuilder {
+            retry_policy: RetryPolicy::default(),
+            cache: EndorsementCache::default_dir().map(EndorsementCache::new),
+            source: Box::new(GcsCli),
+            trust_anchors: TrustAnchors::new(),
+        }
+    }
+
+    /// Sets the host's trust anchors for the launch endorsement's signing
+    /// cert, which are used if the verification context has no GCE TCB
+    /// roots.
     ///
-    /// Returns `Error::NetworkError` if the GCE root cert cannot be dowloaded.
-    pub fn new(mrtd_bytes: &[u8; TDX_MR_REG_LEN]) -> Result<GcpTdxHost> {
-        let root_cert_resp =
-            reqwest::blocking::get("https://pki.goog/cloud_integrity/GCE-cc-tcb-root_1.crt")
-                .map_err(|e| Error::NetworkError(e.without_url().to_string()))?;
-        let root_cert = root_cert_resp
-            .bytes()
-            .map_err(|e| Error::NetworkError(e.without_url().to_string()))?;
+    /// If they include a GCE TCB root, it's used instead of the cached or
+    /// downloaded one.
+    pub fn trust_anchors(mut self, anchors: TrustAnchors) -> Self {
+        self.trust_anchors = anchors;
+        self
+    }
+
+    /// Sets how launch endorsement requests to GCS are authenticated, i.e.,
+    /// fetches endorsements with the `GcsCli` source for
+    /// `GcsAuth::GcloudCli`, and with the `GcsHttp` source otherwise.
+    ///
+    /// Defaults to `GcsAuth::GcloudCli`.
+    pub fn gcs_auth(self, auth: GcsAuth) -> Self {
+        match auth {
+            GcsAuth::GcloudCli => self.endorsement_source(GcsCli),
+            auth => self.endorsement_source(GcsHttp::new(auth)),
+        }
+    }
+
+    /// Sets the source of launch endorsements.
+    ///
+    /// Defaults to `GcsCli`.
+    pub fn endorsement_source<S: EndorsementSource + 'static>(mut self, source: S) -> Self {
+        self.source = Box::new(source);
+        self
+    }
+
+    /// Sets the retry and timeout policy used for the root cert download and
+    /// the launch endorsement retrieval.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Sets the directory used to cache downloaded endorsements.
+    pub fn cache_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.cache = Some(EndorsementCache::new(dir));
+        self
+    }
+
+    /// Disables on-disk caching, so every verification fetches the
+    /// endorsement from GCP.
+    pub fn without_cache(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+
+    /// Builds the `GcpTdxHost`, loading the GCE root cert from the trust
+    /// anchors, or downloading it.
+    ///
+    /// The downloaded root cert is not cached, as the cache cannot protect
+    /// it from being replaced on disk.
+    ///
+    /// Returns `Error::NetworkError` if the GCE root cert is not among the
+    /// trust anchors, and cannot be dowloaded.
+    pub fn build(self) -> Result<GcpTdxHost> {
+        let mut trust_anchors = self.trust_anchors;
+
+        if !trust_anchors.has_roots(TrustAnchorKind::GceTcbRoot) {
+            let root_cert = self
+                .retry_policy
+                .run(|| download(GCE_TCB_ROOT_URL, &self.retry_policy))?;
+
+            trust_anchors.add(TrustAnchor::new(
+                TrustAnchorKind::GceTcbRoot,
+                GCE_TCB_ROOT_CERT_NAME,
+                &root_cert,
+            ));
+        }
 
         Ok(GcpTdxHost {
-            tcb_root_cert: root_cert.to_vec(),
-            mrtd: *mrtd_bytes,
+            trust_anchors,
+            retry_policy: self.retry_policy,
+            cache: self.cache,
+            source: self.source,
         })
     }
+}
+
+impl GcpTdxHost {
+    /// Creates a new `GcpTdxHost` instance.
+    ///
+    /// Returns `Error::NetworkError` if the GCE root cert cannot be dowloaded.
+    pub fn new() -> Result<GcpTdxHost> {
+        GcpTdxHostBuilder::new().build()
+    }
+
+    /// Creates a new `GcpTdxHost` instance, which retrieves launch
+    /// endorsements from `source`.
+    ///
+    /// Returns `Error::NetworkError` if the GCE root cert cannot be dowloaded.
+    pub fn new_with_source<S: EndorsementSource + 'static>(source: S) -> Result<GcpTdxHost> {
+        GcpTdxHostBuilder::new().endorsement_source(source).build()
+    }
 
-    fn retrieve_launch_endorsement(&self) -> Result<endorsement::VMLaunchEndorsement> {
-        // Make sure the GCP CLI is installed
-        let which_cmd = Command::new("which")
-            .arg("gcloud")
-            .output()
-            .expect("failed to execute which command");
+    /// Returns a builder for configuring a `GcpTdxHost`.
+    pub fn builder() -> GcpTdxHostBuilder {
+        GcpTdxHostBuilder::new()
+    }
+
+    /// Retrieves the raw (serialized) launch endorsement for the guest MRTD
+    /// `mrtd`, from the cache or the host's `EndorsementSource`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the source's error if the endorsement cannot be retrieved
+    /// (e.g., an `Error::NetworkError` after exhausting the host's
+    /// `RetryPolicy`), or an `Error::ProtobufError` if it cannot be parsed.
+    pub fn launch_endorsement(&self, mrtd: &[u8; TDX_MR_REG_LEN]) -> Result<Vec<u8>> {
+        let cache_key = format!("{}.binarypb", hex::encode(mrtd));
 
-        if which_cmd.stdout.is_empty() {
-            return Err(Error::NotSupported("gcloud command not found".to_string()));
+        // Endorsements are immutable per MRTD, so try the cache first
+        if let Some(cache) = &self.cache
+            && let Some(bytes) = cache.get(&cache_key)?
+        {
+            match LaunchEndorsement::parse(&bytes) {
+                Ok(_) => return Ok(bytes),
+                // evict the unparseable entry and fall back to fetching
+                Err(_) => cache.remove(&cache_key)?,
+            }
         }
 
-        let gcloud_cli_path = PathBuf::from(
-            String::from_utf8(which_cmd.stdout)
-                .map_err(|e| Error::ParseError(e.to_string()))?
-                .trim_end_matches('\n'),
-        );
+        let raw_endorsement = self.source.fetch(mrtd, &self.retry_policy)?;
+        LaunchEndorsement::parse(&raw_endorsement)?;
 
-        // Insert the MRTD as hex-encoded string into the URL to retrieve the endorsement
-        let storage_url = format!(
-            "gs://gce_tcb_integrity/ovmf_x64_csm/tdx/{}.binarypb",
-            hex::encode(self.mrtd)
-        );
+        if let Some(cache) = &self.cache {
+            // caching is best-effort, so don't fail if the cache isn't writable
+            let _ = cache.put(&cache_key, &raw_endorsement);
+        }
+
+        Ok(raw_endorsement)
+    }
+
+    /// Verifies the signing cert of the launch endorsement against the GCE
+    /// TCB roots, and the endorsement's signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::SignatureError` if the certificate or signature
+    /// verification fails.
+    fn verify_launch_endorsement_signature(
+        &self,
+        launch_endorsement: &LaunchEndorsement,
+        context: &VerificationContext,
+    ) -> Result<()> {
+        let uefi_golden = &launch_endorsement.golden;
+
+        // Check signature on the endorsement
+        let valid_cert = self.verify_launch_endorsement_signing_cert(uefi_golden, context)?;
 
-        let output = Command::new(gcloud_cli_path)
-            .arg("storage")
-            .arg("cat")
-            .arg(storage_url)
-            .output()
-            .map_err(Error::IoError)?;
-
-        if !output.status.success() {
-            return Err(Error::NetworkError(format!(
-                "failed to retrieve GCP launch endorsement for TD verification: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+        if !valid_cert {
+            return Err(Error::SignatureError(
+                "Invalid launch endorsement signing cert".to_string(),
+            ));
         }
 
-        let endorsement = endorsement::VMLaunchEndorsement::parse_from_bytes(&output.stdout)
-            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        let valid_sig = GcpTdxHost::verify_launch_endorsement_sig(
+            &launch_endorsement.endorsement,
+            &uefi_golden.cert,
+        )?;
 
-        Ok(endorsement)
+        if !valid_sig {
+            return Err(Error::SignatureError(
+                "Invalid launch endorsement signature".to_string(),
+            ));
+        }
+
+        Ok(())
     }
 
     fn verify_launch_endorsement_signing_cert(
         &self,
-        golden: &endorsement::VMGoldenMeasurement,
+        golden: &VMGoldenMeasurement,
+        context: &VerificationContext,
     ) -> Result<bool> {
-        let gcp_root_cert = verification::x509::x509_from_der_bytes(self.tcb_root_cert.as_slice())?;
         let signing_cert = verification::x509::x509_from_der_bytes(&golden.cert)?;
+        let unix_time = context.verification_time()?;
+
+        // Prefer the verifier's roots over the host's
+        let trust_anchors = if context.trust_anchors.has_roots(TrustAnchorKind::GceTcbRoot) {
+            &context.trust_anchors
+        } else {
+            &self.trust_anchors
+        };
 
-        verification::x509::verify_x509_cert(&signing_cert, &gcp_root_cert)
+        trust_anchors
+            .verify_any(TrustAnchorKind::GceTcbRoot, |root| {
+                let gcp_root_cert = verification::x509::x509_from_der_bytes(&root.der)?;
+                verification::x509::verify_x509_cert_at(&signing_cert, &gcp_root_cert, unix_time)
+            })
+            .unwrap_or_else(|| {
+                Err(Error::VerificationError(
+                    "No trusted GCE TCB root certificate".to_string(),
+                ))
+            })
     }
 
     fn verify_launch_endorsement_sig(
-        endorsement: &endorsement::VMLaunchEndorsement,
-        signing_cert: Vec<u8>,
+        endorsement: &VMLaunchEndorsement,
+        signing_cert: &[u8],
     ) -> Result<bool> {
-        let cert_x509 = verification::x509::x509_from_der_bytes(&signing_cert)?;
-
-        let signing_key = verification::x509::get_x509_pubkey(&cert_x509)?;
+        let cert_x509 = verification::x509::x509_from_der_bytes(signing_cert)?;
 
-        verification::signature::verify_signature_sha256_rsa_pss(
+        // GCP signs with SHA-256 RSA-PSS, but the algorithm is derived from
+        // the signing cert, so that algorithm migrations don't break
+        // verification
+        verification::signature::verify_signature(
             &endorsement.serialized_uefi_golden,
             &endorsement.signature,
-            &signing_key,
+            &cert_x509,
         )
     }
 }
 
+/// Downloads the resource at `url`, applying the policy's per-request timeout.
+fn download(url: &str, policy: &RetryPolicy) -> Result<Vec<u8>> {
+    let client = crate::http::http_client(policy)?;
+    crate::http::send(client.get(url))
+}
+
 impl TeeHost for GcpTdxHost {
-    /// Verifies the GCP launch endorsement for the current TDX guest.
+    /// Verifies the TDX guest's evidence against its GCP launch endorsement,
+    /// with the checks:
+    /// - `endorsement`: the endorsement's signing certificate chains up to
+    ///   Google's root cert (the context's, or else the host's), and its
+    ///   signature is valid (with the algorithm derived from the signing
+    ///   certificate's signature algorithm and key, see
+    ///   `verification::signature::SignatureAlgorithm::from_certificate()`).
+    /// - `mrtd`: the endorsement endorses the guest's MRTD (for the context's
+    ///   memory size, if set).
+    /// - `firmware-svn` (if the context has a minimum firmware SVN): the
+    ///   endorsed firmware's SVN is at least the minimum.
+    /// - `endorsement-age` (if the context has a maximum endorsement age):
+    ///   the endorsement's timestamp is at most that old at the verification
+    ///   time.
+    /// - `revocation` (if the context has revoked firmware digests): the
+    ///   endorsed firmware's digest isn't revoked.
+    /// - `debug` and `reference-values`: the guest's report matches the
+    ///   context's policy (see the `host` module).
     ///
-    /// This method performs the following steps:
-    /// 1. Retrieves the TDX guest's launch endorsement from GCP storage.
-    /// 2. Verifies the signing certificate of the endorsement against Google's
-    ///    root cert.
-    /// 3. Verifies the signature on the endorsement.
-    /// 4. Compares the endorsed MRTD with the guest's MRTD.
+    /// The endorsement is the evidence's, if any, or else retrieved from the
+    /// cache or the host's `EndorsementSource`.
     ///
     /// # Errors
     ///
-    /// - `Error::NetworkError` if the endorsement cannot be retrieved.
-    /// - `Error::ParseError` if the endorsement or golden measurement cannot be
-    ///   parsed.
-    /// - `Error::SignatureError` if the certificate or signature verification
-    ///   fails.
+    /// - `Error::NetworkError` if the endorsement cannot be retrieved after
+    ///   exhausting the host's `RetryPolicy`, or the source's error otherwise.
+    /// - `Error::ProtobufError` if the endorsement or golden measurement cannot
+    ///   be parsed.
+    /// - `Error::ParseError` if the expected TDX measurements are missing, or
+    ///   `Error::NotSupported` if they're missing from an endorsement with a
+    ///   newer schema (see the `endorsement` module).
     ///
     /// # Note
     ///
-    /// This method calls an internal function that uses the GCP CLI (`gcloud`)
-    /// to fetch the launch endorsement from GCP storage, and assumes is being
-    /// run from within an Intel TDX guest environment on GCP (needed for
-    /// authentication).
-    fn verify_launch_endorsement(&self) -> Result<bool> {
-        // get the launch endorsement
-        let launch_endorsement = self.retrieve_launch_endorsement()?;
-
+    /// By default, this method uses the GCP CLI (`gcloud`) to fetch the launch
+    /// endorsement from GCP storage, and assumes is being run from within an
+    /// Intel TDX guest environment on GCP (needed for authentication). See
+    /// `GcpTdxHostBuilder::gcs_auth()` and
+    /// `GcpTdxHostBuilder::endorsement_source()` for alternatives.
+    fn verify(&self, evidence: &Evidence, context: &VerificationContext) -> Result<Verdict> {
+        let raw_endorsement = match evidence.endorsement() {
+            Some(endorsement) => endorsement.to_vec(),
+            None => self.launch_endorsement(evidence.mrtd())?,
+        };
         // The MRTD is the GCP endorsement is within the UEFI golden measurement
-        let uefi_golden = endorsement::VMGoldenMeasurement::parse_from_bytes(
-            &launch_endorsement.serialized_uefi_golden,
-        )
-        .map_err(|e| Error::ParseError(e.to_string()))?;
+        let launch_endorsement = LaunchEndorsement::parse(&raw_endorsement)?;
+        let mut verdict = Verdict::default();
 
-        // Check signature on the endorsement
-        let valid_cert = self.verify_launch_endorsement_signing_cert(&uefi_golden)?;
+        match self.verify_launch_endorsement_signature(&launch_endorsement, context) {
+            Ok(()) => verdict.pass("endorsement"),
+            Err(e) => verdict.fail("endorsement", &e.to_string()),
+        }
 
-        if !valid_cert {
-            return Err(Error::SignatureError(
-                "Invalid launch endorsement signing cert".to_string(),
-            ));
+        // The endorsed MRTDs will be within the golden value's TDX measurements structs
+        if launch_endorsement.endorses(evidence.mrtd(), context.memory_gib)? {
+            verdict.pass("mrtd");
+        } else {
+            verdict.fail("mrtd", "Endorsement does not match MRTD");
         }
 
-        let valid_sig =
-            GcpTdxHost::verify_launch_endorsement_sig(&launch_endorsement, uefi_golden.cert)?;
+        if let Some(min_svn) = context.min_firmware_svn {
+            let svn = launch_endorsement.firmware_svn()?;
+            if svn >= min_svn {
+                verdict.pass("firmware-svn");
+            } else {
+                verdict.fail(
+                    "firmware-svn",
+                    &format!(
+                        "Endorsed firmware SVN {} is below the minimum {}",
+                        svn, min_svn
+                    ),
+                );
+            }
+        }
 
-        if !valid_sig {
-            return Err(Error::SignatureError(
-                "Invalid launch endorsement signature".to_string(),
-            ));
+        if let Some(max_age) = context.max_endorsement_age {
+            let now = context.verification_time()?;
+            match launch_endorsement.issued_at() {
+                Some(issued_at) if issued_at > now => {
+                    verdict.fail("endorsement-age", "Endorsement is issued in the future")
+                }
+                Some(issued_at) if now - issued_at > max_age.as_secs() => verdict.fail(
+                    "endorsement-age",
+                    &format!(
+                        "Endorsement is {} seconds old, more than the maximum {}",
+                        now - issued_at,
+                        max_age.as_secs()
+                    ),
+                ),
+                Some(_) => verdict.pass("endorsement-age"),
+                None => verdict.fail("endorsement-age", "Endorsement has no timestamp"),
+            }
         }
 
-        // The endorsed MRTD will be within the golden value's TDX measurements structs
-        if uefi_golden.tdx.is_none()
-            || uefi_golden.tdx.measurements.is_empty()
-            || uefi_golden.tdx.measurements[0].mrtd.is_empty()
-        {
-            return Err(Error::ParseError(
-                "Expected TDX measurement structure missing".to_string(),
-            ));
+        if !context.revoked_firmware_digests.is_empty() {
+            let digest = launch_endorsement.firmware_digest();
+            if context
+                .revoked_firmware_digests
+                .iter()
+                .any(|revoked| revoked.as_ref() == digest)
+            {
+                verdict.fail(
+                    "revocation",
+                    &format!("Endorsement of firmware {} is revoked", hex::encode(digest)),
+                );
+            } else {
+                verdict.pass("revocation");
+            }
         }
-        let endorsed_mrtd = uefi_golden.tdx.measurements[0].mrtd.as_slice();
 
-        // Finally, we compare the two MRTD values
-        Ok(endorsed_mrtd == self.mrtd)
+        appraise_report(evidence, context, &mut verdict);
+
+        Ok(verdict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::TdReportV15;
+    use crate::gcp::source::InMemory;
+    use crate::test_utils::cert_builder;
+    use crate::verification::signature::tests::{sign, sign_cert};
+    use crate::verification::signature::{HashAlgorithm, SignatureAlgorithm};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509;
+    use protobuf::Message;
+    use std::time::Duration;
+
+    const MRTD: [u8; TDX_MR_REG_LEN] = [0xaa; TDX_MR_REG_LEN];
+
+    fn make_cert(
+        subject: &str,
+        issuer: &str,
+        key: &PKey<Private>,
+        sign_key: &PKey<Private>,
+        algorithm: SignatureAlgorithm,
+    ) -> X509 {
+        sign_cert(cert_builder(subject, issuer, key, 5), sign_key, algorithm)
+    }
+
+    // Returns a GCE TCB root cert, and an endorsement of `mrtd` signed by it,
+    // with SHA-256 RSA-PSS
+    fn make_endorsement(mrtd: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let signing_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        make_endorsement_with(
+            mrtd,
+            &signing_key,
+            SignatureAlgorithm::RsaPss(HashAlgorithm::Sha256),
+        )
+    }
+
+    // Returns a GCE TCB root cert, and an endorsement of `mrtd` signed with
+    // `signing_key` and `algorithm`, by a signer whose cert the root signs
+    // with the same algorithm
+    fn make_endorsement_with(
+        mrtd: &[u8],
+        signing_key: &PKey<Private>,
+        algorithm: SignatureAlgorithm,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let root_key = match signing_key.ec_key() {
+            Ok(ec_key) => PKey::from_ec_key(EcKey::generate(ec_key.group()).unwrap()).unwrap(),
+            Err(_) => PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(),
+        };
+        let root_cert = make_cert(
+            "GCE TCB root",
+            "GCE TCB root",
+            &root_key,
+            &root_key,
+            algorithm,
+        );
+        let signing_cert = make_cert(
+            "GCE TCB signer",
+            "GCE TCB root",
+            signing_key,
+            &root_key,
+            algorithm,
+        );
+
+        let mut golden = VMGoldenMeasurement::new();
+        golden.cert = signing_cert.to_der().unwrap();
+        golden.tdx.mut_or_insert_default().svn = 3;
+        golden.timestamp.mut_or_insert_default().seconds = 1_700_000_000;
+        golden.digest = vec![0xdd; TDX_MR_REG_LEN];
+        let measurements = &mut golden.tdx.mut_or_insert_default().measurements;
+        measurements.push(Default::default());
+        measurements[0].ram_gib = 4;
+        measurements[0].mrtd = mrtd.to_vec();
+        let serialized_uefi_golden = golden.write_to_bytes().unwrap();
+
+        let signature = sign(signing_key, algorithm, &serialized_uefi_golden);
+
+        let endorsement = VMLaunchEndorsement {
+            serialized_uefi_golden,
+            signature,
+            ..Default::default()
+        };
+        (
+            root_cert.to_der().unwrap(),
+            endorsement.write_to_bytes().unwrap(),
+        )
+    }
+
+    fn make_host(source: InMemory) -> GcpTdxHost {
+        GcpTdxHost::builder()
+            .trust_anchors(TrustAnchors::new().with_anchor(TrustAnchorKind::GceTcbRoot, &[0; 4]))
+            .endorsement_source(source)
+            .without_cache()
+            .build()
+            .unwrap()
+    }
+
+    fn make_context(root_cert: &[u8]) -> VerificationContext {
+        VerificationContext::new().with_trust_anchors(
+            TrustAnchors::new().with_anchor(TrustAnchorKind::GceTcbRoot, root_cert),
+        )
+    }
+
+    fn failed_checks(verdict: &Verdict) -> Vec<&str> {
+        verdict
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_launch_endorsement() -> Result<()> {
+        let (root_cert, endorsement) = make_endorsement(&MRTD);
+        let context = make_context(&root_cert);
+        let host = make_host(InMemory::new().with_endorsement(&MRTD, endorsement.clone()));
+        assert_eq!(host.launch_endorsement(&MRTD)?, endorsement);
+        assert!(host.verify(&Evidence::new(&MRTD), &context)?.passed());
+
+        // an endorsement of a different MRTD doesn't match the guest's
+        let other_mrtd = [0xbb; TDX_MR_REG_LEN];
+        let evidence = Evidence::new(&other_mrtd).with_endorsement(&endorsement);
+        let verdict = host.verify(&evidence, &context)?;
+        assert_eq!(failed_checks(&verdict), ["mrtd"]);
+
+        // a missing endorsement cannot be retrieved
+        assert!(host.verify(&Evidence::new(&other_mrtd), &context).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_launch_endorsement_algorithms() -> Result<()> {
+        use openssl::nid::Nid;
+
+        // SHA-384 RSA PKCS#1 v1.5
+        let rsa_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let pkcs1 = make_endorsement_with(
+            &MRTD,
+            &rsa_key,
+            SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha384),
+        );
+
+        // ECDSA P-384
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let ec_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let ecdsa = make_endorsement_with(
+            &MRTD,
+            &ec_key,
+            SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha384),
+        );
+
+        let host = make_host(InMemory::new());
+        for (root_cert, endorsement) in [pkcs1, ecdsa] {
+            let evidence = Evidence::new(&MRTD).with_endorsement(&endorsement);
+            let verdict = host.verify(&evidence, &make_context(&root_cert))?;
+            assert!(verdict.passed(), "{:?}", verdict);
+
+            // a tampered signature is still rejected
+            let mut tampered = VMLaunchEndorsement::parse_from_bytes(&endorsement).unwrap();
+            let last = tampered.signature.len() - 1;
+            tampered.signature[last] ^= 1;
+            let evidence =
+                Evidence::new(&MRTD).with_endorsement(&tampered.write_to_bytes().unwrap());
+            let verdict = host.verify(&evidence, &make_context(&root_cert))?;
+            assert_eq!(failed_checks(&verdict), ["endorsement"]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_launch_endorsement_untrusted() -> Result<()> {
+        let (root_cert, endorsement) = make_endorsement(&MRTD);
+        let host = make_host(InMemory::new());
+
+        // a tampered signature is rejected
+        let mut tampered = VMLaunchEndorsement::parse_from_bytes(&endorsement).unwrap();
+        tampered.signature[0] ^= 1;
+        let evidence = Evidence::new(&MRTD).with_endorsement(&tampered.write_to_bytes().unwrap());
+        let verdict = host.verify(&evidence, &make_context(&root_cert))?;
+        assert_eq!(failed_checks(&verdict), ["endorsement"]);
+
+        // an endorsement signed by an untrusted root is rejected
+        let (other_root_cert, _) = make_endorsement(&MRTD);
+        let evidence = Evidence::new(&MRTD).with_endorsement(&endorsement);
+        let verdict = host.verify(&evidence, &make_context(&other_root_cert))?;
+        assert_eq!(failed_checks(&verdict), ["endorsement"]);
+
+        // as is an endorsement whose signing cert expired at verification time
+        let context = make_context(&root_cert).with_verification_time(1_000_000_000);
+        let verdict = host.verify(&evidence, &context)?;
+        assert_eq!(failed_checks(&verdict), ["endorsement"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_launch_report() -> Result<()> {
+        let (root_cert, endorsement) = make_endorsement(&MRTD);
+        let host = make_host(InMemory::new().with_endorsement(&MRTD, endorsement));
+        let evidence = |td_attributes: [u8; 8]| {
+            let tdreport = TdReportV15::builder()
+                .with_td_attributes(&td_attributes, &[0; 8])
+                .with_mrtd(&MRTD)
+                .with_rtmrs(&[[1; TDX_MR_REG_LEN]; 4])
+                .build();
+            Evidence::from_report(&serde_json::to_vec(&tdreport).unwrap()).unwrap()
+        };
+        let reference_values = crate::measure::ReferenceValues {
+            rtmr0: Some([1; TDX_MR_REG_LEN].into()),
+            ..Default::default()
+        };
+
+        let context = make_context(&root_cert)
+            .with_reference_values(reference_values.clone())
+            .with_memory_gib(4);
+        let verdict = host.verify(&evidence([0; 8]), &context)?;
+        assert!(verdict.passed());
+        assert!(verdict.check("debug").is_some());
+
+        // debug TDs are rejected unless allowed
+        let debug_evidence = evidence([1, 0, 0, 0, 0, 0, 0, 0]);
+        let verdict = host.verify(&debug_evidence, &context)?;
+        assert_eq!(failed_checks(&verdict), ["debug"]);
+        let context = context.allow_debug(true);
+        assert!(host.verify(&debug_evidence, &context)?.passed());
+
+        // the RTMRs must match the reference values, and the MRTD must be
+        // endorsed for the TD's memory configuration
+        let context = context
+            .with_reference_values(crate::measure::ReferenceValues {
+                rtmr2: Some([2; TDX_MR_REG_LEN].into()),
+                ..reference_values
+            })
+            .with_memory_gib(8);
+        let verdict = host.verify(&evidence([0; 8]), &context)?;
+        assert_eq!(failed_checks(&verdict), ["mrtd", "reference-values"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_firmware_svn() -> Result<()> {
+        let (root_cert, endorsement) = make_endorsement(&MRTD);
+        let host = make_host(InMemory::new().with_endorsement(&MRTD, endorsement));
+        let evidence = Evidence::new(&MRTD);
+
+        let context = make_context(&root_cert).with_min_firmware_svn(3);
+        let verdict = host.verify(&evidence, &context)?;
+        assert!(verdict.passed());
+        assert!(verdict.check("firmware-svn").is_some());
+
+        // validly endorsed, but older firmware is rejected
+        let context = make_context(&root_cert).with_min_firmware_svn(4);
+        let verdict = host.verify(&evidence, &context)?;
+        assert_eq!(failed_checks(&verdict), ["firmware-svn"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_endorsement_age_and_revocation() -> Result<()> {
+        let (root_cert, endorsement) = make_endorsement(&MRTD);
+        let host = make_host(InMemory::new().with_endorsement(&MRTD, endorsement));
+        let evidence = Evidence::new(&MRTD);
+        // the endorsement is issued at 1_700_000_000
+        let context = |now: u64| {
+            make_context(&root_cert)
+                .with_verification_time(now)
+                .with_max_endorsement_age(Duration::from_secs(100))
+        };
+
+        // the signing cert is checked at the verification time too
+        let checks = |now| -> Result<Vec<String>> {
+            Ok(host
+                .verify(&evidence, &context(now))?
+                .checks
+                .into_iter()
+                .filter(|check| check.name != "endorsement" && !check.passed)
+                .map(|check| check.name)
+                .collect())
+        };
+        assert!(checks(1_700_000_100)?.is_empty());
+        assert_eq!(checks(1_700_000_101)?, ["endorsement-age"]);
+        assert_eq!(checks(1_699_999_999)?, ["endorsement-age"]);
+
+        let context =
+            make_context(&root_cert).with_revoked_firmware_digest([0xee; TDX_MR_REG_LEN].into());
+        let verdict = host.verify(&evidence, &context)?;
+        assert!(verdict.passed());
+        let context = context.with_revoked_firmware_digest([0xdd; TDX_MR_REG_LEN].into());
+        let verdict = host.verify(&evidence, &context)?;
+        assert_eq!(failed_checks(&verdict), ["revocation"]);
+        Ok(())
     }
 }
@@ -0,0 +1,269 @@
+//! # Launch Endorsement Sources
+//!
+//! This module abstracts the retrieval of GCP launch endorsements behind the
+//! `EndorsementSource` trait, so that a `GcpTdxHost` can fetch them from
+//! Google Cloud Storage (GCS) with the `gcloud` CLI (`GcsCli`) or the native
+//! HTTP client (`GcsHttp`), read them from disk (`LocalFile`), e.g., in
+//! air-gapped environments, or serve canned ones (`InMemory`), e.g., in tests.
+//!
+//! Sources only retrieve endorsements: the `GcpTdxHost` parses, caches and
+//! verifies them.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::gcp::GcpTdxHost;
+//! use tdx_workload_attestation::gcp::source::LocalFile;
+//!
+//! let host = GcpTdxHost::new_with_source(LocalFile::new("endorsement.binarypb")).unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::gcp::gcs::{self, GcsAuth};
+use crate::retry::RetryPolicy;
+use crate::tdx::TDX_MR_REG_LEN;
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The GCS bucket holding the GCE TCB launch endorsements.
+pub const GCE_TCB_INTEGRITY_BUCKET: &str = "gce_tcb_integrity";
+
+// How often to poll a running `gcloud` command for completion
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the name of the GCS object holding the launch endorsement of the
+/// firmware with the given MRTD.
+pub fn endorsement_object(mrtd: &[u8; TDX_MR_REG_LEN]) -> String {
+    // Insert the MRTD as hex-encoded string into the object name of the endorsement
+    format!("ovmf_x64_csm/tdx/{}.binarypb", hex::encode(mrtd))
+}
+
+/// A source of raw (serialized) launch endorsements.
+pub trait EndorsementSource: Send + Sync {
+    /// Retrieves the raw launch endorsement of the firmware with the given
+    /// MRTD, retrying transient failures according to `policy`.
+    fn fetch(&self, mrtd: &[u8; TDX_MR_REG_LEN], policy: &RetryPolicy) -> Result<Vec<u8>>;
+}
+
+/// Fetches endorsements from GCS with the `gcloud` CLI, and the credentials
+/// it is configured with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GcsCli;
+
+impl EndorsementSource for GcsCli {
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the `gcloud` CLI isn't installed,
+    /// or an `Error::NetworkError` if the endorsement cannot be retrieved
+    /// after exhausting the `RetryPolicy`.
+    fn fetch(&self, mrtd: &[u8; TDX_MR_REG_LEN], policy: &RetryPolicy) -> Result<Vec<u8>> {
+        // Make sure the GCP CLI is installed
+        let which_cmd = Command::new("which")
+            .arg("gcloud")
+            .output()
+            .expect("failed to execute which command");
+
+        if which_cmd.stdout.is_empty() {
+            return Err(Error::NotSupported("gcloud command not found".to_string()));
+        }
+
+        let gcloud_cli_path = PathBuf::from(
+            String::from_utf8(which_cmd.stdout)
+                .map_err(|e| Error::ParseError(e.to_string()))?
+                .trim_end_matches('\n'),
+        );
+
+        let storage_url = format!(
+            "gs://{}/{}",
+            GCE_TCB_INTEGRITY_BUCKET,
+            endorsement_object(mrtd)
+        );
+
+        let output = policy.run(|| {
+            let mut cmd = Command::new(&gcloud_cli_path);
+            cmd.arg("storage").arg("cat").arg(&storage_url);
+            let output = output_with_timeout(cmd, policy.timeout())?;
+
+            if !output.status.success() {
+                return Err(Error::NetworkError(format!(
+                    "failed to retrieve GCP launch endorsement for TD verification: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+            Ok(output)
+        })?;
+
+        Ok(output.stdout)
+    }
+}
+
+/// Fetches endorsements from GCS with the native HTTP client (see the `gcs`
+/// module).
+#[derive(Clone, Debug)]
+pub struct GcsHttp {
+    auth: GcsAuth,
+}
+
+impl GcsHttp {
+    /// Creates a source authenticating its requests with `auth`, which must
+    /// not be `GcsAuth::GcloudCli` (see `GcsCli`).
+    pub fn new(auth: GcsAuth) -> Self {
+        Self { auth }
+    }
+}
+
+impl EndorsementSource for GcsHttp {
+    /// See `gcs::fetch_object()` for the errors.
+    fn fetch(&self, mrtd: &[u8; TDX_MR_REG_LEN], policy: &RetryPolicy) -> Result<Vec<u8>> {
+        gcs::fetch_object(
+            GCE_TCB_INTEGRITY_BUCKET,
+            &endorsement_object(mrtd),
+            &self.auth,
+            policy,
+        )
+    }
+}
+
+/// Reads the endorsement from a local file, whatever the MRTD (a mismatched
+/// endorsement fails verification).
+#[derive(Clone, Debug)]
+pub struct LocalFile {
+    path: PathBuf,
+}
+
+impl LocalFile {
+    /// Creates a source reading the endorsement at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl EndorsementSource for LocalFile {
+    /// Returns an `Error::IoError` if the file cannot be read.
+    fn fetch(&self, _mrtd: &[u8; TDX_MR_REG_LEN], _policy: &RetryPolicy) -> Result<Vec<u8>> {
+        Ok(std::fs::read(&self.path)?)
+    }
+}
+
+/// Serves canned endorsements, by MRTD.
+#[derive(Clone, Debug, Default)]
+pub struct InMemory {
+    endorsements: HashMap<[u8; TDX_MR_REG_LEN], Vec<u8>>,
+}
+
+impl InMemory {
+    /// Creates a source without endorsements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the raw endorsement of the firmware with the given MRTD.
+    pub fn with_endorsement(mut self, mrtd: &[u8; TDX_MR_REG_LEN], endorsement: Vec<u8>) -> Self {
+        self.endorsements.insert(*mrtd, endorsement);
+        self
+    }
+}
+
+impl EndorsementSource for InMemory {
+    /// Returns an `Error::NotSupported` if there is no endorsement for `mrtd`.
+    fn fetch(&self, mrtd: &[u8; TDX_MR_REG_LEN], _policy: &RetryPolicy) -> Result<Vec<u8>> {
+        self.endorsements.get(mrtd).cloned().ok_or_else(|| {
+            Error::NotSupported(format!(
+                "No launch endorsement for MRTD {}",
+                hex::encode(mrtd)
+            ))
+        })
+    }
+}
+
+/// Runs `cmd` to completion and collects its output, killing it if it runs
+/// longer than `timeout`.
+fn output_with_timeout(mut cmd: Command, timeout: Option<Duration>) -> Result<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain the pipes on separate threads so the child can't block on a full pipe
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stderr.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|t| start.elapsed() >= t) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::NetworkError(format!(
+                "command timed out after {:?}",
+                start.elapsed()
+            )));
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+
+    let stdout = stdout_reader
+        .join()
+        .map_err(|_| Error::NetworkError("failed to read command output".to_string()))??;
+    let stderr = stderr_reader
+        .join()
+        .map_err(|_| Error::NetworkError("failed to read command output".to_string()))??;
+
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_sources() -> Result<()> {
+        let policy = RetryPolicy::default();
+        let source =
+            InMemory::new().with_endorsement(&[1; TDX_MR_REG_LEN], b"endorsement".to_vec());
+        assert_eq!(source.fetch(&[1; TDX_MR_REG_LEN], &policy)?, b"endorsement");
+        assert!(
+            source
+                .fetch(&[2; TDX_MR_REG_LEN], &policy)
+                .is_err_and(|e| e.is_not_supported())
+        );
+
+        let path = std::env::temp_dir().join(format!("tdx-endorsement-{}", rand::random::<u64>()));
+        let source = LocalFile::new(&path);
+        assert!(source.fetch(&[1; TDX_MR_REG_LEN], &policy).is_err());
+        std::fs::write(&path, b"endorsement")?;
+        assert_eq!(source.fetch(&[1; TDX_MR_REG_LEN], &policy)?, b"endorsement");
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_endorsement_object() {
+        assert_eq!(
+            endorsement_object(&[0xab; TDX_MR_REG_LEN]),
+            format!("ovmf_x64_csm/tdx/{}.binarypb", "ab".repeat(TDX_MR_REG_LEN))
+        );
+    }
+}
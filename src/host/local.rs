@@ -0,0 +1,407 @@
+//! # Local QEMU/KVM Host Interface for Intel TDX Guests
+//!
+//! This module uses the `TeeHost` trait to implement an interface for Intel
+//! TDX VM guests of self-hosted QEMU/KVM deployments, which have no cloud
+//! provider to endorse their launch measurement. Instead, the guest's `MRTD`
+//! is verified against a reference value computed from the operator-provided
+//! TDVF firmware image (see the `measure::predict` module) and the TD's
+//! configuration (its vCPUs and memory). If the operator also sets the
+//! expected RTMRs of the TD's boot chain (e.g., predicted from its kernel and
+//! initrd), the TD's RTMRs can be verified against them too, with
+//! `TeeHost::verify_runtime_endorsement()`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::host::local::LocalTdxHost;
+//! use tdx_workload_attestation::host::{Evidence, TeeHost, VerificationContext};
+//!
+//! let host = LocalTdxHost::builder()
+//!     .firmware("/usr/share/ovmf/OVMF.tdx.fd")
+//!     .vcpus(4)
+//!     .memory_mib(8192)
+//!     .build()
+//!     .unwrap();
+//!
+//! // Verify a TDX guest's MRTD (here a dummy value) against the reference value
+//! let evidence = Evidence::new(&[0u8; 48]);
+//! match host.verify(&evidence, &VerificationContext::new()) {
+//!     Ok(verdict) if verdict.passed() => println!("Launch measurement matches the firmware."),
+//!     Ok(_) => println!("Launch measurement does not match the firmware."),
+//!     Err(e) => eprintln!("Error verifying launch measurement: {}", e),
+//! }
+//! ```
+//!
+//! # Notes
+//! - In the QEMU/KVM build flow, the TD HOB describing the TD's vCPUs and
+//!   memory is added to the TD without being measured, so the configuration
+//!   doesn't change the `MRTD`. It's checked against the firmware's memory
+//!   layout instead, since QEMU can't launch a TD whose firmware sections
+//!   don't fit in its memory.
+//! - The reference value is only as trustworthy as the firmware image it's
+//!   computed from, so operators should build or obtain it reproducibly.
+
+use crate::core::register::MeasurementRegister;
+use crate::error::{Error, Result};
+use crate::evidence::Verdict;
+use crate::host::{Evidence, TeeHost, VerificationContext, appraise_report};
+use crate::measure::ReferenceValues;
+use crate::measure::predict::{TdvfMetadata, TdvfSectionType, predict_mrtd};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The number of bytes in a MiB
+const MIB: u64 = 1024 * 1024;
+
+/// Represents a self-hosted QEMU/KVM TDX host.
+///
+/// The `expected_mrtd` field holds the reference value computed from the
+/// host's firmware, and the `boot_chain` field the expected RTMRs of the
+/// TD's boot chain, if set.
+pub struct LocalTdxHost {
+    expected_mrtd: MeasurementRegister,
+    boot_chain: Option<ReferenceValues>,
+}
+
+/// The source of the TDVF firmware image of a `LocalTdxHostBuilder`.
+enum Firmware {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// A builder for configuring a `LocalTdxHost`.
+pub struct LocalTdxHostBuilder {
+    firmware: Option<Firmware>,
+    vcpus: u32,
+    memory_mib: u64,
+    boot_chain: Option<ReferenceValues>,
+}
+
+impl Default for LocalTdxHostBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalTdxHostBuilder {
+    /// Creates a new builder for a `LocalTdxHost` with a TD configuration of
+    /// 1 vCPU and 2 GiB of memory.
+    pub fn new() -> Self {
+        LocalTdxHostBuilder {
+            firmware: None,
+            vcpus: 1,
+            memory_mib: 2048,
+            boot_chain: None,
+        }
+    }
+
+    /// Sets the path of the TDVF firmware image the TD was launched with
+    /// (e.g., QEMU's `-bios` argument).
+    pub fn firmware<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.firmware = Some(Firmware::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Sets the TDVF firmware image the TD was launched with.
+    pub fn firmware_bytes(mut self, firmware: &[u8]) -> Self {
+        self.firmware = Some(Firmware::Bytes(firmware.to_vec()));
+        self
+    }
+
+    /// Sets the number of vCPUs the TD was launched with (QEMU's `-smp`
+    /// argument).
+    pub fn vcpus(mut self, vcpus: u32) -> Self {
+        self.vcpus = vcpus;
+        self
+    }
+
+    /// Sets the memory size the TD was launched with, in MiB (QEMU's `-m`
+    /// argument).
+    pub fn memory_mib(mut self, memory_mib: u64) -> Self {
+        self.memory_mib = memory_mib;
+        self
+    }
+
+    /// Sets the expected RTMRs of the TD's boot chain (e.g., predicted from
+    /// its kernel and initrd with `measure::predict::predict_rtmrs()`). Their
+    /// `MRTD` is ignored, and unset RTMRs aren't checked.
+    pub fn boot_chain(mut self, values: ReferenceValues) -> Self {
+        self.boot_chain = Some(ReferenceValues {
+            mrtd: None,
+            ..values
+        });
+        self
+    }
+
+    /// Builds the `LocalTdxHost`, computing the reference MRTD from the
+    /// firmware.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if no firmware was set, the firmware is a
+    ///   symlink, or the TD configuration is invalid or doesn't fit the
+    ///   firmware's memory layout.
+    /// - `Error::IoError` if the firmware cannot be read.
+    /// - `Error::ParseError` if the firmware doesn't contain valid TDVF
+    ///   metadata.
+    pub fn build(self) -> Result<LocalTdxHost> {
+        let firmware = match self.firmware {
+            Some(Firmware::Path(path)) => read_firmware(&path)?,
+            Some(Firmware::Bytes(bytes)) => bytes,
+            None => {
+                return Err(Error::NotSupported(
+                    "No TDVF firmware configured for the local host".to_string(),
+                ));
+            }
+        };
+
+        if self.vcpus == 0 || self.memory_mib == 0 {
+            return Err(Error::NotSupported(
+                "TD configuration must have at least one vCPU and some memory".to_string(),
+            ));
+        }
+        check_memory_layout(&TdvfMetadata::parse(&firmware)?, self.memory_mib)?;
+
+        Ok(LocalTdxHost {
+            expected_mrtd: predict_mrtd(&firmware)?,
+            boot_chain: self.boot_chain,
+        })
+    }
+}
+
+impl LocalTdxHost {
+    /// Creates a new `LocalTdxHost` with the TDVF firmware at
+    /// `firmware_path` and the default TD configuration.
+    ///
+    /// See `LocalTdxHostBuilder::build()` for errors.
+    pub fn new<P: AsRef<Path>>(firmware_path: P) -> Result<LocalTdxHost> {
+        LocalTdxHostBuilder::new().firmware(firmware_path).build()
+    }
+
+    /// Returns a builder for configuring a `LocalTdxHost`.
+    pub fn builder() -> LocalTdxHostBuilder {
+        LocalTdxHostBuilder::new()
+    }
+
+    /// Returns the reference MRTD computed from the firmware.
+    pub fn expected_mrtd(&self) -> MeasurementRegister {
+        self.expected_mrtd
+    }
+
+    /// Returns the reference values for appraising the TD's evidence (see
+    /// `evidence::Policy`), which constrain its `MRTD`, and the RTMRs of its
+    /// boot chain, if set.
+    pub fn reference_values(&self) -> ReferenceValues {
+        ReferenceValues {
+            mrtd: Some(self.expected_mrtd),
+            ..self.boot_chain.clone().unwrap_or_default()
+        }
+    }
+}
+
+impl TeeHost for LocalTdxHost {
+    /// Verifies the guest's evidence against the reference value computed
+    /// from the host's TDVF firmware, with the checks:
+    /// - `mrtd`: the guest's MRTD matches the reference value.
+    /// - `debug` and `reference-values`: the guest's report matches the
+    ///   context's policy (see the `host` module).
+    /// - `firmware-svn` (if the context has a minimum firmware SVN) and
+    ///   `endorsement-age` (if it has a maximum endorsement age): always fail,
+    ///   since the host's firmware has no endorsement.
+    ///
+    /// The context's trust anchors and the evidence's endorsement (if any)
+    /// are not used, since the host has no endorsements.
+    fn verify(&self, evidence: &Evidence, context: &VerificationContext) -> Result<Verdict> {
+        let mut verdict = Verdict::default();
+
+        if *evidence.mrtd() == self.expected_mrtd {
+            verdict.pass("mrtd");
+        } else {
+            verdict.fail("mrtd", "MRTD does not match the firmware's");
+        }
+        if context.min_firmware_svn.is_some() {
+            verdict.fail("firmware-svn", "The host's firmware has no endorsed SVN");
+        }
+        if context.max_endorsement_age.is_some() {
+            verdict.fail("endorsement-age", "The host's firmware has no endorsement");
+        }
+        appraise_report(evidence, context, &mut verdict);
+
+        Ok(verdict)
+    }
+
+    /// Verifies the guest's RTMRs against the expected RTMRs of its boot
+    /// chain, with the `runtime-endorsement` check.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the host has no boot chain (see
+    /// `LocalTdxHostBuilder::boot_chain()`).
+    fn verify_runtime_endorsement(
+        &self,
+        rtmrs: &[MeasurementRegister; 4],
+        _context: &VerificationContext,
+    ) -> Result<Verdict> {
+        let Some(boot_chain) = &self.boot_chain else {
+            return Err(Error::NotSupported(
+                "No boot chain configured for the local host".to_string(),
+            ));
+        };
+
+        let mut verdict = Verdict::default();
+        let mismatches = boot_chain.rtmr_mismatches(rtmrs);
+        if mismatches.is_empty() {
+            verdict.pass("runtime-endorsement");
+        } else {
+            verdict.fail(
+                "runtime-endorsement",
+                &format!("{} do not match the boot chain's", mismatches.join(", ")),
+            );
+        }
+        Ok(verdict)
+    }
+}
+
+/// Reads a firmware image, rejecting symlinks.
+fn read_firmware(path: &Path) -> Result<Vec<u8>> {
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+    Ok(fs::read(path)?)
+}
+
+/// Checks that the firmware's sections backed by the TD's RAM (rather than
+/// mapped from the firmware image) fit in its memory.
+fn check_memory_layout(metadata: &TdvfMetadata, memory_mib: u64) -> Result<()> {
+    let memory = memory_mib.saturating_mul(MIB);
+
+    for section in &metadata.sections {
+        if matches!(
+            section.section_type,
+            TdvfSectionType::Bfv | TdvfSectionType::Cfv
+        ) {
+            continue;
+        }
+
+        let end = section
+            .memory_address
+            .saturating_add(section.memory_data_size);
+        if end > memory {
+            return Err(Error::NotSupported(format!(
+                "TDVF {:?} section at {:#x} does not fit in {} MiB of TD memory",
+                section.section_type, section.memory_address, memory_mib
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::TDX_MR_REG_LEN;
+    use crate::measure::predict::tests::make_tdvf;
+
+    #[test]
+    fn test_verify_local_launch_measurement() -> Result<()> {
+        let firmware = make_tdvf();
+        let mrtd = predict_mrtd(&firmware)?;
+        let context = VerificationContext::new();
+
+        let host = LocalTdxHost::builder()
+            .firmware_bytes(&firmware)
+            .vcpus(2)
+            .memory_mib(16)
+            .build()?;
+        assert!(host.verify(&Evidence::new(&mrtd), &context)?.passed());
+        assert_eq!(host.reference_values().mrtd, Some(mrtd));
+        assert_eq!(host.expected_mrtd(), mrtd);
+
+        let verdict = host.verify(&Evidence::new(&[0; TDX_MR_REG_LEN]), &context)?;
+        assert!(!verdict.check("mrtd").unwrap().passed);
+
+        // the firmware has no endorsed SVN to check
+        let context = context.with_min_firmware_svn(1);
+        let verdict = host.verify(&Evidence::new(&mrtd), &context)?;
+        assert!(!verdict.check("firmware-svn").unwrap().passed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_local_runtime_endorsement() -> Result<()> {
+        use crate::measure::predict::{DirectBootConfig, predict_rtmrs};
+
+        let firmware = make_tdvf();
+        let kernel = crate::measure::pe::tests::make_pe();
+        let boot_chain = predict_rtmrs(&DirectBootConfig {
+            kernel: &kernel,
+            initrd: None,
+            cmdline: "console=ttyS0",
+            rtmr0_digests: None,
+        })?;
+        let context = VerificationContext::new();
+
+        // hosts without a boot chain have no runtime endorsement
+        let host = LocalTdxHost::builder().firmware_bytes(&firmware).build()?;
+        let rtmrs = [
+            [0; TDX_MR_REG_LEN].into(),
+            boot_chain.rtmr1.unwrap(),
+            boot_chain.rtmr2.unwrap(),
+            [3; TDX_MR_REG_LEN].into(),
+        ];
+        assert!(
+            host.verify_runtime_endorsement(&rtmrs, &context)
+                .is_err_and(|e| e.is_not_supported())
+        );
+
+        let host = LocalTdxHost::builder()
+            .firmware_bytes(&firmware)
+            .boot_chain(boot_chain.clone())
+            .build()?;
+        assert_eq!(host.reference_values().rtmr1, boot_chain.rtmr1);
+        assert_eq!(host.reference_values().mrtd, Some(host.expected_mrtd()));
+
+        // RTMR0 isn't predicted, and RTMR3 isn't part of the boot chain
+        let verdict = host.verify_runtime_endorsement(&rtmrs, &context)?;
+        assert!(verdict.passed(), "{:?}", verdict);
+
+        let mut tampered = rtmrs;
+        tampered[2] = [2; TDX_MR_REG_LEN].into();
+        let verdict = host.verify_runtime_endorsement(&tampered, &context)?;
+        let check = verdict.check("runtime-endorsement").unwrap();
+        assert!(!check.passed);
+        assert_eq!(
+            check.detail.as_deref(),
+            Some("RTMR2 do not match the boot chain's")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_local_host_config() {
+        let firmware = make_tdvf();
+        // the test firmware's TD HOB ends at 8 MiB + 4 KiB
+        let build = |vcpus, memory_mib| {
+            LocalTdxHost::builder()
+                .firmware_bytes(&firmware)
+                .vcpus(vcpus)
+                .memory_mib(memory_mib)
+                .build()
+        };
+        assert!(build(1, 8).is_err_and(|e| e.is_not_supported()));
+        assert!(build(0, 16).is_err_and(|e| e.is_not_supported()));
+        assert!(build(1, 9).is_ok());
+
+        assert!(LocalTdxHost::builder().build().is_err());
+        assert!(
+            LocalTdxHost::builder()
+                .firmware_bytes(b"not a TDVF")
+                .build()
+                .is_err()
+        );
+    }
+}
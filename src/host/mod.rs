@@ -0,0 +1,504 @@
+//! # Host Interface for VM-based Trusted Execution Environment (TEE) Guests
+//!
+//! This module provides the `TeeHost` trait, which VM hosts can use to
+//! expose TEE attestation features to VM guests, such as Intel TDX VMs hosted
+//! on public clouds.
+//!
+//! The trait provides a function for verifying a TD's `Evidence` (its
+//! launch measurement and, optionally, its full attestation report or quote
+//! and a launch endorsement) against the values endorsed by the host, in a
+//! `VerificationContext` that holds the verifier's trust anchors, policy and
+//! verification time. It returns a `Verdict` (see the `evidence` module)
+//! listing the result of each check, so that verifiers can tell which checks
+//! failed, and hosts can add checks of their own.
+//!
+//! Hosts that also publish the expected runtime measurements of their boot
+//! chain (e.g., the `RTMR0` and `RTMR1` values of their firmware
+//! configuration and kernel) can check a TD's RTMRs against them with
+//! `TeeHost::verify_runtime_endorsement()`, rather than only its static
+//! `MRTD`.
+//!
+//! Self-hosted QEMU/KVM deployments, which have no cloud endorsements, can
+//! instead verify the launch measurement against a reference value computed
+//! from the operator's firmware (see the `local` module).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::host::{Evidence, TeeHost, VerificationContext};
+//! use tdx_workload_attestation::host::local::LocalTdxHost;
+//!
+//! let host = LocalTdxHost::new("/usr/share/ovmf/OVMF.tdx.fd").unwrap();
+//!
+//! // Verify a TDX guest's report (e.g., from `get_attestation_report()`)
+//! let report = std::fs::read("report.json").unwrap();
+//! let evidence = Evidence::from_report(&report).unwrap();
+//! let verdict = host.verify(&evidence, &VerificationContext::new()).unwrap();
+//! for check in verdict.checks.iter().filter(|c| !c.passed) {
+//!     eprintln!("Check {} failed: {:?}", check.name, check.detail);
+//! }
+//! ```
+//!
+//! # Notes
+//! - Checks that failed are reported in the verdict, while errors are only
+//!   returned if the evidence or the host's endorsement cannot be retrieved
+//!   or parsed.
+//! - The TD's attributes and RTMRs are only checked if the evidence includes
+//!   its report, which isn't authenticated by the host: it must be obtained
+//!   from the TD itself, or from a quote whose signature was verified.
+
+pub mod local;
+
+use crate::clock::{Clock, FixedClock, SystemClock};
+use crate::core::quote::{Quote, TD_ATTRIBUTES_DEBUG, TdQuoteBody};
+use crate::core::register::{Digest384, MeasurementRegister};
+use crate::core::report::{TDX_MR_REG_LEN, TdReportV15};
+use crate::error::{Error, Result};
+use crate::evidence::Verdict;
+use crate::measure::ReferenceValues;
+use crate::trust::TrustAnchors;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A VM host that endorses the measurements of its TEE guests, against which
+/// verifiers check a TD's evidence.
+///
+/// Implementations must report the outcome of appraisal as follows:
+/// - `Ok(verdict)`: the evidence was appraised. Each check the host
+///   performed is recorded in the verdict, and the evidence is only
+///   acceptable if `verdict.passed()`. A failed check (e.g., an invalid
+///   endorsement signature, or a mismatched `MRTD`) is never an `Err`, so
+///   that verifiers can tell which checks failed. Wrappers returning a
+///   boolean (e.g., `verify_launch_endorsement()`) return `Ok(false)` for a
+///   verdict with failed checks.
+/// - `Err(Error::NotSupported)`: the host cannot perform the requested
+///   verification at all, e.g., it publishes no runtime endorsements, or
+///   doesn't support the evidence's format.
+/// - Any other `Err`: the evidence couldn't be appraised, e.g., the host's
+///   endorsement cannot be retrieved, or the evidence or endorsement cannot
+///   be parsed. Verifiers must treat this as a rejection, but may retry.
+pub trait TeeHost {
+    /// Verifies the TD's evidence against the host's launch endorsement in
+    /// the given context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host's endorsement cannot be retrieved or
+    /// parsed. Failed checks are reported in the verdict instead.
+    fn verify(&self, evidence: &Evidence, context: &VerificationContext) -> Result<Verdict>;
+
+    /// Verifies the TD's runtime measurement registers `rtmrs` against the
+    /// host's endorsement of its boot chain (e.g., the expected `RTMR0` and
+    /// `RTMR1` values of its firmware configuration, kernel and initrd) in
+    /// the given context, with the `runtime-endorsement` check.
+    ///
+    /// The RTMRs aren't authenticated by the host: they must be obtained from
+    /// the TD itself, or from a quote whose signature was verified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the host publishes no runtime
+    /// endorsements (the default), or an error if they cannot be retrieved or
+    /// parsed. Failed checks are reported in the verdict instead.
+    fn verify_runtime_endorsement(
+        &self,
+        rtmrs: &[MeasurementRegister; 4],
+        context: &VerificationContext,
+    ) -> Result<Verdict> {
+        let _ = (rtmrs, context);
+        Err(Error::NotSupported(
+            "The host publishes no runtime endorsements".to_string(),
+        ))
+    }
+}
+
+/// The evidence of a TD that a `TeeHost` verifies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evidence {
+    mrtd: MeasurementRegister,
+    report: Option<LaunchReport>,
+    endorsement: Option<Vec<u8>>,
+}
+
+impl Evidence {
+    /// Creates evidence of the TD's launch measurement only.
+    pub fn new(mrtd_bytes: &[u8; TDX_MR_REG_LEN]) -> Self {
+        Self {
+            mrtd: (*mrtd_bytes).into(),
+            report: None,
+            endorsement: None,
+        }
+    }
+
+    /// Creates evidence of the TD's full serialized attestation report or
+    /// quote (see `LaunchReport::parse()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the report cannot be parsed.
+    pub fn from_report(report: &[u8]) -> Result<Self> {
+        let report = LaunchReport::parse(report)?;
+        Ok(Self {
+            mrtd: report.mrtd,
+            report: Some(report),
+            endorsement: None,
+        })
+    }
+
+    /// Adds the TD's raw launch endorsement (e.g., one shipped in an evidence
+    /// bundle), which hosts verify instead of retrieving their own.
+    pub fn with_endorsement(mut self, endorsement: &[u8]) -> Self {
+        self.endorsement = Some(endorsement.to_vec());
+        self
+    }
+
+    /// Returns the TD's launch measurement.
+    pub fn mrtd(&self) -> &MeasurementRegister {
+        &self.mrtd
+    }
+
+    /// Returns the TD's report, if any.
+    pub fn report(&self) -> Option<&LaunchReport> {
+        self.report.as_ref()
+    }
+
+    /// Returns the TD's raw launch endorsement, if any.
+    pub fn endorsement(&self) -> Option<&[u8]> {
+        self.endorsement.as_deref()
+    }
+}
+
+/// The context in which a `TeeHost` verifies a TD's evidence.
+#[derive(Clone, Debug)]
+pub struct VerificationContext {
+    /// The trust anchors the host's endorsement must chain up to. Hosts use
+    /// their own (e.g., downloaded) roots for anchor kinds with none.
+    pub trust_anchors: TrustAnchors,
+    /// The expected measurement register values. RTMRs are only checked if
+    /// the evidence includes the TD's report.
+    pub reference_values: ReferenceValues,
+    /// Whether debug TDs are acceptable.
+    pub allow_debug: bool,
+    /// The memory size the TD was launched with, in GiB, if the endorsed
+    /// `MRTD` must be the one for that memory configuration.
+    pub memory_gib: Option<u32>,
+    /// The minimum security version number (SVN) of the TD's firmware, as
+    /// stated by the host's endorsement, if any.
+    pub min_firmware_svn: Option<u32>,
+    /// The maximum age of the host's endorsement at the verification time,
+    /// if any.
+    pub max_endorsement_age: Option<Duration>,
+    /// The SHA-384 digests of firmware whose endorsements were withdrawn
+    /// (e.g., after its golden measurements were compromised).
+    pub revoked_firmware_digests: Vec<Digest384>,
+    /// The clock at which certificates are checked for expiry (the
+    /// `SystemClock` by default).
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for VerificationContext {
+    fn default() -> Self {
+        Self {
+            trust_anchors: TrustAnchors::default(),
+            reference_values: ReferenceValues::default(),
+            allow_debug: false,
+            memory_gib: None,
+            min_firmware_svn: None,
+            max_endorsement_age: None,
+            revoked_firmware_digests: vec![],
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl VerificationContext {
+    /// Creates a new, empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the trust anchors for the host's endorsement.
+    pub fn with_trust_anchors(mut self, anchors: TrustAnchors) -> Self {
+        self.trust_anchors = anchors;
+        self
+    }
+
+    /// Requires the TD's measurements to match `values`.
+    pub fn with_reference_values(mut self, values: ReferenceValues) -> Self {
+        self.reference_values = values;
+        self
+    }
+
+    /// Sets whether debug TDs are acceptable.
+    pub fn allow_debug(mut self, allow: bool) -> Self {
+        self.allow_debug = allow;
+        self
+    }
+
+    /// Requires the endorsed `MRTD` to be the one for a TD memory size of
+    /// `memory_gib` GiB.
+    pub fn with_memory_gib(mut self, memory_gib: u32) -> Self {
+        self.memory_gib = Some(memory_gib);
+        self
+    }
+
+    /// Requires the endorsed firmware's SVN to be at least `svn`, so that
+    /// old (but still validly endorsed) firmware is rejected.
+    pub fn with_min_firmware_svn(mut self, svn: u32) -> Self {
+        self.min_firmware_svn = Some(svn);
+        self
+    }
+
+    /// Requires the host's endorsement to have been issued at most `max_age`
+    /// before the verification time.
+    pub fn with_max_endorsement_age(mut self, max_age: Duration) -> Self {
+        self.max_endorsement_age = Some(max_age);
+        self
+    }
+
+    /// Rejects endorsements of the firmware with the SHA-384 digest `digest`.
+    pub fn with_revoked_firmware_digest(mut self, digest: Digest384) -> Self {
+        self.revoked_firmware_digests.push(digest);
+        self
+    }
+
+    /// Sets the clock at which certificates are checked for expiry (e.g., a
+    /// `FixedClock` at the time archived evidence was collected).
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Checks certificates for expiry at `unix_time`, in seconds since the
+    /// Unix epoch (see `with_clock()`).
+    pub fn with_verification_time(self, unix_time: u64) -> Self {
+        self.with_clock(FixedClock::new(unix_time))
+    }
+
+    /// Returns the verification time, in seconds since the Unix epoch.
+    #[cfg_attr(not(feature = "host-gcp-tdx"), allow(dead_code))]
+    pub(crate) fn verification_time(&self) -> Result<u64> {
+        self.clock.now()
+    }
+}
+
+/// Appends the checks of the TD's report and measurements against the
+/// context's policy to `verdict`:
+/// - `debug`: the TD isn't a debug TD, unless allowed (if the evidence
+///   includes the TD's report).
+/// - `reference-values`: the TD's measurements match the reference values
+///   (if any are set).
+pub(crate) fn appraise_report(
+    evidence: &Evidence,
+    context: &VerificationContext,
+    verdict: &mut Verdict,
+) {
+    let report = evidence.report();
+
+    if let Some(report) = report {
+        if report.is_debug() && !context.allow_debug {
+            verdict.fail("debug", "TD is a debug TD");
+        } else {
+            verdict.pass("debug");
+        }
+    }
+
+    let reference = &context.reference_values;
+    let rtmrs = report.map(|report| report.rtmrs);
+    let expected = [
+        ("MRTD", reference.mrtd, Some(evidence.mrtd)),
+        ("RTMR0", reference.rtmr0, rtmrs.map(|r| r[0])),
+        ("RTMR1", reference.rtmr1, rtmrs.map(|r| r[1])),
+        ("RTMR2", reference.rtmr2, rtmrs.map(|r| r[2])),
+        ("RTMR3", reference.rtmr3, rtmrs.map(|r| r[3])),
+    ];
+    let constrained: Vec<_> = expected
+        .iter()
+        .filter(|(_, expected, _)| expected.is_some())
+        .collect();
+    if constrained.is_empty() {
+        return;
+    }
+
+    let mismatches: Vec<&str> = constrained
+        .iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(name, _, _)| *name)
+        .collect();
+    if mismatches.is_empty() {
+        verdict.pass("reference-values");
+    } else if rtmrs.is_none() && mismatches.iter().any(|name| name.starts_with("RTMR")) {
+        verdict.fail(
+            "reference-values",
+            "Evidence has no report to check the RTMRs against",
+        );
+    } else {
+        verdict.fail(
+            "reference-values",
+            &format!("{} do not match", mismatches.join(", ")),
+        );
+    }
+}
+
+/// The launch-time fields of a TD's attestation report or quote, which hosts
+/// check against their endorsements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LaunchReport {
+    /// The TD's attributes.
+    pub td_attributes: [u8; 8],
+    /// The extended CPU features enabled for the TD.
+    pub xfam: [u8; 8],
+    /// The TD's launch measurement.
+    pub mrtd: MeasurementRegister,
+    /// The software-defined ID of the TD's non-owner-defined configuration.
+    pub mrconfigid: MeasurementRegister,
+    /// The software-defined ID of the TD's owner.
+    pub mrowner: MeasurementRegister,
+    /// The software-defined ID of the TD's owner-defined configuration.
+    pub mrownerconfig: MeasurementRegister,
+    /// The TD's runtime measurement registers `RTMR[0..3]`.
+    pub rtmrs: [MeasurementRegister; 4],
+}
+
+impl LaunchReport {
+    /// Parses a serialized attestation report, which is either the JSON
+    /// `TDREPORT` returned by `AttestationProvider::get_attestation_report()`
+    /// or a raw TD quote.
+    ///
+    /// The report isn't authenticated, so its fields must only be trusted if
+    /// the report was obtained from the TD itself, or the quote's signature
+    /// was verified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the report is neither a valid JSON
+    /// `TDREPORT` nor a valid TD quote.
+    pub fn parse(report: &[u8]) -> Result<Self> {
+        if let Ok(tdreport) = serde_json::from_slice::<TdReportV15>(report) {
+            return Ok(Self::from(&tdreport));
+        }
+
+        let quote = Quote::from_bytes(report).map_err(|e| {
+            Error::ParseError(format!(
+                "Report is neither a JSON TDREPORT nor a TD quote: {}",
+                e
+            ))
+        })?;
+        Ok(Self::from(&quote.body))
+    }
+
+    /// Returns whether the TD is a debug TD, whose memory and state are
+    /// accessible to the host.
+    pub fn is_debug(&self) -> bool {
+        self.td_attributes[0] & TD_ATTRIBUTES_DEBUG != 0
+    }
+}
+
+impl From<&TdReportV15> for LaunchReport {
+    fn from(report: &TdReportV15) -> Self {
+        Self {
+            td_attributes: report.get_td_attributes(),
+            xfam: report.get_xfam(),
+            mrtd: report.get_mrtd(),
+            mrconfigid: report.get_mrconfigid(),
+            mrowner: report.get_mrowner(),
+            mrownerconfig: report.get_mrownerconfig(),
+            rtmrs: report.get_rtmrs(),
+        }
+    }
+}
+
+impl From<&TdQuoteBody> for LaunchReport {
+    fn from(body: &TdQuoteBody) -> Self {
+        Self {
+            td_attributes: body.td_attributes,
+            xfam: body.xfam,
+            mrtd: body.mrtd,
+            mrconfigid: body.mrconfigid,
+            mrowner: body.mrowner,
+            mrownerconfig: body.mrownerconfig,
+            rtmrs: body.rtmrs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+
+    #[test]
+    fn test_parse_launch_report() -> Result<()> {
+        let tdreport = TdReportV15::builder()
+            .with_td_attributes(&[1, 0, 0, 0, 0, 0, 0, 0], &[3; 8])
+            .with_mrtd(&[4; TDX_MR_REG_LEN])
+            .with_rtmrs(&[[5; TDX_MR_REG_LEN]; 4])
+            .build();
+        let json = serde_json::to_string(&tdreport).unwrap();
+        let report = LaunchReport::parse(json.as_bytes())?;
+        assert_eq!(report, LaunchReport::from(&tdreport));
+        assert_eq!(report.mrtd, [4; TDX_MR_REG_LEN]);
+        assert_eq!(report.xfam, [3; 8]);
+        assert!(report.is_debug());
+
+        let mut parts = QuoteParts::default();
+        parts.rtmrs[3] = [9; TDX_MR_REG_LEN];
+        let report = LaunchReport::parse(&parts.assemble(&[6; 64], &[7; 64]))?;
+        assert_eq!(report.mrtd, parts.mrtd);
+        assert_eq!(report.rtmrs, parts.rtmrs);
+        assert!(!report.is_debug());
+
+        assert!(matches!(
+            LaunchReport::parse(b"{}"),
+            Err(Error::ParseError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_appraise_report() -> Result<()> {
+        let tdreport = TdReportV15::builder()
+            .with_td_attributes(&[1, 0, 0, 0, 0, 0, 0, 0], &[0; 8])
+            .with_mrtd(&[4; TDX_MR_REG_LEN])
+            .with_rtmrs(&[[5; TDX_MR_REG_LEN]; 4])
+            .build();
+        let evidence = Evidence::from_report(&serde_json::to_vec(&tdreport).unwrap())?;
+        assert_eq!(evidence.mrtd(), &[4; TDX_MR_REG_LEN]);
+        let appraise = |evidence: &Evidence, context: &VerificationContext| {
+            let mut verdict = Verdict::default();
+            appraise_report(evidence, context, &mut verdict);
+            verdict
+        };
+
+        // nothing to check against but the debug attribute
+        let verdict = appraise(&evidence, &VerificationContext::new());
+        assert!(!verdict.check("debug").unwrap().passed);
+        assert!(verdict.check("reference-values").is_none());
+
+        let context = VerificationContext::new()
+            .allow_debug(true)
+            .with_reference_values(ReferenceValues {
+                mrtd: Some([4; TDX_MR_REG_LEN].into()),
+                rtmr1: Some([5; TDX_MR_REG_LEN].into()),
+                ..Default::default()
+            });
+        assert!(appraise(&evidence, &context).passed());
+
+        // the RTMRs cannot be checked without the report
+        let verdict = appraise(&Evidence::new(&[4; TDX_MR_REG_LEN]), &context);
+        assert!(verdict.check("debug").is_none());
+        assert!(!verdict.passed());
+
+        let context = context.with_reference_values(ReferenceValues {
+            rtmr2: Some([6; TDX_MR_REG_LEN].into()),
+            ..Default::default()
+        });
+        let verdict = appraise(&evidence, &context);
+        assert_eq!(
+            verdict.check("reference-values").unwrap().detail.as_deref(),
+            Some("RTMR2 do not match")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//! # HTTP Client Utilities
+//!
+//! This module provides the blocking HTTP client helpers shared by the
+//! modules that talk to external web services, such as Google Cloud Storage
+//! (see the `gcp::gcs` module), Intel Trust Authority (see the
+//! `verification::ita` module), Intel's Provisioning Certification Service
+//! (see the `evidence::pck` module), cloud KMS signers (see the
+//! `verification::kms` module) and alert webhooks (see the `alert`
+//! module).
+
+use crate::error::{Error, Result};
+use crate::retry::RetryPolicy;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::HeaderMap;
+
+/// Builds a blocking HTTP client that applies the policy's per-request
+/// timeout.
+pub(crate) fn http_client(policy: &RetryPolicy) -> Result<Client> {
+    let mut client = Client::builder();
+    if let Some(timeout) = policy.timeout() {
+        client = client.timeout(timeout);
+    }
+    client
+        .build()
+        .map_err(|e| Error::NetworkError(e.to_string()))
+}
+
+/// Sends the request and returns the response body, treating non-success
+/// statuses as errors.
+#[cfg_attr(
+    not(any(
+        feature = "alerts",
+        feature = "host-gcp-tdx",
+        feature = "ita-verification",
+        feature = "kbs-client",
+        feature = "kms-signing",
+        feature = "s3-store"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn send(req: RequestBuilder) -> Result<Vec<u8>> {
+    Ok(send_with_headers(req)?.0)
+}
+
+/// Sends the request and returns the response body and headers, treating
+/// non-success statuses as errors.
+pub(crate) fn send_with_headers(req: RequestBuilder) -> Result<(Vec<u8>, HeaderMap)> {
+    let resp = req
+        .send()
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| Error::NetworkError(e.without_url().to_string()))?;
+    let headers = resp.headers().clone();
+    let bytes = resp
+        .bytes()
+        .map_err(|e| Error::NetworkError(e.without_url().to_string()))?;
+
+    Ok((bytes.to_vec(), headers))
+}
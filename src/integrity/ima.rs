@@ -0,0 +1,261 @@
+//! # Linux IMA Runtime Measurement Ingestion
+//!
+//! This module ingests the Linux Integrity Measurement Architecture (IMA)
+//! runtime measurement list into `RTMR3`, so that file-level runtime integrity
+//! becomes part of the TD's attestable state.
+//!
+//! IMA records a measurement for each file accessed according to its policy,
+//! and extends it into a TPM PCR (if any). Since TDs don't necessarily have a
+//! TPM, the `ImaIngester` tails the measurement list exposed in securityfs,
+//! converts each new entry into a SHA-384 event, extends it into `RTMR3`, and
+//! records it in a local event log that verifiers can replay.
+//!
+//! The digest of each event is the SHA-384 digest of the entry's line in the
+//! ASCII measurement list, which is recorded as the event's payload.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tdx_workload_attestation::integrity::ima::ImaIngester;
+//! use tdx_workload_attestation::measure::event_log::EventLog;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let log = EventLog::new("/var/lib/tdx-workload-attestation/ima.cbor");
+//! let mut ingester = ImaIngester::new(LinuxTdxProvider::new(), log).unwrap();
+//!
+//! // Ingest new IMA measurements every 10 seconds
+//! ingester.tail(Duration::from_secs(10)).unwrap();
+//! ```
+//!
+//! # Notes
+//! - Ingestion resumes from the number of IMA events already in the local
+//!   event log, so the same log must be used across restarts within a boot,
+//!   and must be reset on reboot.
+
+use crate::error::{Error, Result};
+use crate::measure::event_log::{Event, EventLog, EventPayload, RtmrExtender, measure_event};
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// The path to the IMA ASCII runtime measurement list.
+pub const IMA_MEASUREMENTS_PATH: &str = "/sys/kernel/security/ima/ascii_runtime_measurements";
+
+/// The RTMR that IMA events are extended into.
+pub const IMA_RTMR_INDEX: u8 = 3;
+
+/// An entry in the IMA ASCII runtime measurement list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImaEntry {
+    /// The PCR the entry was measured into by IMA.
+    pub pcr: u32,
+    /// The hex-encoded digest of the entry's template data.
+    pub template_hash: String,
+    /// The name of the template (e.g., `ima-ng`).
+    pub template_name: String,
+    /// The template's fields (e.g., the file digest and path for `ima-ng`).
+    pub fields: String,
+}
+
+impl ImaEntry {
+    /// Parses a line of the IMA ASCII runtime measurement list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the line is malformed.
+    pub fn parse(line: &str) -> Result<Self> {
+        let invalid = || Error::ParseError(format!("Invalid IMA measurement entry: {}", line));
+
+        let mut parts = line.trim().splitn(4, ' ');
+        let pcr = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let template_hash = parts.next().ok_or_else(invalid)?;
+        let template_name = parts.next().ok_or_else(invalid)?;
+        let fields = parts.next().unwrap_or_default();
+
+        if hex::decode(template_hash).is_err() || template_name.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            pcr,
+            template_hash: template_hash.to_string(),
+            template_name: template_name.to_string(),
+            fields: fields.to_string(),
+        })
+    }
+
+    /// Converts the entry into an `RTMR3` event.
+    pub fn to_event(&self) -> Event {
+        let line = if self.fields.is_empty() {
+            format!("{} {} {}", self.pcr, self.template_hash, self.template_name)
+        } else {
+            format!(
+                "{} {} {} {}",
+                self.pcr, self.template_hash, self.template_name, self.fields
+            )
+        };
+
+        Event::new(IMA_RTMR_INDEX, EventPayload::Ima { line })
+    }
+}
+
+/// Ingests IMA runtime measurements into `RTMR3` and a local event log.
+pub struct ImaIngester<E: RtmrExtender> {
+    extender: E,
+    log: EventLog,
+    measurements_path: PathBuf,
+    ingested: usize,
+}
+
+impl<E: RtmrExtender> ImaIngester<E> {
+    /// Creates a new `ImaIngester` that extends RTMRs with `extender` and
+    /// records events in `log`.
+    ///
+    /// Ingestion resumes after the IMA events already recorded in `log`.
+    pub fn new(extender: E, log: EventLog) -> Result<Self> {
+        let mut ingested = 0;
+        for event in log.reader()? {
+            if matches!(event?.payload, EventPayload::Ima { .. }) {
+                ingested += 1;
+            }
+        }
+
+        Ok(Self {
+            extender,
+            log,
+            measurements_path: PathBuf::from(IMA_MEASUREMENTS_PATH),
+            ingested,
+        })
+    }
+
+    /// Sets the path to the IMA ASCII runtime measurement list.
+    pub fn with_measurements_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.measurements_path = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Returns the number of IMA entries ingested so far.
+    pub fn ingested(&self) -> usize {
+        self.ingested
+    }
+
+    /// Ingests any IMA entries added since the last poll, and returns the
+    /// number of new entries.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if IMA is not enabled in the kernel.
+    /// - `Error::ParseError` if an entry is malformed.
+    /// - `Error::VerificationError` if the event log has more IMA events than
+    ///   the measurement list, e.g., because it is left over from a previous
+    ///   boot.
+    /// - Any error returned by the `RtmrExtender`.
+    pub fn poll(&mut self) -> Result<usize> {
+        if !fs::exists(&self.measurements_path)? {
+            return Err(Error::NotSupported(format!(
+                "IMA measurement list {} not found",
+                self.measurements_path.display()
+            )));
+        }
+
+        // the list is read line by line, as it can grow to tens of MB
+        let list = BufReader::new(fs::File::open(&self.measurements_path)?);
+        let mut lines = 0;
+        let mut count = 0;
+        for line in list.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            lines += 1;
+            if lines <= self.ingested {
+                continue;
+            }
+
+            let event = ImaEntry::parse(&line)?.to_event();
+            measure_event(&mut self.extender, &self.log, &event)?;
+            self.ingested += 1;
+            count += 1;
+        }
+
+        if lines < self.ingested {
+            return Err(Error::VerificationError(format!(
+                "Event log has {} IMA events, but the measurement list only has {}",
+                self.ingested, lines
+            )));
+        }
+
+        Ok(count)
+    }
+
+    /// Continuously ingests new IMA entries, polling the measurement list
+    /// every `interval`. Only returns on error.
+    pub fn tail(&mut self, interval: Duration) -> Result<()> {
+        loop {
+            self.poll()?;
+            thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::event_log::tests::{SoftRtmrs, temp_log};
+
+    const ENTRY: &str = "10 91f34b5c671d73504b274a919661cf80dab1e127 ima-ng sha256:2a6f8b1c0ddb8e1ad1a0b5c2ed4f1e0dd1d4ad3f8a1d2c6e5f1c8b9a0d1e2f3a /usr/bin/kmod";
+
+    #[test]
+    fn test_parse_entry() -> Result<()> {
+        let entry = ImaEntry::parse(ENTRY)?;
+        assert_eq!(entry.pcr, 10);
+        assert_eq!(entry.template_name, "ima-ng");
+        assert!(entry.fields.ends_with("/usr/bin/kmod"));
+        assert_eq!(entry.to_event().payload.measured_data(), ENTRY);
+
+        assert!(ImaEntry::parse("").is_err());
+        assert!(ImaEntry::parse("10 nothex ima-ng").is_err());
+        assert!(ImaEntry::parse("pcr 91f3 ima-ng").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_poll() -> Result<()> {
+        let list = std::env::temp_dir().join(format!("ima-{}.txt", rand::random::<u64>()));
+        fs::write(&list, format!("{}\n", ENTRY))?;
+
+        let log = temp_log();
+        let mut ingester =
+            ImaIngester::new(SoftRtmrs::default(), log.clone())?.with_measurements_path(&list);
+        assert_eq!(ingester.poll()?, 1);
+        assert_eq!(ingester.poll()?, 0);
+
+        fs::write(
+            &list,
+            format!("{}\n{}\n", ENTRY, ENTRY.replace("kmod", "ls")),
+        )?;
+        assert_eq!(ingester.poll()?, 1);
+        assert_eq!(log.replay()?, ingester.extender.rtmrs);
+
+        // a new ingester resumes where the last one left off
+        let mut resumed =
+            ImaIngester::new(SoftRtmrs::default(), log.clone())?.with_measurements_path(&list);
+        assert_eq!(resumed.ingested(), 2);
+        assert_eq!(resumed.poll()?, 0);
+
+        // the measurement list was reset, e.g., by a reboot
+        fs::write(&list, format!("{}\n", ENTRY))?;
+        assert!(resumed.poll().is_err());
+
+        fs::remove_file(&list)?;
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+}
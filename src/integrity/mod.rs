@@ -0,0 +1,11 @@
+//! # Runtime Integrity Measurement
+//!
+//! This module provides integrations with runtime integrity measurement
+//! subsystems, which record measurements of the TD's state after boot into
+//! the TD's runtime measurement registers (RTMRs), making it part of the TD's
+//! attestable state.
+//!
+//! The following subsystems are currently supported:
+//! - `ima`: The Linux Integrity Measurement Architecture (IMA)
+
+pub mod ima;
@@ -4,16 +4,48 @@
 //! of Intel TDX (Trust Domain Extensions) VM workloads.
 //!
 //! The library provides the following functionality:
+//! - `agent`: Attestation agent serving quotes to local workloads over a
+//!   Unix socket (when compiled with the `tdx-linux` feature)
+//! - `clock`: Time sources for certificate, collateral and token validity
+//!   checks
+//! - `config`: Layered configuration (file, environment and flags) shared by
+//!   the CLI and library consumers
+//! - `core`: `no_std` compatible `TDREPORT` and TD quote parsing (the only
+//!   module compiled without the `std` feature)
 //! - `error`: Custom error types
+//! - `evidence`: Attestation evidence bundles and TD quote parsing, and PCK
+//!   certificate retrieval from Intel's PCS (when compiled with the
+//!   `pck-retrieval` feature)
+//! - `ffi`: C bindings for evidence collection and verification (when
+//!   compiled with the `ffi` feature)
 //! - `gcp`: Google Cloud Platform (GCP) host interface for TDX guests (when
 //!   compiled with the `host-gcp-tdx` feature)
 //! - `host`: Host interface for VM-based trusted execution environment (TEE)
 //!   guests (when compiled with the `host-verification` feature)
+//! - `integrity`: Runtime integrity measurement (e.g., Linux IMA) ingestion
+//! - `measure`: TD measurement (MRTD and RTMR) prediction and runtime event
+//!   log utilities
+//! - `metrics`: Quote issuance and appraisal metrics, exported in the
+//!   Prometheus text format
+//! - `platform`: Platform attestation capability detection
 //! - `provider`: Trusted execution environment (TEE) attestation interface
+//! - `retry`: Retry and timeout policy for operations that depend on external
+//!   services
+//! - `secrets`: Attestation-gated release of secrets to TDs (when compiled
+//!   with the `host-verification` feature), and a key broker client (when
+//!   compiled with the `kbs-client` feature)
 //! - `tdx`: Intel TDX guest attestation interface (when compiled with the
-//!   `tdx-linux` feature)
+//!   `tdx-linux` feature, or the `tdx-windows` feature on Windows)
+//! - `trust`: Trust anchor (root certificate) store for all verification
+//!   paths
 //! - `verification`: Workload attestation verification utilities (when compiled
-//!   with the `host-verification` feature)
+//!   with the `host-verification` or `rustcrypto-verification` feature),
+//!   signed JWT attestation results (when compiled with the
+//!   `host-verification` feature, and with cloud KMS keys with the
+//!   `kms-signing` feature), and an Intel Trust Authority client (when
+//!   compiled with the `ita-verification` feature)
+//! - `vtpm`: Virtual TPM interface and RTMR/PCR cross-checking (when compiled
+//!   with the `vtpm` feature)
 //!
 //! ## Example Usage
 //!
@@ -42,18 +74,90 @@
 //!     _ => println!("This platform does not support TDX"),
 //! }
 //! ```
+//!
+//! When compiled with the `host-gcp-tdx` feature, the guest's launch
+//! measurement can be verified against the host's endorsement in one call:
+//!
+//! ```no_run
+//! # #[cfg(all(feature = "host-verification", feature = "tdx-linux"))]
+//! # {
+//! use tdx_workload_attestation::verify_launch_endorsement;
+//!
+//! match verify_launch_endorsement("gcp-tdx") {
+//!     Ok(true) => println!("Launch endorsement is valid."),
+//!     Ok(false) => println!("Launch endorsement is invalid."),
+//!     Err(e) => eprintln!("Error verifying launch endorsement: {}", e),
+//! }
+//! # }
+//! ```
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "tdx-linux")]
+pub mod agent;
+#[cfg(feature = "alerts")]
+pub mod alert;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod config;
+pub mod core;
+#[cfg(feature = "std")]
+#[cfg_attr(not(feature = "host-verification"), allow(dead_code))]
+mod der;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
+pub mod evidence;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 #[cfg(feature = "host-gcp-tdx")]
 pub mod gcp;
 #[cfg(feature = "host-verification")]
 pub mod host;
+#[cfg(any(
+    feature = "alerts",
+    feature = "host-gcp-tdx",
+    feature = "ita-verification",
+    feature = "kbs-client",
+    feature = "kms-signing",
+    feature = "pck-retrieval",
+    feature = "s3-store",
+    feature = "tsa-timestamping"
+))]
+mod http;
+#[cfg(feature = "std")]
+pub mod integrity;
+#[cfg(feature = "std")]
+pub mod measure;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod platform;
+#[cfg(feature = "std")]
 pub mod provider;
-#[cfg(feature = "tdx-linux")]
-pub mod tdx;
+#[cfg(feature = "std")]
+pub mod retry;
+#[cfg(feature = "std")]
+pub mod schema;
 #[cfg(feature = "host-verification")]
+pub mod secrets;
+#[cfg(any(feature = "tdx-linux", all(feature = "tdx-windows", windows)))]
+pub mod tdx;
+#[cfg(all(test, feature = "std"))]
+mod test_utils;
+#[cfg(feature = "std")]
+pub mod trust;
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
 pub mod verification;
+#[cfg(feature = "vtpm")]
+pub mod vtpm;
 
+#[cfg(all(feature = "host-verification", feature = "tdx-linux"))]
+use error::Error;
+#[cfg(feature = "std")]
 use error::Result;
 #[cfg(feature = "tdx-linux")]
 use tdx::linux::is_v15_kvm_device;
@@ -64,21 +168,77 @@ use tdx::linux::is_v15_kvm_device;
 /// additional feature flags.
 ///
 /// If the `tdx-linux` feature is enabled and the system supports TDX (Trust
-/// Domain Extensions) 1.5 on a Linux KVM device, the platform name will be
-/// returned as `"tdx-linux"`. Otherwise, it defaults to the operating system
-/// name.
+/// Domain Extensions) 1.5 on a Linux KVM device, or through a Hyper-V
+/// paravisor, or CPUID enumerates a TDX guest whose device is hidden (see
+/// `platform::is_tdx_guest()`), the platform name will be returned as
+/// `"tdx-linux"`. If the `tdx-windows` feature is enabled on Windows and the
+/// Hyper-V paravisor exposes a TDX report, it will be returned as
+/// `"tdx-windows"`. Otherwise, it defaults to the operating system name.
 ///
 /// # Errors
 ///
 /// Returns an error if support for TDX 1.5 on Linux cannot be determined
 /// (requires the `tdx-linux` feature).
+#[cfg(feature = "std")]
 pub fn get_platform_name() -> Result<String> {
     let name = std::env::consts::OS;
 
     #[cfg(feature = "tdx-linux")]
-    if is_v15_kvm_device()? {
+    if is_v15_kvm_device()? || tdx::hcl::is_available()? || platform::is_tdx_guest() {
         return Ok("tdx-linux".to_string());
     }
 
+    #[cfg(all(feature = "tdx-windows", windows))]
+    if tdx::windows::is_available()? {
+        return Ok("tdx-windows".to_string());
+    }
+
     Ok(name.to_string())
 }
+
+/// Verifies the launch measurement of the current TDX guest against the
+/// launch endorsement published by the given host platform.
+///
+/// This function captures the guest's live launch measurement (MRTD) via the
+/// `LinuxTdxProvider`, selects the `TeeHost` implementation matching `host`,
+/// and runs the host's launch endorsement verification end-to-end. Returns
+/// `Ok(true)` if all of the host's checks passed (see `TeeHost::verify()` for
+/// the individual checks).
+///
+/// Supported hosts:
+/// - `"gcp-tdx"`: Google Cloud Platform (requires the `host-gcp-tdx` feature)
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if the host is unknown or support for it was not
+///   compiled in, or if the current platform does not support TDX.
+/// - Any error returned by the selected host's verification.
+#[cfg(all(feature = "host-verification", feature = "tdx-linux"))]
+pub fn verify_launch_endorsement(host: &str) -> Result<bool> {
+    match host {
+        #[cfg(feature = "host-gcp-tdx")]
+        "gcp-tdx" => {
+            use host::{Evidence, TeeHost, VerificationContext};
+            use provider::AttestationProvider;
+
+            let mrtd = tdx::LinuxTdxProvider::new().get_launch_measurement()?;
+            let evidence = Evidence::new(&mrtd);
+            let verdict = gcp::GcpTdxHost::new()?.verify(&evidence, &VerificationContext::new())?;
+            Ok(verdict.passed())
+        }
+        _ => Err(Error::NotSupported(format!(
+            "Launch endorsement verification is not supported for host {}",
+            host
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "host-verification", feature = "tdx-linux"))]
+    #[test]
+    fn test_verify_launch_endorsement_unknown_host() {
+        let result = super::verify_launch_endorsement("unknown-host");
+        assert!(result.unwrap_err().is_not_supported());
+    }
+}
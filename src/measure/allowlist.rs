@@ -0,0 +1,562 @@
+//! # Measurement Allow-Lists
+//!
+//! This module parses operator-managed allow-lists of golden TD
+//! measurements, so that a fleet's verifiers can accept several named sets
+//! of reference values at once (e.g., the current and the next firmware
+//! release during a rollout), each valid for a limited time.
+//!
+//! Allow-lists are TOML files with one `[[entry]]` table per set of
+//! reference values:
+//!
+//! ```toml
+//! [[entry]]
+//! name = "ovmf-2025.05"
+//! mrtd = "<hex-encoded MRTD>"
+//! rtmr1 = "<hex-encoded RTMR1>"
+//! # optional validity window, as ISO 8601 UTC dates
+//! not_before = "2025-06-01T00:00:00Z"
+//! not_after = "2025-12-01T00:00:00Z"
+//!
+//! [entry.annotations]
+//! ticket = "OPS-1234"
+//! ```
+//!
+//! A TD's measurements are allowed if they match all the registers set in
+//! any entry that is valid at the verification time. Verifiers check
+//! evidence against an allow-list with `Policy::with_allow_list()`.
+//!
+//! With the `allowlist-watch` feature, an `AllowListWatcher` watches the
+//! allow-list file with inotify, and reloads it whenever it's replaced, so
+//! that golden values can be rotated without restarting the verification
+//! service.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Policy;
+//! use tdx_workload_attestation::measure::allowlist::AllowList;
+//!
+//! let allow_list = AllowList::from_file("/etc/tdx-workload-attestation/allowlist.toml").unwrap();
+//! let policy = Policy::new().with_allow_list(allow_list);
+//! ```
+//!
+//! # Notes
+//! - If a reloaded allow-list is invalid, the watcher keeps the previous one,
+//!   and reports the error with `AllowListWatcher::last_error()`.
+//! - The watcher watches the file's directory rather than the file, so that
+//!   files replaced by a rename (e.g., by editors, configuration management
+//!   tools, or Kubernetes ConfigMap updates) are reloaded.
+
+use super::ReferenceValues;
+use crate::core::register::MeasurementRegister;
+use crate::error::{Error, Result};
+use crate::evidence::tcb::parse_utc_timestamp;
+
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// An entry of an allow-list.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct AllowListEntry {
+    /// The unique name of the entry.
+    pub name: String,
+    /// The reference values of the entry.
+    #[serde(flatten)]
+    pub values: ReferenceValues,
+    /// The time from which the entry is valid, in seconds since the Unix
+    /// epoch, if any.
+    #[serde(default, deserialize_with = "utc_time")]
+    pub not_before: Option<u64>,
+    /// The time until which the entry is valid, in seconds since the Unix
+    /// epoch, if any.
+    #[serde(default, deserialize_with = "utc_time")]
+    pub not_after: Option<u64>,
+    /// The operator's annotations of the entry (e.g., its change ticket).
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl AllowListEntry {
+    /// Returns whether the entry is valid at `unix_time`.
+    pub fn is_valid_at(&self, unix_time: u64) -> bool {
+        self.not_before.is_none_or(|t| t <= unix_time)
+            && self.not_after.is_none_or(|t| unix_time <= t)
+    }
+
+    /// Returns whether the `mrtd` and `rtmrs` match the entry's reference
+    /// values.
+    pub fn matches(&self, mrtd: &MeasurementRegister, rtmrs: &[MeasurementRegister; 4]) -> bool {
+        self.values.mismatches(mrtd, rtmrs).is_empty()
+    }
+}
+
+/// An allow-list of golden TD measurements.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllowList {
+    /// The entries of the allow-list.
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<AllowListEntry>,
+}
+
+impl AllowList {
+    /// Parses an allow-list from a TOML string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the allow-list is malformed, or if
+    /// an entry has a duplicate name, constrains no register, or has an
+    /// empty validity window.
+    pub fn from_toml(allow_list: &str) -> Result<Self> {
+        let allow_list: Self = toml::from_str(allow_list)
+            .map_err(|e| Error::ParseError(format!("Invalid allow-list: {}", e)))?;
+
+        let mut names = HashSet::new();
+        for entry in &allow_list.entries {
+            if !names.insert(entry.name.as_str()) {
+                return Err(Error::ParseError(format!(
+                    "Duplicate allow-list entry {}",
+                    entry.name
+                )));
+            }
+            if entry.values == ReferenceValues::default() {
+                return Err(Error::ParseError(format!(
+                    "Allow-list entry {} constrains no register",
+                    entry.name
+                )));
+            }
+            if let (Some(not_before), Some(not_after)) = (entry.not_before, entry.not_after)
+                && not_after < not_before
+            {
+                return Err(Error::ParseError(format!(
+                    "Allow-list entry {} expires before it's valid",
+                    entry.name
+                )));
+            }
+        }
+        Ok(allow_list)
+    }
+
+    /// Reads and parses an allow-list file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the file cannot be read, and the
+    /// errors of `from_toml()`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Reads and parses an allow-list file, once its Sigstore bundle (see the
+    /// `verification::sigstore` module) is verified with `verifier`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the allow-list doesn't have a
+    /// trusted signature, the errors of `ArtifactVerifier::read_verified()`,
+    /// and the errors of `from_toml()`.
+    #[cfg(feature = "host-verification")]
+    pub fn from_signed_file(
+        path: impl AsRef<Path>,
+        verifier: &crate::verification::sigstore::ArtifactVerifier,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let allow_list = String::from_utf8(verifier.read_verified(path)?)
+            .map_err(|_| Error::ParseError(format!("{} is not valid UTF-8", path.display())))?;
+        Self::from_toml(&allow_list)
+    }
+
+    /// Returns the first entry valid at `unix_time` that the `mrtd` and
+    /// `rtmrs` match, if any.
+    pub fn find(
+        &self,
+        mrtd: &MeasurementRegister,
+        rtmrs: &[MeasurementRegister; 4],
+        unix_time: u64,
+    ) -> Option<&AllowListEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_valid_at(unix_time) && entry.matches(mrtd, rtmrs))
+    }
+}
+
+/// An allow-list shared with a watcher that may replace it at any time.
+#[derive(Clone, Debug, Default)]
+pub struct SharedAllowList(Arc<RwLock<Arc<AllowList>>>);
+
+impl SharedAllowList {
+    /// Creates a new shared allow-list.
+    pub fn new(allow_list: AllowList) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(allow_list))))
+    }
+
+    /// Returns the current allow-list.
+    pub fn current(&self) -> Arc<AllowList> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replaces the allow-list, returning whether it changed.
+    pub fn replace(&self, allow_list: AllowList) -> bool {
+        let mut current = self.0.write().unwrap_or_else(|e| e.into_inner());
+        if **current == allow_list {
+            return false;
+        }
+        *current = Arc::new(allow_list);
+        true
+    }
+}
+
+impl From<AllowList> for SharedAllowList {
+    fn from(allow_list: AllowList) -> Self {
+        Self::new(allow_list)
+    }
+}
+
+/// Deserializes an optional ISO 8601 UTC date into seconds since the Unix
+/// epoch.
+fn utc_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<u64>, D::Error> {
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    value
+        .map(|v| parse_utc_timestamp(&v).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(all(feature = "allowlist-watch", target_os = "linux"))]
+pub use watch::AllowListWatcher;
+
+#[cfg(all(feature = "allowlist-watch", target_os = "linux"))]
+mod watch {
+    use super::{AllowList, SharedAllowList};
+    use crate::error::{Error, Result};
+
+    use std::ffi::CString;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+
+    // How often the watcher checks whether it was stopped, in milliseconds
+    const POLL_INTERVAL_MS: i32 = 200;
+
+    // The directory events after which the allow-list is reloaded
+    const RELOAD_EVENTS: u32 =
+        libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_DELETE;
+
+    /// A watcher that reloads an allow-list file whenever it changes.
+    ///
+    /// The watcher's thread is stopped when it's dropped.
+    #[derive(Debug)]
+    pub struct AllowListWatcher {
+        allow_list: SharedAllowList,
+        last_error: Arc<Mutex<Option<String>>>,
+        stop: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl AllowListWatcher {
+        /// Loads the allow-list file at `path`, and starts watching it.
+        ///
+        /// # Errors
+        ///
+        /// Returns the errors of `AllowList::from_file()`, or an
+        /// `Error::IoError` if the file's directory cannot be watched.
+        pub fn watch(path: impl AsRef<Path>) -> Result<Self> {
+            let path = path.as_ref().to_path_buf();
+            let allow_list = SharedAllowList::new(AllowList::from_file(&path)?);
+            let inotify = Inotify::watch_dir(&path)?;
+
+            let last_error = Arc::new(Mutex::new(None));
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread = {
+                let (allow_list, last_error, stop) =
+                    (allow_list.clone(), last_error.clone(), stop.clone());
+                std::thread::spawn(move || {
+                    reload_loop(&inotify, &path, &allow_list, &last_error, &stop)
+                })
+            };
+
+            Ok(Self {
+                allow_list,
+                last_error,
+                stop,
+                thread: Some(thread),
+            })
+        }
+
+        /// Returns the watched allow-list, which is shared with the watcher.
+        pub fn allow_list(&self) -> SharedAllowList {
+            self.allow_list.clone()
+        }
+
+        /// Returns the error of the latest reload, if it failed.
+        pub fn last_error(&self) -> Option<String> {
+            self.last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        }
+    }
+
+    impl Drop for AllowListWatcher {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn reload_loop(
+        inotify: &Inotify,
+        path: &Path,
+        allow_list: &SharedAllowList,
+        last_error: &Mutex<Option<String>>,
+        stop: &AtomicBool,
+    ) {
+        while !stop.load(Ordering::Relaxed) {
+            match inotify.wait(POLL_INTERVAL_MS) {
+                Ok(false) => continue,
+                Ok(true) => {}
+                Err(e) => {
+                    *last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+                    return;
+                }
+            }
+
+            // the file may be briefly missing while it's replaced
+            let error = match AllowList::from_file(path) {
+                Ok(reloaded) => {
+                    allow_list.replace(reloaded);
+                    None
+                }
+                Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => Some(e.to_string()),
+            };
+            *last_error.lock().unwrap_or_else(|e| e.into_inner()) = error;
+        }
+    }
+
+    /// An inotify instance watching a directory.
+    struct Inotify {
+        fd: OwnedFd,
+    }
+
+    impl Inotify {
+        /// Watches the directory of the file at `path`.
+        fn watch_dir(path: &Path) -> Result<Self> {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            let dir = CString::new(dir.as_os_str().as_bytes())
+                .map_err(|e| Error::ParseError(format!("Invalid allow-list path: {}", e)))?;
+
+            // SAFETY: inotify_init1 has no memory safety requirements
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            // SAFETY: `fd` is a newly created descriptor that nothing else
+            // owns
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+            // SAFETY: `dir` is a valid NUL-terminated string, and the
+            // descriptor is owned by `fd`
+            let wd =
+                unsafe { libc::inotify_add_watch(fd.as_raw_fd(), dir.as_ptr(), RELOAD_EVENTS) };
+            if wd < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(Self { fd })
+        }
+
+        /// Waits up to `timeout_ms` milliseconds for directory events, and
+        /// returns whether any occurred. Pending events are consumed.
+        fn wait(&self, timeout_ms: i32) -> Result<bool> {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` is valid for reads and writes of one entry
+            let ret = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+            if ret < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+                return Err(err.into());
+            }
+            if ret == 0 {
+                return Ok(false);
+            }
+
+            // events aren't inspected, since the directory's other files
+            // rarely change, and unchanged allow-lists aren't replaced
+            let mut buf = [0u8; 4096];
+            loop {
+                // SAFETY: `buf` is valid for writes of its length, and the
+                // descriptor is owned by `self.fd`
+                let n = unsafe {
+                    libc::read(
+                        self.fd.as_raw_fd(),
+                        buf.as_mut_ptr() as *mut libc::c_void,
+                        buf.len(),
+                    )
+                };
+                if n <= 0 {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOW_LIST: &str = r#"
+        [[entry]]
+        name = "current"
+        mrtd = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+        not_after = "2025-07-01T00:00:00Z"
+
+        [entry.annotations]
+        ticket = "OPS-1"
+
+        [[entry]]
+        name = "next"
+        mrtd = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+        rtmr1 = "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc"
+        not_before = "2025-06-01T00:00:00Z"
+    "#;
+
+    // 2025-06-15, when both entries are valid
+    const ROLLOUT: u64 = 1_749_945_600;
+
+    #[test]
+    fn test_from_toml() -> Result<()> {
+        let allow_list = AllowList::from_toml(ALLOW_LIST)?;
+        assert_eq!(allow_list.entries.len(), 2);
+
+        let current = &allow_list.entries[0];
+        assert_eq!(current.values.mrtd, Some([0xaa; 48].into()));
+        assert_eq!(current.not_before, None);
+        assert_eq!(current.not_after, Some(1_751_328_000));
+        assert_eq!(current.annotations["ticket"], "OPS-1");
+
+        let next = &allow_list.entries[1];
+        assert_eq!(next.values.rtmr1, Some([0xcc; 48].into()));
+        assert_eq!(next.not_before, Some(1_748_736_000));
+        assert!(next.annotations.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        let duplicate =
+            "[[entry]]\nname = \"a\"\nmrtd = \"".to_string() + &"aa".repeat(48) + "\"\n";
+        assert!(AllowList::from_toml(&duplicate).is_ok());
+        assert!(AllowList::from_toml(&duplicate.repeat(2)).is_err());
+
+        // entries must constrain a register
+        assert!(AllowList::from_toml("[[entry]]\nname = \"a\"\n").is_err());
+
+        let window = duplicate.clone()
+            + "not_before = \"2025-06-01T00:00:00Z\"\nnot_after = \"2025-05-01T00:00:00Z\"\n";
+        assert!(AllowList::from_toml(&window).is_err());
+        assert!(AllowList::from_toml(&(duplicate + "not_after = \"soon\"\n")).is_err());
+        assert!(AllowList::from_toml("[[entries]]\n").is_err());
+    }
+
+    #[test]
+    fn test_find() -> Result<()> {
+        let allow_list = AllowList::from_toml(ALLOW_LIST)?;
+        let mut rtmrs = [MeasurementRegister::ZERO; 4];
+
+        let find = |mrtd, rtmrs: &[MeasurementRegister; 4], time| {
+            allow_list
+                .find(&[mrtd; 48].into(), rtmrs, time)
+                .map(|entry| entry.name.as_str())
+        };
+        assert_eq!(find(0xaa, &rtmrs, ROLLOUT), Some("current"));
+        assert_eq!(find(0xbb, &rtmrs, ROLLOUT), None);
+        rtmrs[1] = [0xcc; 48].into();
+        assert_eq!(find(0xbb, &rtmrs, ROLLOUT), Some("next"));
+
+        // after the rollout, only the next entry is valid
+        assert_eq!(find(0xaa, &rtmrs, 1_760_000_000), None);
+        assert_eq!(find(0xbb, &rtmrs, 1_760_000_000), Some("next"));
+        // and before, only the current entry
+        assert_eq!(find(0xbb, &rtmrs, 1_700_000_000), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_allow_list() -> Result<()> {
+        let shared = SharedAllowList::from(AllowList::default());
+        let before = shared.current();
+        assert!(before.entries.is_empty());
+
+        assert!(shared.replace(AllowList::from_toml(ALLOW_LIST)?));
+        assert!(!shared.replace(AllowList::from_toml(ALLOW_LIST)?));
+        assert_eq!(shared.current().entries.len(), 2);
+        assert!(before.entries.is_empty());
+        Ok(())
+    }
+
+    #[cfg(all(feature = "allowlist-watch", target_os = "linux"))]
+    #[test]
+    fn test_watcher() -> Result<()> {
+        use std::time::{Duration, Instant};
+
+        let dir = std::env::temp_dir().join(format!("tdx-allowlist-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("allowlist.toml");
+        let (first, rest) = ALLOW_LIST.split_at(
+            ALLOW_LIST
+                .find("[[entry]]\n        name = \"next\"")
+                .unwrap(),
+        );
+        std::fs::write(&path, first)?;
+
+        let watcher = AllowListWatcher::watch(&path)?;
+        let allow_list = watcher.allow_list();
+        assert_eq!(allow_list.current().entries.len(), 1);
+
+        let wait_for = |condition: &dyn Fn() -> bool| {
+            let start = Instant::now();
+            while !condition() && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            condition()
+        };
+
+        // replaced by a rename
+        let tmp = dir.join("allowlist.toml.tmp");
+        std::fs::write(&tmp, first.to_string() + rest)?;
+        std::fs::rename(&tmp, &path)?;
+        assert!(wait_for(&|| allow_list.current().entries.len() == 2));
+
+        // invalid allow-lists are reported, and not loaded
+        std::fs::write(&path, "[[entry]]\n")?;
+        assert!(wait_for(&|| watcher.last_error().is_some()));
+        assert_eq!(allow_list.current().entries.len(), 2);
+
+        // rewritten in place
+        std::fs::write(&path, first)?;
+        assert!(wait_for(&|| allow_list.current().entries.len() == 1));
+        assert!(watcher.last_error().is_none());
+
+        drop(watcher);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,384 @@
+//! # Boot-Time Workload Measurement Hook
+//!
+//! This module implements a measurement hook intended to run early at boot
+//! (e.g., as a systemd unit, see `tdx-attest boot-hook`), which measures the
+//! kernel command line and a configured list of files and directories into
+//! `RTMR3`, and records them in an event log.
+//!
+//! The hook is configured by a JSON manifest, e.g.:
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "event_log": "/run/tdx-workload-attestation/boot-hook.cbor",
+//!     "cmdline": true,
+//!     "files": ["/etc/app/config.toml"],
+//!     "directories": ["/opt/app"]
+//! }
+//! ```
+//!
+//! Files are measured by the SHA-384 digest of their contents. Directories are
+//! measured by a Merkle digest over their tree (see `directory_digest()`), so
+//! any change to a file name, content, or symlink target within the directory
+//! changes the measurement.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::boot_hook::{BootManifest, run_boot_hook};
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let manifest = BootManifest::from_file("/etc/tdx-workload-attestation/boot-hook.json").unwrap();
+//! let events = run_boot_hook(&mut LinuxTdxProvider::new(), &manifest).unwrap();
+//! println!("Measured {} items into RTMR3", events.len());
+//! ```
+
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+use crate::measure::event_log::{Event, EventLog, EventPayload, RtmrExtender, measure_event};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+
+/// The supported version of the boot hook manifest format.
+pub const BOOT_MANIFEST_VERSION: u32 = 1;
+
+/// The default path of the boot hook manifest.
+pub const DEFAULT_MANIFEST_PATH: &str = "/etc/tdx-workload-attestation/boot-hook.json";
+
+/// The default path of the boot hook's event log.
+pub const DEFAULT_EVENT_LOG_PATH: &str = "/run/tdx-workload-attestation/boot-hook.cbor";
+
+/// The RTMR that boot hook events are extended into.
+pub const BOOT_HOOK_RTMR_INDEX: u8 = 3;
+
+/// The path of the kernel command line.
+pub const CMDLINE_PATH: &str = "/proc/cmdline";
+
+/// The manifest configuring what the boot hook measures.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BootManifest {
+    /// The manifest format version.
+    pub version: u32,
+    /// The path of the event log (defaults to `DEFAULT_EVENT_LOG_PATH`).
+    #[serde(default)]
+    pub event_log: Option<PathBuf>,
+    /// Whether to measure the kernel command line.
+    #[serde(default)]
+    pub cmdline: bool,
+    /// The files to measure, in order.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// The directories to measure, in order.
+    #[serde(default)]
+    pub directories: Vec<PathBuf>,
+}
+
+impl BootManifest {
+    /// Parses and validates a JSON-encoded manifest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the manifest is malformed or invalid
+    /// (see `validate()`).
+    pub fn parse(manifest: &str) -> Result<Self> {
+        let manifest: BootManifest = serde_json::from_str(manifest)
+            .map_err(|e| Error::ParseError(format!("Invalid boot hook manifest: {}", e)))?;
+        manifest.validate()?;
+
+        Ok(manifest)
+    }
+
+    /// Reads, parses and validates the manifest at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        // throw an error if the manifest is a symlink
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
+            )));
+        }
+
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Validates the manifest.
+    ///
+    /// A valid manifest has a supported version, measures at least one item,
+    /// and only contains normalized, absolute paths without duplicates.
+    pub fn validate(&self) -> Result<()> {
+        if self.version != BOOT_MANIFEST_VERSION {
+            return Err(Error::ParseError(format!(
+                "Unsupported boot hook manifest version {}",
+                self.version
+            )));
+        }
+
+        if !self.cmdline && self.files.is_empty() && self.directories.is_empty() {
+            return Err(Error::ParseError(
+                "Boot hook manifest doesn't measure anything".to_string(),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for path in self.files.iter().chain(&self.directories) {
+            validate_path(path)?;
+            if !seen.insert(path) {
+                return Err(Error::ParseError(format!(
+                    "Path {} is listed more than once",
+                    path.display()
+                )));
+            }
+        }
+
+        if let Some(path) = &self.event_log {
+            validate_path(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the event log the boot hook records its events in.
+    pub fn event_log(&self) -> EventLog {
+        EventLog::new(
+            self.event_log
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_EVENT_LOG_PATH)),
+        )
+    }
+}
+
+/// Measures the items in `manifest` into `RTMR3` with `extender`, records them
+/// in the manifest's event log, and returns the recorded events.
+///
+/// Items are measured in order: the kernel command line, then the files, then
+/// the directories. The hook stops at the first item that fails to measure.
+pub fn run_boot_hook<E: RtmrExtender>(
+    extender: &mut E,
+    manifest: &BootManifest,
+) -> Result<Vec<Event>> {
+    manifest.validate()?;
+
+    let log = manifest.event_log();
+    if let Some(dir) = log.path().parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut payloads = vec![];
+    if manifest.cmdline {
+        let cmdline = fs::read_to_string(CMDLINE_PATH)?;
+        payloads.push(EventPayload::BootCmdline {
+            cmdline: cmdline.trim_end().to_string(),
+        });
+    }
+    for path in &manifest.files {
+        payloads.push(EventPayload::BootFile {
+            path: path.display().to_string(),
+            digest: hex::encode(file_digest(path)?),
+        });
+    }
+    for path in &manifest.directories {
+        payloads.push(EventPayload::BootDirectory {
+            path: path.display().to_string(),
+            digest: hex::encode(directory_digest(path)?),
+        });
+    }
+
+    payloads
+        .into_iter()
+        .map(|payload| measure_event(extender, &log, &Event::new(BOOT_HOOK_RTMR_INDEX, payload)))
+        .collect()
+}
+
+/// Computes the SHA-384 digest of a file's contents.
+pub fn file_digest<P: AsRef<Path>>(path: P) -> Result<[u8; SHA384_LEN]> {
+    let path = path.as_ref();
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha384::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
+/// Computes the Merkle digest of a directory tree.
+///
+/// Each node of the tree is hashed as follows:
+/// - a regular file: `SHA-384("F" || SHA-384(contents))`,
+/// - a symlink (which isn't followed): `SHA-384("L" || target)`, and
+/// - a directory: `SHA-384("D" || entries)`, where each entry, in byte order
+///   of the entry names, is encoded as the name's length (as a 32-bit
+///   little-endian integer), followed by the name and the entry's digest.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if the tree contains special files (e.g.,
+/// sockets or device nodes), or if `path` itself is a symlink.
+pub fn directory_digest<P: AsRef<Path>>(path: P) -> Result<[u8; SHA384_LEN]> {
+    let path = path.as_ref();
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+    if !path.is_dir() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is not a directory",
+            path.display()
+        )));
+    }
+
+    node_digest(path)
+}
+
+fn node_digest(path: &Path) -> Result<[u8; SHA384_LEN]> {
+    let file_type = fs::symlink_metadata(path)?.file_type();
+    let mut hasher = Sha384::new();
+
+    if file_type.is_symlink() {
+        hasher.update(b"L");
+        hasher.update(fs::read_link(path)?.as_os_str().as_bytes());
+    } else if file_type.is_file() {
+        hasher.update(b"F");
+        hasher.update(file_digest(path)?);
+    } else if file_type.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+        hasher.update(b"D");
+        for name in entries {
+            let name_bytes = name.as_bytes();
+            hasher.update((name_bytes.len() as u32).to_le_bytes());
+            hasher.update(name_bytes);
+            hasher.update(node_digest(&path.join(&name))?);
+        }
+    } else {
+        return Err(Error::NotSupported(format!(
+            "Path {} is not a regular file, directory or symlink",
+            path.display()
+        )));
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+fn validate_path(path: &Path) -> Result<()> {
+    let normalized = path
+        .components()
+        .all(|c| matches!(c, Component::RootDir | Component::Normal(_)));
+
+    if !path.is_absolute() || !normalized {
+        return Err(Error::ParseError(format!(
+            "Path {} must be absolute and normalized",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::event_log::tests::SoftRtmrs;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tdx-boot-hook-{}", rand::random::<u64>()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), b"a").unwrap();
+        fs::write(dir.join("sub").join("b.txt"), b"b").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_manifest() -> Result<()> {
+        let manifest =
+            BootManifest::parse(r#"{"version": 1, "cmdline": true, "files": ["/etc/hostname"]}"#)?;
+        assert!(manifest.cmdline);
+        assert_eq!(
+            manifest.event_log().path(),
+            Path::new(DEFAULT_EVENT_LOG_PATH)
+        );
+
+        // invalid manifests
+        for invalid in [
+            r#"{"version": 2, "cmdline": true}"#,
+            r#"{"version": 1}"#,
+            r#"{"version": 1, "files": ["etc/hostname"]}"#,
+            r#"{"version": 1, "files": ["/etc/../etc/hostname"]}"#,
+            r#"{"version": 1, "files": ["/etc/hostname", "/etc/hostname"]}"#,
+            r#"{"version": 1, "cmdline": true, "unknown": 1}"#,
+        ] {
+            assert!(BootManifest::parse(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory_digest() -> Result<()> {
+        let dir = temp_dir();
+        let digest = directory_digest(&dir)?;
+        assert_eq!(directory_digest(&dir)?, digest);
+
+        // changing a nested file changes the digest
+        fs::write(dir.join("sub").join("b.txt"), b"c")?;
+        let changed = directory_digest(&dir)?;
+        assert_ne!(changed, digest);
+
+        // so does renaming a file
+        fs::rename(dir.join("a.txt"), dir.join("z.txt"))?;
+        assert_ne!(directory_digest(&dir)?, changed);
+
+        assert!(directory_digest(dir.join("z.txt")).is_err());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_boot_hook() -> Result<()> {
+        let dir = temp_dir();
+        let manifest = BootManifest {
+            version: BOOT_MANIFEST_VERSION,
+            event_log: Some(dir.join("log").join("boot-hook.cbor")),
+            cmdline: true,
+            files: vec![dir.join("a.txt")],
+            directories: vec![dir.join("sub")],
+        };
+
+        let mut rtmrs = SoftRtmrs::default();
+        let events = run_boot_hook(&mut rtmrs, &manifest)?;
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].payload.event_type(), "boot-cmdline");
+        assert_eq!(
+            events[1].payload.measured_data(),
+            format!(
+                "{} {}",
+                dir.join("a.txt").display(),
+                hex::encode(Sha384::digest(b"a"))
+            )
+        );
+
+        let log = manifest.event_log();
+        assert_eq!(log.events()?, events);
+        assert_eq!(log.replay()?, rtmrs.rtmrs);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! # Confidential Computing Event Log (CCEL) Parsing
+//!
+//! This module parses the Confidential Computing Event Log (CCEL), in which
+//! the TD's firmware (TDVF) records the measurements it extends into
+//! `RTMR0`-`RTMR2` during boot (and the bootloader and kernel may record
+//! further measurements). The guest exposes the log in
+//! `/sys/firmware/acpi/tables/data/CCEL`.
+//!
+//! The CCEL uses the TCG crypto-agile event log format: a legacy
+//! `TCG_PCClientPCREvent` header holding the Spec ID event, which lists the
+//! digest algorithms in the log, followed by `TCG_PCR_EVENT2` entries. In the
+//! CCEL, the "PCR index" of each entry is the CC measurement register index,
+//! where index 0 is `MRTD` and indices 1-4 are `RTMR0`-`RTMR3`.
+//!
+//! Replaying the log yields the expected RTMR values, which verifiers can
+//! compare against the RTMRs in the TD's quote.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::ccel::{CCEL_DATA_PATH, parse_ccel, replay_ccel};
+//!
+//! let log = std::fs::read(CCEL_DATA_PATH).unwrap();
+//! let rtmrs = replay_ccel(&parse_ccel(&log).unwrap());
+//! println!("Expected RTMR0: {}", hex::encode(rtmrs[0]));
+//! ```
+
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+use crate::measure::event_log::NUM_RTMRS;
+use crate::measure::predict::extend_rtmr;
+
+/// The path to the CCEL data in the guest.
+pub const CCEL_DATA_PATH: &str = "/sys/firmware/acpi/tables/data/CCEL";
+
+/// The TCG algorithm ID of SHA-384.
+pub const TPM_ALG_SHA384: u16 = 0x000c;
+
+/// The event type of events that aren't extended into any register.
+pub const EV_NO_ACTION: u32 = 0x0000_0003;
+
+// The signature of the Spec ID event
+const SPEC_ID_SIGNATURE: &[u8; 16] = b"Spec ID Event03\0";
+
+// The length of the SHA-1 digest in the legacy event header
+const SHA1_LEN: usize = 20;
+
+/// An event in the CCEL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CcelEvent {
+    /// The CC measurement register index (0 is `MRTD`, 1-4 are
+    /// `RTMR0`-`RTMR3`).
+    pub mr_index: u32,
+    /// The TCG event type.
+    pub event_type: u32,
+    /// The SHA-384 digest of the event, if the event has one.
+    pub digest: Option<[u8; SHA384_LEN]>,
+    /// The event data.
+    pub data: Vec<u8>,
+}
+
+impl CcelEvent {
+    /// Returns the index of the RTMR the event was extended into, if any.
+    pub fn rtmr_index(&self) -> Option<usize> {
+        if self.event_type == EV_NO_ACTION {
+            return None;
+        }
+        match self.mr_index as usize {
+            i @ 1..=NUM_RTMRS => Some(i - 1),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a CCEL.
+///
+/// The log ends at the end of `log` or at the first unused entry (the CCEL
+/// area is padded with `0xff` or `0x00` bytes).
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the log is malformed or truncated, or
+/// doesn't record SHA-384 digests.
+pub fn parse_ccel(log: &[u8]) -> Result<Vec<CcelEvent>> {
+    let mut reader = Reader::new(log);
+
+    // the legacy header holds the Spec ID event
+    let _mr_index = reader.u32()?;
+    let _event_type = reader.u32()?;
+    reader.take(SHA1_LEN)?;
+    let spec_len = reader.u32()? as usize;
+    let algorithms = parse_spec_id(reader.take(spec_len)?)?;
+    if !algorithms.iter().any(|(id, _)| *id == TPM_ALG_SHA384) {
+        return Err(Error::ParseError(
+            "CCEL does not record SHA-384 digests".to_string(),
+        ));
+    }
+
+    let mut events = vec![];
+    while reader.remaining() >= 8 {
+        let mr_index = reader.u32()?;
+        let event_type = reader.u32()?;
+        if is_padding(mr_index, event_type) {
+            break;
+        }
+
+        let count = reader.u32()?;
+        let mut digest = None;
+        for _ in 0..count {
+            let id = reader.u16()?;
+            let size = algorithms
+                .iter()
+                .find(|(a, _)| *a == id)
+                .map(|(_, size)| *size as usize)
+                .ok_or_else(|| {
+                    Error::ParseError(format!("Unknown CCEL digest algorithm {:#x}", id))
+                })?;
+            let bytes = reader.take(size)?;
+            if id == TPM_ALG_SHA384 {
+                digest = Some(bytes.try_into().map_err(|_| {
+                    Error::ParseError("Invalid CCEL SHA-384 digest size".to_string())
+                })?);
+            }
+        }
+
+        let data_len = reader.u32()? as usize;
+        let data = reader.take(data_len)?.to_vec();
+
+        events.push(CcelEvent {
+            mr_index,
+            event_type,
+            digest,
+            data,
+        });
+    }
+
+    Ok(events)
+}
+
+/// Replays the events of a CCEL into initially all-zero RTMRs.
+pub fn replay_ccel(events: &[CcelEvent]) -> [[u8; SHA384_LEN]; NUM_RTMRS] {
+    let mut rtmrs = [[0u8; SHA384_LEN]; NUM_RTMRS];
+    for event in events {
+        if let (Some(index), Some(digest)) = (event.rtmr_index(), &event.digest) {
+            rtmrs[index] = extend_rtmr(&rtmrs[index], digest);
+        }
+    }
+    rtmrs
+}
+
+/// Parses the Spec ID event, and returns the algorithm IDs and digest sizes
+/// used in the log.
+fn parse_spec_id(event: &[u8]) -> Result<Vec<(u16, u16)>> {
+    let mut reader = Reader::new(event);
+    if reader.take(SPEC_ID_SIGNATURE.len())? != SPEC_ID_SIGNATURE {
+        return Err(Error::ParseError(
+            "CCEL does not start with a Spec ID event".to_string(),
+        ));
+    }
+
+    // platform class, spec version (minor, major, errata) and uintn size
+    reader.take(8)?;
+
+    let count = reader.u32()?;
+    (0..count)
+        .map(|_| Ok((reader.u16()?, reader.u16()?)))
+        .collect()
+}
+
+fn is_padding(mr_index: u32, event_type: u32) -> bool {
+    (mr_index == u32::MAX && event_type == u32::MAX) || (mr_index == 0 && event_type == 0)
+}
+
+/// A bounds-checked little-endian reader over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(Error::ParseError("CCEL is truncated".to_string()));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(
+            self.take(2)?.try_into().expect("2 bytes"),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(
+            self.take(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a CCEL with the given (MR index, event type, SHA-384 digest)
+    /// events, padded with `0xff` bytes.
+    pub(crate) fn make_ccel(events: &[(u32, u32, [u8; SHA384_LEN])]) -> Vec<u8> {
+        let mut spec = SPEC_ID_SIGNATURE.to_vec();
+        spec.extend([0; 4]); // platform class
+        spec.extend([0, 2, 0, 2]); // spec version 2.0, uintn size
+        spec.extend(2u32.to_le_bytes());
+        spec.extend(0x0004u16.to_le_bytes()); // SHA-1
+        spec.extend(20u16.to_le_bytes());
+        spec.extend(TPM_ALG_SHA384.to_le_bytes());
+        spec.extend((SHA384_LEN as u16).to_le_bytes());
+        spec.push(0); // vendor info size
+
+        let mut log = vec![];
+        log.extend(0u32.to_le_bytes());
+        log.extend(EV_NO_ACTION.to_le_bytes());
+        log.extend([0; SHA1_LEN]);
+        log.extend((spec.len() as u32).to_le_bytes());
+        log.extend(spec);
+
+        for (mr_index, event_type, digest) in events {
+            log.extend(mr_index.to_le_bytes());
+            log.extend(event_type.to_le_bytes());
+            log.extend(2u32.to_le_bytes());
+            log.extend(0x0004u16.to_le_bytes());
+            log.extend([0; 20]);
+            log.extend(TPM_ALG_SHA384.to_le_bytes());
+            log.extend(digest);
+            log.extend(4u32.to_le_bytes());
+            log.extend(b"data");
+        }
+
+        log.extend([0xff; 64]);
+        log
+    }
+
+    #[test]
+    fn test_parse_and_replay_ccel() -> Result<()> {
+        let log = make_ccel(&[
+            (1, 0x8000_0001, [1; SHA384_LEN]),
+            (2, 0x8000_0002, [2; SHA384_LEN]),
+            (2, EV_NO_ACTION, [3; SHA384_LEN]),
+            (0, 0x8000_0003, [4; SHA384_LEN]),
+        ]);
+
+        let events = parse_ccel(&log)?;
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[1].digest, Some([2; SHA384_LEN]));
+        assert_eq!(events[1].data, b"data");
+        assert_eq!(events[1].rtmr_index(), Some(1));
+        assert_eq!(events[2].rtmr_index(), None);
+        assert_eq!(events[3].rtmr_index(), None);
+
+        let rtmrs = replay_ccel(&events);
+        assert_eq!(rtmrs[0], extend_rtmr(&[0; SHA384_LEN], &[1; SHA384_LEN]));
+        assert_eq!(rtmrs[1], extend_rtmr(&[0; SHA384_LEN], &[2; SHA384_LEN]));
+        assert_eq!(rtmrs[2], [0; SHA384_LEN]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_ccel() {
+        let log = make_ccel(&[(1, 1, [1; SHA384_LEN])]);
+
+        assert!(parse_ccel(&[]).is_err());
+        assert!(parse_ccel(&log[..log.len() - 100]).is_err());
+
+        let mut bad_spec = log.clone();
+        bad_spec[32] = b'X';
+        assert!(parse_ccel(&bad_spec).is_err());
+    }
+}
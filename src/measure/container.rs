@@ -0,0 +1,543 @@
+//! # Container Image Measurement
+//!
+//! This module measures OCI container images from a local image store into
+//! `RTMR3`, so verifiers can check which container images a TD's workload was
+//! launched from.
+//!
+//! An image is measured by its manifest digest, config digest and layer
+//! digests. The contents of the manifest, the config and (where the store
+//! keeps them) the layers are hashed and checked against their digests, so a
+//! tampered store cannot produce a measurement of a different image.
+//!
+//! The following local image stores are supported:
+//! - OCI image layout directories (e.g., created with `skopeo copy` or
+//!   `podman save --format oci-dir`),
+//! - the containerd content store, and
+//! - the podman (`containers/storage`) image store.
+//!
+//! Each image is recorded in the event log as a `container-image` event, whose
+//! payload is the `ImageMeasurement`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::container::{ImageStore, measure_container_image};
+//! use tdx_workload_attestation::measure::event_log::EventLog;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let mut provider = LinuxTdxProvider::new();
+//! let log = EventLog::new("/var/lib/tdx-workload-attestation/events.cbor");
+//! let store = ImageStore::podman();
+//!
+//! let image =
+//!     measure_container_image(&mut provider, &log, &store, "docker.io/library/nginx:latest")
+//!         .unwrap();
+//! println!("Measured image with manifest {}", image.manifest);
+//! ```
+//!
+//! # Notes
+//! - Multi-platform images are resolved to the manifest for the current
+//!   platform (Linux and the architecture this crate was compiled for).
+//! - The containerd content store has no index of image names, so images in
+//!   it must be referenced by manifest digest (`sha256:<hex>`).
+//! - The podman store keeps layers unpacked, so their digests are taken from
+//!   the (verified) manifest rather than recomputed.
+
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+use crate::measure::event_log::{Event, EventLog, EventPayload, RtmrExtender, measure_event};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The default path of the containerd content store.
+pub const CONTAINERD_CONTENT_PATH: &str = "/var/lib/containerd/io.containerd.content.v1.content";
+
+/// The default path of the (rootful) podman image store.
+pub const PODMAN_STORAGE_PATH: &str = "/var/lib/containers/storage";
+
+/// The RTMR that container image events are extended into.
+pub const CONTAINER_RTMR_INDEX: u8 = 3;
+
+// The OCI annotation holding an image's reference name in an image index
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+/// A local container image store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageStore {
+    /// An OCI image layout directory.
+    OciLayout(PathBuf),
+    /// A containerd content store.
+    Containerd(PathBuf),
+    /// A podman (`containers/storage`) store.
+    Podman(PathBuf),
+}
+
+/// The digests of a measured container image.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageMeasurement {
+    /// The reference the image was looked up by.
+    pub reference: String,
+    /// The digest of the image manifest.
+    pub manifest: String,
+    /// The digest of the image config.
+    pub config: String,
+    /// The digests of the image layers, in order.
+    pub layers: Vec<String>,
+}
+
+impl ImageMeasurement {
+    /// Converts the measurement into an `RTMR3` event.
+    pub fn to_event(&self) -> Event {
+        Event::new(
+            CONTAINER_RTMR_INDEX,
+            EventPayload::ContainerImage(self.clone()),
+        )
+    }
+
+    /// Returns the SHA-384 digest of the measurement, as extended into
+    /// `RTMR3`.
+    pub fn digest(&self) -> [u8; SHA384_LEN] {
+        self.to_event().digest
+    }
+}
+
+/// An OCI content descriptor.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    digest: String,
+    #[serde(default)]
+    platform: Option<Platform>,
+    #[serde(default)]
+    annotations: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    os: String,
+    architecture: String,
+}
+
+/// An OCI image manifest, or image index (manifest list).
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    config: Option<Descriptor>,
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+/// An entry of the podman store's image list.
+#[derive(Debug, Deserialize)]
+struct PodmanImage {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+}
+
+impl ImageStore {
+    /// Returns the containerd content store at its default path.
+    pub fn containerd() -> Self {
+        ImageStore::Containerd(PathBuf::from(CONTAINERD_CONTENT_PATH))
+    }
+
+    /// Returns the podman image store at its default path.
+    pub fn podman() -> Self {
+        ImageStore::Podman(PathBuf::from(PODMAN_STORAGE_PATH))
+    }
+
+    /// Measures the image with the given reference (an image name, or a
+    /// manifest digest of the form `sha256:<hex>`).
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the image cannot be found in the store.
+    /// - `Error::VerificationError` if the contents of a blob don't match its
+    ///   digest.
+    /// - `Error::ParseError` if a manifest cannot be parsed.
+    pub fn measure_image(&self, reference: &str) -> Result<ImageMeasurement> {
+        match self {
+            ImageStore::OciLayout(root) => {
+                let digest = if is_digest(reference) {
+                    reference.to_string()
+                } else {
+                    find_in_oci_index(root, reference)?
+                };
+                measure_from_blobs(root, reference, &digest)
+            }
+            ImageStore::Containerd(root) => {
+                if !is_digest(reference) {
+                    return Err(Error::NotSupported(format!(
+                        "Images in the containerd content store must be referenced by digest, got {}",
+                        reference
+                    )));
+                }
+                measure_from_blobs(root, reference, reference)
+            }
+            ImageStore::Podman(root) => measure_from_podman(root, reference),
+        }
+    }
+}
+
+/// Measures a container image from `store` into `RTMR3`, and records it in
+/// the event log.
+pub fn measure_container_image<E: RtmrExtender>(
+    extender: &mut E,
+    log: &EventLog,
+    store: &ImageStore,
+    reference: &str,
+) -> Result<ImageMeasurement> {
+    let image = store.measure_image(reference)?;
+    measure_event(extender, log, &image.to_event())?;
+
+    Ok(image)
+}
+
+/// Measures an image from a store that keeps all content in
+/// `<root>/blobs/<algorithm>/<hex>`.
+fn measure_from_blobs(root: &Path, reference: &str, digest: &str) -> Result<ImageMeasurement> {
+    let manifest_digest = resolve_platform_manifest(root, digest)?;
+    let manifest: Manifest = parse_json(&read_blob(root, &manifest_digest)?)?;
+
+    let config = manifest
+        .config
+        .ok_or_else(|| Error::ParseError(format!("Manifest {} has no config", manifest_digest)))?;
+    read_blob(root, &config.digest)?;
+
+    let layers = manifest
+        .layers
+        .into_iter()
+        .map(|layer| {
+            verify_blob_file(&blob_path(root, &layer.digest)?, &layer.digest)?;
+            Ok(layer.digest)
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    Ok(ImageMeasurement {
+        reference: reference.to_string(),
+        manifest: manifest_digest,
+        config: config.digest,
+        layers,
+    })
+}
+
+/// Resolves an image index to the manifest for the current platform. Returns
+/// `digest` if it refers to an image manifest.
+fn resolve_platform_manifest(root: &Path, digest: &str) -> Result<String> {
+    let manifest: Manifest = parse_json(&read_blob(root, digest)?)?;
+    if manifest.manifests.is_empty() {
+        return Ok(digest.to_string());
+    }
+
+    let arch = oci_architecture();
+    manifest
+        .manifests
+        .into_iter()
+        .find(|m| {
+            m.platform
+                .as_ref()
+                .is_some_and(|p| p.os == "linux" && p.architecture == arch)
+        })
+        .map(|m| m.digest)
+        .ok_or_else(|| {
+            Error::NotSupported(format!(
+                "Image index {} has no manifest for linux/{}",
+                digest, arch
+            ))
+        })
+}
+
+/// Looks up the manifest digest of an image by name in an OCI layout's
+/// `index.json`. Since layouts typically hold the tags of a single
+/// repository, the reference name may also be just the image's tag.
+fn find_in_oci_index(root: &Path, name: &str) -> Result<String> {
+    let index: Manifest = parse_json(&read_file(&root.join("index.json"))?)?;
+
+    index
+        .manifests
+        .into_iter()
+        .find(|m| {
+            m.annotations
+                .get(REF_NAME_ANNOTATION)
+                .is_some_and(|n| n == name || name.ends_with(&format!(":{}", n)))
+        })
+        .map(|m| m.digest)
+        .ok_or_else(|| Error::NotSupported(format!("Image {} not found in OCI layout", name)))
+}
+
+/// Measures an image from a podman (`containers/storage`) store.
+fn measure_from_podman(root: &Path, reference: &str) -> Result<ImageMeasurement> {
+    let images_dir = root.join("overlay-images");
+    let images: Vec<PodmanImage> = parse_json(&read_file(&images_dir.join("images.json"))?)?;
+
+    let id = reference.strip_prefix("sha256:").unwrap_or(reference);
+    let image = images
+        .iter()
+        .find(|i| i.id == id || i.names.iter().any(|n| n == reference))
+        .ok_or_else(|| {
+            Error::NotSupported(format!("Image {} not found in podman store", reference))
+        })?;
+
+    let image_dir = images_dir.join(&image.id);
+    let manifest_bytes = read_file(&image_dir.join("manifest"))?;
+    let manifest_digest = format!("sha256:{}", hex::encode(Sha256::digest(&manifest_bytes)));
+    let manifest: Manifest = parse_json(&manifest_bytes)?;
+
+    let config = manifest
+        .config
+        .ok_or_else(|| Error::ParseError(format!("Manifest {} has no config", manifest_digest)))?;
+
+    // the image ID is the config digest, and the config is stored under the
+    // base64-encoded digest as a key
+    let config_digest = format!("sha256:{}", image.id);
+    if config.digest != config_digest {
+        return Err(Error::VerificationError(format!(
+            "Manifest config digest {} does not match image ID {}",
+            config.digest, image.id
+        )));
+    }
+    let config_path = image_dir.join(format!("={}", STANDARD.encode(&config_digest)));
+    verify_blob_file(&config_path, &config_digest)?;
+
+    Ok(ImageMeasurement {
+        reference: reference.to_string(),
+        manifest: manifest_digest,
+        config: config_digest,
+        layers: manifest.layers.into_iter().map(|l| l.digest).collect(),
+    })
+}
+
+fn is_digest(reference: &str) -> bool {
+    reference
+        .strip_prefix("sha256:")
+        .is_some_and(|h| h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Returns the path of the blob with the given digest.
+fn blob_path(root: &Path, digest: &str) -> Result<PathBuf> {
+    if !is_digest(digest) {
+        return Err(Error::NotSupported(format!(
+            "Unsupported blob digest {}",
+            digest
+        )));
+    }
+    let (algorithm, hex) = digest.split_once(':').unwrap_or_default();
+    Ok(root.join("blobs").join(algorithm).join(hex))
+}
+
+/// Reads the blob with the given digest, and checks its contents.
+fn read_blob(root: &Path, digest: &str) -> Result<Vec<u8>> {
+    let bytes = read_file(&blob_path(root, digest)?)?;
+    let actual = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+    if actual != digest {
+        return Err(Error::VerificationError(format!(
+            "Blob {} has digest {}",
+            digest, actual
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Checks the contents of a (potentially large) blob file, without reading
+/// it into memory.
+fn verify_blob_file(path: &Path, digest: &str) -> Result<()> {
+    reject_symlink(path)?;
+    let mut file = fs::File::open(path).map_err(|e| {
+        Error::NotSupported(format!("Failed to open blob {}: {}", path.display(), e))
+    })?;
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    let actual = format!("sha256:{}", hex::encode(hasher.finalize()));
+    if actual != digest {
+        return Err(Error::VerificationError(format!(
+            "Blob {} has digest {}",
+            digest, actual
+        )));
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    reject_symlink(path)?;
+    fs::read(path)
+        .map_err(|e| Error::NotSupported(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+fn reject_symlink(path: &Path) -> Result<()> {
+    // throw an error if the path is a symlink
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+fn parse_json<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T> {
+    serde_json::from_slice(bytes).map_err(|e| Error::ParseError(format!("Invalid JSON: {}", e)))
+}
+
+/// Returns the OCI name of the architecture this crate was compiled for.
+fn oci_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        arch => arch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::event_log::tests::{SoftRtmrs, temp_log};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tdx-images-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_blob(root: &Path, content: &[u8]) -> String {
+        let hex = hex::encode(Sha256::digest(content));
+        let dir = root.join("blobs").join("sha256");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(&hex), content).unwrap();
+        format!("sha256:{}", hex)
+    }
+
+    fn manifest_json(config: &str, layers: &[&str]) -> String {
+        let layers: Vec<String> = layers
+            .iter()
+            .map(|l| format!(r#"{{"mediaType":"application/vnd.oci.image.layer.v1.tar+gzip","digest":"{}","size":1}}"#, l))
+            .collect();
+        format!(
+            r#"{{"schemaVersion":2,"config":{{"mediaType":"application/vnd.oci.image.config.v1+json","digest":"{}","size":1}},"layers":[{}]}}"#,
+            config,
+            layers.join(",")
+        )
+    }
+
+    // Creates an OCI layout with a single-layer image named "app:1.0"
+    fn make_oci_layout() -> (PathBuf, String) {
+        let root = temp_dir();
+        let config = write_blob(&root, br#"{"architecture":"amd64","os":"linux"}"#);
+        let layer = write_blob(&root, b"layer contents");
+        let manifest = write_blob(&root, manifest_json(&config, &[&layer]).as_bytes());
+
+        let index = format!(
+            r#"{{"schemaVersion":2,"manifests":[{{"mediaType":"application/vnd.oci.image.manifest.v1+json","digest":"{}","size":1,"annotations":{{"{}":"1.0"}}}}]}}"#,
+            manifest, REF_NAME_ANNOTATION
+        );
+        fs::write(root.join("index.json"), index).unwrap();
+        (root, manifest)
+    }
+
+    #[test]
+    fn test_measure_oci_layout() -> Result<()> {
+        let (root, manifest) = make_oci_layout();
+        let store = ImageStore::OciLayout(root.clone());
+
+        let image = store.measure_image("app:1.0")?;
+        assert_eq!(image.manifest, manifest);
+        assert_eq!(image.layers.len(), 1);
+        assert_eq!(store.measure_image(&manifest)?.config, image.config);
+        assert!(store.measure_image("app:2.0").is_err());
+
+        // tamper with the layer
+        fs::write(blob_path(&root, &image.layers[0])?, b"tampered")?;
+        match store.measure_image("app:1.0") {
+            Err(Error::VerificationError(_)) => {}
+            other => panic!("Expected VerificationError, got {:?}", other),
+        }
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_image_index() -> Result<()> {
+        let (root, manifest) = make_oci_layout();
+        let index = format!(
+            r#"{{"schemaVersion":2,"manifests":[{{"digest":"{}","platform":{{"os":"linux","architecture":"{}"}}}}]}}"#,
+            manifest,
+            oci_architecture()
+        );
+        let index_digest = write_blob(&root, index.as_bytes());
+
+        let store = ImageStore::Containerd(root.clone());
+        assert_eq!(store.measure_image(&index_digest)?.manifest, manifest);
+        assert!(store.measure_image("app:1.0").is_err());
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_podman() -> Result<()> {
+        let root = temp_dir();
+        let config = br#"{"architecture":"amd64","os":"linux"}"#;
+        let id = hex::encode(Sha256::digest(config));
+        let config_digest = format!("sha256:{}", id);
+        let manifest = manifest_json(&config_digest, &["sha256:abcd"]);
+
+        let image_dir = root.join("overlay-images").join(&id);
+        fs::create_dir_all(&image_dir)?;
+        fs::write(
+            root.join("overlay-images").join("images.json"),
+            format!(r#"[{{"id":"{}","names":["localhost/app:latest"]}}]"#, id),
+        )?;
+        fs::write(image_dir.join("manifest"), &manifest)?;
+        fs::write(
+            image_dir.join(format!("={}", STANDARD.encode(&config_digest))),
+            config,
+        )?;
+
+        let store = ImageStore::Podman(root.clone());
+        let image = store.measure_image("localhost/app:latest")?;
+        assert_eq!(image.config, config_digest);
+        assert_eq!(image.layers, vec!["sha256:abcd".to_string()]);
+        assert_eq!(
+            image.manifest,
+            format!(
+                "sha256:{}",
+                hex::encode(Sha256::digest(manifest.as_bytes()))
+            )
+        );
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_container_image() -> Result<()> {
+        let (root, _) = make_oci_layout();
+        let store = ImageStore::OciLayout(root.clone());
+        let log = temp_log();
+        let mut rtmrs = SoftRtmrs::default();
+
+        let image = measure_container_image(&mut rtmrs, &log, &store, "app:1.0")?;
+
+        let events = log.events()?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].payload,
+            EventPayload::ContainerImage(image.clone())
+        );
+        assert_eq!(events[0].digest, image.digest());
+        assert_eq!(log.replay()?, rtmrs.rtmrs);
+
+        fs::remove_dir_all(&root)?;
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,884 @@
+//! # Runtime Measurement Event Log
+//!
+//! This module defines the canonical event log for measurements that are
+//! extended into the TD's runtime measurement registers (RTMRs) after boot,
+//! which is written by all measurement APIs in this crate (e.g., IMA
+//! ingestion, container image measurement and the boot hook).
+//!
+//! Each `Event` records its index in the log, the RTMR it was extended into,
+//! the SHA-384 digest that was extended, and a typed `EventPayload` describing
+//! what was measured. Verifiers can recompute each event's digest from its
+//! payload, and replay the log to compare the result against the RTMRs in the
+//! TD's report.
+//!
+//! Events are measured through an `RtmrExtender`, which is implemented for
+//! `LinuxTdxProvider` (when compiled with the `tdx-linux` feature).
+//!
+//! ## Format
+//!
+//! Event logs are encoded in CBOR. On disk, a log is a CBOR sequence of a
+//! header (holding the format version) followed by one `Event` per
+//! measurement, so events can be appended without rewriting the log. For
+//! exchange with verifiers, a log is exported as a single CBOR map containing
+//! the format version and the list of events (see `export_events()` and
+//! `import_events()`).
+//!
+//! The format is versioned by `EVENT_LOG_VERSION`, and readers reject logs
+//! with a different version.
+//!
+//! Logs can grow to tens of MB (e.g., with IMA), so they're read
+//! incrementally: an `EventReader` decodes one event at a time from any
+//! `io::Read`, and `RtmrReplay` replays events as they're read, so reading,
+//! appending to and replaying a log only hold one event in memory.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::event_log::{
+//!     Event, EventLog, EventPayload, import_events, measure_event, replay,
+//! };
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let mut provider = LinuxTdxProvider::new();
+//! let log = EventLog::new("/var/lib/tdx-workload-attestation/events.cbor");
+//!
+//! let payload = EventPayload::Custom {
+//!     event_type: "config".to_string(),
+//!     data: "/etc/app/config.toml: v1".to_string(),
+//! };
+//! measure_event(&mut provider, &log, &Event::new(3, payload)).unwrap();
+//!
+//! // On the verifier, import the exported log and replay it
+//! let exported = log.export().unwrap();
+//! let rtmrs = replay(&import_events(&exported).unwrap()).unwrap();
+//! println!("Expected RTMR3: {}", hex::encode(rtmrs[3]));
+//! ```
+
+use crate::core::ct;
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+use crate::measure::container::ImageMeasurement;
+use crate::measure::predict::extend_rtmr;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The number of runtime measurement registers (RTMRs).
+pub const NUM_RTMRS: usize = 4;
+
+/// The version of the event log format.
+pub const EVENT_LOG_VERSION: u32 = 1;
+
+// The magic string identifying event log files
+const EVENT_LOG_MAGIC: &str = "tdx-event-log";
+
+/// An interface for extending a TD's runtime measurement registers.
+pub trait RtmrExtender {
+    /// Extends `RTMR[index]` with a SHA-384 digest.
+    fn extend_rtmr(&mut self, index: u8, digest: &[u8; SHA384_LEN]) -> Result<()>;
+}
+
+#[cfg(feature = "tdx-linux")]
+impl RtmrExtender for crate::tdx::LinuxTdxProvider {
+    fn extend_rtmr(&mut self, index: u8, digest: &[u8; SHA384_LEN]) -> Result<()> {
+        crate::tdx::LinuxTdxProvider::extend_rtmr(self, index, digest)
+    }
+}
+
+/// A description of what an event measured.
+///
+/// The digest extended into the RTMR for an event is the SHA-384 digest of
+/// the payload's measured data (see `measured_data()`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum EventPayload {
+    /// An entry of the IMA runtime measurement list.
+    Ima {
+        /// The entry's line in the ASCII measurement list.
+        line: String,
+    },
+    /// A container image.
+    ContainerImage(ImageMeasurement),
+    /// The kernel command line, measured by the boot hook.
+    BootCmdline {
+        /// The kernel command line.
+        cmdline: String,
+    },
+    /// A file, measured by the boot hook.
+    BootFile {
+        /// The file's path.
+        path: String,
+        /// The hex-encoded SHA-384 digest of the file's contents.
+        digest: String,
+    },
+    /// A directory, measured by the boot hook.
+    BootDirectory {
+        /// The directory's path.
+        path: String,
+        /// The hex-encoded Merkle digest of the directory tree.
+        digest: String,
+    },
+    /// A program launched by the measured launcher (see the
+    /// `measure::launch` module).
+    Exec {
+        /// The program's path.
+        path: String,
+        /// The hex-encoded SHA-384 digest of the program.
+        digest: String,
+        /// The program's arguments, excluding the program itself.
+        args: Vec<String>,
+    },
+    /// An application-defined measurement.
+    Custom {
+        /// The application-defined type of the measurement.
+        event_type: String,
+        /// The measured data.
+        data: String,
+    },
+}
+
+impl EventPayload {
+    /// Returns the type of the payload, as used in its encoding.
+    pub fn event_type(&self) -> &str {
+        match self {
+            EventPayload::Ima { .. } => "ima",
+            EventPayload::ContainerImage(_) => "container-image",
+            EventPayload::BootCmdline { .. } => "boot-cmdline",
+            EventPayload::BootFile { .. } => "boot-file",
+            EventPayload::BootDirectory { .. } => "boot-directory",
+            EventPayload::Exec { .. } => "exec",
+            EventPayload::Custom { event_type, .. } => event_type,
+        }
+    }
+
+    /// Returns the data whose SHA-384 digest is extended into the RTMR.
+    pub fn measured_data(&self) -> String {
+        match self {
+            EventPayload::Ima { line } => line.clone(),
+            EventPayload::ContainerImage(image) => format!(
+                "{} {} {} {}",
+                image.reference,
+                image.manifest,
+                image.config,
+                image.layers.join(",")
+            ),
+            EventPayload::BootCmdline { cmdline } => cmdline.clone(),
+            EventPayload::BootFile { path, digest }
+            | EventPayload::BootDirectory { path, digest } => format!("{} {}", path, digest),
+            EventPayload::Exec { path, digest, args } => {
+                format!("{} {} {}", path, digest, encode_args(args))
+            }
+            EventPayload::Custom { data, .. } => data.clone(),
+        }
+    }
+
+    /// Returns the SHA-384 digest of the payload's measured data.
+    ///
+    /// The data is hashed in place rather than formatted first, since
+    /// importing and verifying a log computes the digest of every event.
+    pub fn digest(&self) -> [u8; SHA384_LEN] {
+        let mut hasher = Sha384::new();
+        match self {
+            EventPayload::Ima { line } => hasher.update(line),
+            EventPayload::ContainerImage(image) => {
+                for part in [&image.reference, &image.manifest, &image.config] {
+                    hasher.update(part);
+                    hasher.update(" ");
+                }
+                for (i, layer) in image.layers.iter().enumerate() {
+                    if i > 0 {
+                        hasher.update(",");
+                    }
+                    hasher.update(layer);
+                }
+            }
+            EventPayload::BootCmdline { cmdline } => hasher.update(cmdline),
+            EventPayload::BootFile { path, digest }
+            | EventPayload::BootDirectory { path, digest } => {
+                hasher.update(path);
+                hasher.update(" ");
+                hasher.update(digest);
+            }
+            EventPayload::Exec { path, digest, args } => {
+                for part in [path, digest] {
+                    hasher.update(part);
+                    hasher.update(" ");
+                }
+                hasher.update(encode_args(args));
+            }
+            EventPayload::Custom { data, .. } => hasher.update(data),
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Encodes a program's arguments as a JSON array, so that arguments
+/// containing spaces are measured unambiguously.
+fn encode_args(args: &[String]) -> String {
+    // serializing strings can't fail
+    serde_json::to_string(args).unwrap_or_default()
+}
+
+/// A measurement extended into an RTMR.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// The index of the event in the event log.
+    pub index: u64,
+    /// The index of the RTMR the event was extended into.
+    pub rtmr: u8,
+    /// The SHA-384 digest extended into the RTMR.
+    #[serde(with = "digest_bytes")]
+    pub digest: [u8; SHA384_LEN],
+    /// What was measured.
+    pub payload: EventPayload,
+}
+
+impl Event {
+    /// Creates a new event for `payload`, to be extended into `RTMR[rtmr]`.
+    ///
+    /// The event's index is assigned when it is appended to an event log.
+    pub fn new(rtmr: u8, payload: EventPayload) -> Self {
+        Self {
+            index: 0,
+            rtmr,
+            digest: payload.digest(),
+            payload,
+        }
+    }
+
+    /// Checks that the event's digest matches its payload.
+    pub fn verify_digest(&self) -> bool {
+        ct::eq(&self.digest, &self.payload.digest())
+    }
+}
+
+/// The header of an on-disk event log.
+#[derive(Debug, Serialize, Deserialize)]
+struct EventLogHeader {
+    magic: String,
+    version: u32,
+}
+
+impl EventLogHeader {
+    /// Returns the header of logs in the current format.
+    fn current() -> Self {
+        Self {
+            magic: EVENT_LOG_MAGIC.to_string(),
+            version: EVENT_LOG_VERSION,
+        }
+    }
+}
+
+/// An exported event log.
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedEventLog {
+    version: u32,
+    events: Vec<Event>,
+}
+
+/// An append-only event log, stored as a CBOR sequence.
+#[derive(Clone, Debug)]
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    /// Creates a handle to the event log at `path`. The log file is created
+    /// when the first event is appended.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an event to the log, assigning it the next index, and returns
+    /// the recorded event.
+    pub fn append(&self, event: &Event) -> Result<Event> {
+        // validate the log and count its events without holding them
+        let mut len = 0;
+        for recorded in self.reader()? {
+            recorded?;
+            len += 1;
+        }
+
+        let mut event = event.clone();
+        event.index = len;
+
+        let mut bytes = vec![];
+        if len == 0 {
+            encode(&EventLogHeader::current(), &mut bytes)?;
+        }
+        encode(&event, &mut bytes)?;
+
+        // (re)write the header if the log has no events yet
+        let mut file = fs::File::options()
+            .create(true)
+            .append(len > 0)
+            .write(true)
+            .truncate(len == 0)
+            .open(&self.path)?;
+        file.write_all(&bytes)?;
+        file.sync_data()?;
+
+        Ok(event)
+    }
+
+    /// Opens the log for reading its events one at a time. A missing log
+    /// file contains no events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the log file is a symlink, or an
+    /// `Error::ParseError` if its header is malformed or has an unsupported
+    /// version. Errors in its events are returned by the reader.
+    pub fn reader(&self) -> Result<EventReader<fs::File>> {
+        // throw an error if the log file is a symlink
+        if self.path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                self.path.display()
+            )));
+        }
+
+        match fs::File::open(&self.path) {
+            Ok(file) => EventReader::new(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(EventReader::empty()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads all events in the log. A missing log file contains no events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the log is malformed or has an
+    /// unsupported version.
+    pub fn events(&self) -> Result<Vec<Event>> {
+        self.reader()?.collect()
+    }
+
+    /// Replays the events in the log into initially all-zero RTMRs, reading
+    /// one event at a time.
+    pub fn replay(&self) -> Result<[[u8; SHA384_LEN]; NUM_RTMRS]> {
+        let mut replay = RtmrReplay::new();
+        for event in self.reader()? {
+            replay.extend(&event?)?;
+        }
+        Ok(replay.rtmrs())
+    }
+
+    /// Exports the events in the log (see `export_events()`).
+    pub fn export(&self) -> Result<Vec<u8>> {
+        export_events(&self.events()?)
+    }
+}
+
+/// A reader decoding the events of an on-disk event log (a CBOR sequence)
+/// one at a time, checking that they're in order.
+pub struct EventReader<R: Read> {
+    reader: Option<BufReader<R>>,
+    next_index: u64,
+}
+
+impl<R: Read> EventReader<R> {
+    /// Creates a reader of the event log in `reader`, reading its header.
+    /// An empty log contains no events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the header is malformed or has an
+    /// unsupported version.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+        if reader.fill_buf()?.is_empty() {
+            return Ok(Self::empty());
+        }
+
+        let header: EventLogHeader = decode(&mut reader)?;
+        if header.magic != EVENT_LOG_MAGIC {
+            return Err(Error::ParseError("Not an event log".to_string()));
+        }
+        check_version(header.version)?;
+
+        Ok(Self {
+            reader: Some(reader),
+            next_index: 0,
+        })
+    }
+
+    /// Creates a reader without events.
+    fn empty() -> Self {
+        Self {
+            reader: None,
+            next_index: 0,
+        }
+    }
+
+    fn read_event(reader: &mut BufReader<R>, index: u64) -> Result<Option<Event>> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let event: Event = decode(reader)?;
+        if event.index != index {
+            return Err(Error::ParseError(format!(
+                "Event {} has index {}",
+                index, event.index
+            )));
+        }
+        Ok(Some(event))
+    }
+}
+
+impl<R: Read> Iterator for EventReader<R> {
+    type Item = Result<Event>;
+
+    /// Reads the next event, stopping after the first error.
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = self.reader.as_mut()?;
+        match Self::read_event(reader, self.next_index) {
+            Ok(Some(event)) => {
+                self.next_index += 1;
+                Some(Ok(event))
+            }
+            Ok(None) => {
+                self.reader = None;
+                None
+            }
+            Err(e) => {
+                self.reader = None;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A writer encoding a new event log (a CBOR sequence) one event at a time,
+/// e.g., to convert other logs without holding them in memory.
+pub struct EventWriter<W: Write> {
+    writer: W,
+    next_index: u64,
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Creates a writer of a new event log to `writer`, writing its header.
+    pub fn new(mut writer: W) -> Result<Self> {
+        encode(&EventLogHeader::current(), &mut writer)?;
+        Ok(Self {
+            writer,
+            next_index: 0,
+        })
+    }
+
+    /// Writes an event, assigning it the next index, and returns the written
+    /// event.
+    pub fn write(&mut self, event: &Event) -> Result<Event> {
+        let mut event = event.clone();
+        event.index = self.next_index;
+        encode(&event, &mut self.writer)?;
+        self.next_index += 1;
+        Ok(event)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// An incremental replay of events into initially all-zero RTMRs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RtmrReplay {
+    rtmrs: [[u8; SHA384_LEN]; NUM_RTMRS],
+}
+
+impl Default for RtmrReplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RtmrReplay {
+    /// Creates a replay with all-zero RTMRs.
+    pub fn new() -> Self {
+        Self {
+            rtmrs: [[0u8; SHA384_LEN]; NUM_RTMRS],
+        }
+    }
+
+    /// Extends the event's digest into its RTMR.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the event targets an RTMR that
+    /// doesn't exist.
+    pub fn extend(&mut self, event: &Event) -> Result<()> {
+        let rtmr = self
+            .rtmrs
+            .get_mut(event.rtmr as usize)
+            .ok_or_else(|| Error::ParseError(format!("Invalid RTMR index {}", event.rtmr)))?;
+        *rtmr = extend_rtmr(rtmr, &event.digest);
+        Ok(())
+    }
+
+    /// Returns the replayed RTMRs.
+    pub fn rtmrs(&self) -> [[u8; SHA384_LEN]; NUM_RTMRS] {
+        self.rtmrs
+    }
+}
+
+/// Exports events as a single, versioned CBOR document for verifiers.
+pub fn export_events(events: &[Event]) -> Result<Vec<u8>> {
+    let exported = ExportedEventLog {
+        version: EVENT_LOG_VERSION,
+        events: events.to_vec(),
+    };
+
+    let mut bytes = vec![];
+    encode(&exported, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Imports events exported with `export_events()`.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the exported log is malformed, has an
+/// unsupported version, or its events are out of order, or an
+/// `Error::VerificationError` if an event's digest doesn't match its payload.
+pub fn import_events(bytes: &[u8]) -> Result<Vec<Event>> {
+    let mut reader = bytes;
+    let exported: ExportedEventLog = decode(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(Error::ParseError(
+            "Trailing data after exported event log".to_string(),
+        ));
+    }
+
+    check_version(exported.version)?;
+    check_indices(&exported.events)?;
+    if let Some(event) = exported.events.iter().find(|e| !e.verify_digest()) {
+        return Err(Error::VerificationError(format!(
+            "Digest of event {} does not match its payload",
+            event.index
+        )));
+    }
+
+    Ok(exported.events)
+}
+
+/// Replays `events` into initially all-zero RTMRs.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if an event targets an RTMR that doesn't
+/// exist.
+pub fn replay(events: &[Event]) -> Result<[[u8; SHA384_LEN]; NUM_RTMRS]> {
+    let mut replay = RtmrReplay::new();
+    for event in events {
+        replay.extend(event)?;
+    }
+    Ok(replay.rtmrs())
+}
+
+/// Extends the event's digest into its RTMR, then records it in the event
+/// log, and returns the recorded event.
+///
+/// The event is only logged if the extension succeeds, so that the log never
+/// contains events that are not reflected in the RTMRs.
+pub fn measure_event<E: RtmrExtender>(
+    extender: &mut E,
+    log: &EventLog,
+    event: &Event,
+) -> Result<Event> {
+    if event.rtmr as usize >= NUM_RTMRS {
+        return Err(Error::ParseError(format!(
+            "Invalid RTMR index {}",
+            event.rtmr
+        )));
+    }
+
+    extender.extend_rtmr(event.rtmr, &event.digest)?;
+    log.append(event)
+}
+
+fn check_version(version: u32) -> Result<()> {
+    if version != EVENT_LOG_VERSION {
+        return Err(Error::ParseError(format!(
+            "Unsupported event log version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+fn check_indices(events: &[Event]) -> Result<()> {
+    for (i, event) in events.iter().enumerate() {
+        if event.index != i as u64 {
+            return Err(Error::ParseError(format!(
+                "Event {} has index {}",
+                i, event.index
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn encode<T: Serialize, W: Write>(value: &T, writer: W) -> Result<()> {
+    ciborium::into_writer(value, writer).map_err(|e| Error::SerializationError(e.to_string()))
+}
+
+fn decode<T: for<'de> Deserialize<'de>, R: Read>(reader: R) -> Result<T> {
+    ciborium::from_reader(reader)
+        .map_err(|e| Error::ParseError(format!("Invalid event log entry: {}", e)))
+}
+
+/// Serializes digests as CBOR byte strings.
+mod digest_bytes {
+    use super::SHA384_LEN;
+    use serde::{Deserializer, Serializer, de};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        value: &[u8; SHA384_LEN],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; SHA384_LEN], D::Error> {
+        deserializer.deserialize_bytes(DigestVisitor)
+    }
+
+    struct DigestVisitor;
+
+    impl de::Visitor<'_> for DigestVisitor {
+        type Value = [u8; SHA384_LEN];
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a {}-byte digest", SHA384_LEN)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An `RtmrExtender` that emulates the RTMRs in memory.
+    pub(crate) struct SoftRtmrs {
+        pub(crate) rtmrs: [[u8; SHA384_LEN]; NUM_RTMRS],
+    }
+
+    impl Default for SoftRtmrs {
+        fn default() -> Self {
+            Self {
+                rtmrs: [[0u8; SHA384_LEN]; NUM_RTMRS],
+            }
+        }
+    }
+
+    impl RtmrExtender for SoftRtmrs {
+        fn extend_rtmr(&mut self, index: u8, digest: &[u8; SHA384_LEN]) -> Result<()> {
+            let rtmr = &mut self.rtmrs[index as usize];
+            *rtmr = extend_rtmr(rtmr, digest);
+            Ok(())
+        }
+    }
+
+    pub(crate) fn temp_log() -> EventLog {
+        EventLog::new(
+            std::env::temp_dir().join(format!("tdx-event-log-{}.cbor", rand::random::<u64>())),
+        )
+    }
+
+    fn custom_event(rtmr: u8, data: &str) -> Event {
+        Event::new(
+            rtmr,
+            EventPayload::Custom {
+                event_type: "test".to_string(),
+                data: data.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_measure_and_replay() -> Result<()> {
+        let log = temp_log();
+        let mut rtmrs = SoftRtmrs::default();
+
+        measure_event(&mut rtmrs, &log, &custom_event(3, "first"))?;
+        let second = measure_event(&mut rtmrs, &log, &custom_event(2, "second"))?;
+        assert_eq!(second.index, 1);
+
+        let events = log.events()?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].payload.measured_data(), "first");
+        assert_eq!(events[1], second);
+        assert_eq!(log.replay()?, rtmrs.rtmrs);
+
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import() -> Result<()> {
+        let log = temp_log();
+        let mut rtmrs = SoftRtmrs::default();
+        measure_event(&mut rtmrs, &log, &custom_event(3, "first"))?;
+        measure_event(
+            &mut rtmrs,
+            &log,
+            &Event::new(
+                3,
+                EventPayload::BootFile {
+                    path: "/etc/hostname".to_string(),
+                    digest: "ab".repeat(SHA384_LEN),
+                },
+            ),
+        )?;
+
+        let exported = log.export()?;
+        let events = import_events(&exported)?;
+        assert_eq!(events, log.events()?);
+        assert_eq!(replay(&events)?, rtmrs.rtmrs);
+
+        // an event whose digest doesn't match its payload is rejected
+        let mut tampered = events.clone();
+        tampered[1].payload = EventPayload::BootCmdline {
+            cmdline: "init=/bin/sh".to_string(),
+        };
+        assert!(import_events(&export_events(&tampered)?).is_err());
+
+        // so are out-of-order events
+        let reordered = vec![events[1].clone(), events[0].clone()];
+        assert!(import_events(&export_events(&reordered)?).is_err());
+
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_version() -> Result<()> {
+        let mut bytes = vec![];
+        encode(
+            &ExportedEventLog {
+                version: EVENT_LOG_VERSION + 1,
+                events: vec![],
+            },
+            &mut bytes,
+        )?;
+        assert!(import_events(&bytes).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_invalid_rtmr() {
+        let log = temp_log();
+        let mut rtmrs = SoftRtmrs::default();
+
+        assert!(measure_event(&mut rtmrs, &log, &custom_event(4, "data")).is_err());
+        assert!(!log.path().exists());
+    }
+
+    #[test]
+    fn test_payload_digest() {
+        let payloads = [
+            EventPayload::Ima {
+                line: "10 abcd ima-ng".to_string(),
+            },
+            EventPayload::ContainerImage(ImageMeasurement {
+                reference: "registry.example/app:v1".to_string(),
+                manifest: "sha256:01".to_string(),
+                config: "sha256:02".to_string(),
+                layers: vec!["sha256:03".to_string(), "sha256:04".to_string()],
+            }),
+            EventPayload::BootCmdline {
+                cmdline: "console=ttyS0".to_string(),
+            },
+            EventPayload::BootDirectory {
+                path: "/etc/app".to_string(),
+                digest: "ab".repeat(SHA384_LEN),
+            },
+            EventPayload::Exec {
+                path: "/usr/bin/app".to_string(),
+                digest: "cd".repeat(SHA384_LEN),
+                args: vec!["--config".to_string(), "a b".to_string()],
+            },
+            EventPayload::Custom {
+                event_type: "test".to_string(),
+                data: "data".to_string(),
+            },
+        ];
+        for payload in payloads {
+            let expected: [u8; SHA384_LEN] = Sha384::digest(payload.measured_data()).into();
+            assert_eq!(payload.digest(), expected, "{}", payload.event_type());
+        }
+    }
+
+    #[test]
+    fn test_missing_log_is_empty() -> Result<()> {
+        assert!(temp_log().events()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_reader() -> Result<()> {
+        let log = temp_log();
+        let mut rtmrs = SoftRtmrs::default();
+        for i in 0..3 {
+            measure_event(&mut rtmrs, &log, &custom_event(3, &i.to_string()))?;
+        }
+
+        let bytes = fs::read(log.path())?;
+        let events = EventReader::new(bytes.as_slice())?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(events, log.events()?);
+
+        // the writer encodes logs like `append()`
+        let mut writer = EventWriter::new(vec![])?;
+        for event in &events {
+            writer.write(&Event {
+                index: 0,
+                ..event.clone()
+            })?;
+        }
+        assert_eq!(writer.into_inner(), bytes);
+        assert!(EventReader::new(&[][..])?.next().is_none());
+
+        // a truncated event fails the reader, after the complete events
+        let mut reader = EventReader::new(&bytes[..bytes.len() - 1])?;
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        // so does an event that is out of order
+        let mut skipped = vec![];
+        encode(&EventLogHeader::current(), &mut skipped)?;
+        encode(&events[1], &mut skipped)?;
+        assert!(
+            EventReader::new(skipped.as_slice())?
+                .next()
+                .unwrap()
+                .is_err()
+        );
+
+        assert!(EventReader::new(&b"not a log"[..]).is_err());
+
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! # Measured Workload Launcher
+//!
+//! This module implements a measured launch of a workload (see
+//! `tdx-attest exec`): the launcher measures the program and its arguments
+//! into `RTMR3`, and records them in an event log, before executing the
+//! program. Relying parties can then tell from the TD's quote and event log
+//! exactly which program the TD runs, as they can for the boot hook's
+//! measurements (see the `boot_hook` module).
+//!
+//! The launcher can also gate the launch on the TD's own measurements: once
+//! the program is measured, the TD's `TDREPORT` is checked against an
+//! appraisal policy (see `Policy::check_tdreport()`), and the program is only
+//! launched if it passes.
+//!
+//! Programs are measured by the SHA-384 digest of their file, and launched
+//! from the canonical path they were measured at, rather than looked up in
+//! `PATH` again.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::event_log::EventLog;
+//! use tdx_workload_attestation::measure::launch::{measure_launch, resolve_program};
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//!
+//! let log = EventLog::new("/run/tdx-workload-attestation/boot-hook.cbor");
+//! let program = resolve_program("app").unwrap();
+//! let args = vec!["--config".to_string(), "/etc/app/config.toml".to_string()];
+//! let event = measure_launch(&mut LinuxTdxProvider::new(), &log, &program, &args).unwrap();
+//! println!("Measured {} into RTMR{}", event.payload.measured_data(), event.rtmr);
+//! ```
+
+use crate::core::report::TdReportV15;
+use crate::error::{Error, Result};
+use crate::evidence::Policy;
+use crate::measure::boot_hook::file_digest;
+use crate::measure::event_log::{Event, EventLog, EventPayload, RtmrExtender, measure_event};
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// The RTMR that launched programs are extended into.
+pub const LAUNCH_RTMR_INDEX: u8 = 3;
+
+/// Resolves `program` to the canonical path of an executable file: as a
+/// path if it contains a `/`, or else by searching `PATH`, as the shell
+/// does.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if no executable file is found.
+pub fn resolve_program(program: &str) -> Result<PathBuf> {
+    let candidates: Vec<PathBuf> = if program.contains('/') {
+        vec![PathBuf::from(program)]
+    } else {
+        env::var_os("PATH")
+            .map(|path| {
+                env::split_paths(&path)
+                    .map(|dir| dir.join(program))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    candidates
+        .into_iter()
+        .find(|path| is_executable(path))
+        .ok_or_else(|| Error::NotSupported(format!("No executable {} found", program)))
+        .and_then(|path| Ok(fs::canonicalize(path)?))
+}
+
+/// Measures the program at `path` (e.g., resolved with `resolve_program()`)
+/// and its `args` into `RTMR3` with `extender`, records them in `log`, and
+/// returns the recorded event.
+pub fn measure_launch<E: RtmrExtender>(
+    extender: &mut E,
+    log: &EventLog,
+    path: &Path,
+    args: &[String],
+) -> Result<Event> {
+    if let Some(dir) = log.path().parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let payload = EventPayload::Exec {
+        path: path.display().to_string(),
+        digest: hex::encode(file_digest(path)?),
+        args: args.to_vec(),
+    };
+    measure_event(extender, log, &Event::new(LAUNCH_RTMR_INDEX, payload))
+}
+
+/// Checks the TD's `report` against `policy`, to gate a launch.
+///
+/// # Errors
+///
+/// Returns an `Error::VerificationError` listing the failed checks if the
+/// report doesn't pass the policy (see `Policy::check_tdreport()`).
+pub fn check_launch(policy: &Policy, report: &TdReportV15) -> Result<()> {
+    let failures = policy.check_tdreport(report)?;
+    if !failures.is_empty() {
+        return Err(Error::VerificationError(format!(
+            "The TD doesn't pass the launch policy: {}",
+            failures.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::ReferenceValues;
+    use crate::measure::event_log::tests::{SoftRtmrs, temp_log};
+
+    use sha2::{Digest, Sha384};
+
+    #[test]
+    fn test_resolve_program() -> Result<()> {
+        let sh = resolve_program("sh")?;
+        assert!(sh.is_absolute());
+        assert_eq!(resolve_program(sh.to_str().unwrap())?, sh);
+
+        assert!(resolve_program("no-such-program-tdx").is_err());
+
+        // not executable
+        let file = env::temp_dir().join(format!("tdx-launch-{}", rand::random::<u64>()));
+        fs::write(&file, b"data")?;
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644))?;
+        assert!(resolve_program(file.to_str().unwrap()).is_err());
+        fs::remove_file(&file)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_launch() -> Result<()> {
+        let log = temp_log();
+        let program = resolve_program("sh")?;
+        let args = vec!["-c".to_string(), "echo a b".to_string()];
+
+        let mut rtmrs = SoftRtmrs::default();
+        let event = measure_launch(&mut rtmrs, &log, &program, &args)?;
+        assert_eq!(event.rtmr, LAUNCH_RTMR_INDEX);
+        assert_eq!(
+            event.payload.measured_data(),
+            format!(
+                "{} {} [\"-c\",\"echo a b\"]",
+                program.display(),
+                hex::encode(Sha384::digest(fs::read(&program)?))
+            )
+        );
+        assert_eq!(log.replay()?, rtmrs.rtmrs);
+
+        fs::remove_file(log.path())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_launch() -> Result<()> {
+        let report = TdReportV15::builder().with_rtmrs(&[[3; 48]; 4]).build();
+
+        let mut policy = Policy::new();
+        check_launch(&policy, &report)?;
+
+        policy.reference_values = ReferenceValues {
+            rtmr3: Some([4; 48].into()),
+            ..Default::default()
+        };
+        let error = check_launch(&policy, &report).unwrap_err();
+        assert!(error.to_string().contains("RTMR3"));
+
+        policy.reference_values.rtmr3 = Some([3; 48].into());
+        check_launch(&policy, &report)?;
+        Ok(())
+    }
+}
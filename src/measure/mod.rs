@@ -0,0 +1,118 @@
+//! # TD Measurement Utilities
+//!
+//! This module provides utilities for working with TD measurements outside of
+//! the TD itself, such as predicting the expected values of measurement
+//! registers from the artifacts used to launch a TD, as well as for recording
+//! runtime measurements (e.g., of container images, boot-time files or
+//! launched programs, see `container`, `boot_hook` and `launch`) in an event
+//! log (see `event_log`), and replaying the firmware's event log (see
+//! `ccel`).
+//!
+//! Predicted values are collected in a `ReferenceValues` set, which can be
+//! serialized (with hex-encoded registers) and distributed to verifiers,
+//! or in an operator-managed allow-list of named, time-limited sets (see
+//! `allowlist`).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::predict::predict_mrtd_from_file;
+//!
+//! // Compute the golden MRTD for a TD launched with the given TDVF image
+//! let mrtd = predict_mrtd_from_file("/usr/share/ovmf/OVMF.tdx.fd").unwrap();
+//! println!("Expected MRTD: {}", mrtd);
+//! ```
+
+pub mod allowlist;
+#[cfg(unix)]
+pub mod boot_hook;
+pub mod ccel;
+pub mod container;
+pub mod event_log;
+#[cfg(unix)]
+pub mod launch;
+pub mod pe;
+pub mod predict;
+
+use crate::core::register::MeasurementRegister;
+
+use serde::{Deserialize, Serialize};
+
+/// The length of a SHA-384 digest, which is the length of all TDX measurement
+/// registers.
+pub const SHA384_LEN: usize = 48_usize;
+
+/// A set of expected measurement register values for a TD.
+///
+/// Registers left unset (`None`) are not constrained by the reference values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceValues {
+    /// The expected `MRTD` value.
+    #[serde(default)]
+    pub mrtd: Option<MeasurementRegister>,
+    /// The expected `RTMR0` value (firmware configuration).
+    #[serde(default)]
+    pub rtmr0: Option<MeasurementRegister>,
+    /// The expected `RTMR1` value (OS loader and kernel).
+    #[serde(default)]
+    pub rtmr1: Option<MeasurementRegister>,
+    /// The expected `RTMR2` value (kernel command line and initrd).
+    #[serde(default)]
+    pub rtmr2: Option<MeasurementRegister>,
+    /// The expected `RTMR3` value (runtime and application measurements).
+    #[serde(default)]
+    pub rtmr3: Option<MeasurementRegister>,
+}
+
+impl ReferenceValues {
+    /// Returns the names of the registers whose `mrtd` or `rtmrs` value
+    /// doesn't match the reference values.
+    pub fn mismatches(
+        &self,
+        mrtd: &MeasurementRegister,
+        rtmrs: &[MeasurementRegister; 4],
+    ) -> Vec<&'static str> {
+        let mut mismatches = vec![];
+        if self.mrtd.is_some_and(|expected| expected != *mrtd) {
+            mismatches.push("MRTD");
+        }
+        mismatches.extend(self.rtmr_mismatches(rtmrs));
+        mismatches
+    }
+
+    /// Returns the names of the registers whose `rtmrs` value doesn't match
+    /// the reference values, ignoring the `MRTD`.
+    pub fn rtmr_mismatches(&self, rtmrs: &[MeasurementRegister; 4]) -> Vec<&'static str> {
+        [
+            ("RTMR0", self.rtmr0, &rtmrs[0]),
+            ("RTMR1", self.rtmr1, &rtmrs[1]),
+            ("RTMR2", self.rtmr2, &rtmrs[2]),
+            ("RTMR3", self.rtmr3, &rtmrs[3]),
+        ]
+        .into_iter()
+        .filter(|(_, expected, actual)| expected.is_some_and(|e| e != **actual))
+        .map(|(name, _, _)| name)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_values_serde() {
+        let values = ReferenceValues {
+            mrtd: Some([0xab; SHA384_LEN].into()),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&values).unwrap();
+        assert!(json.contains(&"ab".repeat(SHA384_LEN)));
+
+        let parsed: ReferenceValues = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, values);
+
+        assert!(serde_json::from_str::<ReferenceValues>(r#"{"mrtd":"abcd"}"#).is_err());
+    }
+}
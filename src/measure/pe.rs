@@ -0,0 +1,215 @@
+//! # PE/COFF Authenticode Hashing
+//!
+//! This module computes the Authenticode digest of PE/COFF images, such as
+//! EFI-stub Linux kernels. UEFI firmware measures EFI applications it loads
+//! using this digest, so it is needed to predict the boot-time measurements
+//! of a TD.
+//!
+//! The digest covers the image headers (excluding the `CheckSum` field and
+//! the certificate table data directory entry), the sections in file order,
+//! and any trailing data except for the attached certificate table.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::pe::authenticode_sha384;
+//!
+//! let kernel = std::fs::read("/boot/vmlinuz").unwrap();
+//! let digest = authenticode_sha384(&kernel).unwrap();
+//! println!("Kernel Authenticode digest: {}", hex::encode(digest));
+//! ```
+
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+
+use sha2::{Digest, Sha384};
+
+// The offset of the PE header offset in the DOS header
+const DOS_E_LFANEW_OFFSET: usize = 0x3c;
+
+// The lengths of the PE signature and COFF file header
+const PE_SIGNATURE_LEN: usize = 4;
+const COFF_HEADER_LEN: usize = 20;
+
+// The optional header magic values
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+// The offsets of fields within the optional header
+const OPT_SIZE_OF_HEADERS_OFFSET: usize = 60;
+const OPT_CHECKSUM_OFFSET: usize = 64;
+
+// The index of the certificate table in the data directories
+const CERT_TABLE_INDEX: usize = 4;
+
+// The length of a data directory entry and of a section header
+const DATA_DIRECTORY_LEN: usize = 8;
+const SECTION_HEADER_LEN: usize = 40;
+
+/// Computes the SHA-384 Authenticode digest of a PE/COFF image.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the image is not a valid PE/COFF image.
+pub fn authenticode_sha384(image: &[u8]) -> Result<[u8; SHA384_LEN]> {
+    let pe_offset = read_u32(image, DOS_E_LFANEW_OFFSET)? as usize;
+    if image.get(pe_offset..pe_offset + PE_SIGNATURE_LEN) != Some(b"PE\0\0".as_slice()) {
+        return Err(Error::ParseError("PE signature not found".to_string()));
+    }
+
+    let coff_offset = pe_offset + PE_SIGNATURE_LEN;
+    let num_sections = read_u16(image, coff_offset + 2)? as usize;
+    let opt_header_size = read_u16(image, coff_offset + 16)? as usize;
+
+    let opt_offset = coff_offset + COFF_HEADER_LEN;
+    let (num_dirs_offset, dirs_offset) = match read_u16(image, opt_offset)? {
+        PE32_MAGIC => (opt_offset + 92, opt_offset + 96),
+        PE32_PLUS_MAGIC => (opt_offset + 108, opt_offset + 112),
+        magic => {
+            return Err(Error::ParseError(format!(
+                "Unknown PE optional header magic {:#x}",
+                magic
+            )));
+        }
+    };
+
+    let checksum_offset = opt_offset + OPT_CHECKSUM_OFFSET;
+    let size_of_headers = read_u32(image, opt_offset + OPT_SIZE_OF_HEADERS_OFFSET)? as usize;
+    if size_of_headers > image.len() || size_of_headers < checksum_offset + 4 {
+        return Err(Error::ParseError("PE headers are truncated".to_string()));
+    }
+
+    let num_dirs = read_u32(image, num_dirs_offset)? as usize;
+
+    let mut hasher = Sha384::new();
+    let mut cert_table_size = 0usize;
+
+    // 1. Hash the headers, skipping the checksum and certificate table entry
+    if num_dirs > CERT_TABLE_INDEX {
+        let cert_dir_offset = dirs_offset + CERT_TABLE_INDEX * DATA_DIRECTORY_LEN;
+        if cert_dir_offset + DATA_DIRECTORY_LEN > size_of_headers {
+            return Err(Error::ParseError("PE headers are truncated".to_string()));
+        }
+        cert_table_size = read_u32(image, cert_dir_offset + 4)? as usize;
+
+        hasher.update(&image[..checksum_offset]);
+        hasher.update(&image[checksum_offset + 4..cert_dir_offset]);
+        hasher.update(&image[cert_dir_offset + DATA_DIRECTORY_LEN..size_of_headers]);
+    } else {
+        hasher.update(&image[..checksum_offset]);
+        hasher.update(&image[checksum_offset + 4..size_of_headers]);
+    }
+
+    // 2. Hash the sections in the order of their file offsets
+    let sections_offset = opt_offset + opt_header_size;
+    let mut sections = Vec::with_capacity(num_sections);
+    for i in 0..num_sections {
+        let header = sections_offset + i * SECTION_HEADER_LEN;
+        let size_of_raw_data = read_u32(image, header + 16)? as usize;
+        let pointer_to_raw_data = read_u32(image, header + 20)? as usize;
+        if size_of_raw_data > 0 {
+            sections.push((pointer_to_raw_data, size_of_raw_data));
+        }
+    }
+    sections.sort_unstable();
+
+    let mut bytes_hashed = size_of_headers;
+    for (start, size) in sections {
+        let data = image
+            .get(start..start + size)
+            .ok_or_else(|| Error::ParseError("PE section is truncated".to_string()))?;
+        hasher.update(data);
+        bytes_hashed += size;
+    }
+
+    // 3. Hash any trailing data, except for the certificate table
+    let trailing_end = image.len().saturating_sub(cert_table_size);
+    if trailing_end > bytes_hashed {
+        hasher.update(&image[bytes_hashed..trailing_end]);
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| Error::ParseError("PE image is truncated".to_string()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| Error::ParseError("PE image is truncated".to_string()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Builds a minimal PE32+ image with a single section and a 16-byte
+    // certificate table appended at the end
+    pub(crate) fn make_pe() -> Vec<u8> {
+        let mut image = vec![0u8; 0x200];
+        image[DOS_E_LFANEW_OFFSET..DOS_E_LFANEW_OFFSET + 4].copy_from_slice(&0x40u32.to_le_bytes());
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let coff = 0x44;
+        image[coff + 2..coff + 4].copy_from_slice(&1u16.to_le_bytes());
+        image[coff + 16..coff + 18].copy_from_slice(&0xf0u16.to_le_bytes());
+
+        let opt = coff + COFF_HEADER_LEN;
+        image[opt..opt + 2].copy_from_slice(&PE32_PLUS_MAGIC.to_le_bytes());
+        image[opt + 60..opt + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        image[opt + 64..opt + 68].copy_from_slice(&0xdeadbeefu32.to_le_bytes());
+        image[opt + 108..opt + 112].copy_from_slice(&16u32.to_le_bytes());
+
+        // certificate table entry
+        let cert_dir = opt + 112 + CERT_TABLE_INDEX * DATA_DIRECTORY_LEN;
+        image[cert_dir..cert_dir + 4].copy_from_slice(&0x400u32.to_le_bytes());
+        image[cert_dir + 4..cert_dir + 8].copy_from_slice(&16u32.to_le_bytes());
+
+        // one section of 0x200 bytes at file offset 0x200
+        let section = opt + 0xf0;
+        image[section + 16..section + 20].copy_from_slice(&0x200u32.to_le_bytes());
+        image[section + 20..section + 24].copy_from_slice(&0x200u32.to_le_bytes());
+
+        image.extend(vec![0x90; 0x200]); // section data
+        image.extend(vec![0xcc; 16]); // certificate table
+        image
+    }
+
+    #[test]
+    fn test_authenticode_sha384() -> Result<()> {
+        let image = make_pe();
+        let digest = authenticode_sha384(&image)?;
+
+        // the checksum and certificate table must not affect the digest
+        let mut modified = image.clone();
+        let checksum = 0x44 + COFF_HEADER_LEN + OPT_CHECKSUM_OFFSET;
+        modified[checksum] ^= 0xff;
+        let len = modified.len();
+        modified[len - 1] ^= 0xff;
+        assert_eq!(authenticode_sha384(&modified)?, digest);
+
+        // but the section data must
+        modified[0x300] ^= 0xff;
+        assert_ne!(authenticode_sha384(&modified)?, digest);
+        Ok(())
+    }
+
+    #[test]
+    fn test_authenticode_not_pe() {
+        assert!(authenticode_sha384(&[0u8; 0x100]).is_err());
+        assert!(authenticode_sha384(&[]).is_err());
+
+        // SizeOfHeaders must cover the optional header's checksum
+        let mut image = make_pe();
+        let opt = 0x44 + COFF_HEADER_LEN;
+        image[opt + 108..opt + 112].copy_from_slice(&0u32.to_le_bytes());
+        image[opt + 60..opt + 64].copy_from_slice(&0x40u32.to_le_bytes());
+        assert!(authenticode_sha384(&image).is_err());
+    }
+}
@@ -0,0 +1,740 @@
+//! # TD Measurement Prediction
+//!
+//! This module computes the expected values of TD measurement registers from
+//! the artifacts used to launch the TD, so that users can generate their own
+//! golden values instead of trusting cloud endorsements blindly.
+//!
+//! ## MRTD
+//!
+//! The `MRTD` is an incremental SHA-384 digest that the TDX module computes
+//! while the host builds the TD's initial memory image:
+//! - every private page added with `TDH.MEM.PAGE.ADD` is recorded with a
+//!   128-byte buffer containing `"MEM.PAGE.ADD"` and the page's guest
+//!   physical address (GPA), and
+//! - every 256-byte chunk of page content measured with `TDH.MR.EXTEND` is
+//!   recorded with a 128-byte buffer containing `"MR.EXTEND"` and the chunk's
+//!   GPA, followed by the 256 bytes of content.
+//!
+//! The TDVF (TD Virtual Firmware, e.g., OVMF built for TDX) describes the
+//! memory image in its metadata, which this module parses to replay the
+//! sequence of operations performed by QEMU/KVM at TD build time.
+//!
+//! ## RTMRs
+//!
+//! Each runtime measurement register (RTMR) starts out as all zeros and is
+//! extended with event digests as `RTMR = SHA-384(RTMR || digest)`. For a TD
+//! booted directly into a kernel by TDVF, the standard measured boot flow is:
+//! - `RTMR0`: firmware configuration (TD HOB, UEFI variables, ACPI tables),
+//!   which depends on the VMM and is supplied by the caller as digests,
+//! - `RTMR1`: the kernel's Authenticode digest, followed by the boot and
+//!   `ExitBootServices()` events logged by the firmware, and
+//! - `RTMR2`: the kernel command line and initrd, measured by the kernel's
+//!   EFI stub.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::measure::predict::{
+//!     DirectBootConfig, predict_mrtd_from_file, predict_rtmrs,
+//! };
+//!
+//! let mrtd = predict_mrtd_from_file("/usr/share/ovmf/OVMF.tdx.fd").unwrap();
+//! println!("Expected MRTD: {}", mrtd);
+//!
+//! let kernel = std::fs::read("/boot/vmlinuz").unwrap();
+//! let initrd = std::fs::read("/boot/initrd.img").unwrap();
+//! let config = DirectBootConfig {
+//!     kernel: &kernel,
+//!     initrd: Some(&initrd),
+//!     cmdline: "console=ttyS0 root=/dev/vda1 initrd=initrd",
+//!     rtmr0_digests: None,
+//! };
+//!
+//! let mut reference_values = predict_rtmrs(&config).unwrap();
+//! reference_values.mrtd = Some(mrtd);
+//! println!("{}", serde_json::to_string_pretty(&reference_values).unwrap());
+//! ```
+//!
+//! # Notes
+//! - The prediction assumes the QEMU/KVM build flow, in which TDVF sections
+//!   are added in metadata order, and sections with the `PAGE_AUG` attribute
+//!   are accepted by the guest at runtime rather than added at build time.
+//! - With QEMU direct boot, the command line seen (and measured) by the
+//!   kernel includes the ` initrd=initrd` suffix appended by QEMU when an
+//!   initrd is provided.
+
+use crate::core::register::MeasurementRegister;
+use crate::error::{Error, Result};
+use crate::measure::pe::authenticode_sha384;
+use crate::measure::{ReferenceValues, SHA384_LEN};
+
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::path::Path;
+
+/// The size of a TD private page.
+pub const PAGE_SIZE: u64 = 0x1000;
+
+// The size of the chunks measured by TDH.MR.EXTEND
+const MR_EXTEND_CHUNK_SIZE: usize = 256;
+
+// The size of the buffer recorded for TDH.MEM.PAGE.ADD and TDH.MR.EXTEND
+const MRTD_EXTENSION_BUFFER_LEN: usize = 128;
+
+// The offset of the GPA within the recorded buffer
+const MRTD_EXTENSION_GPA_OFFSET: usize = 16;
+
+// The size of the reset vector area at the end of the firmware, which
+// follows the OVMF GUIDed table footer
+const OVMF_RESET_VECTOR_AREA_LEN: usize = 0x20;
+
+// The length of an EFI GUID
+const GUID_LEN: usize = 16;
+
+// The GUID identifying the footer of the OVMF GUIDed table
+// (96b582de-1fb2-45f7-baea-a366c55a082d)
+const OVMF_TABLE_FOOTER_GUID: [u8; GUID_LEN] = guid_bytes(
+    0x96b582de,
+    0x1fb2,
+    0x45f7,
+    [0xba, 0xea, 0xa3, 0x66, 0xc5, 0x5a, 0x08, 0x2d],
+);
+
+// The GUID identifying the OVMF table entry containing the offset of the TDVF
+// metadata (e47a6535-984a-4798-865e-4685a7bf8ec2)
+const TDX_METADATA_OFFSET_GUID: [u8; GUID_LEN] = guid_bytes(
+    0xe47a6535,
+    0x984a,
+    0x4798,
+    [0x86, 0x5e, 0x46, 0x85, 0xa7, 0xbf, 0x8e, 0xc2],
+);
+
+// The TDVF metadata signature ("TDVF")
+const TDVF_SIGNATURE: &[u8; 4] = b"TDVF";
+
+// The length of the TDVF metadata descriptor header
+const TDVF_DESCRIPTOR_LEN: usize = 16;
+
+// The length of a TDVF metadata section entry
+const TDVF_SECTION_LEN: usize = 32;
+
+// The end of the guest physical address space (TDX GPAs are at most 52 bits
+// wide)
+const MAX_GPA: u64 = 1 << 52;
+
+// The maximum total size of the sections added at build time, well above
+// that of TDVF images (a few MiB), which bounds the work of replaying them
+const MAX_ADDED_MEMORY_SIZE: u64 = 1 << 30;
+
+/// The TDVF section attribute indicating the section's content is measured
+/// into `MRTD` with `TDH.MR.EXTEND`.
+pub const TDVF_ATTRIBUTE_MR_EXTEND: u32 = 1 << 0;
+
+/// The TDVF section attribute indicating the section's pages are added at
+/// runtime with `TDH.MEM.PAGE.AUG`, and are therefore not measured.
+pub const TDVF_ATTRIBUTE_PAGE_AUG: u32 = 1 << 1;
+
+/// Encodes a GUID in the mixed-endian EFI binary layout.
+const fn guid_bytes(d1: u32, d2: u16, d3: u16, d4: [u8; 8]) -> [u8; GUID_LEN] {
+    let d1 = d1.to_le_bytes();
+    let d2 = d2.to_le_bytes();
+    let d3 = d3.to_le_bytes();
+    [
+        d1[0], d1[1], d1[2], d1[3], d2[0], d2[1], d3[0], d3[1], d4[0], d4[1], d4[2], d4[3], d4[4],
+        d4[5], d4[6], d4[7],
+    ]
+}
+
+/// The type of a TDVF metadata section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TdvfSectionType {
+    /// Boot Firmware Volume (firmware code).
+    Bfv,
+    /// Configuration Firmware Volume (e.g., UEFI variables).
+    Cfv,
+    /// TD Hand-Off Block.
+    TdHob,
+    /// Temporary memory used during early boot.
+    TempMem,
+    /// Permanent memory.
+    PermMem,
+    /// A payload, e.g., a kernel image.
+    Payload,
+    /// Payload parameters, e.g., a kernel command line.
+    PayloadParam,
+    /// A section type unknown to this library.
+    Unknown(u32),
+}
+
+impl From<u32> for TdvfSectionType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => TdvfSectionType::Bfv,
+            1 => TdvfSectionType::Cfv,
+            2 => TdvfSectionType::TdHob,
+            3 => TdvfSectionType::TempMem,
+            4 => TdvfSectionType::PermMem,
+            5 => TdvfSectionType::Payload,
+            6 => TdvfSectionType::PayloadParam,
+            other => TdvfSectionType::Unknown(other),
+        }
+    }
+}
+
+/// A section of the TD's initial memory image, as described by the TDVF
+/// metadata.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TdvfSection {
+    /// The offset of the section's content within the firmware image.
+    pub data_offset: u32,
+    /// The size of the section's content within the firmware image.
+    pub raw_data_size: u32,
+    /// The GPA at which the section is mapped.
+    pub memory_address: u64,
+    /// The size of the section in guest memory.
+    pub memory_data_size: u64,
+    /// The section type.
+    pub section_type: TdvfSectionType,
+    /// The section attributes (see `TDVF_ATTRIBUTE_MR_EXTEND` and
+    /// `TDVF_ATTRIBUTE_PAGE_AUG`).
+    pub attributes: u32,
+}
+
+impl TdvfSection {
+    fn from_bytes(raw_bytes: &[u8]) -> TdvfSection {
+        TdvfSection {
+            data_offset: read_u32(raw_bytes, 0),
+            raw_data_size: read_u32(raw_bytes, 4),
+            memory_address: read_u64(raw_bytes, 8),
+            memory_data_size: read_u64(raw_bytes, 16),
+            section_type: read_u32(raw_bytes, 24).into(),
+            attributes: read_u32(raw_bytes, 28),
+        }
+    }
+
+    /// Returns `true` if the section's content is measured into `MRTD`.
+    pub fn is_extended(&self) -> bool {
+        self.attributes & TDVF_ATTRIBUTE_MR_EXTEND != 0
+    }
+
+    /// Returns `true` if the section's pages are added at build time.
+    pub fn is_added(&self) -> bool {
+        self.attributes & TDVF_ATTRIBUTE_PAGE_AUG == 0
+    }
+}
+
+/// The TDVF metadata describing the TD's initial memory image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TdvfMetadata {
+    /// The metadata version.
+    pub version: u32,
+    /// The sections, in metadata order.
+    pub sections: Vec<TdvfSection>,
+}
+
+impl TdvfMetadata {
+    /// Locates and parses the TDVF metadata in a firmware image.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the firmware doesn't contain valid
+    /// TDVF metadata, or if the metadata describes sections outside of the
+    /// firmware image or the guest physical address space, or sections
+    /// added at build time totaling more than 1 GiB.
+    pub fn parse(firmware: &[u8]) -> Result<TdvfMetadata> {
+        let offset_entry = find_ovmf_table_entry(firmware, &TDX_METADATA_OFFSET_GUID)?
+            .ok_or_else(|| Error::ParseError("TDVF metadata offset not found".to_string()))?;
+        if offset_entry.len() < 4 {
+            return Err(Error::ParseError(
+                "TDVF metadata offset entry is truncated".to_string(),
+            ));
+        }
+
+        // the metadata offset is relative to the end of the firmware
+        let from_end = read_u32(offset_entry, 0) as usize;
+        let offset = firmware
+            .len()
+            .checked_sub(from_end)
+            .ok_or_else(|| Error::ParseError("TDVF metadata offset is invalid".to_string()))?;
+
+        let descriptor = firmware
+            .get(offset..offset + TDVF_DESCRIPTOR_LEN)
+            .ok_or_else(|| Error::ParseError("TDVF metadata is truncated".to_string()))?;
+        if &descriptor[0..4] != TDVF_SIGNATURE {
+            return Err(Error::ParseError(
+                "TDVF metadata signature is wrong".to_string(),
+            ));
+        }
+
+        let version = read_u32(descriptor, 8);
+        let num_sections = read_u32(descriptor, 12) as usize;
+
+        let sections_start = offset + TDVF_DESCRIPTOR_LEN;
+        let sections_end = num_sections
+            .checked_mul(TDVF_SECTION_LEN)
+            .and_then(|len| sections_start.checked_add(len))
+            .filter(|end| *end <= firmware.len())
+            .ok_or_else(|| Error::ParseError("TDVF metadata sections are truncated".to_string()))?;
+
+        let sections: Vec<TdvfSection> = firmware[sections_start..sections_end]
+            .chunks_exact(TDVF_SECTION_LEN)
+            .map(TdvfSection::from_bytes)
+            .collect();
+
+        for section in &sections {
+            let data_end = section.data_offset as u64 + section.raw_data_size as u64;
+            if data_end > firmware.len() as u64 {
+                return Err(Error::ParseError(
+                    "TDVF section content is outside of the firmware".to_string(),
+                ));
+            }
+            if section.memory_address % PAGE_SIZE != 0 || section.memory_data_size % PAGE_SIZE != 0
+            {
+                return Err(Error::ParseError(
+                    "TDVF section is not page-aligned".to_string(),
+                ));
+            }
+            if section.raw_data_size as u64 > section.memory_data_size {
+                return Err(Error::ParseError(
+                    "TDVF section content is larger than its memory region".to_string(),
+                ));
+            }
+            if section
+                .memory_address
+                .checked_add(section.memory_data_size)
+                .is_none_or(|end| end > MAX_GPA)
+            {
+                return Err(Error::ParseError(
+                    "TDVF section is outside of the guest physical address space".to_string(),
+                ));
+            }
+        }
+
+        let added_size = sections
+            .iter()
+            .filter(|section| section.is_added())
+            .try_fold(0u64, |size, section| {
+                size.checked_add(section.memory_data_size)
+            });
+        if added_size.is_none_or(|size| size > MAX_ADDED_MEMORY_SIZE) {
+            return Err(Error::ParseError(
+                "TDVF sections added at build time are too large".to_string(),
+            ));
+        }
+
+        Ok(TdvfMetadata { version, sections })
+    }
+}
+
+/// Replays the `MRTD` computation performed by the TDX module.
+#[derive(Clone, Debug, Default)]
+pub struct MrtdBuilder {
+    hasher: Sha384,
+}
+
+impl MrtdBuilder {
+    /// Creates a new `MrtdBuilder` for an empty TD.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the addition of the private page at `gpa` (`TDH.MEM.PAGE.ADD`).
+    pub fn page_add(&mut self, gpa: u64) {
+        self.hasher.update(extension_buffer(b"MEM.PAGE.ADD", gpa));
+    }
+
+    /// Records the measurement of a 256-byte chunk of memory content at `gpa`
+    /// (`TDH.MR.EXTEND`).
+    pub fn mr_extend(&mut self, gpa: u64, chunk: &[u8; MR_EXTEND_CHUNK_SIZE]) {
+        self.hasher.update(extension_buffer(b"MR.EXTEND", gpa));
+        self.hasher.update(chunk);
+    }
+
+    /// Records the addition (and, if `extend` is set, the measurement) of
+    /// the memory region at `gpa` with the given `content`.
+    ///
+    /// `content` must be a multiple of the page size.
+    pub fn add_region(&mut self, gpa: u64, content: &[u8], extend: bool) {
+        for (i, page) in content.chunks(PAGE_SIZE as usize).enumerate() {
+            let page_gpa = gpa + i as u64 * PAGE_SIZE;
+            self.page_add(page_gpa);
+
+            if extend {
+                for (j, chunk) in page.chunks(MR_EXTEND_CHUNK_SIZE).enumerate() {
+                    let mut buf = [0u8; MR_EXTEND_CHUNK_SIZE];
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    self.mr_extend(page_gpa + (j * MR_EXTEND_CHUNK_SIZE) as u64, &buf);
+                }
+            }
+        }
+    }
+
+    /// Finalizes the computation (`TDH.MR.FINALIZE`) and returns the `MRTD`.
+    pub fn finalize(self) -> MeasurementRegister {
+        <[u8; SHA384_LEN]>::from(self.hasher.finalize()).into()
+    }
+}
+
+/// Computes the expected `MRTD` of a TD launched with the given TDVF
+/// firmware image.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the firmware doesn't contain valid TDVF
+/// metadata.
+pub fn predict_mrtd(firmware: &[u8]) -> Result<MeasurementRegister> {
+    let metadata = TdvfMetadata::parse(firmware)?;
+    let mut builder = MrtdBuilder::new();
+
+    for section in metadata.sections.iter().filter(|s| s.is_added()) {
+        let content = match section.section_type {
+            TdvfSectionType::Bfv | TdvfSectionType::Cfv => {
+                let start = section.data_offset as usize;
+                &firmware[start..start + section.raw_data_size as usize]
+            }
+            _ => &[],
+        };
+
+        // the section's content is zero-padded to the size of its memory
+        // region, and added page by page
+        let mut pages = content.chunks(PAGE_SIZE as usize);
+        for i in 0..section.memory_data_size / PAGE_SIZE {
+            let mut page = [0u8; PAGE_SIZE as usize];
+            if let Some(data) = pages.next() {
+                page[..data.len()].copy_from_slice(data);
+            }
+            builder.add_region(
+                section.memory_address + i * PAGE_SIZE,
+                &page,
+                section.is_extended(),
+            );
+        }
+    }
+
+    Ok(builder.finalize())
+}
+
+/// Computes the expected `MRTD` of a TD launched with the TDVF firmware image
+/// at `firmware_path`.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if the file is a symbolic link.
+/// - `Error::IoError` if the file cannot be read.
+/// - `Error::ParseError` if the firmware doesn't contain valid TDVF metadata.
+pub fn predict_mrtd_from_file<P: AsRef<Path>>(firmware_path: P) -> Result<MeasurementRegister> {
+    let path = firmware_path.as_ref();
+
+    // throw an error if the firmware is a symlink
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+
+    predict_mrtd(&fs::read(path)?)
+}
+
+/// Extends a runtime measurement register with an event digest, as done by
+/// `TDG.MR.RTMR.EXTEND`.
+pub fn extend_rtmr(rtmr: &[u8; SHA384_LEN], digest: &[u8; SHA384_LEN]) -> [u8; SHA384_LEN] {
+    let mut hasher = Sha384::new();
+    hasher.update(rtmr);
+    hasher.update(digest);
+    hasher.finalize().into()
+}
+
+/// Replays a sequence of event digests into an initially all-zero runtime
+/// measurement register.
+pub fn replay_rtmr<'a, I>(digests: I) -> MeasurementRegister
+where
+    I: IntoIterator<Item = &'a [u8; SHA384_LEN]>,
+{
+    digests
+        .into_iter()
+        .fold([0u8; SHA384_LEN], |rtmr, digest| extend_rtmr(&rtmr, digest))
+        .into()
+}
+
+/// The artifacts used to boot a TD directly into a kernel.
+#[derive(Clone, Debug)]
+pub struct DirectBootConfig<'a> {
+    /// The EFI-stub kernel image.
+    pub kernel: &'a [u8],
+    /// The initrd, if any.
+    pub initrd: Option<&'a [u8]>,
+    /// The kernel command line, as seen by the kernel.
+    pub cmdline: &'a str,
+    /// The event digests measured into `RTMR0` by the firmware, if known
+    /// (e.g., taken from the event log of a reference boot with the same VMM
+    /// configuration).
+    pub rtmr0_digests: Option<Vec<[u8; SHA384_LEN]>>,
+}
+
+/// Returns the event digests measured into `RTMR1` when TDVF boots `kernel`.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the kernel is not a valid PE/COFF image.
+pub fn rtmr1_digests(kernel: &[u8]) -> Result<Vec<[u8; SHA384_LEN]>> {
+    Ok(vec![
+        authenticode_sha384(kernel)?,
+        sha384(b"Calling EFI Application from Boot Option"),
+        // the separator event
+        sha384(&[0u8; 4]),
+        sha384(b"Exit Boot Services Invocation"),
+        sha384(b"Exit Boot Services Returned with Success"),
+    ])
+}
+
+/// Returns the event digests measured into `RTMR2` by the kernel's EFI stub
+/// for the given command line and initrd.
+pub fn rtmr2_digests(cmdline: &str, initrd: Option<&[u8]>) -> Vec<[u8; SHA384_LEN]> {
+    // the command line is measured as NUL-terminated UTF-16LE load options
+    let load_options: Vec<u8> = cmdline
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+
+    let mut digests = vec![sha384(&load_options)];
+    if let Some(initrd) = initrd {
+        digests.push(sha384(initrd));
+    }
+    digests
+}
+
+/// Computes the expected `RTMR0`, `RTMR1` and `RTMR2` values of a TD booted
+/// directly into a kernel.
+///
+/// `RTMR0` is only predicted if `config.rtmr0_digests` is set.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the kernel is not a valid PE/COFF image.
+pub fn predict_rtmrs(config: &DirectBootConfig) -> Result<ReferenceValues> {
+    Ok(ReferenceValues {
+        rtmr0: config.rtmr0_digests.as_ref().map(replay_rtmr),
+        rtmr1: Some(replay_rtmr(&rtmr1_digests(config.kernel)?)),
+        rtmr2: Some(replay_rtmr(&rtmr2_digests(config.cmdline, config.initrd))),
+        ..Default::default()
+    })
+}
+
+fn sha384(data: &[u8]) -> [u8; SHA384_LEN] {
+    Sha384::digest(data).into()
+}
+
+/// Returns the data of the OVMF GUIDed table entry with the given GUID.
+fn find_ovmf_table_entry<'a>(
+    firmware: &'a [u8],
+    guid: &[u8; GUID_LEN],
+) -> Result<Option<&'a [u8]>> {
+    let footer_end = firmware
+        .len()
+        .checked_sub(OVMF_RESET_VECTOR_AREA_LEN)
+        .ok_or_else(|| Error::ParseError("Firmware is too small".to_string()))?;
+    let footer_start = footer_end
+        .checked_sub(GUID_LEN + 2)
+        .ok_or_else(|| Error::ParseError("Firmware is too small".to_string()))?;
+
+    if firmware[footer_start + 2..footer_end] != OVMF_TABLE_FOOTER_GUID {
+        return Err(Error::ParseError(
+            "OVMF GUIDed table footer not found".to_string(),
+        ));
+    }
+
+    // the table length includes the footer itself
+    let table_len = read_u16(firmware, footer_start) as usize;
+    let table_start = footer_end
+        .checked_sub(table_len)
+        .ok_or_else(|| Error::ParseError("OVMF GUIDed table is truncated".to_string()))?;
+    let table = &firmware[table_start..footer_start];
+
+    // entries are laid out as [data][u16 length][GUID], and are walked from the end
+    let mut end = table.len();
+    while end >= GUID_LEN + 2 {
+        let entry_guid = &table[end - GUID_LEN..end];
+        let entry_len = read_u16(table, end - GUID_LEN - 2) as usize;
+        if entry_len < GUID_LEN + 2 || entry_len > end {
+            return Err(Error::ParseError(
+                "OVMF GUIDed table entry is malformed".to_string(),
+            ));
+        }
+
+        if entry_guid == guid {
+            return Ok(Some(&table[end - entry_len..end - GUID_LEN - 2]));
+        }
+        end -= entry_len;
+    }
+
+    Ok(None)
+}
+
+fn extension_buffer(op: &[u8], gpa: u64) -> [u8; MRTD_EXTENSION_BUFFER_LEN] {
+    let mut buf = [0u8; MRTD_EXTENSION_BUFFER_LEN];
+    buf[..op.len()].copy_from_slice(op);
+    buf[MRTD_EXTENSION_GPA_OFFSET..MRTD_EXTENSION_GPA_OFFSET + 8]
+        .copy_from_slice(&gpa.to_le_bytes());
+    buf
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Builds a minimal TDVF image with a one-page BFV (measured) and a
+    // one-page TD HOB (added, not measured) section
+    pub(crate) fn make_tdvf() -> Vec<u8> {
+        let mut fw = vec![0u8; 0x1000];
+        fw.extend(vec![0xaa; 0x1000]); // BFV content at offset 0x1000
+
+        // TDVF metadata
+        let metadata_offset = fw.len();
+        fw.extend_from_slice(TDVF_SIGNATURE);
+        fw.extend_from_slice(&(16u32 + 2 * 32).to_le_bytes());
+        fw.extend_from_slice(&1u32.to_le_bytes());
+        fw.extend_from_slice(&2u32.to_le_bytes());
+        for (data_offset, raw_size, gpa, section_type, attributes) in [
+            (
+                0x1000u32,
+                0x1000u32,
+                0xffff_f000u64,
+                0u32,
+                TDVF_ATTRIBUTE_MR_EXTEND,
+            ),
+            (0, 0, 0x80_0000, 2, 0),
+        ] {
+            fw.extend_from_slice(&data_offset.to_le_bytes());
+            fw.extend_from_slice(&raw_size.to_le_bytes());
+            fw.extend_from_slice(&gpa.to_le_bytes());
+            fw.extend_from_slice(&0x1000u64.to_le_bytes());
+            fw.extend_from_slice(&section_type.to_le_bytes());
+            fw.extend_from_slice(&attributes.to_le_bytes());
+        }
+
+        // OVMF GUIDed table with the metadata offset entry and the footer
+        let from_end = (fw.len() + 4 + 2 + 16 + 2 + 16 + 0x20 - metadata_offset) as u32;
+        fw.extend_from_slice(&from_end.to_le_bytes());
+        fw.extend_from_slice(&(4u16 + 2 + 16).to_le_bytes());
+        fw.extend_from_slice(&TDX_METADATA_OFFSET_GUID);
+        fw.extend_from_slice(&(4u16 + 2 + 16 + 2 + 16).to_le_bytes());
+        fw.extend_from_slice(&OVMF_TABLE_FOOTER_GUID);
+        fw.extend(vec![0u8; 0x20]); // reset vector
+
+        fw
+    }
+
+    #[test]
+    fn test_parse_tdvf_metadata() -> Result<()> {
+        let metadata = TdvfMetadata::parse(&make_tdvf())?;
+
+        assert_eq!(metadata.version, 1);
+        assert_eq!(metadata.sections.len(), 2);
+        assert_eq!(metadata.sections[0].section_type, TdvfSectionType::Bfv);
+        assert!(metadata.sections[0].is_extended());
+        assert_eq!(metadata.sections[1].section_type, TdvfSectionType::TdHob);
+        assert!(!metadata.sections[1].is_extended());
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_mrtd() -> Result<()> {
+        let mrtd = predict_mrtd(&make_tdvf())?;
+
+        // replay the expected operations by hand
+        let mut hasher = Sha384::new();
+        hasher.update(extension_buffer(b"MEM.PAGE.ADD", 0xffff_f000));
+        for i in 0..16u64 {
+            hasher.update(extension_buffer(b"MR.EXTEND", 0xffff_f000 + i * 256));
+            hasher.update([0xaa; 256]);
+        }
+        hasher.update(extension_buffer(b"MEM.PAGE.ADD", 0x80_0000));
+        let expected: [u8; SHA384_LEN] = hasher.finalize().into();
+
+        assert_eq!(mrtd, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_rtmr() {
+        let digest = [0x11; SHA384_LEN];
+        let rtmr = replay_rtmr(&[digest, digest]);
+
+        let once = extend_rtmr(&[0u8; SHA384_LEN], &digest);
+        assert_eq!(rtmr, extend_rtmr(&once, &digest));
+        assert_eq!(replay_rtmr(&[]), [0u8; SHA384_LEN]);
+    }
+
+    #[test]
+    fn test_rtmr2_digests() {
+        let digests = rtmr2_digests("ro", Some(b"initrd"));
+
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests[0], sha384(&[b'r', 0, b'o', 0, 0, 0]));
+        assert_eq!(digests[1], sha384(b"initrd"));
+    }
+
+    #[test]
+    fn test_predict_rtmrs() -> Result<()> {
+        let kernel = crate::measure::pe::tests::make_pe();
+        let config = DirectBootConfig {
+            kernel: &kernel,
+            initrd: None,
+            cmdline: "console=ttyS0",
+            rtmr0_digests: None,
+        };
+
+        let values = predict_rtmrs(&config)?;
+        assert!(values.mrtd.is_none());
+        assert!(values.rtmr0.is_none());
+        assert_eq!(values.rtmr1, Some(replay_rtmr(&rtmr1_digests(&kernel)?)));
+        assert_eq!(
+            values.rtmr2,
+            Some(replay_rtmr(&rtmr2_digests("console=ttyS0", None)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_mrtd_oversized_section() {
+        let fw = make_tdvf();
+        let section =
+            fw.windows(4).position(|w| w == TDVF_SIGNATURE).unwrap() + TDVF_DESCRIPTOR_LEN;
+
+        // sections beyond the address space, or too large to replay
+        for size in [u64::MAX - PAGE_SIZE + 1, 1 << 40] {
+            let mut fw = fw.clone();
+            fw[section + 16..section + 24].copy_from_slice(&size.to_le_bytes());
+            match predict_mrtd(&fw) {
+                Err(Error::ParseError(_)) => {}
+                other => panic!("Expected ParseError, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_predict_mrtd_not_tdvf() {
+        match predict_mrtd(&[0u8; 0x2000]) {
+            Err(Error::ParseError(_)) => {}
+            other => panic!("Expected ParseError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! # Attestation Metrics
+//!
+//! This module records metrics about the attestation operations performed by
+//! the library, so that fleet operators can monitor attestation health:
+//! - the quotes issued by the TD, by backend, and the quote generation
+//!   failures, by error kind (see `tdx::LinuxTdxProvider::get_quote()`),
+//! - the evidence appraisals, by result, and their latency (see
+//!   `evidence::Bundle::verify()`), and
+//! - the failed appraisal checks, by check name, i.e., the failure reasons.
+//!
+//! The library records its metrics into a process-wide registry, which is
+//! exported in the Prometheus text exposition format. Long-running processes
+//! can serve it to a Prometheus server or an OpenTelemetry collector (via its
+//! Prometheus receiver), and one-shot commands can write it to a file for
+//! node_exporter's textfile collector (see the CLI's `--metrics-file`
+//! option).
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use tdx_workload_attestation::metrics;
+//!
+//! // ... attest or appraise ...
+//!
+//! let exposition = metrics::global().render_prometheus();
+//! assert!(exposition.contains("tdx_attest_quotes_issued_total"));
+//! ```
+
+use crate::error::Result;
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+use crate::evidence::Verdict;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard};
+#[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+use std::time::Duration;
+
+/// The upper bounds of the appraisal latency histogram's buckets, in seconds.
+pub const VERIFICATION_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+static GLOBAL: Metrics = Metrics::new();
+
+/// Returns the process-wide registry the library records its metrics into.
+pub fn global() -> &'static Metrics {
+    &GLOBAL
+}
+
+/// A registry of attestation metrics.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Registry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    quotes_issued: BTreeMap<String, u64>,
+    quote_failures: BTreeMap<String, u64>,
+    verifications: BTreeMap<String, u64>,
+    check_failures: BTreeMap<String, u64>,
+    verification_duration: Histogram,
+}
+
+/// A histogram over `VERIFICATION_BUCKETS`.
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; VERIFICATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [0; VERIFICATION_BUCKETS.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "host-verification", feature = "rustcrypto-verification")),
+        allow(dead_code)
+    )]
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(VERIFICATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+impl Metrics {
+    /// Creates an empty registry.
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(Registry {
+                quotes_issued: BTreeMap::new(),
+                quote_failures: BTreeMap::new(),
+                verifications: BTreeMap::new(),
+                check_failures: BTreeMap::new(),
+                verification_duration: Histogram::new(),
+            }),
+        }
+    }
+
+    /// Records the result of generating a quote with `backend`.
+    pub fn record_quote<T>(&self, backend: &str, result: &Result<T>) {
+        let mut registry = self.lock();
+        match result {
+            Ok(_) => {
+                *registry
+                    .quotes_issued
+                    .entry(backend.to_string())
+                    .or_default() += 1
+            }
+            Err(e) => {
+                let kind = e.kind().as_str().to_string();
+                *registry.quote_failures.entry(kind).or_default() += 1;
+            }
+        }
+    }
+
+    /// Records the result of an evidence appraisal that took `duration`.
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    pub fn record_verification(&self, duration: Duration, result: &Result<Verdict>) {
+        let mut registry = self.lock();
+        let outcome = match result {
+            Ok(verdict) => {
+                for check in verdict.checks.iter().filter(|c| !c.passed) {
+                    *registry
+                        .check_failures
+                        .entry(check.name.clone())
+                        .or_default() += 1;
+                }
+                if verdict.passed() { "passed" } else { "failed" }
+            }
+            Err(_) => "error",
+        };
+        *registry
+            .verifications
+            .entry(outcome.to_string())
+            .or_default() += 1;
+        registry
+            .verification_duration
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render_prometheus(&self) -> String {
+        let registry = self.lock();
+        let mut out = String::new();
+
+        render_counter(
+            &mut out,
+            "tdx_attest_quotes_issued_total",
+            "TD quotes issued, by backend.",
+            "backend",
+            &registry.quotes_issued,
+        );
+        render_counter(
+            &mut out,
+            "tdx_attest_quote_failures_total",
+            "TD quote generation failures, by error kind.",
+            "kind",
+            &registry.quote_failures,
+        );
+        render_counter(
+            &mut out,
+            "tdx_attest_verifications_total",
+            "Evidence appraisals, by result (passed, failed or error).",
+            "result",
+            &registry.verifications,
+        );
+        render_counter(
+            &mut out,
+            "tdx_attest_check_failures_total",
+            "Failed appraisal checks, by check.",
+            "check",
+            &registry.check_failures,
+        );
+
+        let name = "tdx_attest_verification_duration_seconds";
+        let histogram = &registry.verification_duration;
+        let _ = writeln!(out, "# HELP {} Evidence appraisal latency.", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (count, bound) in histogram.buckets.iter().zip(VERIFICATION_BUCKETS) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count);
+        let _ = writeln!(out, "{}_sum {}", name, histogram.sum);
+        let _ = writeln!(out, "{}_count {}", name, histogram.count);
+        out
+    }
+
+    /// Locks the registry, ignoring poisoning: metrics are only ever
+    /// incremented, so they remain consistent if a holder panicked.
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Renders a counter with one label in the Prometheus text exposition format.
+fn render_counter(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &BTreeMap<String, u64>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (value, count) in values {
+        let _ = writeln!(
+            out,
+            "{}{{{}=\"{}\"}} {}",
+            name,
+            label,
+            escape_label(value),
+            count
+        );
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_render_quote_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_quote("kvm", &Ok(()));
+        metrics.record_quote("kvm", &Ok(()));
+        metrics.record_quote::<()>("kvm", &Err(Error::QuoteError("busy".to_string())));
+
+        let out = metrics.render_prometheus();
+        assert!(out.contains("# TYPE tdx_attest_quotes_issued_total counter\n"));
+        assert!(out.contains("tdx_attest_quotes_issued_total{backend=\"kvm\"} 2\n"));
+        assert!(out.contains("tdx_attest_quote_failures_total{kind=\"quote\"} 1\n"));
+        assert!(out.contains("tdx_attest_verification_duration_seconds_count 0\n"));
+        assert_eq!(escape_label("a\"b\\"), "a\\\"b\\\\");
+    }
+
+    #[cfg(any(feature = "host-verification", feature = "rustcrypto-verification"))]
+    #[test]
+    fn test_render_verification_metrics() {
+        use crate::evidence::Check;
+
+        let metrics = Metrics::new();
+        let verdict = Verdict {
+            checks: vec![
+                Check {
+                    name: "quote-signature".to_string(),
+                    passed: true,
+                    detail: None,
+                },
+                Check {
+                    name: "nonce".to_string(),
+                    passed: false,
+                    detail: Some("Quote does not bind the nonce".to_string()),
+                },
+            ],
+            ..Default::default()
+        };
+        metrics.record_verification(Duration::from_millis(20), &Ok(verdict));
+        metrics.record_verification(
+            Duration::from_secs(2),
+            &Err(Error::ParseError("bad".to_string())),
+        );
+
+        let out = metrics.render_prometheus();
+        assert!(out.contains("tdx_attest_verifications_total{result=\"failed\"} 1\n"));
+        assert!(out.contains("tdx_attest_verifications_total{result=\"error\"} 1\n"));
+        assert!(out.contains("tdx_attest_check_failures_total{check=\"nonce\"} 1\n"));
+        assert!(out.contains("tdx_attest_verification_duration_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(out.contains("tdx_attest_verification_duration_seconds_bucket{le=\"0.05\"} 1\n"));
+        assert!(out.contains("tdx_attest_verification_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(out.contains("tdx_attest_verification_duration_seconds_sum 2.02\n"));
+    }
+}
@@ -0,0 +1,520 @@
+//! # Platform Capability Detection
+//!
+//! This module provides utilities for detecting the attestation-related
+//! capabilities of the current compute environment, such as which TDX guest
+//! interfaces are available, whether the Quote Generation Service (QGS) is
+//! reachable, which cloud provider hosts the VM, whether a vTPM is present,
+//! and whether the TD runs under a paravisor or a nested hypervisor. It also
+//! detects TDX guests from CPUID, even when the TDX guest device is hidden
+//! (e.g., from a container).
+//!
+//! The resulting `PlatformCapabilities` can be serialized to JSON, which is
+//! useful for provisioning-time diagnostics.
+//!
+//! Applications that should degrade gracefully rather than treat a missing
+//! attestation interface as fatal (e.g., run, but refuse to load secrets)
+//! can instead branch on the `AttestationCapability` level returned by
+//! `probe_capabilities()`.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::platform::detect_capabilities;
+//!
+//! let caps = detect_capabilities().unwrap();
+//! println!("{}", serde_json::to_string_pretty(&caps).unwrap());
+//! ```
+//!
+//! ```no_run
+//! use tdx_workload_attestation::platform::{AttestationCapability, probe_capabilities};
+//!
+//! let capability = probe_capabilities();
+//! if capability < AttestationCapability::QuoteLocal {
+//!     eprintln!("Attestation unavailable ({:?}): not loading secrets", capability);
+//! }
+//! ```
+
+use crate::error::Result;
+use crate::get_platform_name;
+#[cfg(feature = "tdx-linux")]
+use crate::tdx::linux::{device, qgs};
+#[cfg(feature = "tdx-linux")]
+use crate::tdx::{LinuxTdxProvider, TdxBackend};
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The path to the configfs-tsm report interface.
+pub const TSM_REPORT_PATH: &str = "/sys/kernel/config/tsm/report";
+
+// The paths to the TPM device nodes (resource manager first)
+const TPM_DEV_PATHS: [&str; 2] = ["/dev/tpmrm0", "/dev/tpm0"];
+
+// The DMI files used for cloud provider detection
+const DMI_VENDOR_PATHS: [&str; 3] = [
+    "/sys/class/dmi/id/sys_vendor",
+    "/sys/class/dmi/id/bios_vendor",
+    "/sys/class/dmi/id/product_name",
+];
+
+// The TDX guest enumeration CPUID leaf, and the signature it returns in
+// EBX, EDX and ECX in TDX guests
+const TDX_CPUID_LEAF: u32 = 0x21;
+const TDX_CPUID_SIGNATURE: &[u8; 12] = b"IntelTDX    ";
+
+// The CPU flag the kernel reports in /proc/cpuinfo for TDX guests
+const CPUINFO_PATH: &str = "/proc/cpuinfo";
+const TDX_GUEST_CPU_FLAG: &str = "tdx_guest";
+
+// The hypervisor present bit of CPUID leaf 1 ECX
+const CPUID_HYPERVISOR_PRESENT: u32 = 1 << 31;
+
+// The Hyper-V CPUID leaves and bits used for paravisor and nesting detection
+const HV_CPUID_VENDOR: u32 = 0x4000_0000;
+const HV_CPUID_HINTS: u32 = 0x4000_0004;
+const HV_CPUID_ISOLATION: u32 = 0x4000_000c;
+const HV_NESTED: u32 = 1 << 12;
+const HV_PARAVISOR_PRESENT: u32 = 1 << 0;
+const HYPERV_VENDOR: &[u8; 12] = b"Microsoft Hv";
+
+/// The cloud provider hosting the VM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudProvider {
+    /// Alibaba Cloud
+    Alibaba,
+    /// Amazon Web Services
+    Aws,
+    /// Microsoft Azure
+    Azure,
+    /// Google Cloud Platform
+    Gcp,
+}
+
+/// The virtualization layers between the guest and the TDX module, which
+/// verification policies may need to account for (e.g., a paravisor owns the
+/// TD's RTMRs and reports, and only forwards evidence to the guest).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VirtualizationEnvironment {
+    /// The guest runs directly in the TD.
+    Direct,
+    /// The guest runs in a partitioned TD, under a paravisor (e.g., the
+    /// Hyper-V HCL) in the TD's first VM.
+    Paravisor,
+    /// The guest runs under a nested hypervisor.
+    Nested,
+}
+
+/// A structured report of the attestation capabilities of the platform.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlatformCapabilities {
+    /// The platform name, as returned by `get_platform_name()`.
+    pub platform: String,
+    /// Whether the system is a TDX guest (see `is_tdx_guest()`), even if no
+    /// TDX guest interface is available.
+    #[serde(default)]
+    pub tdx_guest: bool,
+    /// The TDX guest interface version, if a TDX guest device was found.
+    pub tdx_version: Option<String>,
+    /// The path of the TDX guest device, if found.
+    pub tdx_device_path: Option<String>,
+    /// Whether the configfs-tsm report interface is available.
+    pub configfs_tsm: bool,
+    /// The vsock port of the QGS, if configured.
+    pub qgs_vsock_port: Option<u32>,
+    /// Whether the QGS accepted a connection, or `None` if it could not be
+    /// probed.
+    pub qgs_reachable: Option<bool>,
+    /// The cloud provider hosting the VM, if detected.
+    pub cloud_provider: Option<CloudProvider>,
+    /// Whether a TPM (typically a vTPM in cloud VMs) is present.
+    pub vtpm: bool,
+    /// The virtualization environment of the guest, if it could be
+    /// detected.
+    #[serde(default)]
+    pub virtualization: Option<VirtualizationEnvironment>,
+}
+
+/// Detects the attestation capabilities of the current platform.
+///
+/// # Errors
+///
+/// Returns an error if the platform name cannot be determined (see
+/// `get_platform_name()`).
+pub fn detect_capabilities() -> Result<PlatformCapabilities> {
+    let platform = get_platform_name()?;
+
+    #[cfg(feature = "tdx-linux")]
+    let (tdx_version, tdx_device_path) = match device::TdxDeviceKvmV15::is_available() {
+        Ok(true) => (
+            Some("1.5".to_string()),
+            Some(device::TDX15_DEV_PATH.to_string()),
+        ),
+        _ => (None, None),
+    };
+    #[cfg(not(feature = "tdx-linux"))]
+    let (tdx_version, tdx_device_path) = (None, None);
+
+    #[cfg(feature = "tdx-linux")]
+    let qgs_vsock_port = qgs::configured_vsock_port().unwrap_or(None);
+    #[cfg(not(feature = "tdx-linux"))]
+    let qgs_vsock_port = None;
+
+    #[cfg(feature = "tdx-linux")]
+    let qgs_reachable = qgs_vsock_port.map(qgs::is_vsock_reachable);
+    #[cfg(not(feature = "tdx-linux"))]
+    let qgs_reachable = None;
+
+    Ok(PlatformCapabilities {
+        platform,
+        tdx_guest: is_tdx_guest(),
+        tdx_version,
+        tdx_device_path,
+        configfs_tsm: Path::new(TSM_REPORT_PATH).is_dir(),
+        qgs_vsock_port,
+        qgs_reachable,
+        cloud_provider: detect_cloud_provider(),
+        vtpm: TPM_DEV_PATHS.iter().any(|p| Path::new(p).exists()),
+        virtualization: detect_virtualization(),
+    })
+}
+
+/// The level of attestation the platform supports, ordered from none to
+/// quotes that remote verifiers can verify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationCapability {
+    /// The system cannot attest, as it's not a TDX guest.
+    None,
+    /// The system is a TDX guest, but cannot attest, as its TDX guest
+    /// interfaces are unavailable (e.g., the device node is hidden from a
+    /// container, or inaccessible).
+    DeviceUnavailable,
+    /// The TD's reports can be retrieved, but not quotes, e.g., without a
+    /// reachable QGS. Reports can only be verified on the same platform
+    /// (see `LinuxTdxProvider::verify_tdreport()`).
+    ReportOnly,
+    /// Quotes can be generated, but don't embed the PCK certificate chain:
+    /// verifiers must retrieve it (e.g., from Intel's PCS, see the
+    /// `evidence::pck` module).
+    QuoteLocal,
+    /// Quotes can be generated, and embed the PCK certificate chain that
+    /// remote verifiers verify them with.
+    QuoteRemoteVerified,
+}
+
+impl AttestationCapability {
+    /// Returns whether the TD can generate quotes.
+    pub fn can_quote(&self) -> bool {
+        *self >= AttestationCapability::QuoteLocal
+    }
+}
+
+/// Probes the attestation capability of the TD, with the detected TDX
+/// backend.
+///
+/// Unlike `detect_capabilities()`, this actually retrieves a report and
+/// generates a quote (over all-zero report data), so it takes a round trip
+/// to the QGS: applications should probe once, e.g., at startup.
+pub fn probe_capabilities() -> AttestationCapability {
+    #[cfg(feature = "tdx-linux")]
+    return probe_capabilities_with(&LinuxTdxProvider::new());
+    #[cfg(not(feature = "tdx-linux"))]
+    unavailable_capability()
+}
+
+/// Probes the attestation capability of the TD with `provider` (e.g.,
+/// `LinuxTdxProvider::from_config()`), like `probe_capabilities()`.
+#[cfg(feature = "tdx-linux")]
+pub fn probe_capabilities_with(provider: &LinuxTdxProvider) -> AttestationCapability {
+    // configfs-tsm alone generates quotes without exposing TDREPORTs
+    let reports = provider.get_tdreport().is_ok();
+    if !reports && provider.backend() != TdxBackend::Tsm {
+        return unavailable_capability();
+    }
+    match provider.get_quote(&[0; crate::tdx::TDX_REPORT_DATA_LEN]) {
+        Ok(quote) => quote_capability(&quote),
+        Err(_) if reports => AttestationCapability::ReportOnly,
+        Err(_) => unavailable_capability(),
+    }
+}
+
+// Returns the capability of a system whose TDX guest interfaces are
+// unavailable
+fn unavailable_capability() -> AttestationCapability {
+    if is_tdx_guest() {
+        AttestationCapability::DeviceUnavailable
+    } else {
+        AttestationCapability::None
+    }
+}
+
+/// Returns the capability of a TD that generated `quote`.
+#[cfg_attr(not(feature = "tdx-linux"), allow(dead_code))]
+fn quote_capability(quote: &[u8]) -> AttestationCapability {
+    use crate::core::quote::Quote;
+
+    match Quote::from_bytes(quote) {
+        // a malformed quote is as good as none
+        Err(_) => AttestationCapability::ReportOnly,
+        Ok(quote) => match quote.pck_chain() {
+            Ok(chain) if !chain.is_empty() => AttestationCapability::QuoteRemoteVerified,
+            _ => AttestationCapability::QuoteLocal,
+        },
+    }
+}
+
+/// Detects the cloud provider hosting the VM from the DMI system information.
+pub fn detect_cloud_provider() -> Option<CloudProvider> {
+    DMI_VENDOR_PATHS
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .find_map(|vendor| cloud_provider_from_vendor(&vendor))
+}
+
+fn cloud_provider_from_vendor(vendor: &str) -> Option<CloudProvider> {
+    let vendor = vendor.trim();
+
+    if vendor.starts_with("Google") {
+        Some(CloudProvider::Gcp)
+    } else if vendor.starts_with("Microsoft Corporation") {
+        Some(CloudProvider::Azure)
+    } else if vendor.starts_with("Amazon") {
+        Some(CloudProvider::Aws)
+    } else if vendor.starts_with("Alibaba") {
+        Some(CloudProvider::Alibaba)
+    } else {
+        None
+    }
+}
+
+/// Checks whether the system is a TDX guest, from the CPUID TDX guest
+/// enumeration leaf, or the `tdx_guest` CPU flag the kernel reports.
+///
+/// Unlike `get_platform_name()`'s device checks, this doesn't need access to
+/// the TDX guest device, so it also detects TDX guests where the device node
+/// is hidden (e.g., from a container) or inaccessible.
+pub fn is_tdx_guest() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    if tdx_guest_from_cpuid(cpuid) {
+        return true;
+    }
+    fs::read_to_string(CPUINFO_PATH).is_ok_and(|cpuinfo| tdx_guest_from_cpuinfo(&cpuinfo))
+}
+
+// Checks the TDX guest enumeration leaf of the CPUID leaves returned as
+// [EAX, EBX, ECX, EDX] by `cpuid`
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+fn tdx_guest_from_cpuid(cpuid: impl Fn(u32) -> [u32; 4]) -> bool {
+    if cpuid(0)[0] < TDX_CPUID_LEAF {
+        return false;
+    }
+
+    let [_, ebx, ecx, edx] = cpuid(TDX_CPUID_LEAF);
+    let signature: Vec<u8> = [ebx, edx, ecx]
+        .iter()
+        .flat_map(|r| r.to_le_bytes())
+        .collect();
+    signature == TDX_CPUID_SIGNATURE
+}
+
+// Checks the CPU flags of /proc/cpuinfo for the TDX guest flag
+fn tdx_guest_from_cpuinfo(cpuinfo: &str) -> bool {
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .filter_map(|line| line.split_once(':'))
+        .any(|(_, flags)| flags.split_whitespace().any(|f| f == TDX_GUEST_CPU_FLAG))
+}
+
+// Executes CPUID for `leaf` (with subleaf 0), returning [EAX, EBX, ECX, EDX]
+#[cfg(target_arch = "x86_64")]
+fn cpuid(leaf: u32) -> [u32; 4] {
+    let r = std::arch::x86_64::__cpuid(leaf);
+    [r.eax, r.ebx, r.ecx, r.edx]
+}
+
+/// Detects whether the guest runs directly in the TD, under a paravisor or
+/// under a nested hypervisor, from the CPUID hypervisor leaves.
+///
+/// Returns `None` on architectures without CPUID.
+pub fn detect_virtualization() -> Option<VirtualizationEnvironment> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        Some(virtualization_from_cpuid(cpuid))
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        None
+    }
+}
+
+// Classifies the virtualization environment from the CPUID leaves returned
+// as [EAX, EBX, ECX, EDX] by `cpuid`
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+fn virtualization_from_cpuid(cpuid: impl Fn(u32) -> [u32; 4]) -> VirtualizationEnvironment {
+    // without the hypervisor present bit, there are no hypervisor leaves
+    if cpuid(1)[2] & CPUID_HYPERVISOR_PRESENT == 0 {
+        return VirtualizationEnvironment::Direct;
+    }
+
+    let [max_leaf, ebx, ecx, edx] = cpuid(HV_CPUID_VENDOR);
+    let vendor: Vec<u8> = [ebx, ecx, edx]
+        .iter()
+        .flat_map(|r| r.to_le_bytes())
+        .collect();
+    if vendor != HYPERV_VENDOR {
+        return VirtualizationEnvironment::Direct;
+    }
+
+    if max_leaf >= HV_CPUID_ISOLATION && cpuid(HV_CPUID_ISOLATION)[0] & HV_PARAVISOR_PRESENT != 0 {
+        VirtualizationEnvironment::Paravisor
+    } else if max_leaf >= HV_CPUID_HINTS && cpuid(HV_CPUID_HINTS)[0] & HV_NESTED != 0 {
+        VirtualizationEnvironment::Nested
+    } else {
+        VirtualizationEnvironment::Direct
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_capabilities() -> Result<()> {
+        let caps = detect_capabilities()?;
+
+        // the capability report should always be serializable
+        let json = serde_json::to_string(&caps).unwrap();
+        assert!(json.contains("configfs_tsm"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_capability() {
+        use crate::core::quote::tests::QuoteParts;
+
+        let pem = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n";
+        let quote = |pck_chain: &str| {
+            QuoteParts {
+                pck_chain: pck_chain.to_string(),
+                ..Default::default()
+            }
+            .assemble(&[6; 64], &[7; 64])
+        };
+
+        assert_eq!(
+            quote_capability(&quote(pem)),
+            AttestationCapability::QuoteRemoteVerified
+        );
+        assert_eq!(
+            quote_capability(&quote("")),
+            AttestationCapability::QuoteLocal
+        );
+        assert_eq!(
+            quote_capability(&[0; 16]),
+            AttestationCapability::ReportOnly
+        );
+
+        assert!(AttestationCapability::QuoteLocal.can_quote());
+        assert!(!AttestationCapability::ReportOnly.can_quote());
+        assert!(AttestationCapability::None < AttestationCapability::DeviceUnavailable);
+        assert!(AttestationCapability::DeviceUnavailable < AttestationCapability::ReportOnly);
+    }
+
+    #[test]
+    fn test_cloud_provider_from_vendor() {
+        assert_eq!(
+            cloud_provider_from_vendor("Google\n"),
+            Some(CloudProvider::Gcp)
+        );
+        assert_eq!(
+            cloud_provider_from_vendor("Microsoft Corporation"),
+            Some(CloudProvider::Azure)
+        );
+        assert_eq!(cloud_provider_from_vendor("QEMU"), None);
+    }
+
+    #[test]
+    fn test_tdx_guest_from_cpuid() {
+        let r = |b: &[u8]| u32::from_le_bytes(b.try_into().unwrap());
+        let td = |leaf| match leaf {
+            0 => [TDX_CPUID_LEAF, 0, 0, 0],
+            TDX_CPUID_LEAF => [
+                0,
+                r(&TDX_CPUID_SIGNATURE[..4]),
+                r(&TDX_CPUID_SIGNATURE[8..]),
+                r(&TDX_CPUID_SIGNATURE[4..8]),
+            ],
+            _ => [0; 4],
+        };
+        assert!(tdx_guest_from_cpuid(td));
+
+        // the leaf is only valid up to the maximum basic leaf
+        assert!(!tdx_guest_from_cpuid(|leaf| match leaf {
+            0 => [0x20, 0, 0, 0],
+            _ => td(leaf),
+        }));
+        assert!(!tdx_guest_from_cpuid(|_| [u32::MAX; 4]));
+    }
+
+    #[test]
+    fn test_tdx_guest_from_cpuinfo() {
+        assert!(tdx_guest_from_cpuinfo(
+            "processor\t: 0\nflags\t\t: fpu vme tdx_guest x2apic\n"
+        ));
+        assert!(!tdx_guest_from_cpuinfo(
+            "processor\t: 0\nflags\t\t: fpu vme x2apic\nbugs\t\t: tdx_guest\n"
+        ));
+    }
+
+    #[test]
+    fn test_virtualization_from_cpuid() {
+        let hyperv = |isolation: u32, hints: u32| {
+            move |leaf| match leaf {
+                1 => [0, 0, CPUID_HYPERVISOR_PRESENT, 0],
+                HV_CPUID_VENDOR => {
+                    let r = |b: &[u8]| u32::from_le_bytes(b.try_into().unwrap());
+                    [
+                        HV_CPUID_ISOLATION,
+                        r(&HYPERV_VENDOR[..4]),
+                        r(&HYPERV_VENDOR[4..8]),
+                        r(&HYPERV_VENDOR[8..]),
+                    ]
+                }
+                HV_CPUID_HINTS => [hints, 0, 0, 0],
+                HV_CPUID_ISOLATION => [isolation, 0, 0, 0],
+                _ => [0; 4],
+            }
+        };
+
+        assert_eq!(
+            virtualization_from_cpuid(hyperv(HV_PARAVISOR_PRESENT, 0)),
+            VirtualizationEnvironment::Paravisor
+        );
+        assert_eq!(
+            virtualization_from_cpuid(hyperv(0, HV_NESTED)),
+            VirtualizationEnvironment::Nested
+        );
+        assert_eq!(
+            virtualization_from_cpuid(hyperv(0, 0)),
+            VirtualizationEnvironment::Direct
+        );
+
+        // KVM's leaves aren't interpreted
+        assert_eq!(
+            virtualization_from_cpuid(|leaf| match leaf {
+                1 => [0, 0, CPUID_HYPERVISOR_PRESENT, 0],
+                HV_CPUID_VENDOR => [HV_CPUID_ISOLATION, 0x4b4d_564b, 0x564b_4d56, 0x4d],
+                _ => [u32::MAX; 4],
+            }),
+            VirtualizationEnvironment::Direct
+        );
+        assert_eq!(
+            virtualization_from_cpuid(|_| [0; 4]),
+            VirtualizationEnvironment::Direct
+        );
+    }
+}
@@ -6,11 +6,276 @@
 //!
 //! The trait provides a function for retrieving TEE attestation reports and
 //! launch-time measurements.
+//!
+//! Accessing the TDX guest device typically requires root. Unprivileged
+//! processes can instead use an `AgentProvider`, which delegates to an
+//! attestation agent owning the device (see the `agent` module and
+//! `tdx-attest serve`) over its Unix socket:
+//!
+//! ```no_run
+//! use tdx_workload_attestation::provider::{AgentProvider, AttestationProvider};
+//!
+//! let provider = AgentProvider::new();
+//! let report = provider.get_attestation_report().unwrap();
+//! let quote = provider.get_quote(&[0; 64]).unwrap();
+//! ```
 
+use crate::core::register::MeasurementRegister;
 use crate::error::Result;
+#[cfg(feature = "tdx-linux")]
+pub use agent_client::AgentProvider;
 
 pub trait AttestationProvider {
     fn get_attestation_report(&self) -> Result<String>;
     // TODO: Make the return value less dependent on TDX
-    fn get_launch_measurement(&self) -> Result<[u8; 48]>;
+    fn get_launch_measurement(&self) -> Result<MeasurementRegister>;
+}
+
+#[cfg(feature = "tdx-linux")]
+mod agent_client {
+    use super::AttestationProvider;
+    use crate::agent::{DEFAULT_SOCKET_PATH, Request, Response};
+    use crate::core::register::MeasurementRegister;
+    use crate::error::{Error, Result};
+    use crate::schema::VersionedReport;
+    use crate::tdx::TDX_REPORT_DATA_LEN;
+    use crate::tdx::report::{CollectedEvidence, TdReportV15};
+
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    // The default time to wait for the agent's response, which includes
+    // the quote's generation
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// An attestation provider delegating to an attestation agent over its
+    /// Unix socket, so that the caller needs no access to the TDX guest
+    /// device.
+    ///
+    /// The agent's access policy must allow the caller's user, group or
+    /// cgroup. Each request opens a new connection.
+    #[derive(Clone, Debug)]
+    pub struct AgentProvider {
+        socket_path: PathBuf,
+        timeout: Duration,
+    }
+
+    impl Default for AgentProvider {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl AgentProvider {
+        /// Creates a provider delegating to the agent at the default socket
+        /// path.
+        pub fn new() -> Self {
+            Self {
+                socket_path: PathBuf::from(DEFAULT_SOCKET_PATH),
+                timeout: DEFAULT_TIMEOUT,
+            }
+        }
+
+        /// Sets the path of the agent's socket.
+        pub fn with_socket_path(mut self, socket_path: &str) -> Self {
+            self.socket_path = PathBuf::from(socket_path);
+            self
+        }
+
+        /// Sets the time to wait for the agent's response to each request.
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+
+        /// Retrieves the TD's `TDREPORT` over `report_data` from the agent.
+        ///
+        /// # Errors
+        ///
+        /// Same as `get_quote()`, or an `Error::ParseError` if the agent's
+        /// report is malformed, its info hashes don't match its contents, or
+        /// it wasn't retrieved over `report_data`.
+        pub fn get_tdreport(
+            &self,
+            report_data: &[u8; TDX_REPORT_DATA_LEN],
+        ) -> Result<CollectedEvidence> {
+            let request = Request::Report {
+                report_data: hex::encode(report_data),
+            };
+            match self.request(&request)? {
+                Response::Report { report } => {
+                    let report = TdReportV15::from_bytes(&decode(&report)?)?;
+                    Ok(CollectedEvidence::new(report, report_data)?)
+                }
+                response => Err(unexpected(&response)),
+            }
+        }
+
+        /// Retrieves a signed TD quote over `report_data` from the agent.
+        ///
+        /// # Errors
+        ///
+        /// Returns an `Error::NotSupported` if no agent is listening on the
+        /// socket, an `Error::RateLimited` if the agent rate limited the
+        /// request, an `Error::QuoteError` if the agent refused or failed
+        /// the request, or an `Error::IoError` if the connection fails.
+        pub fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+            self.quote(&Request::Quote {
+                report_data: hex::encode(report_data),
+            })
+        }
+
+        /// Retrieves a signed TD quote bound to the caller's `public_key` and
+        /// `nonce` from an agent binding quotes to client keys (see the
+        /// `agent::binding` module).
+        ///
+        /// # Errors
+        ///
+        /// Same as `get_quote()`.
+        pub fn get_bound_quote(&self, public_key: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+            self.quote(&Request::BoundQuote {
+                public_key: STANDARD.encode(public_key),
+                nonce: hex::encode(nonce),
+            })
+        }
+
+        fn quote(&self, request: &Request) -> Result<Vec<u8>> {
+            match self.request(request)? {
+                Response::Quote { quote } => decode(&quote),
+                response => Err(unexpected(&response)),
+            }
+        }
+
+        /// Sends `request` to the agent, and returns its response, or the
+        /// error it responded with.
+        fn request(&self, request: &Request) -> Result<Response> {
+            let mut stream =
+                UnixStream::connect(&self.socket_path).map_err(|e| match e.kind() {
+                    ErrorKind::NotFound | ErrorKind::ConnectionRefused => {
+                        Error::NotSupported(format!(
+                            "No attestation agent is listening on {}",
+                            self.socket_path.display()
+                        ))
+                    }
+                    _ => Error::IoError(e),
+                })?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+
+            let mut line = serde_json::to_vec(request)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            line.push(b'\n');
+            stream.write_all(&line)?;
+
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line)?;
+            let response = serde_json::from_str(&line).map_err(|e| {
+                Error::ParseError(format!("Invalid response from the agent: {}", e))
+            })?;
+            match response {
+                Response::Error {
+                    retry_after_ms: Some(ms),
+                    ..
+                } => Err(Error::RateLimited(Duration::from_millis(ms))),
+                Response::Error { error, .. } => Err(Error::QuoteError(format!(
+                    "The agent failed the request: {}",
+                    error
+                ))),
+                response => Ok(response),
+            }
+        }
+    }
+
+    impl AttestationProvider for AgentProvider {
+        /// Retrieves the TD's report from the agent, serialized into JSON
+        /// (like `LinuxTdxProvider`'s).
+        fn get_attestation_report(&self) -> Result<String> {
+            let report = self.get_tdreport(&[0; TDX_REPORT_DATA_LEN])?;
+            VersionedReport::new(report.into_report()).to_json()
+        }
+
+        /// Retrieves the TD's launch measurement (`MRTD`) from its report.
+        fn get_launch_measurement(&self) -> Result<MeasurementRegister> {
+            Ok(self
+                .get_tdreport(&[0; TDX_REPORT_DATA_LEN])?
+                .report()
+                .get_mrtd())
+        }
+    }
+
+    fn decode(data: &str) -> Result<Vec<u8>> {
+        STANDARD
+            .decode(data)
+            .map_err(|e| Error::ParseError(format!("Invalid response from the agent: {}", e)))
+    }
+
+    fn unexpected(response: &Response) -> Error {
+        Error::ParseError(format!(
+            "Unexpected response from the agent: {:?}",
+            response
+        ))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::agent::peer::AccessPolicy;
+        use crate::agent::{Agent, QuoteSource, bind};
+        use std::thread;
+
+        /// A quote source echoing the report data.
+        struct EchoSource;
+
+        impl QuoteSource for EchoSource {
+            fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+                Ok(report_data.to_vec())
+            }
+
+            fn get_tdreport(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TdReportV15> {
+                Ok(TdReportV15::builder()
+                    .with_report_data(report_data)
+                    .with_mrtd(&[7; 48])
+                    .with_computed_info_hashes()
+                    .build())
+            }
+        }
+
+        #[test]
+        fn test_agent_provider() -> Result<()> {
+            let dir = std::env::temp_dir().join(format!("tdx-provider-{}", rand::random::<u64>()));
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join("agent.sock");
+            let provider = AgentProvider::new().with_socket_path(path.to_str().unwrap());
+
+            // no agent is listening
+            assert!(
+                provider
+                    .get_quote(&[0; 64])
+                    .is_err_and(|e| e.is_not_supported())
+            );
+
+            let listener = bind(&path, 0o600)?;
+            let uid = unsafe { libc::geteuid() };
+            let agent = Agent::new(EchoSource, AccessPolicy::new().with_uid(uid));
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    for stream in listener.incoming().take(3) {
+                        let _ = agent.handle_connection(stream.unwrap());
+                    }
+                });
+
+                assert_eq!(provider.get_quote(&[1; 64])?, vec![1; 64]);
+                assert_eq!(provider.get_tdreport(&[2; 64])?.report_data(), [2; 64]);
+                assert_eq!(provider.get_launch_measurement()?, [7; 48]);
+                Ok::<_, Error>(())
+            })?;
+
+            std::fs::remove_dir_all(&dir)?;
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,284 @@
+//! # Retry and Timeout Policy
+//!
+//! This module provides the `RetryPolicy` type, which controls how the library
+//! retries operations that depend on external services, such as downloading
+//! root certificates or fetching cloud launch endorsements.
+//!
+//! Only transient failures (`ErrorKind::Network` and `ErrorKind::Io`) are
+//! retried. Failures such as parsing or signature errors are returned
+//! immediately, since retrying them would produce the same result. Callers
+//! that know better which of their failures are transient (e.g., a busy Quote
+//! Generation Service) can select them with `RetryPolicy::run_when()`.
+//!
+//! With jitter (see `RetryPolicy::with_jitter()`), the delays between
+//! retries are randomized, so that concurrent callers failing together don't
+//! retry in lockstep.
+//!
+//! ## Example Usage
+//!
+//! ```
+//! use std::time::Duration;
+//! use tdx_workload_attestation::retry::RetryPolicy;
+//!
+//! let policy = RetryPolicy::default()
+//!     .with_max_attempts(5)
+//!     .with_backoff(Duration::from_millis(200), Duration::from_secs(2))
+//!     .with_timeout(Duration::from_secs(10));
+//!
+//! let value = policy.run(|| Ok(42)).unwrap();
+//! assert_eq!(value, 42);
+//! ```
+
+use crate::error::{Error, ErrorKind, Result};
+use std::hash::{BuildHasher, RandomState};
+use std::thread;
+use std::time::Duration;
+
+/// The default number of attempts made for a retryable operation.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// The default delay before the first retry.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The default upper bound on the delay between retries.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// The default timeout applied to each individual request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configures retries with exponential backoff and per-request timeouts for
+/// operations that depend on external services.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Option<Duration>,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            timeout: Some(DEFAULT_TIMEOUT),
+            jitter: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that makes a single attempt without any retries.
+    pub fn no_retry() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the maximum number of attempts (including the first one).
+    ///
+    /// A value of `0` is treated as `1`.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry and the upper bound on the delay
+    /// between subsequent retries. The delay doubles after every attempt.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Sets the timeout applied to each individual request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether the delays between retries are jittered, i.e., drawn
+    /// uniformly between half the backoff and the full backoff.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Disables the per-request timeout.
+    pub fn without_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Returns the maximum number of attempts.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the per-request timeout, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the delay to wait after the given (zero-based) failed attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Returns the delay to wait after the given (zero-based) failed attempt,
+    /// with jitter if enabled.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        if !self.jitter {
+            return backoff;
+        }
+        // a fresh RandomState is randomly keyed, which is random enough to
+        // spread retries
+        let random = RandomState::new().hash_one(attempt);
+        let half = backoff / 2;
+        half + half.mul_f64(random as f64 / u64::MAX as f64)
+    }
+
+    /// Runs `op` until it succeeds, fails with a non-transient error, or the
+    /// maximum number of attempts is reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from the last attempt.
+    pub fn run<T, F>(&self, op: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        self.run_when(op, |e| is_transient(e.kind()))
+    }
+
+    /// Runs `op` until it succeeds, fails with an error that `retryable`
+    /// rejects, or the maximum number of attempts is reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from the last attempt.
+    pub fn run_when<T, F, P>(&self, mut op: F, retryable: P) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+        P: Fn(&Error) -> bool,
+    {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    attempt += 1;
+                    if attempt >= self.max_attempts || !retryable(&e) {
+                        return Err(e);
+                    }
+                    thread::sleep(self.delay(attempt - 1));
+                }
+            }
+        }
+    }
+}
+
+fn is_transient(kind: ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Network | ErrorKind::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .with_max_attempts(attempts)
+            .with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    #[test]
+    fn test_retry_transient_error() -> Result<()> {
+        let mut calls = 0;
+        let value = test_policy(3).run(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(Error::NetworkError("unreachable".to_string()))
+            } else {
+                Ok(calls)
+            }
+        })?;
+
+        assert_eq!(value, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut calls = 0;
+        let result: Result<()> = test_policy(2).run(|| {
+            calls += 1;
+            Err(Error::NetworkError("unreachable".to_string()))
+        });
+
+        assert!(result.unwrap_err().is_network());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_no_retry_on_permanent_error() {
+        let mut calls = 0;
+        let result: Result<()> = test_policy(5).run(|| {
+            calls += 1;
+            Err(Error::ParseError("bad data".to_string()))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(350));
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_retry_when() {
+        let mut calls = 0;
+        let result: Result<()> = test_policy(5).run_when(
+            || {
+                calls += 1;
+                Err(Error::QuoteError(format!("attempt {}", calls)))
+            },
+            |e| e.to_string().ends_with("attempt 1"),
+        );
+
+        assert_eq!(result.unwrap_err().to_string(), "Quote error: attempt 2");
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_jittered_delay() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(true);
+
+        for attempt in 0..8 {
+            let backoff = policy.backoff(attempt);
+            let delay = policy.delay(attempt);
+            assert!(backoff / 2 <= delay && delay <= backoff, "{:?}", delay);
+        }
+        assert_eq!(
+            policy.with_jitter(false).delay(1),
+            Duration::from_millis(200)
+        );
+    }
+}
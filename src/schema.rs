@@ -0,0 +1,225 @@
+//! # Schema Versioning and Migration
+//!
+//! Every artifact the library serializes for storage carries an explicit
+//! schema version, so that artifacts stored by older releases remain
+//! readable (and evidence remains appraisable) as the formats evolve:
+//! - TD reports, serialized into JSON as `VersionedReport`s with a
+//!   `schema_version` field (see `AttestationProvider::get_attestation_report()`),
+//! - evidence bundles, whose `version` field is their schema version (see
+//!   `evidence::BUNDLE_VERSION`), and
+//! - verdicts, i.e., the audit records of appraisals, with a
+//!   `schema_version` field.
+//!
+//! The `Migrate` trait parses an artifact of any supported schema version,
+//! upgrading it to the current one. Artifacts serialized before their
+//! schema was versioned, i.e., reports and verdicts without a
+//! `schema_version` field, are version 1.
+//!
+//! Event logs, boot manifests and signed files carry their own versions,
+//! checked when they're parsed.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::{Bundle, Verdict};
+//! use tdx_workload_attestation::schema::{Migrate, VersionedReport};
+//!
+//! let report = VersionedReport::migrate(&std::fs::read("report.json").unwrap()).unwrap();
+//! let bundle = Bundle::migrate(&std::fs::read("bundle.cbor").unwrap()).unwrap();
+//! let verdict = Verdict::migrate(&std::fs::read("verdict.json").unwrap()).unwrap();
+//! ```
+
+use crate::core::report::TdReportV15;
+use crate::error::{Error, Result};
+use crate::evidence::{BUNDLE_VERSION, Bundle, Verdict};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The schema version of artifacts serialized before their schema was
+/// versioned.
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// The current schema version of TD reports serialized into JSON.
+pub const REPORT_SCHEMA_VERSION: u32 = 2;
+
+/// The current schema version of verdicts.
+pub const VERDICT_SCHEMA_VERSION: u32 = 2;
+
+/// A serialized artifact that can be upgraded from older schema versions.
+pub trait Migrate: Sized {
+    /// The current schema version of the artifact.
+    const SCHEMA_VERSION: u32;
+
+    /// Parses a serialized artifact of any schema version up to
+    /// `SCHEMA_VERSION`, upgrading it to `SCHEMA_VERSION`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the artifact is malformed, or its
+    /// schema version is unsupported (e.g., written by a newer release).
+    fn migrate(bytes: &[u8]) -> Result<Self>;
+}
+
+/// A TD report, serialized into JSON with its schema version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedReport {
+    /// The schema version of the report.
+    pub schema_version: u32,
+    /// The report.
+    #[serde(flatten)]
+    pub report: TdReportV15,
+}
+
+impl VersionedReport {
+    /// Wraps `report` with the current schema version.
+    pub fn new(report: TdReportV15) -> Self {
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            report,
+        }
+    }
+
+    /// Serializes the report into JSON.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+impl Migrate for VersionedReport {
+    const SCHEMA_VERSION: u32 = REPORT_SCHEMA_VERSION;
+
+    /// Parses a JSON-serialized TD report, with or without a schema version.
+    fn migrate(bytes: &[u8]) -> Result<Self> {
+        let value = parse_json(bytes, "TD report")?;
+        check_version("TD report", json_version(&value)?, Self::SCHEMA_VERSION)?;
+
+        // version 2 only added the schema version
+        let report = serde_json::from_value(value)
+            .map_err(|e| Error::ParseError(format!("Invalid TD report: {}", e)))?;
+        Ok(Self::new(report))
+    }
+}
+
+impl Migrate for Bundle {
+    const SCHEMA_VERSION: u32 = BUNDLE_VERSION;
+
+    /// Parses a CBOR-encoded evidence bundle.
+    fn migrate(bytes: &[u8]) -> Result<Self> {
+        let bundle: Self = ciborium::from_reader(bytes)
+            .map_err(|e| Error::ParseError(format!("Invalid evidence bundle: {}", e)))?;
+        check_version("evidence bundle", bundle.version, Self::SCHEMA_VERSION)?;
+        Ok(bundle)
+    }
+}
+
+impl Migrate for Verdict {
+    const SCHEMA_VERSION: u32 = VERDICT_SCHEMA_VERSION;
+
+    /// Parses a JSON-serialized verdict, with or without a schema version.
+    fn migrate(bytes: &[u8]) -> Result<Self> {
+        let value = parse_json(bytes, "verdict")?;
+        check_version("verdict", json_version(&value)?, Self::SCHEMA_VERSION)?;
+
+        // version 2 only added the schema version
+        let verdict: Self = serde_json::from_value(value)
+            .map_err(|e| Error::ParseError(format!("Invalid verdict: {}", e)))?;
+        Ok(Self {
+            schema_version: Self::SCHEMA_VERSION,
+            ..verdict
+        })
+    }
+}
+
+/// Returns the schema version of artifacts deserialized without one.
+pub(crate) fn legacy_schema_version() -> u32 {
+    LEGACY_SCHEMA_VERSION
+}
+
+fn parse_json(bytes: &[u8], artifact: &str) -> Result<Value> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| Error::ParseError(format!("Invalid {}: {}", artifact, e)))
+}
+
+// Returns the `schema_version` of a JSON object, defaulting to the legacy
+// version
+fn json_version(value: &Value) -> Result<u32> {
+    match value.get("schema_version") {
+        None => Ok(LEGACY_SCHEMA_VERSION),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| Error::ParseError(format!("Invalid schema version {}", version))),
+    }
+}
+
+fn check_version(artifact: &str, version: u32, current: u32) -> Result<()> {
+    if !(LEGACY_SCHEMA_VERSION..=current).contains(&version) {
+        return Err(Error::ParseError(format!(
+            "Unsupported {} schema version {} (supported: {} to {})",
+            artifact, version, LEGACY_SCHEMA_VERSION, current
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::Check;
+
+    #[test]
+    fn test_migrate_report() -> Result<()> {
+        let report = TdReportV15::builder().with_mrtd(&[1; 48]).build();
+
+        // unversioned reports are upgraded
+        let legacy = serde_json::to_vec(&report).unwrap();
+        let migrated = VersionedReport::migrate(&legacy)?;
+        assert_eq!(migrated, VersionedReport::new(report));
+
+        let json = migrated.to_json()?;
+        assert!(json.contains("\"schema_version\":2"));
+        assert_eq!(VersionedReport::migrate(json.as_bytes())?, migrated);
+
+        let newer = json.replace("\"schema_version\":2", "\"schema_version\":3");
+        assert!(VersionedReport::migrate(newer.as_bytes()).is_err());
+        assert!(VersionedReport::migrate(b"{}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_bundle() -> Result<()> {
+        let mut bundle = Bundle::new(b"nonce", vec![1, 2, 3]);
+        assert_eq!(Bundle::migrate(&bundle.to_bytes()?)?, bundle);
+
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::migrate(&bundle.to_bytes()?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_verdict() -> Result<()> {
+        let verdict = Verdict {
+            checks: vec![Check {
+                name: "nonce".to_string(),
+                passed: true,
+                detail: None,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(verdict.schema_version, VERDICT_SCHEMA_VERSION);
+
+        let legacy = r#"{"checks":[{"name":"nonce","passed":true,"detail":null}]}"#;
+        assert_eq!(Verdict::migrate(legacy.as_bytes())?, verdict);
+        assert_eq!(
+            serde_json::from_str::<Verdict>(legacy)
+                .unwrap()
+                .schema_version,
+            LEGACY_SCHEMA_VERSION
+        );
+
+        let json = serde_json::to_vec(&verdict).unwrap();
+        assert_eq!(Verdict::migrate(&json)?, verdict);
+        Ok(())
+    }
+}
@@ -0,0 +1,468 @@
+//! # Attestation-Gated Secrets
+//!
+//! This module releases secrets (e.g., disk keys or API credentials) from a
+//! key broker service (KBS) to TDs whose evidence it accepts, such that the
+//! secret is only ever decrypted inside the attested TD.
+//!
+//! The TD generates an ephemeral P-256 key, and sends the KBS a
+//! `SecretRequest` with the key's public half and an evidence bundle whose
+//! nonce binds the public key, the requested key ID and the KBS's challenge
+//! (see `nonce_for_request()`). The KBS appraises the bundle against its
+//! verifier policy and only then wraps the secret to the TD's public key with
+//! `wrap_secret()`: an ECDH key agreement with an ephemeral key of its own,
+//! HKDF-SHA256, and AES-256-GCM. The TD unwraps the secret with its private
+//! key with `unwrap_secret()`, or performs the whole exchange with
+//! `request_secret()` (when compiled with the `kbs-client` feature).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Policy;
+//! use tdx_workload_attestation::secrets::{SecretRequest, wrap_secret};
+//!
+//! // On the KBS, for a request received after issuing `challenge`
+//! let challenge = [0u8; 32];
+//! let request = SecretRequest::from_bytes(&std::fs::read("request.cbor").unwrap()).unwrap();
+//! let policy = Policy::from_file("policy.toml")
+//!     .unwrap()
+//!     .with_collateral_dir("/etc/tdx-workload-attestation")
+//!     .unwrap();
+//!
+//! match wrap_secret(&request, &challenge, b"disk key", &policy) {
+//!     Ok(wrapped) => println!("Releasing {} bytes.", wrapped.to_bytes().unwrap().len()),
+//!     Err(e) => eprintln!("Secret request rejected: {}", e),
+//! }
+//! ```
+//!
+//! On the TD:
+//!
+//! ```ignore
+//! use tdx_workload_attestation::secrets::request_secret;
+//!
+//! let secret = request_secret("https://kbs.example.com", "disk-key").unwrap();
+//! ```
+//!
+//! # Notes
+//!
+//! `request_secret()` implements a minimal KBS protocol: a `GET` of
+//! `{kbs_url}/challenge` returns a raw challenge, and a `POST` of the
+//! CBOR-encoded `SecretRequest` to `{kbs_url}/secrets/{key_id}` returns the
+//! CBOR-encoded `WrappedSecret`. The KBS is responsible for the freshness of
+//! its challenges, which must each be accepted at most once.
+
+use crate::error::{Error, Result};
+use crate::evidence::{Bundle, Policy, Verdict};
+
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::md::Md;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, PKeyRef, Private, Public};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::symm::{Cipher, decrypt_aead, encrypt_aead};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+/// The media type of secret requests and wrapped secrets.
+pub const SECRET_CONTENT_TYPE: &str = "application/cbor";
+
+// The domain separator of the nonce of a secret request
+const SECRET_REQUEST_CONTEXT: &[u8] = b"tdx-workload-attestation/secret-request/v1";
+
+// The domain separator of the key wrapping a secret
+const SECRET_WRAPPING_CONTEXT: &[u8] = b"tdx-workload-attestation/secret-wrapping/v1";
+
+// The lengths of the AES-256-GCM key, IV and tag
+const WRAPPING_KEY_LEN: usize = 32;
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// A TD's request for a secret, with the evidence that its request key is
+/// held by an attested TD.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretRequest {
+    /// The ID of the requested secret.
+    pub key_id: String,
+    /// The DER-encoded public key (`SubjectPublicKeyInfo`) of the TD's
+    /// ephemeral P-256 request key.
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    /// The TD's evidence, whose nonce binds the request.
+    pub bundle: Bundle,
+}
+
+/// A secret wrapped to a TD's request key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedSecret {
+    /// The ID of the secret.
+    pub key_id: String,
+    /// The DER-encoded public key (`SubjectPublicKeyInfo`) of the KBS's
+    /// ephemeral P-256 key.
+    #[serde(with = "serde_bytes")]
+    pub ephemeral_key: Vec<u8>,
+    /// The AES-256-GCM IV.
+    #[serde(with = "serde_bytes")]
+    pub iv: Vec<u8>,
+    /// The encrypted secret.
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: Vec<u8>,
+    /// The AES-256-GCM authentication tag.
+    #[serde(with = "serde_bytes")]
+    pub tag: Vec<u8>,
+}
+
+/// Generates an ephemeral P-256 request key.
+pub fn generate_request_key() -> Result<PKey<Private>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    Ok(PKey::from_ec_key(EcKey::generate(&group)?)?)
+}
+
+/// Computes the nonce of a secret request's evidence, which binds the TD's
+/// DER-encoded request `public_key` and the requested `key_id` to the KBS's
+/// `challenge`.
+pub fn nonce_for_request(challenge: &[u8], key_id: &str, public_key: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(SECRET_REQUEST_CONTEXT);
+    for field in [challenge, key_id.as_bytes(), public_key] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.finalize().to_vec()
+}
+
+impl SecretRequest {
+    /// Creates a request for the secret `key_id`, collecting the current
+    /// TD's evidence over the KBS's `challenge` and the public half of
+    /// `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Bundle::collect()`.
+    #[cfg(feature = "tdx-linux")]
+    pub fn collect(key_id: &str, challenge: &[u8], key: &PKeyRef<Private>) -> Result<Self> {
+        let public_key = key.public_key_to_der()?;
+        let nonce = nonce_for_request(challenge, key_id, &public_key);
+        Ok(Self {
+            key_id: key_id.to_string(),
+            public_key,
+            bundle: Bundle::collect(&nonce, None)?,
+        })
+    }
+
+    /// Encodes the request in CBOR.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a CBOR-encoded request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the request is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        ciborium::from_reader(bytes)
+            .map_err(|e| Error::ParseError(format!("Invalid secret request: {}", e)))
+    }
+
+    /// Appraises the request's evidence against `verifier_policy`, requiring
+    /// its nonce to bind the request to `challenge` (see
+    /// `nonce_for_request()`) instead of the policy's nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Bundle::verify()`.
+    pub fn verify(&self, challenge: &[u8], verifier_policy: &Policy) -> Result<Verdict> {
+        let nonce = nonce_for_request(challenge, &self.key_id, &self.public_key);
+        self.bundle
+            .verify(&verifier_policy.clone().with_nonce(&nonce))
+    }
+}
+
+impl WrappedSecret {
+    /// Encodes the wrapped secret in CBOR.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a CBOR-encoded wrapped secret.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the wrapped secret is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        ciborium::from_reader(bytes)
+            .map_err(|e| Error::ParseError(format!("Invalid wrapped secret: {}", e)))
+    }
+}
+
+/// Wraps `secret` to the request key of a TD, if the TD's evidence passes
+/// `verifier_policy` and binds the request to the KBS's `challenge`.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if any of the evidence's checks fails.
+/// - `Error::ParseError` if the request key isn't a P-256 public key.
+/// - The errors of `SecretRequest::verify()`.
+pub fn wrap_secret(
+    request: &SecretRequest,
+    challenge: &[u8],
+    secret: &[u8],
+    verifier_policy: &Policy,
+) -> Result<WrappedSecret> {
+    let verdict = request.verify(challenge, verifier_policy)?;
+    if !verdict.passed() {
+        return Err(Error::VerificationError(format!(
+            "Secret request failed the checks: {}",
+            verdict.failed_checks().join(", ")
+        )));
+    }
+
+    let peer = parse_public_key(&request.public_key)?;
+    let ephemeral = generate_request_key()?;
+    let ephemeral_key = ephemeral.public_key_to_der()?;
+    let key = wrapping_key(&ephemeral, &peer, &ephemeral_key, &request.key_id)?;
+
+    let mut iv = vec![0u8; IV_LEN];
+    openssl::rand::rand_bytes(&mut iv)?;
+    let mut tag = vec![0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&iv),
+        request.key_id.as_bytes(),
+        secret,
+        &mut tag,
+    )?;
+
+    Ok(WrappedSecret {
+        key_id: request.key_id.clone(),
+        ephemeral_key,
+        iv,
+        ciphertext,
+        tag,
+    })
+}
+
+/// Unwraps a secret wrapped to the TD's request `key`.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if the secret wasn't wrapped to `key`, or
+///   has been tampered with.
+/// - `Error::ParseError` if the KBS's ephemeral key isn't a P-256 public
+///   key.
+pub fn unwrap_secret(wrapped: &WrappedSecret, key: &PKeyRef<Private>) -> Result<Vec<u8>> {
+    let peer = parse_public_key(&wrapped.ephemeral_key)?;
+    let wrapping_key = wrapping_key(key, &peer, &wrapped.ephemeral_key, &wrapped.key_id)?;
+
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &wrapping_key,
+        Some(&wrapped.iv),
+        wrapped.key_id.as_bytes(),
+        &wrapped.ciphertext,
+        &wrapped.tag,
+    )
+    .map_err(|_| Error::VerificationError("Failed to unwrap secret".to_string()))
+}
+
+/// Requests the secret `key_id` from the KBS at `kbs_url`, proving that the
+/// request comes from the current TD with its evidence, and unwraps it.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if `key_id` isn't a valid path segment.
+/// - `Error::NetworkError` if the KBS cannot be reached, or rejects the
+///   request.
+/// - `Error::VerificationError` if the KBS's response can't be unwrapped.
+/// - The errors of `SecretRequest::collect()`.
+#[cfg(feature = "kbs-client")]
+pub fn request_secret(kbs_url: &str, key_id: &str) -> Result<Vec<u8>> {
+    use crate::http::{http_client, send};
+    use crate::retry::RetryPolicy;
+
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if key_id.is_empty() || !key_id.chars().all(valid) {
+        return Err(Error::NotSupported(format!("Invalid key ID: {}", key_id)));
+    }
+
+    let kbs_url = kbs_url.trim_end_matches('/');
+    let retry_policy = RetryPolicy::default();
+    let client = http_client(&retry_policy)?;
+    let challenge = retry_policy.run(|| send(client.get(format!("{}/challenge", kbs_url))))?;
+
+    let key = generate_request_key()?;
+    let request = SecretRequest::collect(key_id, &challenge, &key)?.to_bytes()?;
+    let response = retry_policy.run(|| {
+        send(
+            client
+                .post(format!("{}/secrets/{}", kbs_url, key_id))
+                .header("Content-Type", SECRET_CONTENT_TYPE)
+                .header("Accept", SECRET_CONTENT_TYPE)
+                .body(request.clone()),
+        )
+    })?;
+
+    let wrapped = WrappedSecret::from_bytes(&response)?;
+    if wrapped.key_id != key_id {
+        return Err(Error::VerificationError(format!(
+            "Requested secret {}, but received {}",
+            key_id, wrapped.key_id
+        )));
+    }
+    unwrap_secret(&wrapped, &key)
+}
+
+/// Parses a DER-encoded P-256 public key.
+fn parse_public_key(der: &[u8]) -> Result<PKey<Public>> {
+    let key = PKey::public_key_from_der(der)
+        .map_err(|e| Error::ParseError(format!("Invalid public key: {}", e)))?;
+    let curve = key.ec_key().ok().and_then(|k| k.group().curve_name());
+    if curve != Some(Nid::X9_62_PRIME256V1) {
+        return Err(Error::ParseError("Expected a P-256 public key".to_string()));
+    }
+    Ok(key)
+}
+
+/// Derives the AES-256-GCM key wrapping the secret `key_id` from the ECDH
+/// shared secret of `key` and `peer`, salted with the KBS's ephemeral key.
+fn wrapping_key(
+    key: &PKeyRef<Private>,
+    peer: &PKeyRef<Public>,
+    ephemeral_key: &[u8],
+    key_id: &str,
+) -> Result<[u8; WRAPPING_KEY_LEN]> {
+    let mut deriver = Deriver::new(key)?;
+    deriver.set_peer(peer)?;
+    let shared = deriver.derive_to_vec()?;
+
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_key(&shared)?;
+    ctx.set_hkdf_salt(ephemeral_key)?;
+    ctx.add_hkdf_info(SECRET_WRAPPING_CONTEXT)?;
+    ctx.add_hkdf_info(key_id.as_bytes())?;
+
+    let mut wrapping_key = [0u8; WRAPPING_KEY_LEN];
+    ctx.derive(Some(&mut wrapping_key))?;
+    Ok(wrapping_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::report_data_for_nonce;
+    use crate::measure::ReferenceValues;
+    use crate::verification::quote::tests::TestSigner;
+
+    struct Fixture {
+        signer: TestSigner,
+        key: PKey<Private>,
+        request: SecretRequest,
+    }
+
+    fn fixture(challenge: &[u8]) -> Fixture {
+        let signer = TestSigner::new();
+        let key = generate_request_key().unwrap();
+        let public_key = key.public_key_to_der().unwrap();
+
+        let nonce = nonce_for_request(challenge, "disk-key", &public_key);
+        let quote = signer.sign_quote(QuoteParts {
+            report_data: report_data_for_nonce(&nonce),
+            ..Default::default()
+        });
+
+        Fixture {
+            signer,
+            key,
+            request: SecretRequest {
+                key_id: "disk-key".to_string(),
+                public_key,
+                bundle: Bundle::new(&nonce, quote),
+            },
+        }
+    }
+
+    fn policy(fixture: &Fixture) -> Policy {
+        Policy::new().with_trusted_root(&fixture.signer.root.to_der().unwrap())
+    }
+
+    #[test]
+    fn test_wrap_unwrap_secret() -> Result<()> {
+        let fixture = fixture(b"challenge");
+        let wrapped = wrap_secret(&fixture.request, b"challenge", b"secret", &policy(&fixture))?;
+        assert_eq!(wrapped.key_id, "disk-key");
+        assert_ne!(wrapped.ciphertext, b"secret");
+
+        let wrapped = WrappedSecret::from_bytes(&wrapped.to_bytes()?)?;
+        assert_eq!(unwrap_secret(&wrapped, &fixture.key)?, b"secret");
+
+        // only the request key unwraps the secret
+        let other = generate_request_key()?;
+        assert!(unwrap_secret(&wrapped, &other).is_err());
+
+        // the secret and its ID are authenticated
+        let mut tampered = wrapped.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert!(unwrap_secret(&tampered, &fixture.key).is_err());
+        let mut tampered = wrapped;
+        tampered.key_id = "other-key".to_string();
+        assert!(unwrap_secret(&tampered, &fixture.key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_secret_rejected() -> Result<()> {
+        let fixture = fixture(b"challenge");
+        let policy = policy(&fixture);
+
+        // replayed for another challenge
+        assert!(wrap_secret(&fixture.request, b"other", b"secret", &policy).is_err());
+
+        // the request key or key ID was substituted
+        let mut request = fixture.request.clone();
+        request.public_key = generate_request_key()?.public_key_to_der()?;
+        assert!(wrap_secret(&request, b"challenge", b"secret", &policy).is_err());
+        let mut request = fixture.request.clone();
+        request.key_id = "other-key".to_string();
+        assert!(wrap_secret(&request, b"challenge", b"secret", &policy).is_err());
+
+        // the TD's measurements don't match the verifier policy
+        let policy = policy.with_reference_values(ReferenceValues {
+            mrtd: Some([0xff; 48].into()),
+            ..Default::default()
+        });
+        let err = wrap_secret(&fixture.request, b"challenge", b"secret", &policy).unwrap_err();
+        assert!(err.to_string().contains("reference-values"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_serde() -> Result<()> {
+        let fixture = fixture(b"challenge");
+        let bytes = fixture.request.to_bytes()?;
+        assert_eq!(SecretRequest::from_bytes(&bytes)?, fixture.request);
+        assert!(SecretRequest::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_public_key() -> Result<()> {
+        let key = generate_request_key()?;
+        assert!(parse_public_key(&key.public_key_to_der()?).is_ok());
+
+        let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+        let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        assert!(parse_public_key(&key.public_key_to_der()?).is_err());
+        assert!(parse_public_key(b"not a key").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+//! # TD Owner and Configuration Binding Helpers
+//!
+//! This module provides utilities for computing the expected values of the
+//! host-provided, software-defined `MRCONFIGID`, `MROWNER` and
+//! `MROWNERCONFIG` measurement registers, and for verifying them against a
+//! `TDREPORT`.
+//!
+//! These registers are set by the host when the TD is created and are not
+//! measured by the TDX module, so their semantics are defined by the operator.
+//! Two encodings are commonly used:
+//! - the SHA-384 digest of some operator-supplied input, e.g., a cloud-init
+//!   config or a tenant's public key (see `sha384_binding()`), or
+//! - a raw value of up to 48 bytes, zero-padded (see `padded_binding()`).
+//!
+//! Binding these registers allows verifiers to check _who_ owns a TD and
+//! _how_ it was configured, beyond the pure launch measurement (`MRTD`).
+//!
+//! Bindings can also check the `SERVTD_HASH` of the service TDs the host
+//! bound to the TD (e.g., a migration TD, or a paravisor in a partitioned
+//! TD), which can access the TD's state. Unlike the other registers, it's
+//! computed by the TDX module, and is all-zero if no service TDs are bound.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//! use tdx_workload_attestation::tdx::binding::{OwnerBinding, sha384_binding};
+//!
+//! // The operator launched the TD with MROWNER = SHA-384(tenant public key)
+//! let tenant_key = b"tenant public key bytes";
+//! let binding = OwnerBinding::new().with_mrowner(sha384_binding(tenant_key));
+//!
+//! let report = LinuxTdxProvider::new().get_tdreport().unwrap();
+//! match binding.verify(report.report()) {
+//!     Ok(true) => println!("TD is bound to the expected owner."),
+//!     Ok(false) => println!("TD owner does not match."),
+//!     Err(e) => println!("Error verifying owner binding: {}", e),
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use crate::tdx::TDX_MR_REG_LEN;
+use crate::tdx::register::MeasurementRegister;
+use crate::tdx::report::TdReportV15;
+
+use sha2::{Digest, Sha384};
+
+/// Computes the SHA-384 digest of `data` for use as the expected value of a
+/// software-defined measurement register.
+pub fn sha384_binding(data: &[u8]) -> MeasurementRegister {
+    <[u8; TDX_MR_REG_LEN]>::from(Sha384::digest(data)).into()
+}
+
+/// Zero-pads `value` to the length of a measurement register, for use as the
+/// expected value of a software-defined measurement register.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if `value` is longer than 48 bytes.
+pub fn padded_binding(value: &[u8]) -> Result<MeasurementRegister> {
+    if value.len() > TDX_MR_REG_LEN {
+        return Err(Error::ParseError(format!(
+            "Binding value is {} bytes, but must be at most {} bytes",
+            value.len(),
+            TDX_MR_REG_LEN
+        )));
+    }
+
+    let mut padded = [0u8; TDX_MR_REG_LEN];
+    padded[..value.len()].copy_from_slice(value);
+    Ok(padded.into())
+}
+
+/// The expected values of a TD's software-defined measurement registers.
+///
+/// Registers left unset (`None`) are not checked during verification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OwnerBinding {
+    /// The expected `MRCONFIGID` value.
+    pub mrconfigid: Option<MeasurementRegister>,
+    /// The expected `MROWNER` value.
+    pub mrowner: Option<MeasurementRegister>,
+    /// The expected `MROWNERCONFIG` value.
+    pub mrownerconfig: Option<MeasurementRegister>,
+    /// The expected `SERVTD_HASH` value of the service TDs bound to the TD.
+    pub servtd_hash: Option<MeasurementRegister>,
+}
+
+impl OwnerBinding {
+    /// Creates a new `OwnerBinding` that doesn't check any registers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the expected `MRCONFIGID` value.
+    pub fn with_mrconfigid(mut self, value: MeasurementRegister) -> Self {
+        self.mrconfigid = Some(value);
+        self
+    }
+
+    /// Sets the expected `MROWNER` value.
+    pub fn with_mrowner(mut self, value: MeasurementRegister) -> Self {
+        self.mrowner = Some(value);
+        self
+    }
+
+    /// Sets the expected `MROWNERCONFIG` value.
+    pub fn with_mrownerconfig(mut self, value: MeasurementRegister) -> Self {
+        self.mrownerconfig = Some(value);
+        self
+    }
+
+    /// Sets the expected `SERVTD_HASH` value, i.e., the hash of the service
+    /// TDs (e.g., a migration TD) the host must have bound to the TD
+    /// (`NO_SERVTD_HASH` if none).
+    pub fn with_servtd_hash(mut self, value: MeasurementRegister) -> Self {
+        self.servtd_hash = Some(value);
+        self
+    }
+
+    /// Verifies the expected register values against those in `report`.
+    ///
+    /// Returns `Ok(true)` if all expected registers match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if no expected register values
+    /// were set, since an empty binding would trivially pass.
+    pub fn verify(&self, report: &TdReportV15) -> Result<bool> {
+        if self.mrconfigid.is_none()
+            && self.mrowner.is_none()
+            && self.mrownerconfig.is_none()
+            && self.servtd_hash.is_none()
+        {
+            return Err(Error::VerificationError(
+                "Owner binding has no expected register values".to_string(),
+            ));
+        }
+
+        let checks = [
+            (self.mrconfigid, report.get_mrconfigid()),
+            (self.mrowner, report.get_mrowner()),
+            (self.mrownerconfig, report.get_mrownerconfig()),
+            (self.servtd_hash, report.get_servtd_hash()),
+        ];
+
+        Ok(checks
+            .iter()
+            .all(|(expected, actual)| expected.is_none_or(|e| e == *actual)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::NO_SERVTD_HASH;
+
+    #[test]
+    fn test_padded_binding() -> Result<()> {
+        let padded = padded_binding(&[0xab; 32])?;
+        assert_eq!(padded[..32], [0xab; 32]);
+        assert_eq!(padded[32..], [0; 16]);
+
+        assert!(padded_binding(&[0; 49]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_binding() -> Result<()> {
+        // a freshly created report has all-zero registers
+        let report = TdReportV15::new();
+
+        let binding = OwnerBinding::new().with_mrowner(MeasurementRegister::ZERO);
+        assert!(binding.verify(&report)?);
+
+        let binding = binding.with_mrconfigid(sha384_binding(b"cloud-init config"));
+        assert!(!binding.verify(&report)?);
+
+        // no service TDs are bound to the report
+        let binding = OwnerBinding::new().with_servtd_hash(NO_SERVTD_HASH);
+        assert!(binding.verify(&report)?);
+        assert!(
+            !binding
+                .with_servtd_hash([1; TDX_MR_REG_LEN].into())
+                .verify(&report)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_empty_binding() {
+        let report = TdReportV15::new();
+        match OwnerBinding::new().verify(&report) {
+            Err(Error::VerificationError(_)) => {}
+            other => panic!("Expected VerificationError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,717 @@
+//! # Hyper-V Paravisor (HCL) Utilities
+//!
+//! This module retrieves TD reports and quotes in TDX guests running under
+//! Hyper-V with a paravisor (the Host Compatibility Layer, or HCL), such as
+//! Azure's TDX confidential VMs.
+//!
+//! In these guests, the paravisor owns the TDX guest interface, so
+//! `/dev/tdx_guest` isn't available. Instead, the paravisor exposes an HCL
+//! report through an NV index of the guest's vTPM (`HCL_REPORT_NV_INDEX`),
+//! which wraps the TD's `TDREPORT` along with runtime data (a JSON document
+//! holding the vTPM's attestation keys, the VM configuration, and 64 bytes
+//! of user data). The `report_data` of the `TDREPORT` is the digest of the
+//! runtime data, not the user data itself, so verifiers must check the
+//! runtime data against the report (see `HclReport::verify_runtime_data()`)
+//! before trusting its user data.
+//!
+//! The user data is set by writing it to another NV index
+//! (`HCL_REPORT_DATA_NV_INDEX`), after which the paravisor refreshes the HCL
+//! report. Quotes are generated from the `TDREPORT` by the Azure Instance
+//! Metadata Service (IMDS) (see `get_quote_imds()`).
+//!
+//! The vTPM is accessed through its resource manager device (`/dev/tpmrm0`)
+//! in Linux guests, and through the TPM Base Services (TBS) in Windows
+//! guests (see the `tdx::windows` module).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::tdx::hcl::{get_hcl_report, get_quote_imds};
+//!
+//! let report = get_hcl_report(&[0; 64]).unwrap();
+//! assert!(report.verify_runtime_data());
+//! println!("MRTD: {:?}", report.td_report().unwrap().get_mrtd());
+//!
+//! let quote = get_quote_imds(report.td_report_bytes()).unwrap();
+//! println!("Got a {}-byte quote", quote.len());
+//! ```
+//!
+//! # Notes
+//! - Accessing the vTPM requires read and write access to `/dev/tpmrm0`
+//!   (e.g., as root, or as a member of the `tss` group) in Linux guests,
+//!   and administrator privileges in Windows guests.
+//! - The paravisor doesn't expose the TD's RTMRs for extension, so runtime
+//!   measurements should be made into the vTPM's PCRs instead.
+
+use crate::core::ct;
+use crate::error::{Error, Result};
+#[cfg(unix)]
+use crate::platform::{CloudProvider, detect_cloud_provider};
+use crate::tdx::TDX_REPORT_DATA_LEN;
+use crate::tdx::report::{CollectedEvidence, TdReportV15};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+#[cfg(unix)]
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+
+/// The vTPM device through which the paravisor exposes the HCL report in
+/// Linux guests.
+pub const HCL_TPM_DEV_PATH: &str = "/dev/tpmrm0";
+
+/// The vTPM NV index holding the HCL report.
+pub const HCL_REPORT_NV_INDEX: u32 = 0x0140_0001;
+
+/// The vTPM NV index to which the user data of the HCL report is written.
+pub const HCL_REPORT_DATA_NV_INDEX: u32 = 0x0140_0002;
+
+/// The signature of HCL reports (`HCLA`).
+pub const HCL_REPORT_SIGNATURE: u32 = 0x414c_4348;
+
+/// The HCL report type of TDX guests.
+pub const HCL_REPORT_TYPE_TDX: u32 = 4;
+
+/// The address of the Azure Instance Metadata Service (IMDS).
+pub const AZURE_IMDS_ADDR: &str = "169.254.169.254:80";
+
+// The IMDS endpoint that converts TD reports into quotes
+const IMDS_TDQUOTE_PATH: &str = "/acc/tdquote";
+
+// The timeout of IMDS requests
+const IMDS_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The HCL report layout: a 32-byte header, the hardware report (sized for
+// the largest, SEV-SNP, report), and the IGVM request data, which is
+// followed by the runtime data
+const HCL_HEADER_LEN: usize = 32;
+const HCL_HW_REPORT_LEN: usize = 1184;
+const HCL_REQUEST_DATA_LEN: usize = 20;
+const TDREPORT_LEN: usize = 1024;
+
+// The hash types of the runtime data's digest in the report data
+const HCL_HASH_SHA256: u32 = 1;
+const HCL_HASH_SHA384: u32 = 2;
+const HCL_HASH_SHA512: u32 = 3;
+
+// TPM 2.0 command constants (see the TPM 2.0 specification, part 2)
+const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+const TPM_ST_SESSIONS: u16 = 0x8002;
+const TPM_CC_NV_DEFINE_SPACE: u32 = 0x0000_012a;
+const TPM_CC_NV_WRITE: u32 = 0x0000_0137;
+const TPM_CC_NV_READ: u32 = 0x0000_014e;
+const TPM_CC_NV_READ_PUBLIC: u32 = 0x0000_0169;
+const TPM_RH_OWNER: u32 = 0x4000_0001;
+const TPM_RS_PW: u32 = 0x4000_0009;
+const TPM_ALG_SHA256: u16 = 0x000b;
+const TPMA_NV_OWNERWRITE: u32 = 1 << 1;
+const TPMA_NV_OWNERREAD: u32 = 1 << 17;
+const TPM_HEADER_LEN: usize = 10;
+
+// The largest chunk read from an NV index in one command, which all TPMs
+// must support
+const TPM_NV_CHUNK_LEN: usize = 512;
+
+/// An HCL report, as exposed by a Hyper-V paravisor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HclReport {
+    /// The type of the hardware report (`HCL_REPORT_TYPE_TDX` for TDX).
+    pub report_type: u32,
+    /// The hash type of the runtime data's digest in the report data.
+    pub hash_type: u32,
+    /// The raw hardware report.
+    hw_report: Vec<u8>,
+    /// The runtime data, a JSON document.
+    pub runtime_data: Vec<u8>,
+}
+
+impl HclReport {
+    /// Parses a raw HCL report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the report is truncated or doesn't
+    /// have the HCL report signature.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let request_data = HCL_HEADER_LEN + HCL_HW_REPORT_LEN;
+        if bytes.len() < request_data + HCL_REQUEST_DATA_LEN {
+            return Err(Error::ParseError(format!(
+                "HCL report is {} bytes, but must be at least {} bytes",
+                bytes.len(),
+                request_data + HCL_REQUEST_DATA_LEN
+            )));
+        }
+
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        if u32_at(0) != HCL_REPORT_SIGNATURE {
+            return Err(Error::ParseError(
+                "HCL report has an invalid signature".to_string(),
+            ));
+        }
+
+        let runtime_data_len = u32_at(request_data + 16) as usize;
+        let runtime_data = bytes
+            .get(request_data + HCL_REQUEST_DATA_LEN..)
+            .and_then(|data| data.get(..runtime_data_len))
+            .ok_or_else(|| Error::ParseError("HCL runtime data is truncated".to_string()))?;
+
+        Ok(Self {
+            report_type: u32_at(request_data + 8),
+            hash_type: u32_at(request_data + 12),
+            hw_report: bytes[HCL_HEADER_LEN..request_data].to_vec(),
+            runtime_data: runtime_data.to_vec(),
+        })
+    }
+
+    /// Returns the raw `TDREPORT` of a TDX guest's HCL report.
+    pub fn td_report_bytes(&self) -> &[u8] {
+        &self.hw_report[..TDREPORT_LEN]
+    }
+
+    /// Returns the parsed `TDREPORT` of a TDX guest's HCL report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the HCL report isn't a TDX
+    /// report (e.g., it's an SEV-SNP report).
+    pub fn td_report(&self) -> Result<TdReportV15> {
+        if self.report_type != HCL_REPORT_TYPE_TDX {
+            return Err(Error::NotSupported(format!(
+                "HCL report has type {}, not TDX",
+                self.report_type
+            )));
+        }
+
+        let mut req = [0u8; TDX_REPORT_DATA_LEN + TDREPORT_LEN];
+        req[TDX_REPORT_DATA_LEN..].copy_from_slice(self.td_report_bytes());
+        Ok(TdReportV15::get_tdreport_from_bytes(&req)?)
+    }
+
+    /// Returns the parsed `TDREPORT` of a TDX guest's HCL report requested
+    /// with `user_data`, checking that its `REPORTDATA` is the digest of the
+    /// runtime data, and that the runtime data's user data echoes
+    /// `user_data`.
+    ///
+    /// # Errors
+    ///
+    /// Same as `td_report()`, or an `Error::ParseError` if the report data
+    /// isn't the digest of the runtime data, the user data isn't
+    /// `user_data`, or the report's info hashes don't match its sections.
+    pub fn collected_evidence(
+        &self,
+        user_data: &[u8; TDX_REPORT_DATA_LEN],
+    ) -> Result<CollectedEvidence> {
+        let report = self.td_report()?;
+        if !ct::eq(&self.user_data()?, user_data) {
+            return Err(Error::ParseError(
+                "HCL user data doesn't match the requested report data".to_string(),
+            ));
+        }
+
+        // the paravisor requests the report over the digest of the runtime
+        // data, zero-padded
+        let digest = self
+            .runtime_data_digest()
+            .ok_or_else(|| Error::ParseError("Unsupported HCL report or hash type".to_string()))?;
+        let mut report_data = [0; TDX_REPORT_DATA_LEN];
+        report_data[..digest.len()].copy_from_slice(&digest);
+        Ok(CollectedEvidence::new(report, &report_data)?)
+    }
+
+    /// Returns whether the report data of a TDX guest's `TDREPORT` binds the
+    /// runtime data.
+    pub fn verify_runtime_data(&self) -> bool {
+        let Some(digest) = self.runtime_data_digest() else {
+            return false;
+        };
+
+        // the report data is at offset 0x80 of the TDREPORT's REPORTMACSTRUCT
+        let report_data = &self.hw_report[128..128 + TDX_REPORT_DATA_LEN];
+        ct::eq(&report_data[..digest.len()], &digest)
+    }
+
+    // Returns the digest of the runtime data of a TDX guest's HCL report,
+    // with the report's hash type
+    fn runtime_data_digest(&self) -> Option<Vec<u8>> {
+        if self.report_type != HCL_REPORT_TYPE_TDX {
+            return None;
+        }
+        match self.hash_type {
+            HCL_HASH_SHA256 => Some(Sha256::digest(&self.runtime_data).to_vec()),
+            HCL_HASH_SHA384 => Some(Sha384::digest(&self.runtime_data).to_vec()),
+            HCL_HASH_SHA512 => Some(Sha512::digest(&self.runtime_data).to_vec()),
+            _ => None,
+        }
+    }
+
+    /// Returns the user data in the runtime data, as written to
+    /// `HCL_REPORT_DATA_NV_INDEX`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the runtime data isn't valid JSON,
+    /// or has no valid user data.
+    pub fn user_data(&self) -> Result<Vec<u8>> {
+        let runtime: serde_json::Value = serde_json::from_slice(&self.runtime_data)
+            .map_err(|e| Error::ParseError(format!("Invalid HCL runtime data: {}", e)))?;
+        let user_data = runtime
+            .get("user-data")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| Error::ParseError("HCL runtime data has no user data".to_string()))?;
+
+        hex::decode(user_data)
+            .map_err(|e| Error::ParseError(format!("Invalid HCL user data: {}", e)))
+    }
+}
+
+/// Checks whether a Hyper-V paravisor exposes a TDX HCL report through the
+/// vTPM.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if the vTPM device node is a symlink.
+pub fn is_available() -> Result<bool> {
+    #[cfg(unix)]
+    {
+        let path = Path::new(HCL_TPM_DEV_PATH);
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
+            )));
+        }
+        if !path.exists() || detect_cloud_provider() != Some(CloudProvider::Azure) {
+            return Ok(false);
+        }
+    }
+
+    // only the fixed-size part of the report is needed to check its type
+    let fixed_len = HCL_HEADER_LEN + HCL_HW_REPORT_LEN + HCL_REQUEST_DATA_LEN;
+    let header = match Tpm::open().and_then(|mut tpm| tpm.nv_read(HCL_REPORT_NV_INDEX, fixed_len)) {
+        Ok(header) if header.len() == fixed_len => header,
+        _ => return Ok(false),
+    };
+
+    let report_type = fixed_len - HCL_REQUEST_DATA_LEN + 8;
+    Ok(header[..4] == HCL_REPORT_SIGNATURE.to_le_bytes()
+        && header[report_type..report_type + 4] == HCL_REPORT_TYPE_TDX.to_le_bytes())
+}
+
+/// Retrieves the HCL report, with `user_data` bound into its runtime data.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if the vTPM isn't available.
+/// - `Error::QuoteError` if the user data can't be written or the report
+///   can't be read.
+/// - `Error::ParseError` if the report is malformed.
+pub fn get_hcl_report(user_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<HclReport> {
+    let mut tpm = Tpm::open()?;
+
+    if tpm.nv_size(HCL_REPORT_DATA_NV_INDEX).is_err() {
+        tpm.nv_define(HCL_REPORT_DATA_NV_INDEX, TDX_REPORT_DATA_LEN)?;
+    }
+    tpm.nv_write(HCL_REPORT_DATA_NV_INDEX, user_data)?;
+
+    let size = tpm.nv_size(HCL_REPORT_NV_INDEX)?;
+    HclReport::parse(&tpm.nv_read(HCL_REPORT_NV_INDEX, size)?)
+}
+
+/// Retrieves a signed TD quote for the raw `td_report` from the Azure IMDS.
+///
+/// # Errors
+///
+/// - `Error::NetworkError` if the IMDS can't be reached or returns an error.
+/// - `Error::ParseError` if the IMDS response is malformed.
+pub fn get_quote_imds(td_report: &[u8]) -> Result<Vec<u8>> {
+    let body = serde_json::json!({ "report": URL_SAFE_NO_PAD.encode(td_report) }).to_string();
+    let request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nMetadata: true\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        IMDS_TDQUOTE_PATH,
+        AZURE_IMDS_ADDR,
+        body.len(),
+        body
+    );
+
+    let network_error =
+        |e: std::io::Error| Error::NetworkError(format!("IMDS request failed: {}", e));
+    let addr = AZURE_IMDS_ADDR
+        .parse()
+        .map_err(|e| Error::NetworkError(format!("Invalid IMDS address: {}", e)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, IMDS_TIMEOUT).map_err(network_error)?;
+    stream
+        .set_read_timeout(Some(IMDS_TIMEOUT))
+        .map_err(network_error)?;
+    stream
+        .write_all(request.as_bytes())
+        .map_err(network_error)?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response).map_err(network_error)?;
+
+    parse_imds_quote_response(&response)
+}
+
+/// Parses the HTTP response of the IMDS quote endpoint.
+fn parse_imds_quote_response(response: &[u8]) -> Result<Vec<u8>> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| Error::ParseError("Malformed IMDS response".to_string()))?;
+    let status = String::from_utf8_lossy(&response[..split]);
+    let status = status.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(Error::NetworkError(format!(
+            "IMDS quote request failed: {}",
+            status
+        )));
+    }
+
+    let body: serde_json::Value = serde_json::from_slice(&response[split + 4..])
+        .map_err(|e| Error::ParseError(format!("Invalid IMDS quote response: {}", e)))?;
+    let quote = body
+        .get("quote")
+        .and_then(|q| q.as_str())
+        .ok_or_else(|| Error::ParseError("IMDS response has no quote".to_string()))?;
+
+    URL_SAFE_NO_PAD
+        .decode(quote.trim_end_matches('='))
+        .map_err(|e| Error::ParseError(format!("Invalid IMDS quote: {}", e)))
+}
+
+/// A minimal TPM 2.0 client for the NV commands needed to access the HCL
+/// report, with owner authorization (an empty password, as in Azure's
+/// vTPMs).
+struct Tpm {
+    #[cfg(unix)]
+    device: File,
+    #[cfg(windows)]
+    device: super::windows::TbsContext,
+}
+
+impl Tpm {
+    #[cfg(unix)]
+    fn open() -> Result<Self> {
+        let device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(HCL_TPM_DEV_PATH)
+            .map_err(|e| {
+                Error::NotSupported(format!("Failed to open {}: {}", HCL_TPM_DEV_PATH, e))
+            })?;
+        Ok(Self { device })
+    }
+
+    #[cfg(windows)]
+    fn open() -> Result<Self> {
+        Ok(Self {
+            device: super::windows::TbsContext::open()?,
+        })
+    }
+
+    /// Sends a command, returning the response parameters (after the
+    /// response header, and the parameter size of commands with sessions).
+    #[cfg(unix)]
+    fn transmit(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let tpm_error =
+            |e: std::io::Error| Error::QuoteError(format!("vTPM command failed: {}", e));
+        self.device.write_all(command).map_err(tpm_error)?;
+
+        let mut response = vec![0u8; 4096];
+        let len = self.device.read(&mut response).map_err(tpm_error)?;
+        response.truncate(len);
+
+        parse_response(command, &response)
+    }
+
+    /// Sends a command, returning the response parameters (after the
+    /// response header, and the parameter size of commands with sessions).
+    #[cfg(windows)]
+    fn transmit(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let response = self.device.submit(command)?;
+        parse_response(command, &response)
+    }
+
+    /// Returns the size of an NV index.
+    fn nv_size(&mut self, index: u32) -> Result<usize> {
+        let mut params = vec![];
+        params.extend(index.to_be_bytes());
+        let response =
+            self.transmit(&command(TPM_ST_NO_SESSIONS, TPM_CC_NV_READ_PUBLIC, &params))?;
+
+        // TPM2B_NV_PUBLIC: size, index, name algorithm, attributes,
+        // TPM2B auth policy, data size
+        let policy_len = be_u16(&response, 12)? as usize;
+        Ok(be_u16(&response, 14 + policy_len)? as usize)
+    }
+
+    /// Reads `size` bytes from an NV index.
+    fn nv_read(&mut self, index: u32, size: usize) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let chunk = TPM_NV_CHUNK_LEN.min(size - data.len());
+            let mut params = vec![];
+            params.extend(TPM_RH_OWNER.to_be_bytes());
+            params.extend(index.to_be_bytes());
+            params.extend(password_session());
+            params.extend((chunk as u16).to_be_bytes());
+            params.extend((data.len() as u16).to_be_bytes());
+
+            let response = self.transmit(&command(TPM_ST_SESSIONS, TPM_CC_NV_READ, &params))?;
+            let len = be_u16(&response, 0)? as usize;
+            let bytes = response
+                .get(2..2 + len)
+                .ok_or_else(|| Error::QuoteError("Truncated vTPM NV read".to_string()))?;
+            if bytes.is_empty() {
+                break;
+            }
+            data.extend(bytes);
+        }
+        Ok(data)
+    }
+
+    /// Writes `data` to an NV index at offset 0.
+    fn nv_write(&mut self, index: u32, data: &[u8]) -> Result<()> {
+        let mut params = vec![];
+        params.extend(TPM_RH_OWNER.to_be_bytes());
+        params.extend(index.to_be_bytes());
+        params.extend(password_session());
+        params.extend((data.len() as u16).to_be_bytes());
+        params.extend(data);
+        params.extend(0u16.to_be_bytes());
+
+        self.transmit(&command(TPM_ST_SESSIONS, TPM_CC_NV_WRITE, &params))?;
+        Ok(())
+    }
+
+    /// Defines an owner-readable and -writable NV index of `size` bytes.
+    fn nv_define(&mut self, index: u32, size: usize) -> Result<()> {
+        let mut public = vec![];
+        public.extend(index.to_be_bytes());
+        public.extend(TPM_ALG_SHA256.to_be_bytes());
+        public.extend((TPMA_NV_OWNERWRITE | TPMA_NV_OWNERREAD).to_be_bytes());
+        public.extend(0u16.to_be_bytes());
+        public.extend((size as u16).to_be_bytes());
+
+        let mut params = vec![];
+        params.extend(TPM_RH_OWNER.to_be_bytes());
+        params.extend(password_session());
+        params.extend(0u16.to_be_bytes());
+        params.extend((public.len() as u16).to_be_bytes());
+        params.extend(public);
+
+        self.transmit(&command(TPM_ST_SESSIONS, TPM_CC_NV_DEFINE_SPACE, &params))?;
+        Ok(())
+    }
+}
+
+/// Assembles a TPM command from its handles, authorization area and
+/// parameters.
+fn command(tag: u16, code: u32, params: &[u8]) -> Vec<u8> {
+    let mut command = vec![];
+    command.extend(tag.to_be_bytes());
+    command.extend(((TPM_HEADER_LEN + params.len()) as u32).to_be_bytes());
+    command.extend(code.to_be_bytes());
+    command.extend(params);
+    command
+}
+
+/// Returns the authorization area of a command authorized with an empty
+/// password.
+fn password_session() -> Vec<u8> {
+    let mut session = vec![];
+    session.extend(TPM_RS_PW.to_be_bytes());
+    session.extend(0u16.to_be_bytes()); // nonce
+    session.push(0); // session attributes
+    session.extend(0u16.to_be_bytes()); // password
+
+    let mut area = vec![];
+    area.extend((session.len() as u32).to_be_bytes());
+    area.extend(session);
+    area
+}
+
+/// Checks a TPM response, and returns its parameters.
+fn parse_response(command: &[u8], response: &[u8]) -> Result<Vec<u8>> {
+    if response.len() < TPM_HEADER_LEN {
+        return Err(Error::QuoteError("Truncated vTPM response".to_string()));
+    }
+    let code = u32::from_be_bytes([response[6], response[7], response[8], response[9]]);
+    if code != 0 {
+        return Err(Error::QuoteError(format!(
+            "vTPM command failed with response code {:#x}",
+            code
+        )));
+    }
+
+    // responses to commands with sessions start with the parameter size
+    let params = &response[TPM_HEADER_LEN..];
+    if command[..2] == TPM_ST_SESSIONS.to_be_bytes() {
+        Ok(params.get(4..).unwrap_or_default().to_vec())
+    } else {
+        Ok(params.to_vec())
+    }
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| Error::QuoteError("Truncated vTPM response".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_hcl_report(report_type: u32, runtime_data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(HCL_REPORT_SIGNATURE.to_le_bytes());
+        bytes.resize(HCL_HEADER_LEN, 0);
+
+        let mut hw_report = vec![0u8; HCL_HW_REPORT_LEN];
+        hw_report[128..160].copy_from_slice(&Sha256::digest(runtime_data));
+        bytes.extend(hw_report);
+
+        for value in [
+            (HCL_REQUEST_DATA_LEN + runtime_data.len()) as u32,
+            1,
+            report_type,
+            HCL_HASH_SHA256,
+            runtime_data.len() as u32,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(runtime_data);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_hcl_report() -> Result<()> {
+        let runtime_data = br#"{"keys":[],"user-data":"0A0B"}"#;
+        let report = HclReport::parse(&make_hcl_report(HCL_REPORT_TYPE_TDX, runtime_data))?;
+
+        assert_eq!(report.report_type, HCL_REPORT_TYPE_TDX);
+        assert_eq!(report.runtime_data, runtime_data);
+        assert_eq!(report.td_report_bytes().len(), TDREPORT_LEN);
+        assert!(report.verify_runtime_data());
+        assert_eq!(report.user_data()?, vec![0x0a, 0x0b]);
+        report.td_report()?;
+
+        // tampered runtime data
+        let mut tampered = report.clone();
+        tampered.runtime_data[2] ^= 1;
+        assert!(!tampered.verify_runtime_data());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collected_evidence() -> Result<()> {
+        let user_data = [0x0a; TDX_REPORT_DATA_LEN];
+        let runtime_data = format!(r#"{{"keys":[],"user-data":"{}"}}"#, hex::encode(user_data));
+        let mut report_data = [0; TDX_REPORT_DATA_LEN];
+        report_data[..32].copy_from_slice(&Sha256::digest(&runtime_data));
+        let td_report = TdReportV15::builder()
+            .with_report_data(&report_data)
+            .with_computed_info_hashes()
+            .build();
+
+        let mut bytes = make_hcl_report(HCL_REPORT_TYPE_TDX, runtime_data.as_bytes());
+        bytes[HCL_HEADER_LEN..HCL_HEADER_LEN + TDREPORT_LEN].copy_from_slice(&td_report.to_bytes());
+        let report = HclReport::parse(&bytes)?;
+        assert_eq!(
+            report.collected_evidence(&user_data)?.into_report(),
+            td_report
+        );
+
+        // the report of another request
+        assert!(matches!(
+            report.collected_evidence(&[0x0b; TDX_REPORT_DATA_LEN]),
+            Err(Error::ParseError(_))
+        ));
+
+        // a TDREPORT whose report data isn't the zero-padded digest of the
+        // runtime data
+        report_data[32..].fill(0x0c);
+        let other = TdReportV15::builder()
+            .with_report_data(&report_data)
+            .with_computed_info_hashes()
+            .build();
+        bytes[HCL_HEADER_LEN..HCL_HEADER_LEN + TDREPORT_LEN].copy_from_slice(&other.to_bytes());
+        assert!(matches!(
+            HclReport::parse(&bytes)?.collected_evidence(&user_data),
+            Err(Error::ParseError(_))
+        ));
+
+        // a corrupted report
+        let report = HclReport::parse(&make_hcl_report(
+            HCL_REPORT_TYPE_TDX,
+            runtime_data.as_bytes(),
+        ))?;
+        assert!(report.collected_evidence(&user_data).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_hcl_report_errors() {
+        let bytes = make_hcl_report(2, b"{}");
+        assert!(HclReport::parse(&bytes).unwrap().td_report().is_err());
+
+        assert!(HclReport::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(HclReport::parse(&bytes[..100]).is_err());
+
+        let mut bytes = bytes;
+        bytes[0] = 0;
+        assert!(HclReport::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_tpm_command() -> Result<()> {
+        let cmd = command(TPM_ST_NO_SESSIONS, TPM_CC_NV_READ_PUBLIC, &[1, 2, 3, 4]);
+        assert_eq!(cmd, [0x80, 0x01, 0, 0, 0, 14, 0, 0, 0x01, 0x69, 1, 2, 3, 4]);
+        assert_eq!(password_session().len(), 13);
+
+        // a response with sessions, its parameter size and a TPM2B
+        let response = [
+            0x80, 0x02, 0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, 4, 0, 2, 0xab, 0xcd, 0, 0,
+        ];
+        let cmd = command(TPM_ST_SESSIONS, TPM_CC_NV_READ, &[]);
+        assert_eq!(parse_response(&cmd, &response)?[..4], [0, 2, 0xab, 0xcd]);
+
+        let error = [0x80, 0x01, 0, 0, 0, 10, 0, 0, 0x01, 0x8b];
+        assert!(parse_response(&cmd, &error).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_imds_quote_response() -> Result<()> {
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{{\"quote\":\"{}\"}}",
+            URL_SAFE_NO_PAD.encode(b"quote bytes")
+        );
+        assert_eq!(
+            parse_imds_quote_response(response.as_bytes())?,
+            b"quote bytes"
+        );
+
+        let error = b"HTTP/1.1 400 Bad Request\r\n\r\n{}";
+        assert!(matches!(
+            parse_imds_quote_response(error),
+            Err(Error::NetworkError(_))
+        ));
+        assert!(parse_imds_quote_response(b"garbage").is_err());
+        Ok(())
+    }
+}
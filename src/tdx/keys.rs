@@ -0,0 +1,401 @@
+//! # Measurement-Bound Key Derivation
+//!
+//! This module derives keys bound to a TD's measurements, so that workloads
+//! can encrypt local data (e.g., a state file or a disk key) that only a TD
+//! with identical measurements can decrypt.
+//!
+//! A `KeyPolicy` selects the measurement registers the key is bound to
+//! (`MRTD` by default, plus any of `RTMR[0..3]`, `MRCONFIGID`, `MROWNER` and
+//! `MROWNERCONFIG`). The selected registers are hashed into a measurement
+//! binding, which the TD sends to a key broker service (KBS) in a TD quote,
+//! along with a challenge from the broker. The broker appraises the quote,
+//! checks that its `report_data` binds the registers in the quote (see
+//! `verify_key_request()`), and releases a root secret. The TD then derives
+//! the key from the secret and the measurement binding with HKDF-SHA384, so
+//! TDs with different measurements derive different keys, even if they're
+//! released the same secret.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::error::Result;
+//! use tdx_workload_attestation::tdx::LinuxTdxProvider;
+//! use tdx_workload_attestation::tdx::keys::{KeyBroker, KeyPolicy, MeasurementRegister};
+//!
+//! struct MyBroker;
+//!
+//! impl KeyBroker for MyBroker {
+//!     fn challenge(&self) -> Result<Vec<u8>> {
+//!         // e.g., GET a nonce from the KBS
+//!         Ok(vec![0; 32])
+//!     }
+//!
+//!     fn release_secret(&self, quote: &[u8], binding: &[u8; 48]) -> Result<Vec<u8>> {
+//!         // e.g., POST the quote and binding to the KBS
+//!         unimplemented!()
+//!     }
+//! }
+//!
+//! // Bind the key to the TD's launch measurement and workload measurements
+//! let policy = KeyPolicy::new().with_register(MeasurementRegister::Rtmr3);
+//!
+//! let mut key = [0u8; 32];
+//! LinuxTdxProvider::new()
+//!     .request_key(&MyBroker, &policy, b"state-encryption", &mut key)
+//!     .expect("Failed to derive key");
+//! ```
+//!
+//! # Notes
+//!
+//! TDX 1.5 doesn't provide TDs with a key-derivation `TDCALL` (unlike SGX's
+//! `EGETKEY`), so keys can't be sealed to the CPU, and a key broker is needed
+//! to release the root secret. The broker is responsible for the freshness of
+//! its challenges, for appraising the quote's signature and measurements, and
+//! for protecting the released secret in transit (e.g., over a TLS session).
+//!
+//! Keys bound to `RTMR` registers must be requested after the registers have
+//! been fully extended, and change whenever the measured components change.
+
+use crate::core::ct;
+use crate::core::register::Digest384;
+use crate::error::{Error, Result};
+use crate::evidence::quote::TdQuoteBody;
+use crate::tdx::report::TdReportV15;
+use crate::tdx::{LinuxTdxProvider, TDX_MR_REG_LEN, TDX_REPORT_DATA_LEN};
+
+use hkdf::Hkdf;
+use sha2::{Digest, Sha384, Sha512};
+
+// The domain separator of the measurement binding
+const KEY_BINDING_CONTEXT: &[u8] = b"tdx-workload-attestation/key-binding/v1";
+
+// The domain separator of the report data of a key request
+const KEY_REQUEST_CONTEXT: &[u8] = b"tdx-workload-attestation/key-request/v1";
+
+/// The maximum length of a derived key, as limited by HKDF-SHA384.
+pub const MAX_KEY_LEN: usize = 255 * 48;
+
+/// A measurement register that a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeasurementRegister {
+    /// The build-time measurement of the TD.
+    Mrtd,
+    /// The runtime measurement register `RTMR[0]` (firmware configuration).
+    Rtmr0,
+    /// The runtime measurement register `RTMR[1]` (bootloader and kernel).
+    Rtmr1,
+    /// The runtime measurement register `RTMR[2]` (kernel command line and
+    /// initrd).
+    Rtmr2,
+    /// The runtime measurement register `RTMR[3]` (workload).
+    Rtmr3,
+    /// The software-defined ID for non-owner-defined configuration.
+    Mrconfigid,
+    /// The software-defined ID for the TD's owner.
+    Mrowner,
+    /// The software-defined ID for owner-defined configuration.
+    Mrownerconfig,
+}
+
+impl MeasurementRegister {
+    // The registers in the order they're hashed into the binding
+    const ALL: [MeasurementRegister; 8] = [
+        MeasurementRegister::Mrtd,
+        MeasurementRegister::Rtmr0,
+        MeasurementRegister::Rtmr1,
+        MeasurementRegister::Rtmr2,
+        MeasurementRegister::Rtmr3,
+        MeasurementRegister::Mrconfigid,
+        MeasurementRegister::Mrowner,
+        MeasurementRegister::Mrownerconfig,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+/// The measurement registers a key is bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyPolicy {
+    registers: u8,
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyPolicy {
+    /// Creates a new `KeyPolicy` that binds keys to the `MRTD` only.
+    pub fn new() -> Self {
+        Self {
+            registers: MeasurementRegister::Mrtd.bit(),
+        }
+    }
+
+    /// Additionally binds keys to `register`.
+    pub fn with_register(mut self, register: MeasurementRegister) -> Self {
+        self.registers |= register.bit();
+        self
+    }
+
+    /// Returns whether keys are bound to `register`.
+    pub fn binds(&self, register: MeasurementRegister) -> bool {
+        self.registers & register.bit() != 0
+    }
+
+    /// Computes the measurement binding of the registers in `report`.
+    pub fn binding_for_report(&self, report: &TdReportV15) -> [u8; TDX_MR_REG_LEN] {
+        let rtmrs = report.get_rtmrs();
+        self.binding(&[
+            report.get_mrtd(),
+            rtmrs[0],
+            rtmrs[1],
+            rtmrs[2],
+            rtmrs[3],
+            report.get_mrconfigid(),
+            report.get_mrowner(),
+            report.get_mrownerconfig(),
+        ])
+    }
+
+    /// Computes the measurement binding of the registers in a TD quote's
+    /// `body`, e.g., by a key broker.
+    pub fn binding_for_quote(&self, body: &TdQuoteBody) -> [u8; TDX_MR_REG_LEN] {
+        self.binding(&[
+            body.mrtd,
+            body.rtmrs[0],
+            body.rtmrs[1],
+            body.rtmrs[2],
+            body.rtmrs[3],
+            body.mrconfigid,
+            body.mrowner,
+            body.mrownerconfig,
+        ])
+    }
+
+    // Hashes the selected registers along with the selection itself, so
+    // that policies binding different registers never share a binding.
+    fn binding(&self, values: &[Digest384; 8]) -> [u8; TDX_MR_REG_LEN] {
+        let mut hasher = Sha384::new();
+        hasher.update(KEY_BINDING_CONTEXT);
+        hasher.update([self.registers]);
+        for (register, value) in MeasurementRegister::ALL.iter().zip(values) {
+            if self.binds(*register) {
+                hasher.update(value);
+            }
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// A key broker service (KBS) that releases root secrets to TDs whose
+/// quotes it accepts.
+pub trait KeyBroker {
+    /// Returns a fresh challenge to bind into the key request's quote.
+    fn challenge(&self) -> Result<Vec<u8>>;
+
+    /// Appraises the key request's `quote`, which binds the TD's measurement
+    /// `binding` and the latest challenge (see `verify_key_request()`), and
+    /// returns the root secret to derive keys from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quote is rejected or the secret can't be
+    /// released.
+    fn release_secret(&self, quote: &[u8], binding: &[u8; TDX_MR_REG_LEN]) -> Result<Vec<u8>>;
+}
+
+/// Computes the `report_data` of a key request's quote, which binds the TD's
+/// measurement `binding` to the broker's `challenge`.
+pub fn report_data_for_key_request(
+    binding: &[u8; TDX_MR_REG_LEN],
+    challenge: &[u8],
+) -> [u8; TDX_REPORT_DATA_LEN] {
+    let mut hasher = Sha512::new();
+    hasher.update(KEY_REQUEST_CONTEXT);
+    hasher.update(binding);
+    hasher.update(challenge);
+    hasher.finalize().into()
+}
+
+/// Verifies that a key request's quote `body` binds the measurement binding
+/// of its own registers under `policy`, and the broker's `challenge`.
+///
+/// Returns the measurement binding if the request is consistent, or `None`
+/// otherwise. Brokers must still verify the quote's signature and appraise
+/// its measurements before releasing a secret.
+pub fn verify_key_request(
+    body: &TdQuoteBody,
+    policy: &KeyPolicy,
+    challenge: &[u8],
+) -> Option<[u8; TDX_MR_REG_LEN]> {
+    let binding = policy.binding_for_quote(body);
+    ct::eq(
+        &body.report_data,
+        &report_data_for_key_request(&binding, challenge),
+    )
+    .then_some(binding)
+}
+
+/// Derives a key of `key.len()` bytes from the broker's root `secret` and
+/// the TD's measurement `binding` with HKDF-SHA384, where `label` separates
+/// keys for different purposes.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if the key is empty or longer than
+/// `MAX_KEY_LEN` bytes.
+pub fn derive_key(
+    secret: &[u8],
+    binding: &[u8; TDX_MR_REG_LEN],
+    label: &[u8],
+    key: &mut [u8],
+) -> Result<()> {
+    if key.is_empty() {
+        return Err(Error::NotSupported(
+            "Derived keys can't be empty".to_string(),
+        ));
+    }
+
+    Hkdf::<Sha384>::new(Some(binding), secret)
+        .expand(label, key)
+        .map_err(|_| {
+            Error::NotSupported(format!(
+                "Derived keys can be at most {} bytes, but {} were requested",
+                MAX_KEY_LEN,
+                key.len()
+            ))
+        })
+}
+
+impl LinuxTdxProvider {
+    /// Requests a key bound to the TD's current measurements under `policy`
+    /// from `broker`, filling `key` with the derived key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` or `Error::QuoteError` if the TD
+    /// report or quote cannot be retrieved, any error returned by `broker`,
+    /// or an `Error::NotSupported` if the key length is invalid.
+    pub fn request_key(
+        &self,
+        broker: &dyn KeyBroker,
+        policy: &KeyPolicy,
+        label: &[u8],
+        key: &mut [u8],
+    ) -> Result<()> {
+        let binding = policy.binding_for_report(self.get_tdreport()?.report());
+        let challenge = broker.challenge()?;
+        let quote = self.get_quote(&report_data_for_key_request(&binding, &challenge))?;
+        let secret = broker.release_secret(&quote, &binding)?;
+
+        derive_key(&secret, &binding, label, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::quote::Quote;
+
+    fn quote_body(parts: QuoteParts) -> Result<TdQuoteBody> {
+        Ok(Quote::from_bytes(&parts.assemble(&[6; 64], &[7; 64]))?.body)
+    }
+
+    fn rtmr3_quote_body(rtmr3: u8, report_data: [u8; TDX_REPORT_DATA_LEN]) -> Result<TdQuoteBody> {
+        let mut parts = QuoteParts::default();
+        parts.rtmrs[3] = [rtmr3; TDX_MR_REG_LEN];
+        parts.report_data = report_data;
+        quote_body(parts)
+    }
+
+    #[test]
+    fn test_binding_depends_on_policy() -> Result<()> {
+        let body = rtmr3_quote_body(1, [0; TDX_REPORT_DATA_LEN])?;
+        let changed = rtmr3_quote_body(2, [0; TDX_REPORT_DATA_LEN])?;
+
+        // RTMR3 is only bound if selected
+        let policy = KeyPolicy::new();
+        assert_eq!(
+            policy.binding_for_quote(&body),
+            policy.binding_for_quote(&changed)
+        );
+
+        let policy = policy.with_register(MeasurementRegister::Rtmr3);
+        assert!(policy.binds(MeasurementRegister::Rtmr3));
+        assert_ne!(
+            policy.binding_for_quote(&body),
+            policy.binding_for_quote(&changed)
+        );
+        assert_ne!(
+            policy.binding_for_quote(&body),
+            KeyPolicy::new().binding_for_quote(&body)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_binding_for_report_matches_quote() -> Result<()> {
+        // a freshly created report has all-zero registers
+        let report = TdReportV15::new();
+        let parts = QuoteParts {
+            mrtd: [0; TDX_MR_REG_LEN],
+            ..Default::default()
+        };
+        let body = quote_body(parts)?;
+
+        let policy = KeyPolicy::new()
+            .with_register(MeasurementRegister::Rtmr0)
+            .with_register(MeasurementRegister::Mrowner);
+        assert_eq!(
+            policy.binding_for_report(&report),
+            policy.binding_for_quote(&body)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_key_request() -> Result<()> {
+        let policy = KeyPolicy::new().with_register(MeasurementRegister::Rtmr3);
+        let binding = policy.binding_for_quote(&rtmr3_quote_body(1, [0; TDX_REPORT_DATA_LEN])?);
+        let report_data = report_data_for_key_request(&binding, b"challenge");
+
+        let body = rtmr3_quote_body(1, report_data)?;
+        assert_eq!(
+            verify_key_request(&body, &policy, b"challenge"),
+            Some(binding)
+        );
+        assert_eq!(verify_key_request(&body, &policy, b"replayed"), None);
+
+        // the quote's registers don't match the binding in its report data
+        let body = rtmr3_quote_body(2, report_data)?;
+        assert_eq!(verify_key_request(&body, &policy, b"challenge"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_key() -> Result<()> {
+        let binding = [1; TDX_MR_REG_LEN];
+
+        let mut key = [0u8; 32];
+        derive_key(b"secret", &binding, b"label", &mut key)?;
+
+        let mut other = [0u8; 32];
+        derive_key(b"secret", &binding, b"label", &mut other)?;
+        assert_eq!(key, other);
+
+        derive_key(b"secret", &[2; TDX_MR_REG_LEN], b"label", &mut other)?;
+        assert_ne!(key, other);
+
+        derive_key(b"secret", &binding, b"other label", &mut other)?;
+        assert_ne!(key, other);
+
+        assert!(derive_key(b"secret", &binding, b"label", &mut []).is_err());
+        assert!(derive_key(b"secret", &binding, b"label", &mut vec![0; MAX_KEY_LEN + 1]).is_err());
+        Ok(())
+    }
+}
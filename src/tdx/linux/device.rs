@@ -4,7 +4,24 @@
 //!
 //! This module provides functionality for interacting with a KVM-based
 //! Intel TDX device. Its main purpose is to provide APIs for retrieving
-//! the quote/signed attestation report from the TDX device.
+//! the quote/signed attestation report from the TDX device, and for
+//! extending its runtime measurement registers (RTMRs).
+//!
+//! ## Local Report Verification
+//!
+//! A `TDREPORT` is MACed with a key only the CPU knows, so it can only be
+//! verified on the platform that generated it, by the TDX module's
+//! `TDG.MR.VERIFYREPORT` call. This lets a TD (e.g., a service TD) verify
+//! the reports of other TDs on the same platform without a quote.
+//!
+//! The mainline kernel doesn't expose the call: `verify_report_raw()` uses
+//! the `TDX_CMD_VERIFY_REPORT` ioctl of kernels that carry the TDX guest
+//! driver's report verification patches, and returns an
+//! `Error::NotSupported` on other kernels. The call only verifies the
+//! `REPORTMACSTRUCT`, which covers the report's `TEE_TCB_INFO` and `TDINFO`
+//! sections through their hashes: callers must also check the hashes (see
+//! `TdReportV15::check_info_hashes()`, and `verify_tdreport_v15_kvm_at()`,
+//! which does both).
 //!
 //! The module currently only supports TDX 1.5 KVM devices located at
 //! `"/dev/tdx_guest"`.
@@ -38,19 +55,41 @@
 //!
 //! The module uses custom `Error` types, including:
 //!   - `Error::NotSupported`: Returned when the device node is a symlink or not available.
-//!   - `Error::QuoteError`: Returned when a report operation fails or the device cannot be accessed.
+//!   - `Error::PermissionDenied`: Returned when the device's file permissions
+//!     don't allow the process to open it.
+//!   - `Error::LsmDenied`: Returned when the file permissions allow it, but a
+//!     Linux Security Module (e.g., SELinux or AppArmor) denies the access.
+//!   - `Error::QuoteError`: Returned when a report operation fails otherwise.
+//!
+//! The errors carry a remediation hint. `probe()` reports why the device is
+//! (un)usable without failing, e.g., for diagnostics (see
+//! `tdx-attest platform probe`).
 //!
 //! ## Notes
 //! - The module is currently designed to work specifically with Intel TDX 1.5 devices.
 //! - Ensure that the expected guest OS is based on an enlightened Linux kernel.
 
+use crate::core::report::REPORT_MAC_STRUCT_LEN;
 use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use vmm_sys_util::{errno, ioctl};
 
-// The path to the KVM device node for TDX 1.5
-const TDX15_DEV_PATH: &str = "/dev/tdx_guest";
+/// The path to the KVM device node for TDX 1.5
+pub const TDX15_DEV_PATH: &str = "/dev/tdx_guest";
+
+/// The path to the TDX guest device's measurement register attributes in
+/// sysfs (available since Linux 6.16)
+pub const TDX15_MEASUREMENTS_PATH: &str = "/sys/devices/virtual/misc/tdx_guest/measurements";
+
+/// The number of runtime measurement registers (RTMRs)
+pub const TDX_NUM_RTMRS: u8 = 4;
+
+/// The file listing the active Linux Security Modules
+pub const LSM_PATH: &str = "/sys/kernel/security/lsm";
 
 // The device operators for tdx v1.5
 // Reference: TDX_CMD_GET_REPORT0
@@ -61,6 +100,17 @@ const TDX15_DEV_PATH: &str = "/dev/tdx_guest";
 // 0x40c4 in little-endian.
 const TDX_CMD_GET_REPORT0_V1_5: u64 = u64::from_be_bytes([0, 0, 0, 0, 0xc4, 0x40, b'T', 1]);
 
+// Reference: TDX_CMD_VERIFY_REPORT of the TDX guest driver's report
+// verification patches (not in mainline kernels)
+// Layout: dir(2bit) size(14bit)         type(8bit) nr(8bit)
+//         11        00,0001,0000,1000   b'T'       0000,0010
+// The request is the 256-byte REPORTMACSTRUCT followed by the u64 error
+// code of TDG.MR.VERIFYREPORT, which the driver fills in.
+const TDX_CMD_VERIFY_REPORT_V1_5: u64 = u64::from_be_bytes([0, 0, 0, 0, 0xc1, 0x08, b'T', 2]);
+
+// The length of a TDX_CMD_VERIFY_REPORT request
+const TDX_VERIFY_REPORT_REQ_LEN: usize = REPORT_MAC_STRUCT_LEN + 8;
+
 /// This struct represents a TDX 1.5 KVM device node and provides an interface
 /// for performing operations to retrieve attestation reports.
 #[derive(Debug)]
@@ -70,43 +120,119 @@ pub struct TdxDeviceKvmV15 {
     device_path: String,
 }
 
+impl Default for TdxDeviceKvmV15 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TdxDeviceKvmV15 {
     /// Creates a new instance of `TdxDeviceKvmV15`, and ensures that the TDX
     /// device node is available before creating the instance.
     pub fn new() -> TdxDeviceKvmV15 {
-        match Self::is_available() {
-            Ok(true) => TdxDeviceKvmV15 {
-                device_path: TDX15_DEV_PATH.to_string(),
+        Self::with_path(TDX15_DEV_PATH)
+    }
+
+    /// Creates a new instance of `TdxDeviceKvmV15` for the TDX device node at
+    /// `device_path`, and ensures that it is available before creating the
+    /// instance.
+    pub fn with_path(device_path: &str) -> TdxDeviceKvmV15 {
+        match is_available_at(Path::new(device_path)) {
+            // return an empty device path, if TDX isn't available or the
+            // device node is a symlink
+            Ok(false) | Err(Error::NotSupported(_)) => TdxDeviceKvmV15 {
+                device_path: "".to_string(),
             },
-            // return an empty device path, if TDX isn't available or there was an error
+            // keep the path if the device may exist, so that opening it
+            // reports why it cannot be used
             _ => TdxDeviceKvmV15 {
-                device_path: "".to_string(),
+                device_path: device_path.to_string(),
             },
         }
     }
 
     /// Checks whether the Intel TDX 1.5 KVM device node is available and valid
     /// for use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the device node is a symlink, or
+    /// an `Error::PermissionDenied` or `Error::LsmDenied` if it cannot be
+    /// looked up. Use `probe()` to also check that it can be opened.
     pub fn is_available() -> Result<bool> {
-        let path = Path::new(TDX15_DEV_PATH);
-        let available = fs::exists(path).map_err(|e| Error::NotSupported(format!("{}", e)))?;
-
-        if available {
-            // throw an error if this is a symlink
-            if path.is_symlink() {
-                return Err(Error::NotSupported(format!(
-                    "Path {} is a symlink",
-                    path.display()
-                )));
+        is_available_at(Path::new(TDX15_DEV_PATH))
+    }
+
+    /// Retrieves the raw TD report (Quote/Signed Attestation Report) from the
+    /// TDX device by using an ioctl system call to interact with the device.
+    pub fn get_tdreport_raw(&self, req: &[u8; 1088]) -> Result<[u8; 1088]> {
+        let tdx_dev = self.open()?;
+        get_tdreport_ioctl(&tdx_dev, req)
+    }
+
+    /// Retrieves the raw TD reports for a batch of requests, opening the TDX
+    /// device only once.
+    ///
+    /// # Errors
+    ///
+    /// Same as `get_tdreport_raw()`, failing the whole batch if any request
+    /// fails.
+    pub fn get_tdreports_raw(&self, reqs: &[[u8; 1088]]) -> Result<Vec<[u8; 1088]>> {
+        if reqs.is_empty() {
+            return Ok(vec![]);
+        }
+        let tdx_dev = self.open()?;
+        reqs.iter()
+            .map(|req| get_tdreport_ioctl(&tdx_dev, req))
+            .collect()
+    }
+
+    /// Verifies the MAC of a raw `REPORTMACSTRUCT` (e.g., of a `TDREPORT`
+    /// received from another TD on the same platform) with the TDX module's
+    /// `TDG.MR.VERIFYREPORT` call, via the `TDX_CMD_VERIFY_REPORT` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the device or the kernel doesn't
+    /// support report verification, an `Error::VerificationError` with the
+    /// TDX module's error code if the MAC is invalid (e.g., the report was
+    /// generated on another platform, or tampered with), or an
+    /// `Error::QuoteError` if the ioctl fails otherwise.
+    pub fn verify_report_raw(&self, report_mac_struct: &[u8; REPORT_MAC_STRUCT_LEN]) -> Result<()> {
+        let tdx_dev = self.open()?;
+
+        let mut req = [0u8; TDX_VERIFY_REPORT_REQ_LEN];
+        req[..REPORT_MAC_STRUCT_LEN].copy_from_slice(report_mac_struct);
+
+        let ret =
+            unsafe { ioctl::ioctl_with_mut_ptr(&tdx_dev, TDX_CMD_VERIFY_REPORT_V1_5, &mut req) };
+        if ret < 0 {
+            let err = errno::Error::last();
+            // ENOTTY: the driver doesn't know the ioctl
+            if err.errno() == libc::ENOTTY {
+                return Err(Error::NotSupported(
+                    "TDX report verification is not supported by this kernel".to_string(),
+                ));
             }
+            return Err(Error::QuoteError(format!(
+                "IOCTL failed with errno {}: {}",
+                err.errno(),
+                err
+            )));
         }
 
-        Ok(available)
+        let err_code = u64::from_le_bytes(req[REPORT_MAC_STRUCT_LEN..].try_into().unwrap());
+        if err_code != 0 {
+            return Err(Error::VerificationError(format!(
+                "TDG.MR.VERIFYREPORT failed with error code {:#018x}",
+                err_code
+            )));
+        }
+        Ok(())
     }
 
-    /// Retrieves the raw TD report (Quote/Signed Attestation Report) from the
-    /// TDX device by using an ioctl system call to interact with the device.
-    pub fn get_tdreport_raw(&self, &req: &[u8; 1088]) -> Result<[u8; 1088]> {
+    /// Opens the TDX device, which must be opened in RW mode.
+    fn open(&self) -> Result<fs::File> {
         // Before we do anything, check if the device_path is empty.
         // If it is, TDX isn't supported, throw an error
         if self.device_path.is_empty() {
@@ -115,42 +241,283 @@ impl TdxDeviceKvmV15 {
             ));
         }
 
-        // 1. Get device file descriptor: must open in RW mode
-        let tdx_dev = fs::File::options()
+        fs::File::options()
             .read(true)
             .write(true)
             .open(&self.device_path)
-            .map_err(|e| {
-                Error::QuoteError(format!(
-                    "Failed to open TDX device at {}: {}",
-                    self.device_path, e
-                ))
-            })?;
+            .map_err(|e| access_error(&self.device_path, &e))
+    }
 
-        let mut resp = req;
+    /// Extends the runtime measurement register `RTMR[index]` with a
+    /// SHA-384 digest, by writing the digest to the register's sysfs
+    /// attribute.
+    pub fn extend_rtmr(&self, index: u8, digest: &[u8; 48]) -> Result<()> {
+        if self.device_path.is_empty() {
+            return Err(Error::NotSupported(
+                "TDX 1.5 KVM device is not supported".to_string(),
+            ));
+        }
 
-        // 3. Call the ioctl
-        let ret =
-            unsafe { ioctl::ioctl_with_mut_ptr(&tdx_dev, TDX_CMD_GET_REPORT0_V1_5, &mut resp) };
-        if ret < 0 {
-            // as seen in virtee/tdx
-            let err = errno::Error::last();
-            return Err(Error::QuoteError(format!(
-                "IOCTL failed with errno {}: {}",
-                err.errno(),
-                err
+        if index >= TDX_NUM_RTMRS {
+            return Err(Error::NotSupported(format!("RTMR{} does not exist", index)));
+        }
+
+        let path = Path::new(TDX15_MEASUREMENTS_PATH).join(format!("rtmr{}:sha384", index));
+        if !fs::exists(&path).map_err(|e| Error::NotSupported(format!("{}", e)))? {
+            return Err(Error::NotSupported(format!(
+                "RTMR extension is not supported by this kernel ({} not found)",
+                path.display()
+            )));
+        }
+
+        fs::write(&path, digest)
+            .map_err(|e| Error::QuoteError(format!("Failed to extend RTMR{}: {}", index, e)))
+    }
+}
+
+/// Retrieves a raw TD report from the opened TDX device with the
+/// `TDX_CMD_GET_REPORT0` ioctl.
+fn get_tdreport_ioctl(tdx_dev: &fs::File, &req: &[u8; 1088]) -> Result<[u8; 1088]> {
+    let mut resp = req;
+
+    let ret = unsafe { ioctl::ioctl_with_mut_ptr(tdx_dev, TDX_CMD_GET_REPORT0_V1_5, &mut resp) };
+    if ret < 0 {
+        // as seen in virtee/tdx
+        let err = errno::Error::last();
+        // the device was opened, so only an LSM (e.g., SELinux's ioctl
+        // filtering) denies the ioctl
+        if matches!(err.errno(), libc::EACCES | libc::EPERM) {
+            return Err(Error::LsmDenied(format!(
+                "The TDX_CMD_GET_REPORT0 ioctl was denied: {}. {}",
+                err,
+                remediation(DeviceAccess::LsmDenied, TDX15_DEV_PATH, &active_lsms())
+                    .unwrap_or_default()
+            )));
+        }
+        return Err(Error::QuoteError(format!(
+            "IOCTL failed with errno {}: {}",
+            err.errno(),
+            err
+        )));
+    }
+
+    Ok(resp)
+}
+
+/// Checks whether the TDX device node at `path` is available and valid for
+/// use.
+fn is_available_at(path: &Path) -> Result<bool> {
+    let available = fs::exists(path).map_err(|e| access_error(&path.to_string_lossy(), &e))?;
+
+    if available {
+        // throw an error if this is a symlink
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
             )));
         }
-        drop(tdx_dev);
+    }
+
+    Ok(available)
+}
+
+/// Why the TDX guest device is, or isn't, usable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceAccess {
+    /// The device can be opened.
+    Available,
+    /// The device node doesn't exist (e.g., outside of a TDX guest, or
+    /// without the kernel's TDX guest driver).
+    Absent,
+    /// The device node is a symlink, which isn't trusted.
+    Symlink,
+    /// The device's file permissions don't allow the process to open it.
+    PermissionDenied,
+    /// The device's file permissions allow the process to open it, but a
+    /// Linux Security Module (e.g., SELinux or AppArmor) denies it.
+    LsmDenied,
+    /// The device cannot be opened for another reason.
+    Failed,
+}
+
+/// A report of whether, and why not, the TDX guest device is usable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeReport {
+    /// The path of the probed device.
+    pub device_path: String,
+    /// Whether the device is usable, or why not.
+    pub access: DeviceAccess,
+    /// The active Linux Security Modules (e.g., `selinux` or `apparmor`), if
+    /// they can be read.
+    pub lsms: Vec<String>,
+    /// The error the device couldn't be used with.
+    pub error: Option<String>,
+    /// How to make the device usable.
+    pub remediation: Option<String>,
+}
+
+impl ProbeReport {
+    /// Returns whether the device is usable.
+    pub fn is_available(&self) -> bool {
+        self.access == DeviceAccess::Available
+    }
+}
+
+/// Probes the TDX guest device at `TDX15_DEV_PATH`.
+pub fn probe() -> ProbeReport {
+    probe_at(TDX15_DEV_PATH)
+}
+
+/// Probes the TDX guest device at `device_path`, by opening it as the
+/// reports are retrieved (in RW mode).
+pub fn probe_at(device_path: &str) -> ProbeReport {
+    let (access, error) = match fs::symlink_metadata(device_path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => (
+            DeviceAccess::Symlink,
+            Some(format!("Path {} is a symlink", device_path)),
+        ),
+        Ok(_) => match fs::File::options().read(true).write(true).open(device_path) {
+            Ok(_) => (DeviceAccess::Available, None),
+            Err(e) => (classify(device_path, &e), Some(e.to_string())),
+        },
+        Err(e) => (classify(device_path, &e), Some(e.to_string())),
+    };
+
+    let lsms = active_lsms();
+    ProbeReport {
+        device_path: device_path.to_string(),
+        access,
+        remediation: remediation(access, device_path, &lsms),
+        lsms,
+        error,
+    }
+}
+
+/// Classifies an error accessing the device at `path`.
+fn classify(path: &str, e: &io::Error) -> DeviceAccess {
+    match e.kind() {
+        io::ErrorKind::NotFound => DeviceAccess::Absent,
+        // LSMs deny access with the same errors as the file permissions
+        io::ErrorKind::PermissionDenied if dac_allows(path) => DeviceAccess::LsmDenied,
+        io::ErrorKind::PermissionDenied => DeviceAccess::PermissionDenied,
+        _ => DeviceAccess::Failed,
+    }
+}
+
+/// Converts an error accessing the device at `path` into the matching
+/// variant, with a remediation hint.
+fn access_error(path: &str, e: &io::Error) -> Error {
+    let access = classify(path, e);
+    let msg = format!(
+        "Failed to open TDX device at {}: {}. {}",
+        path,
+        e,
+        remediation(access, path, &active_lsms()).unwrap_or_default()
+    );
+    match access {
+        DeviceAccess::Absent => Error::NotSupported(msg),
+        DeviceAccess::PermissionDenied => Error::PermissionDenied(msg),
+        DeviceAccess::LsmDenied => Error::LsmDenied(msg),
+        _ => Error::QuoteError(format!("Failed to open TDX device at {}: {}", path, e)),
+    }
+}
+
+/// Returns how to make the device at `path` usable, given why it isn't.
+fn remediation(access: DeviceAccess, path: &str, lsms: &[String]) -> Option<String> {
+    let hint = match access {
+        DeviceAccess::Available | DeviceAccess::Failed => return None,
+        DeviceAccess::Absent => {
+            "Run in a TDX guest whose kernel has the TDX guest driver (tdx_guest) loaded"
+                .to_string()
+        }
+        DeviceAccess::Symlink => format!("Replace {} with the device node itself", path),
+        DeviceAccess::PermissionDenied => format!(
+            "Run as root, grant the process's user or group read-write access to {} (e.g., \
+             with a udev rule), or request quotes from an attestation agent (see \
+             `tdx-attest serve`)",
+            path
+        ),
+        DeviceAccess::LsmDenied => {
+            let lsms: Vec<_> = lsms
+                .iter()
+                .filter(|lsm| ["selinux", "apparmor", "smack", "tomoyo"].contains(&lsm.as_str()))
+                .map(String::as_str)
+                .collect();
+            format!(
+                "Allow the process to open {} in the {} policy (its denials are logged in \
+                 the audit log), or request quotes from an attestation agent (see \
+                 `tdx-attest serve`)",
+                path,
+                if lsms.is_empty() {
+                    "LSM".to_string()
+                } else {
+                    lsms.join("/")
+                }
+            )
+        }
+    };
+    Some(hint)
+}
+
+/// Returns whether the file permissions of `path` allow the process to open
+/// it in RW mode, i.e., whether a denial comes from an LSM.
+fn dac_allows(path: &str) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    // root bypasses the file permissions
+    let uid = unsafe { libc::geteuid() };
+    if uid == 0 {
+        return true;
+    }
+
+    let mode = metadata.mode();
+    let rw = if metadata.uid() == uid {
+        mode & 0o600
+    } else if in_group(metadata.gid()) {
+        (mode & 0o060) << 3
+    } else {
+        (mode & 0o006) << 6
+    };
+    rw == 0o600
+}
 
-        Ok(resp)
+/// Returns whether the process is in the group `gid` (as its effective or a
+/// supplementary group).
+fn in_group(gid: u32) -> bool {
+    if unsafe { libc::getegid() } == gid {
+        return true;
+    }
+    let len = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if len <= 0 {
+        return false;
     }
+    let mut groups = vec![0; len as usize];
+    let len = unsafe { libc::getgroups(len, groups.as_mut_ptr()) };
+    len > 0 && groups[..len as usize].contains(&gid)
+}
+
+/// Returns the active Linux Security Modules, from `LSM_PATH`.
+fn active_lsms() -> Vec<String> {
+    fs::read_to_string(LSM_PATH)
+        .map(|lsms| {
+            lsms.trim()
+                .split(',')
+                .filter(|lsm| !lsm.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tdx::report::TdReportV15;
     use crate::tdx::test_utils::handle_expected_tdx_error;
+    use std::os::unix::fs::PermissionsExt;
 
     #[test]
     fn test_is_available() -> Result<()> {
@@ -168,6 +535,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_probe() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-probe-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir)?;
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let report = probe_at(&path("absent"));
+        assert_eq!(report.access, DeviceAccess::Absent);
+        assert!(!report.is_available());
+        assert!(report.remediation.is_some());
+
+        fs::write(path("device"), b"")?;
+        assert_eq!(probe_at(&path("device")).access, DeviceAccess::Available);
+        std::os::unix::fs::symlink(path("device"), path("link"))?;
+        assert_eq!(probe_at(&path("link")).access, DeviceAccess::Symlink);
+
+        // the file permissions deny access (unless the test runs as root)
+        fs::set_permissions(path("device"), fs::Permissions::from_mode(0o400))?;
+        let report = probe_at(&path("device"));
+        if unsafe { libc::geteuid() } != 0 {
+            assert_eq!(report.access, DeviceAccess::PermissionDenied);
+            let e = TdxDeviceKvmV15::with_path(&path("device"))
+                .get_tdreport_raw(&[0; 1088])
+                .unwrap_err();
+            assert!(matches!(e, Error::PermissionDenied(_)));
+        }
+
+        // the report is serializable, e.g., for `tdx-attest platform probe`
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"access\""));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            classify(
+                "/nonexistent/tdx_guest",
+                &io::Error::from(io::ErrorKind::NotFound)
+            ),
+            DeviceAccess::Absent
+        );
+        // without readable file permissions, a denial isn't attributed to an LSM
+        assert_eq!(
+            classify("/nonexistent/tdx_guest", &denied),
+            DeviceAccess::PermissionDenied
+        );
+        assert!(matches!(
+            access_error("/nonexistent/tdx_guest", &denied),
+            Error::PermissionDenied(msg) if msg.contains("attestation agent")
+        ));
+        assert!(
+            remediation(
+                DeviceAccess::LsmDenied,
+                TDX15_DEV_PATH,
+                &["selinux".to_string()]
+            )
+            .unwrap()
+            .contains("selinux")
+        );
+    }
+
+    #[test]
+    fn test_extend_rtmr_invalid_index() {
+        let device = TdxDeviceKvmV15::new();
+        assert!(device.extend_rtmr(TDX_NUM_RTMRS, &[0; 48]).is_err());
+    }
+
     #[test]
     fn test_get_tdreport_raw() -> Result<()> {
         let device = TdxDeviceKvmV15::new();
@@ -182,4 +620,44 @@ mod tests {
             Err(e) => handle_expected_tdx_error(e),
         }
     }
+
+    #[test]
+    fn test_verify_report_raw() -> Result<()> {
+        let device = TdxDeviceKvmV15::new();
+        let request = TdReportV15::create_request(&[0; 64]);
+
+        let report = match device.get_tdreport_raw(&request) {
+            Ok(raw) => TdReportV15::get_tdreport_from_bytes(&raw)?,
+            Err(e) => return handle_expected_tdx_error(e),
+        };
+        match device.verify_report_raw(&report.report_mac_struct_bytes()) {
+            Ok(()) => {
+                // a tampered REPORTMACSTRUCT isn't verified
+                let mut tampered = report.report_mac_struct_bytes();
+                tampered[0x80] ^= 0xff;
+                assert!(device.verify_report_raw(&tampered).is_err());
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
+
+    #[test]
+    fn test_get_tdreports_raw() -> Result<()> {
+        let device = TdxDeviceKvmV15::new();
+        assert!(device.get_tdreports_raw(&[])?.is_empty());
+
+        let requests = [
+            TdReportV15::create_request(&[0; 64]),
+            TdReportV15::create_request(&[1; 64]),
+        ];
+        match device.get_tdreports_raw(&requests) {
+            Ok(reports) => {
+                assert_eq!(reports.len(), 2);
+                assert_ne!(reports[0], reports[1]);
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
 }
@@ -19,11 +19,11 @@
 //! // Example report data (dummy data)
 //! let report_data: [u8; TDX_REPORT_DATA_LEN] = [0; TDX_REPORT_DATA_LEN];
 //!
-//! // Retrieve the TDREPORT
-//! let td_report = get_tdreport_v15_kvm(&report_data).unwrap();
+//! // Retrieve the TDREPORT, checked to echo the report data
+//! let evidence = get_tdreport_v15_kvm(&report_data).unwrap();
 //!
 //! // Access fields from the parsed TDREPORT
-//! println!("MRTD: {:?}", td_report.get_mrtd());
+//! println!("MRTD: {:?}", evidence.report().get_mrtd());
 //! ```
 //!
 //! # Notes
@@ -35,10 +35,13 @@
 //! - The `get_tdreport_v15_kvm` function will panic if the device interaction fails (e.g., due to an invalid ioctl operation).
 
 pub mod device;
+pub use super::hcl;
+pub mod qgs;
+pub mod tsm;
 
 use crate::error::Result;
-use crate::tdx::TDX_REPORT_DATA_LEN;
-use crate::tdx::report::TdReportV15;
+use crate::tdx::report::{CollectedEvidence, TdReportV15};
+use crate::tdx::{TDX_MR_REG_LEN, TDX_REPORT_DATA_LEN};
 
 /// Checks whether the Intel TDX 1.5 KVM device node is available and valid for use.
 pub fn is_v15_kvm_device() -> Result<bool> {
@@ -48,9 +51,25 @@ pub fn is_v15_kvm_device() -> Result<bool> {
 }
 
 /// Retrieves the `TDREPORT` from the Intel TDX 1.5 KVM device and parses it into a `TdReportV15` structure.
-pub fn get_tdreport_v15_kvm(report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TdReportV15> {
+pub fn get_tdreport_v15_kvm(report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<CollectedEvidence> {
+    get_tdreport_v15_kvm_at(device::TDX15_DEV_PATH, report_data)
+}
+
+/// Retrieves the `TDREPORT` from the Intel TDX 1.5 KVM device at
+/// `device_path` and parses it into a `TdReportV15` structure, checked to
+/// have been retrieved over `report_data`.
+///
+/// # Errors
+///
+/// Returns the errors of `TdxDeviceKvmV15::get_tdreport_raw()`, or an
+/// `Error::ParseError` if the report doesn't hold the invariants of
+/// `CollectedEvidence` (e.g., its `REPORTDATA` isn't `report_data`).
+pub fn get_tdreport_v15_kvm_at(
+    device_path: &str,
+    report_data: &[u8; TDX_REPORT_DATA_LEN],
+) -> Result<CollectedEvidence> {
     // Initialize the KVM device for TDX 1.5
-    let tdx_device = device::TdxDeviceKvmV15::new();
+    let tdx_device = device::TdxDeviceKvmV15::with_path(device_path);
 
     // Create the request
     let req = TdReportV15::create_request(report_data);
@@ -58,8 +77,54 @@ pub fn get_tdreport_v15_kvm(report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<T
     // Get the TDREPORT from the hardware device
     let raw_report = tdx_device.get_tdreport_raw(&req)?;
 
-    // Extract the report from the raw report
-    TdReportV15::get_tdreport_from_bytes(&raw_report)
+    // Extract the report from the raw report, checking that the device
+    // echoed the report data
+    let report = TdReportV15::get_tdreport_from_bytes(&raw_report)?;
+    Ok(CollectedEvidence::new(report, report_data)?)
+}
+
+/// Retrieves the `TDREPORT`s over a batch of `report_data` values from the
+/// Intel TDX 1.5 KVM device at `device_path`, opening it only once.
+///
+/// # Errors
+///
+/// Same as `get_tdreport_v15_kvm_at()`, failing the whole batch if any
+/// report cannot be retrieved.
+pub fn get_tdreports_v15_kvm_at(
+    device_path: &str,
+    batch: &[[u8; TDX_REPORT_DATA_LEN]],
+) -> Result<Vec<CollectedEvidence>> {
+    let tdx_device = device::TdxDeviceKvmV15::with_path(device_path);
+    let reqs: Vec<_> = batch.iter().map(TdReportV15::create_request).collect();
+
+    tdx_device
+        .get_tdreports_raw(&reqs)?
+        .iter()
+        .zip(batch)
+        .map(|(raw_report, report_data)| {
+            let report = TdReportV15::get_tdreport_from_bytes(raw_report)?;
+            Ok(CollectedEvidence::new(report, report_data)?)
+        })
+        .collect()
+}
+
+/// Verifies a `TDREPORT` generated on the same platform (e.g., by another
+/// TD) with the Intel TDX 1.5 KVM device at `device_path`: checks that its
+/// info hashes match its contents, and has the TDX module verify its MAC.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the info hashes don't match, or the
+/// errors of `TdxDeviceKvmV15::verify_report_raw()`.
+pub fn verify_tdreport_v15_kvm_at(device_path: &str, report: &TdReportV15) -> Result<()> {
+    report.check_info_hashes()?;
+    device::TdxDeviceKvmV15::with_path(device_path)
+        .verify_report_raw(&report.report_mac_struct_bytes())
+}
+
+/// Extends `RTMR[index]` of the Intel TDX 1.5 KVM device with a SHA-384 digest.
+pub fn extend_rtmr_v15_kvm(index: u8, digest: &[u8; TDX_MR_REG_LEN]) -> Result<()> {
+    device::TdxDeviceKvmV15::new().extend_rtmr(index, digest)
 }
 
 #[cfg(test)]
@@ -75,11 +140,28 @@ mod tests {
         let report_data: [u8; 64] = [0; 64];
 
         match get_tdreport_v15_kvm(&report_data) {
-            Ok(report) => {
-                println!("Got TDREPORT: {:?}", report);
+            Ok(evidence) => {
+                assert_eq!(evidence.report_data(), report_data);
+                println!("Got TDREPORT: {:?}", evidence.report());
                 Ok(())
             }
             Err(e) => handle_expected_tdx_error(e),
         }
     }
+
+    #[test]
+    fn test_verify_tdreport_v15_kvm_at() -> Result<()> {
+        // corrupted reports are rejected before reaching the device
+        let report = TdReportV15::builder().with_mrtd(&[1; 48]).build();
+        assert!(matches!(
+            verify_tdreport_v15_kvm_at(device::TDX15_DEV_PATH, &report),
+            Err(crate::error::Error::ParseError(_))
+        ));
+
+        match get_tdreport_v15_kvm(&[0; 64]) {
+            Ok(evidence) => verify_tdreport_v15_kvm_at(device::TDX15_DEV_PATH, evidence.report())
+                .or_else(handle_expected_tdx_error),
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
 }
@@ -0,0 +1,120 @@
+//! # Quote Generation Service (QGS) Utilities for Linux Guests
+//!
+//! This module provides utilities for locating and probing the Intel TDX
+//! Quote Generation Service (QGS), which runs on the host and converts
+//! `TDREPORT`s into signed quotes.
+//!
+//! TD guests can reach the QGS over vsock when a port is configured in
+//! `/etc/tdx-attest.conf` (the configuration file used by Intel's
+//! `libtdx-attest`). Otherwise, quotes are requested through the kernel's
+//! `GetQuote` TDVMCALL path, and the QGS cannot be probed from the guest.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::tdx::linux::qgs;
+//!
+//! match qgs::configured_vsock_port() {
+//!     Ok(Some(port)) => println!("QGS reachable: {}", qgs::is_vsock_reachable(port)),
+//!     Ok(None) => println!("QGS vsock port is not configured"),
+//!     Err(e) => println!("Error reading QGS config: {}", e),
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+/// The path to the `libtdx-attest` configuration file.
+pub const TDX_ATTEST_CONF_PATH: &str = "/etc/tdx-attest.conf";
+
+// The vsock context ID of the host
+const VMADDR_CID_HOST: u32 = 2;
+
+/// Returns the QGS vsock port configured in `/etc/tdx-attest.conf`, if any.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if the configuration file is a symlink.
+/// - `Error::IoError` if the configuration file exists but cannot be read.
+/// - `Error::ParseError` if the configured port is not a valid number.
+pub fn configured_vsock_port() -> Result<Option<u32>> {
+    let path = Path::new(TDX_ATTEST_CONF_PATH);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    // throw an error if this is a symlink
+    if path.is_symlink() {
+        return Err(Error::NotSupported(format!(
+            "Path {} is a symlink",
+            path.display()
+        )));
+    }
+
+    parse_vsock_port(&fs::read_to_string(path)?)
+}
+
+/// Parses the `port=<n>` entry from the contents of a `tdx-attest.conf` file.
+fn parse_vsock_port(conf: &str) -> Result<Option<u32>> {
+    for line in conf.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+
+        if let Some((key, value)) = line.split_once('=')
+            && key.trim() == "port"
+        {
+            let port = value
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| Error::ParseError(format!("Invalid QGS port: {}", e)))?;
+            return Ok(Some(port));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Checks whether the QGS accepts connections on the given vsock port of the
+/// host.
+pub fn is_vsock_reachable(port: u32) -> bool {
+    // SAFETY: plain socket syscalls on a descriptor owned by this function
+    unsafe {
+        let fd = libc::socket(libc::AF_VSOCK, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return false;
+        }
+
+        let mut addr: libc::sockaddr_vm = mem::zeroed();
+        addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+        addr.svm_cid = VMADDR_CID_HOST;
+        addr.svm_port = port;
+
+        let ret = libc::connect(
+            fd,
+            &addr as *const libc::sockaddr_vm as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t,
+        );
+        libc::close(fd);
+
+        ret == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vsock_port() -> Result<()> {
+        let conf = "# QGS vsock port\nport=4050\n";
+        assert_eq!(parse_vsock_port(conf)?, Some(4050));
+
+        let conf = "# port=4050\n";
+        assert_eq!(parse_vsock_port(conf)?, None);
+
+        assert!(parse_vsock_port("port=abc").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+//! # configfs-tsm Report Utilities for Linux Guests
+//!
+//! This module retrieves signed TD quotes through the Linux kernel's
+//! configfs-tsm report interface (`/sys/kernel/config/tsm/report`), which
+//! forwards the TD's `TDREPORT` to the Quote Generation Service (QGS) and
+//! returns the quote, along with any auxiliary blob (e.g., a certificate
+//! chain) the provider returns with it.
+//!
+//! configfs-tsm is the kernel's vendor-neutral attestation interface, and
+//! needs no access to the TDX guest device. The `TsmReportProvider` is an
+//! `AttestationProvider` built on it alone, which `LinuxTdxProvider` selects
+//! when configfs-tsm is available without the TDX guest device (see
+//! `TdxBackend::Tsm`).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::tdx::linux::tsm::{TsmReportProvider, get_quote_tsm};
+//!
+//! let quote = get_quote_tsm(&[0; 64]).unwrap();
+//! println!("Got a {}-byte quote", quote.len());
+//!
+//! let report = TsmReportProvider::new().get_report(&[0; 64]).unwrap();
+//! println!("Got a quote from {} (generation {})", report.provider, report.generation);
+//! ```
+//!
+//! # Notes
+//! - configfs-tsm requires Linux 6.7 or later, with configfs mounted.
+//! - Each call creates (and removes) its own report entry, so concurrent
+//!   callers don't interfere with each other. `get_quotes_tsm()` reuses a
+//!   single entry for a batch of quotes.
+//! - The QGS serves one request at a time, and the kernel fails quote
+//!   requests with `EBUSY` or `EAGAIN` while it's busy. These requests are
+//!   retried with jittered backoff, according to `quote_retry_policy()` by
+//!   default, or to a caller's policy (see `get_quotes_tsm_with_retry()`).
+
+use crate::core::quote::Quote;
+use crate::core::register::MeasurementRegister;
+use crate::error::{Error, Result};
+use crate::platform::TSM_REPORT_PATH;
+use crate::provider::AttestationProvider;
+use crate::retry::RetryPolicy;
+use crate::tdx::TDX_REPORT_DATA_LEN;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+// The provider name reported by configfs-tsm for TDX guests
+const TSM_TDX_PROVIDER: &str = "tdx_guest";
+
+/// The default number of attempts at a quote request while the QGS is busy.
+pub const DEFAULT_QUOTE_ATTEMPTS: u32 = 5;
+
+/// A report generated through configfs-tsm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TsmReport {
+    /// The provider that generated the report (`tdx_guest` for TDX).
+    pub provider: String,
+    /// The generation of the report entry the report was read from, which
+    /// the kernel increments on every write to it.
+    pub generation: u64,
+    /// The report itself, i.e., the signed TD quote for TDX.
+    pub outblob: Vec<u8>,
+    /// The auxiliary blob returned with the report (e.g., a certificate
+    /// chain), if the provider returned any. TDX quotes embed their PCK
+    /// certificate chain instead.
+    pub auxblob: Option<Vec<u8>>,
+}
+
+impl TsmReport {
+    /// Parses the report's quote.
+    ///
+    /// # Errors
+    ///
+    /// Same as `Quote::from_bytes()`.
+    pub fn quote(&self) -> Result<Quote> {
+        Ok(Quote::from_bytes(&self.outblob)?)
+    }
+}
+
+/// An attestation provider generating reports through configfs-tsm alone,
+/// e.g., in containers where configfs is mounted, but the TDX guest device
+/// isn't available.
+///
+/// configfs-tsm doesn't expose the TD's `TDREPORT`, so the provider's
+/// attestation reports are quotes, and launch measurements are read from
+/// their bodies.
+#[derive(Clone, Debug)]
+pub struct TsmReportProvider {
+    quote_retry_policy: RetryPolicy,
+}
+
+impl Default for TsmReportProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TsmReportProvider {
+    /// Creates a provider retrying requests according to
+    /// `quote_retry_policy()`.
+    pub fn new() -> Self {
+        Self {
+            quote_retry_policy: quote_retry_policy(),
+        }
+    }
+
+    /// Sets the policy for retrying requests while the QGS is busy.
+    pub fn with_quote_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.quote_retry_policy = policy;
+        self
+    }
+
+    /// Retrieves a report over `report_data`, with its auxiliary blob.
+    ///
+    /// # Errors
+    ///
+    /// Same as `get_quote_tsm()`.
+    pub fn get_report(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<TsmReport> {
+        let mut reports = get_reports_tsm_with_retry(
+            std::slice::from_ref(report_data),
+            &self.quote_retry_policy,
+        )?;
+        Ok(reports.remove(0))
+    }
+
+    /// Retrieves a signed TD quote over `report_data`.
+    ///
+    /// # Errors
+    ///
+    /// Same as `get_quote_tsm()`.
+    pub fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+        Ok(self.get_report(report_data)?.outblob)
+    }
+
+    /// Retrieves signed TD quotes over a batch of `report_data` values,
+    /// through a single report entry.
+    ///
+    /// # Errors
+    ///
+    /// Same as `get_quotes_tsm()`.
+    pub fn get_quotes(&self, batch: &[[u8; TDX_REPORT_DATA_LEN]]) -> Result<Vec<Vec<u8>>> {
+        get_quotes_tsm_with_retry(batch, &self.quote_retry_policy)
+    }
+}
+
+impl AttestationProvider for TsmReportProvider {
+    /// Retrieves a report over all-zero report data, serialized into JSON
+    /// with its provider, generation, and base64-encoded blobs.
+    fn get_attestation_report(&self) -> Result<String> {
+        let report = self.get_report(&[0; TDX_REPORT_DATA_LEN])?;
+        let json = serde_json::json!({
+            "provider": report.provider,
+            "generation": report.generation,
+            "outblob": STANDARD.encode(&report.outblob),
+            "auxblob": report.auxblob.as_ref().map(|blob| STANDARD.encode(blob)),
+        });
+        serde_json::to_string(&json).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Retrieves the TD's launch measurement (`MRTD`) from the body of a
+    /// quote.
+    fn get_launch_measurement(&self) -> Result<MeasurementRegister> {
+        Ok(self
+            .get_report(&[0; TDX_REPORT_DATA_LEN])?
+            .quote()?
+            .body
+            .mrtd)
+    }
+}
+
+/// Returns whether the configfs-tsm report interface is available.
+pub fn is_available() -> bool {
+    Path::new(TSM_REPORT_PATH).is_dir()
+}
+
+/// Returns the default policy for retrying quote requests while the QGS is
+/// busy: `DEFAULT_QUOTE_ATTEMPTS` attempts, with jittered backoff from 100ms
+/// up to 2s.
+pub fn quote_retry_policy() -> RetryPolicy {
+    RetryPolicy::default()
+        .with_max_attempts(DEFAULT_QUOTE_ATTEMPTS)
+        .with_backoff(Duration::from_millis(100), Duration::from_secs(2))
+        .with_jitter(true)
+        .without_timeout()
+}
+
+/// Returns whether `error` is a quote request failure worth retrying, i.e.,
+/// an `EBUSY` or `EAGAIN` from the kernel while the QGS is busy.
+pub fn is_qgs_busy(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::IoError(e) if matches!(e.raw_os_error(), Some(libc::EBUSY | libc::EAGAIN))
+    )
+}
+
+/// Retrieves a signed TD quote over `report_data` via configfs-tsm.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if configfs-tsm isn't available, or isn't backed by
+///   the TDX guest driver.
+/// - `Error::QuoteError` if the quote cannot be generated (e.g., because the
+///   QGS is unreachable, or still busy after the retries of
+///   `quote_retry_policy()`).
+pub fn get_quote_tsm(report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+    let mut quotes = get_quotes_tsm(std::slice::from_ref(report_data))?;
+    Ok(quotes.remove(0))
+}
+
+/// Retrieves signed TD quotes over a batch of `report_data` values via
+/// configfs-tsm, through a single report entry.
+///
+/// # Errors
+///
+/// Same as `get_quote_tsm()`, failing the whole batch if any quote cannot be
+/// generated.
+pub fn get_quotes_tsm(batch: &[[u8; TDX_REPORT_DATA_LEN]]) -> Result<Vec<Vec<u8>>> {
+    get_quotes_tsm_with_retry(batch, &quote_retry_policy())
+}
+
+/// Retrieves signed TD quotes over a batch of `report_data` values via
+/// configfs-tsm, retrying each quote request while the QGS is busy according
+/// to `policy` (see `is_qgs_busy()`).
+///
+/// # Errors
+///
+/// Same as `get_quotes_tsm()`.
+pub fn get_quotes_tsm_with_retry(
+    batch: &[[u8; TDX_REPORT_DATA_LEN]],
+    policy: &RetryPolicy,
+) -> Result<Vec<Vec<u8>>> {
+    Ok(get_reports_tsm_with_retry(batch, policy)?
+        .into_iter()
+        .map(|report| report.outblob)
+        .collect())
+}
+
+/// Retrieves reports over a batch of `report_data` values via configfs-tsm,
+/// with their auxiliary blobs, like `get_quotes_tsm_with_retry()`.
+///
+/// # Errors
+///
+/// Same as `get_quotes_tsm()`.
+pub fn get_reports_tsm_with_retry(
+    batch: &[[u8; TDX_REPORT_DATA_LEN]],
+    policy: &RetryPolicy,
+) -> Result<Vec<TsmReport>> {
+    if batch.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let report_path = Path::new(TSM_REPORT_PATH);
+    if !fs::exists(report_path).map_err(|e| Error::NotSupported(format!("{}", e)))? {
+        return Err(Error::NotSupported(format!(
+            "configfs-tsm is not supported by this kernel ({} not found)",
+            report_path.display()
+        )));
+    }
+
+    let entry = report_path.join(format!("tdx-workload-attestation-{}", rand_suffix()));
+    fs::create_dir(&entry)
+        .map_err(|e| Error::QuoteError(format!("Failed to create TSM report: {}", e)))?;
+
+    let reports = read_reports(&entry, batch, policy);
+
+    // configfs entries are removed with rmdir, even though they contain files
+    let _ = fs::remove_dir(&entry);
+
+    reports
+}
+
+fn read_reports(
+    entry: &Path,
+    batch: &[[u8; TDX_REPORT_DATA_LEN]],
+    policy: &RetryPolicy,
+) -> Result<Vec<TsmReport>> {
+    let provider = fs::read_to_string(entry.join("provider"))?;
+    let provider = provider.trim();
+    if provider != TSM_TDX_PROVIDER {
+        return Err(Error::NotSupported(format!(
+            "configfs-tsm provider {} is not supported",
+            provider
+        )));
+    }
+
+    batch
+        .iter()
+        .map(|report_data| {
+            policy
+                .run_when(|| read_report(entry, provider, report_data), is_qgs_busy)
+                .map_err(|e| {
+                    if is_qgs_busy(&e) {
+                        Error::QuoteError(format!(
+                            "The QGS is still busy after {} attempts: {}",
+                            policy.max_attempts(),
+                            e
+                        ))
+                    } else {
+                        e
+                    }
+                })
+        })
+        .collect()
+}
+
+// Fails with an `Error::IoError` if the QGS is busy, so that the request is
+// retried.
+fn read_report(
+    entry: &Path,
+    provider: &str,
+    report_data: &[u8; TDX_REPORT_DATA_LEN],
+) -> Result<TsmReport> {
+    fs::write(entry.join("inblob"), report_data)
+        .map_err(|e| quote_error("Failed to write TSM report data", e))?;
+    let generation = read_generation(entry)?;
+
+    let quote =
+        fs::read(entry.join("outblob")).map_err(|e| quote_error("Failed to get TD quote", e))?;
+    let auxblob = match fs::read(entry.join("auxblob")) {
+        Ok(blob) if blob.is_empty() => None,
+        Ok(blob) => Some(blob),
+        // providers without auxiliary blobs fail their reads
+        Err(e) if matches!(e.raw_os_error(), Some(libc::ENOENT | libc::ENXIO)) => None,
+        Err(e) => return Err(quote_error("Failed to get TSM auxiliary blob", e)),
+    };
+
+    // the generation changes if another writer raced us on the same entry
+    if read_generation(entry)? != generation {
+        return Err(Error::QuoteError(
+            "TSM report was modified while generating the quote".to_string(),
+        ));
+    }
+    if quote.is_empty() {
+        return Err(Error::QuoteError("Got an empty TD quote".to_string()));
+    }
+
+    Ok(TsmReport {
+        provider: provider.to_string(),
+        generation,
+        outblob: quote,
+        auxblob,
+    })
+}
+
+fn read_generation(entry: &Path) -> Result<u64> {
+    let generation = fs::read_to_string(entry.join("generation"))?;
+    generation.trim().parse().map_err(|e| {
+        Error::ParseError(format!(
+            "Invalid TSM report generation {}: {}",
+            generation.trim(),
+            e
+        ))
+    })
+}
+
+fn quote_error(context: &str, e: io::Error) -> Error {
+    if matches!(e.raw_os_error(), Some(libc::EBUSY | libc::EAGAIN)) {
+        Error::IoError(e)
+    } else {
+        Error::QuoteError(format!("{}: {}", context, e))
+    }
+}
+
+fn rand_suffix() -> String {
+    // the entry only needs to be unique among concurrent callers
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tdx::test_utils::handle_expected_tdx_error;
+
+    #[test]
+    fn test_is_qgs_busy() {
+        let busy = |errno| quote_error("Failed", io::Error::from_raw_os_error(errno));
+        assert!(is_qgs_busy(&busy(libc::EBUSY)));
+        assert!(is_qgs_busy(&busy(libc::EAGAIN)));
+        assert!(!is_qgs_busy(&busy(libc::EIO)));
+        assert!(!is_qgs_busy(&Error::IoError(
+            io::ErrorKind::NotFound.into()
+        )));
+    }
+
+    #[test]
+    fn test_get_quote_tsm() -> Result<()> {
+        match get_quote_tsm(&[0; TDX_REPORT_DATA_LEN]) {
+            Ok(quote) => {
+                assert!(!quote.is_empty());
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
+
+    #[test]
+    fn test_tsm_report_provider() -> Result<()> {
+        let provider = TsmReportProvider::new().with_quote_retry_policy(RetryPolicy::no_retry());
+        match provider.get_report(&[2; TDX_REPORT_DATA_LEN]) {
+            Ok(report) => {
+                assert_eq!(report.provider, TSM_TDX_PROVIDER);
+                assert_eq!(report.quote()?.body.report_data, [2; TDX_REPORT_DATA_LEN]);
+                assert!(!provider.get_launch_measurement()?.is_zero());
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
+
+    #[test]
+    fn test_get_quotes_tsm() -> Result<()> {
+        assert!(get_quotes_tsm(&[])?.is_empty());
+        match get_quotes_tsm(&[[0; TDX_REPORT_DATA_LEN], [1; TDX_REPORT_DATA_LEN]]) {
+            Ok(quotes) => {
+                assert_eq!(quotes.len(), 2);
+                assert_ne!(quotes[0], quotes[1]);
+                Ok(())
+            }
+            Err(e) => handle_expected_tdx_error(e),
+        }
+    }
+}
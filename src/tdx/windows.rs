@@ -0,0 +1,208 @@
+//! # TDX Utilities for Windows Guests
+//!
+//! This module retrieves TD reports and quotes in Windows TD guests. Windows
+//! TD guests run under a Hyper-V paravisor (e.g., on Azure), which owns the
+//! TDX guest interface and exposes the TD's `TDREPORT` through the HCL report
+//! in an NV index of the guest's vTPM (see the `hcl` module).
+//!
+//! The vTPM is accessed through the Windows TPM Base Services (TBS), the
+//! system's TPM driver interface, which arbitrates access to the TPM between
+//! applications. Quotes are generated from the `TDREPORT` by the Azure
+//! Instance Metadata Service (IMDS).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::tdx::windows::WindowsTdxProvider;
+//! use tdx_workload_attestation::provider::AttestationProvider;
+//!
+//! let provider = WindowsTdxProvider::new();
+//!
+//! // Get the launch measurement
+//! let measurement = provider.get_launch_measurement().unwrap();
+//! println!("Launch Measurement: {:?}", measurement);
+//!
+//! // Get a quote
+//! let quote = provider.get_quote(&[0; 64]).unwrap();
+//! println!("Got a {}-byte quote", quote.len());
+//! ```
+//!
+//! # Notes
+//! - Reading and writing the vTPM's NV indices with owner authorization
+//!   requires administrator privileges.
+//! - The paravisor doesn't expose the TD's RTMRs for extension, so runtime
+//!   measurements should be made into the vTPM's PCRs instead.
+
+use crate::error::{Error, Result};
+use crate::provider::AttestationProvider;
+use crate::schema::VersionedReport;
+use crate::tdx::TDX_REPORT_DATA_LEN;
+use crate::tdx::hcl;
+use crate::tdx::register::MeasurementRegister;
+use crate::tdx::report::CollectedEvidence;
+
+use std::ffi::c_void;
+use windows_sys::Win32::System::TpmBaseServices::{
+    TBS_COMMAND_LOCALITY_ZERO, TBS_COMMAND_PRIORITY_NORMAL, TBS_CONTEXT_PARAMS,
+    TBS_CONTEXT_PARAMS2, TBS_CONTEXT_PARAMS2_0, TBS_CONTEXT_VERSION_TWO, TBS_SUCCESS,
+    Tbsi_Context_Create, Tbsip_Context_Close, Tbsip_Submit_Command,
+};
+
+// The `includeTpm20` flag of TBS_CONTEXT_PARAMS2, requesting a context for
+// a TPM 2.0
+const TBS_INCLUDE_TPM20: u32 = 1 << 2;
+
+// The largest TPM response, as with the Linux resource manager
+const TBS_RESPONSE_LEN: usize = 4096;
+
+/// Checks whether the Hyper-V paravisor exposes a TDX HCL report through the
+/// vTPM.
+pub fn is_available() -> Result<bool> {
+    hcl::is_available()
+}
+
+/// An interface for retrieving attestation reports and launch measurements
+/// with TDX on Windows VM guests.
+///
+/// This struct implements the `AttestationProvider` trait.
+#[derive(Clone, Debug, Default)]
+pub struct WindowsTdxProvider;
+
+impl WindowsTdxProvider {
+    /// Creates a new instance of `WindowsTdxProvider`.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Retrieves the `TDREPORT` over `report_data`.
+    ///
+    /// The `report_data` of the `TDREPORT` is the digest of the HCL runtime
+    /// data, which includes `report_data` as its user data, rather than
+    /// `report_data` itself (see the `hcl` module).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the vTPM isn't available, an
+    /// `Error::QuoteError` if the HCL report can't be retrieved, or an
+    /// `Error::ParseError` if the report is malformed, its info hashes don't
+    /// match its contents, or its user data isn't `report_data` (see
+    /// `HclReport::collected_evidence()`).
+    pub fn get_tdreport(
+        &self,
+        report_data: &[u8; TDX_REPORT_DATA_LEN],
+    ) -> Result<CollectedEvidence> {
+        hcl::get_hcl_report(report_data)?.collected_evidence(report_data)
+    }
+
+    /// Retrieves a signed TD quote over `report_data` from the Azure IMDS.
+    ///
+    /// Like the `TDREPORT`, the quote binds the digest of the HCL runtime
+    /// data, rather than `report_data` itself.
+    ///
+    /// # Errors
+    ///
+    /// Same as `get_tdreport()`, or an `Error::NetworkError` if the IMDS
+    /// cannot be reached.
+    ///
+    /// Every attempt is recorded in the `metrics` module.
+    pub fn get_quote(&self, report_data: &[u8; TDX_REPORT_DATA_LEN]) -> Result<Vec<u8>> {
+        let quote = hcl::get_hcl_report(report_data)
+            .and_then(|report| hcl::get_quote_imds(report.td_report_bytes()));
+        crate::metrics::global().record_quote("windows", &quote);
+        quote
+    }
+}
+
+impl AttestationProvider for WindowsTdxProvider {
+    /// Retrieves the attestation report for a TDX Windows guest environment,
+    /// serialized into JSON (like `LinuxTdxProvider`'s).
+    fn get_attestation_report(&self) -> Result<String> {
+        let report = self.get_tdreport(&[0; TDX_REPORT_DATA_LEN])?;
+        VersionedReport::new(report.into_report()).to_json()
+    }
+
+    /// Retrieves the TD's launch measurement (`MRTD`) from its report.
+    fn get_launch_measurement(&self) -> Result<MeasurementRegister> {
+        Ok(self
+            .get_tdreport(&[0; TDX_REPORT_DATA_LEN])?
+            .report()
+            .get_mrtd())
+    }
+}
+
+/// A TBS context for submitting raw commands to the TPM 2.0.
+pub(crate) struct TbsContext {
+    handle: *mut c_void,
+}
+
+impl TbsContext {
+    /// Opens a TBS context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if no TPM 2.0 is available.
+    pub(crate) fn open() -> Result<Self> {
+        let params = TBS_CONTEXT_PARAMS2 {
+            version: TBS_CONTEXT_VERSION_TWO,
+            Anonymous: TBS_CONTEXT_PARAMS2_0 {
+                asUINT32: TBS_INCLUDE_TPM20,
+            },
+        };
+        let mut handle = std::ptr::null_mut();
+        // SAFETY: `params` is a valid TBS_CONTEXT_PARAMS2, whose version
+        // tells TBS to read it as such
+        let result = unsafe {
+            Tbsi_Context_Create(
+                &params as *const TBS_CONTEXT_PARAMS2 as *const TBS_CONTEXT_PARAMS,
+                &mut handle,
+            )
+        };
+        if result != TBS_SUCCESS {
+            return Err(Error::NotSupported(format!(
+                "Failed to open a TBS context: {:#x}",
+                result
+            )));
+        }
+        Ok(Self { handle })
+    }
+
+    /// Submits a raw TPM command, returning the raw response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::QuoteError` if TBS fails to submit the command.
+    pub(crate) fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let mut response = vec![0u8; TBS_RESPONSE_LEN];
+        let mut len = response.len() as u32;
+        // SAFETY: the command and response buffers are valid for their
+        // lengths, and the context is open
+        let result = unsafe {
+            Tbsip_Submit_Command(
+                self.handle,
+                TBS_COMMAND_LOCALITY_ZERO,
+                TBS_COMMAND_PRIORITY_NORMAL,
+                command.as_ptr(),
+                command.len() as u32,
+                response.as_mut_ptr(),
+                &mut len,
+            )
+        };
+        if result != TBS_SUCCESS {
+            return Err(Error::QuoteError(format!(
+                "vTPM command failed with TBS error {:#x}",
+                result
+            )));
+        }
+        response.truncate(len as usize);
+        Ok(response)
+    }
+}
+
+impl Drop for TbsContext {
+    fn drop(&mut self) {
+        // SAFETY: the context is open, and isn't used after it's closed
+        unsafe {
+            Tbsip_Context_Close(self.handle);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! # Test Utilities
+//!
+//! This module provides the helpers shared by the tests of several modules,
+//! such as the builders of the certificates their verification tests need.
+
+use crate::der::{
+    TAG_CONTEXT_0, TAG_CONTEXT_3, TAG_GENERALIZED_TIME, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID,
+    TAG_SEQUENCE, tlv,
+};
+
+#[cfg(feature = "host-verification")]
+use openssl::asn1::Asn1Time;
+#[cfg(feature = "host-verification")]
+use openssl::bn::BigNum;
+#[cfg(feature = "host-verification")]
+use openssl::pkey::{HasPublic, PKeyRef};
+#[cfg(feature = "host-verification")]
+use openssl::x509::{X509Builder, X509NameBuilder};
+
+// The DER-encoded OID of ecdsa-with-SHA256
+const ECDSA_WITH_SHA256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_UTC_TIME: u8 = 0x17;
+
+/// Builds a minimal DER-encoded certificate, valid from `not_before` (a
+/// `UTCTime`) to `not_after` (a `GeneralizedTime`), with the `extensions`
+/// given by DER-encoded OID. Its names, key and signature are placeholders,
+/// for tests that only parse certificates.
+pub(crate) fn make_der_cert(
+    not_before: &str,
+    not_after: &str,
+    extensions: &[(&[u8], &[u8])],
+) -> Vec<u8> {
+    let validity = [
+        tlv(TAG_UTC_TIME, not_before.as_bytes()),
+        tlv(TAG_GENERALIZED_TIME, not_after.as_bytes()),
+    ]
+    .concat();
+    let mut tbs = [
+        tlv(TAG_CONTEXT_0, &tlv(TAG_INTEGER, &[2])),
+        tlv(TAG_INTEGER, &[1; 20]),
+        tlv(TAG_SEQUENCE, &tlv(TAG_OID, ECDSA_WITH_SHA256_OID)),
+        tlv(TAG_SEQUENCE, &[0; 200]),
+        tlv(TAG_SEQUENCE, &validity),
+        tlv(TAG_SEQUENCE, &[]),
+    ]
+    .concat();
+    if !extensions.is_empty() {
+        let mut encoded = vec![];
+        for (oid, value) in extensions {
+            let extension = [tlv(TAG_OID, oid), tlv(TAG_OCTET_STRING, value)].concat();
+            encoded.extend(tlv(TAG_SEQUENCE, &extension));
+        }
+        tbs.extend(tlv(TAG_CONTEXT_3, &tlv(TAG_SEQUENCE, &encoded)));
+    }
+    tlv(
+        TAG_SEQUENCE,
+        &[
+            tlv(TAG_SEQUENCE, &tbs),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_BIT_STRING, &[0; 72]),
+        ]
+        .concat(),
+    )
+}
+
+/// Returns a builder of an X.509 v3 certificate of `key`, for the common
+/// name `subject`, issued by `issuer`, and valid from now for `days`.
+#[cfg(feature = "host-verification")]
+pub(crate) fn cert_builder<T: HasPublic>(
+    subject: &str,
+    issuer: &str,
+    key: &PKeyRef<T>,
+    days: u32,
+) -> X509Builder {
+    let name = |cn: &str| {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        name.build()
+    };
+
+    let mut cert = X509Builder::new().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    cert.set_subject_name(&name(subject)).unwrap();
+    cert.set_issuer_name(&name(issuer)).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(days).unwrap())
+        .unwrap();
+    cert.set_pubkey(key).unwrap();
+    cert
+}
@@ -0,0 +1,634 @@
+//! # Trust Anchors
+//!
+//! This module provides the `TrustAnchors` store, which centrally manages the
+//! root certificates that attestation evidence is verified against:
+//! - the Intel SGX Root CA, which the PCK certificate chains of TD quotes and
+//!   Intel's TCB collateral chain up to,
+//! - the GCE Confidential Computing TCB root, which GCP launch endorsements
+//!   chain up to,
+//! - Azure's attestation roots,
+//! - RFC 3161 timestamp authority roots, and
+//! - Sigstore Fulcio roots, which the signing certificates of signed
+//!   policies and reference values chain up to.
+//!
+//! Anchors can be added programmatically (e.g., a private PCCS root) or
+//! loaded from a directory or files, and can be pinned by their SHA-256 fingerprint:
+//! once any fingerprint is pinned, only pinned anchors are trusted. The store
+//! also reports anchors that have expired or are about to.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::trust::{DEFAULT_EXPIRY_WARNING_SECS, TrustAnchorKind, TrustAnchors};
+//!
+//! let anchors = TrustAnchors::from_dir("/etc/tdx-workload-attestation/anchors").unwrap();
+//!
+//! let now = std::time::SystemTime::now()
+//!     .duration_since(std::time::UNIX_EPOCH)
+//!     .unwrap()
+//!     .as_secs();
+//! for warning in anchors.expiry_warnings(now, DEFAULT_EXPIRY_WARNING_SECS) {
+//!     eprintln!("Warning: {}", warning);
+//! }
+//!
+//! for root in anchors.roots(TrustAnchorKind::IntelSgxRoot) {
+//!     println!("Trusting {} ({})", root.name, hex::encode(root.fingerprint()));
+//! }
+//! ```
+//!
+//! # Notes
+//! - Anchors are loaded from the files in the directory with a `.der`,
+//!   `.cer`, `.crt` or `.pem` extension, whose kind is given by the file
+//!   name: `root_ca.*` and `intel_sgx_root*` files hold Intel SGX roots,
+//!   `gce_tcb_root*` and `GCE-cc-tcb-root*` files hold GCE TCB roots,
+//!   `azure*` files hold Azure roots, `tsa_root*` files hold timestamp
+//!   authority roots, and `fulcio_root*` files hold Fulcio roots. Other files
+//!   are ignored.
+//! - Several Intel SGX roots can be trusted at once, e.g., the roots of
+//!   regional provisioning infrastructures whose PCS issues PCK certificates
+//!   under a different root (as `intel_sgx_root_<region>.*` files, or loaded
+//!   with `load_file()`): evidence is accepted if it chains up to any of
+//!   them.
+//! - Certificates are not parsed beyond their validity period, which is only
+//!   used for expiry warnings: malformed certificates are rejected when
+//!   they're used for verification.
+
+use crate::error::{Error, Result};
+use crate::evidence::quote::pem_to_der;
+
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+
+/// The URL of the Intel SGX Provisioning Certification Root CA.
+pub const INTEL_SGX_ROOT_CA_URL: &str = "https://certificates.trustedservices.intel.com/Intel_SGX_Provisioning_Certification_RootCA.der";
+
+/// The URL of the GCE Confidential Computing TCB root certificate.
+pub const GCE_TCB_ROOT_URL: &str = "https://pki.goog/cloud_integrity/GCE-cc-tcb-root_1.crt";
+
+/// The default period before an anchor's expiry in which warnings are
+/// reported.
+pub const DEFAULT_EXPIRY_WARNING_SECS: u64 = 30 * 24 * 3600;
+
+// The extensions of the files loaded from an anchors directory
+const CERT_EXTENSIONS: [&str; 4] = ["der", "cer", "crt", "pem"];
+
+/// The verification path a trust anchor is trusted for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrustAnchorKind {
+    /// The Intel SGX Root CA, for TD quotes and Intel's TCB collateral.
+    IntelSgxRoot,
+    /// The GCE Confidential Computing TCB root, for GCP launch endorsements.
+    GceTcbRoot,
+    /// An Azure attestation root.
+    AzureRoot,
+    /// An RFC 3161 timestamp authority (TSA) root, for evidence timestamps.
+    TsaRoot,
+    /// A Sigstore Fulcio root, for signed policies and reference values.
+    FulcioRoot,
+}
+
+impl TrustAnchorKind {
+    /// Returns the stable string code for this kind of anchor.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrustAnchorKind::IntelSgxRoot => "intel-sgx-root",
+            TrustAnchorKind::GceTcbRoot => "gce-tcb-root",
+            TrustAnchorKind::AzureRoot => "azure-root",
+            TrustAnchorKind::TsaRoot => "tsa-root",
+            TrustAnchorKind::FulcioRoot => "fulcio-root",
+        }
+    }
+
+    /// Returns the kind of the anchors held in the file `file_name`, if any.
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let (stem, ext) = file_name.rsplit_once('.')?;
+        if !CERT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+            return None;
+        }
+
+        let stem = stem.to_ascii_lowercase();
+        if stem == "root_ca" || stem.starts_with("intel_sgx_root") {
+            Some(TrustAnchorKind::IntelSgxRoot)
+        } else if stem.starts_with("gce_tcb_root") || stem.starts_with("gce-cc-tcb-root") {
+            Some(TrustAnchorKind::GceTcbRoot)
+        } else if stem.starts_with("azure") {
+            Some(TrustAnchorKind::AzureRoot)
+        } else if stem.starts_with("tsa_root") {
+            Some(TrustAnchorKind::TsaRoot)
+        } else if stem.starts_with("fulcio_root") {
+            Some(TrustAnchorKind::FulcioRoot)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for TrustAnchorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A trusted root certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrustAnchor {
+    /// The verification path the anchor is trusted for.
+    pub kind: TrustAnchorKind,
+    /// A name for the anchor (e.g., the file it was loaded from).
+    pub name: String,
+    /// The DER-encoded certificate.
+    pub der: Vec<u8>,
+    /// The validity period of the certificate, in seconds since the Unix
+    /// epoch, if it could be parsed.
+    pub validity: Option<(u64, u64)>,
+}
+
+impl TrustAnchor {
+    /// Creates a new anchor from the DER-encoded certificate `der`.
+    pub fn new(kind: TrustAnchorKind, name: &str, der: &[u8]) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            der: der.to_vec(),
+            validity: parse_validity(der),
+        }
+    }
+
+    /// Returns the SHA-256 fingerprint of the certificate.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(&self.der).into()
+    }
+}
+
+/// A store of trusted root certificates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustAnchors {
+    anchors: Vec<TrustAnchor>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl TrustAnchors {
+    /// Creates a new, empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the anchors in `dir` into a new store (see `load_dir()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or an anchor cannot be read.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut anchors = Self::new();
+        anchors.load_dir(dir)?;
+        Ok(anchors)
+    }
+
+    /// Adds the anchors in the certificate files in `dir`, whose kind is
+    /// given by their file name (see the module documentation).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the directory or a file cannot be read,
+    /// or an `Error::ParseError` if a PEM file is malformed. Symlinks are
+    /// skipped.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(kind) = TrustAnchorKind::from_file_name(&file_name) else {
+                continue;
+            };
+            if path.is_symlink() || !path.is_file() {
+                continue;
+            }
+            self.load_file(kind, &path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the anchors of kind `kind` in the DER- or PEM-encoded certificate
+    /// file at `path`, whatever its name (e.g., the Intel SGX root of a
+    /// regional provisioning infrastructure).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the file is a symlink, an
+    /// `Error::IoError` if it cannot be read, or an `Error::ParseError` if a
+    /// PEM file is malformed.
+    pub fn load_file<P: AsRef<Path>>(&mut self, kind: TrustAnchorKind, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if path.is_symlink() {
+            return Err(Error::NotSupported(format!(
+                "Path {} is a symlink",
+                path.display()
+            )));
+        }
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        let bytes = std::fs::read(path)?;
+        if file_name.to_ascii_lowercase().ends_with(".pem") || bytes.starts_with(b"-----") {
+            let pem = String::from_utf8(bytes)
+                .map_err(|_| Error::ParseError(format!("{} is not valid UTF-8", file_name)))?;
+            self.add_pem(kind, &file_name, &pem)
+        } else {
+            self.add(TrustAnchor::new(kind, &file_name, &bytes));
+            Ok(())
+        }
+    }
+
+    /// Adds an anchor.
+    pub fn add(&mut self, anchor: TrustAnchor) {
+        if !self.anchors.contains(&anchor) {
+            self.anchors.push(anchor);
+        }
+    }
+
+    /// Adds the anchors in the PEM-encoded certificates `pem`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the PEM is malformed or holds no
+    /// certificates.
+    pub fn add_pem(&mut self, kind: TrustAnchorKind, name: &str, pem: &str) -> Result<()> {
+        for der in pem_to_der(pem)? {
+            self.add(TrustAnchor::new(kind, name, &der));
+        }
+        Ok(())
+    }
+
+    /// Adds the DER-encoded certificate `der` as an anchor of kind `kind`.
+    pub fn with_anchor(mut self, kind: TrustAnchorKind, der: &[u8]) -> Self {
+        self.add(TrustAnchor::new(kind, kind.as_str(), der));
+        self
+    }
+
+    /// Pins the anchor with the SHA-256 fingerprint `fingerprint`. Once any
+    /// anchor is pinned, unpinned anchors are no longer trusted.
+    pub fn with_pin(mut self, fingerprint: [u8; 32]) -> Self {
+        self.pins.push(fingerprint);
+        self
+    }
+
+    /// Returns all the anchors in the store, including unpinned ones.
+    pub fn anchors(&self) -> &[TrustAnchor] {
+        &self.anchors
+    }
+
+    /// Returns whether `anchor` is trusted, i.e., it is pinned or no anchor
+    /// is pinned.
+    pub fn is_trusted(&self, anchor: &TrustAnchor) -> bool {
+        self.pins.is_empty() || self.pins.contains(&anchor.fingerprint())
+    }
+
+    /// Returns the trusted anchors of kind `kind`.
+    pub fn roots(&self, kind: TrustAnchorKind) -> impl Iterator<Item = &TrustAnchor> {
+        self.anchors
+            .iter()
+            .filter(move |anchor| anchor.kind == kind && self.is_trusted(anchor))
+    }
+
+    /// Returns whether the store has trusted anchors of kind `kind`.
+    pub fn has_roots(&self, kind: TrustAnchorKind) -> bool {
+        self.roots(kind).next().is_some()
+    }
+
+    /// Verifies evidence against each trusted anchor of kind `kind` with
+    /// `verify`, until one succeeds.
+    ///
+    /// Returns `None` if there are no trusted anchors of kind `kind`, and the
+    /// result of the last attempt otherwise.
+    pub fn verify_any<F>(&self, kind: TrustAnchorKind, mut verify: F) -> Option<Result<bool>>
+    where
+        F: FnMut(&TrustAnchor) -> Result<bool>,
+    {
+        let mut result = None;
+        for anchor in self.roots(kind) {
+            let verified = verify(anchor);
+            if matches!(verified, Ok(true)) {
+                return Some(verified);
+            }
+            result = Some(verified);
+        }
+        result
+    }
+
+    /// Returns a warning for each trusted anchor that has expired, or will
+    /// expire within `window_secs` of `unix_time`, or whose validity period
+    /// cannot be determined.
+    pub fn expiry_warnings(&self, unix_time: u64, window_secs: u64) -> Vec<String> {
+        self.anchors
+            .iter()
+            .filter(|anchor| self.is_trusted(anchor))
+            .filter_map(|anchor| {
+                let name = format!("{} trust anchor {}", anchor.kind, anchor.name);
+                match anchor.validity {
+                    None => Some(format!("{} has no parsable validity period", name)),
+                    Some((_, not_after)) if not_after <= unix_time => {
+                        Some(format!("{} has expired", name))
+                    }
+                    Some((_, not_after)) if not_after - unix_time <= window_secs => Some(format!(
+                        "{} expires in {} days",
+                        name,
+                        (not_after - unix_time) / 86400
+                    )),
+                    Some((not_before, _)) if unix_time < not_before => {
+                        Some(format!("{} is not yet valid", name))
+                    }
+                    Some(_) => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the next DER TLV in `der` as its tag, contents and the remaining
+/// bytes.
+fn next_tlv(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let num_bytes = (first & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || rest.len() < num_bytes {
+            return None;
+        }
+        let len = rest[..num_bytes]
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, &rest[num_bytes..])
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+/// Parses the validity period of a DER-encoded X.509 certificate.
+pub(crate) fn parse_validity(der: &[u8]) -> Option<(u64, u64)> {
+    const SEQUENCE: u8 = 0x30;
+    const VERSION: u8 = 0xa0;
+
+    let (SEQUENCE, cert, _) = next_tlv(der)? else {
+        return None;
+    };
+    let (SEQUENCE, tbs, _) = next_tlv(cert)? else {
+        return None;
+    };
+
+    // skip the version, serial number, signature algorithm and issuer
+    let mut rest = tbs;
+    if rest.first() == Some(&VERSION) {
+        rest = next_tlv(rest)?.2;
+    }
+    for _ in 0..3 {
+        rest = next_tlv(rest)?.2;
+    }
+
+    let (SEQUENCE, validity, _) = next_tlv(rest)? else {
+        return None;
+    };
+    let (not_before_tag, not_before, rest) = next_tlv(validity)?;
+    let (not_after_tag, not_after, _) = next_tlv(rest)?;
+
+    Some((
+        parse_time(not_before_tag, not_before)?,
+        parse_time(not_after_tag, not_after)?,
+    ))
+}
+
+/// Parses a DER `UTCTime` or `GeneralizedTime` into seconds since the Unix
+/// epoch.
+fn parse_time(tag: u8, time: &[u8]) -> Option<u64> {
+    const UTC_TIME: u8 = 0x17;
+    const GENERALIZED_TIME: u8 = 0x18;
+
+    let time = std::str::from_utf8(time).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        UTC_TIME if time.len() == 12 => {
+            let year: u64 = time[..2].parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &time[2..],
+            )
+        }
+        GENERALIZED_TIME if time.len() == 14 => (time[..4].parse().ok()?, &time[4..]),
+        _ => return None,
+    };
+
+    let field = |i: usize| rest.get(i..i + 2)?.parse::<u64>().ok();
+    let (month, day) = (field(0)?, field(2)?);
+    let (hour, minute, second) = (field(4)?, field(6)?, field(8)?);
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // days since the epoch of the civil date (see Howard Hinnant's
+    // `days_from_civil`)
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let yoe = y % 400;
+    let doy = (153 * m + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Some(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_der_cert;
+
+    // 2023-11-14T22:13:20Z
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_parse_validity() {
+        let cert = make_der_cert("180521104550Z", "20491231235959Z", &[]);
+        assert_eq!(parse_validity(&cert), Some((1_526_899_550, 2_524_607_999)));
+
+        assert_eq!(parse_validity(&[1, 2, 3]), None);
+        assert_eq!(
+            parse_validity(&make_der_cert("bogus", "20491231235959Z", &[])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_roots_and_pins() {
+        let sgx = make_der_cert("180521104550Z", "20491231235959Z", &[]);
+        let other_sgx = make_der_cert("190521104550Z", "20491231235959Z", &[]);
+        let gce = make_der_cert("220101000000Z", "20470101000000Z", &[]);
+
+        let anchors = TrustAnchors::new()
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, &sgx)
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, &other_sgx)
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, &sgx)
+            .with_anchor(TrustAnchorKind::GceTcbRoot, &gce);
+        assert_eq!(anchors.anchors().len(), 3);
+        assert_eq!(anchors.roots(TrustAnchorKind::IntelSgxRoot).count(), 2);
+        assert_eq!(anchors.roots(TrustAnchorKind::GceTcbRoot).count(), 1);
+        assert!(!anchors.has_roots(TrustAnchorKind::AzureRoot));
+
+        let fingerprint = anchors.anchors()[1].fingerprint();
+        let pinned = anchors.with_pin(fingerprint);
+        let roots: Vec<_> = pinned.roots(TrustAnchorKind::IntelSgxRoot).collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].der, other_sgx);
+        assert!(!pinned.has_roots(TrustAnchorKind::GceTcbRoot));
+    }
+
+    #[test]
+    fn test_verify_any() {
+        let anchors = TrustAnchors::new()
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, &[1])
+            .with_anchor(TrustAnchorKind::IntelSgxRoot, &[2]);
+
+        let result = anchors.verify_any(TrustAnchorKind::IntelSgxRoot, |a| Ok(a.der == [2]));
+        assert!(matches!(result, Some(Ok(true))));
+
+        let result = anchors.verify_any(TrustAnchorKind::IntelSgxRoot, |_| Ok(false));
+        assert!(matches!(result, Some(Ok(false))));
+
+        let result = anchors.verify_any(TrustAnchorKind::IntelSgxRoot, |_| {
+            Err(Error::VerificationError("bad root".to_string()))
+        });
+        assert!(matches!(result, Some(Err(_))));
+
+        assert!(
+            anchors
+                .verify_any(TrustAnchorKind::AzureRoot, |_| Ok(true))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_expiry_warnings() {
+        let anchors = TrustAnchors::new()
+            .with_anchor(
+                TrustAnchorKind::IntelSgxRoot,
+                &make_der_cert("180521104550Z", "20491231235959Z", &[]),
+            )
+            .with_anchor(
+                TrustAnchorKind::GceTcbRoot,
+                &make_der_cert("220101000000Z", "20231201000000Z", &[]),
+            )
+            .with_anchor(
+                TrustAnchorKind::AzureRoot,
+                &make_der_cert("200101000000Z", "20230101000000Z", &[]),
+            )
+            .with_anchor(TrustAnchorKind::AzureRoot, &[1, 2, 3]);
+
+        let warnings = anchors.expiry_warnings(NOW, 30 * 86400);
+        assert_eq!(
+            warnings,
+            vec![
+                "gce-tcb-root trust anchor gce-tcb-root expires in 16 days",
+                "azure-root trust anchor azure-root has expired",
+                "azure-root trust anchor azure-root has no parsable validity period",
+            ]
+        );
+        assert_eq!(anchors.expiry_warnings(NOW, 86400).len(), 2);
+    }
+
+    #[test]
+    fn test_load_dir() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-anchors-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let sgx = make_der_cert("180521104550Z", "20491231235959Z", &[]);
+        let gce = make_der_cert("220101000000Z", "20470101000000Z", &[]);
+        std::fs::write(dir.join("root_ca.der"), &sgx)?;
+        std::fs::write(dir.join("GCE-cc-tcb-root_1.crt"), &gce)?;
+        std::fs::write(dir.join("tsa_root.der"), &gce)?;
+        std::fs::write(dir.join("fulcio_root_v1.pem.bak"), &gce)?;
+        std::fs::write(dir.join("fulcio_root_v1.crt"), &gce)?;
+        std::fs::write(dir.join("tcb_info.json"), "{}")?;
+        std::fs::write(dir.join("tcb_signing_chain.pem"), "not an anchor")?;
+        std::fs::write(
+            dir.join("azure_roots.pem"),
+            format!(
+                "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+                base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &sgx)
+            ),
+        )?;
+
+        let anchors = TrustAnchors::from_dir(&dir)?;
+        let kinds: Vec<_> = anchors
+            .anchors()
+            .iter()
+            .map(|a| (a.kind, a.name.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (TrustAnchorKind::GceTcbRoot, "GCE-cc-tcb-root_1.crt"),
+                (TrustAnchorKind::AzureRoot, "azure_roots.pem"),
+                (TrustAnchorKind::FulcioRoot, "fulcio_root_v1.crt"),
+                (TrustAnchorKind::IntelSgxRoot, "root_ca.der"),
+                (TrustAnchorKind::TsaRoot, "tsa_root.der"),
+            ]
+        );
+        assert_eq!(anchors.anchors()[1].der, sgx);
+
+        std::fs::write(dir.join("azure_roots.pem"), "malformed")?;
+        assert!(TrustAnchors::from_dir(&dir).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        assert!(TrustAnchors::from_dir(&dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_file() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-anchor-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        // regional roots, whatever their file names
+        let global = make_der_cert("180521104550Z", "20491231235959Z", &[]);
+        let regional = make_der_cert("220101000000Z", "20470101000000Z", &[]);
+        std::fs::write(dir.join("global.der"), &global)?;
+        std::fs::write(dir.join("region-cn.cer"), &regional)?;
+
+        let mut anchors = TrustAnchors::new();
+        for file in ["global.der", "region-cn.cer"] {
+            anchors.load_file(TrustAnchorKind::IntelSgxRoot, dir.join(file))?;
+        }
+        let roots: Vec<_> = anchors
+            .roots(TrustAnchorKind::IntelSgxRoot)
+            .map(|root| (root.name.as_str(), &root.der))
+            .collect();
+        assert_eq!(
+            roots,
+            vec![("global.der", &global), ("region-cn.cer", &regional)]
+        );
+
+        // the first root that verifies the evidence is used
+        let verified = anchors.verify_any(TrustAnchorKind::IntelSgxRoot, |root| {
+            Ok(root.der == regional)
+        });
+        assert!(matches!(verified, Some(Ok(true))));
+
+        assert!(
+            anchors
+                .load_file(TrustAnchorKind::IntelSgxRoot, dir.join("missing.der"))
+                .is_err()
+        );
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
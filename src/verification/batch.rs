@@ -0,0 +1,228 @@
+//! # Batch Quote Verification
+//!
+//! Relying parties appraising the quotes of thousands of TDs per minute can
+//! verify them in batches: `verify_quotes()` appraises each quote of a batch
+//! against a policy in parallel (on the thread pool of the `parallel`
+//! module), sharing the batch's
+//! `Collateral` between them:
+//! - the trusted Intel SGX root certificates, parsed once, and
+//! - a `VerificationCache`, so that the PCK certificate chain of each
+//!   platform, and the signatures of the TCB Info and QE Identity, are only
+//!   verified once per batch (or once across batches, with a shared cache).
+//!
+//! Each quote gets its own verdict, with the quote-level checks of
+//! `Bundle::verify()`: `quote-signature`, `tcb`, `tdx-module`,
+//! `qe-identity`, `debug`, `servtd`, `reference-values` and `allow-list`.
+//! Bare quotes carry no nonce or event logs, so the checks on those are
+//! left to the caller (e.g., comparing each quote's report data).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Policy;
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//! use tdx_workload_attestation::verification::batch::{Collateral, verify_quotes};
+//!
+//! let policy = Policy::from_file("policy.toml")
+//!     .unwrap()
+//!     .with_collateral_dir("/etc/tdx-workload-attestation")
+//!     .unwrap();
+//! let collateral = Collateral::from_policy(&policy).unwrap();
+//!
+//! let quotes = vec![Quote::from_bytes(&std::fs::read("quote.dat").unwrap()).unwrap()];
+//! for (i, verdict) in verify_quotes(&quotes, &collateral, &policy).iter().enumerate() {
+//!     match verdict {
+//!         Ok(verdict) => println!("Quote {}: passed: {}", i, verdict.passed()),
+//!         Err(e) => println!("Quote {}: {}", i, e),
+//!     }
+//! }
+//! ```
+
+use crate::error::Result;
+use crate::evidence::cache::VerificationCache;
+use crate::evidence::quote::Quote;
+use crate::evidence::{
+    Policy, Verdict, appraise_measurements, appraise_platform, appraise_td, cached_pck_chain,
+};
+use crate::trust::TrustAnchorKind;
+use crate::verification::quote::{verify_cert_chain, verify_quote_with_pck};
+use crate::verification::x509::x509_from_der_bytes;
+
+use openssl::x509::X509;
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The collateral shared by the quotes of a batch.
+#[derive(Clone)]
+pub struct Collateral {
+    // the trusted roots, with their DER encoding
+    roots: Vec<(Vec<u8>, X509)>,
+    cache: Arc<VerificationCache>,
+}
+
+impl Collateral {
+    /// Parses the Intel SGX roots trusted by `policy`, sharing its
+    /// verification cache, if any, or else a new one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::OpenSslError` if a root certificate cannot be
+    /// parsed.
+    pub fn from_policy(policy: &Policy) -> Result<Self> {
+        let roots = policy
+            .trust_anchors
+            .roots(TrustAnchorKind::IntelSgxRoot)
+            .map(|root| Ok((root.der.clone(), x509_from_der_bytes(&root.der)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            roots,
+            cache: policy.verification_cache.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Sets the verification cache shared by the quotes, e.g., to share it
+    /// across batches.
+    pub fn with_verification_cache(mut self, cache: Arc<VerificationCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Returns the verification cache shared by the quotes.
+    pub fn verification_cache(&self) -> &Arc<VerificationCache> {
+        &self.cache
+    }
+
+    /// Verifies the signature chain of `quote` up to any of the roots.
+    fn verify_signature(&self, quote: &Quote, pck_chain: &[Vec<u8>], now: u64) -> Result<bool> {
+        let chain = pck_chain
+            .iter()
+            .map(|der| x509_from_der_bytes(der))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut result = Ok(false);
+        for (der, root) in &self.roots {
+            result = self
+                .cache
+                .verify_chain(pck_chain, der, now, || verify_cert_chain(&chain, root, now));
+            if matches!(result, Ok(true)) {
+                return verify_quote_with_pck(quote, &chain[0]);
+            }
+        }
+        result
+    }
+}
+
+/// Appraises each of `quotes` against `policy` in parallel, sharing
+/// `collateral`, and returns their verdicts in order.
+///
+/// Every appraisal is recorded in the `metrics` module.
+///
+/// # Errors
+///
+/// A quote's result is an error if its PCK chain cannot be parsed, or
+/// retrieved from the policy's PCK cache. Failed checks are reported in its
+/// verdict instead.
+pub fn verify_quotes(
+    quotes: &[Quote],
+    collateral: &Collateral,
+    policy: &Policy,
+) -> Vec<Result<Verdict>> {
+    // the TCB Info and QE Identity are verified once for the batch
+    let policy = policy
+        .clone()
+        .with_verification_cache(collateral.cache.clone());
+
+    crate::verification::parallel::install(|| {
+        quotes
+            .par_iter()
+            .map(|quote| {
+                let start = Instant::now();
+                let verdict = appraise_quote(quote, collateral, &policy);
+                crate::metrics::global().record_verification(start.elapsed(), &verdict);
+                verdict
+            })
+            .collect()
+    })
+}
+
+/// Performs the quote-level checks of `Bundle::verify()` on `quote`.
+fn appraise_quote(quote: &Quote, collateral: &Collateral, policy: &Policy) -> Result<Verdict> {
+    let pck_chain = cached_pck_chain(quote, policy)?;
+    let mut verdict = Verdict::default();
+
+    let verified = match collateral.roots.is_empty() {
+        true => None,
+        false => Some(
+            policy
+                .verification_time()
+                .and_then(|now| collateral.verify_signature(quote, &pck_chain, now)),
+        ),
+    };
+    match verified {
+        Some(Ok(true)) => verdict.pass("quote-signature"),
+        Some(Ok(false)) => verdict.fail("quote-signature", "Invalid quote signature chain"),
+        Some(Err(e)) => verdict.fail("quote-signature", &e.to_string()),
+        None => verdict.fail("quote-signature", "No trusted root certificate configured"),
+    }
+
+    appraise_platform(quote, &pck_chain, policy, &mut verdict);
+    appraise_td(&quote.body, policy, &mut verdict);
+    appraise_measurements(&quote.body, policy, &mut verdict);
+
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::trust::TrustAnchors;
+    use crate::verification::quote::tests::TestSigner;
+
+    #[test]
+    fn test_verify_quotes() -> Result<()> {
+        let signer = TestSigner::new();
+        let root = signer.root.to_der().unwrap();
+        let policy = Policy::default().with_trust_anchors(
+            TrustAnchors::new().with_anchor(TrustAnchorKind::IntelSgxRoot, &root),
+        );
+        let collateral = Collateral::from_policy(&policy)?;
+
+        let mut tampered = signer.sign_quote(QuoteParts::default());
+        tampered[100] ^= 1;
+        let quotes = vec![
+            Quote::from_bytes(&signer.sign_quote(QuoteParts::default()))?,
+            Quote::from_bytes(&tampered)?,
+            Quote::from_bytes(&signer.sign_quote(QuoteParts::default()))?,
+        ];
+
+        let verdicts = verify_quotes(&quotes, &collateral, &policy);
+        assert_eq!(verdicts.len(), 3);
+        let signed = |i: usize| {
+            verdicts[i]
+                .as_ref()
+                .unwrap()
+                .check("quote-signature")
+                .unwrap()
+                .passed
+        };
+        assert!(signed(0));
+        assert!(!signed(1));
+        assert!(signed(2));
+
+        // the platform's PCK chain isn't verified again in later batches
+        let misses = collateral.verification_cache().stats().misses;
+        verify_quotes(&quotes, &collateral, &policy);
+        assert_eq!(collateral.verification_cache().stats().misses, misses);
+
+        // without roots, no quote is verified
+        let verdicts = verify_quotes(
+            &quotes[..1],
+            &Collateral::from_policy(&Policy::default())?,
+            &policy,
+        );
+        assert!(!verdicts[0].as_ref().unwrap().passed());
+        Ok(())
+    }
+}
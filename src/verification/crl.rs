@@ -0,0 +1,351 @@
+//! # Certificate Revocation Lists
+//!
+//! This module checks certificate chains against the certificate revocation
+//! lists (CRLs) of their issuers, e.g., the PCK chain of a quote against the
+//! CRLs of Intel's PCK Platform (or Processor) CA and of the Intel SGX Root CA,
+//! and, with the `pck-retrieval` feature, retrieves the CRLs of a chain from
+//! the CRL distribution points of its certificates.
+//!
+//! A chain passes if the issuer of each of its certificates has a CRL that
+//! is signed by the issuer and current at the verification time, and none of
+//! the issuer's CRLs lists the certificate. `Bundle::verify()` checks the PCK
+//! chain and the TCB signing chain this way, as its `crl` check, if the
+//! policy has CRLs (see `Policy::with_crl()`).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::verification::crl::check_chain;
+//!
+//! let pck_chain = vec![std::fs::read("pck.der").unwrap(), std::fs::read("platform_ca.der").unwrap()];
+//! let root = std::fs::read("root_ca.der").unwrap();
+//! // e.g., from https://api.trustedservices.intel.com/sgx/certification/v4/pckcrl?ca=platform
+//! let crls = vec![std::fs::read("pck_crl.der").unwrap(), std::fs::read("root_ca_crl.der").unwrap()];
+//!
+//! match check_chain(&pck_chain, &root, &crls, 1_700_000_000) {
+//!     Ok(()) => println!("No certificate is revoked"),
+//!     Err(e) => println!("Revocation check failed: {}", e),
+//! }
+//! ```
+
+use crate::error::{Error, ErrorKind, Result};
+#[cfg(feature = "pck-retrieval")]
+use crate::retry::RetryPolicy;
+use crate::verification::x509::{get_x509_pubkey, x509_from_der_bytes};
+
+use openssl::asn1::Asn1Time;
+use openssl::x509::{CrlStatus, X509, X509Crl, X509NameRef};
+
+/// Parses a DER- or PEM-encoded CRL.
+///
+/// # Errors
+///
+/// Returns an `Error::CertificateError` of kind `ErrorKind::Parse` if the
+/// CRL cannot be parsed.
+pub fn parse_crl(crl: &[u8]) -> Result<X509Crl> {
+    let parsed = match crl.starts_with(b"-----BEGIN") {
+        true => X509Crl::from_pem(crl),
+        false => X509Crl::from_der(crl),
+    };
+    parsed.map_err(|source| Error::CertificateError {
+        kind: ErrorKind::Parse,
+        source,
+    })
+}
+
+/// Returns the URIs of the CRL distribution points of `cert`.
+pub fn distribution_points(cert: &X509) -> Vec<String> {
+    let Some(points) = cert.crl_distribution_points() else {
+        return vec![];
+    };
+    points
+        .iter()
+        .filter_map(|point| point.distpoint()?.fullname())
+        .flat_map(|names| names.iter().filter_map(|name| name.uri()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Checks that no certificate of the DER-encoded `chain` (which may or may
+/// not end with `root`) is revoked by the DER- or PEM-encoded `crls` of its
+/// issuer, at `unix_time` (in seconds since the Unix epoch).
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if a certificate is revoked, or its issuer
+///   has no CRL that is validly signed and current at `unix_time`.
+/// - `Error::CertificateError` if a certificate or CRL cannot be parsed.
+pub fn check_chain(chain: &[Vec<u8>], root: &[u8], crls: &[Vec<u8>], unix_time: u64) -> Result<()> {
+    let mut certs = chain
+        .iter()
+        .filter(|der| der.as_slice() != root)
+        .map(|der| x509_from_der_bytes(der))
+        .collect::<Result<Vec<_>>>()?;
+    certs.push(x509_from_der_bytes(root)?);
+    let crls = crls
+        .iter()
+        .map(|crl| parse_crl(crl))
+        .collect::<Result<Vec<_>>>()?;
+    let now = i64::try_from(unix_time)
+        .ok()
+        .and_then(|time| Asn1Time::from_unix(time).ok())
+        .ok_or_else(|| Error::VerificationError("Verification time out of range".to_string()))?;
+
+    for pair in certs.windows(2) {
+        let (cert, issuer) = (&pair[0], &pair[1]);
+        let issuer_key = get_x509_pubkey(issuer)?;
+
+        let mut current = 0;
+        for crl in crls
+            .iter()
+            .filter(|crl| same_name(crl.issuer_name(), issuer.subject_name()).unwrap_or(false))
+        {
+            let valid = crl.verify(&issuer_key).unwrap_or(false)
+                && crl.last_update() <= now
+                && crl.next_update().is_some_and(|next| next > now);
+            if !valid {
+                continue;
+            }
+            current += 1;
+            if !matches!(
+                crl.get_by_serial(cert.serial_number()),
+                CrlStatus::NotRevoked
+            ) {
+                return Err(Error::VerificationError(format!(
+                    "Certificate {} is revoked",
+                    common_name(cert.subject_name())
+                )));
+            }
+        }
+        if current == 0 {
+            return Err(Error::VerificationError(format!(
+                "No current CRL of {}",
+                common_name(issuer.subject_name())
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Retrieves the DER-encoded CRLs of the certificates of the DER-encoded
+/// `chain` from their CRL distribution points, with `retry_policy`.
+///
+/// # Errors
+///
+/// Returns an `Error::NetworkError` if a CRL cannot be retrieved after
+/// exhausting the `RetryPolicy`, or an `Error::CertificateError` if a
+/// certificate or CRL cannot be parsed.
+#[cfg(feature = "pck-retrieval")]
+pub fn fetch_crls(chain: &[Vec<u8>], retry_policy: &RetryPolicy) -> Result<Vec<Vec<u8>>> {
+    use crate::http::{http_client, send_with_headers};
+
+    let client = http_client(retry_policy)?;
+    let mut urls = vec![];
+    for der in chain {
+        for url in distribution_points(&x509_from_der_bytes(der)?) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+
+    let mut crls = vec![];
+    for url in urls {
+        let crl = retry_policy.run(|| Ok(send_with_headers(client.get(&url))?.0))?;
+        // keep the CRL in DER, however the distribution point encodes it
+        let der = parse_crl(&crl)?.to_der().map_err(Error::OpenSslError)?;
+        crls.push(der);
+    }
+    Ok(crls)
+}
+
+/// Returns whether two names are equal.
+fn same_name(a: &X509NameRef, b: &X509NameRef) -> Result<bool> {
+    Ok(a.to_der().map_err(Error::OpenSslError)? == b.to_der().map_err(Error::OpenSslError)?)
+}
+
+/// Returns the common name in `name`, for error messages.
+fn common_name(name: &X509NameRef) -> String {
+    name.entries_by_nid(openssl::nid::Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().to_string().ok())
+        .unwrap_or_else(|| "(unnamed)".to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKeyRef, Private};
+    use openssl::sign::Signer;
+
+    /// Builds a DER TLV.
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut der = vec![tag];
+        if contents.len() < 0x80 {
+            der.push(contents.len() as u8);
+        } else if contents.len() < 0x100 {
+            der.extend([0x81, contents.len() as u8]);
+        } else {
+            der.push(0x82);
+            der.extend((contents.len() as u16).to_be_bytes());
+        }
+        der.extend(contents);
+        der
+    }
+
+    /// Encodes `unix_time` as a DER `GeneralizedTime`.
+    fn generalized_time(unix_time: u64) -> Vec<u8> {
+        // the civil date of the day, per Howard Hinnant's algorithm
+        let days = (unix_time / 86400) as i64 + 719468;
+        let era = days / 146097;
+        let doe = days - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let secs = unix_time % 86400;
+        let time = format!(
+            "{:04}{:02}{:02}{:02}{:02}{:02}Z",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+        tlv(0x18, time.as_bytes())
+    }
+
+    /// Builds a DER-encoded CRL of `issuer`, signed by `key` with ECDSA and
+    /// SHA-256, current from `this_update` to `next_update`, which revokes
+    /// `revoked`.
+    pub(crate) fn make_crl(
+        issuer: &X509,
+        key: &PKeyRef<Private>,
+        revoked: &[&X509],
+        this_update: u64,
+        next_update: u64,
+    ) -> Vec<u8> {
+        // ecdsa-with-SHA256
+        let algorithm = tlv(
+            0x30,
+            &tlv(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02]),
+        );
+
+        let mut tbs = [
+            tlv(0x02, &[1]),
+            algorithm.clone(),
+            issuer.subject_name().to_der().unwrap(),
+            generalized_time(this_update),
+            generalized_time(next_update),
+        ]
+        .concat();
+        if !revoked.is_empty() {
+            let entries: Vec<u8> = revoked
+                .iter()
+                .flat_map(|cert| {
+                    let serial = cert.serial_number().to_bn().unwrap().to_vec();
+                    let serial = match serial.first() {
+                        Some(b) if *b < 0x80 => serial,
+                        _ => [&[0][..], &serial].concat(),
+                    };
+                    tlv(
+                        0x30,
+                        &[tlv(0x02, &serial), generalized_time(this_update)].concat(),
+                    )
+                })
+                .collect();
+            tbs.extend(tlv(0x30, &entries));
+        }
+        let tbs = tlv(0x30, &tbs);
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(&tbs).unwrap();
+        let signature = [&[0][..], &signer.sign_to_vec().unwrap()].concat();
+
+        tlv(0x30, &[tbs, algorithm, tlv(0x03, &signature)].concat())
+    }
+
+    // The time at which the CRLs are checked
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_check_chain() -> Result<()> {
+        use crate::verification::quote::tests::TestSigner;
+
+        let signer = TestSigner::new();
+        let (pck, _) = signer.issue("Test PCK");
+        let chain = vec![pck.clone(), signer.root.to_der().unwrap()];
+        let root = signer.root.to_der().unwrap();
+        let pck = x509_from_der_bytes(&pck)?;
+        let crl = |revoked: &[&X509], this_update, next_update| {
+            signer.crl(revoked, this_update, next_update)
+        };
+
+        // not revoked, whether or not the chain includes the root
+        let crls = vec![crl(&[], NOW - 10, NOW + 10)];
+        check_chain(&chain, &root, &crls, NOW)?;
+        check_chain(&chain[..1], &root, &crls, NOW)?;
+
+        // PEM-encoded CRLs are accepted too
+        let pem = parse_crl(&crls[0])?.to_pem().unwrap();
+        assert_eq!(parse_crl(&pem)?.to_der().unwrap(), crls[0]);
+
+        // revoked
+        let crls = vec![
+            crl(&[], NOW - 10, NOW + 10),
+            crl(&[&pck], NOW - 10, NOW + 10),
+        ];
+        let e = check_chain(&chain, &root, &crls, NOW).unwrap_err();
+        assert!(e.to_string().contains("Test PCK is revoked"), "{}", e);
+
+        // a stale CRL, or none, isn't current
+        for crls in [vec![crl(&[], NOW - 10, NOW - 1)], vec![]] {
+            let e = check_chain(&chain, &root, &crls, NOW).unwrap_err();
+            assert!(
+                e.to_string().contains("No current CRL of Test Root CA"),
+                "{}",
+                e
+            );
+        }
+
+        // a CRL that isn't signed by the issuer is ignored
+        let other = TestSigner::new();
+        let crls = vec![other.crl(&[], NOW - 10, NOW + 10)];
+        assert!(check_chain(&chain, &root, &crls, NOW).is_err());
+
+        assert!(parse_crl(b"not a CRL").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_points() {
+        use crate::verification::quote::tests::TestSigner;
+        use openssl::asn1::{Asn1Object, Asn1OctetString};
+        use openssl::x509::X509Extension;
+
+        let root = TestSigner::new().root;
+        assert!(distribution_points(&root).is_empty());
+
+        let url = "https://certificates.example.com/root.crl";
+        let points = tlv(
+            0x30,
+            &tlv(0x30, &tlv(0xa0, &tlv(0xa0, &tlv(0x86, url.as_bytes())))),
+        );
+        let mut cert = X509::builder().unwrap();
+        cert.append_extension(
+            X509Extension::new_from_der(
+                &Asn1Object::from_str("2.5.29.31").unwrap(),
+                false,
+                &Asn1OctetString::new_from_bytes(&points).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(distribution_points(&cert.build()), vec![url]);
+    }
+}
@@ -0,0 +1,644 @@
+//! # Intel Trust Authority (ITA) Verification
+//!
+//! This module implements a client for Intel Trust Authority, Intel's
+//! attestation verification service, as an alternative to local DCAP quote
+//! verification (see the `quote` module).
+//!
+//! The client authenticates to the ITA API with an API key, submits the TD's
+//! evidence (see the `evidence::interop::TrustAuthorityEvidence` type) for
+//! appraisal, and validates the returned attestation token, a JWT signed by
+//! ITA, against the signing keys published in ITA's JSON Web Key Set (JWKS).
+//!
+//! To bind an ITA verifier nonce into the quote, the TD's `report_data` must
+//! be the SHA-512 digest of the nonce's value and issue time followed by the
+//! runtime data (i.e., a bundle's nonce, see `report_data_for_nonce()`).
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::evidence::Bundle;
+//! use tdx_workload_attestation::evidence::interop::TrustAuthorityEvidence;
+//! use tdx_workload_attestation::verification::ita::ItaClient;
+//!
+//! let client = ItaClient::new("my-api-key").with_policy_ids(&["my-policy-id"]);
+//!
+//! // Bind ITA's verifier nonce and the runtime data into the quote
+//! let verifier_nonce = client.get_nonce().unwrap();
+//! let runtime_data = b"workload public key";
+//! let nonce = [&verifier_nonce.val[..], &verifier_nonce.iat, runtime_data].concat();
+//! let bundle = Bundle::collect(&nonce, None).unwrap();
+//!
+//! let evidence = TrustAuthorityEvidence {
+//!     runtime_data: runtime_data.to_vec(),
+//!     verifier_nonce: Some(verifier_nonce),
+//!     ..TrustAuthorityEvidence::from_bundle(&bundle)
+//! };
+//! let token = client.appraise(&evidence).unwrap();
+//! println!("TCB status: {:?}", token.claims.tdx.map(|tdx| tdx.attester_tcb_status));
+//! ```
+//!
+//! # Notes
+//! - Only RSA token signing keys (`RS256`, `RS384`, `PS256` and `PS384`
+//!   tokens) are supported. ITA signs tokens with `PS384` by default.
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::evidence::interop::{TrustAuthorityEvidence, TrustAuthorityNonce};
+use crate::http::{http_client, send};
+use crate::retry::RetryPolicy;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use openssl::rsa::{Padding, Rsa};
+use openssl::sign::{RsaPssSaltlen, Verifier};
+use reqwest::blocking::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// The default ITA API URL.
+pub const DEFAULT_API_URL: &str = "https://api.trustauthority.intel.com";
+
+/// The default URL of ITA's token signing JWKS.
+pub const DEFAULT_JWKS_URL: &str = "https://portal.trustauthority.intel.com/certs";
+
+// The ITA API endpoints, relative to the API URL
+const NONCE_PATH: &str = "/appraisal/v1/nonce";
+const ATTEST_PATH: &str = "/appraisal/v1/attest";
+
+// The header carrying the ITA API key
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// A key of a JSON Web Key Set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    /// The key type (e.g., `"RSA"`).
+    pub kty: String,
+    /// The key ID.
+    #[serde(default)]
+    pub kid: String,
+    /// The signature algorithm the key is used with, if restricted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    /// The base64url-encoded RSA modulus.
+    #[serde(default)]
+    pub n: String,
+    /// The base64url-encoded RSA public exponent.
+    #[serde(default)]
+    pub e: String,
+}
+
+/// A JSON Web Key Set, i.e., the token signing keys published by ITA.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwks {
+    /// The keys.
+    pub keys: Vec<Jwk>,
+}
+
+impl Jwks {
+    /// Decodes a JWKS from its JSON encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the JSON is malformed.
+    pub fn from_json(json: &[u8]) -> Result<Self> {
+        serde_json::from_slice(json).map_err(|e| Error::ParseError(format!("Invalid JWKS: {}", e)))
+    }
+
+    /// Returns the key with the given ID, if any.
+    pub fn key(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|k| k.kid == kid)
+    }
+}
+
+/// The TDX claims of an ITA attestation token.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TdxClaims {
+    /// The hex-encoded MRTD.
+    pub tdx_mrtd: String,
+    /// The hex-encoded RTMR0.
+    pub tdx_rtmr0: String,
+    /// The hex-encoded RTMR1.
+    pub tdx_rtmr1: String,
+    /// The hex-encoded RTMR2.
+    pub tdx_rtmr2: String,
+    /// The hex-encoded RTMR3.
+    pub tdx_rtmr3: String,
+    /// The hex-encoded `report_data`.
+    pub tdx_report_data: String,
+    /// Whether the TD is debuggable.
+    pub tdx_is_debuggable: bool,
+    /// The TCB status of the platform (e.g., `"UpToDate"`).
+    pub attester_tcb_status: String,
+    /// The remaining claims.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The claims of an ITA attestation token.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TokenClaims {
+    /// The issuer of the token.
+    pub iss: String,
+    /// The time the token was issued at, in seconds since the Unix epoch.
+    pub iat: Option<u64>,
+    /// The time the token becomes valid, in seconds since the Unix epoch.
+    pub nbf: Option<u64>,
+    /// The time the token expires, in seconds since the Unix epoch.
+    pub exp: Option<u64>,
+    /// The TDX claims, if the token appraises a TD.
+    pub tdx: Option<TdxClaims>,
+    /// The IDs of the appraisal policies that matched, if any.
+    pub policy_ids_matched: Vec<Value>,
+    /// The IDs of the appraisal policies that didn't match, if any.
+    pub policy_ids_unmatched: Vec<Value>,
+    /// The remaining claims.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// A validated ITA attestation token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttestationToken {
+    /// The encoded token (JWT).
+    pub token: String,
+    /// The token's claims.
+    pub claims: TokenClaims,
+}
+
+/// The header of a JWT.
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: String,
+}
+
+/// An ITA attestation request.
+#[derive(Debug, Serialize)]
+struct AttestRequest<'a> {
+    #[serde(flatten)]
+    evidence: &'a TrustAuthorityEvidence,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    policy_ids: &'a [String],
+}
+
+/// An ITA attestation response.
+#[derive(Debug, Deserialize)]
+struct AttestResponse {
+    token: String,
+}
+
+/// A client for the Intel Trust Authority API.
+#[derive(Clone, Debug)]
+pub struct ItaClient {
+    api_key: String,
+    api_url: String,
+    jwks_url: String,
+    policy_ids: Vec<String>,
+    retry_policy: RetryPolicy,
+    clock: Arc<dyn Clock>,
+}
+
+impl ItaClient {
+    /// Creates a new client for the default ITA API, authenticating with
+    /// `api_key`.
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            api_url: DEFAULT_API_URL.to_string(),
+            jwks_url: DEFAULT_JWKS_URL.to_string(),
+            policy_ids: vec![],
+            retry_policy: RetryPolicy::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the ITA API URL (e.g., for a regional ITA deployment).
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the URL of ITA's token signing JWKS.
+    pub fn with_jwks_url(mut self, jwks_url: &str) -> Self {
+        self.jwks_url = jwks_url.to_string();
+        self
+    }
+
+    /// Sets the IDs of the ITA appraisal policies to appraise evidence
+    /// against.
+    pub fn with_policy_ids(mut self, policy_ids: &[&str]) -> Self {
+        self.policy_ids = policy_ids.iter().map(|id| id.to_string()).collect();
+        self
+    }
+
+    /// Sets the retry policy for requests to ITA.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the clock at which tokens are checked for expiry (the
+    /// `SystemClock` by default).
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Requests a verifier nonce from ITA.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if the nonce cannot be retrieved after
+    ///   exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the response cannot be parsed.
+    pub fn get_nonce(&self) -> Result<TrustAuthorityNonce> {
+        let client = http_client(&self.retry_policy)?;
+        let url = format!("{}{}", self.api_url, NONCE_PATH);
+        let resp = self
+            .retry_policy
+            .run(|| send(self.authenticate(client.get(&url))))?;
+
+        serde_json::from_slice(&resp)
+            .map_err(|e| Error::ParseError(format!("Invalid ITA nonce: {}", e)))
+    }
+
+    /// Submits `evidence` to ITA for appraisal, and returns the (not yet
+    /// validated) attestation token.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if ITA rejects the evidence, or cannot be
+    ///   reached after exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the response cannot be parsed.
+    pub fn attest(&self, evidence: &TrustAuthorityEvidence) -> Result<String> {
+        let client = http_client(&self.retry_policy)?;
+        let url = format!("{}{}", self.api_url, ATTEST_PATH);
+        let body = serde_json::to_vec(&AttestRequest {
+            evidence,
+            policy_ids: &self.policy_ids,
+        })
+        .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        let resp = self.retry_policy.run(|| {
+            send(
+                self.authenticate(client.post(&url))
+                    .header("Content-Type", "application/json")
+                    .body(body.clone()),
+            )
+        })?;
+
+        let resp: AttestResponse = serde_json::from_slice(&resp)
+            .map_err(|e| Error::ParseError(format!("Invalid ITA response: {}", e)))?;
+        Ok(resp.token)
+    }
+
+    /// Downloads ITA's token signing JWKS.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if the JWKS cannot be retrieved after
+    ///   exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the JWKS cannot be parsed.
+    pub fn get_jwks(&self) -> Result<Jwks> {
+        let client = http_client(&self.retry_policy)?;
+        let resp = self.retry_policy.run(|| {
+            send(
+                client
+                    .get(&self.jwks_url)
+                    .header("Accept", "application/json"),
+            )
+        })?;
+
+        Jwks::from_json(&resp)
+    }
+
+    /// Validates an attestation token against ITA's current JWKS, at the
+    /// client's clock.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS cannot be retrieved, or if the token
+    /// isn't valid (see `verify_token()`).
+    pub fn verify_token(&self, token: &str) -> Result<AttestationToken> {
+        verify_token(token, &self.get_jwks()?, self.clock.now()?)
+    }
+
+    /// Submits `evidence` to ITA for appraisal, and returns the validated
+    /// attestation token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the evidence cannot be appraised (see `attest()`)
+    /// or the token cannot be validated (see `verify_token()`).
+    pub fn appraise(&self, evidence: &TrustAuthorityEvidence) -> Result<AttestationToken> {
+        let token = self.attest(evidence)?;
+        self.verify_token(&token)
+    }
+
+    /// Adds the API key to an ITA API request.
+    fn authenticate(&self, req: RequestBuilder) -> RequestBuilder {
+        req.header(API_KEY_HEADER, &self.api_key)
+            .header("Accept", "application/json")
+    }
+}
+
+/// Validates an ITA attestation token at `unix_time`, given ITA's token
+/// signing JWKS, and returns its claims.
+///
+/// # Errors
+///
+/// - `Error::ParseError` if the token is malformed.
+/// - `Error::NotSupported` if the token's signature algorithm isn't
+///   supported.
+/// - `Error::SignatureError` if the signing key is malformed.
+/// - `Error::VerificationError` if the signing key isn't in the JWKS, the
+///   signature is invalid, or the token isn't valid at `unix_time`.
+pub fn verify_token(token: &str, jwks: &Jwks, unix_time: u64) -> Result<AttestationToken> {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, claims, signature] = parts[..] else {
+        return Err(Error::ParseError(
+            "Attestation token is not a JWT".to_string(),
+        ));
+    };
+
+    let header: JwtHeader = decode_json(header, "token header")?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| Error::ParseError(format!("Invalid token signature: {}", e)))?;
+
+    let key = jwks.key(&header.kid).ok_or_else(|| {
+        Error::VerificationError(format!("Unknown token signing key {}", header.kid))
+    })?;
+    if key.alg.as_ref().is_some_and(|alg| *alg != header.alg) {
+        return Err(Error::VerificationError(format!(
+            "Token signing key {} is not used with {}",
+            key.kid, header.alg
+        )));
+    }
+
+    let signing_input = &token[..token.rfind('.').unwrap_or_default()];
+    if !verify_signature(&header.alg, signing_input.as_bytes(), &signature, key)? {
+        return Err(Error::VerificationError(
+            "Invalid token signature".to_string(),
+        ));
+    }
+
+    let claims: TokenClaims = decode_json(claims, "token claims")?;
+    if claims.exp.is_some_and(|exp| unix_time >= exp) {
+        return Err(Error::VerificationError(
+            "Attestation token has expired".to_string(),
+        ));
+    }
+    if claims.nbf.is_some_and(|nbf| unix_time < nbf) {
+        return Err(Error::VerificationError(
+            "Attestation token is not yet valid".to_string(),
+        ));
+    }
+
+    Ok(AttestationToken {
+        token: token.to_string(),
+        claims,
+    })
+}
+
+/// Decodes a base64url-encoded JSON JWT segment.
+fn decode_json<T: for<'de> Deserialize<'de>>(segment: &str, what: &str) -> Result<T> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| Error::ParseError(format!("Invalid {}: {}", what, e)))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::ParseError(format!("Invalid {}: {}", what, e)))
+}
+
+/// Builds the RSA public key of a JWK.
+fn rsa_public_key(key: &Jwk) -> Result<PKey<Public>> {
+    if key.kty != "RSA" {
+        return Err(Error::NotSupported(format!(
+            "Token signing key type {} is not supported",
+            key.kty
+        )));
+    }
+
+    let component = |value: &str| {
+        URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|e| Error::SignatureError(format!("Invalid token signing key: {}", e)))
+            .and_then(|bytes| Ok(BigNum::from_slice(&bytes)?))
+    };
+    let rsa = Rsa::from_public_components(component(&key.n)?, component(&key.e)?)?;
+
+    Ok(PKey::from_rsa(rsa)?)
+}
+
+/// Verifies a JWS signature with the given algorithm.
+fn verify_signature(alg: &str, data: &[u8], signature: &[u8], key: &Jwk) -> Result<bool> {
+    let (digest, pss) = match alg {
+        "RS256" => (MessageDigest::sha256(), false),
+        "RS384" => (MessageDigest::sha384(), false),
+        "PS256" => (MessageDigest::sha256(), true),
+        "PS384" => (MessageDigest::sha384(), true),
+        _ => {
+            return Err(Error::NotSupported(format!(
+                "Token signature algorithm {} is not supported",
+                alg
+            )));
+        }
+    };
+
+    let public_key = rsa_public_key(key)?;
+    let mut verifier = Verifier::new(digest, &public_key)?;
+    if pss {
+        verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+        verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+        verifier.set_rsa_mgf1_md(digest)?;
+    }
+    verifier.update(data)?;
+
+    Ok(verifier.verify(signature).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+
+    const NOW: u64 = 1_700_000_000;
+
+    struct TestKey {
+        key: PKey<Private>,
+        jwks: Jwks,
+    }
+
+    impl TestKey {
+        fn new(alg: Option<&str>) -> Self {
+            let rsa = Rsa::generate(2048).unwrap();
+            let jwk = Jwk {
+                kty: "RSA".to_string(),
+                kid: "test-kid".to_string(),
+                alg: alg.map(str::to_string),
+                n: URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+                e: URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+            };
+            Self {
+                key: PKey::from_rsa(rsa).unwrap(),
+                jwks: Jwks { keys: vec![jwk] },
+            }
+        }
+
+        fn sign(&self, alg: &str, claims: &Value) -> String {
+            let header = serde_json::json!({"alg": alg, "kid": "test-kid", "typ": "JWT"});
+            let signing_input = format!(
+                "{}.{}",
+                URL_SAFE_NO_PAD.encode(header.to_string()),
+                URL_SAFE_NO_PAD.encode(claims.to_string())
+            );
+
+            let digest = match alg {
+                "RS256" | "PS256" => MessageDigest::sha256(),
+                _ => MessageDigest::sha384(),
+            };
+            let mut signer = Signer::new(digest, &self.key).unwrap();
+            if alg.starts_with("PS") {
+                signer.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+                signer
+                    .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+                    .unwrap();
+                signer.set_rsa_mgf1_md(digest).unwrap();
+            }
+            signer.update(signing_input.as_bytes()).unwrap();
+            let signature = signer.sign_to_vec().unwrap();
+
+            format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature))
+        }
+    }
+
+    fn tdx_claims() -> Value {
+        serde_json::json!({
+            "iss": "Intel Trust Authority",
+            "exp": NOW + 300,
+            "nbf": NOW - 60,
+            "tdx": {
+                "tdx_mrtd": "01".repeat(48),
+                "tdx_report_data": "02".repeat(64),
+                "attester_tcb_status": "UpToDate",
+                "tdx_is_debuggable": false,
+                "tdx_seamsvn": 3,
+            },
+            "policy_ids_matched": [{"id": "policy", "version": "v1"}],
+            "ver": "2.0.0",
+        })
+    }
+
+    #[test]
+    fn test_verify_token() -> Result<()> {
+        for alg in ["RS256", "RS384", "PS256", "PS384"] {
+            let key = TestKey::new(Some(alg));
+            let token = key.sign(alg, &tdx_claims());
+
+            let verified = verify_token(&token, &key.jwks, NOW)?;
+            assert_eq!(verified.token, token);
+            assert_eq!(verified.claims.iss, "Intel Trust Authority");
+            assert_eq!(verified.claims.policy_ids_matched.len(), 1);
+            assert_eq!(verified.claims.other["ver"], "2.0.0");
+
+            let tdx = verified.claims.tdx.unwrap();
+            assert_eq!(tdx.tdx_mrtd, "01".repeat(48));
+            assert_eq!(tdx.attester_tcb_status, "UpToDate");
+            assert_eq!(tdx.other["tdx_seamsvn"], 3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_token_invalid_signature() {
+        let key = TestKey::new(None);
+        let token = key.sign("PS384", &tdx_claims());
+
+        // tamper with the claims
+        let mut claims = tdx_claims();
+        claims["tdx"]["attester_tcb_status"] = "OutOfDate".into();
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let forged = key.sign("PS384", &claims);
+        let (forged_input, _) = forged.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", forged_input, signature);
+        let err = verify_token(&forged, &key.jwks, NOW).unwrap_err();
+        assert!(err.to_string().contains("Invalid token signature"));
+
+        // signed by another key with the same ID
+        let other = TestKey::new(None);
+        assert!(verify_token(&token, &other.jwks, NOW).is_err());
+    }
+
+    #[test]
+    fn test_verify_token_key_selection() {
+        let key = TestKey::new(Some("PS384"));
+
+        let mut jwks = key.jwks.clone();
+        jwks.keys[0].kid = "other-kid".to_string();
+        let token = key.sign("PS384", &tdx_claims());
+        let err = verify_token(&token, &jwks, NOW).unwrap_err();
+        assert!(err.to_string().contains("Unknown token signing key"));
+
+        let token = key.sign("RS256", &tdx_claims());
+        let err = verify_token(&token, &key.jwks, NOW).unwrap_err();
+        assert!(err.to_string().contains("is not used with RS256"));
+
+        let key = TestKey::new(None);
+        let token = key.sign("ES256", &tdx_claims());
+        assert!(
+            verify_token(&token, &key.jwks, NOW)
+                .unwrap_err()
+                .is_not_supported()
+        );
+    }
+
+    #[test]
+    fn test_verify_token_validity() {
+        let key = TestKey::new(None);
+        let token = key.sign("PS384", &tdx_claims());
+
+        let err = verify_token(&token, &key.jwks, NOW + 300).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+        let err = verify_token(&token, &key.jwks, NOW - 61).unwrap_err();
+        assert!(err.to_string().contains("not yet valid"));
+
+        assert!(verify_token("not-a-jwt", &key.jwks, NOW).is_err());
+        assert!(verify_token("a.b.c", &key.jwks, NOW).is_err());
+    }
+
+    #[test]
+    fn test_attest_request() {
+        let evidence = TrustAuthorityEvidence {
+            quote: vec![1, 2, 3],
+            runtime_data: b"runtime".to_vec(),
+            ..Default::default()
+        };
+        let policy_ids = vec!["policy".to_string()];
+
+        let request = serde_json::to_value(AttestRequest {
+            evidence: &evidence,
+            policy_ids: &policy_ids,
+        })
+        .unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({
+                "quote": "AQID",
+                "runtime_data": "cnVudGltZQ==",
+                "policy_ids": ["policy"],
+            })
+        );
+
+        let client = ItaClient::new("key").with_api_url("https://ita.example.com/");
+        assert_eq!(client.api_url, "https://ita.example.com");
+    }
+}
@@ -0,0 +1,343 @@
+//! # Cloud KMS Signers
+//!
+//! This module implements `Signer`s (see the `signature` module) for keys
+//! held in a cloud key management service, so that verifiers can sign the
+//! tokens they issue without ever holding the signing key:
+//! - `GcpKmsSigner` signs with a Google Cloud KMS asymmetric signing key
+//!   version, and
+//! - `AzureKeyVaultSigner` signs with an Azure Key Vault (or Managed HSM)
+//!   key.
+//!
+//! Both services sign a digest of the data, which the signers compute
+//! locally, so the data itself never leaves the verifier. Requests are
+//! authenticated with an OAuth2 access token, obtained by the caller (e.g.,
+//! from the instance metadata service of the verifier's VM).
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::verification::kms::GcpKmsSigner;
+//! use tdx_workload_attestation::verification::result::{JwtClaims, sign_jwt_with};
+//!
+//! let signer = GcpKmsSigner::new(
+//!     "projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1",
+//!     "ES256",
+//!     &access_token,
+//! )
+//! .unwrap();
+//! let token = sign_jwt_with(&verdict, &signer, &JwtClaims::new()).unwrap();
+//! ```
+//!
+//! # Notes
+//! - The `ES256`, `ES384`, `RS256` and `PS256` algorithms are supported, and
+//!   must match the algorithm of the KMS key.
+//! - Access tokens are short-lived, so long-running services should recreate
+//!   their signer when they refresh their token.
+
+use crate::error::{Error, Result};
+use crate::http::{http_client, send};
+use crate::retry::RetryPolicy;
+use crate::verification::signature::{Signer, der_to_raw_signature, jws_digest};
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use serde::Deserialize;
+use std::fmt;
+
+/// The default Google Cloud KMS API URL.
+pub const DEFAULT_GCP_KMS_URL: &str = "https://cloudkms.googleapis.com";
+
+/// The Azure Key Vault API version of the sign requests.
+pub const AZURE_KEY_VAULT_API_VERSION: &str = "7.4";
+
+/// A Google Cloud KMS asymmetric sign response.
+#[derive(Debug, Deserialize)]
+struct GcpSignResponse {
+    signature: String,
+}
+
+/// An Azure Key Vault sign response.
+#[derive(Debug, Deserialize)]
+struct AzureSignResponse {
+    value: String,
+}
+
+/// Signs with a Google Cloud KMS asymmetric signing key version.
+#[derive(Clone)]
+pub struct GcpKmsSigner {
+    key_version: String,
+    alg: &'static str,
+    access_token: String,
+    api_url: String,
+    retry_policy: RetryPolicy,
+}
+
+impl GcpKmsSigner {
+    /// Creates a signer with the key version with the resource name
+    /// `key_version` (i.e., `projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*`),
+    /// signing with the JWS algorithm `alg`, and authenticating with
+    /// `access_token`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the algorithm isn't supported.
+    pub fn new(key_version: &str, alg: &str, access_token: &str) -> Result<Self> {
+        Ok(Self {
+            key_version: key_version.trim_matches('/').to_string(),
+            alg: supported_algorithm(alg)?,
+            access_token: access_token.to_string(),
+            api_url: DEFAULT_GCP_KMS_URL.to_string(),
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    /// Sets the Cloud KMS API URL (e.g., for a private endpoint).
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the retry policy for requests to Cloud KMS.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+}
+
+impl fmt::Debug for GcpKmsSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcpKmsSigner")
+            .field("key_version", &self.key_version)
+            .field("alg", &self.alg)
+            .field("api_url", &self.api_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer for GcpKmsSigner {
+    fn algorithm(&self) -> &str {
+        self.alg
+    }
+
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if Cloud KMS rejects the request, or cannot be
+    ///   reached after exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the response cannot be parsed.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let digest_name = match self.alg {
+            "ES384" => "sha384",
+            _ => "sha256",
+        };
+        let body = serde_json::json!({
+            "digest": { digest_name: STANDARD.encode(jws_digest(self.alg, data)?) }
+        })
+        .to_string();
+
+        let client = http_client(&self.retry_policy)?;
+        let url = format!("{}/v1/{}:asymmetricSign", self.api_url, self.key_version);
+        let resp = self.retry_policy.run(|| {
+            send(
+                client
+                    .post(&url)
+                    .bearer_auth(&self.access_token)
+                    .header("Content-Type", "application/json")
+                    .body(body.clone()),
+            )
+        })?;
+
+        let resp: GcpSignResponse = serde_json::from_slice(&resp)
+            .map_err(|e| Error::ParseError(format!("Invalid Cloud KMS response: {}", e)))?;
+        let signature = STANDARD
+            .decode(resp.signature)
+            .map_err(|e| Error::ParseError(format!("Invalid Cloud KMS signature: {}", e)))?;
+
+        // Cloud KMS returns DER-encoded ECDSA signatures
+        match self.alg {
+            "ES256" => der_to_raw_signature(&signature, 32),
+            "ES384" => der_to_raw_signature(&signature, 48),
+            _ => Ok(signature),
+        }
+    }
+}
+
+/// Signs with an Azure Key Vault (or Managed HSM) key.
+#[derive(Clone)]
+pub struct AzureKeyVaultSigner {
+    key_id: String,
+    alg: &'static str,
+    access_token: String,
+    retry_policy: RetryPolicy,
+}
+
+impl AzureKeyVaultSigner {
+    /// Creates a signer with the key with the identifier `key_id` (i.e.,
+    /// `https://{vault}.vault.azure.net/keys/{name}/{version}`), signing with
+    /// the JWS algorithm `alg`, and authenticating with `access_token`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the algorithm isn't supported.
+    pub fn new(key_id: &str, alg: &str, access_token: &str) -> Result<Self> {
+        Ok(Self {
+            key_id: key_id.trim_end_matches('/').to_string(),
+            alg: supported_algorithm(alg)?,
+            access_token: access_token.to_string(),
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    /// Sets the retry policy for requests to Key Vault.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+}
+
+impl fmt::Debug for AzureKeyVaultSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureKeyVaultSigner")
+            .field("key_id", &self.key_id)
+            .field("alg", &self.alg)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer for AzureKeyVaultSigner {
+    fn algorithm(&self) -> &str {
+        self.alg
+    }
+
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if Key Vault rejects the request, or cannot be
+    ///   reached after exhausting the `RetryPolicy`.
+    /// - `Error::ParseError` if the response cannot be parsed.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let body = serde_json::json!({
+            "alg": self.alg,
+            "value": URL_SAFE_NO_PAD.encode(jws_digest(self.alg, data)?),
+        })
+        .to_string();
+
+        let client = http_client(&self.retry_policy)?;
+        let url = format!(
+            "{}/sign?api-version={}",
+            self.key_id, AZURE_KEY_VAULT_API_VERSION
+        );
+        let resp = self.retry_policy.run(|| {
+            send(
+                client
+                    .post(&url)
+                    .bearer_auth(&self.access_token)
+                    .header("Content-Type", "application/json")
+                    .body(body.clone()),
+            )
+        })?;
+
+        // Key Vault returns ECDSA signatures in their JWS encoding already
+        let resp: AzureSignResponse = serde_json::from_slice(&resp)
+            .map_err(|e| Error::ParseError(format!("Invalid Key Vault response: {}", e)))?;
+        URL_SAFE_NO_PAD
+            .decode(resp.value.trim_end_matches('='))
+            .map_err(|e| Error::ParseError(format!("Invalid Key Vault signature: {}", e)))
+    }
+}
+
+/// Checks that the JWS algorithm `alg` is supported by the KMS signers.
+fn supported_algorithm(alg: &str) -> Result<&'static str> {
+    ["ES256", "ES384", "RS256", "PS256"]
+        .into_iter()
+        .find(|supported| *supported == alg)
+        .ok_or_else(|| Error::NotSupported(format!("KMS signing with {} is not supported", alg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::ecdsa::EcdsaSig;
+    use openssl::nid::Nid;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serves a single JSON response, and returns the request.
+    fn serve_once(body: String) -> (String, std::thread::JoinHandle<std::io::Result<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request)?;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )?;
+            Ok(String::from_utf8_lossy(&request[..len]).to_string())
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn test_gcp_kms_signer() -> Result<()> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let key = EcKey::generate(&group)?;
+        let data = b"header.payload";
+        let digest = openssl::sha::sha256(data);
+        let der = EcdsaSig::sign(&digest, &key)?.to_der()?;
+
+        let (url, server) = serve_once(format!(r#"{{"signature":"{}"}}"#, STANDARD.encode(der)));
+        let signer = GcpKmsSigner::new("projects/p/cryptoKeyVersions/1", "ES256", "token")?
+            .with_api_url(&url)
+            .with_retry_policy(RetryPolicy::no_retry());
+        assert_eq!(signer.algorithm(), "ES256");
+        let signature = signer.sign(data)?;
+
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("POST /v1/projects/p/cryptoKeyVersions/1:asymmetricSign "));
+        assert!(request.contains("authorization: Bearer token"));
+        assert!(request.contains(&format!(
+            r#"{{"digest":{{"sha256":"{}"}}}}"#,
+            STANDARD.encode(digest)
+        )));
+
+        assert_eq!(signature.len(), 64);
+        let sig = EcdsaSig::from_private_components(
+            openssl::bn::BigNum::from_slice(&signature[..32])?,
+            openssl::bn::BigNum::from_slice(&signature[32..])?,
+        )?;
+        assert!(sig.verify(&digest, &key)?);
+        assert!(!format!("{:?}", signer).contains("token"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_azure_key_vault_signer() -> Result<()> {
+        let (url, server) = serve_once(r#"{"kid":"k","value":"AQID"}"#.to_string());
+        let signer = AzureKeyVaultSigner::new(&format!("{}/keys/k/1", url), "PS256", "token")?
+            .with_retry_policy(RetryPolicy::no_retry());
+        assert_eq!(signer.sign(b"data")?, vec![1, 2, 3]);
+
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("POST /keys/k/1/sign?api-version=7.4 "));
+        assert!(request.contains(&format!(
+            r#""value":"{}""#,
+            URL_SAFE_NO_PAD.encode(openssl::sha::sha256(b"data"))
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn test_kms_signer_errors() {
+        assert!(GcpKmsSigner::new("k", "HS256", "t").is_err_and(|e| e.is_not_supported()));
+        assert!(AzureKeyVaultSigner::new("k", "EdDSA", "t").is_err_and(|e| e.is_not_supported()));
+
+        let unreachable = GcpKmsSigner::new("k", "RS256", "t")
+            .unwrap()
+            .with_api_url("http://127.0.0.1:1/")
+            .with_retry_policy(RetryPolicy::no_retry());
+        assert!(unreachable.sign(b"data").unwrap_err().is_network());
+    }
+}
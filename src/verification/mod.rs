@@ -2,7 +2,33 @@
 //!
 //! This module implements utilities for performing cryptographic operations
 //! needed for Intel TDX-based attestation verification.
-//! It currently supports digital signature and X.509 certificate utilities.
+//! It currently supports digital signature, X.509 certificate and TD quote
+//! signature verification utilities, backed by OpenSSL (with the
+//! `host-verification` feature), and a pure-Rust TD quote and TCB Info
+//! signature verification backend for targets without OpenSSL, such as
+//! WebAssembly (the `rustcrypto` module, with the `rustcrypto-verification`
+//! feature). With the `ita-verification` feature, quotes can instead be
+//! appraised remotely by Intel Trust Authority (the `ita` module). With
+//! either backend, the QE report in quotes can be checked against Intel's QE
+//! Identity (the `qe` module), and the platform info of PCK certificates can
+//! be extracted (the `pck` module). With the `host-verification` feature,
+//! appraisal verdicts can be signed as JWTs for relying parties (the `result`
+//! module), evidence timestamps from an RFC 3161 timestamp authority can be
+//! verified (the `timestamp` module), session keys can be derived from key
+//! shares bound into verified evidence (the `session` module), launch
+//! endorsements can be checked against a Rekor transparency log (the
+//! `transparency` module), Sigstore-signed policies and reference values
+//! can be verified before they're loaded (the `sigstore` module), and
+//! certificate chains can be checked against the CRLs of their issuers (the
+//! `crl` module). Results
+//! can be signed with local keys, HSMs (the `signature` module) or, with the
+//! `kms-signing` feature, cloud KMS keys (the `kms` module). Measurements,
+//! report data bindings and MACs are compared in constant time (the `ct`
+//! module, also available without `std` as `core::ct`). With the
+//! `parallel-verification` feature, the independent checks of an appraisal
+//! run in parallel (the `parallel` module) and, with the
+//! `batch-verification` feature, batches of quotes can be appraised in
+//! parallel, sharing their collateral (the `batch` module).
 //!
 //! ## Example Usage
 //!
@@ -24,5 +50,34 @@
 //! }
 //! ```
 
+pub use crate::core::ct;
+#[cfg(feature = "batch-verification")]
+pub mod batch;
+#[cfg(feature = "host-verification")]
+pub mod crl;
+#[cfg(feature = "ita-verification")]
+pub mod ita;
+#[cfg(feature = "kms-signing")]
+pub mod kms;
+#[cfg(feature = "parallel-verification")]
+pub mod parallel;
+pub mod pck;
+pub mod qe;
+#[cfg(feature = "host-verification")]
+pub mod quote;
+#[cfg(feature = "host-verification")]
+pub mod result;
+#[cfg(feature = "rustcrypto-verification")]
+pub mod rustcrypto;
+#[cfg(feature = "host-verification")]
+pub mod session;
+#[cfg(feature = "host-verification")]
 pub mod signature;
+#[cfg(feature = "host-verification")]
+pub mod sigstore;
+#[cfg(feature = "host-verification")]
+pub mod timestamp;
+#[cfg(feature = "host-verification")]
+pub mod transparency;
+#[cfg(feature = "host-verification")]
 pub mod x509;
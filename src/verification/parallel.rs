@@ -0,0 +1,93 @@
+//! # Parallel Verification
+//!
+//! With the `parallel-verification` feature, `Bundle::verify()` runs its
+//! independent checks in parallel on a work-stealing thread pool (with
+//! rayon), so that the latency of an appraisal is that of its slowest check
+//! (e.g., replaying a large event log, or checking the PCK chain against its
+//! CRLs) rather than the sum of them. Batches of quotes (see the `batch`
+//! module) are verified on the same pool.
+//!
+//! The pool is owned by this crate, so that verifications don't compete with
+//! (or reconfigure) an application's own rayon pools. It has a thread per
+//! CPU, unless configured otherwise with `set_jobs()` before the first
+//! verification.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::verification::parallel::set_jobs;
+//!
+//! // verify on at most 4 threads
+//! set_jobs(4).unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::OnceLock;
+
+// The verification pool, or `None` if its threads cannot be started
+static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+
+/// Sets the number of threads verifications run on (a thread per CPU if
+/// `jobs` is 0).
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if the thread pool was already started,
+/// e.g., by an earlier verification, or its threads cannot be started.
+pub fn set_jobs(jobs: usize) -> Result<()> {
+    let pool = build_pool(jobs)?;
+    POOL.set(Some(pool)).map_err(|_| {
+        Error::NotSupported("The verification threads were already started".to_string())
+    })
+}
+
+/// Returns the number of threads verifications run on.
+pub fn jobs() -> usize {
+    pool().map_or(1, ThreadPool::current_num_threads)
+}
+
+/// Runs `op` on the verification pool, so that the rayon operations within
+/// it (e.g., `rayon::scope()` or `par_iter()`) use the pool's threads.
+///
+/// If the pool's threads cannot be started, `op` runs on the calling thread.
+pub(crate) fn install<R, OP>(op: OP) -> R
+where
+    OP: FnOnce() -> R + Send,
+    R: Send,
+{
+    match pool() {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+/// Returns the verification pool, starting it with a thread per CPU if
+/// `set_jobs()` wasn't called.
+fn pool() -> Option<&'static ThreadPool> {
+    POOL.get_or_init(|| build_pool(0).ok()).as_ref()
+}
+
+fn build_pool(jobs: usize) -> Result<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .thread_name(|i| format!("tdx-verify-{}", i))
+        .build()
+        .map_err(|e| {
+            Error::NotSupported(format!("Cannot configure the verification threads: {}", e))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs() {
+        assert!(jobs() > 0);
+        // the pool was started by `jobs()`
+        assert!(set_jobs(2).unwrap_err().is_not_supported());
+        assert_eq!(install(rayon::current_num_threads), jobs());
+    }
+}
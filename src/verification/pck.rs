@@ -0,0 +1,169 @@
+//! # PCK Certificate Platform Info
+//!
+//! This module extracts the identity and TCB of a TDX platform from the
+//! Intel SGX extensions (OID `1.2.840.113741.1.13.1`) of its Provisioning
+//! Certification Key (PCK) certificate: its Platform Provisioning ID (PPID),
+//! platform family (FMSPC), PCE ID, SGX TCB component SVNs, PCE SVN and
+//! CPUSVN, and the type of its SGX implementation.
+//!
+//! The FMSPC and PCE ID select the platform's collateral (e.g., the TCB Info
+//! is retrieved per FMSPC), and the TCB components are evaluated against the
+//! TCB Info's levels (see the `evidence::tcb` module).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//! use tdx_workload_attestation::verification::pck::extract_platform_info;
+//!
+//! let quote = Quote::from_bytes(&std::fs::read("quote.bin").unwrap()).unwrap();
+//! let pck_chain = quote.pck_chain().unwrap();
+//!
+//! let info = extract_platform_info(&pck_chain[0]).unwrap();
+//! println!("FMSPC {}, PCE ID {}", hex::encode(info.fmspc), hex::encode(info.pceid));
+//! ```
+//!
+//! # Notes
+//! - The certificate's signature isn't verified: the platform info is only
+//!   trustworthy once the PCK chain is (see `verify_quote_signature()`).
+
+use crate::error::{Error, Result};
+use crate::evidence::tcb::{
+    SGX_FMSPC_ARC, SGX_PCESVN_ARC, SGX_TCB_ARC, SgxExtensions, TCB_COMPONENTS_LEN, der_uint,
+};
+
+// The sub-OIDs of the PPID (1), PCE ID (3), SGX type (5) and platform
+// instance ID (6) extensions
+const SGX_PPID_ARC: u8 = 1;
+const SGX_PCEID_ARC: u8 = 3;
+const SGX_TYPE_ARC: u8 = 5;
+const SGX_PLATFORM_INSTANCE_ID_ARC: u8 = 6;
+
+// The TCB sub-OID of the CPUSVN
+const SGX_CPUSVN_ARC: u8 = 18;
+
+/// The type of a platform's SGX implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SgxType {
+    /// SGX on client platforms.
+    Standard,
+    /// Scalable SGX, on server platforms (the only type supporting TDX).
+    Scalable,
+    /// Scalable SGX with memory integrity protection.
+    ScalableWithIntegrity,
+}
+
+impl SgxType {
+    /// Returns the SGX type with the given `SgxType` enumeration value.
+    pub fn from_value(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Standard),
+            1 => Some(Self::Scalable),
+            2 => Some(Self::ScalableWithIntegrity),
+            _ => None,
+        }
+    }
+}
+
+/// The identity and TCB of a platform, from its PCK certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlatformInfo {
+    /// The Platform Provisioning ID.
+    pub ppid: [u8; 16],
+    /// The platform family (FMSPC).
+    pub fmspc: [u8; 6],
+    /// The ID of the Provisioning Certification Enclave.
+    pub pceid: [u8; 2],
+    /// The SGX TCB component SVNs.
+    pub sgx_svns: [u8; TCB_COMPONENTS_LEN],
+    /// The PCE SVN.
+    pub pcesvn: u16,
+    /// The CPU SVN.
+    pub cpusvn: [u8; 16],
+    /// The type of the platform's SGX implementation.
+    pub sgx_type: SgxType,
+    /// The ID of the platform instance, for multi-package platforms (whose
+    /// PCK certificates are issued by the Intel SGX PCK Platform CA).
+    pub platform_instance_id: Option<[u8; 16]>,
+}
+
+/// Extracts the platform info from the SGX extensions of the DER-encoded PCK
+/// certificate `cert`.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the certificate is malformed, doesn't
+/// have the SGX extensions, or one of the required extensions is malformed.
+pub fn extract_platform_info(cert: &[u8]) -> Result<PlatformInfo> {
+    let missing =
+        |name: &str| Error::ParseError(format!("PCK certificate is missing the {}", name));
+    let extensions = SgxExtensions::from_cert(cert)?;
+    let octets = |arcs: &[u8]| extensions.get(arcs, 0x04);
+    let uint = |arcs: &[u8], tag| extensions.get(arcs, tag).and_then(der_uint);
+
+    let ppid = octets(&[SGX_PPID_ARC])
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| missing("PPID"))?;
+    let fmspc = octets(&[SGX_FMSPC_ARC])
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| missing("FMSPC"))?;
+    let pceid = octets(&[SGX_PCEID_ARC])
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| missing("PCE ID"))?;
+
+    let mut sgx_svns = [0u8; TCB_COMPONENTS_LEN];
+    for (i, svn) in sgx_svns.iter_mut().enumerate() {
+        *svn = uint(&[SGX_TCB_ARC, i as u8 + 1], 0x02)
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or_else(|| missing("SGX TCB component SVNs"))?;
+    }
+    let pcesvn = uint(&[SGX_TCB_ARC, SGX_PCESVN_ARC], 0x02)
+        .and_then(|n| u16::try_from(n).ok())
+        .ok_or_else(|| missing("PCE SVN"))?;
+    let cpusvn = octets(&[SGX_TCB_ARC, SGX_CPUSVN_ARC])
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| missing("CPUSVN"))?;
+
+    // the SGX type is an ENUMERATED
+    let sgx_type = uint(&[SGX_TYPE_ARC], 0x0a)
+        .and_then(SgxType::from_value)
+        .ok_or_else(|| missing("SGX type"))?;
+    let platform_instance_id =
+        octets(&[SGX_PLATFORM_INSTANCE_ID_ARC]).and_then(|v| v.try_into().ok());
+
+    Ok(PlatformInfo {
+        ppid,
+        fmspc,
+        pceid,
+        sgx_svns,
+        pcesvn,
+        cpusvn,
+        sgx_type,
+        platform_instance_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::tcb::tests::{make_pck_cert, make_pck_extensions};
+
+    #[test]
+    fn test_extract_platform_info() -> Result<()> {
+        let der = make_pck_cert(&make_pck_extensions([1, 2, 3, 4, 5, 6], 7, 300));
+        let info = extract_platform_info(&der)?;
+        assert_eq!(info.ppid, [0xaa; 16]);
+        assert_eq!(info.fmspc, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(info.pceid, [0, 0]);
+        assert_eq!(info.sgx_svns, [7; TCB_COMPONENTS_LEN]);
+        assert_eq!(info.pcesvn, 300);
+        assert_eq!(info.cpusvn, [7; 16]);
+        assert_eq!(info.sgx_type, SgxType::Scalable);
+        assert_eq!(info.platform_instance_id, None);
+
+        // a truncated certificate
+        assert!(extract_platform_info(&der[..der.len() - 20]).is_err());
+        assert!(extract_platform_info(&[]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+//! # Quoting Enclave Identity Verification
+//!
+//! This module checks the Quoting Enclave (QE) report in a TD quote against
+//! Intel's QE Identity collateral (see the `evidence::tcb` module), which
+//! closes the gap between the quote's signature chain and the QE that
+//! produced it: the PCK only certifies that the QE report was generated on
+//! a genuine platform, not that it came from Intel's TD QE at an acceptable
+//! TCB level.
+//!
+//! A QE report matches the identity if its `MRSIGNER` and `ISVPRODID` are
+//! the expected ones, its `MISCSELECT` and attributes match under the
+//! identity's masks, and its `ISVSVN` reaches at least one of the identity's
+//! TCB levels.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//! use tdx_workload_attestation::evidence::tcb::SignedQeIdentity;
+//! use tdx_workload_attestation::verification::qe::{QeReport, verify_qe_identity};
+//!
+//! let quote = Quote::from_bytes(&std::fs::read("quote.bin").unwrap()).unwrap();
+//!
+//! // e.g., from https://api.trustedservices.intel.com/tdx/certification/v4/qe/identity
+//! let json = std::fs::read_to_string("qe_identity.json").unwrap();
+//! let qe_identity = SignedQeIdentity::parse(&json).unwrap().qe_identity;
+//!
+//! match verify_qe_identity(&quote, &qe_identity) {
+//!     Ok(true) => {
+//!         let isvsvn = QeReport::from_quote(&quote).isvsvn;
+//!         let status = qe_identity.tcb_level(isvsvn).map(|l| l.tcb_status.as_str());
+//!         println!("QE TCB status: {:?}", status);
+//!     }
+//!     Ok(false) => println!("QE does not match the QE Identity."),
+//!     Err(e) => eprintln!("Error verifying the QE identity: {}", e),
+//! }
+//! ```
+//!
+//! # Notes
+//! - This module doesn't verify the QE report's signature, which is part of
+//!   the quote's signature chain (see `verify_quote_signature()`), nor the
+//!   QE Identity's signature (see `SignedQeIdentity::verify_signature()`).
+
+use crate::core::quote::QE_REPORT_LEN;
+use crate::error::{Error, Result};
+use crate::evidence::quote::Quote;
+use crate::evidence::tcb::QeIdentity;
+
+/// The fields of a QE report (an SGX report body) that identify the QE.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QeReport {
+    /// The CPU SVN of the platform.
+    pub cpusvn: [u8; 16],
+    /// The `MISCSELECT` of the QE.
+    pub miscselect: u32,
+    /// The attributes of the QE.
+    pub attributes: [u8; 16],
+    /// The `MRENCLAVE` of the QE.
+    pub mrenclave: [u8; 32],
+    /// The `MRSIGNER` of the QE.
+    pub mrsigner: [u8; 32],
+    /// The product ID of the QE.
+    pub isvprodid: u16,
+    /// The security version of the QE.
+    pub isvsvn: u16,
+    /// The `report_data` of the QE report.
+    pub report_data: [u8; 64],
+}
+
+impl QeReport {
+    /// Parses a raw QE report.
+    pub fn parse(report: &[u8; QE_REPORT_LEN]) -> Self {
+        Self {
+            cpusvn: field(report, 0),
+            miscselect: u32::from_le_bytes(field(report, 16)),
+            attributes: field(report, 48),
+            mrenclave: field(report, 64),
+            mrsigner: field(report, 128),
+            isvprodid: u16::from_le_bytes(field(report, 256)),
+            isvsvn: u16::from_le_bytes(field(report, 258)),
+            report_data: field(report, 320),
+        }
+    }
+
+    /// Parses the QE report of a quote.
+    pub fn from_quote(quote: &Quote) -> Self {
+        Self::parse(&quote.qe_report)
+    }
+}
+
+/// Verifies that the QE report of `quote` matches `qe_identity`.
+///
+/// Returns `Ok(true)` if the QE's `MRSIGNER`, `ISVPRODID`, `MISCSELECT` and
+/// attributes match, and its `ISVSVN` reaches one of the identity's TCB
+/// levels (whose status must be checked separately, see
+/// `QeIdentity::tcb_level()`).
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if a hex-encoded field of the QE Identity
+/// is malformed.
+pub fn verify_qe_identity(quote: &Quote, qe_identity: &QeIdentity) -> Result<bool> {
+    let report = QeReport::from_quote(quote);
+
+    let mrsigner: [u8; 32] = decode_hex(&qe_identity.mrsigner, "MRSIGNER")?;
+    let miscselect = u32::from_be_bytes(decode_hex(&qe_identity.miscselect, "MISCSELECT")?);
+    let miscselect_mask =
+        u32::from_be_bytes(decode_hex(&qe_identity.miscselect_mask, "MISCSELECT mask")?);
+    let attributes: [u8; 16] = decode_hex(&qe_identity.attributes, "attributes")?;
+    let attributes_mask: [u8; 16] = decode_hex(&qe_identity.attributes_mask, "attributes mask")?;
+
+    let attributes_match = report
+        .attributes
+        .iter()
+        .zip(attributes)
+        .zip(attributes_mask)
+        .all(|((actual, expected), mask)| actual & mask == expected & mask);
+
+    Ok(report.mrsigner == mrsigner
+        && report.isvprodid == qe_identity.isvprodid
+        && report.miscselect & miscselect_mask == miscselect & miscselect_mask
+        && attributes_match
+        && qe_identity.tcb_level(report.isvsvn).is_some())
+}
+
+/// Returns the `N`-byte field at `offset` of a QE report.
+fn field<const N: usize>(report: &[u8; QE_REPORT_LEN], offset: usize) -> [u8; N] {
+    report[offset..offset + N].try_into().unwrap()
+}
+
+/// Decodes a hex-encoded QE Identity field.
+fn decode_hex<const N: usize>(field: &str, name: &str) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(field, &mut bytes)
+        .map_err(|e| Error::ParseError(format!("Invalid QE Identity {}: {}", name, e)))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::tcb::SignedQeIdentity;
+    use crate::evidence::tcb::tests::make_qe_identity_json;
+
+    pub(crate) const QE_MRSIGNER: [u8; 32] = [0xdc; 32];
+
+    /// Builds a QE report matching `make_qe_identity_json(QE_MRSIGNER)`, with
+    /// the given attributes and ISV SVN.
+    pub(crate) fn make_qe_report(attributes: u8, isvsvn: u16) -> [u8; QE_REPORT_LEN] {
+        let mut report = [0u8; QE_REPORT_LEN];
+        report[48] = attributes;
+        report[64..96].fill(0xee);
+        report[128..160].copy_from_slice(&QE_MRSIGNER);
+        report[256..258].copy_from_slice(&2u16.to_le_bytes());
+        report[258..260].copy_from_slice(&isvsvn.to_le_bytes());
+        report
+    }
+
+    fn qe_identity() -> QeIdentity {
+        let json = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            make_qe_identity_json(QE_MRSIGNER),
+            "00".repeat(64)
+        );
+        SignedQeIdentity::parse(&json).unwrap().qe_identity
+    }
+
+    fn quote(qe_report: [u8; QE_REPORT_LEN]) -> Quote {
+        let parts = QuoteParts {
+            qe_report,
+            ..Default::default()
+        };
+        Quote::from_bytes(&parts.assemble(&[6; 64], &[7; 64])).unwrap()
+    }
+
+    #[test]
+    fn test_parse_qe_report() {
+        let report = QeReport::from_quote(&quote(make_qe_report(0x11, 4)));
+        assert_eq!(report.attributes[0], 0x11);
+        assert_eq!(report.mrenclave, [0xee; 32]);
+        assert_eq!(report.mrsigner, QE_MRSIGNER);
+        assert_eq!((report.isvprodid, report.isvsvn), (2, 4));
+    }
+
+    #[test]
+    fn test_verify_qe_identity() -> Result<()> {
+        let qe_identity = qe_identity();
+        assert!(verify_qe_identity(
+            &quote(make_qe_report(0x11, 4)),
+            &qe_identity
+        )?);
+
+        // an out-of-date QE still matches, and a masked-out attribute is ignored
+        assert!(verify_qe_identity(
+            &quote(make_qe_report(0x15, 2)),
+            &qe_identity
+        )?);
+
+        // a QE below every TCB level, or in debug mode
+        assert!(!verify_qe_identity(
+            &quote(make_qe_report(0x11, 1)),
+            &qe_identity
+        )?);
+        assert!(!verify_qe_identity(
+            &quote(make_qe_report(0x13, 4)),
+            &qe_identity
+        )?);
+
+        // a QE from another signer or product
+        let mut report = make_qe_report(0x11, 4);
+        report[128] ^= 1;
+        assert!(!verify_qe_identity(&quote(report), &qe_identity)?);
+        let mut report = make_qe_report(0x11, 4);
+        report[256] = 1;
+        assert!(!verify_qe_identity(&quote(report), &qe_identity)?);
+
+        let malformed = QeIdentity {
+            mrsigner: "dc".to_string(),
+            ..qe_identity
+        };
+        assert!(verify_qe_identity(&quote(make_qe_report(0x11, 4)), &malformed).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+//! # TD Quote Signature Verification
+//!
+//! This module verifies the signature chain of an Intel TDX DCAP quote:
+//! 1. The PCK certificate chain is verified up to a trusted root (usually the
+//!    Intel SGX Root CA).
+//! 2. The QE report is verified against its signature by the PCK.
+//! 3. The QE report's `report_data` is checked to bind the attestation key
+//!    (and the QE authentication data).
+//! 4. The quote's header and body are verified against their signature by
+//!    the attestation key.
+//!
+//! ## Example Usage
+//!
+//! ```compile_fail
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//! use tdx_workload_attestation::verification::quote::verify_quote_signature;
+//! use tdx_workload_attestation::verification::x509::load_x509_der;
+//!
+//! let quote = Quote::from_bytes(&quote_bytes)?;
+//! let root = load_x509_der("/path/to/Intel_SGX_Provisioning_Certification_RootCA.cer")?;
+//!
+//! match verify_quote_signature(&quote, &quote.pck_chain()?, &root) {
+//!     Ok(true) => println!("Quote signature is valid."),
+//!     Ok(false) => println!("Quote signature is not valid."),
+//!     Err(e) => println!("Quote verification failed: {e}"),
+//! }
+//! ```
+//!
+//! # Notes
+//! - This module does not check the TCB status of the platform (see the
+//!   `evidence::tcb` module), or the QE identity, against Intel's collateral.
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::evidence::quote::Quote;
+use crate::verification::x509::{get_x509_pubkey, verify_x509_cert_at, x509_from_der_bytes};
+
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Public};
+use openssl::sha::sha256;
+use openssl::x509::X509;
+
+/// Verifies the signature chain of a TD quote, given the DER-encoded PCK
+/// certificate chain (starting with the PCK certificate) and the trusted root
+/// certificate.
+///
+/// Returns `Ok(false)` if any certificate or signature in the chain is
+/// invalid.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if the chain doesn't end in the trusted root,
+///   or a certificate's issuer doesn't match.
+/// - `Error::OpenSslError` if a certificate or key cannot be parsed.
+pub fn verify_quote_signature(quote: &Quote, pck_chain: &[Vec<u8>], root: &X509) -> Result<bool> {
+    verify_quote_signature_at(quote, pck_chain, root, SystemClock.now()?)
+}
+
+/// Verifies the signature chain of a TD quote, like
+/// `verify_quote_signature()`, with the certificates checked for expiry at
+/// `unix_time` (in seconds since the Unix epoch) rather than the current
+/// time.
+///
+/// # Errors
+///
+/// Same as `verify_quote_signature()`.
+pub fn verify_quote_signature_at(
+    quote: &Quote,
+    pck_chain: &[Vec<u8>],
+    root: &X509,
+    unix_time: u64,
+) -> Result<bool> {
+    let chain = pck_chain
+        .iter()
+        .map(|der| x509_from_der_bytes(der))
+        .collect::<Result<Vec<_>>>()?;
+
+    if !verify_cert_chain(&chain, root, unix_time)? {
+        return Ok(false);
+    }
+    verify_quote_with_pck(quote, &chain[0])
+}
+
+/// Verifies the signatures of a TD quote, given its PCK certificate, whose
+/// chain is assumed to be verified already (e.g., with `verify_cert_chain()`).
+///
+/// # Errors
+///
+/// Returns an `Error::OpenSslError` if the PCK or attestation key cannot be
+/// parsed.
+pub fn verify_quote_with_pck(quote: &Quote, pck: &X509) -> Result<bool> {
+    // the QE report is signed by the PCK
+    let pck_key = get_x509_pubkey(pck)?;
+    if !verify_ecdsa_p256(&quote.qe_report, &quote.qe_report_signature, &pck_key)? {
+        return Ok(false);
+    }
+
+    // the QE report binds the attestation key
+    if !quote.qe_report_binds_attestation_key() {
+        return Ok(false);
+    }
+
+    // the quote is signed by the attestation key
+    let attestation_key = ecdsa_p256_key(&quote.attestation_key)?;
+    verify_ecdsa_p256(quote.signed_data(), &quote.signature, &attestation_key)
+}
+
+/// Verifies each certificate in `chain` against the next one, and the last
+/// one against `root`, at `unix_time`.
+pub(crate) fn verify_cert_chain(chain: &[X509], root: &X509, unix_time: u64) -> Result<bool> {
+    if chain.is_empty() {
+        return Err(Error::VerificationError(
+            "Empty certificate chain".to_string(),
+        ));
+    }
+
+    // the chain may or may not include the root itself
+    let root_der = root.to_der().map_err(Error::OpenSslError)?;
+    let mut chain = chain.to_vec();
+    if chain.len() > 1
+        && chain
+            .last()
+            .unwrap()
+            .to_der()
+            .map_err(Error::OpenSslError)?
+            == root_der
+    {
+        chain.pop();
+    }
+    chain.push(root.clone());
+
+    for pair in chain.windows(2) {
+        if !verify_x509_cert_at(&pair[0], &pair[1], unix_time)? {
+            return Ok(false);
+        }
+    }
+
+    verify_x509_cert_at(root, root, unix_time)
+}
+
+/// Converts a raw (`x || y`) ECDSA P-256 public key into an OpenSSL key.
+fn ecdsa_p256_key(raw: &[u8; 64]) -> Result<PKey<Public>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(Error::OpenSslError)?;
+    let mut ctx = BigNumContext::new().map_err(Error::OpenSslError)?;
+
+    let mut uncompressed = vec![0x04];
+    uncompressed.extend(raw);
+    let point =
+        EcPoint::from_bytes(&group, &uncompressed, &mut ctx).map_err(Error::OpenSslError)?;
+    let key = EcKey::from_public_key(&group, &point).map_err(Error::OpenSslError)?;
+
+    PKey::from_ec_key(key).map_err(Error::OpenSslError)
+}
+
+/// Verifies a raw (`r || s`) ECDSA P-256 signature over the SHA-256 digest of
+/// `data`.
+pub(crate) fn verify_ecdsa_p256(
+    data: &[u8],
+    signature: &[u8; 64],
+    key: &PKey<Public>,
+) -> Result<bool> {
+    let key = key
+        .ec_key()
+        .map_err(|_| Error::SignatureError("Expected an ECDSA P-256 signing key".to_string()))?;
+
+    let r = BigNum::from_slice(&signature[..32]).map_err(Error::OpenSslError)?;
+    let s = BigNum::from_slice(&signature[32..]).map_err(Error::OpenSslError)?;
+    let sig = EcdsaSig::from_private_components(r, s).map_err(Error::OpenSslError)?;
+
+    sig.verify(&sha256(data), &key).map_err(Error::OpenSslError)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::test_utils::cert_builder;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKeyRef, Private};
+
+    /// A test PCK hierarchy and attestation key.
+    pub(crate) struct TestSigner {
+        pub(crate) root: X509,
+        root_key: PKey<Private>,
+        pck: X509,
+        pck_key: EcKey<Private>,
+        attestation_key: EcKey<Private>,
+    }
+
+    fn ec_key() -> EcKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        EcKey::generate(&group).unwrap()
+    }
+
+    fn make_cert(
+        subject: &str,
+        issuer: &str,
+        pubkey: &PKeyRef<Public>,
+        sign_key: &PKeyRef<Private>,
+        sgx_extensions: Option<&[u8]>,
+    ) -> X509 {
+        let mut cert = cert_builder(subject, issuer, pubkey, 5);
+        if let Some(der) = sgx_extensions {
+            let oid = openssl::asn1::Asn1Object::from_str("1.2.840.113741.1.13.1").unwrap();
+            let value = openssl::asn1::Asn1OctetString::new_from_bytes(der).unwrap();
+            cert.append_extension(
+                openssl::x509::X509Extension::new_from_der(&oid, false, &value).unwrap(),
+            )
+            .unwrap();
+        }
+        cert.sign(sign_key, MessageDigest::sha256()).unwrap();
+        cert.build()
+    }
+
+    fn public(key: &EcKey<Private>) -> PKey<Public> {
+        PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap()
+    }
+
+    pub(crate) fn sign(data: &[u8], key: &EcKey<Private>) -> [u8; 64] {
+        let sig = EcdsaSig::sign(&sha256(data), key).unwrap();
+        let mut raw = [0u8; 64];
+        raw[..32].copy_from_slice(&sig.r().to_vec_padded(32).unwrap());
+        raw[32..].copy_from_slice(&sig.s().to_vec_padded(32).unwrap());
+        raw
+    }
+
+    impl TestSigner {
+        pub(crate) fn new() -> Self {
+            let root_key = ec_key();
+            let pck_key = ec_key();
+            let root_pkey = PKey::from_ec_key(root_key.clone()).unwrap();
+
+            Self {
+                root: make_cert(
+                    "Test Root CA",
+                    "Test Root CA",
+                    &public(&root_key),
+                    &root_pkey,
+                    None,
+                ),
+                pck: make_cert(
+                    "Test PCK",
+                    "Test Root CA",
+                    &public(&pck_key),
+                    &root_pkey,
+                    None,
+                ),
+                root_key: root_pkey,
+                pck_key,
+                attestation_key: ec_key(),
+            }
+        }
+
+        /// Reissues the PCK certificate with the given DER-encoded SGX
+        /// extensions.
+        pub(crate) fn with_sgx_extensions(mut self, der: &[u8]) -> Self {
+            self.pck = make_cert(
+                "Test PCK",
+                "Test Root CA",
+                &public(&self.pck_key),
+                &self.root_key,
+                Some(der),
+            );
+            self
+        }
+
+        /// Issues a signing certificate under the root, and returns it (in
+        /// DER) with its key.
+        pub(crate) fn issue(&self, subject: &str) -> (Vec<u8>, EcKey<Private>) {
+            let key = ec_key();
+            let cert = make_cert(subject, "Test Root CA", &public(&key), &self.root_key, None);
+            (cert.to_der().unwrap(), key)
+        }
+
+        /// Returns a DER-encoded CRL of the root, current from `this_update`
+        /// to `next_update`, which revokes `revoked`.
+        pub(crate) fn crl(&self, revoked: &[&X509], this_update: u64, next_update: u64) -> Vec<u8> {
+            crate::verification::crl::tests::make_crl(
+                &self.root,
+                &self.root_key,
+                revoked,
+                this_update,
+                next_update,
+            )
+        }
+
+        /// Returns the PCK certificate.
+        pub(crate) fn pck(&self) -> &X509 {
+            &self.pck
+        }
+
+        /// Signs a quote with the given parts, embedding the PCK chain.
+        pub(crate) fn sign_quote(&self, mut parts: QuoteParts) -> Vec<u8> {
+            let mut ctx = BigNumContext::new().unwrap();
+            let point = self
+                .attestation_key
+                .public_key()
+                .to_bytes(
+                    self.attestation_key.group(),
+                    openssl::ec::PointConversionForm::UNCOMPRESSED,
+                    &mut ctx,
+                )
+                .unwrap();
+            parts.attestation_key.copy_from_slice(&point[1..]);
+
+            let mut binding = parts.attestation_key.to_vec();
+            binding.extend(&parts.qe_auth_data);
+            let len = parts.qe_report.len();
+            parts.qe_report[len - 64..].fill(0);
+            parts.qe_report[len - 64..len - 32].copy_from_slice(&sha256(&binding));
+
+            parts.pck_chain = [&self.pck, &self.root]
+                .iter()
+                .map(|c| String::from_utf8(c.to_pem().unwrap()).unwrap())
+                .collect();
+
+            let signature = sign(&parts.signed_data(), &self.attestation_key);
+            let qe_signature = sign(&parts.qe_report, &self.pck_key);
+            parts.assemble(&signature, &qe_signature)
+        }
+    }
+
+    #[test]
+    fn test_verify_quote_signature() -> Result<()> {
+        let signer = TestSigner::new();
+        let bytes = signer.sign_quote(QuoteParts::default());
+        let quote = Quote::from_bytes(&bytes)?;
+
+        assert!(verify_quote_signature(
+            &quote,
+            &quote.pck_chain()?,
+            &signer.root
+        )?);
+
+        // the chain may omit the root
+        assert!(verify_quote_signature(
+            &quote,
+            &quote.pck_chain()?[..1],
+            &signer.root
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_quote_signature_at() -> Result<()> {
+        let signer = TestSigner::new();
+        let quote = Quote::from_bytes(&signer.sign_quote(QuoteParts::default()))?;
+        let chain = quote.pck_chain()?;
+        let now = SystemClock.now()?;
+
+        // the test certificates are valid for five days
+        assert!(verify_quote_signature_at(
+            &quote,
+            &chain,
+            &signer.root,
+            now + 86400
+        )?);
+        assert!(!verify_quote_signature_at(
+            &quote,
+            &chain,
+            &signer.root,
+            now + 10 * 86400
+        )?);
+        assert!(!verify_quote_signature_at(
+            &quote,
+            &chain,
+            &signer.root,
+            1_000_000_000
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_quote_signature_tampered() -> Result<()> {
+        let signer = TestSigner::new();
+        let bytes = signer.sign_quote(QuoteParts::default());
+
+        // flip a bit in the MRTD
+        let mut tampered = bytes.clone();
+        tampered[200] ^= 1;
+        let quote = Quote::from_bytes(&tampered)?;
+        assert!(!verify_quote_signature(
+            &quote,
+            &quote.pck_chain()?,
+            &signer.root
+        )?);
+
+        // a quote chained to a different root
+        let other = TestSigner::new();
+        let quote = Quote::from_bytes(&bytes)?;
+        assert!(!matches!(
+            verify_quote_signature(&quote, &quote.pck_chain()?, &other.root),
+            Ok(true)
+        ));
+        Ok(())
+    }
+}
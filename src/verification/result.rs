@@ -0,0 +1,347 @@
+//! # Signed Attestation Results
+//!
+//! This module signs the `Verdict` of an appraised evidence bundle as a
+//! standard JWT, so that the crate can act as a lightweight verifier service
+//! issuing tokens to relying parties, which only need a JWT library and the
+//! verifier's key to consume them.
+//!
+//! Tokens are signed with a caller-provided key, whose type selects the
+//! signature algorithm:
+//! - an HMAC key (`PKey::hmac()`): `HS256`,
+//! - an ECDSA P-256 key: `ES256`, and
+//! - an RSA key: `RS256`.
+//!
+//! Alternatively, tokens can be signed with any `Signer` with
+//! `sign_jwt_with()`, e.g., with a key held in a cloud KMS or an HSM, so
+//! that the verifier doesn't keep its raw key on disk.
+//!
+//! The token's registered claims (issuer, subject, audience and lifetime) are
+//! set with `JwtClaims`, and the verdict is carried in the `verdict` claim,
+//! along with its overall result in the `passed` claim.
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use openssl::pkey::PKey;
+//! use tdx_workload_attestation::verification::result::{JwtClaims, sign_jwt};
+//!
+//! let verdict = bundle.verify(&policy).unwrap();
+//!
+//! let key = PKey::private_key_from_pem(&std::fs::read("verifier.pem").unwrap()).unwrap();
+//! let claims = JwtClaims::new()
+//!     .with_issuer("https://verifier.example.com")
+//!     .with_audience("https://relying-party.example.com")
+//!     .with_ttl(Duration::from_secs(600));
+//! let token = sign_jwt(&verdict, &key, &claims).unwrap();
+//! ```
+//!
+//! # Notes
+//! - Each token gets a random `jti` claim, so that relying parties can
+//!   detect replayed tokens.
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::evidence::Verdict;
+use crate::verification::signature::{KeySigner, Signer};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use openssl::pkey::{PKey, Private};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// The default lifetime of a signed attestation result.
+pub const DEFAULT_JWT_TTL: Duration = Duration::from_secs(300);
+
+// The number of random bytes in a token's `jti` claim
+const JTI_LEN: usize = 16;
+
+/// The claims of a signed attestation result, besides the verdict.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JwtClaims {
+    /// The issuer (`iss`) of the token, if any.
+    pub issuer: Option<String>,
+    /// The subject (`sub`) of the token, e.g., the appraised TD, if any.
+    pub subject: Option<String>,
+    /// The audience (`aud`) of the token, if any.
+    pub audience: Option<String>,
+    /// The lifetime of the token, from its issuance to its expiry (`exp`).
+    pub ttl: Duration,
+    /// The time the token is issued at (`iat`), in seconds since the Unix
+    /// epoch, or `None` for the current time.
+    pub issued_at: Option<u64>,
+    /// Additional private claims, by name.
+    pub extra: Map<String, Value>,
+}
+
+impl Default for JwtClaims {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            subject: None,
+            audience: None,
+            ttl: DEFAULT_JWT_TTL,
+            issued_at: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl JwtClaims {
+    /// Creates claims with no issuer, subject or audience, and the default
+    /// lifetime.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the issuer of the token.
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    /// Sets the subject of the token.
+    pub fn with_subject(mut self, subject: &str) -> Self {
+        self.subject = Some(subject.to_string());
+        self
+    }
+
+    /// Sets the audience of the token.
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Sets the lifetime of the token.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the time the token is issued at, in seconds since the Unix epoch.
+    pub fn with_issued_at(mut self, issued_at: u64) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    /// Adds a private claim to the token.
+    pub fn with_claim(mut self, name: &str, value: Value) -> Self {
+        self.extra.insert(name.to_string(), value);
+        self
+    }
+}
+
+/// Signs an appraisal verdict as a JWT with `key`, with the given claims.
+///
+/// # Errors
+///
+/// - `Error::NotSupported` if the key is neither an HMAC, ECDSA P-256 nor
+///   RSA key, the lifetime is zero, or a private claim shadows a registered
+///   or verdict claim.
+/// - `Error::SerializationError` if the verdict cannot be encoded.
+/// - `Error::VerificationError` if the system time is invalid.
+/// - `Error::OpenSslError` if the token cannot be signed.
+pub fn sign_jwt(result: &Verdict, key: &PKey<Private>, claims: &JwtClaims) -> Result<String> {
+    sign_jwt_with(result, &KeySigner::new(key.clone())?, claims)
+}
+
+/// Signs an appraisal verdict as a JWT with `signer` (e.g., a key held in a
+/// KMS or HSM), with the given claims.
+///
+/// # Errors
+///
+/// Same as `sign_jwt()`, or any error of the signer.
+pub fn sign_jwt_with(result: &Verdict, signer: &dyn Signer, claims: &JwtClaims) -> Result<String> {
+    if claims.ttl.is_zero() {
+        return Err(Error::NotSupported(
+            "Attestation result lifetime must not be zero".to_string(),
+        ));
+    }
+
+    let issued_at = match claims.issued_at {
+        Some(issued_at) => issued_at,
+        None => SystemClock.now()?,
+    };
+    let mut jti = [0u8; JTI_LEN];
+    openssl::rand::rand_bytes(&mut jti)?;
+
+    let mut payload = Map::new();
+    let registered = [
+        ("iss", claims.issuer.clone().map(Value::from)),
+        ("sub", claims.subject.clone().map(Value::from)),
+        ("aud", claims.audience.clone().map(Value::from)),
+        ("iat", Some(issued_at.into())),
+        ("nbf", Some(issued_at.into())),
+        ("exp", Some((issued_at + claims.ttl.as_secs()).into())),
+        ("jti", Some(hex::encode(jti).into())),
+        ("passed", Some(result.passed().into())),
+        (
+            "verdict",
+            Some(
+                serde_json::to_value(result)
+                    .map_err(|e| Error::SerializationError(e.to_string()))?,
+            ),
+        ),
+    ];
+    for (name, value) in registered {
+        if claims.extra.contains_key(name) {
+            return Err(Error::NotSupported(format!(
+                "Private claim {} shadows a reserved claim",
+                name
+            )));
+        }
+        if let Some(value) = value {
+            payload.insert(name.to_string(), value);
+        }
+    }
+    payload.extend(claims.extra.clone());
+
+    let header = serde_json::json!({"alg": signer.algorithm(), "typ": "JWT"});
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(Value::Object(payload).to_string())
+    );
+
+    let signature = signer.sign(signing_input.as_bytes())?;
+
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::Check;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::ecdsa::EcdsaSig;
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::rsa::Rsa;
+    use openssl::sign::Verifier;
+
+    /// A signer that "signs" by reversing the data.
+    struct ReverseSigner;
+
+    impl Signer for ReverseSigner {
+        fn algorithm(&self) -> &str {
+            "ES384"
+        }
+
+        fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.iter().rev().copied().collect())
+        }
+    }
+
+    fn verdict() -> Verdict {
+        let check = |name: &str, passed| Check {
+            name: name.to_string(),
+            passed,
+            detail: None,
+        };
+        Verdict {
+            checks: vec![check("quote-signature", true), check("nonce", false)],
+            ..Default::default()
+        }
+    }
+
+    /// Splits a token into its header, payload, signing input and signature.
+    fn split(token: &str) -> (Value, Value, String, Vec<u8>) {
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(parts.len(), 3);
+        let decode = |part: &str| URL_SAFE_NO_PAD.decode(part).unwrap();
+        (
+            serde_json::from_slice(&decode(parts[0])).unwrap(),
+            serde_json::from_slice(&decode(parts[1])).unwrap(),
+            format!("{}.{}", parts[0], parts[1]),
+            decode(parts[2]),
+        )
+    }
+
+    #[test]
+    fn test_sign_jwt_claims() -> Result<()> {
+        let key = PKey::hmac(b"verifier key")?;
+        let claims = JwtClaims::new()
+            .with_issuer("https://verifier.example.com")
+            .with_audience("relying-party")
+            .with_ttl(Duration::from_secs(60))
+            .with_issued_at(1000)
+            .with_claim("tenant", "acme".into());
+        let token = sign_jwt(&verdict(), &key, &claims)?;
+
+        let (header, payload, signing_input, signature) = split(&token);
+        assert_eq!(header["alg"], "HS256");
+        assert_eq!(payload["iss"], "https://verifier.example.com");
+        assert_eq!(payload["aud"], "relying-party");
+        assert_eq!(
+            (payload["iat"].as_u64(), payload["exp"].as_u64()),
+            (Some(1000), Some(1060))
+        );
+        assert_eq!(payload["tenant"], "acme");
+        assert_eq!(payload["passed"], false);
+        assert_eq!(payload["verdict"]["checks"][1]["name"], "nonce");
+        assert!(payload.get("sub").is_none());
+
+        let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(signing_input.as_bytes())?;
+        assert_eq!(signer.sign_to_vec()?, signature);
+
+        // tokens are unique
+        let (_, other, _, _) = split(&sign_jwt(&verdict(), &key, &claims)?);
+        assert_ne!(payload["jti"], other["jti"]);
+
+        // private claims can't shadow the verdict, and tokens must expire
+        let shadowing = claims.clone().with_claim("passed", true.into());
+        assert!(sign_jwt(&verdict(), &key, &shadowing).is_err_and(|e| e.is_not_supported()));
+        let eternal = claims.with_ttl(Duration::ZERO);
+        assert!(sign_jwt(&verdict(), &key, &eternal).is_err_and(|e| e.is_not_supported()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_jwt_asymmetric() -> Result<()> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let ec_key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        let token = sign_jwt(&verdict(), &ec_key, &JwtClaims::new())?;
+
+        let (header, _, signing_input, signature) = split(&token);
+        assert_eq!(header["alg"], "ES256");
+        assert_eq!(signature.len(), 64);
+        let sig = EcdsaSig::from_private_components(
+            openssl::bn::BigNum::from_slice(&signature[..32])?,
+            openssl::bn::BigNum::from_slice(&signature[32..])?,
+        )?;
+        let digest = openssl::sha::sha256(signing_input.as_bytes());
+        assert!(sig.verify(&digest, &*ec_key.ec_key()?)?);
+
+        let rsa_key = PKey::from_rsa(Rsa::generate(2048)?)?;
+        let token = sign_jwt(&verdict(), &rsa_key, &JwtClaims::new())?;
+        let (header, _, signing_input, signature) = split(&token);
+        assert_eq!(header["alg"], "RS256");
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &rsa_key)?;
+        verifier.update(signing_input.as_bytes())?;
+        assert!(verifier.verify(&signature)?);
+
+        // other curves aren't supported
+        let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+        let p384_key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        assert!(
+            sign_jwt(&verdict(), &p384_key, &JwtClaims::new()).is_err_and(|e| e.is_not_supported())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_jwt_with() -> Result<()> {
+        let token = sign_jwt_with(&verdict(), &ReverseSigner, &JwtClaims::new())?;
+        let (header, payload, signing_input, signature) = split(&token);
+        assert_eq!(header["alg"], "ES384");
+        assert_eq!(payload["passed"], false);
+        assert_eq!(signature, signing_input.bytes().rev().collect::<Vec<u8>>());
+        Ok(())
+    }
+}
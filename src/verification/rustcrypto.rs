@@ -0,0 +1,369 @@
+//! # Pure-Rust Verification Backend
+//!
+//! This module implements the TD quote, TCB Info and QE Identity signature
+//! verification of the `quote` module and `evidence::tcb` module in pure
+//! Rust (using the RustCrypto `p256` and `x509-cert` crates), so that relying
+//! parties can appraise TDX evidence on targets without OpenSSL, such as
+//! `wasm32-unknown-unknown`.
+//!
+//! Certificates and keys are passed DER-encoded, and the time at which
+//! certificates and collateral are checked for expiry is passed explicitly,
+//! in seconds since the Unix epoch (see `system_time()`).
+//!
+//! ## Example Usage
+//!
+//! ```compile_fail
+//! use tdx_workload_attestation::evidence::quote::Quote;
+//! use tdx_workload_attestation::verification::rustcrypto::{system_time, verify_quote_signature};
+//!
+//! let quote = Quote::from_bytes(&quote_bytes)?;
+//! let root = std::fs::read("/path/to/Intel_SGX_Provisioning_Certification_RootCA.cer")?;
+//!
+//! match verify_quote_signature(&quote, &quote.pck_chain()?, &root, system_time()?) {
+//!     Ok(true) => println!("Quote signature is valid."),
+//!     Ok(false) => println!("Quote signature is not valid."),
+//!     Err(e) => println!("Quote verification failed: {e}"),
+//! }
+//! ```
+//!
+//! # Notes
+//! - Only ECDSA P-256 keys and `ecdsa-with-SHA256` certificate signatures
+//!   are supported, which is what Intel's PCK and TCB signing hierarchies
+//!   use.
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::evidence::quote::Quote;
+use crate::evidence::tcb::{SignedQeIdentity, SignedTcbInfo};
+
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use x509_cert::Certificate;
+use x509_cert::der::{Decode, Encode};
+use x509_cert::spki::ObjectIdentifier;
+
+// The OID of ECDSA signatures over SHA-256 digests
+const ECDSA_WITH_SHA256_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+
+/// Returns the current system time, in seconds since the Unix epoch (see
+/// `SystemClock`).
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` on targets without a system clock (e.g.,
+/// `wasm32-unknown-unknown`), where callers must provide the time.
+pub fn system_time() -> Result<u64> {
+    SystemClock.now()
+}
+
+/// Verifies the signature chain of a TD quote, given the DER-encoded PCK
+/// certificate chain (starting with the PCK certificate), the DER-encoded
+/// trusted root certificate, and the verification time.
+///
+/// Returns `Ok(false)` if any certificate or signature in the chain is
+/// invalid.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if the chain doesn't end in the trusted root,
+///   or a certificate's issuer doesn't match.
+/// - `Error::ParseError` if a certificate or key cannot be parsed.
+/// - `Error::NotSupported` if a certificate isn't an ECDSA P-256 certificate.
+pub fn verify_quote_signature(
+    quote: &Quote,
+    pck_chain: &[Vec<u8>],
+    root: &[u8],
+    now: u64,
+) -> Result<bool> {
+    if !verify_cert_chain(pck_chain, root, now)? {
+        return Ok(false);
+    }
+    verify_quote_with_pck(quote, &pck_chain[0])
+}
+
+/// Verifies the signatures of a TD quote, given its DER-encoded PCK
+/// certificate, whose chain is assumed to be verified already (e.g., with
+/// `verify_cert_chain()`).
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the PCK or attestation key cannot be
+/// parsed, or an `Error::NotSupported` if the PCK isn't an ECDSA P-256
+/// certificate.
+pub fn verify_quote_with_pck(quote: &Quote, pck: &[u8]) -> Result<bool> {
+    // the QE report is signed by the PCK
+    let pck_key = cert_public_key(&parse_cert(pck)?)?;
+    if !verify_ecdsa_p256(&quote.qe_report, &quote.qe_report_signature, &pck_key) {
+        return Ok(false);
+    }
+
+    // the QE report binds the attestation key
+    if !quote.qe_report_binds_attestation_key() {
+        return Ok(false);
+    }
+
+    // the quote is signed by the attestation key
+    let mut sec1 = vec![0x04];
+    sec1.extend(quote.attestation_key);
+    let attestation_key = VerifyingKey::from_sec1_bytes(&sec1)
+        .map_err(|_| Error::ParseError("Invalid attestation key".to_string()))?;
+    Ok(verify_ecdsa_p256(
+        quote.signed_data(),
+        &quote.signature,
+        &attestation_key,
+    ))
+}
+
+/// Verifies Intel's signature over a TCB Info, given the DER-encoded TCB
+/// signing certificate chain (starting with the signing certificate), the
+/// DER-encoded trusted root certificate, and the verification time, and
+/// checks that the TCB Info hasn't passed its next update date.
+///
+/// # Errors
+///
+/// Same as `verify_quote_signature()`.
+pub fn verify_tcb_info_signature(
+    tcb_info: &SignedTcbInfo,
+    signing_chain: &[Vec<u8>],
+    root: &[u8],
+    now: u64,
+) -> Result<bool> {
+    verify_collateral_signature(
+        tcb_info.signed_data(),
+        tcb_info.signature(),
+        tcb_info.tcb_info.next_update_timestamp()?,
+        signing_chain,
+        root,
+        now,
+    )
+}
+
+/// Verifies Intel's signature over a QE Identity, given the DER-encoded TCB
+/// signing certificate chain (starting with the signing certificate), the
+/// DER-encoded trusted root certificate, and the verification time, and
+/// checks that the QE Identity hasn't passed its next update date.
+///
+/// # Errors
+///
+/// Same as `verify_quote_signature()`.
+pub fn verify_qe_identity_signature(
+    qe_identity: &SignedQeIdentity,
+    signing_chain: &[Vec<u8>],
+    root: &[u8],
+    now: u64,
+) -> Result<bool> {
+    verify_collateral_signature(
+        qe_identity.signed_data(),
+        qe_identity.signature(),
+        qe_identity.qe_identity.next_update_timestamp()?,
+        signing_chain,
+        root,
+        now,
+    )
+}
+
+/// Verifies Intel's signature over PCS collateral at time `now`, and checks
+/// that it hasn't passed its next update date.
+fn verify_collateral_signature(
+    signed_data: &[u8],
+    signature: &[u8; 64],
+    next_update: u64,
+    signing_chain: &[Vec<u8>],
+    root: &[u8],
+    now: u64,
+) -> Result<bool> {
+    if !verify_cert_chain(signing_chain, root, now)? {
+        return Ok(false);
+    }
+
+    let key = cert_public_key(&parse_cert(&signing_chain[0])?)?;
+    if !verify_ecdsa_p256(signed_data, signature, &key) {
+        return Ok(false);
+    }
+
+    Ok(now < next_update)
+}
+
+/// Verifies each DER-encoded certificate in `chain` against the next one, and
+/// the last one against the DER-encoded `root`, at time `now`.
+///
+/// # Errors
+///
+/// Same as `verify_quote_signature()`.
+pub fn verify_cert_chain(chain: &[Vec<u8>], root: &[u8], now: u64) -> Result<bool> {
+    if chain.is_empty() {
+        return Err(Error::VerificationError(
+            "Empty certificate chain".to_string(),
+        ));
+    }
+
+    // the chain may or may not include the root itself
+    let mut chain = chain
+        .iter()
+        .map(|der| parse_cert(der))
+        .collect::<Result<Vec<_>>>()?;
+    let root = parse_cert(root)?;
+    if chain.len() > 1 && chain.last() == Some(&root) {
+        chain.pop();
+    }
+    chain.push(root.clone());
+
+    for pair in chain.windows(2) {
+        if !verify_cert(&pair[0], &pair[1], now)? {
+            return Ok(false);
+        }
+    }
+
+    verify_cert(&root, &root, now)
+}
+
+/// Verifies a certificate's issuer, validity period and signature.
+fn verify_cert(cert: &Certificate, issuer: &Certificate, now: u64) -> Result<bool> {
+    let tbs = &cert.tbs_certificate;
+    if tbs.issuer != issuer.tbs_certificate.subject {
+        return Err(Error::VerificationError(
+            "Cert issuer verification failed".to_string(),
+        ));
+    }
+
+    let validity = &tbs.validity;
+    if now < validity.not_before.to_unix_duration().as_secs()
+        || now >= validity.not_after.to_unix_duration().as_secs()
+    {
+        return Ok(false);
+    }
+
+    if cert.signature_algorithm.oid != ECDSA_WITH_SHA256_OID {
+        return Err(Error::NotSupported(format!(
+            "Certificate signature algorithm {} is not supported",
+            cert.signature_algorithm.oid
+        )));
+    }
+    let Some(signature) = cert.signature.as_bytes() else {
+        return Ok(false);
+    };
+    let Ok(signature) = Signature::from_der(signature) else {
+        return Ok(false);
+    };
+
+    let tbs = tbs
+        .to_der()
+        .map_err(|e| Error::SerializationError(e.to_string()))?;
+    Ok(cert_public_key(issuer)?.verify(&tbs, &signature).is_ok())
+}
+
+fn parse_cert(der: &[u8]) -> Result<Certificate> {
+    Certificate::from_der(der).map_err(|e| Error::ParseError(format!("Invalid certificate: {}", e)))
+}
+
+fn cert_public_key(cert: &Certificate) -> Result<VerifyingKey> {
+    let spki = &cert.tbs_certificate.subject_public_key_info;
+    spki.subject_public_key
+        .as_bytes()
+        .and_then(|key| VerifyingKey::from_sec1_bytes(key).ok())
+        .ok_or_else(|| Error::NotSupported("Expected an ECDSA P-256 key".to_string()))
+}
+
+/// Verifies a raw (`r || s`) ECDSA P-256 signature over the SHA-256 digest of
+/// `data`.
+fn verify_ecdsa_p256(data: &[u8], signature: &[u8; 64], key: &VerifyingKey) -> bool {
+    Signature::from_slice(signature).is_ok_and(|sig| key.verify(data, &sig).is_ok())
+}
+
+#[cfg(all(test, feature = "host-verification"))]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::tcb::tests::make_tcb_info_json;
+    use crate::verification::quote::tests::{TestSigner, sign};
+
+    fn now() -> u64 {
+        system_time().unwrap()
+    }
+
+    #[test]
+    fn test_verify_quote_signature() -> Result<()> {
+        let signer = TestSigner::new();
+        let root = signer.root.to_der().unwrap();
+        let quote = Quote::from_bytes(&signer.sign_quote(QuoteParts::default()))?;
+        let chain = quote.pck_chain()?;
+
+        assert!(verify_quote_signature(&quote, &chain, &root, now())?);
+        assert!(verify_quote_signature(&quote, &chain[..1], &root, now())?);
+
+        // before the chain is valid
+        assert!(!verify_quote_signature(
+            &quote,
+            &chain,
+            &root,
+            now() - 86_400
+        )?);
+
+        // a quote chained to a different root
+        let other = TestSigner::new().root.to_der().unwrap();
+        assert!(!matches!(
+            verify_quote_signature(&quote, &chain, &other, now()),
+            Ok(true)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_quote_signature_tampered() -> Result<()> {
+        let signer = TestSigner::new();
+        let root = signer.root.to_der().unwrap();
+        let mut bytes = signer.sign_quote(QuoteParts::default());
+
+        // flip a bit in the MRTD
+        bytes[200] ^= 1;
+        let quote = Quote::from_bytes(&bytes)?;
+        assert!(!verify_quote_signature(
+            &quote,
+            &quote.pck_chain()?,
+            &root,
+            now()
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_tcb_info_signature() -> Result<()> {
+        let signer = TestSigner::new();
+        let root = signer.root.to_der().unwrap();
+        let (cert, key) = signer.issue("Test TCB Signing");
+
+        let signed = |tcb_info: &str| {
+            let signature = hex::encode(sign(tcb_info.as_bytes(), &key));
+            let json = format!(r#"{{"tcbInfo":{},"signature":"{}"}}"#, tcb_info, signature);
+            SignedTcbInfo::parse(&json).unwrap()
+        };
+        let tcb_info = make_tcb_info_json([0; 6]);
+        let chain = [cert];
+
+        assert!(verify_tcb_info_signature(
+            &signed(&tcb_info),
+            &chain,
+            &root,
+            now()
+        )?);
+
+        // a TCB Info past its next update
+        let expired = tcb_info.replace("2999-01-01", "2001-01-01");
+        assert!(!verify_tcb_info_signature(
+            &signed(&expired),
+            &chain,
+            &root,
+            now()
+        )?);
+
+        // a TCB Info signed by another key
+        let (other, _) = signer.issue("Other");
+        assert!(!verify_tcb_info_signature(
+            &signed(&tcb_info),
+            &[other],
+            &root,
+            now()
+        )?);
+        Ok(())
+    }
+}
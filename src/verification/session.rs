@@ -0,0 +1,298 @@
+//! # Attested Session Keys
+//!
+//! This module derives session keys from an ephemeral Diffie-Hellman
+//! exchange between a TD and a verifier, where the TD's key share is bound
+//! into its evidence, so that a secure channel can be established with the
+//! attested TD once its evidence has been verified.
+//!
+//! The TD generates a P-256 `KeyShare`, and collects its evidence over the
+//! share's public key (the bundle's nonce, see `KeyShare::nonce()`). The
+//! verifier generates a key share of its own, and derives the session key
+//! from the TD's evidence with `derive_session_key()`, which only succeeds if
+//! the evidence passes the verifier's policy. The TD derives the same key
+//! from the verifier's public key with `KeyShare::derive_session_key()`.
+//! Keys are derived from the ECDH shared secret with HKDF-SHA384, salted
+//! with both public keys.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::{Bundle, Policy};
+//! use tdx_workload_attestation::verification::session::{
+//!     KeyShare, SessionContext, derive_session_key,
+//! };
+//!
+//! // On the TD
+//! let td_share = KeyShare::generate().unwrap();
+//! let bundle = Bundle::collect(&td_share.nonce(), None).unwrap();
+//!
+//! // On the verifier, once it received the bundle
+//! let policy = Policy::new()
+//!     .with_collateral_dir("/etc/tdx-workload-attestation")
+//!     .unwrap();
+//! let context = SessionContext::new(KeyShare::generate().unwrap(), policy)
+//!     .with_info(b"my-protocol/v1");
+//! let key = derive_session_key(&bundle, &context).unwrap();
+//!
+//! // On the TD, once it received the verifier's public key
+//! let td_key = td_share
+//!     .derive_session_key(context.public_key(), b"my-protocol/v1")
+//!     .unwrap();
+//! assert_eq!(key, td_key);
+//! ```
+//!
+//! # Notes
+//! - The verifier's public key isn't authenticated to the TD, so the channel
+//!   only authenticates the TD to the verifier.
+//! - Replayed evidence is harmless without the TD's private key, since the
+//!   verifier's key share is fresh for each session, so the policy's nonce
+//!   is ignored.
+
+use crate::error::{Error, Result};
+use crate::evidence::{Bundle, Policy};
+
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::md::Md;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, PKeyRef, Private, Public};
+use openssl::pkey_ctx::PkeyCtx;
+use sha2::{Digest, Sha384};
+
+/// The length of session keys.
+pub const SESSION_KEY_LEN: usize = 32;
+
+// The domain separator of session keys
+const SESSION_KEY_CONTEXT: &[u8] = b"tdx-workload-attestation/session-key/v1";
+
+/// An ephemeral P-256 key share of one side of a session.
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    key: PKey<Private>,
+    public_key: Vec<u8>,
+}
+
+impl KeyShare {
+    /// Generates a new key share.
+    pub fn generate() -> Result<Self> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        let public_key = key.public_key_to_der()?;
+        Ok(Self { key, public_key })
+    }
+
+    /// Returns the DER-encoded public key (`SubjectPublicKeyInfo`) of the
+    /// share.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Returns the nonce of the TD's evidence that binds its key share.
+    pub fn nonce(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    /// Derives the TD's session key from the verifier's DER-encoded
+    /// `verifier_public_key`, where `info` separates keys for different
+    /// purposes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the verifier's public key isn't a
+    /// P-256 public key.
+    pub fn derive_session_key(
+        &self,
+        verifier_public_key: &[u8],
+        info: &[u8],
+    ) -> Result<[u8; SESSION_KEY_LEN]> {
+        let peer = parse_public_key(verifier_public_key)?;
+        session_key(
+            &self.key,
+            &peer,
+            &self.public_key,
+            verifier_public_key,
+            info,
+        )
+    }
+}
+
+/// The verifier's side of a session.
+#[derive(Clone, Debug)]
+pub struct SessionContext {
+    share: KeyShare,
+    policy: Policy,
+    info: Vec<u8>,
+}
+
+impl SessionContext {
+    /// Creates a new context for a session with a TD whose evidence passes
+    /// `policy`, with the verifier's key `share`.
+    pub fn new(share: KeyShare, policy: Policy) -> Self {
+        Self {
+            share,
+            policy,
+            info: vec![],
+        }
+    }
+
+    /// Sets the info that separates session keys for different purposes.
+    pub fn with_info(mut self, info: &[u8]) -> Self {
+        self.info = info.to_vec();
+        self
+    }
+
+    /// Returns the DER-encoded public key of the verifier's key share, to
+    /// be sent to the TD.
+    pub fn public_key(&self) -> &[u8] {
+        self.share.public_key()
+    }
+}
+
+/// Derives the verifier's session key with the TD whose key share is bound
+/// into `evidence`, if the evidence passes the context's policy.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if any of the evidence's checks fails.
+/// - `Error::ParseError` if the evidence's nonce isn't a P-256 public key.
+/// - The errors of `Bundle::verify()`.
+pub fn derive_session_key(
+    evidence: &Bundle,
+    context: &SessionContext,
+) -> Result<[u8; SESSION_KEY_LEN]> {
+    let mut policy = context.policy.clone();
+    policy.nonce = None;
+
+    let verdict = evidence.verify(&policy)?;
+    if !verdict.passed() {
+        return Err(Error::VerificationError(format!(
+            "Session evidence failed the checks: {}",
+            verdict.failed_checks().join(", ")
+        )));
+    }
+
+    let peer = parse_public_key(&evidence.nonce)?;
+    session_key(
+        &context.share.key,
+        &peer,
+        &evidence.nonce,
+        context.public_key(),
+        &context.info,
+    )
+}
+
+/// Parses a DER-encoded P-256 public key.
+fn parse_public_key(der: &[u8]) -> Result<PKey<Public>> {
+    let key = PKey::public_key_from_der(der)
+        .map_err(|e| Error::ParseError(format!("Invalid key share: {}", e)))?;
+    let curve = key.ec_key().ok().and_then(|k| k.group().curve_name());
+    if curve != Some(Nid::X9_62_PRIME256V1) {
+        return Err(Error::ParseError("Expected a P-256 key share".to_string()));
+    }
+    Ok(key)
+}
+
+/// Derives the session key from the ECDH shared secret of `key` and `peer`,
+/// salted with the TD's and the verifier's public keys.
+fn session_key(
+    key: &PKeyRef<Private>,
+    peer: &PKeyRef<Public>,
+    td_public_key: &[u8],
+    verifier_public_key: &[u8],
+    info: &[u8],
+) -> Result<[u8; SESSION_KEY_LEN]> {
+    let mut deriver = Deriver::new(key)?;
+    deriver.set_peer(peer)?;
+    let shared = deriver.derive_to_vec()?;
+
+    let mut salt = Sha384::new();
+    for public_key in [td_public_key, verifier_public_key] {
+        salt.update((public_key.len() as u64).to_be_bytes());
+        salt.update(public_key);
+    }
+
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha384())?;
+    ctx.set_hkdf_key(&shared)?;
+    ctx.set_hkdf_salt(&salt.finalize())?;
+    ctx.add_hkdf_info(SESSION_KEY_CONTEXT)?;
+    ctx.add_hkdf_info(info)?;
+
+    let mut session_key = [0u8; SESSION_KEY_LEN];
+    ctx.derive(Some(&mut session_key))?;
+    Ok(session_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::quote::tests::QuoteParts;
+    use crate::evidence::report_data_for_nonce;
+    use crate::verification::quote::tests::TestSigner;
+
+    fn evidence(signer: &TestSigner, nonce: &[u8]) -> Bundle {
+        let quote = signer.sign_quote(QuoteParts {
+            report_data: report_data_for_nonce(nonce),
+            ..Default::default()
+        });
+        Bundle::new(nonce, quote)
+    }
+
+    fn make_context(signer: &TestSigner) -> Result<SessionContext> {
+        let policy = Policy::new().with_trusted_root(&signer.root.to_der().unwrap());
+        Ok(SessionContext::new(KeyShare::generate()?, policy))
+    }
+
+    #[test]
+    fn test_derive_session_key() -> Result<()> {
+        let signer = TestSigner::new();
+        let td_share = KeyShare::generate()?;
+        let bundle = evidence(&signer, &td_share.nonce());
+
+        // the policy's nonce is ignored
+        let context = make_context(&signer)?.with_info(b"info");
+        let mut policy = context.policy.clone();
+        policy.nonce = Some(b"stale".to_vec());
+        let context = SessionContext { policy, ..context };
+
+        let key = derive_session_key(&bundle, &context)?;
+        assert_eq!(
+            td_share.derive_session_key(context.public_key(), b"info")?,
+            key
+        );
+        assert_ne!(
+            td_share.derive_session_key(context.public_key(), b"other")?,
+            key
+        );
+
+        // each verifier share yields a different key
+        let other = make_context(&signer)?.with_info(b"info");
+        assert_ne!(derive_session_key(&bundle, &other)?, key);
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_session_key_rejected() -> Result<()> {
+        let signer = TestSigner::new();
+        let td_share = KeyShare::generate()?;
+        let context = make_context(&signer)?;
+
+        // signed under another root
+        let bundle = evidence(&TestSigner::new(), &td_share.nonce());
+        let err = derive_session_key(&bundle, &context).unwrap_err();
+        assert!(err.to_string().contains("quote-signature"), "{}", err);
+
+        // the key share was substituted
+        let mut bundle = evidence(&signer, &td_share.nonce());
+        bundle.nonce = KeyShare::generate()?.nonce();
+        assert!(derive_session_key(&bundle, &context).is_err());
+
+        // the nonce isn't a key share
+        let bundle = evidence(&signer, b"nonce");
+        assert!(
+            derive_session_key(&bundle, &context).is_err_and(|e| matches!(e, Error::ParseError(_)))
+        );
+        Ok(())
+    }
+}
@@ -2,8 +2,19 @@
 //!
 //! This module provides utilities for working with digital signatures
 //! used in attestation verification.
-//! It currently supports verification of SHA256 signatures that use RSA-PSS
-//! padding.
+//! It supports verification of RSA-PSS, RSA PKCS#1 v1.5 and ECDSA
+//! signatures, whose algorithm (`SignatureAlgorithm`) is derived from the
+//! signing certificate, so that verifiers keep working when signers (e.g.,
+//! GCP's launch endorsement signer) migrate algorithms along with their
+//! certificates.
+//!
+//! It also abstracts the signing of the tokens a verifier issues (see the
+//! `result` module and `AttestationResult::sign_with()`) behind the `Signer`
+//! trait, so that result-issuing services don't need to keep raw keys on
+//! disk. Tokens can be signed with a local key (`KeySigner`), a key held in
+//! an HSM or smart card, through its PKCS#11 module (`Pkcs11Signer`), or,
+//! with the `kms-signing` feature, a key held in a cloud KMS (see the `kms`
+//! module).
 //!
 //! ## Example Usage
 //!
@@ -25,13 +36,329 @@
 //! }
 //! ```
 
+use crate::der::{
+    DerReader, SHA256_OID, SHA384_OID, SHA512_OID, TAG_CONTEXT_0, TAG_CONTEXT_1, TAG_CONTEXT_2,
+    TAG_INTEGER, TAG_OID, TAG_SEQUENCE,
+};
 use crate::error::{Error, Result};
+use crate::verification::x509::get_x509_pubkey;
 
-use openssl::hash::MessageDigest;
-use openssl::pkey::{PKey, Public};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::{MessageDigest, hash};
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, Private, Public};
 use openssl::rsa::Padding;
 use openssl::sign::RsaPssSaltlen;
 use openssl::sign::Verifier;
+use openssl::x509::X509;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// The default path of the OpenSC `pkcs11-tool`, used by `Pkcs11Signer`.
+pub const DEFAULT_PKCS11_TOOL_PATH: &str = "/usr/bin/pkcs11-tool";
+
+// The environment variable passing the user PIN to `pkcs11-tool`, so that it
+// doesn't show up in the process list
+const PKCS11_PIN_ENV: &str = "TDX_ATTEST_PKCS11_PIN";
+
+// The first OpenSC version whose `pkcs11-tool` reads the PIN from the
+// environment (`--pin env:VAR`), rather than taking `env:VAR` as the PIN
+const MIN_OPENSC_PIN_ENV_VERSION: (u32, u32) = (0, 23);
+
+// The DER-encoded OID of the MGF1 mask generation function
+const MGF1_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x08];
+
+// The minimum size of RSA signing keys, in bits
+const MIN_RSA_KEY_BITS: u32 = 2048;
+
+// The maximum size of RSA signing keys, in bits
+const MAX_RSA_KEY_BITS: u32 = 16384;
+
+/// A signer of JSON Web Signatures (JWS), such as the tokens a verifier
+/// issues to relying parties.
+///
+/// Implementations hold (or have access to) a signing key, and sign with a
+/// fixed JWS algorithm.
+pub trait Signer: Send + Sync {
+    /// Returns the JWS algorithm (`alg`) of the signatures, e.g., `"ES256"`.
+    fn algorithm(&self) -> &str;
+
+    /// Signs `data` (the JWS signing input), and returns the signature in its
+    /// JWS encoding (e.g., `r || s` for ECDSA).
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Signs with a local private key, whose type selects the JWS algorithm:
+/// - an HMAC key (`PKey::hmac()`): `HS256`,
+/// - an ECDSA P-256 key: `ES256`, and
+/// - an RSA key: `RS256`.
+#[derive(Clone, Debug)]
+pub struct KeySigner {
+    key: PKey<Private>,
+    alg: &'static str,
+}
+
+impl KeySigner {
+    /// Creates a signer with `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the key is neither an HMAC, ECDSA
+    /// P-256 nor RSA key.
+    pub fn new(key: PKey<Private>) -> Result<Self> {
+        let alg = match key.id() {
+            Id::HMAC => "HS256",
+            Id::RSA => "RS256",
+            Id::EC if key.ec_key()?.group().curve_name() == Some(Nid::X9_62_PRIME256V1) => "ES256",
+            _ => {
+                return Err(Error::NotSupported(
+                    "Tokens must be signed with an HMAC, ECDSA P-256 or RSA key".to_string(),
+                ));
+            }
+        };
+        Ok(Self { key, alg })
+    }
+}
+
+impl Signer for KeySigner {
+    fn algorithm(&self) -> &str {
+        self.alg
+    }
+
+    /// # Errors
+    ///
+    /// Returns an `Error::OpenSslError` if the data cannot be signed.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &self.key)?;
+        signer.update(data)?;
+        let signature = signer.sign_to_vec()?;
+        if self.key.id() == Id::EC {
+            return der_to_raw_signature(&signature, 32);
+        }
+        Ok(signature)
+    }
+}
+
+/// Signs with a key held in an HSM or smart card, through its PKCS#11 module,
+/// with the OpenSC `pkcs11-tool`.
+///
+/// Only `ES256` (ECDSA P-256) and `RS256` (RSA PKCS#1 v1.5) keys are
+/// supported. The token's user PIN, if set, is passed to the tool through its
+/// environment rather than its arguments, which requires OpenSC 0.23 or
+/// later: the version is checked with the `opensc-tool` next to
+/// `pkcs11-tool` before the PIN is passed, as older tools would try (and
+/// count) `env:` as the PIN itself.
+#[derive(Clone, Debug)]
+pub struct Pkcs11Signer {
+    module: PathBuf,
+    key_id: String,
+    alg: &'static str,
+    pin: Option<String>,
+    slot: Option<u64>,
+    tool_path: PathBuf,
+}
+
+impl Pkcs11Signer {
+    /// Creates a signer with the key with the (hex-encoded) PKCS#11 object ID
+    /// `key_id`, through the PKCS#11 module at `module` (e.g.,
+    /// `/usr/lib/softhsm/libsofthsm2.so`), signing with the JWS algorithm
+    /// `alg`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the algorithm is neither `ES256`
+    /// nor `RS256`.
+    pub fn new(module: &str, key_id: &str, alg: &str) -> Result<Self> {
+        let alg = match alg {
+            "ES256" => "ES256",
+            "RS256" => "RS256",
+            _ => {
+                return Err(Error::NotSupported(format!(
+                    "PKCS#11 signing with {} is not supported",
+                    alg
+                )));
+            }
+        };
+        Ok(Self {
+            module: PathBuf::from(module),
+            key_id: key_id.to_string(),
+            alg,
+            pin: None,
+            slot: None,
+            tool_path: PathBuf::from(DEFAULT_PKCS11_TOOL_PATH),
+        })
+    }
+
+    /// Sets the user PIN to log into the token with.
+    pub fn with_pin(mut self, pin: &str) -> Self {
+        self.pin = Some(pin.to_string());
+        self
+    }
+
+    /// Sets the ID of the slot holding the token (the first slot with a token
+    /// by default).
+    pub fn with_slot(mut self, slot: u64) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    /// Sets the path of the `pkcs11-tool` binary.
+    pub fn with_tool_path(mut self, tool_path: &str) -> Self {
+        self.tool_path = PathBuf::from(tool_path);
+        self
+    }
+
+    /// Checks that `pkcs11-tool` reads the PIN from its environment, per the
+    /// version of its OpenSC installation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if `opensc-tool` cannot be run, or
+    /// the OpenSC version is unknown or older than 0.23.
+    fn check_opensc_version(&self) -> Result<()> {
+        let opensc_tool = self.tool_path.with_file_name("opensc-tool");
+        let output = Command::new(&opensc_tool)
+            .arg("--info")
+            .output()
+            .map_err(|e| {
+                Error::NotSupported(format!("Failed to run {}: {}", opensc_tool.display(), e))
+            })?;
+        let info = String::from_utf8_lossy(&output.stdout);
+        let version = parse_opensc_version(&info).ok_or_else(|| {
+            Error::NotSupported(format!("Unknown OpenSC version: {}", info.trim()))
+        })?;
+        if version < MIN_OPENSC_PIN_ENV_VERSION {
+            return Err(Error::NotSupported(format!(
+                "Passing the PIN to pkcs11-tool requires OpenSC {}.{} or later, found {}.{}",
+                MIN_OPENSC_PIN_ENV_VERSION.0, MIN_OPENSC_PIN_ENV_VERSION.1, version.0, version.1
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    fn algorithm(&self) -> &str {
+        self.alg
+    }
+
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if `pkcs11-tool` cannot be run, or (with a
+    ///   PIN) its OpenSC version cannot read the PIN from the environment.
+    /// - `Error::SignatureError` if the token fails to sign the data, or the
+    ///   tool's output isn't a signature of the algorithm.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mechanism = match self.alg {
+            "ES256" => "ECDSA",
+            _ => "SHA256-RSA-PKCS",
+        };
+        // ECDSA mechanisms sign a digest, RSA mechanisms hash the data
+        let input = match self.alg {
+            "ES256" => jws_digest(self.alg, data)?,
+            _ => data.to_vec(),
+        };
+
+        let mut cmd = Command::new(&self.tool_path);
+        cmd.arg("--module")
+            .arg(&self.module)
+            .args(["--sign", "--mechanism", mechanism, "--id", &self.key_id])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if self.alg == "ES256" {
+            cmd.args(["--signature-format", "rs"]);
+        }
+        if let Some(slot) = self.slot {
+            cmd.arg("--slot").arg(slot.to_string());
+        }
+        if let Some(pin) = &self.pin {
+            self.check_opensc_version()?;
+            cmd.args(["--login", "--pin", &format!("env:{}", PKCS11_PIN_ENV)])
+                .env(PKCS11_PIN_ENV, pin);
+        }
+
+        let mut child = cmd.spawn().map_err(|e| {
+            Error::NotSupported(format!("Failed to run {}: {}", self.tool_path.display(), e))
+        })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&input)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(Error::SignatureError(format!(
+                "PKCS#11 signing failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        check_pkcs11_signature(self.alg, &output.stdout)?;
+        Ok(output.stdout)
+    }
+}
+
+// Returns the OpenSC version (major, minor) in the output of
+// `opensc-tool --info`, e.g., `OpenSC 0.23.0 [gcc  12.2.0]`
+fn parse_opensc_version(info: &str) -> Option<(u32, u32)> {
+    let version = info
+        .lines()
+        .find_map(|line| line.strip_prefix("OpenSC "))?
+        .split_whitespace()
+        .next()?;
+    let mut parts = version.split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+// Checks that the output of `pkcs11-tool` is a signature in the JWS encoding
+// of `alg`: `r || s` (neither of which is zero) for ES256, and as long as the
+// modulus of an RSA key of a supported size for RS256
+fn check_pkcs11_signature(alg: &str, signature: &[u8]) -> Result<()> {
+    let valid = match alg {
+        "ES256" => {
+            let (r, s) = signature.split_at(signature.len().min(32));
+            signature.len() == 64 && r.iter().any(|b| *b != 0) && s.iter().any(|b| *b != 0)
+        }
+        _ => (MIN_RSA_KEY_BITS / 8..=MAX_RSA_KEY_BITS / 8).contains(&(signature.len() as u32)),
+    };
+    if !valid {
+        return Err(Error::SignatureError(format!(
+            "pkcs11-tool returned an invalid {} signature ({} bytes)",
+            alg,
+            signature.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the digest of `data` that the JWS algorithm `alg` signs, for
+/// signers that sign digests rather than data.
+///
+/// # Errors
+///
+/// Returns an `Error::NotSupported` if the algorithm isn't one of `ES256`,
+/// `ES384`, `RS256` or `PS256`.
+pub(crate) fn jws_digest(alg: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let md = match alg {
+        "ES256" | "RS256" | "PS256" => MessageDigest::sha256(),
+        "ES384" => MessageDigest::sha384(),
+        _ => {
+            return Err(Error::NotSupported(format!(
+                "Signing with {} is not supported",
+                alg
+            )));
+        }
+    };
+    Ok(hash(md, data)?.to_vec())
+}
+
+/// Converts a DER-encoded ECDSA signature to the JWS encoding (the
+/// concatenation of `r` and `s`, each `len` bytes long).
+pub(crate) fn der_to_raw_signature(der: &[u8], len: i32) -> Result<Vec<u8>> {
+    let sig = EcdsaSig::from_der(der)?;
+    let mut raw = sig.r().to_vec_padded(len)?;
+    raw.extend(sig.s().to_vec_padded(len)?);
+    Ok(raw)
+}
 
 /// Verifies a SHA256 signature using RSA-PSS padding.
 ///
@@ -51,50 +378,338 @@ pub fn verify_signature_sha256_rsa_pss(
     signature: &[u8],
     public_key: &PKey<Public>,
 ) -> Result<bool> {
-    // Validate inputs
-    if data.is_empty() {
-        return Err(Error::SignatureError(
-            "Empty data provided for verification".to_string(),
-        ));
-    }
-    if signature.is_empty() {
-        return Err(Error::SignatureError(
-            "Empty signature provided for verification".to_string(),
-        ));
-    }
-
-    // Create verifier with error handling
-    let mut verifier = Verifier::new(MessageDigest::sha256(), public_key)
-        .map_err(|e| Error::SignatureError(format!("Failed to create verifier: {}", e)))?;
-
-    // Set RSA-PSS parameters with error handling
-    verifier
-        .set_rsa_padding(Padding::PKCS1_PSS)
-        .map_err(|e| Error::SignatureError(format!("Failed to set RSA padding: {}", e)))?;
-    verifier
-        .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
-        .map_err(|e| Error::SignatureError(format!("Failed to set PSS salt length: {}", e)))?;
-    verifier
-        .set_rsa_mgf1_md(MessageDigest::sha256())
-        .map_err(|e| Error::SignatureError(format!("Failed to set MGF1 hash: {}", e)))?;
-
-    // Update with data
-    verifier.update(data).map_err(|e| {
-        Error::SignatureError(format!("Failed to update verifier with data: {}", e))
-    })?;
-
-    // Verify signature
-    verifier
-        .verify(signature)
-        .map_err(|e| Error::VerificationError(format!("Signature verification failed: {}", e)))
+    SignatureAlgorithm::RsaPss(HashAlgorithm::Sha256).verify(data, signature, public_key)
+}
+
+/// Verifies a signature by the key of `signing_cert`, with the algorithm
+/// derived from the certificate (see `SignatureAlgorithm::from_certificate()`).
+///
+/// # Errors
+///
+/// Same as `SignatureAlgorithm::verify()`, and returns an
+/// `Error::NotSupported` if the certificate's signature algorithm and key
+/// aren't a supported combination.
+pub fn verify_signature(data: &[u8], signature: &[u8], signing_cert: &X509) -> Result<bool> {
+    let public_key = get_x509_pubkey(signing_cert)?;
+    SignatureAlgorithm::from_certificate(signing_cert)?.verify(data, signature, &public_key)
+}
+
+/// A hash algorithm of signatures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// SHA-256.
+    Sha256,
+    /// SHA-384.
+    Sha384,
+    /// SHA-512.
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn message_digest(self) -> MessageDigest {
+        match self {
+            Self::Sha256 => MessageDigest::sha256(),
+            Self::Sha384 => MessageDigest::sha384(),
+            Self::Sha512 => MessageDigest::sha512(),
+        }
+    }
+}
+
+/// A signature algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// RSASSA-PSS, with the hash as the MGF1 hash, and a salt as long as the
+    /// digest.
+    RsaPss(HashAlgorithm),
+    /// RSASSA-PKCS1-v1_5.
+    RsaPkcs1(HashAlgorithm),
+    /// ECDSA, with a DER-encoded signature.
+    Ecdsa(HashAlgorithm),
+}
+
+impl SignatureAlgorithm {
+    /// Returns the algorithm of the signatures by the key of `cert`, from
+    /// the certificate's signature algorithm (`signatureAlgorithm`) and the
+    /// type and size of its key:
+    /// - `sha256WithRSAEncryption`, `sha384WithRSAEncryption` or
+    ///   `sha512WithRSAEncryption`, with an RSA key of at least 2048 bits:
+    ///   RSA PKCS#1 v1.5 with that hash.
+    /// - `id-RSASSA-PSS`, with an RSA or RSA-PSS key of at least 2048 bits,
+    ///   and parameters with SHA-256, SHA-384 or SHA-512 as both the hash
+    ///   and the MGF1 hash, and a salt as long as the digest: RSA-PSS with
+    ///   that hash.
+    /// - `ecdsa-with-SHA256`, `ecdsa-with-SHA384` or `ecdsa-with-SHA512`,
+    ///   with an ECDSA key on the P-256, P-384 or P-521 curve respectively:
+    ///   ECDSA with that hash.
+    ///
+    /// The signatures themselves are never inspected, so that signers cannot
+    /// select the verifier.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` for any other combination of signature
+    ///   algorithm and key.
+    /// - `Error::ParseError` if the certificate's RSA-PSS parameters are
+    ///   malformed.
+    pub fn from_certificate(cert: &X509) -> Result<Self> {
+        let public_key = get_x509_pubkey(cert)?;
+        let unsupported = || {
+            Error::NotSupported(format!(
+                "Signatures by {:?} keys of {} bits with {} certificates are not supported",
+                public_key.id(),
+                public_key.bits(),
+                cert.signature_algorithm().object()
+            ))
+        };
+
+        // RSA-PSS keys are restricted to RSA-PSS signatures
+        let rsa_bits = public_key.bits() >= MIN_RSA_KEY_BITS;
+        let rsa = rsa_bits && public_key.id() == Id::RSA;
+        let rsa_pss = rsa_bits && matches!(public_key.id(), Id::RSA | Id::RSA_PSS);
+        let curve = match public_key.id() {
+            Id::EC => public_key.ec_key()?.group().curve_name(),
+            _ => None,
+        };
+        let algorithm = match cert.signature_algorithm().object().nid() {
+            Nid::SHA256WITHRSAENCRYPTION if rsa => Self::RsaPkcs1(HashAlgorithm::Sha256),
+            Nid::SHA384WITHRSAENCRYPTION if rsa => Self::RsaPkcs1(HashAlgorithm::Sha384),
+            Nid::SHA512WITHRSAENCRYPTION if rsa => Self::RsaPkcs1(HashAlgorithm::Sha512),
+            Nid::RSASSAPSS if rsa_pss => Self::RsaPss(pss_hash(cert)?.ok_or_else(unsupported)?),
+            Nid::ECDSA_WITH_SHA256 if curve == Some(Nid::X9_62_PRIME256V1) => {
+                Self::Ecdsa(HashAlgorithm::Sha256)
+            }
+            Nid::ECDSA_WITH_SHA384 if curve == Some(Nid::SECP384R1) => {
+                Self::Ecdsa(HashAlgorithm::Sha384)
+            }
+            Nid::ECDSA_WITH_SHA512 if curve == Some(Nid::SECP521R1) => {
+                Self::Ecdsa(HashAlgorithm::Sha512)
+            }
+            _ => return Err(unsupported()),
+        };
+        Ok(algorithm)
+    }
+
+    /// Verifies the `signature` of `data` with `public_key`.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::SignatureError` if there are issues with the inputs, verifier
+    ///   setup, or configuration.
+    /// - `Error::VerificationError` if the signature verification fails.
+    pub fn verify(&self, data: &[u8], signature: &[u8], public_key: &PKey<Public>) -> Result<bool> {
+        // Validate inputs
+        if data.is_empty() {
+            return Err(Error::SignatureError(
+                "Empty data provided for verification".to_string(),
+            ));
+        }
+        if signature.is_empty() {
+            return Err(Error::SignatureError(
+                "Empty signature provided for verification".to_string(),
+            ));
+        }
+
+        // Create verifier with error handling
+        let (Self::RsaPss(hash) | Self::RsaPkcs1(hash) | Self::Ecdsa(hash)) = *self;
+        let md = hash.message_digest();
+        let mut verifier = Verifier::new(md, public_key)
+            .map_err(|e| Error::SignatureError(format!("Failed to create verifier: {}", e)))?;
+
+        // Set RSA parameters with error handling
+        match self {
+            Self::RsaPss(_) => {
+                verifier.set_rsa_padding(Padding::PKCS1_PSS).map_err(|e| {
+                    Error::SignatureError(format!("Failed to set RSA padding: {}", e))
+                })?;
+                verifier
+                    .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+                    .map_err(|e| {
+                        Error::SignatureError(format!("Failed to set PSS salt length: {}", e))
+                    })?;
+                verifier.set_rsa_mgf1_md(md).map_err(|e| {
+                    Error::SignatureError(format!("Failed to set MGF1 hash: {}", e))
+                })?;
+            }
+            Self::RsaPkcs1(_) => {
+                verifier.set_rsa_padding(Padding::PKCS1).map_err(|e| {
+                    Error::SignatureError(format!("Failed to set RSA padding: {}", e))
+                })?;
+            }
+            Self::Ecdsa(_) => {}
+        }
+
+        // Update with data
+        verifier.update(data).map_err(|e| {
+            Error::SignatureError(format!("Failed to update verifier with data: {}", e))
+        })?;
+
+        // Verify signature
+        verifier
+            .verify(signature)
+            .map_err(|e| Error::VerificationError(format!("Signature verification failed: {}", e)))
+    }
+}
+
+// Returns the hash of the RSA-PSS parameters of `cert`'s signature algorithm,
+// if the parameters have the same hash and MGF1 hash, and a salt as long as
+// the digest
+fn pss_hash(cert: &X509) -> Result<Option<HashAlgorithm>> {
+    let der = cert.to_der()?;
+    let mut certificate = DerReader::new(&der).read_sequence()?;
+    certificate.read(TAG_SEQUENCE)?;
+    let mut algorithm = certificate.read_sequence()?;
+    algorithm.read(TAG_OID)?;
+
+    // absent parameters (and hashes) default to SHA-1
+    let Some(params) = algorithm.read_optional(TAG_SEQUENCE)? else {
+        return Ok(None);
+    };
+    let mut params = DerReader::new(params);
+    let (Some(hash), Some(mgf)) = (
+        params.read_optional(TAG_CONTEXT_0)?,
+        params.read_optional(TAG_CONTEXT_1)?,
+    ) else {
+        return Ok(None);
+    };
+    let Some(hash) = hash_algorithm(DerReader::new(hash).read_sequence()?)? else {
+        return Ok(None);
+    };
+    let mut mgf = DerReader::new(mgf).read_sequence()?;
+    if mgf.read(TAG_OID)? != MGF1_OID || hash_algorithm(mgf.read_sequence()?)? != Some(hash) {
+        return Ok(None);
+    }
+
+    // digests are shorter than 128 bytes, so their lengths are single bytes
+    let salt_len = match params.read_optional(TAG_CONTEXT_2)? {
+        Some(salt_len) => DerReader::new(salt_len).read(TAG_INTEGER)?,
+        None => return Ok(None),
+    };
+    Ok((salt_len == [hash.message_digest().size() as u8]).then_some(hash))
+}
+
+// Returns the hash of the DER-encoded `AlgorithmIdentifier` read by
+// `algorithm`, if it's SHA-256, SHA-384 or SHA-512
+fn hash_algorithm(mut algorithm: DerReader<'_>) -> Result<Option<HashAlgorithm>> {
+    Ok(match algorithm.read(TAG_OID)? {
+        SHA256_OID => Some(HashAlgorithm::Sha256),
+        SHA384_OID => Some(HashAlgorithm::Sha384),
+        SHA512_OID => Some(HashAlgorithm::Sha512),
+        _ => None,
+    })
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::der::{TAG_NULL, tlv};
+    use crate::test_utils::cert_builder;
     use openssl::pkey::Private;
     use openssl::rsa::Rsa;
     use openssl::sign::Signer;
+    use openssl::x509::X509Builder;
+
+    use super::Signer as _;
+
+    // The DER-encoded OID of RSASSA-PSS signatures
+    const RSASSA_PSS_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0a];
+    const TAG_BIT_STRING: u8 = 0x03;
+
+    /// Signs `data` with `key` and `algorithm`.
+    pub(crate) fn sign(key: &PKey<Private>, algorithm: SignatureAlgorithm, data: &[u8]) -> Vec<u8> {
+        let (SignatureAlgorithm::RsaPss(hash)
+        | SignatureAlgorithm::RsaPkcs1(hash)
+        | SignatureAlgorithm::Ecdsa(hash)) = algorithm;
+        let mut signer = Signer::new(hash.message_digest(), key).unwrap();
+        if let SignatureAlgorithm::RsaPss(_) = algorithm {
+            signer.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+            signer
+                .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+                .unwrap();
+            signer.set_rsa_mgf1_md(hash.message_digest()).unwrap();
+        }
+        signer.sign_oneshot_to_vec(data).unwrap()
+    }
+
+    /// Signs `cert` with `sign_key` and `algorithm`.
+    pub(crate) fn sign_cert(
+        cert: X509Builder,
+        sign_key: &PKey<Private>,
+        algorithm: SignatureAlgorithm,
+    ) -> X509 {
+        match algorithm {
+            SignatureAlgorithm::RsaPss(hash) => {
+                sign_cert_pss(cert, sign_key, hash, hash.message_digest().size() as u8)
+            }
+            SignatureAlgorithm::RsaPkcs1(hash) | SignatureAlgorithm::Ecdsa(hash) => {
+                let mut cert = cert;
+                cert.sign(sign_key, hash.message_digest()).unwrap();
+                cert.build()
+            }
+        }
+    }
+
+    // Signs `cert` with `sign_key` and RSA-PSS with `hash` and `salt_len`,
+    // which OpenSSL's certificate builder doesn't support
+    fn sign_cert_pss(
+        mut cert: X509Builder,
+        sign_key: &PKey<Private>,
+        hash: HashAlgorithm,
+        salt_len: u8,
+    ) -> X509 {
+        let md = hash.message_digest();
+        cert.sign(sign_key, md).unwrap();
+        let der = cert.build().to_der().unwrap();
+
+        let oid = match hash {
+            HashAlgorithm::Sha256 => SHA256_OID,
+            HashAlgorithm::Sha384 => SHA384_OID,
+            HashAlgorithm::Sha512 => SHA512_OID,
+        };
+        let mut hash_algorithm = tlv(TAG_OID, oid);
+        hash_algorithm.extend(tlv(TAG_NULL, &[]));
+        let hash_algorithm = tlv(TAG_SEQUENCE, &hash_algorithm);
+        let mut mgf = tlv(TAG_OID, MGF1_OID);
+        mgf.extend(&hash_algorithm);
+        let mut params = tlv(TAG_CONTEXT_0, &hash_algorithm);
+        params.extend(tlv(TAG_CONTEXT_1, &tlv(TAG_SEQUENCE, &mgf)));
+        params.extend(tlv(TAG_CONTEXT_2, &tlv(TAG_INTEGER, &[salt_len])));
+        let mut algorithm = tlv(TAG_OID, RSASSA_PSS_OID);
+        algorithm.extend(tlv(TAG_SEQUENCE, &params));
+        let algorithm = tlv(TAG_SEQUENCE, &algorithm);
+
+        // replace the signature algorithm in the TBS certificate, and re-sign it
+        let mut certificate = DerReader::new(&der).read_sequence().unwrap();
+        let mut fields = DerReader::new(certificate.read(TAG_SEQUENCE).unwrap());
+        let (_, _, signed_with) = certificate.read_tlv().unwrap();
+        let mut tbs = Vec::new();
+        while let Ok((_, _, field)) = fields.read_tlv() {
+            tbs.extend(if field == signed_with {
+                algorithm.as_slice()
+            } else {
+                field
+            });
+        }
+        let tbs = tlv(TAG_SEQUENCE, &tbs);
+
+        let mut signer = Signer::new(md, sign_key).unwrap();
+        signer.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+        signer
+            .set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_len as i32))
+            .unwrap();
+        signer.set_rsa_mgf1_md(md).unwrap();
+        let mut signature = vec![0];
+        signature.extend(signer.sign_oneshot_to_vec(&tbs).unwrap());
+
+        let mut certificate = tbs;
+        certificate.extend(algorithm);
+        certificate.extend(tlv(TAG_BIT_STRING, &signature));
+        X509::from_der(&tlv(TAG_SEQUENCE, &certificate)).unwrap()
+    }
+
+    // Returns a certificate of `key`, self-signed with `algorithm`
+    fn make_cert(key: &PKey<Private>, algorithm: SignatureAlgorithm) -> X509 {
+        sign_cert(cert_builder("Test", "Test", key, 1), key, algorithm)
+    }
 
     struct TestKeys {
         privkey: PKey<Private>,
@@ -186,4 +801,197 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_signature_algorithm_from_certificate() -> Result<()> {
+        use openssl::ec::{EcGroup, EcKey};
+
+        let data = b"hello, world";
+        let rsa = PKey::from_rsa(Rsa::generate(2048)?)?;
+        let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+        let p384 = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        let cases = [
+            (&rsa, SignatureAlgorithm::RsaPss(HashAlgorithm::Sha256)),
+            (&rsa, SignatureAlgorithm::RsaPss(HashAlgorithm::Sha512)),
+            (&rsa, SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha256)),
+            (&rsa, SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha512)),
+            (&p384, SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha384)),
+        ];
+        for (key, algorithm) in cases {
+            let cert = make_cert(key, algorithm);
+            assert_eq!(SignatureAlgorithm::from_certificate(&cert)?, algorithm);
+            let signature = sign(key, algorithm, data);
+            assert!(
+                verify_signature(data, &signature, &cert)?,
+                "{:?}",
+                algorithm
+            );
+            assert!(!verify_signature(b"hola, mundo", &signature, &cert)?);
+        }
+
+        // a PKCS#1 v1.5 signature isn't verified as RSA-PSS, or vice versa
+        let pss_cert = make_cert(&rsa, SignatureAlgorithm::RsaPss(HashAlgorithm::Sha256));
+        let pkcs1 = sign(
+            &rsa,
+            SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha256),
+            data,
+        );
+        assert!(!verify_signature(data, &pkcs1, &pss_cert)?);
+
+        // the hash must match the curve, RSA keys must be large enough, and
+        // the RSA-PSS salt as long as the digest
+        let unsupported = [
+            make_cert(&p384, SignatureAlgorithm::Ecdsa(HashAlgorithm::Sha256)),
+            make_cert(
+                &PKey::from_rsa(Rsa::generate(1024)?)?,
+                SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha256),
+            ),
+            sign_cert_pss(
+                cert_builder("Test", "Test", &rsa, 1),
+                &rsa,
+                HashAlgorithm::Sha256,
+                20,
+            ),
+        ];
+        for cert in unsupported {
+            assert!(
+                SignatureAlgorithm::from_certificate(&cert).is_err_and(|e| e.is_not_supported())
+            );
+        }
+
+        // ECDSA keys cannot be used with RSA certificates
+        let mut cert = X509Builder::new()?;
+        cert.set_pubkey(&p384)?;
+        let cert = sign_cert(
+            cert,
+            &rsa,
+            SignatureAlgorithm::RsaPkcs1(HashAlgorithm::Sha256),
+        );
+        assert!(SignatureAlgorithm::from_certificate(&cert).is_err_and(|e| e.is_not_supported()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_signer() -> Result<()> {
+        use openssl::ec::{EcGroup, EcKey};
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let ec_key = EcKey::generate(&group)?;
+        let signer = KeySigner::new(PKey::from_ec_key(ec_key.clone())?)?;
+        assert_eq!(signer.algorithm(), "ES256");
+        let signature = signer.sign(b"data")?;
+        assert_eq!(signature.len(), 64);
+        let sig = EcdsaSig::from_private_components(
+            openssl::bn::BigNum::from_slice(&signature[..32])?,
+            openssl::bn::BigNum::from_slice(&signature[32..])?,
+        )?;
+        assert!(sig.verify(&openssl::sha::sha256(b"data"), &ec_key)?);
+
+        let hmac = KeySigner::new(PKey::hmac(b"key")?)?;
+        assert_eq!(hmac.algorithm(), "HS256");
+        assert_eq!(hmac.sign(b"data")?.len(), 32);
+
+        let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+        let p384_key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        assert!(KeySigner::new(p384_key).is_err_and(|e| e.is_not_supported()));
+        Ok(())
+    }
+
+    // Writes stand-ins for OpenSC's tools, of `version`, to `dir`: a
+    // pkcs11-tool that logs its PIN and arguments, and outputs a signature
+    // of the mechanism's size (or garbage, for the key ID `bad`).
+    #[cfg(unix)]
+    fn write_opensc_tools(dir: &std::path::Path, version: &str) -> Result<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::create_dir_all(dir)?;
+        let tool = dir.join("pkcs11-tool");
+        let opensc_tool = dir.join("opensc-tool");
+        std::fs::write(
+            &tool,
+            format!(
+                "#!/bin/sh\n\
+                 cat > /dev/null\n\
+                 echo \"${}\" \"$@\" > \"$0.args\"\n\
+                 case \"$*\" in\n\
+                 *'--id bad'*) echo 'not a signature' ;;\n\
+                 *ECDSA*) head -c 64 /dev/urandom ;;\n\
+                 *) head -c 256 /dev/urandom ;;\n\
+                 esac\n",
+                PKCS11_PIN_ENV
+            ),
+        )?;
+        std::fs::write(
+            &opensc_tool,
+            format!("#!/bin/sh\necho 'OpenSC {} [gcc  12.2.0]'\n", version),
+        )?;
+        for path in [&tool, &opensc_tool] {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(tool)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pkcs11_signer() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pkcs11-tool-{}", std::process::id()));
+        let tool = write_opensc_tools(&dir, "0.23.0")?;
+
+        let signer = Pkcs11Signer::new("/usr/lib/libp11.so", "01", "ES256")?
+            .with_pin("1234")
+            .with_slot(2)
+            .with_tool_path(tool.to_str().unwrap());
+        assert_eq!(signer.algorithm(), "ES256");
+        assert_eq!(signer.sign(b"data")?.len(), 64);
+        let args = std::fs::read_to_string(dir.join("pkcs11-tool.args"))?;
+        assert_eq!(
+            args.trim(),
+            "1234 --module /usr/lib/libp11.so --sign --mechanism ECDSA --id 01 \
+             --signature-format rs --slot 2 --login --pin env:TDX_ATTEST_PKCS11_PIN"
+        );
+
+        let rsa = Pkcs11Signer::new("/usr/lib/libp11.so", "01", "RS256")?
+            .with_tool_path(tool.to_str().unwrap());
+        assert_eq!(rsa.sign(b"data")?.len(), 256);
+
+        // the tool's output must be a signature
+        let bad = Pkcs11Signer::new("/usr/lib/libp11.so", "bad", "ES256")?
+            .with_tool_path(tool.to_str().unwrap());
+        assert!(matches!(bad.sign(b"data"), Err(Error::SignatureError(_))));
+
+        // older tools would take env:... as the PIN
+        write_opensc_tools(&dir, "0.22.0")?;
+        assert!(signer.sign(b"data").is_err_and(|e| e.is_not_supported()));
+        // which doesn't matter without a PIN
+        assert_eq!(rsa.sign(b"data")?.len(), 256);
+        std::fs::remove_dir_all(&dir)?;
+
+        assert!(
+            Pkcs11Signer::new("/usr/lib/libp11.so", "01", "HS256")
+                .is_err_and(|e| e.is_not_supported())
+        );
+        let missing = Pkcs11Signer::new("/usr/lib/libp11.so", "01", "RS256")?
+            .with_tool_path("/nonexistent/pkcs11-tool");
+        assert!(missing.sign(b"data").is_err_and(|e| e.is_not_supported()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_pkcs11_signature() {
+        assert_eq!(
+            parse_opensc_version("OpenSC 0.23.0 [gcc  12.2.0]\n"),
+            Some((0, 23))
+        );
+        assert_eq!(parse_opensc_version("OpenSC 0.9 [gcc]"), Some((0, 9)));
+        assert_eq!(parse_opensc_version("pkcs11-tool: unknown option"), None);
+
+        let mut signature = [1; 64];
+        assert!(check_pkcs11_signature("ES256", &signature).is_ok());
+        signature[32..].fill(0);
+        assert!(check_pkcs11_signature("ES256", &signature).is_err());
+        assert!(check_pkcs11_signature("ES256", &[1; 72]).is_err());
+        assert!(check_pkcs11_signature("RS256", &[1; 384]).is_ok());
+        assert!(check_pkcs11_signature("RS256", &[1; 128]).is_err());
+        assert!(check_pkcs11_signature("RS256", b"").is_err());
+    }
 }
@@ -0,0 +1,830 @@
+//! # Sigstore-Signed Artifacts
+//!
+//! This module verifies artifacts distributed with a Sigstore bundle, such
+//! as appraisal policies and allow-lists of reference values signed with
+//! `cosign sign-blob --bundle`, so that verifiers only load the policies and
+//! reference values their operators signed.
+//!
+//! An `ArtifactVerifier` accepts artifacts signed either:
+//! - with one of its trusted keys (`cosign sign-blob --key`), or
+//! - keylessly, with a Fulcio signing certificate that chains up to a
+//!   trusted Fulcio root (see the `trust` module), and whose identity (the
+//!   email address or URI it was issued to) and OIDC issuer are trusted.
+//!   Since Fulcio certificates are short-lived, the certificate is checked
+//!   at the time the signature was recorded in a Rekor transparency log,
+//!   which is authenticated by the log's signed entry timestamp.
+//!
+//! Bundles are saved next to the artifact, with the `.sigstore.json`
+//! extension (see `bundle_path()`). `Policy::from_signed_file()` and
+//! `AllowList::from_signed_file()` verify them before the policy or
+//! allow-list is parsed.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::evidence::Policy;
+//! use tdx_workload_attestation::trust::TrustAnchors;
+//! use tdx_workload_attestation::verification::sigstore::ArtifactVerifier;
+//!
+//! let verifier = ArtifactVerifier::new()
+//!     .with_trust_anchors(TrustAnchors::from_dir("/etc/tdx-workload-attestation/anchors").unwrap())
+//!     .with_identity("policy-admins@example.com", "https://accounts.google.com")
+//!     .with_log_key(&std::fs::read("rekor.pub.der").unwrap());
+//!
+//! // Verifies policy.toml.sigstore.json before parsing policy.toml
+//! let policy = Policy::from_signed_file("policy.toml", &verifier).unwrap();
+//! ```
+//!
+//! # Notes
+//! - Only bundles with a message signature are supported: DSSE envelopes
+//!   (e.g., in-toto attestations) aren't.
+//! - Signatures must be ECDSA or RSA (PKCS#1 v1.5) signatures over SHA-256,
+//!   which are cosign's defaults.
+//! - Log entries are only required for keyless signatures, or if the
+//!   verifier has log keys.
+
+use crate::der::{DerReader, certificate_extensions};
+use crate::error::{Error, Result};
+use crate::trust::{TrustAnchorKind, TrustAnchors};
+use crate::verification::ct;
+use crate::verification::quote::verify_cert_chain;
+use crate::verification::transparency::body_records;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, PKeyRef, Public};
+use openssl::sign::Verifier;
+use openssl::x509::X509;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// The extension of the Sigstore bundle saved next to an artifact.
+pub const BUNDLE_FILE_EXT: &str = "sigstore.json";
+
+/// The message digest algorithm of supported bundles.
+pub const SHA2_256: &str = "SHA2_256";
+
+// The DER-encoded OIDs of Fulcio's OIDC issuer extensions: the current one
+// (1.3.6.1.4.1.57264.1.8), which holds a UTF8String, and the deprecated one
+// (1.3.6.1.4.1.57264.1.1), which holds the raw issuer
+const FULCIO_ISSUER_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x08];
+const FULCIO_ISSUER_V1_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x01];
+
+// The DER tag of UTF8Strings
+const TAG_UTF8_STRING: u8 = 0x0c;
+
+/// A DER-encoded value, base64-encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawBytes {
+    /// The base64-encoded value.
+    pub raw_bytes: String,
+}
+
+/// A certificate chain, from the leaf up.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct CertificateChain {
+    /// The certificates of the chain.
+    pub certificates: Vec<RawBytes>,
+}
+
+/// The ID of a transparency log.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogId {
+    /// The base64-encoded SHA-256 digest of the log's public key.
+    pub key_id: String,
+}
+
+/// The log's promise to include an entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionPromise {
+    /// The base64-encoded signed entry timestamp (SET).
+    pub signed_entry_timestamp: String,
+}
+
+/// A Rekor log entry of a bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlogEntry {
+    /// The global index of the entry in the log.
+    #[serde(deserialize_with = "int64")]
+    pub log_index: u64,
+    /// The ID of the log.
+    pub log_id: LogId,
+    /// The time the entry was integrated into the log, in seconds since the
+    /// Unix epoch.
+    #[serde(deserialize_with = "int64")]
+    pub integrated_time: u64,
+    /// The log's signed promise to include the entry, if any.
+    pub inclusion_promise: Option<InclusionPromise>,
+    /// The base64-encoded (canonicalized JSON) body of the entry.
+    pub canonicalized_body: String,
+}
+
+/// The material to verify a bundle's signature with.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMaterial {
+    /// The signing certificate (bundle v0.3).
+    pub certificate: Option<RawBytes>,
+    /// The signing certificate chain (bundles v0.1 and v0.2).
+    pub x509_certificate_chain: Option<CertificateChain>,
+    /// The log entries of the signature.
+    #[serde(default)]
+    pub tlog_entries: Vec<TlogEntry>,
+}
+
+/// A digest of the signed artifact.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct HashOutput {
+    /// The digest algorithm (e.g., `SHA2_256`).
+    pub algorithm: String,
+    /// The base64-encoded digest.
+    pub digest: String,
+}
+
+/// A signature over an artifact.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSignature {
+    /// The digest of the artifact, if any.
+    pub message_digest: Option<HashOutput>,
+    /// The base64-encoded signature.
+    pub signature: String,
+}
+
+/// A Sigstore bundle (versions 0.1 to 0.3), in JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigstoreBundle {
+    /// The media type of the bundle, including its version.
+    pub media_type: String,
+    /// The material to verify the signature with.
+    pub verification_material: VerificationMaterial,
+    /// The signature over the artifact, if it isn't a DSSE envelope.
+    pub message_signature: Option<MessageSignature>,
+}
+
+impl SigstoreBundle {
+    /// Parses a bundle from its JSON format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the bundle is malformed.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::ParseError(format!("Invalid Sigstore bundle: {}", e)))
+    }
+
+    /// Reads and parses the bundle saved next to the artifact at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::IoError` if the bundle cannot be read, and the
+    /// errors of `from_json()`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(bundle_path(path))?)
+    }
+
+    /// Returns the DER-encoded signing certificate chain, from the leaf up,
+    /// if the artifact was signed keylessly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if a certificate isn't valid base64.
+    pub fn certificates(&self) -> Result<Vec<Vec<u8>>> {
+        let material = &self.verification_material;
+        let certificates = match (&material.certificate, &material.x509_certificate_chain) {
+            (Some(certificate), _) => std::slice::from_ref(certificate),
+            (None, Some(chain)) => chain.certificates.as_slice(),
+            (None, None) => &[],
+        };
+        certificates
+            .iter()
+            .map(|c| decode("certificate", &c.raw_bytes))
+            .collect()
+    }
+}
+
+/// A trusted keyless signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignerIdentity {
+    /// The email address or URI the signing certificate was issued to.
+    pub subject: String,
+    /// The OIDC issuer that authenticated the subject.
+    pub issuer: String,
+}
+
+/// A verifier of Sigstore-signed artifacts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArtifactVerifier {
+    keys: Vec<Vec<u8>>,
+    identities: Vec<SignerIdentity>,
+    log_keys: Vec<Vec<u8>>,
+    anchors: TrustAnchors,
+}
+
+impl ArtifactVerifier {
+    /// Creates a new verifier that trusts no signer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts artifacts signed with the key whose DER-encoded public key
+    /// (`SubjectPublicKeyInfo`) is `der`.
+    pub fn with_key(mut self, der: &[u8]) -> Self {
+        self.keys.push(der.to_vec());
+        self
+    }
+
+    /// Trusts artifacts signed keylessly by `subject`, as authenticated by
+    /// the OIDC `issuer`.
+    pub fn with_identity(mut self, subject: &str, issuer: &str) -> Self {
+        self.identities.push(SignerIdentity {
+            subject: subject.to_string(),
+            issuer: issuer.to_string(),
+        });
+        self
+    }
+
+    /// Trusts the Rekor log with the DER-encoded public key `der`, and
+    /// requires signatures to be recorded in a trusted log.
+    pub fn with_log_key(mut self, der: &[u8]) -> Self {
+        self.log_keys.push(der.to_vec());
+        self
+    }
+
+    /// Sets the trust anchors, whose Fulcio roots signing certificates must
+    /// chain up to.
+    pub fn with_trust_anchors(mut self, anchors: TrustAnchors) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Verifies that `bundle` holds a trusted signature over `artifact`.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NotSupported` if the bundle holds a DSSE envelope.
+    /// - `Error::VerificationError` if the artifact was signed keylessly,
+    ///   but there are no trusted Fulcio roots or log keys.
+    /// - `Error::ParseError` if the bundle, a certificate or a key is
+    ///   malformed.
+    pub fn verify(&self, artifact: &[u8], bundle: &SigstoreBundle) -> Result<bool> {
+        let Some(message_signature) = &bundle.message_signature else {
+            return Err(Error::NotSupported(
+                "Only Sigstore bundles with a message signature are supported".to_string(),
+            ));
+        };
+        if let Some(digest) = &message_signature.message_digest
+            && (digest.algorithm != SHA2_256
+                || !ct::eq(
+                    &decode("digest", &digest.digest)?,
+                    &Sha256::digest(artifact),
+                ))
+        {
+            return Ok(false);
+        }
+        let signature = decode("signature", &message_signature.signature)?;
+
+        let certificates = bundle.certificates()?;
+        if certificates.is_empty() {
+            for key in &self.keys {
+                let key = PKey::public_key_from_der(key)
+                    .map_err(|e| Error::ParseError(format!("Invalid signing key: {}", e)))?;
+                if verify_sha256(&key, artifact, &signature)? {
+                    return Ok(self.log_keys.is_empty()
+                        || self.logged_time(artifact, &signature, bundle)?.is_some());
+                }
+            }
+            return Ok(false);
+        }
+
+        // keyless signatures are checked at the time they were logged
+        if self.log_keys.is_empty() {
+            return Err(Error::VerificationError(
+                "Keyless signatures need a trusted transparency log".to_string(),
+            ));
+        }
+        let Some(signing_time) = self.logged_time(artifact, &signature, bundle)? else {
+            return Ok(false);
+        };
+
+        let chain = certificates
+            .iter()
+            .map(|der| {
+                X509::from_der(der)
+                    .map_err(|e| Error::ParseError(format!("Invalid certificate: {}", e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let chained = self
+            .anchors
+            .verify_any(TrustAnchorKind::FulcioRoot, |root| {
+                verify_cert_chain(&chain, &X509::from_der(&root.der)?, signing_time)
+            })
+            .ok_or_else(|| Error::VerificationError("No trusted Fulcio roots".to_string()))??;
+        if !chained || !self.trusts_identity(&chain[0], &certificates[0])? {
+            return Ok(false);
+        }
+
+        let signing_key = chain[0].public_key()?;
+        verify_sha256(&signing_key, artifact, &signature)
+    }
+
+    /// Reads the artifact at `path`, and verifies it against the bundle
+    /// saved next to it (see `bundle_path()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::VerificationError` if the artifact doesn't have a
+    /// trusted signature, an `Error::IoError` if the artifact or its bundle
+    /// cannot be read, and the errors of `verify()`.
+    pub fn read_verified<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        let artifact = std::fs::read(path)?;
+        if !self.verify(&artifact, &SigstoreBundle::load(path)?)? {
+            return Err(Error::VerificationError(format!(
+                "{} doesn't have a trusted signature",
+                path.display()
+            )));
+        }
+        Ok(artifact)
+    }
+
+    /// Returns the time at which the `signature` over `artifact` was
+    /// recorded in a trusted log, if any of the bundle's entries records it.
+    fn logged_time(
+        &self,
+        artifact: &[u8],
+        signature: &[u8],
+        bundle: &SigstoreBundle,
+    ) -> Result<Option<u64>> {
+        for entry in &bundle.verification_material.tlog_entries {
+            let Some(promise) = &entry.inclusion_promise else {
+                continue;
+            };
+            let log_id = hex::encode(decode("log ID", &entry.log_id.key_id)?);
+            let Some(log_key) = self
+                .log_keys
+                .iter()
+                .find(|key| hex::encode(Sha256::digest(key)) == log_id)
+            else {
+                continue;
+            };
+            let log_key = PKey::public_key_from_der(log_key)
+                .map_err(|e| Error::ParseError(format!("Invalid log public key: {}", e)))?;
+
+            // the SET signs the canonical JSON of the entry
+            let payload = format!(
+                r#"{{"body":"{}","integratedTime":{},"logID":"{}","logIndex":{}}}"#,
+                entry.canonicalized_body, entry.integrated_time, log_id, entry.log_index
+            );
+            let set = decode("signed entry timestamp", &promise.signed_entry_timestamp)?;
+            if !verify_sha256(&log_key, payload.as_bytes(), &set)? {
+                continue;
+            }
+
+            let body: serde_json::Value =
+                serde_json::from_slice(&decode("log entry body", &entry.canonicalized_body)?)
+                    .map_err(|e| Error::ParseError(format!("Invalid log entry body: {}", e)))?;
+            if body_records(&body, artifact)
+                && body["spec"]["signature"]["content"].as_str()
+                    == Some(&STANDARD.encode(signature))
+            {
+                return Ok(Some(entry.integrated_time));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns whether the signing certificate `cert` (with DER encoding
+    /// `der`) was issued to a trusted identity.
+    fn trusts_identity(&self, cert: &X509, der: &[u8]) -> Result<bool> {
+        let Some(issuer) = fulcio_issuer(der) else {
+            return Ok(false);
+        };
+        let subjects: Vec<String> = cert
+            .subject_alt_names()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| name.email().or_else(|| name.uri()).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(self
+            .identities
+            .iter()
+            .any(|id| id.issuer == issuer && subjects.contains(&id.subject)))
+    }
+}
+
+/// Returns the path of the bundle saved next to the artifact at `path`.
+pub fn bundle_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut bundle = path.as_ref().as_os_str().to_owned();
+    bundle.push(".");
+    bundle.push(BUNDLE_FILE_EXT);
+    PathBuf::from(bundle)
+}
+
+/// Returns the OIDC issuer of the DER-encoded Fulcio certificate `der`.
+fn fulcio_issuer(der: &[u8]) -> Option<String> {
+    let extensions = certificate_extensions(der).ok()?;
+    let extension = |oid: &[u8]| {
+        extensions
+            .iter()
+            .find(|(extension_oid, _)| *extension_oid == oid)
+            .map(|(_, value)| *value)
+    };
+
+    let issuer = match extension(FULCIO_ISSUER_OID) {
+        Some(value) => DerReader::new(value).read(TAG_UTF8_STRING).ok()?,
+        None => extension(FULCIO_ISSUER_V1_OID)?,
+    };
+    String::from_utf8(issuer.to_vec()).ok()
+}
+
+/// Verifies the SHA-256 `signature` over `data` with `key`.
+fn verify_sha256(key: &PKeyRef<Public>, data: &[u8], signature: &[u8]) -> Result<bool> {
+    let mut verifier = Verifier::new(MessageDigest::sha256(), key)?;
+    verifier.update(data)?;
+    Ok(verifier.verify(signature).unwrap_or(false))
+}
+
+/// Decodes the base64-encoded `field` of a bundle.
+fn decode(field: &str, value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| Error::ParseError(format!("Invalid bundle {}: {}", field, e)))
+}
+
+/// Deserializes a protobuf JSON 64-bit integer, which may be a string.
+fn int64<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Int64 {
+        Number(u64),
+        String(String),
+    }
+
+    match Int64::deserialize(deserializer)? {
+        Int64::Number(n) => Ok(n),
+        Int64::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::{TAG_OCTET_STRING, TAG_OID, tlv};
+    use openssl::asn1::{Asn1Object, Asn1OctetString, Asn1Time};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+    use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+    use openssl::x509::{X509Extension, X509NameBuilder};
+
+    const ISSUER: &str = "https://accounts.example.com";
+    const SUBJECT: &str = "admin@example.com";
+
+    fn ec_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn sign_sha256(key: &PKey<Private>, data: &[u8]) -> Vec<u8> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(data).unwrap();
+        signer.sign_to_vec().unwrap()
+    }
+
+    /// A test Sigstore deployment, with a Fulcio root and a Rekor log.
+    struct TestSigstore {
+        root_key: PKey<Private>,
+        root: X509,
+        log_key: PKey<Private>,
+    }
+
+    impl TestSigstore {
+        fn new() -> Self {
+            let root_key = ec_key();
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", "Test Fulcio Root").unwrap();
+            let name = name.build();
+
+            let mut root = X509::builder().unwrap();
+            root.set_version(2).unwrap();
+            root.set_subject_name(&name).unwrap();
+            root.set_issuer_name(&name).unwrap();
+            root.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            root.set_not_after(&Asn1Time::days_from_now(5).unwrap())
+                .unwrap();
+            root.set_pubkey(&root_key).unwrap();
+            root.append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                .unwrap();
+            root.sign(&root_key, MessageDigest::sha256()).unwrap();
+
+            Self {
+                root_key,
+                root: root.build(),
+                log_key: ec_key(),
+            }
+        }
+
+        /// Returns the DER-encoded public key of the log.
+        fn log_key(&self) -> Vec<u8> {
+            self.log_key.public_key_to_der().unwrap()
+        }
+
+        /// Returns trust anchors with the Fulcio root.
+        fn anchors(&self) -> TrustAnchors {
+            TrustAnchors::new()
+                .with_anchor(TrustAnchorKind::FulcioRoot, &self.root.to_der().unwrap())
+        }
+
+        /// Issues a short-lived signing certificate to `subject`, as
+        /// authenticated by `issuer`.
+        fn issue(&self, key: &PKey<Private>, subject: &str, issuer: &str) -> X509 {
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", "Test Fulcio Root").unwrap();
+
+            let mut cert = X509::builder().unwrap();
+            cert.set_version(2).unwrap();
+            cert.set_subject_name(&X509NameBuilder::new().unwrap().build())
+                .unwrap();
+            cert.set_issuer_name(&name.build()).unwrap();
+            let now = now() as i64;
+            cert.set_not_before(&Asn1Time::from_unix(now - 60).unwrap())
+                .unwrap();
+            cert.set_not_after(&Asn1Time::from_unix(now + 600).unwrap())
+                .unwrap();
+            cert.set_pubkey(key).unwrap();
+            let san = SubjectAlternativeName::new()
+                .email(subject)
+                .build(&cert.x509v3_context(Some(&self.root), None))
+                .unwrap();
+            cert.append_extension(san).unwrap();
+
+            let oid = Asn1Object::from_str("1.3.6.1.4.1.57264.1.8").unwrap();
+            let mut value = vec![TAG_UTF8_STRING, issuer.len() as u8];
+            value.extend(issuer.as_bytes());
+            let value = Asn1OctetString::new_from_bytes(&value).unwrap();
+            cert.append_extension(X509Extension::new_from_der(&oid, false, &value).unwrap())
+                .unwrap();
+
+            cert.sign(&self.root_key, MessageDigest::sha256()).unwrap();
+            cert.build()
+        }
+
+        /// Returns a log entry of the `signature` over `artifact`, signed by
+        /// the log at `integrated_time`.
+        fn tlog_entry(
+            &self,
+            artifact: &[u8],
+            signature: &[u8],
+            integrated_time: u64,
+        ) -> serde_json::Value {
+            let body = STANDARD.encode(
+                serde_json::json!({
+                    "apiVersion": "0.0.1",
+                    "kind": "hashedrekord",
+                    "spec": {
+                        "data": {"hash": {"algorithm": "sha256", "value": hex::encode(Sha256::digest(artifact))}},
+                        "signature": {"content": STANDARD.encode(signature)},
+                    },
+                })
+                .to_string(),
+            );
+            let log_id = Sha256::digest(self.log_key());
+            let payload = format!(
+                r#"{{"body":"{}","integratedTime":{},"logID":"{}","logIndex":{}}}"#,
+                body,
+                integrated_time,
+                hex::encode(log_id),
+                42
+            );
+            serde_json::json!({
+                "logIndex": "42",
+                "logId": {"keyId": STANDARD.encode(log_id)},
+                "kindVersion": {"kind": "hashedrekord", "version": "0.0.1"},
+                "integratedTime": integrated_time.to_string(),
+                "inclusionPromise": {
+                    "signedEntryTimestamp": STANDARD.encode(sign_sha256(&self.log_key, payload.as_bytes())),
+                },
+                "canonicalizedBody": body,
+            })
+        }
+
+        /// Signs `artifact` keylessly as `subject`, and returns the bundle.
+        fn sign(&self, artifact: &[u8], subject: &str) -> SigstoreBundle {
+            let key = ec_key();
+            let cert = self.issue(&key, subject, ISSUER);
+            let signature = sign_sha256(&key, artifact);
+            let json = serde_json::json!({
+                "mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json",
+                "verificationMaterial": {
+                    "certificate": {"rawBytes": STANDARD.encode(cert.to_der().unwrap())},
+                    "tlogEntries": [self.tlog_entry(artifact, &signature, now())],
+                },
+                "messageSignature": {
+                    "messageDigest": {"algorithm": SHA2_256, "digest": STANDARD.encode(Sha256::digest(artifact))},
+                    "signature": STANDARD.encode(&signature),
+                },
+            });
+            SigstoreBundle::from_json(&json.to_string()).unwrap()
+        }
+    }
+
+    /// Signs `artifact` with `key`, without a log entry.
+    fn sign_with_key(artifact: &[u8], key: &PKey<Private>) -> SigstoreBundle {
+        let json = serde_json::json!({
+            "mediaType": "application/vnd.dev.sigstore.bundle+json;version=0.2",
+            "verificationMaterial": {"publicKey": {"hint": ""}},
+            "messageSignature": {"signature": STANDARD.encode(sign_sha256(key, artifact))},
+        });
+        SigstoreBundle::from_json(&json.to_string()).unwrap()
+    }
+
+    fn verifier(sigstore: &TestSigstore) -> ArtifactVerifier {
+        ArtifactVerifier::new()
+            .with_trust_anchors(sigstore.anchors())
+            .with_identity(SUBJECT, ISSUER)
+            .with_log_key(&sigstore.log_key())
+    }
+
+    #[test]
+    fn test_verify_keyless() -> Result<()> {
+        let sigstore = TestSigstore::new();
+        let bundle = sigstore.sign(b"policy", SUBJECT);
+        assert!(verifier(&sigstore).verify(b"policy", &bundle)?);
+        assert!(!verifier(&sigstore).verify(b"tampered", &bundle)?);
+
+        // another signer
+        let other = sigstore.sign(b"policy", "mallory@example.com");
+        assert!(!verifier(&sigstore).verify(b"policy", &other)?);
+        let verifier = verifier(&sigstore).with_identity("mallory@example.com", "https://other");
+        assert!(!verifier.verify(b"policy", &other)?);
+
+        // another Fulcio root
+        let verifier = verifier.with_trust_anchors(TestSigstore::new().anchors());
+        assert!(!verifier.verify(b"policy", &bundle)?);
+        assert!(
+            ArtifactVerifier::new()
+                .with_identity(SUBJECT, ISSUER)
+                .with_log_key(&sigstore.log_key())
+                .verify(b"policy", &bundle)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_keyless_log() -> Result<()> {
+        let sigstore = TestSigstore::new();
+        let mut bundle = sigstore.sign(b"policy", SUBJECT);
+
+        // the log isn't trusted
+        let untrusted = ArtifactVerifier::new()
+            .with_trust_anchors(sigstore.anchors())
+            .with_identity(SUBJECT, ISSUER);
+        assert!(untrusted.verify(b"policy", &bundle).is_err());
+        let untrusted = untrusted.with_log_key(&TestSigstore::new().log_key());
+        assert!(!untrusted.verify(b"policy", &bundle)?);
+
+        // the entry was logged after the certificate expired
+        let signature = decode("", &bundle.message_signature.as_ref().unwrap().signature)?;
+        let entry = sigstore.tlog_entry(b"policy", &signature, now() + 3600);
+        bundle.verification_material.tlog_entries = vec![serde_json::from_value(entry).unwrap()];
+        assert!(!verifier(&sigstore).verify(b"policy", &bundle)?);
+
+        // the entry records another signature
+        let entry = sigstore.tlog_entry(b"policy", b"other", now());
+        bundle.verification_material.tlog_entries = vec![serde_json::from_value(entry).unwrap()];
+        assert!(!verifier(&sigstore).verify(b"policy", &bundle)?);
+
+        // the entry's time was tampered with
+        let mut entry: TlogEntry =
+            serde_json::from_value(sigstore.tlog_entry(b"policy", &signature, now())).unwrap();
+        entry.integrated_time -= 1;
+        bundle.verification_material.tlog_entries = vec![entry];
+        assert!(!verifier(&sigstore).verify(b"policy", &bundle)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_key() -> Result<()> {
+        let key = ec_key();
+        let bundle = sign_with_key(b"allow-list", &key);
+        let verifier = ArtifactVerifier::new().with_key(&key.public_key_to_der()?);
+        assert!(verifier.verify(b"allow-list", &bundle)?);
+        assert!(!verifier.verify(b"tampered", &bundle)?);
+        assert!(!ArtifactVerifier::new().verify(b"allow-list", &bundle)?);
+
+        // the verifier requires a log entry
+        let verifier = verifier.with_log_key(&TestSigstore::new().log_key());
+        assert!(!verifier.verify(b"allow-list", &bundle)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fulcio_issuer() -> Result<()> {
+        let sigstore = TestSigstore::new();
+        let cert = sigstore.issue(&ec_key(), SUBJECT, ISSUER);
+        assert_eq!(fulcio_issuer(&cert.to_der()?).as_deref(), Some(ISSUER));
+
+        // an extension embedding the encoding of an issuer extension is
+        // skipped
+        let evil = "https://evil.example.com";
+        let mut decoy = tlv(TAG_OID, FULCIO_ISSUER_OID);
+        decoy.extend(tlv(
+            TAG_OCTET_STRING,
+            &tlv(TAG_UTF8_STRING, evil.as_bytes()),
+        ));
+        let mut cert = X509::builder()?;
+        cert.set_version(2)?;
+        cert.set_subject_name(sigstore.root.subject_name())?;
+        cert.set_issuer_name(sigstore.root.subject_name())?;
+        cert.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+        cert.set_not_after(&*Asn1Time::days_from_now(1)?)?;
+        cert.set_pubkey(&ec_key())?;
+        let oid = Asn1Object::from_str("1.3.6.1.4.1.57264.1.99")?;
+        let value = Asn1OctetString::new_from_bytes(&decoy)?;
+        cert.append_extension(X509Extension::new_from_der(&oid, false, &value)?)?;
+        cert.sign(&sigstore.root_key, MessageDigest::sha256())?;
+        assert_eq!(fulcio_issuer(&cert.build().to_der()?), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_verified() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tdx-sigstore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("policy.toml");
+        assert_eq!(bundle_path(&path), dir.join("policy.toml.sigstore.json"));
+
+        let key = ec_key();
+        let verifier = ArtifactVerifier::new().with_key(&key.public_key_to_der()?);
+        std::fs::write(&path, b"allow_debug = false\n")?;
+        let bundle = serde_json::json!({
+            "mediaType": "application/vnd.dev.sigstore.bundle+json;version=0.2",
+            "verificationMaterial": {"publicKey": {"hint": ""}},
+            "messageSignature": {"signature": STANDARD.encode(sign_sha256(&key, b"allow_debug = false\n"))},
+        });
+        std::fs::write(bundle_path(&path), bundle.to_string())?;
+        assert_eq!(verifier.read_verified(&path)?, b"allow_debug = false\n");
+
+        std::fs::write(&path, b"allow_debug = true\n")?;
+        assert!(
+            verifier
+                .read_verified(&path)
+                .is_err_and(|e| e.is_verification_failure())
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() {
+        let bundle = SigstoreBundle::from_json(
+            r#"{
+                "mediaType": "application/vnd.dev.sigstore.bundle+json;version=0.1",
+                "verificationMaterial": {
+                    "x509CertificateChain": {"certificates": [{"rawBytes": "AAE="}, {"rawBytes": "Ag=="}]},
+                    "tlogEntries": [{
+                        "logIndex": 7,
+                        "logId": {"keyId": "AA=="},
+                        "integratedTime": "1750000000",
+                        "canonicalizedBody": ""
+                    }]
+                },
+                "dsseEnvelope": {}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(bundle.certificates().unwrap(), vec![vec![0, 1], vec![2]]);
+        let entry = &bundle.verification_material.tlog_entries[0];
+        assert_eq!((entry.log_index, entry.integrated_time), (7, 1_750_000_000));
+        assert!(
+            ArtifactVerifier::new()
+                .verify(b"", &bundle)
+                .is_err_and(|e| e.is_not_supported())
+        );
+
+        assert!(SigstoreBundle::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,611 @@
+//! # RFC 3161 Evidence Timestamps
+//!
+//! This module requests and verifies RFC 3161 timestamps over attestation
+//! evidence, for audit scenarios where evidence is stored and appraised long
+//! after it was collected. A timestamp authority (TSA) signs the digest of
+//! the evidence together with the time, proving that the evidence existed at
+//! that time.
+//!
+//! Timestamp tokens are requested with a `TsaClient` (when compiled with the
+//! `tsa-timestamping` feature), or with any other transport from the request
+//! returned by `timestamp_request()`, and are verified against a trusted TSA
+//! root with `verify_timestamp()`. Evidence bundles carry a timestamp over
+//! their quote (see `evidence::Bundle::with_timestamp()`), which
+//! `Bundle::verify()` checks against the policy's TSA roots.
+//!
+//! ## Example Usage
+//!
+//! ```ignore
+//! use tdx_workload_attestation::evidence::{Bundle, Policy};
+//! use tdx_workload_attestation::verification::timestamp::{Timestamp, TsaClient};
+//!
+//! // When the evidence is collected
+//! let bundle = Bundle::from_bytes(&std::fs::read("bundle.cbor").unwrap()).unwrap();
+//! let bundle = bundle
+//!     .request_timestamp(&TsaClient::new("https://tsa.example.com"))
+//!     .unwrap();
+//!
+//! // Much later, appraise the evidence at the time it was timestamped
+//! let token = bundle.timestamp.as_ref().unwrap();
+//! let timestamped_at = Timestamp::from_token(token).unwrap().gen_time;
+//! let policy = Policy::new()
+//!     .with_collateral_dir("/etc/tdx-workload-attestation")
+//!     .unwrap()
+//!     .with_verification_time(timestamped_at);
+//! let verdict = bundle.verify(&policy).unwrap();
+//! assert!(verdict.check("timestamp").is_some_and(|check| check.passed));
+//! ```
+//!
+//! # Notes
+//! - `Timestamp::from_token()` doesn't verify the token: its time should
+//!   only be relied on once `verify_timestamp()` (or the `timestamp` check of
+//!   `Bundle::verify()`) passes.
+//! - The TSA's certificate is checked for expiry at the token's time rather
+//!   than the current time, so that archived tokens remain verifiable after
+//!   the certificate expires.
+//! - Requests use SHA-256 digests. Tokens over SHA-384 and SHA-512 digests
+//!   are also accepted.
+
+use crate::der::{
+    DerReader, SHA256_OID, SHA384_OID, SHA512_OID, TAG_BOOLEAN, TAG_CONTEXT_0,
+    TAG_GENERALIZED_TIME, TAG_INTEGER, TAG_NULL, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_SET,
+    tlv,
+};
+use crate::error::{Error, Result};
+use crate::evidence::tcb::parse_utc_timestamp;
+
+use openssl::cms::{CMSOptions, CmsContentInfo};
+use openssl::hash::{MessageDigest, hash};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::verify::X509VerifyParam;
+use openssl::x509::{X509, X509PurposeId};
+
+/// The media type of RFC 3161 timestamp requests.
+pub const TIMESTAMP_QUERY_CONTENT_TYPE: &str = "application/timestamp-query";
+
+/// The media type of RFC 3161 timestamp responses.
+pub const TIMESTAMP_REPLY_CONTENT_TYPE: &str = "application/timestamp-reply";
+
+// The DER-encoded OIDs of CMS signed data and RFC 3161 TSTInfo content
+const SIGNED_DATA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const TST_INFO_OID: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+
+// The PKI statuses of granted timestamp requests
+const STATUS_GRANTED: &[u8] = &[0];
+const STATUS_GRANTED_WITH_MODS: &[u8] = &[1];
+
+/// The contents of an RFC 3161 timestamp token (its `TSTInfo`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timestamp {
+    /// The time at which the data was timestamped, in seconds since the
+    /// Unix epoch.
+    pub gen_time: u64,
+    /// The TSA's serial number of the timestamp (a DER integer's content).
+    pub serial_number: Vec<u8>,
+    /// The DER-encoded OID of the TSA policy the timestamp was issued under.
+    pub policy: Vec<u8>,
+    /// The digest of the timestamped data.
+    pub digest: Vec<u8>,
+    /// The nonce of the timestamp request, if any (a DER integer's content).
+    pub nonce: Option<Vec<u8>>,
+    digest_algorithm: Vec<u8>,
+}
+
+impl Timestamp {
+    /// Parses a DER-encoded timestamp token. The token's signature isn't
+    /// verified (see `verify_timestamp()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the token is malformed, or isn't a
+    /// CMS signed `TSTInfo`.
+    pub fn from_token(token: &[u8]) -> Result<Self> {
+        let mut content_info = DerReader::new(token).read_sequence()?;
+        if content_info.read(TAG_OID)? != SIGNED_DATA_OID {
+            return Err(Error::ParseError(
+                "Timestamp token is not CMS signed data".to_string(),
+            ));
+        }
+
+        let mut signed_data = DerReader::new(content_info.read(TAG_CONTEXT_0)?).read_sequence()?;
+        signed_data.read(TAG_INTEGER)?;
+        signed_data.read(TAG_SET)?;
+
+        let mut content = signed_data.read_sequence()?;
+        if content.read(TAG_OID)? != TST_INFO_OID {
+            return Err(Error::ParseError(
+                "Timestamp token does not hold a TSTInfo".to_string(),
+            ));
+        }
+        let tst_info = DerReader::new(content.read(TAG_CONTEXT_0)?).read(TAG_OCTET_STRING)?;
+
+        Self::from_tst_info(tst_info)
+    }
+
+    /// Parses a DER-encoded `TSTInfo`.
+    fn from_tst_info(der: &[u8]) -> Result<Self> {
+        let mut tst_info = DerReader::new(der).read_sequence()?;
+        tst_info.read(TAG_INTEGER)?;
+        let policy = tst_info.read(TAG_OID)?.to_vec();
+
+        let mut imprint = tst_info.read_sequence()?;
+        let mut algorithm = imprint.read_sequence()?;
+        let digest_algorithm = algorithm.read(TAG_OID)?.to_vec();
+        let digest = imprint.read(TAG_OCTET_STRING)?.to_vec();
+
+        let serial_number = tst_info.read(TAG_INTEGER)?.to_vec();
+        let gen_time = parse_generalized_time(tst_info.read(TAG_GENERALIZED_TIME)?)?;
+
+        // skip the accuracy and ordering
+        tst_info.read_optional(TAG_SEQUENCE)?;
+        tst_info.read_optional(TAG_BOOLEAN)?;
+        let nonce = tst_info.read_optional(TAG_INTEGER)?.map(<[u8]>::to_vec);
+
+        Ok(Self {
+            gen_time,
+            serial_number,
+            policy,
+            digest,
+            nonce,
+            digest_algorithm,
+        })
+    }
+
+    /// Returns whether the timestamp is over `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::NotSupported` if the timestamp's digest algorithm
+    /// isn't SHA-256, SHA-384 or SHA-512.
+    pub fn covers(&self, data: &[u8]) -> Result<bool> {
+        let md = match self.digest_algorithm.as_slice() {
+            SHA256_OID => MessageDigest::sha256(),
+            SHA384_OID => MessageDigest::sha384(),
+            SHA512_OID => MessageDigest::sha512(),
+            _ => {
+                return Err(Error::NotSupported(
+                    "Unsupported timestamp digest algorithm".to_string(),
+                ));
+            }
+        };
+        Ok(*hash(md, data)? == *self.digest)
+    }
+}
+
+/// Returns a DER-encoded RFC 3161 timestamp request for the SHA-256 digest
+/// of `data`, with the given nonce, asking the TSA to include its
+/// certificate in the token.
+pub fn timestamp_request(data: &[u8], nonce: u64) -> Vec<u8> {
+    let digest = openssl::sha::sha256(data);
+
+    let mut request = tlv(TAG_INTEGER, &[1]);
+    request.extend(message_imprint(SHA256_OID, &digest));
+    request.extend(tlv(TAG_INTEGER, &uint_content(nonce)));
+    request.extend(tlv(TAG_BOOLEAN, &[0xff]));
+    tlv(TAG_SEQUENCE, &request)
+}
+
+/// Returns the timestamp token of a DER-encoded RFC 3161 timestamp
+/// response.
+///
+/// # Errors
+///
+/// - `Error::VerificationError` if the TSA rejected the request.
+/// - `Error::ParseError` if the response is malformed.
+pub fn parse_timestamp_response(response: &[u8]) -> Result<Vec<u8>> {
+    let mut response = DerReader::new(response).read_sequence()?;
+    let status = response.read_sequence()?.read(TAG_INTEGER)?;
+    if status != STATUS_GRANTED && status != STATUS_GRANTED_WITH_MODS {
+        return Err(Error::VerificationError(format!(
+            "TSA rejected the timestamp request with status {}",
+            hex::encode(status)
+        )));
+    }
+
+    match response.read_tlv() {
+        Ok((TAG_SEQUENCE, _, token)) => Ok(token.to_vec()),
+        _ => Err(Error::ParseError(
+            "Timestamp response has no timestamp token".to_string(),
+        )),
+    }
+}
+
+/// Verifies a DER-encoded timestamp token over `data`, given the trusted
+/// TSA root certificate.
+///
+/// Returns `Ok(false)` if the token isn't over `data`, or if its signature
+/// or the TSA's certificate chain (checked at the token's time) is invalid.
+///
+/// # Errors
+///
+/// - `Error::ParseError` if the token is malformed.
+/// - `Error::NotSupported` if the token's digest algorithm isn't supported.
+/// - `Error::OpenSslError` if the token cannot be decoded by OpenSSL.
+pub fn verify_timestamp(token: &[u8], data: &[u8], root: &X509) -> Result<bool> {
+    let timestamp = Timestamp::from_token(token)?;
+    if !timestamp.covers(data)? {
+        return Ok(false);
+    }
+
+    let mut param = X509VerifyParam::new()?;
+    param.set_purpose(X509PurposeId::TIMESTAMP_SIGN)?;
+    param.set_time(
+        timestamp
+            .gen_time
+            .try_into()
+            .map_err(|_| Error::ParseError("Timestamp time out of range".to_string()))?,
+    );
+    let mut store = X509StoreBuilder::new()?;
+    store.add_cert(root.clone())?;
+    store.set_param(&param)?;
+    let store = store.build();
+
+    let mut cms = CmsContentInfo::from_der(token)?;
+    let mut content = vec![];
+    if cms
+        .verify(
+            None,
+            Some(&store),
+            None,
+            Some(&mut content),
+            CMSOptions::BINARY,
+        )
+        .is_err()
+    {
+        return Ok(false);
+    }
+
+    // the signed content is the parsed TSTInfo
+    Ok(Timestamp::from_tst_info(&content)? == timestamp)
+}
+
+/// A client for an RFC 3161 timestamp authority.
+#[cfg(feature = "tsa-timestamping")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TsaClient {
+    url: String,
+    retry_policy: crate::retry::RetryPolicy,
+}
+
+#[cfg(feature = "tsa-timestamping")]
+impl TsaClient {
+    /// Creates a new client for the TSA at `url`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            retry_policy: crate::retry::RetryPolicy::default(),
+        }
+    }
+
+    /// Sets the retry policy for requests to the TSA.
+    pub fn with_retry_policy(mut self, retry_policy: crate::retry::RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Requests a timestamp token over `data` from the TSA.
+    ///
+    /// The token's signature isn't verified, but it's checked to be over
+    /// `data` and to answer this request.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::NetworkError` if the token cannot be retrieved after
+    ///   exhausting the `RetryPolicy`.
+    /// - `Error::VerificationError` if the TSA rejected the request, or its
+    ///   token doesn't match the request.
+    /// - `Error::ParseError` if the response is malformed.
+    pub fn timestamp(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use crate::http::{http_client, send};
+
+        let mut nonce = [0u8; 8];
+        openssl::rand::rand_bytes(&mut nonce)?;
+        let nonce = u64::from_be_bytes(nonce);
+        let request = timestamp_request(data, nonce);
+
+        let client = http_client(&self.retry_policy)?;
+        let response = self.retry_policy.run(|| {
+            send(
+                client
+                    .post(&self.url)
+                    .header("Content-Type", TIMESTAMP_QUERY_CONTENT_TYPE)
+                    .header("Accept", TIMESTAMP_REPLY_CONTENT_TYPE)
+                    .body(request.clone()),
+            )
+        })?;
+
+        let token = parse_timestamp_response(&response)?;
+        let timestamp = Timestamp::from_token(&token)?;
+        if !timestamp.covers(data)? || timestamp.nonce != Some(uint_content(nonce)) {
+            return Err(Error::VerificationError(
+                "Timestamp token does not match the request".to_string(),
+            ));
+        }
+        Ok(token)
+    }
+}
+
+/// Encodes a DER `MessageImprint`.
+fn message_imprint(algorithm: &[u8], digest: &[u8]) -> Vec<u8> {
+    let mut algorithm = tlv(TAG_OID, algorithm);
+    algorithm.extend(tlv(TAG_NULL, &[]));
+    let mut imprint = tlv(TAG_SEQUENCE, &algorithm);
+    imprint.extend(tlv(TAG_OCTET_STRING, digest));
+    tlv(TAG_SEQUENCE, &imprint)
+}
+
+/// Returns the content of the DER integer encoding `n`.
+fn uint_content(n: u64) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(7);
+    let mut content = bytes[start..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    content
+}
+
+/// Parses a DER `GeneralizedTime` (`YYYYMMDDhhmmss[.f]Z`) into seconds since
+/// the Unix epoch.
+fn parse_generalized_time(time: &[u8]) -> Result<u64> {
+    let invalid = || Error::ParseError("Invalid timestamp time".to_string());
+
+    let time = std::str::from_utf8(time).map_err(|_| invalid())?;
+    let (date, rest) = (time.get(..8), time.get(8..14));
+    let (Some(date), Some(clock), Some(fraction)) = (date, rest, time.get(14..)) else {
+        return Err(invalid());
+    };
+    parse_utc_timestamp(&format!(
+        "{}-{}-{}T{}:{}:{}{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &clock[..2],
+        &clock[2..4],
+        &clock[4..],
+        fraction
+    ))
+    .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_utils::cert_builder;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::sign::Signer;
+    use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage};
+
+    // The DER-encoded OIDs of the CMS signed attributes, and of
+    // ecdsa-with-SHA256
+    const CONTENT_TYPE_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+    const MESSAGE_DIGEST_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+    const ECDSA_WITH_SHA256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+
+    // 2021-05-03 and 2022-04-26
+    const CERT_NOT_BEFORE: i64 = 1_620_000_000;
+    const CERT_NOT_AFTER: i64 = 1_651_000_000;
+
+    /// A test TSA, whose certificate has long expired.
+    pub(crate) struct TestTsa {
+        pub(crate) root: X509,
+        cert: X509,
+        key: PKey<Private>,
+    }
+
+    fn ec_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn make_cert(subject: &str, key: &PKey<Private>, issuer: Option<&PKey<Private>>) -> X509 {
+        let mut cert = cert_builder(subject, "Test TSA Root", key, 0);
+        cert.set_not_before(&Asn1Time::from_unix(CERT_NOT_BEFORE).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::from_unix(CERT_NOT_AFTER).unwrap())
+            .unwrap();
+        let extension = match issuer {
+            Some(_) => ExtendedKeyUsage::new().critical().time_stamping().build(),
+            None => BasicConstraints::new().critical().ca().build(),
+        };
+        cert.append_extension(extension.unwrap()).unwrap();
+        cert.sign(issuer.unwrap_or(key), MessageDigest::sha256())
+            .unwrap();
+        cert.build()
+    }
+
+    impl TestTsa {
+        pub(crate) fn new() -> Self {
+            let root_key = ec_key();
+            let key = ec_key();
+            Self {
+                root: make_cert("Test TSA Root", &root_key, None),
+                cert: make_cert("Test TSA", &key, Some(&root_key)),
+                key,
+            }
+        }
+
+        /// Returns a `TSTInfo` over `data` at `gen_time`.
+        pub(crate) fn tst_info(data: &[u8], gen_time: i64, nonce: Option<u64>) -> Vec<u8> {
+            let mut tst_info = tlv(TAG_INTEGER, &[1]);
+            tst_info.extend(tlv(TAG_OID, &[0x2a, 0x03, 0x04]));
+            tst_info.extend(message_imprint(SHA256_OID, &openssl::sha::sha256(data)));
+            tst_info.extend(tlv(TAG_INTEGER, &[0x2a]));
+            tst_info.extend(tlv(
+                TAG_GENERALIZED_TIME,
+                generalized_time(gen_time).as_bytes(),
+            ));
+            if let Some(nonce) = nonce {
+                tst_info.extend(tlv(TAG_INTEGER, &uint_content(nonce)));
+            }
+            tlv(TAG_SEQUENCE, &tst_info)
+        }
+
+        /// Signs a `TSTInfo` into a timestamp token.
+        pub(crate) fn sign(&self, tst_info: &[u8]) -> Vec<u8> {
+            let attribute = |oid: &[u8], value: Vec<u8>| {
+                let mut attribute = tlv(TAG_OID, oid);
+                attribute.extend(tlv(TAG_SET, &value));
+                tlv(TAG_SEQUENCE, &attribute)
+            };
+            let mut attributes = attribute(CONTENT_TYPE_OID, tlv(TAG_OID, TST_INFO_OID));
+            attributes.extend(attribute(
+                MESSAGE_DIGEST_OID,
+                tlv(TAG_OCTET_STRING, &openssl::sha::sha256(tst_info)),
+            ));
+
+            let mut signer = Signer::new(MessageDigest::sha256(), &self.key).unwrap();
+            let signature = signer
+                .sign_oneshot_to_vec(&tlv(TAG_SET, &attributes))
+                .unwrap();
+
+            let mut sid = self.cert.issuer_name().to_der().unwrap();
+            sid.extend(tlv(TAG_INTEGER, &[1]));
+            let mut signer_info = tlv(TAG_INTEGER, &[1]);
+            signer_info.extend(tlv(TAG_SEQUENCE, &sid));
+            signer_info.extend(tlv(TAG_SEQUENCE, &tlv(TAG_OID, SHA256_OID)));
+            signer_info.extend(tlv(TAG_CONTEXT_0, &attributes));
+            signer_info.extend(tlv(TAG_SEQUENCE, &tlv(TAG_OID, ECDSA_WITH_SHA256_OID)));
+            signer_info.extend(tlv(TAG_OCTET_STRING, &signature));
+
+            let mut content = tlv(TAG_OID, TST_INFO_OID);
+            content.extend(tlv(TAG_CONTEXT_0, &tlv(TAG_OCTET_STRING, tst_info)));
+
+            let mut signed_data = tlv(TAG_INTEGER, &[3]);
+            signed_data.extend(tlv(TAG_SET, &tlv(TAG_SEQUENCE, &tlv(TAG_OID, SHA256_OID))));
+            signed_data.extend(tlv(TAG_SEQUENCE, &content));
+            signed_data.extend(tlv(TAG_CONTEXT_0, &self.cert.to_der().unwrap()));
+            signed_data.extend(tlv(TAG_SET, &tlv(TAG_SEQUENCE, &signer_info)));
+
+            let mut content_info = tlv(TAG_OID, SIGNED_DATA_OID);
+            content_info.extend(tlv(TAG_CONTEXT_0, &tlv(TAG_SEQUENCE, &signed_data)));
+            tlv(TAG_SEQUENCE, &content_info)
+        }
+
+        /// Returns a timestamp token over `data` at `gen_time`.
+        pub(crate) fn timestamp(&self, data: &[u8], gen_time: i64) -> Vec<u8> {
+            self.sign(&Self::tst_info(data, gen_time, None))
+        }
+    }
+
+    fn generalized_time(unix_time: i64) -> String {
+        let (days, secs) = (unix_time / 86_400, unix_time % 86_400);
+        let (y, m, d) = civil_from_days(days);
+        format!(
+            "{:04}{:02}{:02}{:02}{:02}{:02}Z",
+            y,
+            m,
+            d,
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    }
+
+    // Howard Hinnant's civil_from_days algorithm
+    fn civil_from_days(days: i64) -> (i64, i64, i64) {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = doy - (153 * mp + 2) / 5 + 1;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 };
+        (yoe + era * 400 + i64::from(m <= 2), m, d)
+    }
+
+    #[test]
+    fn test_timestamp_request() -> Result<()> {
+        let request = timestamp_request(b"evidence", 0x80);
+
+        let mut reader = DerReader::new(&request).read_sequence()?;
+        assert_eq!(reader.read(TAG_INTEGER)?, [1]);
+        let mut imprint = reader.read_sequence()?;
+        assert_eq!(imprint.read_sequence()?.read(TAG_OID)?, SHA256_OID);
+        assert_eq!(
+            imprint.read(TAG_OCTET_STRING)?,
+            openssl::sha::sha256(b"evidence")
+        );
+        assert_eq!(reader.read(TAG_INTEGER)?, [0, 0x80]);
+        assert_eq!(reader.read(TAG_BOOLEAN)?, [0xff]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_timestamp_response() -> Result<()> {
+        let tsa = TestTsa::new();
+        let token = tsa.timestamp(b"evidence", 1_640_000_000);
+
+        let mut response = tlv(TAG_SEQUENCE, &tlv(TAG_INTEGER, &[0]));
+        response.extend(&token);
+        assert_eq!(
+            parse_timestamp_response(&tlv(TAG_SEQUENCE, &response))?,
+            token
+        );
+
+        // a rejection
+        let response = tlv(TAG_SEQUENCE, &tlv(TAG_SEQUENCE, &tlv(TAG_INTEGER, &[2])));
+        assert!(matches!(
+            parse_timestamp_response(&response),
+            Err(Error::VerificationError(_))
+        ));
+
+        assert!(parse_timestamp_response(&[0x30, 0x05, 0x30]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_timestamp() -> Result<()> {
+        let tsa = TestTsa::new();
+        let tst_info = TestTsa::tst_info(b"evidence", 1_640_000_000, Some(0x1234));
+        let timestamp = Timestamp::from_token(&tsa.sign(&tst_info))?;
+
+        assert_eq!(timestamp.gen_time, 1_640_000_000);
+        assert_eq!(timestamp.serial_number, [0x2a]);
+        assert_eq!(timestamp.policy, [0x2a, 0x03, 0x04]);
+        assert_eq!(timestamp.nonce, Some(vec![0x12, 0x34]));
+        assert!(timestamp.covers(b"evidence")?);
+        assert!(!timestamp.covers(b"other evidence")?);
+
+        assert_eq!(parse_generalized_time(b"20211220113320.5Z")?, 1_640_000_000);
+        assert!(parse_generalized_time(b"2021122011Z").is_err());
+        assert!(Timestamp::from_token(&tst_info).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_timestamp() -> Result<()> {
+        let tsa = TestTsa::new();
+        let token = tsa.timestamp(b"evidence", 1_640_000_000);
+
+        // the TSA's certificate has expired, but was valid at the token's time
+        assert!(verify_timestamp(&token, b"evidence", &tsa.root)?);
+
+        assert!(!verify_timestamp(&token, b"other evidence", &tsa.root)?);
+        assert!(!verify_timestamp(
+            &token,
+            b"evidence",
+            &TestTsa::new().root
+        )?);
+
+        // a token from after the TSA's certificate expired
+        let token = tsa.timestamp(b"evidence", CERT_NOT_AFTER + 1);
+        assert!(!verify_timestamp(&token, b"evidence", &tsa.root)?);
+
+        // a tampered token
+        let mut token = tsa.timestamp(b"evidence", 1_640_000_000);
+        let time = token
+            .windows(4)
+            .position(|w| w == b"2021")
+            .expect("token has a time");
+        token[time + 3] = b'0';
+        assert!(!verify_timestamp(&token, b"evidence", &tsa.root)?);
+        Ok(())
+    }
+}
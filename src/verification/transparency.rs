@@ -0,0 +1,537 @@
+//! # Transparency Log Verification
+//!
+//! This module checks that artifacts the verifier relies on, such as GCP
+//! launch endorsements or operator-managed golden values (e.g., an
+//! allow-list file, see the `measure::allowlist` module), are recorded in a
+//! Rekor (Sigstore) transparency log. Since logged artifacts can be audited
+//! by anyone, a compromised distribution channel can no longer silently
+//! substitute the reference values a verifier is given.
+//!
+//! A Rekor `LogEntry` (as returned by Rekor's `/api/v1/log/entries` API) is
+//! verified offline against the log's public key:
+//! - the entry's body is a `hashedrekord` record of the artifact's SHA-256
+//!   digest,
+//! - its inclusion proof proves that the entry is a leaf of the log's Merkle
+//!   tree (RFC 9162), and
+//! - the proof's root hash and tree size are those of a checkpoint signed by
+//!   the log.
+//!
+//! Evidence bundles carry the log entries of their launch endorsements (see
+//! `evidence::Endorsement`), which `Bundle::verify()` checks against the
+//! policy's transparency log keys (see `Policy::with_transparency_log_key()`).
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::verification::transparency::{LogEntry, verify_logged};
+//!
+//! let allow_list = std::fs::read("allowlist.toml").unwrap();
+//! let entry = LogEntry::from_json(&std::fs::read_to_string("allowlist.rekor.json").unwrap()).unwrap();
+//! let log_key = std::fs::read("rekor.pub.der").unwrap();
+//!
+//! match verify_logged(&allow_list, &entry, &log_key) {
+//!     Ok(true) => println!("Allow-list is logged."),
+//!     Ok(false) => println!("Allow-list is not logged."),
+//!     Err(e) => eprintln!("Error verifying the log entry: {}", e),
+//! }
+//! ```
+//!
+//! # Notes
+//! - Only the presence of the artifact in the log is verified: the identity
+//!   of the entry's signer isn't checked.
+//! - The entry's signed entry timestamp (SET) isn't verified, since the
+//!   signed checkpoint provides a stronger proof of inclusion.
+//! - Checkpoints must be signed with an ECDSA or RSA key over SHA-256, as
+//!   Rekor's are.
+
+use crate::error::{Error, Result};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, PKeyRef, Public};
+use openssl::sign::Verifier;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// The kind of Rekor entries that record an artifact's digest.
+pub const HASHED_REKORD_KIND: &str = "hashedrekord";
+
+// The prefix of the signature lines of a signed note
+const NOTE_SIGNATURE_PREFIX: &str = "\u{2014} ";
+
+// The RFC 9162 domain separators of leaf and node hashes
+const LEAF_HASH_PREFIX: u8 = 0x00;
+const NODE_HASH_PREFIX: u8 = 0x01;
+
+/// The inclusion proof of a log entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProof {
+    /// The index of the entry in the log's (shard's) tree.
+    pub log_index: u64,
+    /// The hex-encoded root hash of the tree.
+    pub root_hash: String,
+    /// The size of the tree.
+    pub tree_size: u64,
+    /// The hex-encoded hashes of the audit path, from the leaf up.
+    pub hashes: Vec<String>,
+    /// The log's signed checkpoint of the tree, as a signed note.
+    pub checkpoint: String,
+}
+
+/// The verification material of a log entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryVerification {
+    /// The inclusion proof of the entry.
+    pub inclusion_proof: InclusionProof,
+}
+
+/// A Rekor log entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// The base64-encoded (canonicalized JSON) body of the entry.
+    pub body: String,
+    /// The time the entry was integrated into the log, in seconds since the
+    /// Unix epoch.
+    pub integrated_time: u64,
+    /// The hex-encoded ID of the log.
+    #[serde(rename = "logID")]
+    pub log_id: String,
+    /// The global index of the entry in the log.
+    pub log_index: u64,
+    /// The verification material of the entry.
+    pub verification: EntryVerification,
+}
+
+/// A tree checkpoint signed by a log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The origin (name) of the log.
+    pub origin: String,
+    /// The size of the tree.
+    pub tree_size: u64,
+    /// The root hash of the tree.
+    pub root_hash: Vec<u8>,
+}
+
+impl LogEntry {
+    /// Parses a log entry from Rekor's JSON format, either as the entry
+    /// itself or as a map from the entry's UUID to the entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the entry is malformed, or if a map
+    /// doesn't hold exactly one entry.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let invalid = |e: serde_json::Error| Error::ParseError(format!("Invalid log entry: {}", e));
+
+        let value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+        if value.get("body").is_some() {
+            return serde_json::from_value(value).map_err(invalid);
+        }
+
+        let entries: HashMap<String, Self> = serde_json::from_value(value).map_err(invalid)?;
+        let mut entries = entries.into_values();
+        match (entries.next(), entries.next()) {
+            (Some(entry), None) => Ok(entry),
+            _ => Err(Error::ParseError(
+                "Expected exactly one log entry".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the decoded body of the entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the body isn't valid base64.
+    pub fn decode_body(&self) -> Result<Vec<u8>> {
+        STANDARD
+            .decode(&self.body)
+            .map_err(|e| Error::ParseError(format!("Invalid log entry body: {}", e)))
+    }
+
+    /// Returns whether the entry is a `hashedrekord` record of the SHA-256
+    /// digest of `artifact`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::ParseError` if the body is malformed.
+    pub fn records(&self, artifact: &[u8]) -> Result<bool> {
+        let body = serde_json::from_slice(&self.decode_body()?)
+            .map_err(|e| Error::ParseError(format!("Invalid log entry body: {}", e)))?;
+        Ok(body_records(&body, artifact))
+    }
+}
+
+/// Returns whether the (decoded) entry `body` is a `hashedrekord` record of
+/// the SHA-256 digest of `artifact`.
+pub(crate) fn body_records(body: &serde_json::Value, artifact: &[u8]) -> bool {
+    if body["kind"] != HASHED_REKORD_KIND {
+        return false;
+    }
+
+    let hash = &body["spec"]["data"]["hash"];
+    hash["algorithm"] == "sha256"
+        && hash["value"].as_str() == Some(&hex::encode(Sha256::digest(artifact)))
+}
+
+/// Returns the RFC 9162 hash of the tree leaf `data`.
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_HASH_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Returns the RFC 9162 hash of the tree node with children `left` and
+/// `right`.
+fn node_hash(left: &[u8], right: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_HASH_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Verifies the RFC 9162 inclusion proof of the leaf with hash `leaf` at
+/// `index` in a tree of `tree_size` leaves with root hash `root`, given the
+/// audit path `proof`.
+pub fn verify_inclusion(
+    leaf: &[u8; 32],
+    index: u64,
+    tree_size: u64,
+    proof: &[[u8; 32]],
+    root: &[u8],
+) -> bool {
+    if index >= tree_size {
+        return false;
+    }
+
+    let (mut fn_, mut sn) = (index, tree_size - 1);
+    let mut hash = *leaf;
+    for sibling in proof {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            hash = node_hash(sibling, &hash);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && hash == root
+}
+
+/// Verifies the signed note `checkpoint` with the log's public key, and
+/// returns the checkpoint if any of its signatures is by the key.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the checkpoint is malformed.
+pub fn verify_checkpoint(
+    checkpoint: &str,
+    log_key: &PKeyRef<Public>,
+) -> Result<Option<Checkpoint>> {
+    let malformed = || Error::ParseError("Malformed checkpoint".to_string());
+
+    let (text, signatures) = checkpoint.split_once("\n\n").ok_or_else(malformed)?;
+    let text = format!("{}\n", text);
+
+    let mut lines = text.lines();
+    let origin = lines
+        .next()
+        .filter(|l| !l.is_empty())
+        .ok_or_else(malformed)?;
+    let tree_size = lines
+        .next()
+        .and_then(|l| l.parse().ok())
+        .ok_or_else(malformed)?;
+    let root_hash = lines
+        .next()
+        .and_then(|l| STANDARD.decode(l).ok())
+        .ok_or_else(malformed)?;
+
+    let key_hint = &Sha256::digest(log_key.public_key_to_der()?)[..4];
+    for line in signatures.lines().filter(|l| !l.is_empty()) {
+        let signature = line
+            .strip_prefix(NOTE_SIGNATURE_PREFIX)
+            .and_then(|l| l.rsplit_once(' '))
+            .and_then(|(_, sig)| STANDARD.decode(sig).ok())
+            .filter(|sig| sig.len() > 4)
+            .ok_or_else(malformed)?;
+        if &signature[..4] != key_hint {
+            continue;
+        }
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), log_key)?;
+        verifier.update(text.as_bytes())?;
+        if verifier.verify(&signature[4..]).unwrap_or(false) {
+            return Ok(Some(Checkpoint {
+                origin: origin.to_string(),
+                tree_size,
+                root_hash,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Verifies that `entry` records `artifact`, and is included in the log with
+/// the DER-encoded public key (`SubjectPublicKeyInfo`) `log_key`.
+///
+/// # Errors
+///
+/// Returns an `Error::ParseError` if the entry, its inclusion proof or the
+/// log's public key is malformed.
+pub fn verify_logged(artifact: &[u8], entry: &LogEntry, log_key: &[u8]) -> Result<bool> {
+    let log_key = PKey::public_key_from_der(log_key)
+        .map_err(|e| Error::ParseError(format!("Invalid log public key: {}", e)))?;
+    if !entry.records(artifact)? {
+        return Ok(false);
+    }
+
+    let proof = &entry.verification.inclusion_proof;
+    let malformed = || Error::ParseError("Malformed inclusion proof".to_string());
+    let root = hex::decode(&proof.root_hash).map_err(|_| malformed())?;
+    let hashes = proof
+        .hashes
+        .iter()
+        .map(|h| {
+            let mut hash = [0u8; 32];
+            hex::decode_to_slice(h, &mut hash).map_err(|_| malformed())?;
+            Ok(hash)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let leaf = leaf_hash(&entry.decode_body()?);
+    if !verify_inclusion(&leaf, proof.log_index, proof.tree_size, &hashes, &root) {
+        return Ok(false);
+    }
+
+    Ok(verify_checkpoint(&proof.checkpoint, &log_key)?
+        .is_some_and(|c| c.tree_size == proof.tree_size && c.root_hash == root))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+
+    /// A test log with a single tree.
+    pub(crate) struct TestLog {
+        key: PKey<Private>,
+        leaves: Vec<Vec<u8>>,
+    }
+
+    /// Returns the RFC 9162 root hash of `leaves`.
+    fn tree_hash(leaves: &[Vec<u8>]) -> [u8; 32] {
+        if leaves.len() == 1 {
+            return leaf_hash(&leaves[0]);
+        }
+        let k = leaves.len().next_power_of_two() / 2;
+        node_hash(&tree_hash(&leaves[..k]), &tree_hash(&leaves[k..]))
+    }
+
+    /// Returns the RFC 9162 audit path of the leaf at `index`.
+    fn audit_path(index: usize, leaves: &[Vec<u8>]) -> Vec<[u8; 32]> {
+        if leaves.len() == 1 {
+            return vec![];
+        }
+        let k = leaves.len().next_power_of_two() / 2;
+        if index < k {
+            let mut path = audit_path(index, &leaves[..k]);
+            path.push(tree_hash(&leaves[k..]));
+            path
+        } else {
+            let mut path = audit_path(index - k, &leaves[k..]);
+            path.push(tree_hash(&leaves[..k]));
+            path
+        }
+    }
+
+    /// Returns the body of a `hashedrekord` entry of `artifact`.
+    fn hashed_rekord(artifact: &[u8]) -> Vec<u8> {
+        format!(
+            r#"{{"apiVersion":"0.0.1","kind":"hashedrekord","spec":{{"data":{{"hash":{{"algorithm":"sha256","value":"{}"}}}}}}}}"#,
+            hex::encode(Sha256::digest(artifact))
+        )
+        .into_bytes()
+    }
+
+    impl TestLog {
+        /// Creates a log of `size` leaves, which records `artifacts` first.
+        pub(crate) fn new(artifacts: &[&[u8]], size: usize) -> Self {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+            let mut leaves: Vec<Vec<u8>> = artifacts.iter().map(|a| hashed_rekord(a)).collect();
+            while leaves.len() < size {
+                leaves.push(hashed_rekord(&leaves.len().to_be_bytes()));
+            }
+            Self { key, leaves }
+        }
+
+        /// Returns the DER-encoded public key of the log.
+        pub(crate) fn public_key(&self) -> Vec<u8> {
+            self.key.public_key_to_der().unwrap()
+        }
+
+        /// Returns the signed checkpoint of the log's tree.
+        fn checkpoint(&self, key: &PKey<Private>) -> String {
+            let text = format!(
+                "test.log - 1\n{}\n{}\n",
+                self.leaves.len(),
+                STANDARD.encode(tree_hash(&self.leaves))
+            );
+            let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+            signer.update(text.as_bytes()).unwrap();
+            let hint = &Sha256::digest(key.public_key_to_der().unwrap())[..4];
+            let signature = [hint, &signer.sign_to_vec().unwrap()].concat();
+            format!(
+                "{}\n\u{2014} test.log {}\n",
+                text,
+                STANDARD.encode(signature)
+            )
+        }
+
+        /// Returns the Rekor JSON of the entry at `index`.
+        pub(crate) fn entry_json(&self, index: usize) -> String {
+            serde_json::json!({
+                "24296fb24b8ad77a": {
+                    "body": STANDARD.encode(&self.leaves[index]),
+                    "integratedTime": 1_750_000_000,
+                    "logID": "c0d23d6ad406973f",
+                    "logIndex": 1000 + index,
+                    "verification": {
+                        "inclusionProof": {
+                            "logIndex": index,
+                            "rootHash": hex::encode(tree_hash(&self.leaves)),
+                            "treeSize": self.leaves.len(),
+                            "hashes": audit_path(index, &self.leaves)
+                                .iter()
+                                .map(hex::encode)
+                                .collect::<Vec<_>>(),
+                            "checkpoint": self.checkpoint(&self.key),
+                        },
+                        "signedEntryTimestamp": "",
+                    },
+                }
+            })
+            .to_string()
+        }
+    }
+
+    #[test]
+    fn test_verify_inclusion() {
+        for size in 1..=9 {
+            let leaves: Vec<Vec<u8>> = (0..size).map(|i: u8| vec![i]).collect();
+            let root = tree_hash(&leaves);
+            for index in 0..size as usize {
+                let leaf = leaf_hash(&leaves[index]);
+                let path = audit_path(index, &leaves);
+                let (index, size) = (index as u64, size as u64);
+                assert!(verify_inclusion(&leaf, index, size, &path, &root));
+
+                assert!(!verify_inclusion(&leaf, size, size, &path, &root));
+                if size > 1 {
+                    assert!(!verify_inclusion(
+                        &leaf,
+                        (index + 1) % size,
+                        size,
+                        &path,
+                        &root
+                    ));
+                    assert!(!verify_inclusion(&leaf, index, size, &path[1..], &root));
+                }
+                assert!(!verify_inclusion(
+                    &leaf_hash(b"other"),
+                    index,
+                    size,
+                    &path,
+                    &root
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_checkpoint() -> Result<()> {
+        let log = TestLog::new(&[], 3);
+        let log_key = PKey::public_key_from_der(&log.public_key())?;
+
+        let checkpoint = verify_checkpoint(&log.checkpoint(&log.key), &log_key)?.unwrap();
+        assert_eq!(checkpoint.origin, "test.log - 1");
+        assert_eq!(checkpoint.tree_size, 3);
+        assert_eq!(checkpoint.root_hash, tree_hash(&log.leaves));
+
+        // signed by another log
+        let other = TestLog::new(&[], 3);
+        assert!(verify_checkpoint(&other.checkpoint(&other.key), &log_key)?.is_none());
+
+        // tampered with
+        let tampered = log.checkpoint(&log.key).replacen("\n3\n", "\n4\n", 1);
+        assert!(verify_checkpoint(&tampered, &log_key)?.is_none());
+
+        assert!(verify_checkpoint("test.log\n3\n", &log_key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_logged() -> Result<()> {
+        let log = TestLog::new(&[b"endorsement", b"allow-list"], 6);
+
+        let entry = LogEntry::from_json(&log.entry_json(1))?;
+        assert_eq!(entry.log_index, 1001);
+        assert!(entry.records(b"allow-list")?);
+        assert!(verify_logged(b"allow-list", &entry, &log.public_key())?);
+
+        // the entry is for another artifact
+        assert!(!verify_logged(b"endorsement", &entry, &log.public_key())?);
+
+        // the entry is from another log
+        let other = TestLog::new(&[b"endorsement", b"allow-list"], 6);
+        assert!(!verify_logged(b"allow-list", &entry, &other.public_key())?);
+
+        // the proof is for another leaf
+        let mut tampered = entry.clone();
+        tampered.verification.inclusion_proof.log_index = 0;
+        assert!(!verify_logged(b"allow-list", &tampered, &log.public_key())?);
+
+        // the proof doesn't match the checkpoint
+        let mut tampered = entry;
+        tampered.verification.inclusion_proof.checkpoint =
+            TestLog::new(&[], 6).checkpoint(&log.key);
+        assert!(!verify_logged(b"allow-list", &tampered, &log.public_key())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() -> Result<()> {
+        let log = TestLog::new(&[b"artifact"], 1);
+        let json = log.entry_json(0);
+        let entry = LogEntry::from_json(&json)?;
+
+        // the entry can also be given without its UUID
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let inner = value.as_object().unwrap().values().next().unwrap();
+        assert_eq!(LogEntry::from_json(&inner.to_string())?, entry);
+
+        assert!(LogEntry::from_json("{}").is_err());
+        assert!(LogEntry::from_json("[]").is_err());
+        Ok(())
+    }
+}
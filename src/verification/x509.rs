@@ -27,8 +27,8 @@
 //! }
 //! ```
 
-use crate::error::{Error, Result};
-use openssl::asn1::Asn1Time;
+use crate::error::{Error, ErrorKind, Result};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
 use openssl::pkey::{PKey, Public};
 use openssl::x509::{X509, X509VerifyResult};
 use std::fs::File;
@@ -43,10 +43,13 @@ use std::path::Path;
 ///
 /// # Errors
 ///
-/// Returns an `Error::SignatureError` if the public key cannot be extracted.
+/// Returns an `Error::CertificateError` of kind `ErrorKind::Signature` if the
+/// public key cannot be extracted.
 pub fn get_x509_pubkey(cert: &X509) -> Result<PKey<Public>> {
-    cert.public_key()
-        .map_err(|e| Error::SignatureError(e.to_string()))
+    cert.public_key().map_err(|source| Error::CertificateError {
+        kind: ErrorKind::Signature,
+        source,
+    })
 }
 
 /// Parses an X.509 certificate from DER-encoded bytes.
@@ -57,9 +60,13 @@ pub fn get_x509_pubkey(cert: &X509) -> Result<PKey<Public>> {
 ///
 /// # Errors
 ///
-/// Returns an `Error::ParseError` if the certificate cannot be parsed.
+/// Returns an `Error::CertificateError` of kind `ErrorKind::Parse` if the
+/// certificate cannot be parsed.
 pub fn x509_from_der_bytes(der_bytes: &[u8]) -> Result<X509> {
-    X509::from_der(der_bytes).map_err(|e| Error::ParseError(e.to_string()))
+    X509::from_der(der_bytes).map_err(|source| Error::CertificateError {
+        kind: ErrorKind::Parse,
+        source,
+    })
 }
 
 /// Loads an X.509 certificate from a file in DER format.
@@ -72,7 +79,7 @@ pub fn x509_from_der_bytes(der_bytes: &[u8]) -> Result<X509> {
 ///
 /// - `Error::NotSupported` if the file is a symbolic link.
 /// - `Error::IoError` if the file cannot be read.
-/// - `Error::ParseError` if the certificate cannot be parsed.
+/// - `Error::CertificateError` if the certificate cannot be parsed.
 pub fn load_x509_der(cert_path: &str) -> Result<X509> {
     let path = Path::new(cert_path);
 
@@ -102,9 +109,30 @@ pub fn load_x509_der(cert_path: &str) -> Result<X509> {
 ///
 /// # Errors
 ///
-/// - `Error::VerificationError` if the issuer or validity verification fails.
-/// - `Error::SignatureError` if the signature verification fails.
+/// - `Error::VerificationError` if the issuer verification fails.
+/// - `Error::CertificateError` of kind `ErrorKind::Verification` if the
+///   signature cannot be checked.
+/// - `Error::OpenSslError` if the validity period cannot be checked.
 pub fn verify_x509_cert(cert: &X509, issuer_cert: &X509) -> Result<bool> {
+    let now = Asn1Time::days_from_now(0).map_err(Error::OpenSslError)?;
+    verify_cert_at(cert, issuer_cert, &now)
+}
+
+/// Verifies an X.509 certificate's signature and expiry, like
+/// `verify_x509_cert()`, at `unix_time` (in seconds since the Unix epoch)
+/// rather than the current time.
+///
+/// # Errors
+///
+/// Same as `verify_x509_cert()`.
+pub fn verify_x509_cert_at(cert: &X509, issuer_cert: &X509, unix_time: u64) -> Result<bool> {
+    let time = i64::try_from(unix_time)
+        .map_err(|_| Error::VerificationError("Verification time out of range".to_string()))?;
+    let time = Asn1Time::from_unix(time).map_err(Error::OpenSslError)?;
+    verify_cert_at(cert, issuer_cert, &time)
+}
+
+fn verify_cert_at(cert: &X509, issuer_cert: &X509, now: &Asn1TimeRef) -> Result<bool> {
     // First, check the issuer
     match issuer_cert.issued(cert) {
         X509VerifyResult::OK => {} // valid issuer so pass through
@@ -116,7 +144,6 @@ pub fn verify_x509_cert(cert: &X509, issuer_cert: &X509) -> Result<bool> {
     };
 
     // Second, check the certificate's validity period
-    let now = Asn1Time::days_from_now(0).map_err(Error::OpenSslError)?;
     if now
         .compare(cert.not_before())
         .map_err(Error::OpenSslError)?
@@ -133,7 +160,10 @@ pub fn verify_x509_cert(cert: &X509, issuer_cert: &X509) -> Result<bool> {
     let issuer_pkey = get_x509_pubkey(issuer_cert)?;
 
     cert.verify(&issuer_pkey)
-        .map_err(|e| Error::VerificationError(e.to_string()))
+        .map_err(|source| Error::CertificateError {
+            kind: ErrorKind::Verification,
+            source,
+        })
 }
 
 #[cfg(test)]
@@ -298,6 +328,13 @@ mod tests {
             !verify_x509_cert(&test_certs.expired, &test_certs.root)
                 .expect("certificate signature should be expired")
         );
+
+        // the certificate was valid on 2025-06-01
+        assert!(verify_x509_cert_at(
+            &test_certs.expired,
+            &test_certs.root,
+            1_748_736_000
+        )?);
         Ok(())
     }
 }
@@ -0,0 +1,352 @@
+//! # Virtual TPM (vTPM) Interface
+//!
+//! This module provides an interface for reading PCRs and generating quotes
+//! from the virtual TPM exposed by cloud TDX VMs (e.g., on GCP and Azure),
+//! and for cross-checking the vTPM's PCRs against the TD's RTMRs.
+//!
+//! When both roots of trust are available, the TD firmware measures each
+//! boot event into an RTMR (SHA-384) as well as into the corresponding vTPM
+//! PCR (SHA-256), following the mapping defined by the UEFI specification:
+//!
+//! | TDX register | TPM PCRs |
+//! |--------------|----------|
+//! | `MRTD`       | 0        |
+//! | `RTMR0`      | 1, 7     |
+//! | `RTMR1`      | 2-6      |
+//! | `RTMR2`      | 8-15     |
+//!
+//! Replaying the same event log into both sets of registers and checking that
+//! each matches the reported values shows that the two roots of trust agree,
+//! which strengthens the assurance provided by either one alone.
+//!
+//! ## Example Usage
+//!
+//! ```no_run
+//! use tdx_workload_attestation::vtpm::{Vtpm, MirroredEvent, cross_check_rtmrs_vs_pcrs};
+//!
+//! let mut vtpm = Vtpm::open().unwrap();
+//! let pcrs = vtpm.read_pcrs(&[1, 2, 3, 4, 5, 6, 7]).unwrap();
+//!
+//! // The RTMRs from the TD's report, and the events measured into both
+//! // (e.g., parsed from the TCG and CC event logs)
+//! let rtmrs = [[0u8; 48]; 4];
+//! let events: Vec<MirroredEvent> = vec![];
+//!
+//! match cross_check_rtmrs_vs_pcrs(&rtmrs, &pcrs, &events) {
+//!     Ok(true) => println!("The vTPM and the TD agree."),
+//!     Ok(false) => println!("The vTPM and the TD disagree."),
+//!     Err(e) => println!("Error cross-checking the vTPM: {}", e),
+//! }
+//! ```
+//!
+//! # Notes
+//! - This module requires the `vtpm` feature, and the TPM2 Software Stack
+//!   (`tpm2-tss`) libraries on the system.
+
+use crate::error::{Error, Result};
+use crate::measure::SHA384_LEN;
+use crate::measure::predict::extend_rtmr;
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tss_esapi::abstraction::pcr;
+use tss_esapi::handles::{KeyHandle, PersistentTpmHandle, TpmHandle};
+use tss_esapi::interface_types::algorithm::HashingAlgorithm;
+use tss_esapi::structures::{
+    Data, PcrSelectionList, PcrSelectionListBuilder, PcrSlot, SignatureScheme,
+};
+use tss_esapi::tcti_ldr::DeviceConfig;
+use tss_esapi::traits::Marshall;
+use tss_esapi::{Context, TctiNameConf};
+
+/// The default vTPM device path (the kernel's TPM resource manager).
+pub const VTPM_DEVICE_PATH: &str = "/dev/tpmrm0";
+
+/// The persistent handle of the attestation key (AK) provisioned in Azure
+/// vTPMs.
+pub const AZURE_AK_HANDLE: u32 = 0x8100_0003;
+
+/// The length of a SHA-256 PCR value.
+pub const SHA256_LEN: usize = 32;
+
+// The number of PCRs in a TPM 2.0 PCR bank
+const NUM_PCRS: u32 = 24;
+
+/// A set of SHA-256 PCR values, indexed by PCR number.
+pub type PcrValues = BTreeMap<u32, [u8; SHA256_LEN]>;
+
+/// A vTPM quote over a set of SHA-256 PCRs.
+#[derive(Clone, Debug)]
+pub struct VtpmQuote {
+    /// The marshalled `TPMS_ATTEST` structure signed by the AK.
+    pub attest: Vec<u8>,
+    /// The marshalled `TPMT_SIGNATURE` over `attest`.
+    pub signature: Vec<u8>,
+    /// The quoted PCR values, read right after the quote was generated.
+    ///
+    /// Verifiers must check these against the PCR digest in `attest`.
+    pub pcrs: PcrValues,
+}
+
+/// A boot event measured into both an RTMR and a vTPM PCR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MirroredEvent {
+    /// The PCR the event was measured into.
+    pub pcr_index: u32,
+    /// The event's SHA-256 digest, as extended into the PCR.
+    pub sha256: [u8; SHA256_LEN],
+    /// The event's SHA-384 digest, as extended into the RTMR.
+    pub sha384: [u8; SHA384_LEN],
+}
+
+/// A handle to the vTPM.
+pub struct Vtpm {
+    context: Context,
+}
+
+impl Vtpm {
+    /// Opens the vTPM at the default device path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::QuoteError` if the vTPM cannot be opened.
+    pub fn open() -> Result<Self> {
+        Self::open_device(VTPM_DEVICE_PATH)
+    }
+
+    /// Opens the vTPM at the given device path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::QuoteError` if the vTPM cannot be opened.
+    pub fn open_device(path: &str) -> Result<Self> {
+        let config = DeviceConfig::from_str(path).map_err(tpm_error)?;
+        let context = Context::new(TctiNameConf::Device(config)).map_err(tpm_error)?;
+
+        Ok(Self { context })
+    }
+
+    /// Reads the SHA-256 values of the given PCRs.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::ParseError` if a PCR index is out of range.
+    /// - `Error::QuoteError` if the PCRs cannot be read from the vTPM.
+    pub fn read_pcrs(&mut self, indices: &[u32]) -> Result<PcrValues> {
+        let selection = pcr_selection(indices)?;
+        let data = pcr::read_all(&mut self.context, selection).map_err(tpm_error)?;
+
+        let bank = data
+            .pcr_bank(HashingAlgorithm::Sha256)
+            .ok_or_else(|| Error::QuoteError("vTPM has no SHA-256 PCR bank".to_string()))?;
+
+        let mut pcrs = PcrValues::new();
+        for &index in indices {
+            let digest = bank
+                .get_digest(pcr_slot(index)?)
+                .ok_or_else(|| Error::QuoteError(format!("PCR {} was not read", index)))?;
+            let value = digest.value().try_into().map_err(|_| {
+                Error::QuoteError(format!("PCR {} has an invalid SHA-256 value", index))
+            })?;
+            pcrs.insert(index, value);
+        }
+
+        Ok(pcrs)
+    }
+
+    /// Generates a quote over the given SHA-256 PCRs, signed by the AK at the
+    /// persistent handle `ak_handle`, and including `nonce` as qualifying
+    /// data.
+    ///
+    /// # Errors
+    ///
+    /// - `Error::ParseError` if a PCR index is out of range, or the nonce is
+    ///   too long.
+    /// - `Error::QuoteError` if the vTPM fails to generate the quote.
+    pub fn quote(&mut self, ak_handle: u32, nonce: &[u8], indices: &[u32]) -> Result<VtpmQuote> {
+        let qualifying_data = Data::try_from(nonce.to_vec())
+            .map_err(|e| Error::ParseError(format!("Invalid quote nonce: {}", e)))?;
+        let selection = pcr_selection(indices)?;
+
+        let handle = PersistentTpmHandle::new(ak_handle).map_err(tpm_error)?;
+        let ak = self
+            .context
+            .tr_from_tpm_public(TpmHandle::Persistent(handle))
+            .map_err(tpm_error)?;
+
+        let (attest, signature) = self
+            .context
+            .execute_with_nullauth_session(|ctx| {
+                ctx.quote(
+                    KeyHandle::from(ak),
+                    qualifying_data,
+                    SignatureScheme::Null,
+                    selection,
+                )
+            })
+            .map_err(tpm_error)?;
+
+        Ok(VtpmQuote {
+            attest: attest.marshall().map_err(tpm_error)?,
+            signature: signature.marshall().map_err(tpm_error)?,
+            pcrs: self.read_pcrs(indices)?,
+        })
+    }
+}
+
+/// Returns the index of the RTMR that mirrors the given PCR, if any.
+///
+/// PCR 0 is mirrored by `MRTD`, which is not extensible, so `None` is
+/// returned for it.
+pub fn pcr_to_rtmr_index(pcr_index: u32) -> Option<usize> {
+    match pcr_index {
+        1 | 7 => Some(0),
+        2..=6 => Some(1),
+        8..=15 => Some(2),
+        _ => None,
+    }
+}
+
+/// Cross-checks a TD's RTMRs against the vTPM's PCRs.
+///
+/// The `events` are replayed into both sets of registers, and the results are
+/// compared against the reported `rtmrs` and `pcrs`. Only registers that at
+/// least one event was measured into are checked.
+///
+/// Returns `Ok(true)` if all replayed registers match the reported values.
+///
+/// # Errors
+///
+/// Returns an `Error::VerificationError` if `events` is empty, or if an event
+/// was measured into a PCR that was not reported.
+pub fn cross_check_rtmrs_vs_pcrs(
+    rtmrs: &[[u8; SHA384_LEN]; 4],
+    pcrs: &PcrValues,
+    events: &[MirroredEvent],
+) -> Result<bool> {
+    if events.is_empty() {
+        return Err(Error::VerificationError(
+            "No events to cross-check the RTMRs and PCRs with".to_string(),
+        ));
+    }
+
+    let mut replayed_rtmrs: [Option<[u8; SHA384_LEN]>; 4] = [None; 4];
+    let mut replayed_pcrs = PcrValues::new();
+
+    for event in events {
+        let pcr = replayed_pcrs
+            .entry(event.pcr_index)
+            .or_insert([0u8; SHA256_LEN]);
+        *pcr = extend_pcr(pcr, &event.sha256);
+
+        if let Some(index) = pcr_to_rtmr_index(event.pcr_index) {
+            let rtmr = replayed_rtmrs[index].get_or_insert([0u8; SHA384_LEN]);
+            *rtmr = extend_rtmr(rtmr, &event.sha384);
+        }
+    }
+
+    for (index, replayed) in &replayed_pcrs {
+        let reported = pcrs
+            .get(index)
+            .ok_or_else(|| Error::VerificationError(format!("PCR {} was not reported", index)))?;
+        if reported != replayed {
+            return Ok(false);
+        }
+    }
+
+    Ok(replayed_rtmrs
+        .iter()
+        .zip(rtmrs)
+        .all(|(replayed, reported)| replayed.is_none_or(|r| r == *reported)))
+}
+
+/// Extends a SHA-256 PCR value with an event digest.
+fn extend_pcr(pcr: &[u8; SHA256_LEN], digest: &[u8; SHA256_LEN]) -> [u8; SHA256_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(pcr);
+    hasher.update(digest);
+    hasher.finalize().into()
+}
+
+fn pcr_slot(index: u32) -> Result<PcrSlot> {
+    if index >= NUM_PCRS {
+        return Err(Error::ParseError(format!("Invalid PCR index {}", index)));
+    }
+    PcrSlot::try_from(1u32 << index).map_err(tpm_error)
+}
+
+fn pcr_selection(indices: &[u32]) -> Result<PcrSelectionList> {
+    let slots = indices
+        .iter()
+        .map(|&index| pcr_slot(index))
+        .collect::<Result<Vec<PcrSlot>>>()?;
+
+    PcrSelectionListBuilder::new()
+        .with_selection(HashingAlgorithm::Sha256, &slots)
+        .build()
+        .map_err(tpm_error)
+}
+
+fn tpm_error(e: tss_esapi::Error) -> Error {
+    Error::QuoteError(format!("TPM error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_event(pcr_index: u32, data: &[u8]) -> MirroredEvent {
+        MirroredEvent {
+            pcr_index,
+            sha256: Sha256::digest(data).into(),
+            sha384: sha2::Sha384::digest(data).into(),
+        }
+    }
+
+    #[test]
+    fn test_pcr_to_rtmr_index() {
+        assert_eq!(pcr_to_rtmr_index(0), None);
+        assert_eq!(pcr_to_rtmr_index(7), Some(0));
+        assert_eq!(pcr_to_rtmr_index(4), Some(1));
+        assert_eq!(pcr_to_rtmr_index(9), Some(2));
+        assert_eq!(pcr_to_rtmr_index(16), None);
+    }
+
+    #[test]
+    fn test_cross_check() -> Result<()> {
+        let events = [make_event(4, b"kernel"), make_event(9, b"cmdline")];
+
+        let mut pcrs = PcrValues::new();
+        pcrs.insert(4, extend_pcr(&[0; SHA256_LEN], &events[0].sha256));
+        pcrs.insert(9, extend_pcr(&[0; SHA256_LEN], &events[1].sha256));
+
+        let mut rtmrs = [[0u8; SHA384_LEN]; 4];
+        rtmrs[1] = extend_rtmr(&[0; SHA384_LEN], &events[0].sha384);
+        rtmrs[2] = extend_rtmr(&[0; SHA384_LEN], &events[1].sha384);
+
+        assert!(cross_check_rtmrs_vs_pcrs(&rtmrs, &pcrs, &events)?);
+
+        // the RTMRs disagree with the PCRs
+        rtmrs[2] = [0xff; SHA384_LEN];
+        assert!(!cross_check_rtmrs_vs_pcrs(&rtmrs, &pcrs, &events)?);
+
+        // a PCR is missing
+        pcrs.remove(&9);
+        assert!(cross_check_rtmrs_vs_pcrs(&rtmrs, &pcrs, &events).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_check_no_events() {
+        let rtmrs = [[0u8; SHA384_LEN]; 4];
+        assert!(cross_check_rtmrs_vs_pcrs(&rtmrs, &PcrValues::new(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_pcr_slot() {
+        assert!(matches!(pcr_slot(0), Ok(PcrSlot::Slot0)));
+        assert!(matches!(pcr_slot(23), Ok(PcrSlot::Slot23)));
+        assert!(pcr_slot(24).is_err());
+    }
+}